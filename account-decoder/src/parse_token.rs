@@ -228,6 +228,10 @@ pub fn get_token_account_mint(data: &[u8]) -> Option<Pubkey> {
     }
 }
 
+pub fn is_initialized_token_account(data: &[u8]) -> bool {
+    data.len() == Account::get_packed_len() && Account::unpack(data).is_ok()
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -334,6 +338,23 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_is_initialized_token_account() {
+        let mut account_data = vec![0; Account::get_packed_len()];
+        assert!(!is_initialized_token_account(&account_data));
+
+        let mut account = Account::unpack_unchecked(&account_data).unwrap();
+        account.state = AccountState::Initialized;
+        Account::pack(account, &mut account_data).unwrap();
+        assert!(is_initialized_token_account(&account_data));
+
+        assert!(!is_initialized_token_account(&account_data[1..]));
+        assert!(!is_initialized_token_account(&vec![
+            0;
+            Mint::get_packed_len()
+        ]));
+    }
+
     #[test]
     fn test_ui_token_amount_real_string() {
         let token_amount = token_amount_to_ui_amount(1, 0);
@@ -6,7 +6,10 @@
 //! cumbersome to use.
 
 use futures::{future::join_all, Future, FutureExt};
-pub use solana_banks_interface::{BanksClient as TarpcClient, TransactionStatus};
+pub use solana_banks_interface::{
+    BanksClient as TarpcClient, BanksTransactionResultWithMetadata, TransactionMetadata,
+    TransactionStatus,
+};
 use solana_banks_interface::{BanksRequest, BanksResponse};
 use solana_sdk::{
     account::{from_account, Account},
@@ -106,6 +109,25 @@ impl BanksClient {
             .get_account_with_commitment_and_context(ctx, address, commitment)
     }
 
+    pub fn simulate_transaction_with_commitment_and_context(
+        &mut self,
+        ctx: Context,
+        transaction: Transaction,
+        commitment: CommitmentLevel,
+    ) -> impl Future<Output = io::Result<BanksTransactionResultWithMetadata>> + '_ {
+        self.inner
+            .simulate_transaction_with_commitment_and_context(ctx, transaction, commitment)
+    }
+
+    pub fn process_transaction_with_metadata_and_context(
+        &mut self,
+        ctx: Context,
+        transaction: Transaction,
+    ) -> impl Future<Output = io::Result<BanksTransactionResultWithMetadata>> + '_ {
+        self.inner
+            .process_transaction_with_metadata_and_context(ctx, transaction)
+    }
+
     /// Send a transaction and return immediately. The server will resend the
     /// transaction until either it is accepted by the cluster or the transaction's
     /// blockhash expires.
@@ -169,6 +191,41 @@ impl BanksClient {
         self.process_transaction_with_commitment(transaction, CommitmentLevel::default())
     }
 
+    /// Process a transaction against the server's working bank and return its result along
+    /// with the log messages, inner instructions and compute units it recorded. The metadata
+    /// is None if the transaction could not be executed, for example because its blockhash
+    /// expired or its fee-payer could not pay the fee. A validator's server sends the
+    /// transaction to the cluster instead and never returns metadata.
+    pub fn process_transaction_with_metadata(
+        &mut self,
+        transaction: Transaction,
+    ) -> impl Future<Output = io::Result<BanksTransactionResultWithMetadata>> + '_ {
+        self.process_transaction_with_metadata_and_context(context::current(), transaction)
+    }
+
+    /// Run a transaction against the bank at the given level of commitment without
+    /// committing its results, returning the same metadata as
+    /// `process_transaction_with_metadata`.
+    pub fn simulate_transaction_with_commitment(
+        &mut self,
+        transaction: Transaction,
+        commitment: CommitmentLevel,
+    ) -> impl Future<Output = io::Result<BanksTransactionResultWithMetadata>> + '_ {
+        self.simulate_transaction_with_commitment_and_context(
+            context::current(),
+            transaction,
+            commitment,
+        )
+    }
+
+    /// Run a transaction against the most recent root without committing its results.
+    pub fn simulate_transaction(
+        &mut self,
+        transaction: Transaction,
+    ) -> impl Future<Output = io::Result<BanksTransactionResultWithMetadata>> + '_ {
+        self.simulate_transaction_with_commitment(transaction, CommitmentLevel::default())
+    }
+
     pub async fn process_transactions_with_commitment(
         &mut self,
         transactions: Vec<Transaction>,
@@ -289,8 +346,14 @@ pub async fn start_tcp_client<T: ToSocketAddrs>(addr: T) -> io::Result<BanksClie
 mod tests {
     use super::*;
     use solana_banks_server::banks_server::start_local_server;
-    use solana_runtime::{bank::Bank, bank_forks::BankForks, genesis_utils::create_genesis_config};
-    use solana_sdk::{message::Message, signature::Signer, system_instruction};
+    use solana_runtime::{
+        bank::Bank, bank_forks::BankForks, commitment::BlockCommitmentCache,
+        genesis_utils::create_genesis_config,
+    };
+    use solana_sdk::{
+        instruction::InstructionError, message::Message, signature::Signer, system_instruction,
+        system_program, transaction::TransactionError,
+    };
     use std::sync::{Arc, RwLock};
     use tarpc::transport;
     use tokio::{runtime::Runtime, time::sleep};
@@ -311,6 +374,9 @@ mod tests {
         let bank_forks = Arc::new(RwLock::new(BankForks::new(Bank::new(
             &genesis.genesis_config,
         ))));
+        let block_commitment_cache = Arc::new(RwLock::new(
+            BlockCommitmentCache::new_for_tests_with_slots(0, 0),
+        ));
 
         let bob_pubkey = solana_sdk::pubkey::new_rand();
         let mint_pubkey = genesis.mint_keypair.pubkey();
@@ -318,7 +384,7 @@ mod tests {
         let message = Message::new(&[instruction], Some(&mint_pubkey));
 
         Runtime::new()?.block_on(async {
-            let client_transport = start_local_server(&bank_forks).await;
+            let client_transport = start_local_server(&bank_forks, &block_commitment_cache).await;
             let mut banks_client = start_client(client_transport).await?;
 
             let recent_blockhash = banks_client.get_recent_blockhash().await?;
@@ -339,6 +405,9 @@ mod tests {
         let bank_forks = Arc::new(RwLock::new(BankForks::new(Bank::new(
             &genesis.genesis_config,
         ))));
+        let block_commitment_cache = Arc::new(RwLock::new(
+            BlockCommitmentCache::new_for_tests_with_slots(0, 0),
+        ));
 
         let mint_pubkey = &genesis.mint_keypair.pubkey();
         let bob_pubkey = solana_sdk::pubkey::new_rand();
@@ -346,7 +415,7 @@ mod tests {
         let message = Message::new(&[instruction], Some(&mint_pubkey));
 
         Runtime::new()?.block_on(async {
-            let client_transport = start_local_server(&bank_forks).await;
+            let client_transport = start_local_server(&bank_forks, &block_commitment_cache).await;
             let mut banks_client = start_client(client_transport).await?;
             let (_, recent_blockhash, last_valid_slot) = banks_client.get_fees().await?;
             let transaction = Transaction::new(&[&genesis.mint_keypair], message, recent_blockhash);
@@ -368,4 +437,70 @@ mod tests {
             Ok(())
         })
     }
+
+    #[test]
+    fn test_banks_server_transaction_metadata() -> io::Result<()> {
+        let genesis = create_genesis_config(10);
+        let bank_forks = Arc::new(RwLock::new(BankForks::new(Bank::new(
+            &genesis.genesis_config,
+        ))));
+        let block_commitment_cache = Arc::new(RwLock::new(
+            BlockCommitmentCache::new_for_tests_with_slots(0, 0),
+        ));
+
+        let mint_pubkey = genesis.mint_keypair.pubkey();
+        let bob_pubkey = solana_sdk::pubkey::new_rand();
+        let transfer = |lamports| {
+            let instruction = system_instruction::transfer(&mint_pubkey, &bob_pubkey, lamports);
+            Message::new(&[instruction], Some(&mint_pubkey))
+        };
+
+        Runtime::new()?.block_on(async {
+            let client_transport = start_local_server(&bank_forks, &block_commitment_cache).await;
+            let mut banks_client = start_client(client_transport).await?;
+            let recent_blockhash = banks_client.get_recent_blockhash().await?;
+
+            // Simulation reports why the transfer fails without committing anything
+            let transaction =
+                Transaction::new(&[&genesis.mint_keypair], transfer(100), recent_blockhash);
+            let simulation = banks_client.simulate_transaction(transaction).await?;
+            assert_eq!(
+                simulation.result,
+                Err(TransactionError::InstructionError(
+                    0,
+                    InstructionError::Custom(1)
+                ))
+            );
+            let metadata = simulation.metadata.unwrap();
+            assert_eq!(
+                metadata.log_messages[0],
+                format!("Program {} invoke [1]", system_program::id())
+            );
+            assert_eq!(metadata.compute_units_consumed.len(), 1);
+            assert_eq!(banks_client.get_balance(bob_pubkey).await?, 0);
+
+            let transaction =
+                Transaction::new(&[&genesis.mint_keypair], transfer(1), recent_blockhash);
+            let processed = banks_client
+                .process_transaction_with_metadata(transaction.clone())
+                .await?;
+            assert_eq!(processed.result, Ok(()));
+            assert_eq!(
+                processed.metadata.unwrap().log_messages,
+                vec![
+                    format!("Program {} invoke [1]", system_program::id()),
+                    format!("Program {} success", system_program::id()),
+                ]
+            );
+            assert_eq!(banks_client.get_balance(bob_pubkey).await?, 1);
+
+            // A duplicate is rejected before execution, so there is no metadata
+            let processed = banks_client
+                .process_transaction_with_metadata(transaction)
+                .await?;
+            assert_eq!(processed.result, Err(TransactionError::DuplicateSignature));
+            assert_eq!(processed.metadata, None);
+            Ok(())
+        })
+    }
 }
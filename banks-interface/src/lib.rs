@@ -5,6 +5,7 @@ use solana_sdk::{
     commitment_config::CommitmentLevel,
     fee_calculator::FeeCalculator,
     hash::Hash,
+    instruction::CompiledInstruction,
     pubkey::Pubkey,
     signature::Signature,
     transaction::{self, Transaction, TransactionError},
@@ -17,6 +18,23 @@ pub struct TransactionStatus {
     pub err: Option<TransactionError>,
 }
 
+/// What a transaction recorded while it was executed
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct TransactionMetadata {
+    pub log_messages: Vec<String>,
+    /// Instructions invoked by each top-level instruction, if any were recorded
+    pub inner_instructions: Option<Vec<Vec<CompiledInstruction>>>,
+    /// Compute units consumed by each top-level instruction
+    pub compute_units_consumed: Vec<u64>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BanksTransactionResultWithMetadata {
+    pub result: transaction::Result<()>,
+    /// None if the transaction could not be executed
+    pub metadata: Option<TransactionMetadata>,
+}
+
 #[tarpc::service]
 pub trait Banks {
     async fn send_transaction_with_context(transaction: Transaction);
@@ -34,6 +52,13 @@ pub trait Banks {
         address: Pubkey,
         commitment: CommitmentLevel,
     ) -> Option<Account>;
+    async fn simulate_transaction_with_commitment_and_context(
+        transaction: Transaction,
+        commitment: CommitmentLevel,
+    ) -> BanksTransactionResultWithMetadata;
+    async fn process_transaction_with_metadata_and_context(
+        transaction: Transaction,
+    ) -> BanksTransactionResultWithMetadata;
 }
 
 #[cfg(test)]
@@ -4,8 +4,15 @@ use futures::{
     future,
     prelude::stream::{self, StreamExt},
 };
-use solana_banks_interface::{Banks, BanksRequest, BanksResponse, TransactionStatus};
-use solana_runtime::{bank::Bank, bank_forks::BankForks, commitment::BlockCommitmentCache};
+use solana_banks_interface::{
+    Banks, BanksRequest, BanksResponse, BanksTransactionResultWithMetadata, TransactionMetadata,
+    TransactionStatus,
+};
+use solana_runtime::{
+    bank::{Bank, TransactionExecutionDetails, TransactionSimulationResult},
+    bank_forks::BankForks,
+    commitment::BlockCommitmentCache,
+};
 use solana_sdk::{
     account::Account,
     clock::Slot,
@@ -14,7 +21,7 @@ use solana_sdk::{
     hash::Hash,
     pubkey::Pubkey,
     signature::Signature,
-    transaction::{self, Transaction},
+    transaction::{self, Transaction, TransactionError},
 };
 use std::{
     io,
@@ -27,7 +34,7 @@ use std::{
     time::Duration,
 };
 use tarpc::{
-    context::Context,
+    context::{self, Context},
     rpc::{transport::channel::UnboundedChannel, ClientMessage, Response},
     serde_transport::tcp,
     server::{self, Channel, Handler},
@@ -41,6 +48,9 @@ struct BanksServer {
     bank_forks: Arc<RwLock<BankForks>>,
     block_commitment_cache: Arc<RwLock<BlockCommitmentCache>>,
     transaction_sender: Sender<TransactionInfo>,
+    /// Whether transactions are processed by the server itself rather than forwarded to
+    /// the cluster, in which case they may be committed to the working bank directly
+    is_loopback: bool,
 }
 
 impl BanksServer {
@@ -57,10 +67,11 @@ impl BanksServer {
             bank_forks,
             block_commitment_cache,
             transaction_sender,
+            is_loopback: false,
         }
     }
 
-    fn run(bank: &Bank, transaction_receiver: Receiver<TransactionInfo>) {
+    fn run(bank_forks: &RwLock<BankForks>, transaction_receiver: Receiver<TransactionInfo>) {
         while let Ok(info) = transaction_receiver.recv() {
             let mut transaction_infos = vec![info];
             while let Ok(info) = transaction_receiver.try_recv() {
@@ -70,23 +81,26 @@ impl BanksServer {
                 .into_iter()
                 .map(|info| deserialize(&info.wire_transaction).unwrap())
                 .collect();
+            let bank = bank_forks.read().unwrap().working_bank();
             let _ = bank.process_transactions(&transactions);
         }
     }
 
     /// Useful for unit-testing
-    fn new_loopback(bank_forks: Arc<RwLock<BankForks>>) -> Self {
+    fn new_loopback(
+        bank_forks: Arc<RwLock<BankForks>>,
+        block_commitment_cache: Arc<RwLock<BlockCommitmentCache>>,
+    ) -> Self {
         let (transaction_sender, transaction_receiver) = channel();
-        let bank = bank_forks.read().unwrap().working_bank();
-        let slot = bank.slot();
-        let block_commitment_cache = Arc::new(RwLock::new(
-            BlockCommitmentCache::new_for_tests_with_slots(slot, slot),
-        ));
+        let server_bank_forks = bank_forks.clone();
         Builder::new()
             .name("solana-bank-forks-client".to_string())
-            .spawn(move || Self::run(&bank, transaction_receiver))
+            .spawn(move || Self::run(&server_bank_forks, transaction_receiver))
             .unwrap();
-        Self::new(bank_forks, block_commitment_cache, transaction_sender)
+        Self {
+            is_loopback: true,
+            ..Self::new(bank_forks, block_commitment_cache, transaction_sender)
+        }
     }
 
     fn slot(&self, commitment: CommitmentLevel) -> Slot {
@@ -208,12 +222,86 @@ impl Banks for BanksServer {
         let bank = self.bank(commitment);
         bank.get_account(&address)
     }
+
+    async fn simulate_transaction_with_commitment_and_context(
+        self,
+        _: Context,
+        transaction: Transaction,
+        commitment: CommitmentLevel,
+    ) -> BanksTransactionResultWithMetadata {
+        let TransactionSimulationResult {
+            result,
+            logs,
+            post_simulation_accounts,
+            units_consumed,
+            inner_instructions,
+        } = self
+            .bank(commitment)
+            .simulate_transaction_unchecked(transaction);
+        // Accounts are only returned for transactions that could be loaded and executed
+        let metadata = if post_simulation_accounts.is_empty() {
+            None
+        } else {
+            Some(TransactionMetadata {
+                log_messages: logs,
+                inner_instructions,
+                compute_units_consumed: units_consumed,
+            })
+        };
+        BanksTransactionResultWithMetadata { result, metadata }
+    }
+
+    async fn process_transaction_with_metadata_and_context(
+        self,
+        _: Context,
+        transaction: Transaction,
+    ) -> BanksTransactionResultWithMetadata {
+        // A validator's bank may only change through the TPU, which records nothing
+        // about the transaction for the server to return
+        if !self.is_loopback {
+            let result = self
+                .process_transaction_with_commitment_and_context(
+                    context::current(),
+                    transaction,
+                    CommitmentLevel::default(),
+                )
+                .await
+                .unwrap_or(Err(TransactionError::BlockhashNotFound));
+            return BanksTransactionResultWithMetadata {
+                result,
+                metadata: None,
+            };
+        }
+
+        let bank = self.bank_forks.read().unwrap().working_bank();
+        match bank.process_transaction_with_metadata(transaction) {
+            Ok(TransactionExecutionDetails {
+                result,
+                logs,
+                units_consumed,
+                inner_instructions,
+            }) => BanksTransactionResultWithMetadata {
+                result,
+                metadata: Some(TransactionMetadata {
+                    log_messages: logs,
+                    inner_instructions,
+                    compute_units_consumed: units_consumed,
+                }),
+            },
+            Err(err) => BanksTransactionResultWithMetadata {
+                result: Err(err),
+                metadata: None,
+            },
+        }
+    }
 }
 
 pub async fn start_local_server(
     bank_forks: &Arc<RwLock<BankForks>>,
+    block_commitment_cache: &Arc<RwLock<BlockCommitmentCache>>,
 ) -> UnboundedChannel<Response<BanksResponse>, ClientMessage<BanksRequest>> {
-    let banks_server = BanksServer::new_loopback(bank_forks.clone());
+    let banks_server =
+        BanksServer::new_loopback(bank_forks.clone(), block_commitment_cache.clone());
     let (client_transport, server_transport) = transport::channel::unbounded();
     let server = server::new(server::Config::default())
         .incoming(stream::once(future::ready(server_transport)))
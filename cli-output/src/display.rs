@@ -1,8 +1,12 @@
 use console::style;
 use indicatif::{ProgressBar, ProgressStyle};
 use solana_sdk::{
-    hash::Hash, native_token::lamports_to_sol, program_utils::limited_deserialize,
-    transaction::Transaction,
+    hash::Hash,
+    message::VersionedMessage,
+    native_token::lamports_to_sol,
+    program_utils::limited_deserialize,
+    pubkey::Pubkey,
+    transaction::{Transaction, VersionedTransaction},
 };
 use solana_transaction_status::UiTransactionStatusMeta;
 use std::{collections::HashMap, fmt, io};
@@ -85,12 +89,44 @@ pub fn write_transaction<W: io::Write>(
     transaction: &Transaction,
     transaction_status: &Option<UiTransactionStatusMeta>,
     prefix: &str,
+) -> io::Result<()> {
+    write_versioned_transaction(w, &transaction.clone().into(), transaction_status, prefix)
+}
+
+pub fn write_versioned_transaction<W: io::Write>(
+    w: &mut W,
+    transaction: &VersionedTransaction,
+    transaction_status: &Option<UiTransactionStatusMeta>,
+    prefix: &str,
 ) -> io::Result<()> {
     let message = &transaction.message;
+    // Versioned messages only list their static account keys, the rest were loaded from address
+    // lookup tables and are reported in the transaction status
+    let static_account_keys = message.static_account_keys();
+    let mut account_keys = static_account_keys.to_vec();
+    if let Some(loaded_addresses) = transaction_status
+        .as_ref()
+        .and_then(|transaction_status| transaction_status.loaded_addresses.as_ref())
+    {
+        account_keys.extend(
+            loaded_addresses
+                .writable
+                .iter()
+                .chain(&loaded_addresses.readonly)
+                .filter_map(|address| address.parse::<Pubkey>().ok()),
+        );
+    }
+    let account_key = |index: u8| {
+        account_keys
+            .get(index as usize)
+            .map(|pubkey| pubkey.to_string())
+            .unwrap_or_else(|| "<unresolved lookup>".to_string())
+    };
     writeln!(
         w,
         "{}Recent Blockhash: {:?}",
-        prefix, message.recent_blockhash
+        prefix,
+        message.recent_blockhash()
     )?;
     for (signature_index, signature) in transaction.signatures.iter().enumerate() {
         writeln!(
@@ -99,12 +135,39 @@ pub fn write_transaction<W: io::Write>(
             prefix, signature_index, signature
         )?;
     }
-    writeln!(w, "{}{:?}", prefix, message.header)?;
-    for (account_index, account) in message.account_keys.iter().enumerate() {
-        writeln!(w, "{}Account {}: {:?}", prefix, account_index, account)?;
+    writeln!(w, "{}{:?}", prefix, message.header())?;
+    for (account_index, account) in account_keys.iter().enumerate() {
+        if account_index < static_account_keys.len() {
+            writeln!(w, "{}Account {}: {:?}", prefix, account_index, account)?;
+        } else {
+            writeln!(
+                w,
+                "{}Account {}: {:?} (loaded)",
+                prefix, account_index, account
+            )?;
+        }
+    }
+    if let VersionedMessage::V0(message) = message {
+        for (lookup_index, lookup) in message.address_table_lookups.iter().enumerate() {
+            writeln!(
+                w,
+                "{}Address Table Lookup {}: {}",
+                prefix, lookup_index, lookup.account_key
+            )?;
+            writeln!(
+                w,
+                "{}  Writable Indexes: {:?}",
+                prefix, lookup.writable_indexes
+            )?;
+            writeln!(
+                w,
+                "{}  Read-only Indexes: {:?}",
+                prefix, lookup.readonly_indexes
+            )?;
+        }
     }
-    for (instruction_index, instruction) in message.instructions.iter().enumerate() {
-        let program_pubkey = message.account_keys[instruction.program_id_index as usize];
+    for (instruction_index, instruction) in message.instructions().iter().enumerate() {
+        let program_pubkey = static_account_keys[instruction.program_id_index as usize];
         writeln!(w, "{}Instruction {}", prefix, instruction_index)?;
         writeln!(
             w,
@@ -112,11 +175,13 @@ pub fn write_transaction<W: io::Write>(
             prefix, program_pubkey, instruction.program_id_index
         )?;
         for (account_index, account) in instruction.accounts.iter().enumerate() {
-            let account_pubkey = message.account_keys[*account as usize];
             writeln!(
                 w,
                 "{}  Account {}: {} ({})",
-                prefix, account_index, account_pubkey, account
+                prefix,
+                account_index,
+                account_key(*account),
+                account
             )?;
         }
 
@@ -238,7 +303,43 @@ pub fn new_spinner_progress_bar() -> ProgressBar {
 #[cfg(test)]
 mod test {
     use super::*;
-    use solana_sdk::pubkey::Pubkey;
+    use solana_sdk::{message::v0, signature::Signature, system_instruction};
+    use solana_transaction_status::{TransactionStatusMeta, UiLoadedAddresses};
+
+    #[test]
+    fn test_write_transaction_loaded_addresses() {
+        let payer = Pubkey::new_unique();
+        let loaded_key = Pubkey::new_unique();
+        let table_key = Pubkey::new_unique();
+        let message = v0::Message::new_with_address_table_lookups(
+            &[system_instruction::transfer(&payer, &loaded_key, 1)],
+            Some(&payer),
+            &[(table_key, vec![loaded_key])],
+        );
+        let transaction = VersionedTransaction {
+            signatures: vec![Signature::default()],
+            message: VersionedMessage::V0(message),
+        };
+        let mut transaction_status: UiTransactionStatusMeta =
+            TransactionStatusMeta::default().into();
+        transaction_status.loaded_addresses = Some(UiLoadedAddresses {
+            writable: vec![loaded_key.to_string()],
+            readonly: vec![],
+        });
+
+        let mut w = Vec::new();
+        write_versioned_transaction(&mut w, &transaction, &Some(transaction_status), "").unwrap();
+        let output = String::from_utf8(w).unwrap();
+        assert!(output.contains(&format!("Account 2: {} (loaded)", loaded_key)));
+        assert!(output.contains(&format!("Address Table Lookup 0: {}", table_key)));
+        assert!(output.contains(&format!("  Account 1: {} (2)", loaded_key)));
+
+        // Without the transaction status, loaded accounts can't be resolved
+        let mut w = Vec::new();
+        write_versioned_transaction(&mut w, &transaction, &None, "").unwrap();
+        let output = String::from_utf8(w).unwrap();
+        assert!(output.contains("  Account 1: <unresolved lookup> (2)"));
+    }
 
     #[test]
     fn test_format_labeled_address() {
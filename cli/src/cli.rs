@@ -43,6 +43,7 @@ use solana_rbpf::vm::{Config, Executable};
 use solana_remote_wallet::remote_wallet::RemoteWalletManager;
 use solana_sdk::{
     bpf_loader, bpf_loader_deprecated,
+    bpf_loader_upgradeable::{self, UpgradeableLoaderState},
     clock::{Epoch, Slot},
     commitment_config::CommitmentConfig,
     decode_error::DecodeError,
@@ -71,7 +72,7 @@ use std::{
     fmt::Write as FmtWrite,
     fs::File,
     io::{Read, Write},
-    net::{IpAddr, SocketAddr, UdpSocket},
+    net::{IpAddr, SocketAddr},
     str::FromStr,
     sync::Arc,
     thread::sleep,
@@ -189,6 +190,22 @@ pub enum CliCommand {
         use_deprecated_loader: bool,
         allow_excessive_balance: bool,
     },
+    DeployUpgradeable {
+        program_location: String,
+        address: Option<SignerIndex>,
+        upgrade_authority: SignerIndex,
+        max_len: Option<usize>,
+    },
+    Upgrade {
+        program_location: String,
+        program_id: Pubkey,
+        upgrade_authority: SignerIndex,
+    },
+    SetUpgradeAuthority {
+        program_id: Pubkey,
+        upgrade_authority: SignerIndex,
+        new_upgrade_authority: Option<Pubkey>,
+    },
     // Stake Commands
     CreateStakeAccount {
         stake_account: SignerIndex,
@@ -422,6 +439,7 @@ pub struct CliConfig<'a> {
     pub commitment: CommitmentConfig,
     pub send_transaction_config: RpcSendTransactionConfig,
     pub address_labels: HashMap<String, String>,
+    pub use_quic: bool,
 }
 
 impl CliConfig<'_> {
@@ -528,6 +546,7 @@ impl Default for CliConfig<'_> {
             commitment: CommitmentConfig::default(),
             send_transaction_config: RpcSendTransactionConfig::default(),
             address_labels: HashMap::new(),
+            use_quic: false,
         }
     }
 }
@@ -618,15 +637,61 @@ pub fn parse_command(
             let mut signers = vec![default_signer.signer_from_path(matches, wallet_manager)?];
             let address = address_signer.map(|signer| {
                 signers.push(signer);
-                1
+                signers.len() - 1
             });
+            let program_location = matches.value_of("program_location").unwrap().to_string();
+
+            let command = if matches.is_present("upgradeable") {
+                let (upgrade_authority_signer, _) =
+                    signer_of(matches, "upgrade_authority", wallet_manager)?;
+                let upgrade_authority = upgrade_authority_signer
+                    .map(|signer| {
+                        signers.push(signer);
+                        signers.len() - 1
+                    })
+                    .unwrap_or(0);
 
-            Ok(CliCommandInfo {
-                command: CliCommand::Deploy {
-                    program_location: matches.value_of("program_location").unwrap().to_string(),
+                if let Some(program_id) = pubkey_of(matches, "program_id") {
+                    CliCommand::Upgrade {
+                        program_location,
+                        program_id,
+                        upgrade_authority,
+                    }
+                } else {
+                    CliCommand::DeployUpgradeable {
+                        program_location,
+                        address,
+                        upgrade_authority,
+                        max_len: value_of(matches, "max_len"),
+                    }
+                }
+            } else {
+                CliCommand::Deploy {
+                    program_location,
                     address,
                     use_deprecated_loader: matches.is_present("use_deprecated_loader"),
                     allow_excessive_balance: matches.is_present("allow_excessive_balance"),
+                }
+            };
+
+            Ok(CliCommandInfo { command, signers })
+        }
+        ("set-upgrade-authority", Some(matches)) => {
+            let mut signers = vec![default_signer.signer_from_path(matches, wallet_manager)?];
+            let (upgrade_authority_signer, _) =
+                signer_of(matches, "upgrade_authority", wallet_manager)?;
+            let upgrade_authority = upgrade_authority_signer
+                .map(|signer| {
+                    signers.push(signer);
+                    1
+                })
+                .unwrap_or(0);
+
+            Ok(CliCommandInfo {
+                command: CliCommand::SetUpgradeAuthority {
+                    program_id: pubkey_of(matches, "program_id").unwrap(),
+                    upgrade_authority,
+                    new_upgrade_authority: pubkey_of(matches, "new_upgrade_authority"),
                 },
                 signers,
             })
@@ -1043,13 +1108,14 @@ fn send_and_confirm_transactions_with_spinner<T: Signers>(
     mut transactions: Vec<Transaction>,
     signer_keys: &T,
     commitment: CommitmentConfig,
+    use_quic: bool,
     mut last_valid_slot: Slot,
 ) -> Result<(), Box<dyn error::Error>> {
     let progress_bar = new_spinner_progress_bar();
     let mut send_retries = 5;
     let mut leader_schedule: Option<RpcLeaderSchedule> = None;
     let mut leader_schedule_epoch = 0;
-    let send_socket = UdpSocket::bind("0.0.0.0:0").unwrap();
+    let mut tpu_sender = TpuSender::new(use_quic);
     let cluster_nodes = rpc_client.get_cluster_nodes().ok();
 
     loop {
@@ -1075,7 +1141,7 @@ fn send_and_confirm_transactions_with_spinner<T: Signers>(
             if let Some(tpu_address) = tpu_address {
                 let wire_transaction =
                     serialize(&transaction).expect("serialization should succeed");
-                send_transaction_tpu(&send_socket, &tpu_address, &wire_transaction);
+                tpu_sender.send(&tpu_address, &wire_transaction);
             } else {
                 let _result = rpc_client
                     .send_transaction_with_config(
@@ -1166,6 +1232,25 @@ fn send_and_confirm_transactions_with_spinner<T: Signers>(
     }
 }
 
+fn read_and_verify_elf(program_location: &str) -> Result<Vec<u8>, Box<dyn error::Error>> {
+    let mut file = File::open(program_location).map_err(|err| {
+        CliError::DynamicProgramError(format!("Unable to open program file: {}", err))
+    })?;
+    let mut program_data = Vec::new();
+    file.read_to_end(&mut program_data).map_err(|err| {
+        CliError::DynamicProgramError(format!("Unable to read program file: {}", err))
+    })?;
+
+    Executable::<BPFError, ThisInstructionMeter>::from_elf(
+        &program_data,
+        Some(|x| bpf_verifier::check(x, false)),
+        Config::default(),
+    )
+    .map_err(|err| CliError::DynamicProgramError(format!("ELF error: {}", err)))?;
+
+    Ok(program_data)
+}
+
 fn process_deploy(
     rpc_client: &RpcClient,
     config: &CliConfig,
@@ -1223,20 +1308,7 @@ fn do_process_deploy(
     } else {
         &new_keypair
     };
-    let mut file = File::open(program_location).map_err(|err| {
-        CliError::DynamicProgramError(format!("Unable to open program file: {}", err))
-    })?;
-    let mut program_data = Vec::new();
-    file.read_to_end(&mut program_data).map_err(|err| {
-        CliError::DynamicProgramError(format!("Unable to read program file: {}", err))
-    })?;
-
-    Executable::<BPFError, ThisInstructionMeter>::from_elf(
-        &program_data,
-        Some(|x| bpf_verifier::check(x, false)),
-        Config::default(),
-    )
-    .map_err(|err| CliError::DynamicProgramError(format!("ELF error: {}", err)))?;
+    let program_data = read_and_verify_elf(program_location)?;
 
     let loader_id = if use_deprecated_loader {
         bpf_loader_deprecated::id()
@@ -1398,6 +1470,7 @@ fn do_process_deploy(
         write_transactions,
         &signers,
         config.commitment,
+        config.use_quic,
         last_valid_slot,
     )
     .map_err(|err| {
@@ -1430,6 +1503,324 @@ fn do_process_deploy(
     .to_string())
 }
 
+/// Create a Buffer account owned by the upgradeable loader and write the program data into it
+fn write_program_to_buffer(
+    rpc_client: &RpcClient,
+    config: &CliConfig,
+    program_data: &[u8],
+    buffer_signer: &dyn Signer,
+    upgrade_authority: &dyn Signer,
+    final_message: &Message,
+    final_balance_needed: u64,
+) -> Result<(), Box<dyn error::Error>> {
+    let payer = config.signers[0];
+    let buffer_lamports = rpc_client.get_minimum_balance_for_rent_exemption(
+        UpgradeableLoaderState::buffer_len(program_data.len())?,
+    )?;
+
+    let create_buffer_message = Message::new(
+        &bpf_loader_upgradeable::create_buffer(
+            &payer.pubkey(),
+            &buffer_signer.pubkey(),
+            Some(&upgrade_authority.pubkey()),
+            buffer_lamports,
+            program_data.len(),
+        )?,
+        Some(&payer.pubkey()),
+    );
+
+    let mut write_messages = vec![];
+    for (chunk, i) in program_data.chunks(DATA_CHUNK_SIZE).zip(0..) {
+        let instruction = bpf_loader_upgradeable::write(
+            &buffer_signer.pubkey(),
+            &upgrade_authority.pubkey(),
+            (i * DATA_CHUNK_SIZE) as u32,
+            chunk.to_vec(),
+        );
+        write_messages.push(Message::new(&[instruction], Some(&payer.pubkey())));
+    }
+
+    let mut messages: Vec<&Message> = vec![&create_buffer_message];
+    messages.extend(write_messages.iter());
+    messages.push(final_message);
+
+    let (blockhash, fee_calculator, _) = rpc_client
+        .get_recent_blockhash_with_commitment(config.commitment)?
+        .value;
+
+    check_account_for_spend_multiple_fees_with_commitment(
+        rpc_client,
+        &payer.pubkey(),
+        buffer_lamports + final_balance_needed,
+        &fee_calculator,
+        &messages,
+        config.commitment,
+    )?;
+
+    trace!("Creating buffer account");
+    let mut create_buffer_tx = Transaction::new_unsigned(create_buffer_message);
+    create_buffer_tx.try_sign(&[payer, buffer_signer], blockhash)?;
+    let result = rpc_client.send_and_confirm_transaction_with_spinner_and_config(
+        &create_buffer_tx,
+        config.commitment,
+        config.send_transaction_config,
+    );
+    log_instruction_custom_error::<SystemError>(result, &config).map_err(|err| {
+        CliError::DynamicProgramError(format!("Buffer account allocation failed: {}", err))
+    })?;
+
+    let (blockhash, _, last_valid_slot) = rpc_client
+        .get_recent_blockhash_with_commitment(config.commitment)?
+        .value;
+
+    let write_signers = [payer, upgrade_authority];
+    let mut write_transactions = vec![];
+    for message in write_messages.into_iter() {
+        let mut tx = Transaction::new_unsigned(message);
+        tx.try_sign(&write_signers, blockhash)?;
+        write_transactions.push(tx);
+    }
+
+    trace!("Writing program data");
+    send_and_confirm_transactions_with_spinner(
+        &rpc_client,
+        write_transactions,
+        &write_signers,
+        config.commitment,
+        config.use_quic,
+        last_valid_slot,
+    )
+    .map_err(|err| {
+        CliError::DynamicProgramError(format!(
+            "Data writes to buffer account {} failed: {}",
+            buffer_signer.pubkey(),
+            err
+        ))
+    })?;
+
+    Ok(())
+}
+
+fn process_deploy_upgradeable(
+    rpc_client: &RpcClient,
+    config: &CliConfig,
+    program_location: &str,
+    address: Option<SignerIndex>,
+    upgrade_authority: SignerIndex,
+    max_len: Option<usize>,
+) -> ProcessResult {
+    let payer = config.signers[0];
+    let upgrade_authority = config.signers[upgrade_authority];
+    let new_keypair = Keypair::new();
+    let program_signer = if let Some(i) = address {
+        config.signers[i]
+    } else {
+        &new_keypair
+    };
+    let buffer_keypair = Keypair::new();
+
+    let program_data = read_and_verify_elf(program_location)?;
+    // Default to twice the current program length to leave room for upgrades
+    let max_len = max_len.unwrap_or(program_data.len() * 2);
+    if max_len < program_data.len() {
+        return Err(CliError::BadParameter(format!(
+            "Max program length {} is smaller than the program, {} bytes",
+            max_len,
+            program_data.len()
+        ))
+        .into());
+    }
+
+    if rpc_client
+        .get_account_with_commitment(&program_signer.pubkey(), config.commitment)?
+        .value
+        .is_some()
+    {
+        return Err(CliError::DynamicProgramError(format!(
+            "Program account {} already exists",
+            program_signer.pubkey()
+        ))
+        .into());
+    }
+
+    let program_lamports =
+        rpc_client.get_minimum_balance_for_rent_exemption(UpgradeableLoaderState::program_len()?)?;
+    let programdata_lamports = rpc_client.get_minimum_balance_for_rent_exemption(
+        UpgradeableLoaderState::programdata_len(max_len)?,
+    )?;
+    let deploy_message = Message::new(
+        &bpf_loader_upgradeable::deploy_with_max_program_len(
+            &payer.pubkey(),
+            &program_signer.pubkey(),
+            &buffer_keypair.pubkey(),
+            &upgrade_authority.pubkey(),
+            program_lamports,
+            max_len,
+        )?,
+        Some(&payer.pubkey()),
+    );
+
+    write_program_to_buffer(
+        rpc_client,
+        config,
+        &program_data,
+        &buffer_keypair,
+        upgrade_authority,
+        &deploy_message,
+        program_lamports + programdata_lamports,
+    )?;
+
+    let (blockhash, _, _) = rpc_client
+        .get_recent_blockhash_with_commitment(config.commitment)?
+        .value;
+    let mut deploy_tx = Transaction::new_unsigned(deploy_message);
+    deploy_tx.try_sign(&[payer, program_signer, upgrade_authority], blockhash)?;
+
+    trace!("Deploying program");
+    rpc_client
+        .send_and_confirm_transaction_with_spinner_and_config(
+            &deploy_tx,
+            config.commitment,
+            RpcSendTransactionConfig {
+                skip_preflight: true,
+                ..RpcSendTransactionConfig::default()
+            },
+        )
+        .map_err(|e| CliError::DynamicProgramError(format!("Deploying program failed: {}", e)))?;
+
+    Ok(json!({
+        "programId": format!("{}", program_signer.pubkey()),
+        "upgradeAuthority": format!("{}", upgrade_authority.pubkey()),
+    })
+    .to_string())
+}
+
+fn process_upgrade(
+    rpc_client: &RpcClient,
+    config: &CliConfig,
+    program_location: &str,
+    program_id: &Pubkey,
+    upgrade_authority: SignerIndex,
+) -> ProcessResult {
+    let payer = config.signers[0];
+    let upgrade_authority = config.signers[upgrade_authority];
+    let buffer_keypair = Keypair::new();
+
+    let program_data = read_and_verify_elf(program_location)?;
+
+    match rpc_client
+        .get_account_with_commitment(program_id, config.commitment)?
+        .value
+    {
+        Some(account) if bpf_loader_upgradeable::check_id(&account.owner) => {}
+        Some(_) => {
+            return Err(CliError::DynamicProgramError(format!(
+                "Program {} is not upgradeable",
+                program_id
+            ))
+            .into())
+        }
+        None => {
+            return Err(CliError::DynamicProgramError(format!(
+                "Program {} does not exist",
+                program_id
+            ))
+            .into())
+        }
+    }
+
+    let upgrade_message = Message::new(
+        &[bpf_loader_upgradeable::upgrade(
+            program_id,
+            &buffer_keypair.pubkey(),
+            &upgrade_authority.pubkey(),
+            &payer.pubkey(),
+        )],
+        Some(&payer.pubkey()),
+    );
+
+    write_program_to_buffer(
+        rpc_client,
+        config,
+        &program_data,
+        &buffer_keypair,
+        upgrade_authority,
+        &upgrade_message,
+        0,
+    )?;
+
+    let (blockhash, _, _) = rpc_client
+        .get_recent_blockhash_with_commitment(config.commitment)?
+        .value;
+    let mut upgrade_tx = Transaction::new_unsigned(upgrade_message);
+    upgrade_tx.try_sign(&[payer, upgrade_authority], blockhash)?;
+
+    trace!("Upgrading program");
+    rpc_client
+        .send_and_confirm_transaction_with_spinner_and_config(
+            &upgrade_tx,
+            config.commitment,
+            RpcSendTransactionConfig {
+                skip_preflight: true,
+                ..RpcSendTransactionConfig::default()
+            },
+        )
+        .map_err(|e| CliError::DynamicProgramError(format!("Upgrading program failed: {}", e)))?;
+
+    Ok(json!({
+        "programId": format!("{}", program_id),
+    })
+    .to_string())
+}
+
+fn process_set_upgrade_authority(
+    rpc_client: &RpcClient,
+    config: &CliConfig,
+    program_id: &Pubkey,
+    upgrade_authority: SignerIndex,
+    new_upgrade_authority: Option<&Pubkey>,
+) -> ProcessResult {
+    let payer = config.signers[0];
+    let upgrade_authority = config.signers[upgrade_authority];
+
+    let (blockhash, fee_calculator, _) = rpc_client
+        .get_recent_blockhash_with_commitment(config.commitment)?
+        .value;
+    let message = Message::new(
+        &[bpf_loader_upgradeable::set_upgrade_authority(
+            program_id,
+            &upgrade_authority.pubkey(),
+            new_upgrade_authority,
+        )],
+        Some(&payer.pubkey()),
+    );
+    check_account_for_fee_with_commitment(
+        rpc_client,
+        &payer.pubkey(),
+        &fee_calculator,
+        &message,
+        config.commitment,
+    )?;
+
+    let mut tx = Transaction::new_unsigned(message);
+    tx.try_sign(&[payer, upgrade_authority], blockhash)?;
+    rpc_client
+        .send_and_confirm_transaction_with_spinner_and_config(
+            &tx,
+            config.commitment,
+            config.send_transaction_config,
+        )
+        .map_err(|e| {
+            CliError::DynamicProgramError(format!("Setting upgrade authority failed: {}", e))
+        })?;
+
+    Ok(json!({
+        "programId": format!("{}", program_id),
+        "upgradeAuthority": new_upgrade_authority.map(|pubkey| pubkey.to_string()),
+    })
+    .to_string())
+}
+
 #[allow(clippy::too_many_arguments)]
 fn process_transfer(
     rpc_client: &RpcClient,
@@ -1689,6 +2080,47 @@ pub fn process_command(config: &CliConfig) -> ProcessResult {
             *allow_excessive_balance,
         ),
 
+        // Deploy a custom program with the upgradeable loader
+        CliCommand::DeployUpgradeable {
+            program_location,
+            address,
+            upgrade_authority,
+            max_len,
+        } => process_deploy_upgradeable(
+            &rpc_client,
+            config,
+            program_location,
+            *address,
+            *upgrade_authority,
+            *max_len,
+        ),
+
+        // Upgrade a program deployed with the upgradeable loader
+        CliCommand::Upgrade {
+            program_location,
+            program_id,
+            upgrade_authority,
+        } => process_upgrade(
+            &rpc_client,
+            config,
+            program_location,
+            program_id,
+            *upgrade_authority,
+        ),
+
+        // Set or revoke the upgrade authority of a program
+        CliCommand::SetUpgradeAuthority {
+            program_id,
+            upgrade_authority,
+            new_upgrade_authority,
+        } => process_set_upgrade_authority(
+            &rpc_client,
+            config,
+            program_id,
+            *upgrade_authority,
+            new_upgrade_authority.as_ref(),
+        ),
+
         // Stake Commands
 
         // Create stake account
@@ -2321,8 +2753,74 @@ pub fn app<'ab, 'v>(name: &str, about: &'ab str, version: &'v str) -> App<'ab, '
                         .takes_value(false)
                         .help("Use the designated program id, even if the account already holds a large balance of SOL")
                 )
+                .arg(
+                    Arg::with_name("upgradeable")
+                        .long("upgradeable")
+                        .takes_value(false)
+                        .conflicts_with("use_deprecated_loader")
+                        .help("Deploy or upgrade the program with the upgradeable BPF loader")
+                )
+                .arg(
+                    Arg::with_name("upgrade_authority")
+                        .long("upgrade-authority")
+                        .value_name("UPGRADE_AUTHORITY_SIGNER")
+                        .takes_value(true)
+                        .requires("upgradeable")
+                        .validator(is_valid_signer)
+                        .help("Upgrade authority of the program [default: the default configured keypair]")
+                )
+                .arg(
+                    pubkey!(Arg::with_name("program_id")
+                        .long("program-id")
+                        .value_name("PROGRAM_ID")
+                        .requires("upgradeable")
+                        .conflicts_with("address_signer"),
+                        "Upgrade this existing program instead of deploying a new one. "),
+                )
+                .arg(
+                    Arg::with_name("max_len")
+                        .long("max-len")
+                        .value_name("MAX_LEN")
+                        .takes_value(true)
+                        .requires("upgradeable")
+                        .conflicts_with("program_id")
+                        .validator(is_parsable::<usize>)
+                        .help("Maximum length of the upgradeable program [default: twice the length of the original deployed program]")
+                )
                 .arg(commitment_arg_with_default("max")),
         )
+        .subcommand(
+            SubCommand::with_name("set-upgrade-authority")
+                .about("Set or revoke the upgrade authority of an upgradeable program")
+                .arg(
+                    pubkey!(Arg::with_name("program_id")
+                        .index(1)
+                        .value_name("PROGRAM_ID")
+                        .required(true),
+                        "Address of the upgradeable program. "),
+                )
+                .arg(
+                    Arg::with_name("upgrade_authority")
+                        .long("upgrade-authority")
+                        .value_name("UPGRADE_AUTHORITY_SIGNER")
+                        .takes_value(true)
+                        .validator(is_valid_signer)
+                        .help("Current upgrade authority [default: the default configured keypair]")
+                )
+                .arg(
+                    pubkey!(Arg::with_name("new_upgrade_authority")
+                        .long("new-upgrade-authority")
+                        .value_name("NEW_UPGRADE_AUTHORITY")
+                        .required_unless("final"),
+                        "Address of the new upgrade authority. "),
+                )
+                .arg(
+                    Arg::with_name("final")
+                        .long("final")
+                        .conflicts_with("new_upgrade_authority")
+                        .help("Revoke the upgrade authority, the program will no longer be upgradeable")
+                ),
+        )
         .subcommand(
             SubCommand::with_name("pay")
                 .about("Deprecated alias for the transfer command")
@@ -2720,6 +3218,100 @@ mod tests {
             }
         );
 
+        let test_deploy = test_commands.clone().get_matches_from(vec![
+            "test",
+            "deploy",
+            "/Users/test/program.o",
+            "--upgradeable",
+            "--max-len",
+            "42",
+        ]);
+        assert_eq!(
+            parse_command(&test_deploy, &default_signer, &mut None).unwrap(),
+            CliCommandInfo {
+                command: CliCommand::DeployUpgradeable {
+                    program_location: "/Users/test/program.o".to_string(),
+                    address: None,
+                    upgrade_authority: 0,
+                    max_len: Some(42),
+                },
+                signers: vec![read_keypair_file(&keypair_file).unwrap().into()],
+            }
+        );
+
+        let upgrade_authority = Keypair::new();
+        let upgrade_authority_file = make_tmp_path("upgrade_authority_file");
+        write_keypair_file(&upgrade_authority, &upgrade_authority_file).unwrap();
+        let program_id = Pubkey::new_unique();
+        let test_deploy = test_commands.clone().get_matches_from(vec![
+            "test",
+            "deploy",
+            "/Users/test/program.o",
+            "--upgradeable",
+            "--program-id",
+            &program_id.to_string(),
+            "--upgrade-authority",
+            &upgrade_authority_file,
+        ]);
+        assert_eq!(
+            parse_command(&test_deploy, &default_signer, &mut None).unwrap(),
+            CliCommandInfo {
+                command: CliCommand::Upgrade {
+                    program_location: "/Users/test/program.o".to_string(),
+                    program_id,
+                    upgrade_authority: 1,
+                },
+                signers: vec![
+                    read_keypair_file(&keypair_file).unwrap().into(),
+                    read_keypair_file(&upgrade_authority_file).unwrap().into(),
+                ],
+            }
+        );
+
+        // Test SetUpgradeAuthority Subcommand
+        let new_upgrade_authority = Pubkey::new_unique();
+        let test_set_upgrade_authority = test_commands.clone().get_matches_from(vec![
+            "test",
+            "set-upgrade-authority",
+            &program_id.to_string(),
+            "--new-upgrade-authority",
+            &new_upgrade_authority.to_string(),
+        ]);
+        assert_eq!(
+            parse_command(&test_set_upgrade_authority, &default_signer, &mut None).unwrap(),
+            CliCommandInfo {
+                command: CliCommand::SetUpgradeAuthority {
+                    program_id,
+                    upgrade_authority: 0,
+                    new_upgrade_authority: Some(new_upgrade_authority),
+                },
+                signers: vec![read_keypair_file(&keypair_file).unwrap().into()],
+            }
+        );
+
+        let test_set_upgrade_authority = test_commands.clone().get_matches_from(vec![
+            "test",
+            "set-upgrade-authority",
+            &program_id.to_string(),
+            "--upgrade-authority",
+            &upgrade_authority_file,
+            "--final",
+        ]);
+        assert_eq!(
+            parse_command(&test_set_upgrade_authority, &default_signer, &mut None).unwrap(),
+            CliCommandInfo {
+                command: CliCommand::SetUpgradeAuthority {
+                    program_id,
+                    upgrade_authority: 1,
+                    new_upgrade_authority: None,
+                },
+                signers: vec![
+                    read_keypair_file(&keypair_file).unwrap().into(),
+                    read_keypair_file(&upgrade_authority_file).unwrap().into(),
+                ],
+            }
+        );
+
         // Test ResolveSigner Subcommand, KeypairUrl::Filepath
         let test_resolve_signer =
             test_commands
@@ -203,6 +203,7 @@ pub fn parse_args<'a>(
             commitment,
             send_transaction_config: RpcSendTransactionConfig::default(),
             address_labels,
+            use_quic: matches.is_present("use_quic"),
         },
         signers,
     ))
@@ -270,6 +271,12 @@ fn main() -> Result<(), Box<dyn error::Error>> {
             .global(true)
             .help("Do not use address labels in the output"),
     )
+    .arg(
+        Arg::with_name("use_quic")
+            .long("use-quic")
+            .global(true)
+            .help("Send transactions directly to the leader over QUIC instead of UDP"),
+    )
     .arg(
         Arg::with_name("output_format")
             .long("output")
@@ -1,6 +1,13 @@
 use log::*;
-use solana_client::rpc_response::{RpcContactInfo, RpcLeaderSchedule};
-use std::net::{SocketAddr, UdpSocket};
+use solana_client::{
+    quic_client::QuicClient,
+    rpc_response::{RpcContactInfo, RpcLeaderSchedule},
+};
+use solana_sdk::quic::tpu_quic_addr;
+use std::{
+    collections::HashMap,
+    net::{SocketAddr, UdpSocket},
+};
 
 pub fn get_leader_tpu(
     slot_index: u64,
@@ -27,3 +34,44 @@ pub fn send_transaction_tpu(
         warn!("Failed to send transaction to {}: {:?}", tpu_address, err);
     }
 }
+
+/// Sends transactions to leader TPUs, over QUIC when enabled.  A leader whose QUIC TPU cannot be
+/// reached is sent to over UDP from then on.
+pub struct TpuSender {
+    send_socket: UdpSocket,
+    use_quic: bool,
+    quic_clients: HashMap<SocketAddr, Option<QuicClient>>,
+}
+
+impl TpuSender {
+    pub fn new(use_quic: bool) -> Self {
+        Self {
+            send_socket: UdpSocket::bind("0.0.0.0:0").unwrap(),
+            use_quic,
+            quic_clients: HashMap::new(),
+        }
+    }
+
+    pub fn send(&mut self, tpu_address: &SocketAddr, wire_transaction: &[u8]) {
+        if self.use_quic {
+            let quic_client = self
+                .quic_clients
+                .entry(*tpu_address)
+                .or_insert_with(|| QuicClient::new(tpu_quic_addr(tpu_address)).ok());
+            if let Some(client) = quic_client {
+                match client.send_wire_transaction(wire_transaction) {
+                    Ok(()) => return,
+                    Err(err) => {
+                        warn!(
+                            "Failed to send transaction over QUIC to {}, using UDP instead: {:?}",
+                            client.tpu_addr(),
+                            err
+                        );
+                        *quic_client = None;
+                    }
+                }
+            }
+        }
+        send_transaction_tpu(&self.send_socket, tpu_address, wire_transaction);
+    }
+}
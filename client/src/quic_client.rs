@@ -0,0 +1,417 @@
+//! The `quic_client` module sends wire transactions to the QUIC TPU of a node, one transaction
+//! per unidirectional stream.  The connection is kept open between sends and re-established
+//! whenever the server has closed it.
+
+use bytes::BytesMut;
+use log::*;
+use quinn_proto::{
+    ClientConfig, Connection, ConnectionHandle, DatagramEvent, Dir, Endpoint, EndpointConfig,
+    Event, StreamId, TransportConfig, WriteError,
+};
+use solana_sdk::{
+    quic::{QUIC_ALPN_PROTOCOL, QUIC_MAX_TIMEOUT_MS},
+    transport::{Result as TransportResult, TransportError},
+};
+use std::{
+    collections::HashSet,
+    io,
+    net::{IpAddr, Ipv4Addr, SocketAddr, UdpSocket},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+const MAX_DATAGRAM_SIZE: usize = 64 * 1024;
+
+/// The TPU presents a self-signed certificate, the connection is only used to carry transactions
+/// which are signed and verified on their own
+struct SkipServerVerification;
+
+impl rustls::ServerCertVerifier for SkipServerVerification {
+    fn verify_server_cert(
+        &self,
+        _roots: &rustls::RootCertStore,
+        _presented_certs: &[rustls::Certificate],
+        _dns_name: webpki::DNSNameRef,
+        _ocsp_response: &[u8],
+    ) -> Result<rustls::ServerCertVerified, rustls::TLSError> {
+        Ok(rustls::ServerCertVerified::assertion())
+    }
+}
+
+fn new_client_config() -> ClientConfig {
+    let mut transport_config = TransportConfig::default();
+    transport_config
+        .stream_window_bidi(0)
+        .stream_window_uni(0)
+        .max_idle_timeout(Some(Duration::from_millis(QUIC_MAX_TIMEOUT_MS)))
+        .expect("idle timeout is in range");
+
+    let mut client_config = ClientConfig {
+        transport: Arc::new(transport_config),
+        ..ClientConfig::default()
+    };
+    let crypto = Arc::make_mut(&mut client_config.crypto);
+    crypto
+        .dangerous()
+        .set_certificate_verifier(Arc::new(SkipServerVerification));
+    crypto.set_protocols(&[QUIC_ALPN_PROTOCOL.to_vec()]);
+    client_config
+}
+
+fn quic_error<E: std::fmt::Display>(err: E) -> TransportError {
+    TransportError::Custom(format!("quic error: {}", err))
+}
+
+/// A connection to the QUIC TPU of one node
+pub struct QuicClient {
+    tpu_addr: SocketAddr,
+    inner: Mutex<QuicClientInner>,
+}
+
+struct QuicClientInner {
+    socket: UdpSocket,
+    endpoint: Endpoint,
+    client_config: ClientConfig,
+    connection: Option<(ConnectionHandle, Connection)>,
+}
+
+impl QuicClient {
+    /// Create a client for the QUIC TPU at `tpu_addr`, no connection is made until the first send
+    pub fn new(tpu_addr: SocketAddr) -> io::Result<Self> {
+        let socket = UdpSocket::bind(SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), 0))?;
+        Ok(Self {
+            tpu_addr,
+            inner: Mutex::new(QuicClientInner {
+                socket,
+                endpoint: Endpoint::new(Arc::new(EndpointConfig::default()), None),
+                client_config: new_client_config(),
+                connection: None,
+            }),
+        })
+    }
+
+    pub fn tpu_addr(&self) -> &SocketAddr {
+        &self.tpu_addr
+    }
+
+    pub fn send_wire_transaction(&self, wire_transaction: &[u8]) -> TransportResult<()> {
+        self.send_batch(&[wire_transaction])
+    }
+
+    /// Send each transaction on its own stream, and wait until the server has received them all.
+    /// Fails if the server refuses any of the streams, e.g. over its rate limit
+    pub fn send_batch<T: AsRef<[u8]>>(&self, wire_transactions: &[T]) -> TransportResult<()> {
+        let mut inner = self.inner.lock().unwrap();
+        let result = inner.send_batch(self.tpu_addr, wire_transactions);
+        if result.is_err() {
+            // Start over with a new connection next time
+            inner.connection = None;
+        }
+        result
+    }
+}
+
+impl QuicClientInner {
+    fn send_batch<T: AsRef<[u8]>>(
+        &mut self,
+        tpu_addr: SocketAddr,
+        wire_transactions: &[T],
+    ) -> TransportResult<()> {
+        let deadline = Instant::now() + Duration::from_millis(QUIC_MAX_TIMEOUT_MS);
+        self.connect(tpu_addr, deadline)?;
+
+        let mut unfinished = HashSet::new();
+        for wire_transaction in wire_transactions {
+            let stream = loop {
+                if let Some(stream) = self.connection_mut().open(Dir::Uni) {
+                    break stream;
+                }
+                // Wait for the server to allow more streams
+                self.drive(deadline, &mut unfinished)?;
+            };
+            self.write_all(stream, wire_transaction.as_ref(), deadline, &mut unfinished)?;
+            self.connection_mut().finish(stream).map_err(quic_error)?;
+            unfinished.insert(stream);
+        }
+        while !unfinished.is_empty() {
+            self.drive(deadline, &mut unfinished)?;
+        }
+        Ok(())
+    }
+
+    fn connection_mut(&mut self) -> &mut Connection {
+        &mut self.connection.as_mut().unwrap().1
+    }
+
+    /// Reuse the open connection or make a new one
+    fn connect(&mut self, tpu_addr: SocketAddr, deadline: Instant) -> TransportResult<()> {
+        if let Some((_, connection)) = self.connection.as_mut() {
+            let now = Instant::now();
+            if connection.poll_timeout().map_or(false, |t| t <= now) {
+                connection.handle_timeout(now);
+            }
+            if !connection.is_closed() {
+                return Ok(());
+            }
+        }
+        let connection = self
+            .endpoint
+            .connect(self.client_config.clone(), tpu_addr, "solana-tpu")
+            .map_err(quic_error)?;
+        self.connection = Some(connection);
+        let mut unfinished = HashSet::new();
+        while self.connection_mut().is_handshaking() {
+            self.drive(deadline, &mut unfinished)?;
+        }
+        Ok(())
+    }
+
+    fn write_all(
+        &mut self,
+        stream: StreamId,
+        mut data: &[u8],
+        deadline: Instant,
+        unfinished: &mut HashSet<StreamId>,
+    ) -> TransportResult<()> {
+        while !data.is_empty() {
+            match self.connection_mut().write(stream, data) {
+                Ok(len) => data = &data[len..],
+                Err(WriteError::Blocked) => self.drive(deadline, unfinished)?,
+                Err(err) => return Err(quic_error(err)),
+            }
+        }
+        Ok(())
+    }
+
+    /// Sends whatever the connection has queued, then waits for and processes the next datagrams
+    /// from the server.  `unfinished` streams are removed once the server has acknowledged them,
+    /// a stream the server stopped instead is an error.
+    fn drive(
+        &mut self,
+        deadline: Instant,
+        unfinished: &mut HashSet<StreamId>,
+    ) -> TransportResult<()> {
+        self.flush()?;
+        let now = Instant::now();
+        if now >= deadline {
+            return Err(io::Error::new(io::ErrorKind::TimedOut, "quic send timed out").into());
+        }
+        let (handle, connection) = self.connection.as_mut().unwrap();
+        let handle = *handle;
+        let wakeup = connection
+            .poll_timeout()
+            .map_or(deadline, |t| t.min(deadline));
+        let timeout = wakeup
+            .saturating_duration_since(now)
+            .max(Duration::from_millis(1));
+        self.socket.set_read_timeout(Some(timeout))?;
+
+        let mut buf = vec![0u8; MAX_DATAGRAM_SIZE];
+        match self.socket.recv_from(&mut buf) {
+            Ok((len, remote)) => {
+                let event =
+                    self.endpoint
+                        .handle(Instant::now(), remote, None, BytesMut::from(&buf[..len]));
+                if let Some((_, DatagramEvent::ConnectionEvent(event))) = event {
+                    self.connection_mut().handle_event(event);
+                }
+            }
+            Err(err)
+                if err.kind() == io::ErrorKind::WouldBlock
+                    || err.kind() == io::ErrorKind::TimedOut => {}
+            Err(err) => return Err(err.into()),
+        }
+
+        let now = Instant::now();
+        let (_, connection) = self.connection.as_mut().unwrap();
+        if connection.poll_timeout().map_or(false, |t| t <= now) {
+            connection.handle_timeout(now);
+        }
+        while let Some(event) = connection.poll() {
+            match event {
+                Event::StreamFinished {
+                    stream,
+                    stop_reason: None,
+                } => {
+                    unfinished.remove(&stream);
+                }
+                Event::StreamFinished {
+                    stream,
+                    stop_reason: Some(error_code),
+                } => {
+                    debug!("quic stream {:?} stopped: {:?}", stream, error_code);
+                    return Err(quic_error(format!(
+                        "stream refused by the server with {:?}",
+                        error_code
+                    )));
+                }
+                Event::ConnectionLost { reason } => {
+                    debug!("quic connection lost: {}", reason);
+                    return Err(quic_error(reason));
+                }
+                _ => (),
+            }
+        }
+        while let Some(event) = connection.poll_endpoint_events() {
+            if let Some(event) = self.endpoint.handle_event(handle, event) {
+                connection.handle_event(event);
+            }
+        }
+        self.flush()
+    }
+
+    fn flush(&mut self) -> TransportResult<()> {
+        let now = Instant::now();
+        let (_, connection) = self.connection.as_mut().unwrap();
+        while let Some(transmit) = connection.poll_transmit(now) {
+            self.socket
+                .send_to(&transmit.contents, transmit.destination)?;
+        }
+        while let Some(transmit) = self.endpoint.poll_transmit() {
+            self.socket
+                .send_to(&transmit.contents, transmit.destination)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_streamer::quic::{spawn_server, QuicServerConfig, StakedNodes};
+    use std::sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::channel,
+    };
+
+    fn received_transactions(
+        packet_receiver: &std::sync::mpsc::Receiver<solana_streamer::packet::Packets>,
+        count: usize,
+    ) -> Vec<Vec<u8>> {
+        let mut received = vec![];
+        while received.len() < count {
+            let packets = packet_receiver
+                .recv_timeout(Duration::from_secs(10))
+                .unwrap();
+            received.extend(
+                packets
+                    .packets
+                    .iter()
+                    .map(|p| p.data[..p.meta.size].to_vec()),
+            );
+        }
+        received.sort();
+        received
+    }
+
+    #[test]
+    fn test_quic_client_sends_to_server() {
+        solana_logger::setup();
+        let sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let tpu_addr = sock.local_addr().unwrap();
+        let exit = Arc::new(AtomicBool::new(false));
+        let (packet_sender, packet_receiver) = channel();
+        let server = spawn_server(
+            sock,
+            packet_sender,
+            StakedNodes::default(),
+            QuicServerConfig::default(),
+            exit.clone(),
+        )
+        .unwrap();
+
+        let client = QuicClient::new(tpu_addr).unwrap();
+        let transactions: Vec<_> = (0..10u8).map(|i| vec![i; 100 + i as usize]).collect();
+        client.send_batch(&transactions).unwrap();
+        assert_eq!(
+            received_transactions(&packet_receiver, transactions.len()),
+            transactions
+        );
+
+        // The connection is reused for the next send
+        client.send_wire_transaction(&[42; 200]).unwrap();
+        assert_eq!(
+            received_transactions(&packet_receiver, 1),
+            vec![vec![42; 200]]
+        );
+
+        exit.store(true, Ordering::Relaxed);
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_quic_unfinished_handshake_holds_no_quota() {
+        solana_logger::setup();
+        let sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let tpu_addr = sock.local_addr().unwrap();
+        let exit = Arc::new(AtomicBool::new(false));
+        let (packet_sender, packet_receiver) = channel();
+        let server = spawn_server(
+            sock,
+            packet_sender,
+            StakedNodes::default(),
+            QuicServerConfig {
+                max_connections_per_ip: 1,
+                max_unstaked_connections: 1,
+                ..QuicServerConfig::default()
+            },
+            exit.clone(),
+        )
+        .unwrap();
+
+        // Start a handshake from the same address and never finish it
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let mut endpoint = Endpoint::new(Arc::new(EndpointConfig::default()), None);
+        let (_, mut connection) = endpoint
+            .connect(new_client_config(), tpu_addr, "solana-tpu")
+            .unwrap();
+        while let Some(transmit) = connection.poll_transmit(Instant::now()) {
+            socket
+                .send_to(&transmit.contents, transmit.destination)
+                .unwrap();
+        }
+        std::thread::sleep(Duration::from_millis(100));
+
+        let client = QuicClient::new(tpu_addr).unwrap();
+        client.send_wire_transaction(&[42; 200]).unwrap();
+        assert_eq!(
+            received_transactions(&packet_receiver, 1),
+            vec![vec![42; 200]]
+        );
+
+        exit.store(true, Ordering::Relaxed);
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_quic_client_rate_limited() {
+        solana_logger::setup();
+        let sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let tpu_addr = sock.local_addr().unwrap();
+        let exit = Arc::new(AtomicBool::new(false));
+        let (packet_sender, packet_receiver) = channel();
+        let server = spawn_server(
+            sock,
+            packet_sender,
+            StakedNodes::default(),
+            QuicServerConfig {
+                max_streams_per_second: 2,
+                ..QuicServerConfig::default()
+            },
+            exit.clone(),
+        )
+        .unwrap();
+
+        // Only the first two make it to the server, and the refused ones fail the send
+        let client = QuicClient::new(tpu_addr).unwrap();
+        let transactions: Vec<_> = (0..5u8).map(|i| vec![i; 100]).collect();
+        assert!(client.send_batch(&transactions).is_err());
+        assert_eq!(received_transactions(&packet_receiver, 2).len(), 2);
+        assert!(packet_receiver
+            .recv_timeout(Duration::from_millis(500))
+            .is_err());
+
+        exit.store(true, Ordering::Relaxed);
+        server.join().unwrap();
+    }
+}
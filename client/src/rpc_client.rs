@@ -659,6 +659,7 @@ impl RpcClient {
             encoding: Some(UiAccountEncoding::Base64),
             commitment: Some(commitment_config),
             data_slice: None,
+            at_slot: None,
         };
         let response = self.sender.send(
             RpcRequest::GetAccountInfo,
@@ -706,6 +707,7 @@ impl RpcClient {
             encoding: Some(UiAccountEncoding::Base64),
             commitment: Some(commitment_config),
             data_slice: None,
+            at_slot: None,
         };
         let pubkeys: Vec<_> = pubkeys.iter().map(|pubkey| pubkey.to_string()).collect();
         let response = self.send(RpcRequest::GetMultipleAccounts, json!([pubkeys, config]))?;
@@ -947,6 +949,7 @@ impl RpcClient {
             encoding: Some(UiAccountEncoding::JsonParsed),
             commitment: Some(commitment_config),
             data_slice: None,
+            at_slot: None,
         };
         let response = self.sender.send(
             RpcRequest::GetAccountInfo,
@@ -1041,6 +1044,7 @@ impl RpcClient {
             encoding: Some(UiAccountEncoding::JsonParsed),
             commitment: Some(commitment_config),
             data_slice: None,
+            at_slot: None,
         };
 
         self.send(
@@ -1080,6 +1084,7 @@ impl RpcClient {
             encoding: Some(UiAccountEncoding::JsonParsed),
             commitment: Some(commitment_config),
             data_slice: None,
+            at_slot: None,
         };
 
         self.send(
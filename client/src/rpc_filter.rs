@@ -1,3 +1,6 @@
+use solana_account_decoder::parse_token::{is_initialized_token_account, spl_token_id_v2_0};
+use solana_sdk::{account::Account, pubkey::Pubkey};
+use std::borrow::Cow;
 use thiserror::Error;
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -5,23 +8,42 @@ use thiserror::Error;
 pub enum RpcFilterType {
     DataSize(u64),
     Memcmp(Memcmp),
+    /// Matches initialized SPL Token accounts
+    TokenAccountState,
+    /// Matches accounts whose balance falls within an inclusive range
+    Lamports(LamportsRange),
+    /// Matches accounts whose owner differs from the owner in the parent bank, and accounts that
+    /// didn't exist in the parent bank
+    OwnerChanged,
 }
 
 impl RpcFilterType {
     pub fn verify(&self) -> Result<(), RpcFilterError> {
         match self {
             RpcFilterType::DataSize(_) => Ok(()),
-            RpcFilterType::Memcmp(compare) => {
-                let encoding = compare.encoding.as_ref().unwrap_or(&MemcmpEncoding::Binary);
-                match encoding {
-                    MemcmpEncoding::Binary => {
-                        let MemcmpEncodedBytes::Binary(bytes) = &compare.bytes;
-                        bs58::decode(&bytes)
-                            .into_vec()
-                            .map(|_| ())
-                            .map_err(|e| e.into())
-                    }
-                }
+            RpcFilterType::Memcmp(compare) => compare.decode_bytes().map(|_| ()),
+            RpcFilterType::TokenAccountState => Ok(()),
+            RpcFilterType::Lamports(range) => match (range.min, range.max) {
+                (Some(min), Some(max)) if min > max => Err(RpcFilterError::InvalidLamportsRange),
+                _ => Ok(()),
+            },
+            RpcFilterType::OwnerChanged => Ok(()),
+        }
+    }
+
+    /// Returns true if `account` passes the filter. `previous_owner` is the owner of the account
+    /// in the parent bank, or None if it didn't exist there, and is only consulted by
+    /// `OwnerChanged`
+    pub fn allows(&self, account: &Account, previous_owner: Option<&Pubkey>) -> bool {
+        match self {
+            RpcFilterType::DataSize(size) => account.data.len() as u64 == *size,
+            RpcFilterType::Memcmp(compare) => compare.bytes_match(&account.data),
+            RpcFilterType::TokenAccountState => {
+                account.owner == spl_token_id_v2_0() && is_initialized_token_account(&account.data)
+            }
+            RpcFilterType::Lamports(range) => range.contains(account.lamports),
+            RpcFilterType::OwnerChanged => {
+                previous_owner.map_or(true, |owner| *owner != account.owner)
             }
         }
     }
@@ -31,12 +53,19 @@ impl RpcFilterType {
 pub enum RpcFilterError {
     #[error("bs58 decode error")]
     DecodeError(#[from] bs58::decode::Error),
+    #[error("base64 decode error")]
+    Base64DecodeError(#[from] base64::DecodeError),
+    #[error("lamports range minimum exceeds its maximum")]
+    InvalidLamportsRange,
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub enum MemcmpEncoding {
+    /// Base-58 encoded bytes, retained for compatibility with existing clients
     Binary,
+    Base58,
+    Base64,
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -56,14 +85,19 @@ pub struct Memcmp {
 }
 
 impl Memcmp {
+    pub fn decode_bytes(&self) -> Result<Cow<[u8]>, RpcFilterError> {
+        let MemcmpEncodedBytes::Binary(bytes) = &self.bytes;
+        match self.encoding.as_ref().unwrap_or(&MemcmpEncoding::Binary) {
+            MemcmpEncoding::Binary | MemcmpEncoding::Base58 => {
+                Ok(Cow::Owned(bs58::decode(bytes).into_vec()?))
+            }
+            MemcmpEncoding::Base64 => Ok(Cow::Owned(base64::decode(bytes)?)),
+        }
+    }
+
     pub fn bytes_match(&self, data: &[u8]) -> bool {
-        match &self.bytes {
-            MemcmpEncodedBytes::Binary(bytes) => {
-                let bytes = bs58::decode(bytes).into_vec();
-                if bytes.is_err() {
-                    return false;
-                }
-                let bytes = bytes.unwrap();
+        match self.decode_bytes() {
+            Ok(bytes) => {
                 if self.offset > data.len() {
                     return false;
                 }
@@ -72,10 +106,23 @@ impl Memcmp {
                 }
                 data[self.offset..self.offset + bytes.len()] == bytes[..]
             }
+            Err(_) => false,
         }
     }
 }
 
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LamportsRange {
+    pub min: Option<u64>,
+    pub max: Option<u64>,
+}
+
+impl LamportsRange {
+    pub fn contains(&self, lamports: u64) -> bool {
+        self.min.map_or(true, |min| lamports >= min) && self.max.map_or(true, |max| lamports <= max)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -140,4 +187,76 @@ mod tests {
         }
         .bytes_match(&data));
     }
+
+    #[test]
+    fn test_bytes_match_base64() {
+        let data = vec![1, 2, 3, 4, 5];
+
+        assert!(Memcmp {
+            offset: 1,
+            bytes: MemcmpEncodedBytes::Binary(base64::encode(vec![2, 3])),
+            encoding: Some(MemcmpEncoding::Base64),
+        }
+        .bytes_match(&data));
+
+        assert!(!Memcmp {
+            offset: 0,
+            bytes: MemcmpEncodedBytes::Binary(base64::encode(vec![2, 3])),
+            encoding: Some(MemcmpEncoding::Base64),
+        }
+        .bytes_match(&data));
+
+        // Invalid base-64 fails
+        assert!(!Memcmp {
+            offset: 0,
+            bytes: MemcmpEncodedBytes::Binary("#".to_string()),
+            encoding: Some(MemcmpEncoding::Base64),
+        }
+        .bytes_match(&data));
+    }
+
+    #[test]
+    fn test_filter_allows() {
+        let owner = Pubkey::new_unique();
+        let account = Account::new(42, 3, &owner);
+
+        let lamports = |min, max| RpcFilterType::Lamports(LamportsRange { min, max });
+        assert!(lamports(None, None).allows(&account, None));
+        assert!(lamports(Some(42), Some(42)).allows(&account, None));
+        assert!(lamports(Some(1), None).allows(&account, None));
+        assert!(!lamports(Some(43), None).allows(&account, None));
+        assert!(!lamports(None, Some(41)).allows(&account, None));
+        assert!(lamports(Some(43), Some(42)).verify().is_err());
+
+        assert!(RpcFilterType::OwnerChanged.allows(&account, None));
+        assert!(!RpcFilterType::OwnerChanged.allows(&account, Some(&owner)));
+        assert!(RpcFilterType::OwnerChanged.allows(&account, Some(&Pubkey::default())));
+
+        assert!(!RpcFilterType::TokenAccountState.allows(&account, None));
+    }
+
+    #[test]
+    fn test_filter_serde() {
+        let filters: Vec<RpcFilterType> = serde_json::from_str(
+            r#"[{"dataSize":165},"tokenAccountState","ownerChanged",{"lamports":{"min":1}},{"memcmp":{"offset":4,"bytes":"AQI=","encoding":"base64"}}]"#,
+        )
+        .unwrap();
+        assert_eq!(
+            filters,
+            vec![
+                RpcFilterType::DataSize(165),
+                RpcFilterType::TokenAccountState,
+                RpcFilterType::OwnerChanged,
+                RpcFilterType::Lamports(LamportsRange {
+                    min: Some(1),
+                    max: None
+                }),
+                RpcFilterType::Memcmp(Memcmp {
+                    offset: 4,
+                    bytes: MemcmpEncodedBytes::Binary("AQI=".to_string()),
+                    encoding: Some(MemcmpEncoding::Base64),
+                }),
+            ]
+        );
+    }
 }
@@ -3,7 +3,10 @@
 //! messages to the network directly. The binary encoding of its messages are
 //! unstable and may change in future releases.
 
-use crate::{rpc_client::RpcClient, rpc_config::RpcProgramAccountsConfig, rpc_response::Response};
+use crate::{
+    quic_client::QuicClient, rpc_client::RpcClient, rpc_config::RpcProgramAccountsConfig,
+    rpc_response::Response,
+};
 use bincode::{serialize_into, serialized_size};
 use log::*;
 use solana_sdk::{
@@ -18,6 +21,7 @@ use solana_sdk::{
     message::Message,
     packet::PACKET_DATA_SIZE,
     pubkey::Pubkey,
+    quic::tpu_quic_addr,
     signature::{Keypair, Signature, Signer},
     signers::Signers,
     system_instruction,
@@ -118,6 +122,7 @@ impl ClientOptimizer {
 pub struct ThinClient {
     transactions_socket: UdpSocket,
     tpu_addrs: Vec<SocketAddr>,
+    quic_clients: Vec<QuicClient>,
     rpc_clients: Vec<RpcClient>,
     optimizer: ClientOptimizer,
 }
@@ -151,6 +156,7 @@ impl ThinClient {
         Self {
             transactions_socket,
             tpu_addrs: vec![tpu_addr],
+            quic_clients: vec![],
             rpc_clients: vec![rpc_client],
             optimizer: ClientOptimizer::new(0),
         }
@@ -169,15 +175,43 @@ impl ThinClient {
         Self {
             tpu_addrs,
             transactions_socket,
+            quic_clients: vec![],
             rpc_clients,
             optimizer,
         }
     }
 
+    /// Send transactions to the QUIC TPU of each node instead of its UDP TPU, falling back to UDP
+    /// whenever a QUIC send fails
+    pub fn with_quic(mut self) -> io::Result<Self> {
+        self.quic_clients = self
+            .tpu_addrs
+            .iter()
+            .map(|tpu_addr| QuicClient::new(tpu_quic_addr(tpu_addr)))
+            .collect::<io::Result<_>>()?;
+        Ok(self)
+    }
+
     fn tpu_addr(&self) -> &SocketAddr {
         &self.tpu_addrs[self.optimizer.best()]
     }
 
+    fn send_wire_transaction(&self, wire_transaction: &[u8]) -> TransportResult<()> {
+        if let Some(quic_client) = self.quic_clients.get(self.optimizer.best()) {
+            match quic_client.send_wire_transaction(wire_transaction) {
+                Ok(()) => return Ok(()),
+                Err(err) => warn!(
+                    "QUIC send to {} failed, falling back to UDP: {}",
+                    quic_client.tpu_addr(),
+                    err
+                ),
+            }
+        }
+        self.transactions_socket
+            .send_to(wire_transaction, self.tpu_addr())?;
+        Ok(())
+    }
+
     fn rpc_client(&self) -> &RpcClient {
         &self.rpc_clients[self.optimizer.best()]
     }
@@ -223,8 +257,7 @@ impl ThinClient {
             while now.elapsed().as_secs() < wait_time as u64 {
                 if num_confirmed == 0 {
                     // Send the transaction if there has been no confirmation (e.g. the first time)
-                    self.transactions_socket
-                        .send_to(&buf[..], &self.tpu_addr())?;
+                    self.send_wire_transaction(&buf[..])?;
                 }
 
                 if let Ok(confirmed_blocks) = self.poll_for_signature_confirmation(
@@ -565,8 +598,7 @@ impl AsyncClient for ThinClient {
         serialize_into(&mut wr, &transaction)
             .expect("serialize Transaction in pub fn transfer_signed");
         assert!(buf.len() < PACKET_DATA_SIZE);
-        self.transactions_socket
-            .send_to(&buf[..], &self.tpu_addr())?;
+        self.send_wire_transaction(&buf[..])?;
         Ok(transaction.signatures[0])
     }
     fn async_send_message<T: Signers>(
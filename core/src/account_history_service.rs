@@ -0,0 +1,108 @@
+use crossbeam_channel::{Receiver, RecvTimeoutError};
+use solana_ledger::{blockstore::Blockstore, blockstore_processor::AccountHistoryBatch};
+use solana_measure::measure::Measure;
+use solana_sdk::clock::Slot;
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::{self, Builder, JoinHandle},
+    time::Duration,
+};
+
+pub type AccountHistoryReceiver = Receiver<AccountHistoryBatch>;
+
+pub struct AccountHistoryService {
+    thread_hdl: JoinHandle<()>,
+}
+
+const ACCOUNT_HISTORY_WARNING_MS: u64 = 150;
+
+impl AccountHistoryService {
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new(
+        account_history_receiver: AccountHistoryReceiver,
+        blockstore: Arc<Blockstore>,
+        exit: &Arc<AtomicBool>,
+    ) -> Self {
+        let exit = exit.clone();
+        let thread_hdl = Builder::new()
+            .name("solana-account-history".to_string())
+            .spawn(move || {
+                let mut last_recorded_slot = blockstore
+                    .get_account_history_slot_range()
+                    .unwrap_or_else(|err| {
+                        error!("get_account_history_slot_range failed: {:?}", err);
+                        None
+                    })
+                    .map(|(_, last_slot)| last_slot);
+                loop {
+                    if exit.load(Ordering::Relaxed) {
+                        break;
+                    }
+                    let recv_result = account_history_receiver.recv_timeout(Duration::from_secs(1));
+                    match recv_result {
+                        Err(RecvTimeoutError::Disconnected) => {
+                            break;
+                        }
+                        Ok(account_history_batch) => {
+                            Self::record_account_history(
+                                account_history_batch,
+                                &blockstore,
+                                &mut last_recorded_slot,
+                            );
+                        }
+                        _ => {}
+                    }
+                }
+            })
+            .unwrap();
+        Self { thread_hdl }
+    }
+
+    fn record_account_history(
+        account_history_batch: AccountHistoryBatch,
+        blockstore: &Blockstore,
+        last_recorded_slot: &mut Option<Slot>,
+    ) {
+        let AccountHistoryBatch {
+            rooted_banks,
+            held_root,
+        } = account_history_batch;
+        // The slots rooted between the last recorded slot and the held root, if any, were never
+        // recorded, for example because the validator restarted from a newer snapshot. The
+        // history recorded before them would answer for those slots with stale accounts
+        if let Some(slot) = last_recorded_slot.filter(|slot| *slot < held_root) {
+            warn!(
+                "account history has no record of the roots after slot {} up to slot {}, purging it up to slot {}",
+                slot, held_root, slot
+            );
+            blockstore.purge_account_history(slot);
+        }
+        for rooted_bank in &rooted_banks {
+            let slot = rooted_bank.slot();
+            let mut account_history_timer = Measure::start("account_history_timer");
+            let accounts = rooted_bank.get_accounts_delta_hash_inputs();
+            if let Err(e) = blockstore.write_account_history(slot, &accounts) {
+                error!("write_account_history failed: slot {:?} {:?}", slot, e);
+            }
+            account_history_timer.stop();
+            if account_history_timer.as_ms() > ACCOUNT_HISTORY_WARNING_MS {
+                warn!(
+                    "write_account_history of {} accounts took: {}ms",
+                    accounts.len(),
+                    account_history_timer.as_ms()
+                );
+            }
+            *last_recorded_slot = Some(last_recorded_slot.map_or(slot, |last| last.max(slot)));
+        }
+        if let Some(rooted_bank) = rooted_banks.first() {
+            rooted_bank.release_max_root(held_root);
+        }
+    }
+
+    pub fn join(self) -> thread::Result<()> {
+        self.thread_hdl.join()
+    }
+}
@@ -6,7 +6,7 @@
 
 use crate::cluster_info::{ClusterInfo, MAX_SNAPSHOT_HASHES};
 use solana_runtime::snapshot_package::{
-    AccountsPackage, AccountsPackageReceiver, AccountsPackageSender,
+    AccountsPackage, AccountsPackageReceiver, AccountsPackageSender, SnapshotType,
 };
 use solana_sdk::{clock::Slot, hash::Hash, pubkey::Pubkey};
 use std::collections::{HashMap, HashSet};
@@ -110,7 +110,13 @@ impl AccountsHashVerifier {
             }
         }
 
-        if accounts_package.block_height % snapshot_interval_slots == 0 {
+        let is_snapshot = match accounts_package.snapshot_type {
+            SnapshotType::FullSnapshot => {
+                accounts_package.block_height % snapshot_interval_slots == 0
+            }
+            SnapshotType::IncrementalSnapshot(_) => true,
+        };
+        if is_snapshot {
             if let Some(sender) = accounts_package_sender.as_ref() {
                 if sender.send(accounts_package).is_err() {}
             }
@@ -241,6 +247,7 @@ mod tests {
                 storages: vec![],
                 compression: CompressionType::Bzip2,
                 snapshot_version: SnapshotVersion::default(),
+                snapshot_type: SnapshotType::FullSnapshot,
             };
 
             AccountsHashVerifier::process_accounts_package(
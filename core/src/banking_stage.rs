@@ -20,6 +20,7 @@ use solana_perf::{
     cuda_runtime::PinnedVec,
     packet::{limited_deserialize, Packet, Packets, PACKETS_PER_BATCH},
     perf_libs,
+    sigverify::packet_compute_price,
 };
 use solana_runtime::{
     accounts_db::ErrorCounters,
@@ -33,13 +34,20 @@ use solana_sdk::{
         Slot, DEFAULT_TICKS_PER_SLOT, MAX_PROCESSING_AGE, MAX_TRANSACTION_FORWARDING_DELAY,
         MAX_TRANSACTION_FORWARDING_DELAY_GPU,
     },
+    compute_budget,
     poh_config::PohConfig,
     pubkey::Pubkey,
     timing::{duration_as_ms, timestamp},
     transaction::{self, Transaction, TransactionError},
 };
+use solana_transaction_status::token_balances::{
+    collect_token_balances, TransactionTokenBalancesSet,
+};
 use std::{
-    cmp, env,
+    borrow::Cow,
+    cmp::{self, Reverse},
+    collections::{HashMap, HashSet},
+    env,
     net::UdpSocket,
     sync::atomic::AtomicBool,
     sync::mpsc::Receiver,
@@ -155,12 +163,15 @@ impl BankingStage {
             .collect()
     }
 
+    // The next leader orders the forwarded packets on its own, forwarding the highest paying
+    // first only makes them the least likely to be lost on the way
     fn forward_buffered_packets(
         socket: &std::net::UdpSocket,
         tpu_forwards: &std::net::SocketAddr,
         unprocessed_packets: &[PacketsAndOffsets],
     ) -> std::io::Result<()> {
-        let packets = Self::filter_valid_packets_for_forwarding(unprocessed_packets);
+        let mut packets = Self::filter_valid_packets_for_forwarding(unprocessed_packets);
+        packets.sort_by_cached_key(|packet| Reverse(packet_compute_price(packet)));
         inc_new_counter_info!("banking_stage-forwarded_packets", packets.len());
         for p in packets {
             socket.send_to(&p.data[..p.meta.size], &tpu_forwards)?;
@@ -180,6 +191,14 @@ impl BankingStage {
         let mut unprocessed_packets = vec![];
         let mut rebuffered_packets = 0;
         let mut new_tx_count = 0;
+        let compute_budget_program_enabled = poh_recorder
+            .lock()
+            .unwrap()
+            .bank()
+            .map_or(false, |bank| bank.compute_budget_program_enabled());
+        if compute_budget_program_enabled {
+            Self::prioritize_buffered_packets(buffered_packets);
+        }
         let buffered_len = buffered_packets.len();
         let mut buffered_packets_iter = buffered_packets.drain(..);
         let mut dropped_batches_count = 0;
@@ -195,6 +214,7 @@ impl BankingStage {
                     unprocessed_indexes,
                     &mut dropped_batches_count,
                     batch_limit,
+                    compute_budget_program_enabled,
                 );
                 continue;
             }
@@ -220,6 +240,7 @@ impl BankingStage {
                 new_unprocessed_indexes,
                 &mut dropped_batches_count,
                 batch_limit,
+                compute_budget_program_enabled,
             );
 
             if processed < verified_txs_len {
@@ -240,6 +261,7 @@ impl BankingStage {
                         unprocessed_indexes,
                         &mut dropped_batches_count,
                         batch_limit,
+                        compute_budget_program_enabled,
                     );
                 }
             }
@@ -264,6 +286,24 @@ impl BankingStage {
         unprocessed_packets
     }
 
+    /// Orders the buffered packets by the price per compute unit their transactions pay, highest
+    /// first: the packets within each batch, and then the batches by their best paying packet.
+    /// Packets and batches of equal priority keep their arrival order. Only the indexes and the
+    /// order of the batches change, the packets themselves aren't copied.
+    fn prioritize_buffered_packets(buffered_packets: &mut UnprocessedPackets) {
+        let batch_priority = |(packets, packet_indexes): &PacketsAndOffsets| {
+            packet_indexes
+                .first()
+                .map_or(0, |index| packet_compute_price(&packets.packets[*index]))
+        };
+        for (packets, packet_indexes) in buffered_packets.iter_mut() {
+            packet_indexes.sort_by_cached_key(|index| {
+                Reverse(packet_compute_price(&packets.packets[*index]))
+            });
+        }
+        buffered_packets.sort_by_cached_key(|batch| Reverse(batch_priority(batch)));
+    }
+
     fn consume_or_forward_packets(
         my_pubkey: &Pubkey,
         leader_pubkey: Option<Pubkey>,
@@ -525,16 +565,23 @@ impl BankingStage {
         // TODO: Banking stage threads should be prioritized to complete faster then this queue
         // expires.
         let txs = batch.transactions();
+        let mut mint_decimals: HashMap<Pubkey, u8> = HashMap::new();
         let pre_balances = if transaction_status_sender.is_some() {
             bank.collect_balances(batch)
         } else {
             vec![]
         };
+        let pre_token_balances = if transaction_status_sender.is_some() {
+            collect_token_balances(bank, batch, &mut mint_decimals)
+        } else {
+            vec![]
+        };
         let (
             mut loaded_accounts,
             results,
             inner_instructions,
             transaction_logs,
+            _,
             mut retryable_txs,
             tx_count,
             signature_count,
@@ -574,12 +621,14 @@ impl BankingStage {
             bank_utils::find_and_send_votes(txs, &tx_results, Some(gossip_vote_sender));
             if let Some(sender) = transaction_status_sender {
                 let post_balances = bank.collect_balances(batch);
+                let post_token_balances = collect_token_balances(bank, batch, &mut mint_decimals);
                 send_transaction_status_batch(
                     bank.clone(),
                     batch.transactions(),
                     batch.iteration_order_vec(),
                     tx_results.processing_results,
                     TransactionBalancesSet::new(pre_balances, post_balances),
+                    TransactionTokenBalancesSet::new(pre_token_balances, post_token_balances),
                     inner_instructions,
                     transaction_logs,
                     sender,
@@ -641,6 +690,42 @@ impl BankingStage {
         (result, retryable_txs)
     }
 
+    /// Splits `transactions`, in order, into batches of at most `MAX_NUM_TRANSACTIONS_PER_BATCH`
+    /// transactions that can all lock their accounts together. A transaction that conflicts with
+    /// the batch being built is deferred to a later batch, instead of failing to lock its
+    /// accounts and being retried on the next pass.
+    fn lock_aware_batches(transactions: &[Transaction]) -> Vec<Vec<usize>> {
+        let mut batches = vec![];
+        let mut pending = (0..transactions.len()).collect_vec();
+        while !pending.is_empty() {
+            let mut batch = vec![];
+            let mut deferred = vec![];
+            let mut write_locks: HashSet<&Pubkey> = HashSet::new();
+            let mut read_locks: HashSet<&Pubkey> = HashSet::new();
+            for index in pending {
+                if batch.len() < MAX_NUM_TRANSACTIONS_PER_BATCH {
+                    let (writable_keys, readonly_keys) = transactions[index]
+                        .message()
+                        .get_account_keys_by_lock_type();
+                    let conflicts = writable_keys
+                        .iter()
+                        .any(|key| write_locks.contains(key) || read_locks.contains(key))
+                        || readonly_keys.iter().any(|key| write_locks.contains(key));
+                    if !conflicts {
+                        write_locks.extend(writable_keys);
+                        read_locks.extend(readonly_keys);
+                        batch.push(index);
+                        continue;
+                    }
+                }
+                deferred.push(index);
+            }
+            batches.push(batch);
+            pending = deferred;
+        }
+        batches
+    }
+
     /// Sends transactions to the bank.
     ///
     /// Returns the number of transactions successfully processed by the bank, which may be less
@@ -652,19 +737,23 @@ impl BankingStage {
         transaction_status_sender: Option<TransactionStatusSender>,
         gossip_vote_sender: &ReplayVoteSender,
     ) -> (usize, Vec<usize>) {
-        let mut chunk_start = 0;
+        let mut processed = 0;
         let mut unprocessed_txs = vec![];
-        while chunk_start != transactions.len() {
-            let chunk_end = std::cmp::min(
-                transactions.len(),
-                chunk_start + MAX_NUM_TRANSACTIONS_PER_BATCH,
-            );
+        let mut batches = Self::lock_aware_batches(transactions).into_iter();
+        while let Some(batch) = batches.next() {
+            // Batches are usually contiguous, only deferred transactions need to be copied
+            let batch_transactions: Cow<[Transaction]> =
+                if batch.last().unwrap() - batch[0] + 1 == batch.len() {
+                    Cow::Borrowed(&transactions[batch[0]..=*batch.last().unwrap()])
+                } else {
+                    Cow::Owned(batch.iter().map(|i| transactions[*i].clone()).collect())
+                };
 
-            let (result, retryable_txs_in_chunk) = Self::process_and_record_transactions(
+            let (result, retryable_txs_in_batch) = Self::process_and_record_transactions(
                 bank,
-                &transactions[chunk_start..chunk_end],
+                &batch_transactions,
                 poh,
-                chunk_start,
+                0,
                 transaction_status_sender.clone(),
                 gossip_vote_sender,
             );
@@ -672,7 +761,7 @@ impl BankingStage {
 
             // Add the retryable txs (transactions that errored in a way that warrants a retry)
             // to the list of unprocessed txs.
-            unprocessed_txs.extend_from_slice(&retryable_txs_in_chunk);
+            unprocessed_txs.extend(retryable_txs_in_batch.into_iter().map(|i| batch[i]));
             if let Err(PohRecorderError::MaxHeightReached) = result {
                 info!(
                     "process transactions: max height reached slot: {} height: {}",
@@ -680,16 +769,16 @@ impl BankingStage {
                     bank.tick_height()
                 );
                 // process_and_record_transactions has returned all retryable errors in
-                // transactions[chunk_start..chunk_end], so we just need to push the remaining
-                // transactions into the unprocessed queue.
-                unprocessed_txs.extend(chunk_end..transactions.len());
+                // the batch, so we just need to push the remaining batches into the
+                // unprocessed queue.
+                unprocessed_txs.extend(batches.flatten());
                 break;
             }
             // Don't exit early on any other type of error, continue processing...
-            chunk_start = chunk_end;
+            processed += batch.len();
         }
 
-        (chunk_start, unprocessed_txs)
+        (processed, unprocessed_txs)
     }
 
     // This function returns a vector of transactions that are not None. It also returns a vector
@@ -770,6 +859,33 @@ impl BankingStage {
         Self::filter_transaction_indexes(maybe_secp_verified_transactions, &transaction_indexes)
     }
 
+    // This function orders transactions by the price they pay per requested compute unit,
+    // highest first. Transactions that don't request a compute budget keep their relative order
+    // behind the ones that do
+    fn prioritize_transactions(
+        transactions: Vec<Transaction>,
+        transaction_to_packet_indexes: Vec<usize>,
+        compute_budget_program_enabled: bool,
+    ) -> (Vec<Transaction>, Vec<usize>) {
+        if !compute_budget_program_enabled {
+            return (transactions, transaction_to_packet_indexes);
+        }
+        let mut prioritized = transactions
+            .into_iter()
+            .zip(transaction_to_packet_indexes)
+            .collect_vec();
+        prioritized.sort_by_key(|(tx, _)| {
+            Reverse(
+                compute_budget::get_requested_units(&tx.message)
+                    .map(|(units, lamports_per_unit)| {
+                        compute_budget::requested_units_price(units, lamports_per_unit)
+                    })
+                    .unwrap_or_default(),
+            )
+        });
+        prioritized.into_iter().unzip()
+    }
+
     /// This function filters pending packets that are still valid
     /// # Arguments
     /// * `transactions` - a batch of transactions deserialized from packets
@@ -821,6 +937,11 @@ impl BankingStage {
             &packet_indexes,
             bank.secp256k1_program_enabled(),
         );
+        let (transactions, transaction_to_packet_indexes) = Self::prioritize_transactions(
+            transactions,
+            transaction_to_packet_indexes,
+            bank.compute_budget_program_enabled(),
+        );
         debug!(
             "bank: {} filtered transactions {}",
             bank.slot(),
@@ -951,6 +1072,7 @@ impl BankingStage {
                     packet_indexes,
                     &mut dropped_batches_count,
                     batch_limit,
+                    false,
                 );
                 continue;
             }
@@ -974,6 +1096,7 @@ impl BankingStage {
                 unprocessed_indexes,
                 &mut dropped_batches_count,
                 batch_limit,
+                false,
             );
 
             if processed < verified_txs_len {
@@ -995,6 +1118,7 @@ impl BankingStage {
                         unprocessed_indexes,
                         &mut dropped_batches_count,
                         batch_limit,
+                        false,
                     );
                 }
             }
@@ -1022,17 +1146,24 @@ impl BankingStage {
         Ok(unprocessed_packets)
     }
 
+    /// Buffers the unprocessed packets of a batch, dropping a batch once `batch_limit` batches
+    /// are buffered: the oldest one, or when the batches are pushed in priority order, the
+    /// incoming one since it has the lowest priority
     fn push_unprocessed(
         unprocessed_packets: &mut UnprocessedPackets,
         packets: Packets,
         packet_indexes: Vec<usize>,
         dropped_batches_count: &mut usize,
         batch_limit: usize,
+        prioritized: bool,
     ) {
         if !packet_indexes.is_empty() {
             if unprocessed_packets.len() >= batch_limit {
-                unprocessed_packets.remove(0);
                 *dropped_batches_count += 1;
+                if prioritized {
+                    return;
+                }
+                unprocessed_packets.remove(0);
             }
             unprocessed_packets.push((packets, packet_indexes));
         }
@@ -1095,9 +1226,12 @@ mod tests {
     use solana_perf::packet::to_packets;
     use solana_runtime::bank::HashAgeKind;
     use solana_sdk::{
+        hash::Hash,
         instruction::InstructionError,
+        message::Message,
+        packet::PACKET_DATA_SIZE,
         signature::{Keypair, Signer},
-        system_instruction::SystemError,
+        system_instruction::{self, SystemError},
         system_transaction,
         transaction::TransactionError,
     };
@@ -1521,6 +1655,235 @@ mod tests {
         Blockstore::destroy(&ledger_path).unwrap();
     }
 
+    #[test]
+    fn test_prioritize_transactions() {
+        let GenesisConfigInfo {
+            genesis_config,
+            mint_keypair,
+            ..
+        } = create_genesis_config(10_000);
+        let pubkey = solana_sdk::pubkey::new_rand();
+        let transfer_with_price = |lamports_per_unit| {
+            let message = Message::new(
+                &[
+                    compute_budget::request_units(1_000, lamports_per_unit),
+                    system_instruction::transfer(&mint_keypair.pubkey(), &pubkey, 1),
+                ],
+                Some(&mint_keypair.pubkey()),
+            );
+            Transaction::new(&[&mint_keypair], message, genesis_config.hash())
+        };
+
+        let transactions = vec![
+            system_transaction::transfer(&mint_keypair, &pubkey, 1, genesis_config.hash()),
+            transfer_with_price(1),
+            system_transaction::transfer(&mint_keypair, &pubkey, 2, genesis_config.hash()),
+            transfer_with_price(3),
+        ];
+        let transaction_to_packet_indexes = vec![2, 4, 5, 9];
+
+        assert_eq!(
+            BankingStage::prioritize_transactions(
+                transactions.clone(),
+                transaction_to_packet_indexes.clone(),
+                true,
+            ),
+            (
+                vec![
+                    transactions[3].clone(),
+                    transactions[1].clone(),
+                    transactions[0].clone(),
+                    transactions[2].clone(),
+                ],
+                vec![9, 4, 2, 5]
+            )
+        );
+
+        assert_eq!(
+            BankingStage::prioritize_transactions(
+                transactions.clone(),
+                transaction_to_packet_indexes.clone(),
+                false,
+            ),
+            (transactions, transaction_to_packet_indexes)
+        );
+    }
+
+    #[test]
+    fn test_prioritize_buffered_packets() {
+        let mint_keypair = Keypair::new();
+        let pubkey = solana_sdk::pubkey::new_rand();
+        let transfer_with_price = |lamports, lamports_per_unit| {
+            let mut instructions = vec![system_instruction::transfer(
+                &mint_keypair.pubkey(),
+                &pubkey,
+                lamports,
+            )];
+            if let Some(lamports_per_unit) = lamports_per_unit {
+                instructions.push(compute_budget::request_units(1_000, lamports_per_unit));
+            }
+            let message = Message::new(&instructions, Some(&mint_keypair.pubkey()));
+            Transaction::new(&[&mint_keypair], message, Hash::default())
+        };
+
+        let transactions = vec![
+            transfer_with_price(1, None),
+            transfer_with_price(2, Some(1)),
+            transfer_with_price(3, None),
+            transfer_with_price(4, Some(5)),
+            transfer_with_price(5, Some(2)),
+        ];
+        let mut packets = to_packets(&transactions);
+        let packets = packets.pop().unwrap();
+        let mut buffered_packets = vec![
+            (packets.clone(), vec![0, 1, 2]),
+            (packets.clone(), vec![3]),
+            (packets, vec![4]),
+        ];
+
+        BankingStage::prioritize_buffered_packets(&mut buffered_packets);
+        assert_eq!(
+            buffered_packets
+                .iter()
+                .map(|(_, packet_indexes)| packet_indexes.clone())
+                .collect::<Vec<_>>(),
+            vec![vec![3], vec![4], vec![1, 0, 2]]
+        );
+        // The packets were reordered in place rather than copied into new batches
+        assert!(buffered_packets
+            .iter()
+            .all(|(packets, _)| packets.packets.len() == transactions.len()));
+    }
+
+    #[test]
+    fn test_push_unprocessed_drops_lowest_priority() {
+        let mint_keypair = Keypair::new();
+        let batches: Vec<_> = [1, 4, 2, 3]
+            .iter()
+            .map(|lamports_per_unit| {
+                let message = Message::new(
+                    &[
+                        system_instruction::transfer(
+                            &mint_keypair.pubkey(),
+                            &solana_sdk::pubkey::new_rand(),
+                            1,
+                        ),
+                        compute_budget::request_units(1_000, *lamports_per_unit),
+                    ],
+                    Some(&mint_keypair.pubkey()),
+                );
+                let transaction = Transaction::new(&[&mint_keypair], message, Hash::default());
+                (to_packets(&[transaction]).pop().unwrap(), vec![0])
+            })
+            .collect();
+        let survivors = |unprocessed_packets: &UnprocessedPackets| {
+            unprocessed_packets
+                .iter()
+                .map(|(packets, _)| packet_compute_price(&packets.packets[0]))
+                .collect::<Vec<_>>()
+        };
+
+        // Fill a buffer of two batches the way consume_buffered_packets() does
+        let fill = |mut buffered_packets: UnprocessedPackets, prioritized| {
+            if prioritized {
+                BankingStage::prioritize_buffered_packets(&mut buffered_packets);
+            }
+            let mut unprocessed_packets = vec![];
+            let mut dropped_batches_count = 0;
+            for (packets, packet_indexes) in buffered_packets {
+                BankingStage::push_unprocessed(
+                    &mut unprocessed_packets,
+                    packets,
+                    packet_indexes,
+                    &mut dropped_batches_count,
+                    2,
+                    prioritized,
+                );
+            }
+            assert_eq!(dropped_batches_count, 2);
+            unprocessed_packets
+        };
+
+        // The best paying batches survive
+        assert_eq!(survivors(&fill(batches.clone(), true)), vec![4, 3]);
+        // Without prioritization, the most recent batches do
+        assert_eq!(survivors(&fill(batches, false)), vec![2, 3]);
+    }
+
+    #[test]
+    fn test_forward_buffered_packets_in_priority_order() {
+        let mint_keypair = Keypair::new();
+        let transactions: Vec<_> = (0..3)
+            .map(|lamports_per_unit| {
+                let message = Message::new(
+                    &[
+                        system_instruction::transfer(
+                            &mint_keypair.pubkey(),
+                            &solana_sdk::pubkey::new_rand(),
+                            1,
+                        ),
+                        compute_budget::request_units(1_000, lamports_per_unit),
+                    ],
+                    Some(&mint_keypair.pubkey()),
+                );
+                Transaction::new(&[&mint_keypair], message, Hash::default())
+            })
+            .collect();
+        let packets = to_packets(&transactions).pop().unwrap();
+
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let tpu_forwards = UdpSocket::bind("127.0.0.1:0").unwrap();
+        tpu_forwards
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        BankingStage::forward_buffered_packets(
+            &socket,
+            &tpu_forwards.local_addr().unwrap(),
+            &[(packets, vec![0, 1, 2])],
+        )
+        .unwrap();
+
+        let mut buf = [0u8; PACKET_DATA_SIZE];
+        for expected in transactions.iter().rev() {
+            let len = tpu_forwards.recv(&mut buf).unwrap();
+            let forwarded: Transaction = limited_deserialize(&buf[..len]).unwrap();
+            assert_eq!(&forwarded, expected);
+        }
+    }
+
+    #[test]
+    fn test_lock_aware_batches() {
+        let payer = Keypair::new();
+        let other_payer = Keypair::new();
+        let pubkey = solana_sdk::pubkey::new_rand();
+        let transfer = |from: &Keypair, to: &Pubkey| {
+            system_transaction::transfer(from, to, 1, Hash::default())
+        };
+
+        let transactions = vec![
+            transfer(&payer, &pubkey),
+            // Conflicts on both the payer and the recipient
+            transfer(&payer, &pubkey),
+            transfer(&other_payer, &solana_sdk::pubkey::new_rand()),
+            // Conflicts on the recipient only
+            transfer(&other_payer, &pubkey),
+        ];
+        assert_eq!(
+            BankingStage::lock_aware_batches(&transactions),
+            vec![vec![0, 2], vec![1], vec![3]]
+        );
+
+        // Batches are bounded even when nothing conflicts
+        let transactions: Vec<_> = (0..MAX_NUM_TRANSACTIONS_PER_BATCH + 1)
+            .map(|_| transfer(&Keypair::new(), &solana_sdk::pubkey::new_rand()))
+            .collect();
+        let batches = BankingStage::lock_aware_batches(&transactions);
+        assert_eq!(
+            batches.iter().map(|batch| batch.len()).collect::<Vec<_>>(),
+            vec![MAX_NUM_TRANSACTIONS_PER_BATCH, 1]
+        );
+    }
+
     #[test]
     fn test_bank_filter_transaction_indexes() {
         let GenesisConfigInfo {
@@ -2031,6 +2394,8 @@ mod tests {
             let transaction_status_service = TransactionStatusService::new(
                 transaction_status_receiver,
                 blockstore.clone(),
+                true,
+                None,
                 &Arc::new(AtomicBool::new(false)),
             );
 
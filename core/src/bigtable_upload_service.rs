@@ -1,4 +1,4 @@
-use solana_ledger::blockstore::Blockstore;
+use solana_ledger::{bigtable_upload::ConfirmedBlockUploadConfig, blockstore::Blockstore};
 use solana_runtime::commitment::BlockCommitmentCache;
 use std::{
     sync::atomic::{AtomicBool, Ordering},
@@ -73,7 +73,10 @@ impl BigTableUploadService {
                 bigtable_ledger_storage.clone(),
                 start_slot,
                 Some(end_slot),
-                true,
+                ConfirmedBlockUploadConfig {
+                    allow_missing_metadata: true,
+                    ..ConfirmedBlockUploadConfig::default()
+                },
                 exit.clone(),
             ));
 
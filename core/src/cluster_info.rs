@@ -18,8 +18,8 @@ use crate::{
     crds_gossip_error::CrdsGossipError,
     crds_gossip_pull::{CrdsFilter, ProcessPullStats, CRDS_GOSSIP_PULL_CRDS_TIMEOUT_MS},
     crds_value::{
-        self, CrdsData, CrdsValue, CrdsValueLabel, EpochSlotsIndex, LowestSlot, SnapshotHash,
-        Version, Vote, MAX_WALLCLOCK,
+        self, CrdsData, CrdsValue, CrdsValueLabel, EpochSlotsIndex, IncrementalSnapshotHashes,
+        LowestSlot, SnapshotHash, Version, Vote, MAX_WALLCLOCK,
     },
     data_budget::DataBudget,
     epoch_slots::EpochSlots,
@@ -45,7 +45,7 @@ use solana_measure::thread_mem_usage;
 use solana_metrics::{inc_new_counter_debug, inc_new_counter_error};
 use solana_net_utils::{
     bind_common, bind_common_in_range, bind_in_range, find_available_port_in_range,
-    multi_bind_in_range, PortRange,
+    multi_bind_in_range, multi_bind_in_range_with_offset, PortRange,
 };
 use solana_perf::packet::{
     limited_deserialize, to_packets_with_destination, Packet, Packets, PacketsRecycler,
@@ -58,6 +58,7 @@ use solana_sdk::{
     feature_set::{self, FeatureSet},
     hash::Hash,
     pubkey::Pubkey,
+    quic::QUIC_PORT_OFFSET,
     signature::{Keypair, Signable, Signature, Signer},
     timing::timestamp,
     transaction::Transaction,
@@ -69,6 +70,7 @@ use std::{
     cmp::min,
     collections::{hash_map::Entry, HashMap, HashSet, VecDeque},
     fmt::{self, Debug},
+    io,
     net::{IpAddr, Ipv4Addr, SocketAddr, TcpListener, UdpSocket},
     ops::{Deref, DerefMut},
     sync::atomic::{AtomicBool, AtomicU64, Ordering},
@@ -100,6 +102,9 @@ const PUSH_MESSAGE_MAX_PAYLOAD_SIZE: usize = PACKET_DATA_SIZE - 44;
 /// PACKET_DATA_SIZE.
 // TODO: Update this to 26 once payload sizes are upgraded across fleet.
 pub const MAX_SNAPSHOT_HASHES: usize = 16;
+/// Maximum number of hashes in IncrementalSnapshotHashes a node publishes,
+/// leaving room for the hash of the full snapshot they are based on.
+pub const MAX_INCREMENTAL_SNAPSHOT_HASHES: usize = MAX_SNAPSHOT_HASHES - 1;
 /// Maximum number of origin nodes that a PruneData may contain, such that the
 /// serialized size of the PruneMessage stays below PACKET_DATA_SIZE.
 const MAX_PRUNE_DATA_NODES: usize = 32;
@@ -424,7 +429,7 @@ pub fn make_accounts_hashes_message(
 type Ping = ping_pong::Ping<[u8; GOSSIP_PING_TOKEN_SIZE]>;
 
 // TODO These messages should go through the gpu pipeline for spam filtering
-#[frozen_abi(digest = "3jHXixLRv6fuCykW47hBZSwFuwDjbZShR73GVQB6TjGr")]
+#[frozen_abi(digest = "2ag5hxqMzJqvzvdrndpBahBfaCcWeYXrBWQzjZSF7TTv")]
 #[derive(Serialize, Deserialize, Debug, AbiEnumVisitor, AbiExample)]
 #[allow(clippy::large_enum_variant)]
 enum Protocol {
@@ -969,6 +974,27 @@ impl ClusterInfo {
         self.push_message(CrdsValue::new_signed(message, &self.keypair));
     }
 
+    pub fn push_incremental_snapshot_hashes(
+        &self,
+        base: (Slot, Hash),
+        incremental_snapshot_hashes: Vec<(Slot, Hash)>,
+    ) {
+        if incremental_snapshot_hashes.len() > MAX_INCREMENTAL_SNAPSHOT_HASHES {
+            warn!(
+                "incremental snapshot hashes too large, ignored: {}",
+                incremental_snapshot_hashes.len(),
+            );
+            return;
+        }
+
+        let message = CrdsData::IncrementalSnapshotHashes(IncrementalSnapshotHashes::new(
+            self.id(),
+            base,
+            incremental_snapshot_hashes,
+        ));
+        self.push_message(CrdsValue::new_signed(message, &self.keypair));
+    }
+
     pub fn push_vote(&self, tower_index: usize, vote: Transaction) {
         let now = timestamp();
         let vote = Vote::new(&self.id(), vote, now);
@@ -1057,6 +1083,23 @@ impl ClusterInfo {
             .map(map)
     }
 
+    pub fn get_incremental_snapshot_hashes_for_node<F, Y>(
+        &self,
+        pubkey: &Pubkey,
+        map: F,
+    ) -> Option<Y>
+    where
+        F: FnOnce(&IncrementalSnapshotHashes) -> Y,
+    {
+        self.gossip
+            .read()
+            .unwrap()
+            .crds
+            .get(&CrdsValueLabel::IncrementalSnapshotHashes(*pubkey))
+            .map(|x| x.value.incremental_snapshot_hashes().unwrap())
+            .map(map)
+    }
+
     pub fn get_lowest_slot_for_node<F, Y>(
         &self,
         pubkey: &Pubkey,
@@ -2903,7 +2946,8 @@ impl ClusterInfo {
     ) -> (ContactInfo, UdpSocket, Option<TcpListener>) {
         let bind_ip_addr = IpAddr::V4(Ipv4Addr::new(0, 0, 0, 0));
         let (port, (gossip_socket, ip_echo)) =
-            Node::get_gossip_port(gossip_addr, VALIDATOR_PORT_RANGE, bind_ip_addr);
+            Node::get_gossip_port(gossip_addr, VALIDATOR_PORT_RANGE, bind_ip_addr)
+                .unwrap_or_else(|err| panic!("{}", err));
         let contact_info =
             Self::gossip_contact_info(id, SocketAddr::new(gossip_addr.ip(), port), shred_version);
 
@@ -2968,6 +3012,7 @@ pub struct Sockets {
     pub tvu_forwards: Vec<UdpSocket>,
     pub tpu: Vec<UdpSocket>,
     pub tpu_forwards: Vec<UdpSocket>,
+    pub tpu_quic: Option<UdpSocket>,
     pub broadcast: Vec<UdpSocket>,
     pub repair: UdpSocket,
     pub retransmit_sockets: Vec<UdpSocket>,
@@ -3031,6 +3076,7 @@ impl Node {
                 tvu_forwards: vec![tvu_forwards],
                 tpu: vec![tpu],
                 tpu_forwards: vec![tpu_forwards],
+                tpu_quic: None,
                 broadcast,
                 repair,
                 retransmit_sockets: vec![retransmit_socket],
@@ -3042,51 +3088,54 @@ impl Node {
         gossip_addr: &SocketAddr,
         port_range: PortRange,
         bind_ip_addr: IpAddr,
-    ) -> (u16, (UdpSocket, TcpListener)) {
+    ) -> io::Result<(u16, (UdpSocket, TcpListener))> {
         if gossip_addr.port() != 0 {
-            (
-                gossip_addr.port(),
-                bind_common(bind_ip_addr, gossip_addr.port(), false).unwrap_or_else(|e| {
-                    panic!("gossip_addr bind_to port {}: {}", gossip_addr.port(), e)
-                }),
-            )
+            let sockets = bind_common(bind_ip_addr, gossip_addr.port(), false).map_err(|e| {
+                io::Error::new(
+                    e.kind(),
+                    format!("gossip_addr bind_to port {}: {}", gossip_addr.port(), e),
+                )
+            })?;
+            Ok((gossip_addr.port(), sockets))
         } else {
-            bind_common_in_range(bind_ip_addr, port_range).expect("Failed to bind")
+            bind_common_in_range(bind_ip_addr, port_range)
         }
     }
-    fn bind(bind_ip_addr: IpAddr, port_range: PortRange) -> (u16, UdpSocket) {
-        bind_in_range(bind_ip_addr, port_range).expect("Failed to bind")
-    }
-
+    /// Binds the sockets of a node in `port_range`. With `tpu_use_quic` the QUIC tpu socket is
+    /// bound too, at `QUIC_PORT_OFFSET` from the tpu port so that peers can derive it
     pub fn new_with_external_ip(
         pubkey: &Pubkey,
         gossip_addr: &SocketAddr,
         port_range: PortRange,
         bind_ip_addr: IpAddr,
-    ) -> Node {
+        tpu_use_quic: bool,
+    ) -> io::Result<Node> {
         let (gossip_port, (gossip, ip_echo)) =
-            Self::get_gossip_port(gossip_addr, port_range, bind_ip_addr);
+            Self::get_gossip_port(gossip_addr, port_range, bind_ip_addr)?;
 
-        let (tvu_port, tvu_sockets) =
-            multi_bind_in_range(bind_ip_addr, port_range, 8).expect("tvu multi_bind");
+        let (tvu_port, tvu_sockets) = multi_bind_in_range(bind_ip_addr, port_range, 8)?;
 
         let (tvu_forwards_port, tvu_forwards_sockets) =
-            multi_bind_in_range(bind_ip_addr, port_range, 8).expect("tvu_forwards multi_bind");
+            multi_bind_in_range(bind_ip_addr, port_range, 8)?;
 
-        let (tpu_port, tpu_sockets) =
-            multi_bind_in_range(bind_ip_addr, port_range, 32).expect("tpu multi_bind");
+        let (tpu_port, tpu_sockets, tpu_quic) = if tpu_use_quic {
+            let (tpu_port, tpu_sockets, tpu_quic) =
+                multi_bind_in_range_with_offset(bind_ip_addr, port_range, 32, QUIC_PORT_OFFSET)?;
+            (tpu_port, tpu_sockets, Some(tpu_quic))
+        } else {
+            let (tpu_port, tpu_sockets) = multi_bind_in_range(bind_ip_addr, port_range, 32)?;
+            (tpu_port, tpu_sockets, None)
+        };
 
         let (tpu_forwards_port, tpu_forwards_sockets) =
-            multi_bind_in_range(bind_ip_addr, port_range, 8).expect("tpu_forwards multi_bind");
+            multi_bind_in_range(bind_ip_addr, port_range, 8)?;
 
-        let (_, retransmit_sockets) =
-            multi_bind_in_range(bind_ip_addr, port_range, 8).expect("retransmit multi_bind");
+        let (_, retransmit_sockets) = multi_bind_in_range(bind_ip_addr, port_range, 8)?;
 
-        let (repair_port, repair) = Self::bind(bind_ip_addr, port_range);
-        let (serve_repair_port, serve_repair) = Self::bind(bind_ip_addr, port_range);
+        let (repair_port, repair) = bind_in_range(bind_ip_addr, port_range)?;
+        let (serve_repair_port, serve_repair) = bind_in_range(bind_ip_addr, port_range)?;
 
-        let (_, broadcast) =
-            multi_bind_in_range(bind_ip_addr, port_range, 4).expect("broadcast multi_bind");
+        let (_, broadcast) = multi_bind_in_range(bind_ip_addr, port_range, 4)?;
 
         let info = ContactInfo {
             id: *pubkey,
@@ -3105,7 +3154,7 @@ impl Node {
         };
         trace!("new ContactInfo: {:?}", info);
 
-        Node {
+        Ok(Node {
             info,
             sockets: Sockets {
                 gossip,
@@ -3113,13 +3162,14 @@ impl Node {
                 tvu_forwards: tvu_forwards_sockets,
                 tpu: tpu_sockets,
                 tpu_forwards: tpu_forwards_sockets,
+                tpu_quic,
                 broadcast,
                 repair,
                 retransmit_sockets,
                 serve_repair,
                 ip_echo: Some(ip_echo),
             },
-        }
+        })
     }
 }
 
@@ -3137,6 +3187,7 @@ mod tests {
     use crate::crds_value::{CrdsValue, CrdsValueLabel, Vote as CrdsVote};
     use itertools::izip;
     use solana_perf::test_tx::test_tx;
+    use solana_sdk::quic::tpu_quic_addr;
     use solana_sdk::signature::{Keypair, Signer};
     use solana_vote_program::{vote_instruction, vote_state::Vote};
     use std::collections::HashSet;
@@ -3530,6 +3581,12 @@ mod tests {
 
         check_sockets(&node.sockets.tvu, ip, range);
         check_sockets(&node.sockets.tpu, ip, range);
+
+        if let Some(tpu_quic) = &node.sockets.tpu_quic {
+            check_socket(tpu_quic, ip, range);
+            let tpu_addr = node.sockets.tpu[0].local_addr().unwrap();
+            assert_eq!(tpu_quic.local_addr().unwrap(), tpu_quic_addr(&tpu_addr));
+        }
     }
 
     #[test]
@@ -3540,9 +3597,12 @@ mod tests {
             &socketaddr!(ip, 0),
             VALIDATOR_PORT_RANGE,
             IpAddr::V4(ip),
-        );
+            false,
+        )
+        .unwrap();
 
         check_node_sockets(&node, IpAddr::V4(ip), VALIDATOR_PORT_RANGE);
+        assert!(node.sockets.tpu_quic.is_none());
     }
 
     #[test]
@@ -3558,9 +3618,12 @@ mod tests {
             &socketaddr!(0, port),
             port_range,
             ip,
-        );
+            true,
+        )
+        .unwrap();
 
         check_node_sockets(&node, ip, port_range);
+        assert!(node.sockets.tpu_quic.is_some());
 
         assert_eq!(node.sockets.gossip.local_addr().unwrap().port(), port);
     }
@@ -23,7 +23,7 @@ use solana_vote_program::{
 };
 use std::{
     cmp::Ordering,
-    collections::{HashMap, HashSet},
+    collections::{HashMap, HashSet, VecDeque},
     fs::{self, File},
     io::BufReader,
     ops::Bound::{Included, Unbounded},
@@ -418,6 +418,49 @@ impl Tower {
         self.stray_restored_slot
     }
 
+    pub fn node_pubkey(&self) -> &Pubkey {
+        &self.node_pubkey
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Sets the file `save()` writes this tower to
+    pub fn set_path(&mut self, path: PathBuf) {
+        self.tmp_path = Self::get_tmp_filename(&path);
+        self.path = path;
+    }
+
+    pub fn last_vote(&self) -> &Vote {
+        &self.last_vote
+    }
+
+    pub fn lockouts(&self) -> &VecDeque<Lockout> {
+        &self.lockouts.votes
+    }
+
+    pub fn threshold_depth(&self) -> usize {
+        self.threshold_depth
+    }
+
+    pub fn threshold_size(&self) -> f64 {
+        self.threshold_size
+    }
+
+    // The vote `threshold_depth` deep in the tower, which check_vote_stake_threshold()
+    // requires to be confirmed by `threshold_size` of the stake
+    pub fn threshold_vote(&self) -> Option<&Lockout> {
+        self.lockouts.nth_recent_vote(self.threshold_depth)
+    }
+
+    // Forcibly override the root for operator-driven recovery. Lockouts at or below the
+    // new root are dropped since they are implied by it.
+    pub fn set_root(&mut self, root: Slot) {
+        self.initialize_lockouts(|lockout| lockout.slot > root);
+        self.initialize_root(root);
+    }
+
     pub fn last_vote_and_timestamp(&mut self) -> Vote {
         let mut last_vote = self.last_vote.clone();
         last_vote.timestamp = self.maybe_timestamp(last_vote.last_voted_slot().unwrap_or(0));
@@ -1106,6 +1149,17 @@ impl Tower {
         path.with_extension("bin.new")
     }
 
+    pub fn get_backup_filename(path: &Path) -> PathBuf {
+        path.with_extension("bin.bak")
+    }
+
+    fn sync_parent_dir(filename: &Path) -> Result<()> {
+        if let Some(parent) = filename.parent().filter(|p| !p.as_os_str().is_empty()) {
+            File::open(parent)?.sync_all()?;
+        }
+        Ok(())
+    }
+
     pub fn save(&self, node_keypair: &Arc<Keypair>) -> Result<()> {
         let mut measure = Measure::start("tower_save-ms");
 
@@ -1124,11 +1178,17 @@ impl Tower {
             let mut file = File::create(&new_filename)?;
             let saved_tower = SavedTower::new(self, node_keypair)?;
             bincode::serialize_into(&mut file, &saved_tower)?;
-            // file.sync_all() hurts performance; pipeline sync-ing and submitting votes to the cluster!
+            // the tower must be durable before any vote reflecting it is sent to the cluster
+            file.sync_all()?;
         }
         trace!("persisted votes: {:?}", self.voted_slots());
+        // Rotate the previously saved tower into the backup; a crash between the two
+        // renames leaves the new tower at the tmp path, which restore() recovers
+        if filename.exists() {
+            fs::rename(filename, Self::get_backup_filename(filename))?;
+        }
         fs::rename(&new_filename, &filename)?;
-        // self.path.parent().sync_all() hurts performance same as the above sync
+        Self::sync_parent_dir(filename)?;
 
         measure.stop();
         inc_new_counter_info!("tower_save-ms", measure.as_ms() as usize);
@@ -1142,6 +1202,39 @@ impl Tower {
         // Ensure to create parent dir here, because restore() precedes save() always
         fs::create_dir_all(&filename.parent().unwrap())?;
 
+        // The saved tower is authoritative: no vote reflecting a tower left at the tmp path has
+        // been sent while the saved one is still intact. Only when it is missing or unreadable,
+        // after a crash between the renames in save(), is the tmp tower recovered. A torn write
+        // there fails verification. Never fall back to the backup here: it may be older than votes
+        // already sent to the cluster. An operator can restore it with `ledger-tool tower --backup`.
+        let err = match Self::restore_from_file(&filename, node_pubkey) {
+            Ok(tower) => return Ok(tower),
+            Err(err) => err,
+        };
+        let tmp_filename = Self::get_tmp_filename(&filename);
+        if !tmp_filename.exists() {
+            return Err(err);
+        }
+        match Self::restore_from_file(&tmp_filename, node_pubkey) {
+            Ok(mut tower) => {
+                info!("Recovering tower from {:?}: {}", tmp_filename, err);
+                // promote it so the next save() doesn't overwrite the only good copy
+                fs::rename(&tmp_filename, &filename)?;
+                Self::sync_parent_dir(&filename)?;
+                tower.set_path(filename);
+                Ok(tower)
+            }
+            Err(tmp_err) => {
+                warn!(
+                    "Ignoring incomplete tower at {:?}: {}",
+                    tmp_filename, tmp_err
+                );
+                Err(err)
+            }
+        }
+    }
+
+    pub fn restore_from_file(filename: &Path, node_pubkey: &Pubkey) -> Result<Self> {
         let file = File::open(&filename)?;
         let mut stream = BufReader::new(file);
 
@@ -1150,8 +1243,7 @@ impl Tower {
             return Err(TowerError::InvalidSignature);
         }
         let mut tower = saved_tower.deserialize()?;
-        tower.path = filename;
-        tower.tmp_path = Self::get_tmp_filename(&tower.path);
+        tower.set_path(filename.to_path_buf());
 
         // check that the tower actually belongs to this node
         if &tower.node_pubkey != node_pubkey {
@@ -2732,6 +2824,105 @@ pub mod test {
         assert_matches!(loaded, Err(TowerError::IOError(_)))
     }
 
+    #[test]
+    fn test_load_tower_from_backup() {
+        let dir = TempDir::new().unwrap();
+        let identity_keypair = Arc::new(Keypair::new());
+        let mut tower = Tower::new_with_key(&identity_keypair.pubkey());
+        tower.path = Tower::get_filename(dir.path(), &identity_keypair.pubkey());
+        tower.tmp_path = Tower::get_tmp_filename(&tower.path);
+        let backup_path = Tower::get_backup_filename(&tower.path);
+
+        tower.record_vote(1, Hash::default());
+        tower.save(&identity_keypair).unwrap();
+        assert!(!backup_path.exists());
+        let previous = tower.clone();
+
+        tower.record_vote(2, Hash::default());
+        tower.save(&identity_keypair).unwrap();
+        assert_eq!(
+            Tower::restore_from_file(&backup_path, &identity_keypair.pubkey()).unwrap(),
+            Tower {
+                path: backup_path.clone(),
+                tmp_path: Tower::get_tmp_filename(&backup_path),
+                ..previous.clone()
+            }
+        );
+
+        // a corrupted tower is an error rather than silently replaced by the backup
+        OpenOptions::new()
+            .write(true)
+            .truncate(true)
+            .open(&tower.path)
+            .unwrap();
+        assert_matches!(
+            Tower::restore(dir.path(), &identity_keypair.pubkey()),
+            Err(TowerError::SerializeError(_))
+        );
+
+        // as is a missing one
+        remove_file(&tower.path).unwrap();
+        assert!(Tower::restore(dir.path(), &identity_keypair.pubkey())
+            .unwrap_err()
+            .is_file_missing());
+        assert!(backup_path.exists());
+    }
+
+    #[test]
+    fn test_load_tower_from_tmp() {
+        let dir = TempDir::new().unwrap();
+        let identity_keypair = Arc::new(Keypair::new());
+        let mut tower = Tower::new_with_key(&identity_keypair.pubkey());
+        tower.path = Tower::get_filename(dir.path(), &identity_keypair.pubkey());
+        tower.tmp_path = Tower::get_tmp_filename(&tower.path);
+
+        tower.record_vote(1, Hash::default());
+        tower.save(&identity_keypair).unwrap();
+
+        // simulate a crash between the renames of the previous tower to the backup and the new
+        // one to the saved path
+        tower.record_vote(2, Hash::default());
+        tower.save(&identity_keypair).unwrap();
+        fs::rename(&tower.path, &tower.tmp_path).unwrap();
+        let loaded = Tower::restore(dir.path(), &identity_keypair.pubkey()).unwrap();
+        assert_eq!(loaded, tower);
+        // and it is promoted to the saved path
+        assert!(tower.path.exists());
+        assert!(!tower.tmp_path.exists());
+
+        // a tmp tower never wins over an intact saved one
+        fs::copy(Tower::get_backup_filename(&tower.path), &tower.tmp_path).unwrap();
+        let loaded = Tower::restore(dir.path(), &identity_keypair.pubkey()).unwrap();
+        assert_eq!(loaded, tower);
+
+        // a torn tmp tower is ignored
+        remove_file(&tower.path).unwrap();
+        OpenOptions::new()
+            .write(true)
+            .truncate(true)
+            .open(&tower.tmp_path)
+            .unwrap();
+        assert!(Tower::restore(dir.path(), &identity_keypair.pubkey())
+            .unwrap_err()
+            .is_file_missing());
+        assert!(tower.tmp_path.exists());
+    }
+
+    #[test]
+    fn test_tower_set_root() {
+        let mut tower = Tower::new_for_tests(2, 0.67);
+        for slot in 1..=4 {
+            tower.record_vote(slot, Hash::default());
+        }
+        assert_eq!(tower.threshold_vote().unwrap().slot, 2);
+
+        tower.set_root(2);
+        assert_eq!(tower.root(), 2);
+        assert_eq!(tower.voted_slots(), vec![3, 4]);
+        assert_eq!(tower.last_voted_slot(), Some(4));
+        assert!(tower.threshold_vote().is_none());
+    }
+
     #[test]
     fn test_reconcile_blockstore_roots_with_tower_normal() {
         solana_logger::setup();
@@ -1,5 +1,6 @@
 use crate::crds_value::MAX_WALLCLOCK;
 use solana_sdk::pubkey::Pubkey;
+use solana_sdk::quic::tpu_quic_addr;
 #[cfg(test)]
 use solana_sdk::rpc_port;
 use solana_sdk::sanitize::{Sanitize, SanitizeError};
@@ -218,6 +219,11 @@ impl ContactInfo {
         (addr.port() != 0) && Self::is_valid_ip(addr.ip())
     }
 
+    /// The QUIC TPU listens on a fixed offset from the UDP TPU port
+    pub fn tpu_quic(&self) -> SocketAddr {
+        tpu_quic_addr(&self.tpu)
+    }
+
     pub fn client_facing_addr(&self) -> (SocketAddr, SocketAddr) {
         (self.rpc, self.tpu)
     }
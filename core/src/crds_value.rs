@@ -79,6 +79,7 @@ pub enum CrdsData {
     EpochSlots(EpochSlotsIndex, EpochSlots),
     LegacyVersion(LegacyVersion),
     Version(Version),
+    IncrementalSnapshotHashes(IncrementalSnapshotHashes),
 }
 
 impl Sanitize for CrdsData {
@@ -107,6 +108,7 @@ impl Sanitize for CrdsData {
             }
             CrdsData::LegacyVersion(version) => version.sanitize(),
             CrdsData::Version(version) => version.sanitize(),
+            CrdsData::IncrementalSnapshotHashes(val) => val.sanitize(),
         }
     }
 }
@@ -181,6 +183,45 @@ impl SnapshotHash {
         }
     }
 }
+
+/// Hashes of the incremental snapshots a node has available, all based on the full snapshot
+/// identified by `base`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, AbiExample)]
+pub struct IncrementalSnapshotHashes {
+    pub from: Pubkey,
+    pub base: (Slot, Hash),
+    pub hashes: Vec<(Slot, Hash)>,
+    pub wallclock: u64,
+}
+
+impl Sanitize for IncrementalSnapshotHashes {
+    fn sanitize(&self) -> Result<(), SanitizeError> {
+        if self.wallclock >= MAX_WALLCLOCK {
+            return Err(SanitizeError::ValueOutOfBounds);
+        }
+        if self.base.0 >= MAX_SLOT {
+            return Err(SanitizeError::ValueOutOfBounds);
+        }
+        for (slot, _) in &self.hashes {
+            if *slot >= MAX_SLOT || *slot <= self.base.0 {
+                return Err(SanitizeError::ValueOutOfBounds);
+            }
+        }
+        self.from.sanitize()
+    }
+}
+
+impl IncrementalSnapshotHashes {
+    pub fn new(from: Pubkey, base: (Slot, Hash), hashes: Vec<(Slot, Hash)>) -> Self {
+        Self {
+            from,
+            base,
+            hashes,
+            wallclock: timestamp(),
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, AbiExample)]
 pub struct LowestSlot {
     pub from: Pubkey,
@@ -335,6 +376,7 @@ pub enum CrdsValueLabel {
     AccountsHashes(Pubkey),
     LegacyVersion(Pubkey),
     Version(Pubkey),
+    IncrementalSnapshotHashes(Pubkey),
 }
 
 impl fmt::Display for CrdsValueLabel {
@@ -348,6 +390,9 @@ impl fmt::Display for CrdsValueLabel {
             CrdsValueLabel::AccountsHashes(_) => write!(f, "AccountsHashes({})", self.pubkey()),
             CrdsValueLabel::LegacyVersion(_) => write!(f, "LegacyVersion({})", self.pubkey()),
             CrdsValueLabel::Version(_) => write!(f, "Version({})", self.pubkey()),
+            CrdsValueLabel::IncrementalSnapshotHashes(_) => {
+                write!(f, "IncrementalSnapshotHashes({})", self.pubkey())
+            }
         }
     }
 }
@@ -363,6 +408,7 @@ impl CrdsValueLabel {
             CrdsValueLabel::AccountsHashes(p) => *p,
             CrdsValueLabel::LegacyVersion(p) => *p,
             CrdsValueLabel::Version(p) => *p,
+            CrdsValueLabel::IncrementalSnapshotHashes(p) => *p,
         }
     }
 }
@@ -409,6 +455,7 @@ impl CrdsValue {
             CrdsData::EpochSlots(_, p) => p.wallclock,
             CrdsData::LegacyVersion(version) => version.wallclock,
             CrdsData::Version(version) => version.wallclock,
+            CrdsData::IncrementalSnapshotHashes(hash) => hash.wallclock,
         }
     }
     pub fn pubkey(&self) -> Pubkey {
@@ -421,6 +468,7 @@ impl CrdsValue {
             CrdsData::EpochSlots(_, p) => p.from,
             CrdsData::LegacyVersion(version) => version.from,
             CrdsData::Version(version) => version.from,
+            CrdsData::IncrementalSnapshotHashes(hash) => hash.from,
         }
     }
     pub fn label(&self) -> CrdsValueLabel {
@@ -433,6 +481,9 @@ impl CrdsValue {
             CrdsData::EpochSlots(ix, _) => CrdsValueLabel::EpochSlots(*ix, self.pubkey()),
             CrdsData::LegacyVersion(_) => CrdsValueLabel::LegacyVersion(self.pubkey()),
             CrdsData::Version(_) => CrdsValueLabel::Version(self.pubkey()),
+            CrdsData::IncrementalSnapshotHashes(_) => {
+                CrdsValueLabel::IncrementalSnapshotHashes(self.pubkey())
+            }
         }
     }
     pub fn contact_info(&self) -> Option<&ContactInfo> {
@@ -469,6 +520,13 @@ impl CrdsValue {
         }
     }
 
+    pub fn incremental_snapshot_hashes(&self) -> Option<&IncrementalSnapshotHashes> {
+        match &self.data {
+            CrdsData::IncrementalSnapshotHashes(hashes) => Some(hashes),
+            _ => None,
+        }
+    }
+
     pub fn accounts_hash(&self) -> Option<&SnapshotHash> {
         match &self.data {
             CrdsData::AccountsHashes(slots) => Some(slots),
@@ -499,13 +557,14 @@ impl CrdsValue {
 
     /// Return all the possible labels for a record identified by Pubkey.
     pub fn record_labels(key: Pubkey) -> impl Iterator<Item = CrdsValueLabel> {
-        const CRDS_VALUE_LABEL_STUBS: [fn(Pubkey) -> CrdsValueLabel; 6] = [
+        const CRDS_VALUE_LABEL_STUBS: [fn(Pubkey) -> CrdsValueLabel; 7] = [
             CrdsValueLabel::ContactInfo,
             CrdsValueLabel::LowestSlot,
             CrdsValueLabel::SnapshotHashes,
             CrdsValueLabel::AccountsHashes,
             CrdsValueLabel::LegacyVersion,
             CrdsValueLabel::Version,
+            CrdsValueLabel::IncrementalSnapshotHashes,
         ];
         CRDS_VALUE_LABEL_STUBS
             .iter()
@@ -584,7 +643,7 @@ mod test {
 
     #[test]
     fn test_labels() {
-        let mut hits = [false; 6 + MAX_VOTES as usize + MAX_EPOCH_SLOTS as usize];
+        let mut hits = [false; 7 + MAX_VOTES as usize + MAX_EPOCH_SLOTS as usize];
         // this method should cover all the possible labels
         for v in CrdsValue::record_labels(Pubkey::default()) {
             match &v {
@@ -594,9 +653,10 @@ mod test {
                 CrdsValueLabel::AccountsHashes(_) => hits[3] = true,
                 CrdsValueLabel::LegacyVersion(_) => hits[4] = true,
                 CrdsValueLabel::Version(_) => hits[5] = true,
-                CrdsValueLabel::Vote(ix, _) => hits[*ix as usize + 6] = true,
+                CrdsValueLabel::IncrementalSnapshotHashes(_) => hits[6] = true,
+                CrdsValueLabel::Vote(ix, _) => hits[*ix as usize + 7] = true,
                 CrdsValueLabel::EpochSlots(ix, _) => {
-                    hits[*ix as usize + MAX_VOTES as usize + 6] = true
+                    hits[*ix as usize + MAX_VOTES as usize + 7] = true
                 }
             }
         }
@@ -652,6 +712,28 @@ mod test {
         assert_eq!(v.sanitize(), Err(SanitizeError::InvalidValue));
     }
 
+    #[test]
+    fn test_incremental_snapshot_hashes_sanitize() {
+        let hashes = IncrementalSnapshotHashes::new(
+            Pubkey::default(),
+            (100, Hash::default()),
+            vec![(150, Hash::default()), (200, Hash::default())],
+        );
+        let v = CrdsValue::new_unsigned(CrdsData::IncrementalSnapshotHashes(hashes.clone()));
+        assert_eq!(v.sanitize(), Ok(()));
+
+        // Incremental snapshots must be newer than the full snapshot they are based on
+        let mut o = hashes.clone();
+        o.hashes.push((100, Hash::default()));
+        let v = CrdsValue::new_unsigned(CrdsData::IncrementalSnapshotHashes(o));
+        assert_eq!(v.sanitize(), Err(SanitizeError::ValueOutOfBounds));
+
+        let mut o = hashes;
+        o.base.0 = MAX_SLOT;
+        let v = CrdsValue::new_unsigned(CrdsData::IncrementalSnapshotHashes(o));
+        assert_eq!(v.sanitize(), Err(SanitizeError::ValueOutOfBounds));
+    }
+
     #[test]
     fn test_signature() {
         let keypair = Keypair::new();
@@ -1,6 +1,6 @@
 //! The `ledger_cleanup_service` drops older ledger data to limit disk space usage
 
-use solana_ledger::blockstore::{Blockstore, PurgeType};
+use solana_ledger::blockstore::{Blockstore, TransactionMetadataCleanupSlots};
 use solana_ledger::blockstore_db::Result as BlockstoreResult;
 use solana_measure::measure::Measure;
 use solana_sdk::clock::{Slot, DEFAULT_TICKS_PER_SLOT, TICKS_PER_DAY};
@@ -33,6 +33,17 @@ pub const DEFAULT_PURGE_SLOT_INTERVAL: u64 = 512;
 // Once a day should be ample
 const DEFAULT_COMPACTION_SLOT_INTERVAL: u64 = TICKS_PER_DAY / DEFAULT_TICKS_PER_SLOT;
 
+/// How many slots below the latest root each transaction metadata column, and the account
+/// history, is retained for, even once the shreds of those slots are purged. Columns without a
+/// limit are purged along with the shreds
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TransactionHistoryLimits {
+    pub transaction_status_slots: Option<u64>,
+    pub address_signatures_slots: Option<u64>,
+    pub rewards_slots: Option<u64>,
+    pub account_history_slots: Option<u64>,
+}
+
 pub struct LedgerCleanupService {
     t_cleanup: JoinHandle<()>,
 }
@@ -42,11 +53,12 @@ impl LedgerCleanupService {
         new_root_receiver: Receiver<Slot>,
         blockstore: Arc<Blockstore>,
         max_ledger_shreds: u64,
+        transaction_history_limits: TransactionHistoryLimits,
         exit: &Arc<AtomicBool>,
     ) -> Self {
         info!(
-            "LedgerCleanupService active. Max Ledger Slots {}",
-            max_ledger_shreds
+            "LedgerCleanupService active. Max Ledger Slots {}, Transaction History Limits {:?}",
+            max_ledger_shreds, transaction_history_limits
         );
         let exit = exit.clone();
         let mut last_purge_slot = 0;
@@ -62,6 +74,7 @@ impl LedgerCleanupService {
                     &new_root_receiver,
                     &blockstore,
                     max_ledger_shreds,
+                    transaction_history_limits,
                     &mut last_purge_slot,
                     DEFAULT_PURGE_SLOT_INTERVAL,
                     &mut last_compaction_slot,
@@ -132,10 +145,15 @@ impl LedgerCleanupService {
         Ok(root)
     }
 
+    /// Purges the shreds of the oldest slots once the ledger holds more than `max_ledger_shreds`.
+    /// The transaction metadata of those slots is purged along with them, unless
+    /// `transaction_history_limits` retains a column for longer
+    #[allow(clippy::too_many_arguments)]
     pub fn cleanup_ledger(
         new_root_receiver: &Receiver<Slot>,
         blockstore: &Arc<Blockstore>,
         max_ledger_shreds: u64,
+        transaction_history_limits: TransactionHistoryLimits,
         last_purge_slot: &mut u64,
         purge_interval: u64,
         last_compaction_slot: &mut u64,
@@ -158,6 +176,24 @@ impl LedgerCleanupService {
             Self::find_slots_to_clean(&blockstore, root, max_ledger_shreds);
 
         if slots_to_clean {
+            // Transaction metadata is never purged ahead of the shreds of its slot
+            let lowest_cleanup_column_slot = |max_slots: Option<u64>| {
+                max_slots.map_or(lowest_cleanup_slot, |max_slots| {
+                    lowest_cleanup_slot.min(root.saturating_sub(max_slots))
+                })
+            };
+            let transaction_metadata_cleanup_slots = TransactionMetadataCleanupSlots {
+                transaction_status: lowest_cleanup_column_slot(
+                    transaction_history_limits.transaction_status_slots,
+                ),
+                address_signatures: lowest_cleanup_column_slot(
+                    transaction_history_limits.address_signatures_slots,
+                ),
+                rewards: lowest_cleanup_column_slot(transaction_history_limits.rewards_slots),
+            };
+            let account_history_cleanup_slot =
+                lowest_cleanup_column_slot(transaction_history_limits.account_history_slots);
+
             let mut compact_first_slot = std::u64::MAX;
             if lowest_cleanup_slot.saturating_sub(*last_compaction_slot) > compaction_interval {
                 compact_first_slot = *last_compaction_slot;
@@ -180,11 +216,17 @@ impl LedgerCleanupService {
                     );
 
                     let mut purge_time = Measure::start("purge_slots");
-                    blockstore.purge_slots(
-                        purge_first_slot,
-                        lowest_cleanup_slot,
-                        PurgeType::PrimaryIndex,
+                    blockstore.purge_shreds(purge_first_slot, lowest_cleanup_slot);
+                    info!(
+                        "purging transaction metadata up to slots {:?}",
+                        transaction_metadata_cleanup_slots
+                    );
+                    blockstore.purge_transaction_metadata(transaction_metadata_cleanup_slots);
+                    info!(
+                        "purging account history up to slot {}",
+                        account_history_cleanup_slot
                     );
+                    blockstore.purge_account_history(account_history_cleanup_slot);
                     purge_time.stop();
                     info!("{}", purge_time);
 
@@ -248,6 +290,8 @@ mod tests {
     use super::*;
     use solana_ledger::blockstore::make_many_slot_entries;
     use solana_ledger::get_tmp_ledger_path;
+    use solana_sdk::{pubkey::Pubkey, signature::Signature};
+    use solana_transaction_status::TransactionStatusMeta;
     use std::sync::mpsc::channel;
 
     #[test]
@@ -268,6 +312,7 @@ mod tests {
             &receiver,
             &blockstore,
             5,
+            TransactionHistoryLimits::default(),
             &mut last_purge_slot,
             10,
             &mut last_compaction_slot,
@@ -285,6 +330,99 @@ mod tests {
         Blockstore::destroy(&blockstore_path).expect("Expected successful database destruction");
     }
 
+    #[test]
+    fn test_cleanup_with_transaction_history() {
+        solana_logger::setup();
+        let blockstore_path = get_tmp_ledger_path!();
+        let blockstore = Blockstore::open(&blockstore_path).unwrap();
+        let (shreds, _) = make_many_slot_entries(0, 50, 5);
+        blockstore.insert_shreds(shreds, None, false).unwrap();
+        blockstore.set_roots(&(0..50).collect::<Vec<_>>()).unwrap();
+        let address = Pubkey::new_unique();
+        let signature = |slot: Slot| Signature::new(&[slot as u8 + 1; 64]);
+        for slot in 0..50 {
+            blockstore.write_rewards(slot, vec![]).unwrap();
+            blockstore.write_account_history(slot, &[]).unwrap();
+            blockstore
+                .write_transaction_status(
+                    slot,
+                    signature(slot),
+                    vec![&address],
+                    vec![],
+                    &TransactionStatusMeta::default(),
+                )
+                .unwrap();
+        }
+        let blockstore = Arc::new(blockstore);
+        let (sender, receiver) = channel();
+
+        // Keep 5 shreds, but the transaction statuses of the last 20 slots below the root, the
+        // rewards of the last 15 and the account history of the last 25. Address signatures go
+        // along with the shreds
+        let mut last_purge_slot = 0;
+        let mut last_compaction_slot = 0;
+        sender.send(50).unwrap();
+        LedgerCleanupService::cleanup_ledger(
+            &receiver,
+            &blockstore,
+            5,
+            TransactionHistoryLimits {
+                transaction_status_slots: Some(20),
+                address_signatures_slots: None,
+                rewards_slots: Some(15),
+                account_history_slots: Some(25),
+            },
+            &mut last_purge_slot,
+            10,
+            &mut last_compaction_slot,
+            10,
+        )
+        .unwrap();
+
+        blockstore
+            .slot_meta_iterator(0)
+            .unwrap()
+            .for_each(|(slot, _)| assert!(slot > 40));
+        let lowest_cleanup_slot = *blockstore.lowest_cleanup_slot.read().unwrap();
+        assert_eq!(
+            *blockstore
+                .lowest_cleanup_transaction_metadata_slots
+                .read()
+                .unwrap(),
+            TransactionMetadataCleanupSlots {
+                transaction_status: 30,
+                address_signatures: lowest_cleanup_slot,
+                rewards: 35,
+            }
+        );
+        for slot in 0..50 {
+            assert_eq!(blockstore.is_root(slot), slot > 30);
+            assert_eq!(blockstore.read_rewards(slot).unwrap().is_some(), slot > 35);
+            assert_eq!(
+                blockstore
+                    .get_transaction_status(signature(slot))
+                    .unwrap()
+                    .is_some(),
+                slot > 30
+            );
+        }
+        assert_eq!(
+            blockstore
+                .get_confirmed_signatures_for_address(address, 0, 50)
+                .unwrap(),
+            ((lowest_cleanup_slot + 1)..50)
+                .map(signature)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            blockstore.get_account_history_slot_range().unwrap(),
+            Some((26, 49))
+        );
+
+        drop(blockstore);
+        Blockstore::destroy(&blockstore_path).expect("Expected successful database destruction");
+    }
+
     #[test]
     fn test_cleanup_speed() {
         solana_logger::setup();
@@ -325,6 +463,7 @@ mod tests {
                 &receiver,
                 &blockstore,
                 initial_slots,
+                TransactionHistoryLimits::default(),
                 &mut last_purge_slot,
                 10,
                 &mut last_compaction_slot,
@@ -6,6 +6,7 @@
 //! command-line tools to spin up validators and a Rust library
 //!
 
+pub mod account_history_service;
 pub mod accounts_hash_verifier;
 pub mod banking_stage;
 pub mod bigtable_upload_service;
@@ -69,6 +70,7 @@ pub mod sigverify;
 pub mod sigverify_shreds;
 pub mod sigverify_stage;
 pub mod snapshot_packager_service;
+pub mod staked_nodes_updater_service;
 pub mod test_validator;
 pub mod tpu;
 pub mod transaction_status_service;
@@ -76,6 +78,7 @@ pub mod tree_diff;
 pub mod tvu;
 pub mod validator;
 pub mod verified_vote_packets;
+pub mod vote_signing_service;
 pub mod vote_stake_tracker;
 pub mod weighted_shuffle;
 pub mod window_service;
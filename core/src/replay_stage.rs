@@ -19,11 +19,15 @@ use crate::{
     result::Result,
     rewards_recorder_service::RewardsRecorderSender,
     rpc_subscriptions::RpcSubscriptions,
+    vote_signing_service::{VoteSigningRequest, VoteSigningService},
 };
+use solana_geyser_plugin_manager::slot_status_notifier::SlotStatusNotifier;
 use solana_ledger::{
     block_error::BlockError,
     blockstore::Blockstore,
-    blockstore_processor::{self, BlockstoreProcessorError, TransactionStatusSender},
+    blockstore_processor::{
+        self, AccountHistorySender, BlockstoreProcessorError, TransactionStatusSender,
+    },
     entry::VerifyRecyclers,
     leader_schedule_cache::LeaderScheduleCache,
 };
@@ -43,6 +47,7 @@ use solana_sdk::{
     transaction::Transaction,
 };
 use solana_vote_program::{vote_instruction, vote_state::Vote};
+use solana_vote_signer::{remote::RemoteVoteSigner, slashing_protection::BankHashLink};
 use std::{
     collections::{HashMap, HashSet},
     ops::Deref,
@@ -97,6 +102,7 @@ pub struct ReplayStageConfig {
     pub my_pubkey: Pubkey,
     pub vote_account: Pubkey,
     pub authorized_voter_keypairs: Vec<Arc<Keypair>>,
+    pub remote_vote_signer: Option<Arc<RemoteVoteSigner>>,
     pub exit: Arc<AtomicBool>,
     pub subscriptions: Arc<RpcSubscriptions>,
     pub leader_schedule_cache: Arc<LeaderScheduleCache>,
@@ -106,7 +112,9 @@ pub struct ReplayStageConfig {
     pub transaction_status_sender: Option<TransactionStatusSender>,
     pub rewards_recorder_sender: Option<RewardsRecorderSender>,
     pub cache_block_time_sender: Option<CacheBlockTimeSender>,
+    pub account_history_sender: Option<AccountHistorySender>,
     pub bank_notification_sender: Option<BankNotificationSender>,
+    pub slot_status_notifier: Option<SlotStatusNotifier>,
 }
 
 #[derive(Default)]
@@ -207,6 +215,7 @@ impl ReplayTiming {
 pub struct ReplayStage {
     t_replay: JoinHandle<Result<()>>,
     commitment_service: AggregateCommitmentService,
+    vote_signing_service: Option<VoteSigningService>,
 }
 
 impl ReplayStage {
@@ -229,6 +238,7 @@ impl ReplayStage {
             my_pubkey,
             vote_account,
             authorized_voter_keypairs,
+            remote_vote_signer,
             exit,
             subscriptions,
             leader_schedule_cache,
@@ -238,7 +248,9 @@ impl ReplayStage {
             transaction_status_sender,
             rewards_recorder_sender,
             cache_block_time_sender,
+            account_history_sender,
             bank_notification_sender,
+            slot_status_notifier,
         } = config;
 
         trace!("replay stage");
@@ -248,6 +260,15 @@ impl ReplayStage {
             block_commitment_cache.clone(),
             subscriptions.clone(),
         );
+        // Votes are signed by the remote signer, if any, in its own thread
+        let (vote_signing_sender, vote_signing_service) = match remote_vote_signer {
+            Some(remote_vote_signer) => {
+                let (sender, service) =
+                    VoteSigningService::new(&exit, remote_vote_signer, cluster_info.clone());
+                (Some(sender), Some(service))
+            }
+            None => (None, None),
+        };
 
         #[allow(clippy::cognitive_complexity)]
         let t_replay = Builder::new()
@@ -310,6 +331,7 @@ impl ReplayStage {
                         &replay_vote_sender,
                         &bank_notification_sender,
                         &rewards_recorder_sender,
+                        &slot_status_notifier,
                     );
                     replay_active_banks_time.stop();
                     Self::report_memory(&allocated, "replay_active_banks", start);
@@ -376,6 +398,17 @@ impl ReplayStage {
                                 .unwrap()
                                 .fork_stats
                                 .confirmation_reported = true;
+                            if let Some(slot_status_notifier) = &slot_status_notifier {
+                                let parent = bank_forks
+                                    .read()
+                                    .unwrap()
+                                    .get(slot)
+                                    .map(|bank| bank.parent_slot());
+                                slot_status_notifier
+                                    .read()
+                                    .unwrap()
+                                    .notify_slot_confirmed(slot, parent);
+                            }
                         }
                     }
                     compute_slot_stats_time.stop();
@@ -451,6 +484,7 @@ impl ReplayStage {
                             &mut progress,
                             &vote_account,
                             &authorized_voter_keypairs,
+                            vote_signing_sender.as_ref(),
                             &cluster_info,
                             &blockstore,
                             &leader_schedule_cache,
@@ -462,7 +496,9 @@ impl ReplayStage {
                             &block_commitment_cache,
                             &mut heaviest_subtree_fork_choice,
                             &cache_block_time_sender,
+                            &account_history_sender,
                             &bank_notification_sender,
+                            &slot_status_notifier,
                         )?;
                     };
                     voting_time.stop();
@@ -601,6 +637,7 @@ impl ReplayStage {
         Self {
             t_replay,
             commitment_service,
+            vote_signing_service,
         }
     }
 
@@ -1019,6 +1056,7 @@ impl ReplayStage {
         progress: &mut ProgressMap,
         vote_account_pubkey: &Pubkey,
         authorized_voter_keypairs: &[Arc<Keypair>],
+        vote_signing_sender: Option<&Sender<VoteSigningRequest>>,
         cluster_info: &Arc<ClusterInfo>,
         blockstore: &Arc<Blockstore>,
         leader_schedule_cache: &Arc<LeaderScheduleCache>,
@@ -1030,7 +1068,9 @@ impl ReplayStage {
         block_commitment_cache: &Arc<RwLock<BlockCommitmentCache>>,
         heaviest_subtree_fork_choice: &mut HeaviestSubtreeForkChoice,
         cache_block_time_sender: &Option<CacheBlockTimeSender>,
+        account_history_sender: &Option<AccountHistorySender>,
         bank_notification_sender: &Option<BankNotificationSender>,
+        slot_status_notifier: &Option<SlotStatusNotifier>,
     ) -> Result<()> {
         if bank.is_empty() {
             inc_new_counter_info!("replay_stage-voted_empty_bank", 1);
@@ -1070,6 +1110,14 @@ impl ReplayStage {
                 &rooted_slots,
                 cache_block_time_sender,
             );
+            // The writes of the new roots are loaded by the account history service, which
+            // holds off cleaning them until they are recorded
+            if let Some(account_history_sender) = account_history_sender {
+                blockstore_processor::send_account_history_batch(
+                    &root_bank,
+                    account_history_sender,
+                );
+            }
             let highest_confirmed_root = Some(
                 block_commitment_cache
                     .read()
@@ -1085,6 +1133,13 @@ impl ReplayStage {
                 highest_confirmed_root,
                 heaviest_subtree_fork_choice,
             );
+            if let Some(slot_status_notifier) = slot_status_notifier {
+                let slot_status_notifier = slot_status_notifier.read().unwrap();
+                for rooted_bank in &rooted_banks {
+                    slot_status_notifier
+                        .notify_slot_rooted(rooted_bank.slot(), Some(rooted_bank.parent_slot()));
+                }
+            }
             subscriptions.notify_roots(rooted_slots);
             if let Some(sender) = bank_notification_sender {
                 sender
@@ -1110,6 +1165,7 @@ impl ReplayStage {
             bank,
             vote_account_pubkey,
             authorized_voter_keypairs,
+            vote_signing_sender,
             last_vote,
             tower_index,
             switch_fork_decision,
@@ -1122,11 +1178,12 @@ impl ReplayStage {
         bank: &Arc<Bank>,
         vote_account_pubkey: &Pubkey,
         authorized_voter_keypairs: &[Arc<Keypair>],
+        vote_signing_sender: Option<&Sender<VoteSigningRequest>>,
         vote: Vote,
         tower_index: usize,
         switch_fork_decision: &SwitchForkDecision,
     ) {
-        if authorized_voter_keypairs.is_empty() {
+        if authorized_voter_keypairs.is_empty() && vote_signing_sender.is_none() {
             return;
         }
         let vote_account = match bank.get_vote_account(vote_account_pubkey) {
@@ -1162,16 +1219,20 @@ impl ReplayStage {
                 return;
             };
 
-        let authorized_voter_keypair = match authorized_voter_keypairs
-            .iter()
-            .find(|keypair| keypair.pubkey() == authorized_voter_pubkey)
-        {
-            None => {
+        // A remote vote signer is the only signer when configured, so that every vote
+        // goes through its slashing protection
+        let authorized_voter_keypair = if vote_signing_sender.is_some() {
+            None
+        } else {
+            let authorized_voter_keypair = authorized_voter_keypairs
+                .iter()
+                .find(|keypair| keypair.pubkey() == authorized_voter_pubkey);
+            if authorized_voter_keypair.is_none() {
                 warn!("The authorized keypair {} for vote account {} is not available.  Unable to vote",
                       authorized_voter_pubkey, vote_account_pubkey);
                 return;
             }
-            Some(authorized_voter_keypair) => authorized_voter_keypair,
+            authorized_voter_keypair
         };
         let node_keypair = cluster_info.keypair.clone();
 
@@ -1181,13 +1242,13 @@ impl ReplayStage {
                 .to_vote_instruction(
                     vote,
                     &vote_account_pubkey,
-                    &authorized_voter_keypair.pubkey(),
+                    &authorized_voter_pubkey,
                 )
                 .expect("Switch threshold failure should not lead to voting")
         } else {
             vote_instruction::vote(
                 &vote_account_pubkey,
-                &authorized_voter_keypair.pubkey(),
+                &authorized_voter_pubkey,
                 vote,
             )
         };
@@ -1196,11 +1257,48 @@ impl ReplayStage {
 
         let blockhash = bank.last_blockhash();
         vote_tx.partial_sign(&[node_keypair.as_ref()], blockhash);
-        vote_tx.partial_sign(&[authorized_voter_keypair.as_ref()], blockhash);
+        if let Some(vote_signing_sender) = vote_signing_sender {
+            let _ = vote_signing_sender.send(VoteSigningRequest {
+                slot: bank.slot(),
+                vote_tx,
+                ancestry: Self::vote_ancestry(bank),
+                tower_index,
+            });
+            return;
+        }
+        if let Some(authorized_voter_keypair) = authorized_voter_keypair {
+            vote_tx.partial_sign(&[authorized_voter_keypair.as_ref()], blockhash);
+        }
         let _ = cluster_info.send_vote(&vote_tx);
         cluster_info.push_vote(tower_index, vote_tx);
     }
 
+    // The hash chain from the oldest frozen parent of `bank` up to `bank`, which lets a
+    // remote vote signer verify the ancestors of the vote on its own
+    fn vote_ancestry(bank: &Arc<Bank>) -> Vec<BankHashLink> {
+        let mut ancestry = vec![];
+        for bank in std::iter::once(bank.clone()).chain(bank.parents()) {
+            let accounts_delta_hash = match bank.accounts_delta_hash() {
+                Some(accounts_delta_hash) => accounts_delta_hash,
+                None => break,
+            };
+            let hard_fork_data = bank
+                .hard_forks()
+                .read()
+                .unwrap()
+                .get_hash_data(bank.slot(), bank.parent_slot());
+            ancestry.push(BankHashLink {
+                parent_hash: bank.parent_hash(),
+                accounts_delta_hash,
+                signature_count: bank.signature_count(),
+                last_blockhash: bank.last_blockhash(),
+                hard_fork_data,
+            });
+        }
+        ancestry.reverse();
+        ancestry
+    }
+
     fn update_commitment_cache(
         bank: Arc<Bank>,
         root: Slot,
@@ -1261,6 +1359,7 @@ impl ReplayStage {
         replay_vote_sender: &ReplayVoteSender,
         bank_notification_sender: &Option<BankNotificationSender>,
         rewards_recorder_sender: &Option<RewardsRecorderSender>,
+        slot_status_notifier: &Option<SlotStatusNotifier>,
     ) -> bool {
         let mut did_complete_bank = false;
         let mut tx_count = 0;
@@ -1336,6 +1435,12 @@ impl ReplayStage {
                         .send(BankNotification::Frozen(bank.clone()))
                         .unwrap_or_else(|err| warn!("bank_notification_sender failed: {:?}", err));
                 }
+                if let Some(slot_status_notifier) = slot_status_notifier {
+                    slot_status_notifier
+                        .read()
+                        .unwrap()
+                        .notify_slot_processed(bank.slot(), Some(bank.parent_slot()));
+                }
 
                 Self::record_rewards(&bank, &rewards_recorder_sender);
             } else {
@@ -1958,6 +2063,9 @@ impl ReplayStage {
 
     pub fn join(self) -> thread::Result<()> {
         self.commitment_service.join()?;
+        if let Some(vote_signing_service) = self.vote_signing_service {
+            vote_signing_service.join()?;
+        }
         self.t_replay.join().map(|_| ())
     }
 }
@@ -2235,6 +2343,22 @@ pub(crate) mod tests {
         assert!(progress.get(&root).is_some());
     }
 
+    #[test]
+    fn test_vote_ancestry() {
+        let genesis_config = create_genesis_config(10_000).genesis_config;
+        let bank0 = Arc::new(Bank::new(&genesis_config));
+        let bank1 = Arc::new(Bank::new_from_parent(&bank0, &Pubkey::default(), 1));
+        bank1.freeze();
+        let bank2 = Arc::new(Bank::new_from_parent(&bank1, &Pubkey::default(), 2));
+        bank2.freeze();
+
+        let ancestry = ReplayStage::vote_ancestry(&bank2);
+        assert!(ancestry.len() >= 2);
+        assert_eq!(ancestry.last().unwrap().hash(), bank2.hash());
+        assert_eq!(ancestry[ancestry.len() - 2].hash(), bank1.hash());
+        assert_eq!(ancestry.last().unwrap().parent_hash, bank1.hash());
+    }
+
     #[test]
     fn test_handle_new_root_ahead_of_highest_confirmed_root() {
         let genesis_config = create_genesis_config(10_000).genesis_config;
@@ -2700,6 +2824,8 @@ pub(crate) mod tests {
         let transaction_status_service = TransactionStatusService::new(
             transaction_status_receiver,
             blockstore,
+            true,
+            None,
             &Arc::new(AtomicBool::new(false)),
         );
 
@@ -39,7 +39,11 @@ use solana_metrics::inc_new_counter_info;
 use solana_perf::packet::PACKET_DATA_SIZE;
 use solana_runtime::{
     accounts::AccountAddressFilter,
-    bank::Bank,
+    accounts_index::{AccountIndex, AccountSecondaryIndexes, IndexKey},
+    bank::{
+        inline_spl_token_v2_0::{SPL_TOKEN_ACCOUNT_MINT_OFFSET, SPL_TOKEN_ACCOUNT_OWNER_OFFSET},
+        Bank, TransactionSimulationResult,
+    },
     bank_forks::BankForks,
     commitment::{BlockCommitmentArray, BlockCommitmentCache, CommitmentSlots},
 };
@@ -60,7 +64,8 @@ use solana_sdk::{
 };
 use solana_stake_program::stake_state::StakeState;
 use solana_transaction_status::{
-    EncodedConfirmedBlock, EncodedConfirmedTransaction, TransactionStatus, UiTransactionEncoding,
+    EncodedConfirmedBlock, EncodedConfirmedTransaction, InnerInstructions, TransactionStatus,
+    UiTransactionEncoding,
 };
 use solana_vote_program::vote_state::{VoteState, MAX_LOCKOUT_HISTORY};
 use spl_token_v2_0::{
@@ -71,6 +76,7 @@ use std::{
     cmp::{max, min},
     collections::{HashMap, HashSet},
     net::SocketAddr,
+    path::PathBuf,
     str::FromStr,
     sync::{
         atomic::{AtomicBool, Ordering},
@@ -103,11 +109,14 @@ pub struct JsonRpcConfig {
     pub enable_validator_exit: bool,
     pub enable_set_log_filter: bool,
     pub enable_rpc_transaction_history: bool,
+    pub enable_rpc_account_history: bool,
     pub identity_pubkey: Pubkey,
     pub faucet_addr: Option<SocketAddr>,
     pub health_check_slot_distance: u64,
     pub enable_bigtable_ledger_storage: bool,
     pub enable_bigtable_ledger_upload: bool,
+    pub local_ledger_storage_path: Option<PathBuf>, // None = BigTable ledger storage
+    pub account_indexes: AccountSecondaryIndexes,
 }
 
 #[derive(Clone)]
@@ -266,14 +275,82 @@ impl JsonRpcRequestProcessor {
         config: Option<RpcAccountInfoConfig>,
     ) -> Result<RpcResponse<Option<UiAccount>>> {
         let config = config.unwrap_or_default();
-        let bank = self.bank(config.commitment);
         let encoding = config.encoding.unwrap_or(UiAccountEncoding::Binary);
         check_slice_and_encoding(&encoding, config.data_slice.is_some())?;
 
+        if let Some(slot) = config.at_slot {
+            let response =
+                self.get_encoded_account_at_slot(pubkey, slot, encoding, config.data_slice)?;
+            let context = RpcResponseContext { slot };
+            return Ok(Response {
+                context,
+                value: response,
+            });
+        }
+
+        let bank = self.bank(config.commitment);
         let response = get_encoded_account(&bank, pubkey, encoding, config.data_slice)?;
         Ok(new_response(&bank, response))
     }
 
+    /// The account as it was at the end of rooted `slot`, read from the account history for
+    /// slots older than the current root
+    fn get_encoded_account_at_slot(
+        &self,
+        pubkey: &Pubkey,
+        slot: Slot,
+        encoding: UiAccountEncoding,
+        data_slice: Option<UiDataSliceConfig>,
+    ) -> Result<Option<UiAccount>> {
+        let root_bank = self.bank_forks.read().unwrap().root_bank().clone();
+        if slot == root_bank.slot() {
+            return get_encoded_account(&root_bank, pubkey, encoding, data_slice);
+        }
+        if slot > root_bank.slot() || !self.config.enable_rpc_account_history {
+            return Err(RpcCustomError::AccountHistoryNotAvailable { slot }.into());
+        }
+        // Only the slots from the first to the last recorded one can be answered for, the
+        // account history knows nothing of the writes before it started or not yet recorded
+        let recorded_slots = self
+            .blockstore
+            .get_account_history_slot_range()
+            .map_err(|_| Error::internal_error())?;
+        match recorded_slots {
+            Some((first_slot, last_slot)) if first_slot <= slot && slot <= last_slot => {}
+            _ => return Err(RpcCustomError::AccountHistoryNotAvailable { slot }.into()),
+        }
+
+        let account = match self
+            .blockstore
+            .get_account_history_at_slot(pubkey, slot)
+            .map_err(|_| Error::internal_error())?
+        {
+            Some((_, entry)) => Some(entry.account),
+            // Not written since the history started. If it has not been written since `slot`
+            // either, it still is as the root bank has it
+            None => match root_bank.get_account_modified_slot(pubkey) {
+                Some((account, modified_slot)) if modified_slot <= slot => Some(account),
+                Some(_) => return Err(RpcCustomError::AccountHistoryNotAvailable { slot }.into()),
+                None => {
+                    if self
+                        .blockstore
+                        .get_next_account_history_slot(pubkey, slot)
+                        .map_err(|_| Error::internal_error())?
+                        .is_some()
+                    {
+                        return Err(RpcCustomError::AccountHistoryNotAvailable { slot }.into());
+                    }
+                    None
+                }
+            },
+        };
+        account
+            // Accounts are removed by writing them with zero lamports
+            .filter(|account| account.lamports != 0)
+            .map(|account| encode_account(&root_bank, pubkey, account, encoding, data_slice))
+            .transpose()
+    }
+
     pub fn get_multiple_accounts(
         &self,
         pubkeys: Vec<Pubkey>,
@@ -282,10 +359,26 @@ impl JsonRpcRequestProcessor {
         let mut accounts: Vec<Option<UiAccount>> = vec![];
 
         let config = config.unwrap_or_default();
-        let bank = self.bank(config.commitment);
         let encoding = config.encoding.unwrap_or(UiAccountEncoding::Base64);
         check_slice_and_encoding(&encoding, config.data_slice.is_some())?;
 
+        if let Some(slot) = config.at_slot {
+            for pubkey in pubkeys {
+                accounts.push(self.get_encoded_account_at_slot(
+                    &pubkey,
+                    slot,
+                    encoding.clone(),
+                    config.data_slice,
+                )?);
+            }
+            let context = RpcResponseContext { slot };
+            return Ok(Response {
+                context,
+                value: accounts,
+            });
+        }
+
+        let bank = self.bank(config.commitment);
         for pubkey in pubkeys {
             let response_account =
                 get_encoded_account(&bank, &pubkey, encoding.clone(), config.data_slice)?;
@@ -294,6 +387,41 @@ impl JsonRpcRequestProcessor {
         Ok(new_response(&bank, accounts))
     }
 
+    /// Load the program accounts that pass `filters`, using the secondary account indexes when
+    /// they are enabled. Secondary index lookups may return stale entries, so every account is
+    /// still checked against the program id and the filters.
+    fn get_filtered_program_accounts(
+        &self,
+        bank: &Arc<Bank>,
+        program_id: &Pubkey,
+        filters: Vec<RpcFilterType>,
+    ) -> Vec<(Pubkey, Account)> {
+        let account_indexes = &self.config.account_indexes;
+        let index_key = if let Some(owner) = get_spl_token_owner_filter(program_id, &filters)
+            .filter(|_| account_indexes.contains(&AccountIndex::SplTokenOwner))
+        {
+            Some(IndexKey::SplTokenOwner(owner))
+        } else if let Some(mint) = get_spl_token_mint_filter(program_id, &filters)
+            .filter(|_| account_indexes.contains(&AccountIndex::SplTokenMint))
+        {
+            Some(IndexKey::SplTokenMint(mint))
+        } else if account_indexes.contains(&AccountIndex::ProgramId) {
+            Some(IndexKey::ProgramId(*program_id))
+        } else {
+            None
+        };
+
+        let accounts = match index_key {
+            Some(index_key) => bank
+                .get_filtered_indexed_accounts(&index_key, |account| account.owner == *program_id),
+            None => bank.get_program_accounts(program_id),
+        };
+        accounts
+            .into_iter()
+            .filter(|(pubkey, account)| filters_allow(bank, pubkey, account, &filters))
+            .collect()
+    }
+
     pub fn get_minimum_balance_for_rent_exemption(
         &self,
         data_len: usize,
@@ -314,7 +442,11 @@ impl JsonRpcRequestProcessor {
         let encoding = config.encoding.unwrap_or(UiAccountEncoding::Binary);
         let data_slice_config = config.data_slice;
         check_slice_and_encoding(&encoding, data_slice_config.is_some())?;
-        let keyed_accounts = get_filtered_program_accounts(&bank, program_id, filters);
+        check_at_slot_unsupported(config.at_slot)?;
+        check_owner_changed_filter(&bank, &filters)?;
+        let keyed_accounts = self
+            .get_filtered_program_accounts(&bank, program_id, filters)
+            .into_iter();
         let result =
             if program_id == &spl_token_id_v2_0() && encoding == UiAccountEncoding::JsonParsed {
                 get_parsed_token_accounts(bank, keyed_accounts).collect()
@@ -1162,19 +1294,20 @@ impl JsonRpcRequestProcessor {
             // Filter on Token Account state
             RpcFilterType::DataSize(TokenAccount::get_packed_len() as u64),
         ];
-        let mut token_balances: Vec<RpcTokenAccountBalance> =
-            get_filtered_program_accounts(&bank, &mint_owner, filters)
-                .map(|(address, account)| {
-                    let amount = TokenAccount::unpack(&account.data)
-                        .map(|account| account.amount)
-                        .unwrap_or(0);
-                    let amount = token_amount_to_ui_amount(amount, decimals);
-                    RpcTokenAccountBalance {
-                        address: address.to_string(),
-                        amount,
-                    }
-                })
-                .collect();
+        let mut token_balances: Vec<RpcTokenAccountBalance> = self
+            .get_filtered_program_accounts(&bank, &mint_owner, filters)
+            .into_iter()
+            .map(|(address, account)| {
+                let amount = TokenAccount::unpack(&account.data)
+                    .map(|account| account.amount)
+                    .unwrap_or(0);
+                let amount = token_amount_to_ui_amount(amount, decimals);
+                RpcTokenAccountBalance {
+                    address: address.to_string(),
+                    amount,
+                }
+            })
+            .collect();
         token_balances.sort_by(|a, b| {
             a.amount
                 .amount
@@ -1198,6 +1331,7 @@ impl JsonRpcRequestProcessor {
         let encoding = config.encoding.unwrap_or(UiAccountEncoding::Binary);
         let data_slice_config = config.data_slice;
         check_slice_and_encoding(&encoding, data_slice_config.is_some())?;
+        check_at_slot_unsupported(config.at_slot)?;
         let (token_program_id, mint) = get_token_program_id_and_mint(&bank, token_account_filter)?;
 
         let mut filters = vec![
@@ -1218,7 +1352,9 @@ impl JsonRpcRequestProcessor {
                 encoding: None,
             }));
         }
-        let keyed_accounts = get_filtered_program_accounts(&bank, &token_program_id, filters);
+        let keyed_accounts = self
+            .get_filtered_program_accounts(&bank, &token_program_id, filters)
+            .into_iter();
         let accounts = if encoding == UiAccountEncoding::JsonParsed {
             get_parsed_token_accounts(bank.clone(), keyed_accounts).collect()
         } else {
@@ -1249,6 +1385,7 @@ impl JsonRpcRequestProcessor {
         let encoding = config.encoding.unwrap_or(UiAccountEncoding::Binary);
         let data_slice_config = config.data_slice;
         check_slice_and_encoding(&encoding, data_slice_config.is_some())?;
+        check_at_slot_unsupported(config.at_slot)?;
         let (token_program_id, mint) = get_token_program_id_and_mint(&bank, token_account_filter)?;
 
         let mut filters = vec![
@@ -1277,7 +1414,9 @@ impl JsonRpcRequestProcessor {
                 encoding: None,
             }));
         }
-        let keyed_accounts = get_filtered_program_accounts(&bank, &token_program_id, filters);
+        let keyed_accounts = self
+            .get_filtered_program_accounts(&bank, &token_program_id, filters)
+            .into_iter();
         let accounts = if encoding == UiAccountEncoding::JsonParsed {
             get_parsed_token_accounts(bank.clone(), keyed_accounts).collect()
         } else {
@@ -1310,7 +1449,7 @@ fn verify_transaction(transaction: &Transaction) -> Result<()> {
     Ok(())
 }
 
-fn verify_filter(input: &RpcFilterType) -> Result<()> {
+pub(crate) fn verify_filter(input: &RpcFilterType) -> Result<()> {
     input
         .verify()
         .map_err(|e| Error::invalid_params(format!("Invalid param: {:?}", e)))
@@ -1343,6 +1482,29 @@ fn verify_token_account_filter(
     }
 }
 
+/// Rooted banks have no parent, so the owners they wrote can't be compared with previous ones
+fn check_owner_changed_filter(bank: &Bank, filters: &[RpcFilterType]) -> Result<()> {
+    if filters.contains(&RpcFilterType::OwnerChanged) && bank.parent().is_none() {
+        Err(Error::invalid_params(format!(
+            "Invalid param: ownerChanged filter requires an unrooted bank, slot {} is rooted",
+            bank.slot()
+        )))
+    } else {
+        Ok(())
+    }
+}
+
+/// Past account states are only served by the methods that read accounts by address
+pub(crate) fn check_at_slot_unsupported(at_slot: Option<Slot>) -> Result<()> {
+    if at_slot.is_some() {
+        Err(Error::invalid_params(
+            "Invalid param: atSlot is not supported by this method",
+        ))
+    } else {
+        Ok(())
+    }
+}
+
 fn check_slice_and_encoding(encoding: &UiAccountEncoding, data_slice_is_some: bool) -> Result<()> {
     match encoding {
         UiAccountEncoding::JsonParsed => {
@@ -1372,42 +1534,89 @@ fn get_encoded_account(
     encoding: UiAccountEncoding,
     data_slice: Option<UiDataSliceConfig>,
 ) -> Result<Option<UiAccount>> {
-    let mut response = None;
-    if let Some(account) = bank.get_account(pubkey) {
-        if account.owner == spl_token_id_v2_0() && encoding == UiAccountEncoding::JsonParsed {
-            response = Some(get_parsed_token_account(bank.clone(), pubkey, account));
-        } else if (encoding == UiAccountEncoding::Binary || encoding == UiAccountEncoding::Base58)
-            && account.data.len() > 128
-        {
-            let message = "Encoded binary (base 58) data should be less than 128 bytes, please use Base64 encoding.".to_string();
-            return Err(error::Error {
-                code: error::ErrorCode::InvalidRequest,
-                message,
-                data: None,
-            });
-        } else {
-            response = Some(UiAccount::encode(
-                pubkey, account, encoding, None, data_slice,
-            ));
-        }
+    bank.get_account(pubkey)
+        .map(|account| encode_account(bank, pubkey, account, encoding, data_slice))
+        .transpose()
+}
+
+fn encode_account(
+    bank: &Arc<Bank>,
+    pubkey: &Pubkey,
+    account: Account,
+    encoding: UiAccountEncoding,
+    data_slice: Option<UiDataSliceConfig>,
+) -> Result<UiAccount> {
+    if account.owner == spl_token_id_v2_0() && encoding == UiAccountEncoding::JsonParsed {
+        Ok(get_parsed_token_account(bank.clone(), pubkey, account))
+    } else if (encoding == UiAccountEncoding::Binary || encoding == UiAccountEncoding::Base58)
+        && account.data.len() > 128
+    {
+        let message = "Encoded binary (base 58) data should be less than 128 bytes, please use Base64 encoding.".to_string();
+        Err(error::Error {
+            code: error::ErrorCode::InvalidRequest,
+            message,
+            data: None,
+        })
+    } else {
+        Ok(UiAccount::encode(
+            pubkey, account, encoding, None, data_slice,
+        ))
     }
-    Ok(response)
 }
 
 /// Use a set of filters to get an iterator of keyed program accounts from a bank
-fn get_filtered_program_accounts(
-    bank: &Arc<Bank>,
+/// If the filters select SPL Token accounts by a fixed owner, return that owner
+fn get_spl_token_owner_filter(program_id: &Pubkey, filters: &[RpcFilterType]) -> Option<Pubkey> {
+    get_spl_token_pubkey_filter(program_id, filters, SPL_TOKEN_ACCOUNT_OWNER_OFFSET)
+}
+
+/// If the filters select SPL Token accounts by a fixed mint, return that mint
+fn get_spl_token_mint_filter(program_id: &Pubkey, filters: &[RpcFilterType]) -> Option<Pubkey> {
+    get_spl_token_pubkey_filter(program_id, filters, SPL_TOKEN_ACCOUNT_MINT_OFFSET)
+}
+
+fn get_spl_token_pubkey_filter(
     program_id: &Pubkey,
-    filters: Vec<RpcFilterType>,
-) -> impl Iterator<Item = (Pubkey, Account)> {
-    bank.get_program_accounts(&program_id)
-        .into_iter()
-        .filter(move |(_, account)| {
-            filters.iter().all(|filter_type| match filter_type {
-                RpcFilterType::DataSize(size) => account.data.len() as u64 == *size,
-                RpcFilterType::Memcmp(compare) => compare.bytes_match(&account.data),
-            })
-        })
+    filters: &[RpcFilterType],
+    offset: usize,
+) -> Option<Pubkey> {
+    if program_id != &spl_token_id_v2_0()
+        || !filters
+            .iter()
+            .any(|filter| filter == &RpcFilterType::DataSize(TokenAccount::get_packed_len() as u64))
+    {
+        return None;
+    }
+    filters.iter().find_map(|filter| match filter {
+        RpcFilterType::Memcmp(compare) if compare.offset == offset => compare
+            .decode_bytes()
+            .ok()
+            .filter(|bytes| bytes.len() == std::mem::size_of::<Pubkey>())
+            .map(|bytes| Pubkey::new(&bytes)),
+        _ => None,
+    })
+}
+
+/// Check an account against a set of filters, all of which must pass. Without a parent bank to
+/// compare with, no account passes an `OwnerChanged` filter, see `check_owner_changed_filter()`
+pub(crate) fn filters_allow(
+    bank: &Bank,
+    pubkey: &Pubkey,
+    account: &Account,
+    filters: &[RpcFilterType],
+) -> bool {
+    // Only look up the previous owner when a filter needs it
+    let previous_owner = if filters.contains(&RpcFilterType::OwnerChanged) {
+        match bank.parent() {
+            Some(parent) => parent.get_account(pubkey).map(|account| account.owner),
+            None => return false,
+        }
+    } else {
+        None
+    };
+    filters
+        .iter()
+        .all(|filter| filter.allows(account, previous_owner.as_ref()))
 }
 
 pub(crate) fn get_parsed_token_account(
@@ -2332,12 +2541,20 @@ impl RpcSol for RpcSolImpl {
                 .preflight_commitment
                 .map(|commitment| CommitmentConfig { commitment });
             let preflight_bank = &*meta.bank(preflight_commitment);
-            if let (Err(err), logs) = preflight_bank.simulate_transaction(transaction.clone()) {
+            if let TransactionSimulationResult {
+                result: Err(err),
+                logs,
+                ..
+            } = preflight_bank.simulate_transaction(transaction.clone())
+            {
                 return Err(RpcCustomError::SendTransactionPreflightFailure {
                     message: format!("Transaction simulation failed: {}", err),
                     result: RpcSimulateTransactionResult {
                         err: Some(err),
                         logs: Some(logs),
+                        accounts: None,
+                        units_consumed: None,
+                        inner_instructions: None,
                     },
                 }
                 .into());
@@ -2356,22 +2573,103 @@ impl RpcSol for RpcSolImpl {
         debug!("simulate_transaction rpc request received");
         let config = config.unwrap_or_default();
         let encoding = config.encoding.unwrap_or(UiTransactionEncoding::Base58);
-        let (_, transaction) = deserialize_transaction(data, encoding)?;
+        let (_, mut transaction) = deserialize_transaction(data, encoding)?;
 
         if config.sig_verify {
+            if config.replace_recent_blockhash {
+                return Err(Error::invalid_params(
+                    "sigVerify may not be used with replaceRecentBlockhash",
+                ));
+            }
             if let Err(e) = verify_transaction(&transaction) {
                 return Err(e);
             }
         }
 
-        let bank = &*meta.bank(config.commitment);
-        let (result, logs) = bank.simulate_transaction(transaction);
+        let bank = meta.bank(config.commitment);
+        if config.replace_recent_blockhash {
+            transaction.message.recent_blockhash = bank.last_blockhash();
+        }
+
+        let accounts_config = config.accounts;
+        let account_keys = match &accounts_config {
+            Some(accounts_config) => {
+                if accounts_config.addresses.len() > MAX_MULTIPLE_ACCOUNTS {
+                    return Err(Error::invalid_params(format!(
+                        "Too many accounts provided; max {}",
+                        MAX_MULTIPLE_ACCOUNTS
+                    )));
+                }
+                let mut account_keys = vec![];
+                for address in &accounts_config.addresses {
+                    account_keys.push(verify_pubkey(address.clone())?);
+                }
+                account_keys
+            }
+            None => vec![],
+        };
+
+        let TransactionSimulationResult {
+            result,
+            logs,
+            post_simulation_accounts,
+            units_consumed,
+            inner_instructions,
+        } = bank.simulate_transaction(transaction);
+
+        let accounts = if let Some(accounts_config) = accounts_config {
+            let encoding = accounts_config
+                .encoding
+                .unwrap_or(UiAccountEncoding::Base64);
+
+            let mut accounts = vec![];
+            for pubkey in &account_keys {
+                // Post-simulation state is only meaningful if the transaction succeeded
+                let account = if result.is_err() {
+                    None
+                } else {
+                    post_simulation_accounts
+                        .iter()
+                        .find(|(key, _)| key == pubkey)
+                        .map(|(_, account)| account.clone())
+                        .or_else(|| bank.get_account(pubkey))
+                };
+                accounts.push(
+                    account
+                        .map(|account| {
+                            encode_account(&bank, pubkey, account, encoding.clone(), None)
+                        })
+                        .transpose()?,
+                );
+            }
+            Some(accounts)
+        } else {
+            None
+        };
+
+        let inner_instructions = inner_instructions.map(|inner_instructions| {
+            inner_instructions
+                .into_iter()
+                .enumerate()
+                .filter(|(_, instructions)| !instructions.is_empty())
+                .map(|(index, instructions)| {
+                    InnerInstructions {
+                        index: index as u8,
+                        instructions,
+                    }
+                    .into()
+                })
+                .collect()
+        });
 
         Ok(new_response(
             &bank,
             RpcSimulateTransactionResult {
                 err: result.err(),
                 logs: Some(logs),
+                accounts,
+                units_consumed: Some(units_consumed),
+                inner_instructions,
             },
         ))
     }
@@ -2721,7 +3019,10 @@ pub mod tests {
         futures::future::Future, ErrorCode, MetaIoHandler, Output, Response, Value,
     };
     use jsonrpc_core_client::transports::local;
-    use solana_client::rpc_filter::{Memcmp, MemcmpEncodedBytes};
+    use solana_client::{
+        rpc_custom_error::JSON_RPC_SERVER_ERROR_ACCOUNT_HISTORY_NOT_AVAILABLE,
+        rpc_filter::{Memcmp, MemcmpEncodedBytes},
+    };
     use solana_ledger::{
         blockstore_meta::PerfSample,
         blockstore_processor::fill_blockstore_slot_with_ticks,
@@ -2879,6 +3180,7 @@ pub mod tests {
         let (meta, receiver) = JsonRpcRequestProcessor::new(
             JsonRpcConfig {
                 enable_rpc_transaction_history: true,
+                enable_rpc_account_history: true,
                 identity_pubkey: *pubkey,
                 ..JsonRpcConfig::default()
             },
@@ -3472,6 +3774,120 @@ pub mod tests {
         result["error"].as_object().unwrap();
     }
 
+    #[test]
+    fn test_rpc_get_account_info_at_slot() {
+        let bob_pubkey = solana_sdk::pubkey::new_rand();
+        let roots = vec![1, 2, 3, 4, 5];
+        let RpcHandler { io, meta, .. } =
+            start_rpc_handler_with_tx_and_blockstore(&bob_pubkey, roots);
+
+        let address = solana_sdk::pubkey::new_rand();
+        let created_address = solana_sdk::pubkey::new_rand();
+        meta.blockstore
+            .write_account_history(
+                2,
+                &[(
+                    address,
+                    Account::new(42, 0, &Pubkey::default()),
+                    Hash::default(),
+                )],
+            )
+            .unwrap();
+        meta.blockstore.write_account_history(3, &[]).unwrap();
+        meta.blockstore
+            .write_account_history(
+                4,
+                &[
+                    (address, Account::default(), Hash::default()),
+                    (
+                        created_address,
+                        Account::new(7, 0, &Pubkey::default()),
+                        Hash::default(),
+                    ),
+                ],
+            )
+            .unwrap();
+
+        let get_account_info_at_slot = |address: &Pubkey, slot: Slot| {
+            let req = format!(
+                r#"{{"jsonrpc":"2.0","id":1,"method":"getAccountInfo","params":["{}", {{"atSlot":{}}}]}}"#,
+                address, slot
+            );
+            let res = io.handle_request_sync(&req, meta.clone());
+            let result: Value = serde_json::from_str(&res.expect("actual response"))
+                .expect("actual response deserialization");
+            result
+        };
+
+        let result = get_account_info_at_slot(&address, 3);
+        assert_eq!(result["result"]["context"]["slot"], 3);
+        assert_eq!(result["result"]["value"]["lamports"], 42);
+        // The write at slot 4 removed the account
+        let result = get_account_info_at_slot(&address, 4);
+        assert_eq!(result["result"]["value"], Value::Null);
+
+        // Accounts never written do not exist
+        let result = get_account_info_at_slot(&solana_sdk::pubkey::new_rand(), 3);
+        assert_eq!(result["result"]["value"], Value::Null);
+        // Accounts first written after the slot may have existed before the history started
+        let result = get_account_info_at_slot(&created_address, 3);
+        assert_eq!(
+            result["error"]["code"],
+            JSON_RPC_SERVER_ERROR_ACCOUNT_HISTORY_NOT_AVAILABLE
+        );
+
+        // Slots before the history started, or past the root, have no history
+        for slot in &[1, 6] {
+            let result = get_account_info_at_slot(&address, *slot);
+            assert_eq!(
+                result["error"]["code"],
+                JSON_RPC_SERVER_ERROR_ACCOUNT_HISTORY_NOT_AVAILABLE
+            );
+        }
+
+        // Other account methods do not serve past account states
+        let req = format!(
+            r#"{{"jsonrpc":"2.0","id":1,"method":"getProgramAccounts","params":["{}", {{"atSlot":3}}]}}"#,
+            Pubkey::default()
+        );
+        let res = io.handle_request_sync(&req, meta);
+        let result: Value = serde_json::from_str(&res.expect("actual response"))
+            .expect("actual response deserialization");
+        assert_eq!(result["error"]["code"], -32602);
+    }
+    #[test]
+    fn test_rpc_get_account_info_at_slot_from_root_bank() {
+        let GenesisConfigInfo { genesis_config, .. } = create_genesis_config(100);
+        let bank0 = Arc::new(Bank::new(&genesis_config));
+        // Eager rent collection of the following slots does not rewrite this address
+        let address = Pubkey::new(&[0; 32]);
+        let bank1 = Arc::new(Bank::new_from_parent(&bank0, &Pubkey::default(), 1));
+        bank1.store_account(&address, &Account::new(42, 0, &Pubkey::default()));
+        let bank2 = Arc::new(Bank::new_from_parent(&bank1, &Pubkey::default(), 2));
+        let bank3 = Arc::new(Bank::new_from_parent(&bank2, &Pubkey::default(), 3));
+        let mut meta = JsonRpcRequestProcessor::new_from_bank(&bank3);
+        meta.config.enable_rpc_account_history = true;
+        meta.blockstore.write_account_history(2, &[]).unwrap();
+
+        let get_lamports_at_slot = |slot: Slot| {
+            meta.get_account_info(
+                &address,
+                Some(RpcAccountInfoConfig {
+                    at_slot: Some(slot),
+                    ..RpcAccountInfoConfig::default()
+                }),
+            )
+            .map(|response| response.value.map(|account| account.lamports))
+        };
+        // Not written since the history started at slot 2, so as the root bank has it
+        assert_eq!(get_lamports_at_slot(2).unwrap(), Some(42));
+        assert!(get_lamports_at_slot(1).is_err());
+
+        // Once written after the slot, the account as of the slot is unknown
+        bank3.store_account(&address, &Account::new(43, 0, &Pubkey::default()));
+        assert!(get_lamports_at_slot(2).is_err());
+    }
+
     #[test]
     fn test_rpc_get_multiple_accounts() {
         let bob_pubkey = solana_sdk::pubkey::new_rand();
@@ -3818,10 +4234,16 @@ pub mod tests {
             "jsonrpc": "2.0",
             "result": {
                 "context":{"slot":0},
-                "value":{"err":null, "logs":[
-                    "Program 11111111111111111111111111111111 invoke [1]",
-                    "Program 11111111111111111111111111111111 success"
-                ]}
+                "value":{
+                    "accounts": null,
+                    "err":null,
+                    "innerInstructions": [],
+                    "logs":[
+                        "Program 11111111111111111111111111111111 invoke [1]",
+                        "Program 11111111111111111111111111111111 success"
+                    ],
+                    "unitsConsumed": [0],
+                }
             },
             "id": 1,
         });
@@ -3862,10 +4284,16 @@ pub mod tests {
             "jsonrpc": "2.0",
             "result": {
                 "context":{"slot":0},
-                "value":{"err":null, "logs":[
-                    "Program 11111111111111111111111111111111 invoke [1]",
-                    "Program 11111111111111111111111111111111 success"
-                ]}
+                "value":{
+                    "accounts": null,
+                    "err":null,
+                    "innerInstructions": [],
+                    "logs":[
+                        "Program 11111111111111111111111111111111 invoke [1]",
+                        "Program 11111111111111111111111111111111 success"
+                    ],
+                    "unitsConsumed": [0],
+                }
             },
             "id": 1,
         });
@@ -3880,15 +4308,56 @@ pub mod tests {
             r#"{{"jsonrpc":"2.0","id":1,"method":"simulateTransaction","params":["{}"]}}"#,
             tx_serialized_encoded,
         );
-        let res = io.handle_request_sync(&req, meta);
+        let res = io.handle_request_sync(&req, meta.clone());
+        let expected = json!({
+            "jsonrpc": "2.0",
+            "result": {
+                "context":{"slot":0},
+                "value":{
+                    "accounts": null,
+                    "err":null,
+                    "innerInstructions": [],
+                    "logs":[
+                        "Program 11111111111111111111111111111111 invoke [1]",
+                        "Program 11111111111111111111111111111111 success"
+                    ],
+                    "unitsConsumed": [0],
+                }
+            },
+            "id": 1,
+        });
+        let expected: Response =
+            serde_json::from_value(expected).expect("expected response deserialization");
+        let result: Response = serde_json::from_str(&res.expect("actual response"))
+            .expect("actual response deserialization");
+        assert_eq!(expected, result);
+
+        // Post-simulation state of requested accounts
+        let req = format!(
+            r#"{{"jsonrpc":"2.0","id":1,"method":"simulateTransaction","params":["{}", {{"accounts": {{"encoding": "base64", "addresses": ["{}"]}}}}]}}"#,
+            tx_serialized_encoded, bob_pubkey,
+        );
+        let res = io.handle_request_sync(&req, meta.clone());
         let expected = json!({
             "jsonrpc": "2.0",
             "result": {
                 "context":{"slot":0},
-                "value":{"err":null, "logs":[
-                    "Program 11111111111111111111111111111111 invoke [1]",
-                    "Program 11111111111111111111111111111111 success"
-                ]}
+                "value":{
+                    "accounts": [{
+                        "data": ["", "base64"],
+                        "executable": false,
+                        "lamports": 1254,
+                        "owner": "11111111111111111111111111111111",
+                        "rentEpoch": 0,
+                    }],
+                    "err":null,
+                    "innerInstructions": [],
+                    "logs":[
+                        "Program 11111111111111111111111111111111 invoke [1]",
+                        "Program 11111111111111111111111111111111 success"
+                    ],
+                    "unitsConsumed": [0],
+                }
             },
             "id": 1,
         });
@@ -3897,6 +4366,39 @@ pub mod tests {
         let result: Response = serde_json::from_str(&res.expect("actual response"))
             .expect("actual response deserialization");
         assert_eq!(expected, result);
+        assert_eq!(bank.get_balance(&bob_pubkey), 20);
+
+        // Unknown blockhash, replaced with replaceRecentBlockhash=true
+        let tx = system_transaction::transfer(&alice, &bob_pubkey, 1234, Hash::default());
+        let tx_bad_blockhash_serialized_encoded =
+            bs58::encode(serialize(&tx).unwrap()).into_string();
+        let req = format!(
+            r#"{{"jsonrpc":"2.0","id":1,"method":"simulateTransaction","params":["{}"]}}"#,
+            tx_bad_blockhash_serialized_encoded,
+        );
+        let res = io.handle_request_sync(&req, meta.clone());
+        let result: Value = serde_json::from_str(&res.expect("actual response"))
+            .expect("actual response deserialization");
+        assert_eq!(result["result"]["value"]["err"], json!("BlockhashNotFound"));
+
+        let req = format!(
+            r#"{{"jsonrpc":"2.0","id":1,"method":"simulateTransaction","params":["{}", {{"replaceRecentBlockhash": true}}]}}"#,
+            tx_bad_blockhash_serialized_encoded,
+        );
+        let res = io.handle_request_sync(&req, meta.clone());
+        let result: Value = serde_json::from_str(&res.expect("actual response"))
+            .expect("actual response deserialization");
+        assert_eq!(result["result"]["value"]["err"], Value::Null);
+
+        // replaceRecentBlockhash=true is not compatible with sigVerify=true
+        let req = format!(
+            r#"{{"jsonrpc":"2.0","id":1,"method":"simulateTransaction","params":["{}", {{"sigVerify": true, "replaceRecentBlockhash": true}}]}}"#,
+            tx_serialized_encoded,
+        );
+        let res = io.handle_request_sync(&req, meta);
+        let result: Value = serde_json::from_str(&res.expect("actual response"))
+            .expect("actual response deserialization");
+        assert_eq!(result["error"]["code"], json!(-32602));
     }
 
     #[test]
@@ -4312,7 +4814,7 @@ pub mod tests {
         assert_eq!(
             res,
             Some(
-                r#"{"jsonrpc":"2.0","error":{"code":-32002,"message":"Transaction simulation failed: Blockhash not found","data":{"err":"BlockhashNotFound","logs":[]}},"id":1}"#.to_string(),
+                r#"{"jsonrpc":"2.0","error":{"code":-32002,"message":"Transaction simulation failed: Blockhash not found","data":{"accounts":null,"err":"BlockhashNotFound","innerInstructions":null,"logs":[],"unitsConsumed":null}},"id":1}"#.to_string(),
             )
         );
 
@@ -4328,7 +4830,7 @@ pub mod tests {
         assert_eq!(
             res,
             Some(
-                r#"{"jsonrpc":"2.0","error":{"code":-32002,"message":"Transaction simulation failed: Transaction failed to sanitize accounts offsets correctly","data":{"err":"SanitizeFailure","logs":[]}},"id":1}"#.to_string(),
+                r#"{"jsonrpc":"2.0","error":{"code":-32002,"message":"Transaction simulation failed: Transaction failed to sanitize accounts offsets correctly","data":{"accounts":null,"err":"SanitizeFailure","innerInstructions":null,"logs":[],"unitsConsumed":null}},"id":1}"#.to_string(),
             )
         );
         let mut bad_transaction = system_transaction::transfer(
@@ -4417,6 +4919,86 @@ pub mod tests {
         assert!(verify_filter(&filter).is_err());
     }
 
+    #[test]
+    fn test_owner_changed_filter() {
+        let GenesisConfigInfo {
+            genesis_config,
+            mint_keypair,
+            ..
+        } = create_genesis_config(100);
+        let bank0 = Arc::new(Bank::new(&genesis_config));
+        let filters = vec![RpcFilterType::OwnerChanged];
+        let mint = mint_keypair.pubkey();
+        let account = bank0.get_account(&mint).unwrap();
+
+        // Without a parent there is nothing to compare with
+        assert!(check_owner_changed_filter(&bank0, &filters).is_err());
+        assert!(check_owner_changed_filter(&bank0, &[]).is_ok());
+        assert!(!filters_allow(&bank0, &mint, &account, &filters));
+
+        let bank1 = Bank::new_from_parent(&bank0, &Pubkey::default(), 1);
+        assert!(check_owner_changed_filter(&bank1, &filters).is_ok());
+        assert!(!filters_allow(&bank1, &mint, &account, &filters));
+
+        let mut reassigned = account.clone();
+        reassigned.owner = solana_sdk::pubkey::new_rand();
+        assert!(filters_allow(&bank1, &mint, &reassigned, &filters));
+
+        // New accounts are reported, even when owned by the system program
+        assert!(filters_allow(
+            &bank1,
+            &solana_sdk::pubkey::new_rand(),
+            &account,
+            &filters
+        ));
+    }
+
+    #[test]
+    fn test_get_spl_token_owner_and_mint_filters() {
+        let owner = solana_sdk::pubkey::new_rand();
+        let mint = solana_sdk::pubkey::new_rand();
+        let memcmp = |offset, pubkey: &Pubkey| {
+            RpcFilterType::Memcmp(Memcmp {
+                offset,
+                bytes: MemcmpEncodedBytes::Binary(pubkey.to_string()),
+                encoding: None,
+            })
+        };
+        let data_size = RpcFilterType::DataSize(TokenAccount::get_packed_len() as u64);
+
+        let filters = vec![memcmp(32, &owner), data_size.clone()];
+        assert_eq!(
+            get_spl_token_owner_filter(&spl_token_id_v2_0(), &filters),
+            Some(owner)
+        );
+        assert_eq!(
+            get_spl_token_mint_filter(&spl_token_id_v2_0(), &filters),
+            None
+        );
+
+        let filters = vec![memcmp(0, &mint), data_size.clone()];
+        assert_eq!(
+            get_spl_token_mint_filter(&spl_token_id_v2_0(), &filters),
+            Some(mint)
+        );
+        assert_eq!(
+            get_spl_token_owner_filter(&spl_token_id_v2_0(), &filters),
+            None
+        );
+
+        // Only SPL Token accounts of the packed account size qualify
+        let filters = vec![memcmp(32, &owner), data_size];
+        assert_eq!(
+            get_spl_token_owner_filter(&solana_sdk::pubkey::new_rand(), &filters),
+            None
+        );
+        let filters = vec![memcmp(32, &owner)];
+        assert_eq!(
+            get_spl_token_owner_filter(&spl_token_id_v2_0(), &filters),
+            None
+        );
+    }
+
     #[test]
     fn test_rpc_verify_pubkey() {
         let pubkey = solana_sdk::pubkey::new_rand();
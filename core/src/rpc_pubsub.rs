@@ -1,15 +1,19 @@
 //! The `pubsub` module implements a threaded subscription service on client RPC request
 
-use crate::rpc_subscriptions::{RpcSubscriptions, RpcVote};
+use crate::{
+    rpc::{check_at_slot_unsupported, verify_filter},
+    rpc_subscriptions::{RpcSubscriptions, RpcVote},
+};
 use jsonrpc_core::{Error, ErrorCode, Result};
 use jsonrpc_derive::rpc;
 use jsonrpc_pubsub::{typed::Subscriber, Session, SubscriptionId};
 use solana_account_decoder::UiAccount;
 use solana_client::{
     rpc_config::{
-        RpcAccountInfoConfig, RpcProgramAccountsConfig, RpcSignatureSubscribeConfig,
+        RpcAccountSubscribeConfig, RpcProgramAccountsConfig, RpcSignatureSubscribeConfig,
         RpcTransactionLogsConfig, RpcTransactionLogsFilter,
     },
+    rpc_filter::RpcFilterType,
     rpc_response::{
         Response as RpcResponse, RpcKeyedAccount, RpcLogsResponse, RpcSignatureResult, SlotInfo,
     },
@@ -44,7 +48,7 @@ pub trait RpcSolPubSub {
         meta: Self::Metadata,
         subscriber: Subscriber<RpcResponse<UiAccount>>,
         pubkey_str: String,
-        config: Option<RpcAccountInfoConfig>,
+        config: Option<RpcAccountSubscribeConfig>,
     );
 
     // Unsubscribe from account notification subscription.
@@ -189,6 +193,10 @@ fn param<T: FromStr>(param_str: &str, thing: &str) -> Result<T> {
     })
 }
 
+fn verify_filters(filters: Option<&Vec<RpcFilterType>>) -> Result<()> {
+    filters.into_iter().flatten().try_for_each(verify_filter)
+}
+
 impl RpcSolPubSub for RpcSolPubSubImpl {
     type Metadata = Arc<Session>;
 
@@ -197,9 +205,17 @@ impl RpcSolPubSub for RpcSolPubSubImpl {
         _meta: Self::Metadata,
         subscriber: Subscriber<RpcResponse<UiAccount>>,
         pubkey_str: String,
-        config: Option<RpcAccountInfoConfig>,
+        config: Option<RpcAccountSubscribeConfig>,
     ) {
-        match param::<Pubkey>(&pubkey_str, "pubkey") {
+        let filters = config.as_ref().and_then(|config| config.filters.as_ref());
+        let at_slot = config
+            .as_ref()
+            .and_then(|config| config.account_config.at_slot);
+        match param::<Pubkey>(&pubkey_str, "pubkey").and_then(|pubkey| {
+            verify_filters(filters)?;
+            check_at_slot_unsupported(at_slot)?;
+            Ok(pubkey)
+        }) {
             Ok(pubkey) => {
                 let id = self.uid.fetch_add(1, atomic::Ordering::Relaxed);
                 let sub_id = SubscriptionId::Number(id as u64);
@@ -235,7 +251,15 @@ impl RpcSolPubSub for RpcSolPubSubImpl {
         pubkey_str: String,
         config: Option<RpcProgramAccountsConfig>,
     ) {
-        match param::<Pubkey>(&pubkey_str, "pubkey") {
+        let filters = config.as_ref().and_then(|config| config.filters.as_ref());
+        let at_slot = config
+            .as_ref()
+            .and_then(|config| config.account_config.at_slot);
+        match param::<Pubkey>(&pubkey_str, "pubkey").and_then(|pubkey| {
+            verify_filters(filters)?;
+            check_at_slot_unsupported(at_slot)?;
+            Ok(pubkey)
+        }) {
             Ok(pubkey) => {
                 let id = self.uid.fetch_add(1, atomic::Ordering::Relaxed);
                 let sub_id = SubscriptionId::Number(id as u64);
@@ -446,7 +470,10 @@ mod tests {
     use jsonrpc_pubsub::{PubSubHandler, Session};
     use serial_test_derive::serial;
     use solana_account_decoder::{parse_account_data::parse_account_data, UiAccountEncoding};
-    use solana_client::rpc_response::{ProcessedSignatureResult, ReceivedSignatureResult};
+    use solana_client::{
+        rpc_config::RpcAccountInfoConfig,
+        rpc_response::{ProcessedSignatureResult, ReceivedSignatureResult},
+    };
     use solana_runtime::{
         bank::Bank,
         bank_forks::BankForks,
@@ -672,10 +699,14 @@ mod tests {
             session,
             subscriber,
             stake_account.pubkey().to_string(),
-            Some(RpcAccountInfoConfig {
-                commitment: Some(CommitmentConfig::recent()),
-                encoding: None,
-                data_slice: None,
+            Some(RpcAccountSubscribeConfig {
+                filters: None,
+                account_config: RpcAccountInfoConfig {
+                    commitment: Some(CommitmentConfig::recent()),
+                    encoding: None,
+                    data_slice: None,
+                    at_slot: None,
+                },
             }),
         );
 
@@ -781,10 +812,14 @@ mod tests {
             session,
             subscriber,
             nonce_account.pubkey().to_string(),
-            Some(RpcAccountInfoConfig {
-                commitment: Some(CommitmentConfig::recent()),
-                encoding: Some(UiAccountEncoding::JsonParsed),
-                data_slice: None,
+            Some(RpcAccountSubscribeConfig {
+                filters: None,
+                account_config: RpcAccountInfoConfig {
+                    commitment: Some(CommitmentConfig::recent()),
+                    encoding: Some(UiAccountEncoding::JsonParsed),
+                    data_slice: None,
+                    at_slot: None,
+                },
             }),
         );
 
@@ -875,6 +910,31 @@ mod tests {
         assert_eq!(expected, result);
     }
 
+    #[test]
+    fn test_account_subscribe_at_slot() {
+        let bob_pubkey = solana_sdk::pubkey::new_rand();
+        let session = create_session();
+        let GenesisConfigInfo { genesis_config, .. } = create_genesis_config(10_000);
+        let bank_forks = Arc::new(RwLock::new(BankForks::new(Bank::new(&genesis_config))));
+
+        let mut io = PubSubHandler::default();
+        let rpc = RpcSolPubSubImpl::default_with_bank_forks(bank_forks);
+
+        io.extend_with(rpc.to_delegate());
+
+        // Notifications are always of the latest account state
+        let req = format!(
+            r#"{{"jsonrpc":"2.0","id":1,"method":"accountSubscribe","params":["{}", {{"atSlot":1}}]}}"#,
+            bob_pubkey.to_string()
+        );
+        let res = io.handle_request_sync(&req, session);
+        let expected = r#"{"jsonrpc":"2.0","error":{"code":-32602,"message":"Invalid param: atSlot is not supported by this method"},"id":1}"#;
+        let expected: Response = serde_json::from_str(&expected).unwrap();
+
+        let result: Response = serde_json::from_str(&res.unwrap()).unwrap();
+        assert_eq!(expected, result);
+    }
+
     #[test]
     #[should_panic]
     fn test_account_commitment_not_fulfilled() {
@@ -903,10 +963,14 @@ mod tests {
             session,
             subscriber,
             bob.pubkey().to_string(),
-            Some(RpcAccountInfoConfig {
-                commitment: Some(CommitmentConfig::root()),
-                encoding: None,
-                data_slice: None,
+            Some(RpcAccountSubscribeConfig {
+                filters: None,
+                account_config: RpcAccountInfoConfig {
+                    commitment: Some(CommitmentConfig::root()),
+                    encoding: None,
+                    data_slice: None,
+                    at_slot: None,
+                },
             }),
         );
 
@@ -957,10 +1021,14 @@ mod tests {
             session,
             subscriber,
             bob.pubkey().to_string(),
-            Some(RpcAccountInfoConfig {
-                commitment: Some(CommitmentConfig::root()),
-                encoding: None,
-                data_slice: None,
+            Some(RpcAccountSubscribeConfig {
+                filters: None,
+                account_config: RpcAccountInfoConfig {
+                    commitment: Some(CommitmentConfig::root()),
+                    encoding: None,
+                    data_slice: None,
+                    at_slot: None,
+                },
             }),
         );
 
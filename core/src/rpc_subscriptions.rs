@@ -2,7 +2,7 @@
 
 use crate::{
     optimistically_confirmed_bank_tracker::OptimisticallyConfirmedBank,
-    rpc::{get_parsed_token_account, get_parsed_token_accounts},
+    rpc::{filters_allow, get_parsed_token_account, get_parsed_token_accounts},
 };
 use core::hash::Hash;
 use jsonrpc_core::futures::Future;
@@ -13,7 +13,9 @@ use jsonrpc_pubsub::{
 use serde::Serialize;
 use solana_account_decoder::{parse_token::spl_token_id_v2_0, UiAccount, UiAccountEncoding};
 use solana_client::{
-    rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig, RpcSignatureSubscribeConfig},
+    rpc_config::{
+        RpcAccountSubscribeConfig, RpcProgramAccountsConfig, RpcSignatureSubscribeConfig,
+    },
     rpc_filter::RpcFilterType,
     rpc_response::{
         ProcessedSignatureResult, ReceivedSignatureResult, Response, RpcKeyedAccount,
@@ -117,15 +119,12 @@ struct SubscriptionData<S, T> {
     config: Option<T>,
 }
 #[derive(Default, Clone)]
-struct ProgramConfig {
+struct AccountConfig {
     filters: Vec<RpcFilterType>,
     encoding: Option<UiAccountEncoding>,
 }
 type RpcAccountSubscriptions = RwLock<
-    HashMap<
-        Pubkey,
-        HashMap<SubscriptionId, SubscriptionData<Response<UiAccount>, UiAccountEncoding>>,
-    >,
+    HashMap<Pubkey, HashMap<SubscriptionId, SubscriptionData<Response<UiAccount>, AccountConfig>>>,
 >;
 type RpcLogsSubscriptions = RwLock<
     HashMap<
@@ -136,7 +135,7 @@ type RpcLogsSubscriptions = RwLock<
 type RpcProgramSubscriptions = RwLock<
     HashMap<
         Pubkey,
-        HashMap<SubscriptionId, SubscriptionData<Response<RpcKeyedAccount>, ProgramConfig>>,
+        HashMap<SubscriptionId, SubscriptionData<Response<RpcKeyedAccount>, AccountConfig>>,
     >,
 >;
 type RpcSignatureSubscriptions = RwLock<
@@ -278,17 +277,20 @@ fn filter_account_result(
     result: Option<(Account, Slot)>,
     pubkey: &Pubkey,
     last_notified_slot: Slot,
-    encoding: Option<UiAccountEncoding>,
+    config: Option<AccountConfig>,
     bank: Arc<Bank>,
 ) -> (Box<dyn Iterator<Item = UiAccount>>, Slot) {
+    let config = config.unwrap_or_default();
     // If the account is not found, `last_modified_slot` will default to zero and
     // we will notify clients that the account no longer exists if we haven't already
     let (account, last_modified_slot) = result.unwrap_or_default();
 
     // If last_modified_slot < last_notified_slot this means that we last notified for a fork
     // and should notify that the account state has been reverted.
-    let results: Box<dyn Iterator<Item = UiAccount>> = if last_modified_slot != last_notified_slot {
-        let encoding = encoding.unwrap_or(UiAccountEncoding::Binary);
+    let results: Box<dyn Iterator<Item = UiAccount>> = if last_modified_slot != last_notified_slot
+        && filters_allow(&bank, pubkey, &account, &config.filters)
+    {
+        let encoding = config.encoding.unwrap_or(UiAccountEncoding::Binary);
         if account.owner == spl_token_id_v2_0() && encoding == UiAccountEncoding::JsonParsed {
             Box::new(iter::once(get_parsed_token_account(bank, pubkey, account)))
         } else {
@@ -322,19 +324,17 @@ fn filter_program_results(
     accounts: Vec<(Pubkey, Account)>,
     program_id: &Pubkey,
     last_notified_slot: Slot,
-    config: Option<ProgramConfig>,
+    config: Option<AccountConfig>,
     bank: Arc<Bank>,
 ) -> (Box<dyn Iterator<Item = RpcKeyedAccount>>, Slot) {
     let config = config.unwrap_or_default();
     let encoding = config.encoding.unwrap_or(UiAccountEncoding::Binary);
     let filters = config.filters;
     let accounts_is_empty = accounts.is_empty();
-    let keyed_accounts = accounts.into_iter().filter(move |(_, account)| {
-        filters.iter().all(|filter_type| match filter_type {
-            RpcFilterType::DataSize(size) => account.data.len() as u64 == *size,
-            RpcFilterType::Memcmp(compare) => compare.bytes_match(&account.data),
-        })
-    });
+    let filter_bank = bank.clone();
+    let keyed_accounts = accounts
+        .into_iter()
+        .filter(move |(pubkey, account)| filters_allow(&filter_bank, pubkey, account, &filters));
     let accounts: Box<dyn Iterator<Item = RpcKeyedAccount>> = if program_id == &spl_token_id_v2_0()
         && encoding == UiAccountEncoding::JsonParsed
         && !accounts_is_empty
@@ -597,12 +597,13 @@ impl RpcSubscriptions {
     pub fn add_account_subscription(
         &self,
         pubkey: Pubkey,
-        config: Option<RpcAccountInfoConfig>,
+        config: Option<RpcAccountSubscribeConfig>,
         sub_id: SubscriptionId,
         subscriber: Subscriber<Response<UiAccount>>,
     ) {
         let config = config.unwrap_or_default();
         let commitment = config
+            .account_config
             .commitment
             .unwrap_or_else(CommitmentConfig::single_gossip);
 
@@ -654,7 +655,10 @@ impl RpcSubscriptions {
             sub_id,
             subscriber,
             last_notified_slot,
-            config.encoding,
+            Some(AccountConfig {
+                filters: config.filters.unwrap_or_default(),
+                encoding: config.account_config.encoding,
+            }),
         );
     }
 
@@ -701,7 +705,7 @@ impl RpcSubscriptions {
             sub_id,
             subscriber,
             0, // last_notified_slot is not utilized for program subscriptions
-            Some(ProgramConfig {
+            Some(AccountConfig {
                 filters: config.filters.unwrap_or_default(),
                 encoding: config.account_config.encoding,
             }),
@@ -1270,6 +1274,7 @@ pub(crate) mod tests {
     use jsonrpc_core::futures::{self, stream::Stream};
     use jsonrpc_pubsub::typed::Subscriber;
     use serial_test_derive::serial;
+    use solana_client::{rpc_config::RpcAccountInfoConfig, rpc_filter::LamportsRange};
     use solana_runtime::{
         commitment::BlockCommitment,
         genesis_utils::{create_genesis_config, GenesisConfigInfo},
@@ -1341,10 +1346,14 @@ pub(crate) mod tests {
         );
         subscriptions.add_account_subscription(
             alice.pubkey(),
-            Some(RpcAccountInfoConfig {
-                commitment: Some(CommitmentConfig::recent()),
-                encoding: None,
-                data_slice: None,
+            Some(RpcAccountSubscribeConfig {
+                filters: None,
+                account_config: RpcAccountInfoConfig {
+                    commitment: Some(CommitmentConfig::recent()),
+                    encoding: None,
+                    data_slice: None,
+                    at_slot: None,
+                },
             }),
             create_sub_id.clone(),
             create_sub,
@@ -1398,10 +1407,14 @@ pub(crate) mod tests {
 
         subscriptions.add_account_subscription(
             alice.pubkey(),
-            Some(RpcAccountInfoConfig {
-                commitment: Some(CommitmentConfig::recent()),
-                encoding: None,
-                data_slice: None,
+            Some(RpcAccountSubscribeConfig {
+                filters: None,
+                account_config: RpcAccountInfoConfig {
+                    commitment: Some(CommitmentConfig::recent()),
+                    encoding: None,
+                    data_slice: None,
+                    at_slot: None,
+                },
             }),
             close_sub_id.clone(),
             close_sub,
@@ -1451,6 +1464,103 @@ pub(crate) mod tests {
             .contains_key(&alice.pubkey()));
     }
 
+    #[test]
+    #[serial]
+    fn test_check_account_subscribe_with_filters() {
+        let GenesisConfigInfo {
+            genesis_config,
+            mint_keypair,
+            ..
+        } = create_genesis_config(100);
+        let bank = Bank::new(&genesis_config);
+        let blockhash = bank.last_blockhash();
+        let bank_forks = Arc::new(RwLock::new(BankForks::new(bank)));
+        let bank0 = bank_forks.read().unwrap().get(0).unwrap().clone();
+        let bank1 = Bank::new_from_parent(&bank0, &Pubkey::default(), 1);
+        bank_forks.write().unwrap().insert(bank1);
+        let alice = Keypair::new();
+
+        let (subscriber, _id_receiver, transport_receiver) =
+            Subscriber::new_test("accountNotification");
+        let sub_id = SubscriptionId::Number(0 as u64);
+
+        let exit = Arc::new(AtomicBool::new(false));
+        let subscriptions = RpcSubscriptions::new(
+            &exit,
+            bank_forks.clone(),
+            Arc::new(RwLock::new(BlockCommitmentCache::new_for_tests_with_slots(
+                1, 1,
+            ))),
+            OptimisticallyConfirmedBank::locked_from_bank_forks_root(&bank_forks),
+        );
+        subscriptions.add_account_subscription(
+            alice.pubkey(),
+            Some(RpcAccountSubscribeConfig {
+                filters: Some(vec![RpcFilterType::Lamports(LamportsRange {
+                    min: Some(2),
+                    max: None,
+                })]),
+                account_config: RpcAccountInfoConfig {
+                    commitment: Some(CommitmentConfig::recent()),
+                    ..RpcAccountInfoConfig::default()
+                },
+            }),
+            sub_id.clone(),
+            subscriber,
+        );
+
+        // Creating the account with a single lamport does not pass the filter
+        let tx = system_transaction::create_account(
+            &mint_keypair,
+            &alice,
+            blockhash,
+            1,
+            0,
+            &system_program::id(),
+        );
+        bank_forks
+            .write()
+            .unwrap()
+            .get(1)
+            .unwrap()
+            .process_transaction(&tx)
+            .unwrap();
+        let mut commitment_slots = CommitmentSlots::default();
+        commitment_slots.slot = 1;
+        subscriptions.notify_subscribers(commitment_slots);
+
+        // Funding it further does
+        let tx = system_transaction::transfer(&mint_keypair, &alice.pubkey(), 2, blockhash);
+        bank_forks
+            .write()
+            .unwrap()
+            .get(1)
+            .unwrap()
+            .process_transaction(&tx)
+            .unwrap();
+        subscriptions.notify_subscribers(commitment_slots);
+        let (response, _) = robust_poll_or_panic(transport_receiver);
+        let expected = json!({
+           "jsonrpc": "2.0",
+           "method": "accountNotification",
+           "params": {
+               "result": {
+                   "context": { "slot": 1 },
+                   "value": {
+                       "data": "",
+                       "executable": false,
+                       "lamports": 3,
+                       "owner": "11111111111111111111111111111111",
+                       "rentEpoch": 0,
+                    },
+               },
+               "subscription": 0,
+           }
+        });
+        assert_eq!(serde_json::to_string(&expected).unwrap(), response);
+        subscriptions.remove_account_subscription(&sub_id);
+    }
+
     #[test]
     #[serial]
     fn test_check_program_subscribe() {
@@ -1947,10 +2057,14 @@ pub(crate) mod tests {
         let sub_id0 = SubscriptionId::Number(0 as u64);
         subscriptions.add_account_subscription(
             alice.pubkey(),
-            Some(RpcAccountInfoConfig {
-                commitment: Some(CommitmentConfig::single_gossip()),
-                encoding: None,
-                data_slice: None,
+            Some(RpcAccountSubscribeConfig {
+                filters: None,
+                account_config: RpcAccountInfoConfig {
+                    commitment: Some(CommitmentConfig::single_gossip()),
+                    encoding: None,
+                    data_slice: None,
+                    at_slot: None,
+                },
             }),
             sub_id0.clone(),
             subscriber0,
@@ -2028,10 +2142,14 @@ pub(crate) mod tests {
         let sub_id1 = SubscriptionId::Number(1 as u64);
         subscriptions.add_account_subscription(
             alice.pubkey(),
-            Some(RpcAccountInfoConfig {
-                commitment: Some(CommitmentConfig::single_gossip()),
-                encoding: None,
-                data_slice: None,
+            Some(RpcAccountSubscribeConfig {
+                filters: None,
+                account_config: RpcAccountInfoConfig {
+                    commitment: Some(CommitmentConfig::single_gossip()),
+                    encoding: None,
+                    data_slice: None,
+                    at_slot: None,
+                },
             }),
             sub_id1.clone(),
             subscriber1,
@@ -8,19 +8,40 @@
 use crate::sigverify;
 use crossbeam_channel::{SendError, Sender as CrossbeamSender};
 use solana_measure::measure::Measure;
-use solana_metrics::datapoint_debug;
-use solana_perf::packet::Packets;
+use solana_metrics::{datapoint_debug, inc_new_counter_info};
+use solana_perf::packet::{Packet, Packets};
 use solana_perf::perf_libs;
-use solana_sdk::timing;
+use solana_perf::sigverify::packet_compute_price;
+use solana_runtime::bloom::{AtomicBloom, Bloom};
+use solana_sdk::{
+    hash::{hash, Hash},
+    short_vec::decode_len,
+    signature::Signature,
+    timing,
+};
+use solana_streamer::quic::StakedNodes;
 use solana_streamer::streamer::{self, PacketReceiver, StreamerError};
+use std::cmp::Reverse;
+use std::collections::HashSet;
+use std::mem::size_of;
 use std::sync::mpsc::{Receiver, RecvTimeoutError};
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, RwLock};
 use std::thread::{self, Builder, JoinHandle};
+use std::time::{Duration, Instant};
 use thiserror::Error;
 
 const RECV_BATCH_MAX_CPU: usize = 1_000;
 const RECV_BATCH_MAX_GPU: usize = 5_000;
 
+// With admission control, up to this many verification batches worth of packets are received at
+// once, everything beyond one batch is shed
+const ADMISSION_BACKLOG_BATCHES: usize = 4;
+
+const DEDUP_MAX_ITEMS: usize = 1_000_000;
+const DEDUP_FALSE_RATE: f64 = 0.001;
+const DEDUP_MAX_BITS: usize = 16 * 1024 * 1024;
+const DEDUP_RESET_INTERVAL: Duration = Duration::from_secs(2);
+
 #[derive(Error, Debug)]
 pub enum SigVerifyServiceError {
     #[error("send packets batch error")]
@@ -36,6 +57,169 @@ pub struct SigVerifyStage {
     thread_hdls: Vec<JoinHandle<()>>,
 }
 
+#[derive(Default, Debug, PartialEq)]
+struct AdmissionStats {
+    num_duplicates: usize,
+    num_malformed: usize,
+    num_shed_staked: usize,
+    num_shed_unstaked: usize,
+}
+
+struct Deduper {
+    filter: AtomicBloom<Hash>,
+    created: Instant,
+}
+
+impl Deduper {
+    fn new() -> Self {
+        Self {
+            filter: Bloom::random(DEDUP_MAX_ITEMS, DEDUP_FALSE_RATE, DEDUP_MAX_BITS).into(),
+            created: Instant::now(),
+        }
+    }
+}
+
+/// Admission control of transaction packets ahead of signature verification.  Packets that were
+/// admitted recently are dropped, and when more packets are queued than one verification batch
+/// the excess is shed, keeping packets from staked senders first and then the ones paying the
+/// highest price per compute unit.
+///
+/// Senders are matched to stake by IP address, which UDP senders can spoof, so only packets
+/// received over QUIC are ranked by stake.
+pub struct PacketAdmission {
+    staked_nodes: StakedNodes,
+    deduper: RwLock<Deduper>,
+}
+
+impl PacketAdmission {
+    pub fn new(staked_nodes: StakedNodes) -> Self {
+        Self {
+            staked_nodes,
+            deduper: RwLock::new(Deduper::new()),
+        }
+    }
+
+    fn admit(&self, batches: &mut [Packets], max_packets: usize) -> AdmissionStats {
+        let mut stats = AdmissionStats::default();
+        let packet_hashes = self.dedup_packets(batches, &mut stats);
+        self.shed_packets(batches, max_packets, &mut stats);
+        self.record_packets(batches, &packet_hashes);
+        stats
+    }
+
+    /// Discards packets identical to one admitted since the filter was last reset, or to an
+    /// earlier packet of `batches`. Returns the hash of the data of every remaining packet.
+    ///
+    /// Packets are matched on all of their data rather than their signature, which is not
+    /// verified yet, so that a forged packet can't shadow the transaction it copies a
+    /// signature from.
+    fn dedup_packets(
+        &self,
+        batches: &mut [Packets],
+        stats: &mut AdmissionStats,
+    ) -> Vec<(usize, usize, Hash)> {
+        if self.deduper.read().unwrap().created.elapsed() >= DEDUP_RESET_INTERVAL {
+            let mut deduper = self.deduper.write().unwrap();
+            if deduper.created.elapsed() >= DEDUP_RESET_INTERVAL {
+                *deduper = Deduper::new();
+            }
+        }
+        let deduper = self.deduper.read().unwrap();
+        let mut seen = HashSet::new();
+        let mut packet_hashes = vec![];
+        for (batch_index, batch) in batches.iter_mut().enumerate() {
+            for (packet_index, packet) in batch.packets.iter_mut().enumerate() {
+                if packet.meta.discard {
+                    continue;
+                }
+                if first_signature(packet).is_none() {
+                    packet.meta.discard = true;
+                    stats.num_malformed += 1;
+                    continue;
+                }
+                let packet_hash = hash(&packet.data[..packet.meta.size]);
+                if deduper.filter.contains(&packet_hash) || !seen.insert(packet_hash) {
+                    packet.meta.discard = true;
+                    stats.num_duplicates += 1;
+                } else {
+                    packet_hashes.push((batch_index, packet_index, packet_hash));
+                }
+            }
+        }
+        packet_hashes
+    }
+
+    /// Records the packets that were admitted. Shed packets are left out so that their retries
+    /// get another chance.
+    fn record_packets(&self, batches: &[Packets], packet_hashes: &[(usize, usize, Hash)]) {
+        let deduper = self.deduper.read().unwrap();
+        for (batch_index, packet_index, packet_hash) in packet_hashes {
+            if !batches[*batch_index].packets[*packet_index].meta.discard {
+                deduper.filter.add(packet_hash);
+            }
+        }
+    }
+
+    /// Discards all but the `max_packets` highest priority packets
+    fn shed_packets(
+        &self,
+        batches: &mut [Packets],
+        max_packets: usize,
+        stats: &mut AdmissionStats,
+    ) {
+        let num_packets = batches
+            .iter()
+            .flat_map(|batch| batch.packets.iter())
+            .filter(|packet| !packet.meta.discard)
+            .count();
+        if num_packets <= max_packets {
+            return;
+        }
+
+        let staked_nodes = self.staked_nodes.read().unwrap();
+        let mut priorities = vec![];
+        for (batch_index, batch) in batches.iter().enumerate() {
+            for (packet_index, packet) in batch.packets.iter().enumerate() {
+                if packet.meta.discard {
+                    continue;
+                }
+                let staked = packet.meta.from_quic
+                    && staked_nodes
+                        .get(&packet.meta.addr().ip())
+                        .map_or(false, |stake| *stake > 0);
+                priorities.push((
+                    staked,
+                    packet_compute_price(packet),
+                    batch_index,
+                    packet_index,
+                ));
+            }
+        }
+        drop(staked_nodes);
+
+        // The sort is stable, so equally ranked packets are kept in arrival order
+        priorities.sort_by_key(|(staked, compute_price, _, _)| Reverse((*staked, *compute_price)));
+        for (staked, _, batch_index, packet_index) in priorities.into_iter().skip(max_packets) {
+            batches[batch_index].packets[packet_index].meta.discard = true;
+            if staked {
+                stats.num_shed_staked += 1;
+            } else {
+                stats.num_shed_unstaked += 1;
+            }
+        }
+    }
+}
+
+fn first_signature(packet: &Packet) -> Option<Signature> {
+    let data = &packet.data[..packet.meta.size];
+    let (num_signatures, offset) = decode_len(data).ok()?;
+    if num_signatures == 0 {
+        return None;
+    }
+    data.get(offset..offset + size_of::<Signature>())
+        .map(Signature::new)
+}
+
 pub trait SigVerifier {
     fn verify_batch(&self, batch: Vec<Packets>) -> Vec<Packets>;
 }
@@ -58,7 +242,25 @@ impl SigVerifyStage {
         verified_sender: CrossbeamSender<Vec<Packets>>,
         verifier: T,
     ) -> Self {
-        let thread_hdls = Self::verifier_services(packet_receiver, verified_sender, verifier);
+        let thread_hdls = Self::verifier_services(packet_receiver, verified_sender, verifier, None);
+        Self { thread_hdls }
+    }
+
+    /// Like `new`, with `admission` deduplicating and shedding transaction packets before they
+    /// are verified
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new_with_admission<T: SigVerifier + 'static + Send + Clone>(
+        packet_receiver: Receiver<Packets>,
+        verified_sender: CrossbeamSender<Vec<Packets>>,
+        verifier: T,
+        admission: PacketAdmission,
+    ) -> Self {
+        let thread_hdls = Self::verifier_services(
+            packet_receiver,
+            verified_sender,
+            verifier,
+            Some(Arc::new(admission)),
+        );
         Self { thread_hdls }
     }
 
@@ -67,16 +269,33 @@ impl SigVerifyStage {
         sendr: &CrossbeamSender<Vec<Packets>>,
         id: usize,
         verifier: &T,
+        admission: Option<&PacketAdmission>,
     ) -> Result<()> {
-        let (batch, len, recv_time) = streamer::recv_batch(
+        let max_packets = if perf_libs::api().is_some() {
+            RECV_BATCH_MAX_GPU
+        } else {
+            RECV_BATCH_MAX_CPU
+        };
+        let (mut batch, len, recv_time) = streamer::recv_batch(
             &recvr.lock().expect("'recvr' lock in fn verifier"),
-            if perf_libs::api().is_some() {
-                RECV_BATCH_MAX_GPU
+            if admission.is_some() {
+                max_packets * ADMISSION_BACKLOG_BATCHES
             } else {
-                RECV_BATCH_MAX_CPU
+                max_packets
             },
         )?;
 
+        if let Some(admission) = admission {
+            let stats = admission.admit(&mut batch, max_packets);
+            inc_new_counter_info!("sigverify_stage-duplicate_packets", stats.num_duplicates);
+            inc_new_counter_info!("sigverify_stage-malformed_packets", stats.num_malformed);
+            inc_new_counter_info!("sigverify_stage-shed_staked_packets", stats.num_shed_staked);
+            inc_new_counter_info!(
+                "sigverify_stage-shed_unstaked_packets",
+                stats.num_shed_unstaked
+            );
+        }
+
         let mut verify_batch_time = Measure::start("sigverify_batch_time");
         let batch_len = batch.len();
         debug!(
@@ -120,12 +339,19 @@ impl SigVerifyStage {
         verified_sender: CrossbeamSender<Vec<Packets>>,
         id: usize,
         verifier: &T,
+        admission: Option<Arc<PacketAdmission>>,
     ) -> JoinHandle<()> {
         let verifier = verifier.clone();
         Builder::new()
             .name(format!("solana-verifier-{}", id))
             .spawn(move || loop {
-                if let Err(e) = Self::verifier(&packet_receiver, &verified_sender, id, &verifier) {
+                if let Err(e) = Self::verifier(
+                    &packet_receiver,
+                    &verified_sender,
+                    id,
+                    &verifier,
+                    admission.as_deref(),
+                ) {
                     match e {
                         SigVerifyServiceError::StreamerError(StreamerError::RecvTimeoutError(
                             RecvTimeoutError::Disconnected,
@@ -147,11 +373,18 @@ impl SigVerifyStage {
         packet_receiver: PacketReceiver,
         verified_sender: CrossbeamSender<Vec<Packets>>,
         verifier: T,
+        admission: Option<Arc<PacketAdmission>>,
     ) -> Vec<JoinHandle<()>> {
         let receiver = Arc::new(Mutex::new(packet_receiver));
         (0..4)
             .map(|id| {
-                Self::verifier_service(receiver.clone(), verified_sender.clone(), id, &verifier)
+                Self::verifier_service(
+                    receiver.clone(),
+                    verified_sender.clone(),
+                    id,
+                    &verifier,
+                    admission.clone(),
+                )
             })
             .collect()
     }
@@ -163,3 +396,191 @@ impl SigVerifyStage {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::{
+        compute_budget,
+        hash::Hash,
+        signature::{Keypair, Signer},
+        system_instruction,
+        transaction::Transaction,
+    };
+    use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+
+    fn new_packet(lamports_per_unit: Option<u64>, sender: IpAddr) -> Packet {
+        let payer = Keypair::new();
+        let mut instructions = vec![system_instruction::transfer(
+            &payer.pubkey(),
+            &solana_sdk::pubkey::new_rand(),
+            1,
+        )];
+        if let Some(lamports_per_unit) = lamports_per_unit {
+            instructions.push(compute_budget::request_units(200_000, lamports_per_unit));
+        }
+        let tx = Transaction::new_signed_with_payer(
+            &instructions,
+            Some(&payer.pubkey()),
+            &[&payer],
+            Hash::default(),
+        );
+        Packet::from_data(&SocketAddr::new(sender, 8000), tx).unwrap()
+    }
+
+    fn new_quic_packet(lamports_per_unit: Option<u64>, sender: IpAddr) -> Packet {
+        let mut packet = new_packet(lamports_per_unit, sender);
+        packet.meta.from_quic = true;
+        packet
+    }
+
+    fn discards(batches: &[Packets]) -> Vec<bool> {
+        batches
+            .iter()
+            .flat_map(|batch| batch.packets.iter())
+            .map(|packet| packet.meta.discard)
+            .collect()
+    }
+
+    #[test]
+    fn test_dedup_packets() {
+        let sender = IpAddr::V4(Ipv4Addr::LOCALHOST);
+        let packet = new_packet(None, sender);
+        let mut malformed = Packet::default();
+        malformed.meta.size = 1;
+        let mut batches = vec![
+            Packets::new(vec![packet.clone(), new_packet(None, sender)]),
+            Packets::new(vec![packet.clone(), malformed]),
+        ];
+
+        let admission = PacketAdmission::new(StakedNodes::default());
+        let stats = admission.admit(&mut batches, RECV_BATCH_MAX_CPU);
+        assert_eq!(
+            stats,
+            AdmissionStats {
+                num_duplicates: 1,
+                num_malformed: 1,
+                ..AdmissionStats::default()
+            }
+        );
+        assert_eq!(discards(&batches), vec![false, false, true, true]);
+
+        // Seen in an earlier batch
+        let mut batches = vec![Packets::new(vec![packet])];
+        assert_eq!(
+            admission
+                .admit(&mut batches, RECV_BATCH_MAX_CPU)
+                .num_duplicates,
+            1
+        );
+    }
+
+    #[test]
+    fn test_dedup_packets_forged_signature() {
+        let sender = IpAddr::V4(Ipv4Addr::LOCALHOST);
+        let packet = new_packet(None, sender);
+        // Same signature, different transaction
+        let mut forged = new_packet(None, sender);
+        forged.data[..1 + size_of::<Signature>()]
+            .copy_from_slice(&packet.data[..1 + size_of::<Signature>()]);
+
+        let admission = PacketAdmission::new(StakedNodes::default());
+        let mut batches = vec![Packets::new(vec![forged])];
+        assert_eq!(
+            admission.admit(&mut batches, RECV_BATCH_MAX_CPU),
+            AdmissionStats::default()
+        );
+        let mut batches = vec![Packets::new(vec![packet])];
+        assert_eq!(
+            admission.admit(&mut batches, RECV_BATCH_MAX_CPU),
+            AdmissionStats::default()
+        );
+        assert_eq!(discards(&batches), vec![false]);
+    }
+
+    #[test]
+    fn test_dedup_shed_packets_retried() {
+        let sender = IpAddr::V4(Ipv4Addr::LOCALHOST);
+        let paying = new_packet(Some(1), sender);
+        let packet = new_packet(None, sender);
+
+        let admission = PacketAdmission::new(StakedNodes::default());
+        let mut batches = vec![Packets::new(vec![packet.clone(), paying])];
+        assert_eq!(admission.admit(&mut batches, 1).num_shed_unstaked, 1);
+        assert_eq!(discards(&batches), vec![true, false]);
+
+        // The retry of the shed packet is not a duplicate
+        let mut batches = vec![Packets::new(vec![packet])];
+        assert_eq!(admission.admit(&mut batches, 1), AdmissionStats::default());
+        assert_eq!(discards(&batches), vec![false]);
+    }
+
+    #[test]
+    fn test_shed_packets() {
+        let staked = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+        let unstaked = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2));
+        let staked_nodes = StakedNodes::default();
+        staked_nodes.write().unwrap().insert(staked, 42);
+
+        let mut batches = vec![
+            Packets::new(vec![
+                new_packet(None, unstaked),
+                new_packet(Some(5), unstaked),
+            ]),
+            Packets::new(vec![
+                new_packet(Some(1), unstaked),
+                new_quic_packet(None, staked),
+                new_quic_packet(None, staked),
+            ]),
+        ];
+
+        let admission = PacketAdmission::new(staked_nodes);
+        let stats = admission.admit(&mut batches, 3);
+        assert_eq!(
+            stats,
+            AdmissionStats {
+                num_shed_unstaked: 2,
+                ..AdmissionStats::default()
+            }
+        );
+        assert_eq!(discards(&batches), vec![true, false, true, false, false]);
+
+        // Nothing is shed while the packets fit in one verification batch
+        let mut batches = vec![Packets::new(vec![new_packet(None, unstaked)])];
+        assert_eq!(admission.admit(&mut batches, 1), AdmissionStats::default());
+        assert_eq!(discards(&batches), vec![false]);
+
+        // Staked senders are shed last
+        let mut batches = vec![Packets::new(vec![
+            new_quic_packet(None, staked),
+            new_quic_packet(None, staked),
+        ])];
+        let stats = admission.admit(&mut batches, 1);
+        assert_eq!(stats.num_shed_staked, 1);
+        assert_eq!(discards(&batches), vec![false, true]);
+
+        // A staked address is not trusted unless the packet arrived over QUIC
+        let mut batches = vec![Packets::new(vec![
+            new_packet(None, staked),
+            new_packet(Some(1), unstaked),
+        ])];
+        let stats = admission.admit(&mut batches, 1);
+        assert_eq!(stats.num_shed_unstaked, 1);
+        assert_eq!(discards(&batches), vec![true, false]);
+
+        // A high price for zero units pays nothing, so it doesn't outrank a real price
+        let payer = Keypair::new();
+        let zero_units = Transaction::new_signed_with_payer(
+            &[compute_budget::request_units(0, u64::MAX)],
+            Some(&payer.pubkey()),
+            &[&payer],
+            Hash::default(),
+        );
+        let mut batches = vec![Packets::new(vec![
+            Packet::from_data(&SocketAddr::new(unstaked, 8000), zero_units).unwrap(),
+            new_packet(Some(1), unstaked),
+        ])];
+        admission.admit(&mut batches, 1);
+        assert_eq!(discards(&batches), vec![true, false]);
+    }
+}
@@ -1,7 +1,11 @@
-use crate::cluster_info::{ClusterInfo, MAX_SNAPSHOT_HASHES};
-use solana_runtime::{snapshot_package::AccountsPackageReceiver, snapshot_utils};
+use crate::cluster_info::{ClusterInfo, MAX_INCREMENTAL_SNAPSHOT_HASHES, MAX_SNAPSHOT_HASHES};
+use solana_runtime::{
+    snapshot_package::{AccountsPackage, AccountsPackageReceiver, SnapshotType},
+    snapshot_utils,
+};
 use solana_sdk::{clock::Slot, hash::Hash};
 use std::{
+    iter,
     sync::{
         atomic::{AtomicBool, Ordering},
         mpsc::RecvTimeoutError,
@@ -33,29 +37,55 @@ impl SnapshotPackagerService {
                     hashes.push(starting_snapshot_hash);
                 }
                 cluster_info.push_snapshot_hashes(hashes.clone());
+                let mut full_snapshot_hash = starting_snapshot_hash;
+                let mut incremental_hashes = vec![];
                 loop {
                     if exit.load(Ordering::Relaxed) {
                         break;
                     }
 
                     match snapshot_package_receiver.recv_timeout(Duration::from_secs(1)) {
-                        Ok(mut snapshot_package) => {
-                            // Only package the latest
-                            while let Ok(new_snapshot_package) =
-                                snapshot_package_receiver.try_recv()
+                        Ok(snapshot_package) => {
+                            // Only package the latest full snapshot, and the latest incremental
+                            // snapshot that follows it
+                            let mut full_snapshot_package = None;
+                            let mut incremental_snapshot_package = None;
+                            for snapshot_package in iter::once(snapshot_package)
+                                .chain(snapshot_package_receiver.try_iter())
                             {
-                                snapshot_package = new_snapshot_package;
+                                match snapshot_package.snapshot_type {
+                                    SnapshotType::FullSnapshot => {
+                                        full_snapshot_package = Some(snapshot_package);
+                                        incremental_snapshot_package = None;
+                                    }
+                                    SnapshotType::IncrementalSnapshot(_) => {
+                                        incremental_snapshot_package = Some(snapshot_package);
+                                    }
+                                }
                             }
-                            if let Err(err) =
-                                snapshot_utils::archive_snapshot_package(&snapshot_package)
-                            {
-                                warn!("Failed to create snapshot archive: {}", err);
-                            } else {
-                                hashes.push((snapshot_package.root, snapshot_package.hash));
-                                while hashes.len() > MAX_SNAPSHOT_HASHES {
-                                    hashes.remove(0);
+
+                            if let Some(snapshot_package) = full_snapshot_package {
+                                if Self::archive(&snapshot_package) {
+                                    hashes.push((snapshot_package.root, snapshot_package.hash));
+                                    while hashes.len() > MAX_SNAPSHOT_HASHES {
+                                        hashes.remove(0);
+                                    }
+                                    cluster_info.push_snapshot_hashes(hashes.clone());
+                                    full_snapshot_hash =
+                                        Some((snapshot_package.root, snapshot_package.hash));
+                                    incremental_hashes.clear();
+                                }
+                            }
+
+                            if let Some(snapshot_package) = incremental_snapshot_package {
+                                if Self::archive(&snapshot_package) {
+                                    Self::push_incremental_snapshot_hash(
+                                        &cluster_info,
+                                        &snapshot_package,
+                                        full_snapshot_hash,
+                                        &mut incremental_hashes,
+                                    );
                                 }
-                                cluster_info.push_snapshot_hashes(hashes.clone());
                             }
                         }
                         Err(RecvTimeoutError::Disconnected) => break,
@@ -69,6 +99,43 @@ impl SnapshotPackagerService {
         }
     }
 
+    fn archive(snapshot_package: &AccountsPackage) -> bool {
+        if let Err(err) = snapshot_utils::archive_snapshot_package(snapshot_package) {
+            warn!("Failed to create snapshot archive: {}", err);
+            false
+        } else {
+            true
+        }
+    }
+
+    fn push_incremental_snapshot_hash(
+        cluster_info: &ClusterInfo,
+        snapshot_package: &AccountsPackage,
+        full_snapshot_hash: Option<(Slot, Hash)>,
+        incremental_hashes: &mut Vec<(Slot, Hash)>,
+    ) {
+        let full_snapshot_slot = match snapshot_package.snapshot_type {
+            SnapshotType::IncrementalSnapshot(full_snapshot_slot) => full_snapshot_slot,
+            SnapshotType::FullSnapshot => unreachable!(),
+        };
+        // Without the hash of the full snapshot this is based on, peers can't make use of it
+        let base = match full_snapshot_hash {
+            Some(base) if base.0 == full_snapshot_slot => base,
+            _ => {
+                warn!(
+                    "Unknown full snapshot {} for incremental snapshot {}, not advertised",
+                    full_snapshot_slot, snapshot_package.root
+                );
+                return;
+            }
+        };
+        incremental_hashes.push((snapshot_package.root, snapshot_package.hash));
+        while incremental_hashes.len() > MAX_INCREMENTAL_SNAPSHOT_HASHES {
+            incremental_hashes.remove(0);
+        }
+        cluster_info.push_incremental_snapshot_hashes(base, incremental_hashes.clone());
+    }
+
     pub fn join(self) -> thread::Result<()> {
         self.t_snapshot_packager.join()
     }
@@ -175,6 +242,7 @@ mod tests {
             Hash::default(),
             CompressionType::Bzip2,
             SnapshotVersion::default(),
+            SnapshotType::FullSnapshot,
         );
 
         // Make tarball from packageable snapshot
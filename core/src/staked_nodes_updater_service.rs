@@ -0,0 +1,72 @@
+//! The `staked_nodes_updater_service` keeps the stake of every TPU address up to date, so the QUIC
+//! TPU can weight the connection quota of each address by the stake behind it.
+
+use crate::cluster_info::ClusterInfo;
+use solana_ledger::staking_utils;
+use solana_runtime::bank_forks::BankForks;
+use solana_streamer::quic::StakedNodes;
+use std::{
+    collections::HashMap,
+    net::IpAddr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, RwLock,
+    },
+    thread::{self, sleep, Builder, JoinHandle},
+    time::{Duration, Instant},
+};
+
+const UPDATE_INTERVAL: Duration = Duration::from_secs(5);
+const SLEEP_INTERVAL: Duration = Duration::from_millis(100);
+
+pub struct StakedNodesUpdaterService {
+    thread_hdl: JoinHandle<()>,
+}
+
+impl StakedNodesUpdaterService {
+    pub fn new(
+        exit: &Arc<AtomicBool>,
+        cluster_info: &Arc<ClusterInfo>,
+        bank_forks: &Arc<RwLock<BankForks>>,
+        staked_nodes: StakedNodes,
+    ) -> Self {
+        let exit = exit.clone();
+        let cluster_info = cluster_info.clone();
+        let bank_forks = bank_forks.clone();
+        let thread_hdl = Builder::new()
+            .name("sol-staked-nodes".to_string())
+            .spawn(move || {
+                let mut last_update: Option<Instant> = None;
+                while !exit.load(Ordering::Relaxed) {
+                    if last_update.map_or(true, |t| t.elapsed() >= UPDATE_INTERVAL) {
+                        let ip_to_stake = Self::ip_to_stake(&cluster_info, &bank_forks);
+                        *staked_nodes.write().unwrap() = ip_to_stake;
+                        last_update = Some(Instant::now());
+                    }
+                    sleep(SLEEP_INTERVAL);
+                }
+            })
+            .unwrap();
+        Self { thread_hdl }
+    }
+
+    /// The stake of the root bank behind the tpu address of every node in gossip
+    fn ip_to_stake(
+        cluster_info: &ClusterInfo,
+        bank_forks: &RwLock<BankForks>,
+    ) -> HashMap<IpAddr, u64> {
+        let root_bank = bank_forks.read().unwrap().root_bank().clone();
+        let staked_nodes = staking_utils::staked_nodes(&root_bank);
+        let mut ip_to_stake = HashMap::new();
+        for node in cluster_info.tpu_peers() {
+            if let Some(stake) = staked_nodes.get(&node.id) {
+                *ip_to_stake.entry(node.tpu.ip()).or_default() += stake;
+            }
+        }
+        ip_to_stake
+    }
+
+    pub fn join(self) -> thread::Result<()> {
+        self.thread_hdl.join()
+    }
+}
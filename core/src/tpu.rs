@@ -11,14 +11,17 @@ use crate::{
     poh_recorder::{PohRecorder, WorkingBankEntry},
     rpc_subscriptions::RpcSubscriptions,
     sigverify::TransactionSigVerifier,
-    sigverify_stage::SigVerifyStage,
+    sigverify_stage::{PacketAdmission, SigVerifyStage},
+    staked_nodes_updater_service::StakedNodesUpdaterService,
 };
 use crossbeam_channel::unbounded;
+use log::*;
 use solana_ledger::{blockstore::Blockstore, blockstore_processor::TransactionStatusSender};
 use solana_runtime::{
     bank_forks::BankForks,
     vote_sender_types::{ReplayVoteReceiver, ReplayVoteSender},
 };
+use solana_streamer::quic::{spawn_server, QuicServerConfig, StakedNodes};
 use std::{
     net::UdpSocket,
     sync::{
@@ -26,11 +29,13 @@ use std::{
         mpsc::{channel, Receiver},
         Arc, Mutex, RwLock,
     },
-    thread,
+    thread::{self, JoinHandle},
 };
 
 pub struct Tpu {
     fetch_stage: FetchStage,
+    tpu_quic_server: Option<JoinHandle<()>>,
+    staked_nodes_updater_service: StakedNodesUpdaterService,
     sigverify_stage: SigVerifyStage,
     banking_stage: BankingStage,
     cluster_info_vote_listener: ClusterInfoVoteListener,
@@ -46,6 +51,8 @@ impl Tpu {
         retransmit_slots_receiver: RetransmitSlotsReceiver,
         transactions_sockets: Vec<UdpSocket>,
         tpu_forwards_sockets: Vec<UdpSocket>,
+        tpu_quic_socket: Option<UdpSocket>,
+        tpu_quic_config: Option<QuicServerConfig>,
         broadcast_sockets: Vec<UdpSocket>,
         subscriptions: &Arc<RpcSubscriptions>,
         transaction_status_sender: Option<TransactionStatusSender>,
@@ -68,11 +75,42 @@ impl Tpu {
             &packet_sender,
             &poh_recorder,
         );
+
+        // Stake by TPU address, for the QUIC connection quotas and sigverify packet admission
+        let staked_nodes = StakedNodes::default();
+        let staked_nodes_updater_service =
+            StakedNodesUpdaterService::new(exit, cluster_info, &bank_forks, staked_nodes.clone());
+
+        // The QUIC listener feeds the same channel as the UDP fetch stage, so its packets go
+        // through the same sigverify and banking pipeline
+        let tpu_quic_server = tpu_quic_config.map(|tpu_quic_config| {
+            let tpu_quic_socket =
+                tpu_quic_socket.expect("the QUIC TPU socket is bound when QUIC is enabled");
+            let tpu_quic_server = spawn_server(
+                tpu_quic_socket,
+                packet_sender.clone(),
+                staked_nodes.clone(),
+                tpu_quic_config,
+                exit.clone(),
+            )
+            .unwrap_or_else(|err| panic!("Failed to start the QUIC TPU: {:?}", err));
+            info!(
+                "QUIC TPU listening on {}",
+                cluster_info.my_contact_info().tpu_quic()
+            );
+            tpu_quic_server
+        });
+
         let (verified_sender, verified_receiver) = unbounded();
 
         let sigverify_stage = {
             let verifier = TransactionSigVerifier::default();
-            SigVerifyStage::new(packet_receiver, verified_sender, verifier)
+            SigVerifyStage::new_with_admission(
+                packet_receiver,
+                verified_sender,
+                verifier,
+                PacketAdmission::new(staked_nodes),
+            )
         };
 
         let (verified_vote_packets_sender, verified_vote_packets_receiver) = unbounded();
@@ -111,6 +149,8 @@ impl Tpu {
 
         Self {
             fetch_stage,
+            tpu_quic_server,
+            staked_nodes_updater_service,
             sigverify_stage,
             banking_stage,
             cluster_info_vote_listener,
@@ -121,6 +161,10 @@ impl Tpu {
     pub fn join(self) -> thread::Result<()> {
         let mut results = vec![];
         results.push(self.fetch_stage.join());
+        if let Some(tpu_quic_server) = self.tpu_quic_server {
+            results.push(tpu_quic_server.join());
+        }
+        results.push(self.staked_nodes_updater_service.join());
         results.push(self.sigverify_stage.join());
         results.push(self.cluster_info_vote_listener.join());
         results.push(self.banking_stage.join());
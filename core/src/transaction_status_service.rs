@@ -1,7 +1,9 @@
 use crossbeam_channel::{Receiver, RecvTimeoutError};
 use itertools::izip;
+use solana_geyser_plugin_manager::transaction_notifier::TransactionNotifierLock;
 use solana_ledger::{blockstore::Blockstore, blockstore_processor::TransactionStatusBatch};
 use solana_runtime::{bank::Bank, transaction_utils::OrderedIterator};
+use solana_sdk::fee_calculator::FeeConfig;
 use solana_transaction_status::{InnerInstructions, TransactionStatusMeta};
 use std::{
     sync::{
@@ -21,6 +23,8 @@ impl TransactionStatusService {
     pub fn new(
         write_transaction_status_receiver: Receiver<TransactionStatusBatch>,
         blockstore: Arc<Blockstore>,
+        enable_rpc_transaction_history: bool,
+        transaction_notifier: Option<TransactionNotifierLock>,
         exit: &Arc<AtomicBool>,
     ) -> Self {
         let exit = exit.clone();
@@ -33,6 +37,8 @@ impl TransactionStatusService {
                 if let Err(RecvTimeoutError::Disconnected) = Self::write_transaction_status_batch(
                     &write_transaction_status_receiver,
                     &blockstore,
+                    enable_rpc_transaction_history,
+                    transaction_notifier.as_ref(),
                 ) {
                     break;
                 }
@@ -44,6 +50,8 @@ impl TransactionStatusService {
     fn write_transaction_status_batch(
         write_transaction_status_receiver: &Receiver<TransactionStatusBatch>,
         blockstore: &Arc<Blockstore>,
+        enable_rpc_transaction_history: bool,
+        transaction_notifier: Option<&TransactionNotifierLock>,
     ) -> Result<(), RecvTimeoutError> {
         let TransactionStatusBatch {
             bank,
@@ -51,16 +59,24 @@ impl TransactionStatusService {
             iteration_order,
             statuses,
             balances,
+            token_balances,
             inner_instructions,
             transaction_logs,
         } = write_transaction_status_receiver.recv_timeout(Duration::from_secs(1))?;
 
         let slot = bank.slot();
+        // Charge what the bank charged, which depends on the features active on it
+        let fee_config = FeeConfig {
+            secp256k1_program_enabled: bank.secp256k1_program_enabled(),
+            compute_budget_program_enabled: bank.compute_budget_program_enabled(),
+        };
         for (
             (_, transaction),
             (status, hash_age_kind),
             pre_balances,
             post_balances,
+            pre_token_balances,
+            post_token_balances,
             inner_instructions,
             log_messages,
         ) in izip!(
@@ -68,6 +84,8 @@ impl TransactionStatusService {
             statuses,
             balances.pre_balances,
             balances.post_balances,
+            token_balances.pre_token_balances,
+            token_balances.post_token_balances,
             inner_instructions,
             transaction_logs
         ) {
@@ -78,7 +96,8 @@ impl TransactionStatusService {
                         bank.get_fee_calculator(&transaction.message().recent_blockhash)
                     })
                     .expect("FeeCalculator must exist");
-                let fee = fee_calculator.calculate_fee(transaction.message());
+                let fee =
+                    fee_calculator.calculate_fee_with_config(transaction.message(), &fee_config);
                 let (writable_keys, readonly_keys) =
                     transaction.message.get_account_keys_by_lock_type();
 
@@ -95,23 +114,42 @@ impl TransactionStatusService {
                 });
 
                 let log_messages = Some(log_messages);
+                let pre_token_balances = Some(pre_token_balances);
+                let post_token_balances = Some(post_token_balances);
 
-                blockstore
-                    .write_transaction_status(
+                let transaction_status_meta = TransactionStatusMeta {
+                    status,
+                    fee,
+                    pre_balances,
+                    post_balances,
+                    inner_instructions,
+                    log_messages,
+                    pre_token_balances,
+                    post_token_balances,
+                    // Only versioned messages load addresses from lookup tables
+                    loaded_addresses: None,
+                };
+
+                if let Some(transaction_notifier) = transaction_notifier {
+                    transaction_notifier.read().unwrap().notify_transaction(
                         slot,
-                        transaction.signatures[0],
-                        writable_keys,
-                        readonly_keys,
-                        &TransactionStatusMeta {
-                            status,
-                            fee,
-                            pre_balances,
-                            post_balances,
-                            inner_instructions,
-                            log_messages,
-                        },
-                    )
-                    .expect("Expect database write to succeed");
+                        &transaction.signatures[0],
+                        &transaction_status_meta,
+                        transaction,
+                    );
+                }
+
+                if enable_rpc_transaction_history {
+                    blockstore
+                        .write_transaction_status(
+                            slot,
+                            transaction.signatures[0],
+                            writable_keys,
+                            readonly_keys,
+                            &transaction_status_meta,
+                        )
+                        .expect("Expect database write to succeed");
+                }
             }
         }
         Ok(())
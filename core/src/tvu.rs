@@ -10,7 +10,7 @@ use crate::{
     cluster_slots::ClusterSlots,
     completed_data_sets_service::CompletedDataSetsSender,
     consensus::Tower,
-    ledger_cleanup_service::LedgerCleanupService,
+    ledger_cleanup_service::{LedgerCleanupService, TransactionHistoryLimits},
     optimistically_confirmed_bank_tracker::BankNotificationSender,
     poh_recorder::PohRecorder,
     replay_stage::{ReplayStage, ReplayStageConfig},
@@ -22,9 +22,10 @@ use crate::{
     sigverify_stage::SigVerifyStage,
 };
 use crossbeam_channel::unbounded;
+use solana_geyser_plugin_manager::slot_status_notifier::SlotStatusNotifier;
 use solana_ledger::{
     blockstore::{Blockstore, CompletedSlotsReceiver},
-    blockstore_processor::TransactionStatusSender,
+    blockstore_processor::{AccountHistorySender, TransactionStatusSender},
     leader_schedule_cache::LeaderScheduleCache,
 };
 use solana_runtime::{
@@ -38,6 +39,7 @@ use solana_sdk::{
     pubkey::Pubkey,
     signature::{Keypair, Signer},
 };
+use solana_vote_signer::remote::RemoteVoteSigner;
 use std::{
     collections::HashSet,
     net::UdpSocket,
@@ -69,11 +71,13 @@ pub struct Sockets {
 #[derive(Default)]
 pub struct TvuConfig {
     pub max_ledger_shreds: Option<u64>,
+    pub transaction_history_limits: TransactionHistoryLimits,
     pub shred_version: u16,
     pub halt_on_trusted_validators_accounts_hash_mismatch: bool,
     pub trusted_validators: Option<HashSet<Pubkey>>,
     pub repair_validators: Option<HashSet<Pubkey>>,
     pub accounts_hash_fault_injection_slots: u64,
+    pub remote_vote_signer: Option<Arc<RemoteVoteSigner>>,
 }
 
 impl Tvu {
@@ -103,6 +107,7 @@ impl Tvu {
         transaction_status_sender: Option<TransactionStatusSender>,
         rewards_recorder_sender: Option<RewardsRecorderSender>,
         cache_block_time_sender: Option<CacheBlockTimeSender>,
+        account_history_sender: Option<AccountHistorySender>,
         snapshot_config_and_package_sender: Option<(SnapshotConfig, AccountsPackageSender)>,
         vote_tracker: Arc<VoteTracker>,
         retransmit_slots_sender: RetransmitSlotsSender,
@@ -110,6 +115,7 @@ impl Tvu {
         replay_vote_sender: ReplayVoteSender,
         completed_data_sets_sender: CompletedDataSetsSender,
         bank_notification_sender: Option<BankNotificationSender>,
+        slot_status_notifier: Option<SlotStatusNotifier>,
         tvu_config: TvuConfig,
     ) -> Self {
         let keypair: Arc<Keypair> = cluster_info.keypair.clone();
@@ -212,6 +218,7 @@ impl Tvu {
             my_pubkey: keypair.pubkey(),
             vote_account: *vote_account,
             authorized_voter_keypairs,
+            remote_vote_signer: tvu_config.remote_vote_signer,
             exit: exit.clone(),
             subscriptions: subscriptions.clone(),
             leader_schedule_cache: leader_schedule_cache.clone(),
@@ -221,7 +228,9 @@ impl Tvu {
             transaction_status_sender,
             rewards_recorder_sender,
             cache_block_time_sender,
+            account_history_sender,
             bank_notification_sender,
+            slot_status_notifier,
         };
 
         let replay_stage = ReplayStage::new(
@@ -239,11 +248,13 @@ impl Tvu {
             replay_vote_sender,
         );
 
+        let transaction_history_limits = tvu_config.transaction_history_limits;
         let ledger_cleanup_service = tvu_config.max_ledger_shreds.map(|max_ledger_shreds| {
             LedgerCleanupService::new(
                 ledger_cleanup_slot_receiver,
                 blockstore.clone(),
                 max_ledger_shreds,
+                transaction_history_limits,
                 &exit,
             )
         });
@@ -365,12 +376,14 @@ pub mod tests {
             None,
             None,
             None,
+            None,
             Arc::new(VoteTracker::new(&bank)),
             retransmit_slots_sender,
             verified_vote_receiver,
             replay_vote_sender,
             completed_data_sets_sender,
             None,
+            None,
             TvuConfig::default(),
         );
         exit.store(true, Ordering::Relaxed);
@@ -1,6 +1,7 @@
 //! The `validator` module hosts all the validator microservices.
 
 use crate::{
+    account_history_service::AccountHistoryService,
     broadcast_stage::BroadcastStageType,
     cache_block_time_service::{CacheBlockTimeSender, CacheBlockTimeService},
     cluster_info::{ClusterInfo, Node, DEFAULT_CONTACT_DEBUG_INTERVAL},
@@ -9,6 +10,7 @@ use crate::{
     consensus::{reconcile_blockstore_roots_with_tower, Tower},
     contact_info::ContactInfo,
     gossip_service::GossipService,
+    ledger_cleanup_service::TransactionHistoryLimits,
     optimistically_confirmed_bank_tracker::{
         OptimisticallyConfirmedBank, OptimisticallyConfirmedBankTracker,
     },
@@ -31,17 +33,24 @@ use crate::{
 use crossbeam_channel::{bounded, unbounded};
 use rand::{thread_rng, Rng};
 use solana_banks_server::rpc_banks_service::RpcBanksService;
+use solana_geyser_plugin_manager::{
+    geyser_plugin_service::GeyserPluginService, transaction_notifier::TransactionNotifierLock,
+};
 use solana_ledger::{
     bank_forks_utils,
-    blockstore::{Blockstore, BlockstoreSignals, CompletedSlotsReceiver, PurgeType},
+    blockstore::{
+        write_genesis_slot, Blockstore, BlockstoreSignals, CompletedSlotsReceiver, PurgeType,
+    },
     blockstore_db::BlockstoreRecoveryMode,
-    blockstore_processor::{self, TransactionStatusSender},
+    blockstore_processor::{self, AccountHistorySender, TransactionStatusSender},
     leader_schedule::FixedSchedule,
     leader_schedule_cache::LeaderScheduleCache,
 };
 use solana_measure::measure::Measure;
 use solana_metrics::datapoint_info;
 use solana_runtime::{
+    accounts_index::AccountSecondaryIndexes,
+    accounts_update_notifier_interface::AccountsUpdateNotifier,
     bank::Bank,
     bank_forks::{BankForks, SnapshotConfig},
     commitment::BlockCommitmentCache,
@@ -57,7 +66,9 @@ use solana_sdk::{
     signature::{Keypair, Signer},
     timing::timestamp,
 };
+use solana_streamer::quic::QuicServerConfig;
 use solana_vote_program::vote_state::VoteState;
+use solana_vote_signer::remote::RemoteVoteSigner;
 use std::time::Instant;
 use std::{
     collections::HashSet,
@@ -86,6 +97,7 @@ pub struct ValidatorConfig {
     pub pubsub_config: PubSubConfig,
     pub snapshot_config: Option<SnapshotConfig>,
     pub max_ledger_shreds: Option<u64>,
+    pub transaction_history_limits: TransactionHistoryLimits,
     pub broadcast_stage_type: BroadcastStageType,
     pub enable_partition: Option<Arc<AtomicBool>>,
     pub fixed_leader_schedule: Option<FixedSchedule>,
@@ -98,6 +110,7 @@ pub struct ValidatorConfig {
     pub accounts_hash_fault_injection_slots: u64, // 0 = no fault injection
     pub frozen_accounts: Vec<Pubkey>,
     pub no_rocksdb_compaction: bool,
+    pub blockstore_in_memory: bool, // Keep the blockstore in memory instead of in the ledger directory
     pub accounts_hash_interval_slots: u64,
     pub max_genesis_archive_unpacked_size: u64,
     pub wal_recovery_mode: Option<BlockstoreRecoveryMode>,
@@ -106,6 +119,10 @@ pub struct ValidatorConfig {
     pub require_tower: bool,
     pub debug_keys: Option<Arc<HashSet<Pubkey>>>,
     pub contact_debug_interval: u64,
+    pub account_indexes: AccountSecondaryIndexes,
+    pub geyser_plugin_config_files: Option<Vec<PathBuf>>,
+    pub tpu_quic_config: Option<QuicServerConfig>, // None = no QUIC TPU listener
+    pub remote_vote_signer: Option<Arc<RemoteVoteSigner>>, // None = sign votes in-process
 }
 
 impl Default for ValidatorConfig {
@@ -117,6 +134,7 @@ impl Default for ValidatorConfig {
             expected_shred_version: None,
             voting_disabled: false,
             max_ledger_shreds: None,
+            transaction_history_limits: TransactionHistoryLimits::default(),
             account_paths: Vec::new(),
             rpc_config: JsonRpcConfig::default(),
             rpc_addrs: None,
@@ -134,6 +152,7 @@ impl Default for ValidatorConfig {
             accounts_hash_fault_injection_slots: 0,
             frozen_accounts: vec![],
             no_rocksdb_compaction: false,
+            blockstore_in_memory: false,
             accounts_hash_interval_slots: std::u64::MAX,
             max_genesis_archive_unpacked_size: MAX_GENESIS_ARCHIVE_UNPACKED_SIZE,
             wal_recovery_mode: None,
@@ -142,6 +161,10 @@ impl Default for ValidatorConfig {
             require_tower: false,
             debug_keys: None,
             contact_debug_interval: DEFAULT_CONTACT_DEBUG_INTERVAL,
+            account_indexes: AccountSecondaryIndexes::default(),
+            geyser_plugin_config_files: None,
+            tpu_quic_config: None,
+            remote_vote_signer: None,
         }
     }
 }
@@ -171,6 +194,8 @@ struct TransactionHistoryServices {
     rewards_recorder_service: Option<RewardsRecorderService>,
     cache_block_time_sender: Option<CacheBlockTimeSender>,
     cache_block_time_service: Option<CacheBlockTimeService>,
+    account_history_sender: Option<AccountHistorySender>,
+    account_history_service: Option<AccountHistoryService>,
 }
 
 struct RpcServices {
@@ -187,6 +212,7 @@ pub struct Validator {
     transaction_status_service: Option<TransactionStatusService>,
     rewards_recorder_service: Option<RewardsRecorderService>,
     cache_block_time_service: Option<CacheBlockTimeService>,
+    account_history_service: Option<AccountHistoryService>,
     sample_performance_service: Option<SamplePerformanceService>,
     gossip_service: GossipService,
     serve_repair_service: ServeRepairService,
@@ -197,6 +223,7 @@ pub struct Validator {
     tpu: Tpu,
     tvu: Tvu,
     ip_echo_server: solana_net_utils::IpEchoServer,
+    geyser_plugin_service: Option<GeyserPluginService>,
 }
 
 impl Validator {
@@ -215,14 +242,25 @@ impl Validator {
         warn!("identity: {}", id);
         warn!("vote account: {}", vote_account);
 
-        if config.voting_disabled {
+        let remote_vote_signer = if config.voting_disabled {
             warn!("voting disabled");
             authorized_voter_keypairs.clear();
+            None
         } else {
             for authorized_voter_keypair in &authorized_voter_keypairs {
                 warn!("authorized voter: {}", authorized_voter_keypair.pubkey());
             }
-        }
+            if let Some(remote_vote_signer) = &config.remote_vote_signer {
+                warn!("remote vote signer: {}", remote_vote_signer.addr());
+                if !authorized_voter_keypairs.is_empty() {
+                    error!(
+                        "Authorized voter keypairs can't be used along with a remote vote signer"
+                    );
+                    process::exit(1);
+                }
+            }
+            config.remote_vote_signer.clone()
+        };
         report_target_features();
 
         info!("entrypoint: {:?}", cluster_entrypoint);
@@ -243,7 +281,10 @@ impl Validator {
             process::exit(1);
         }
 
-        if let Some(shred_version) = config.expected_shred_version {
+        // An in-memory blockstore starts out empty, so there are no shreds to back up
+        if let (Some(shred_version), false) =
+            (config.expected_shred_version, config.blockstore_in_memory)
+        {
             if let Some(wait_for_supermajority_slot) = config.wait_for_supermajority {
                 backup_and_clear_blockstore(
                     ledger_path,
@@ -267,6 +308,17 @@ impl Validator {
         validator_exit.register_exit(Box::new(move || exit_.store(true, Ordering::Relaxed)));
         let validator_exit = Arc::new(RwLock::new(Some(validator_exit)));
 
+        let geyser_plugin_service =
+            config
+                .geyser_plugin_config_files
+                .as_ref()
+                .map(|geyser_plugin_config_files| {
+                    GeyserPluginService::new(geyser_plugin_config_files).unwrap_or_else(|err| {
+                        error!("Failed to load the Geyser plugin: {:?}", err);
+                        process::exit(1);
+                    })
+                });
+
         let (replay_vote_sender, replay_vote_receiver) = unbounded();
         let (
             genesis_config,
@@ -283,6 +335,8 @@ impl Validator {
                 rewards_recorder_service,
                 cache_block_time_sender,
                 cache_block_time_service,
+                account_history_sender,
+                account_history_service,
             },
             tower,
         ) = new_banks_from_ledger(
@@ -292,7 +346,16 @@ impl Validator {
             ledger_path,
             config.poh_verify,
             &exit,
+            geyser_plugin_service
+                .as_ref()
+                .map(|service| service.accounts_update_notifier()),
+            geyser_plugin_service
+                .as_ref()
+                .map(|service| service.transaction_notifier()),
         );
+        if let Some(geyser_plugin_service) = &geyser_plugin_service {
+            geyser_plugin_service.notify_end_of_startup();
+        }
 
         let leader_schedule_cache = Arc::new(leader_schedule_cache);
         let bank = bank_forks.working_bank();
@@ -562,6 +625,7 @@ impl Validator {
             transaction_status_sender.clone(),
             rewards_recorder_sender,
             cache_block_time_sender,
+            account_history_sender,
             snapshot_config_and_package_sender,
             vote_tracker.clone(),
             retransmit_slots_sender,
@@ -569,14 +633,19 @@ impl Validator {
             replay_vote_sender.clone(),
             completed_data_sets_sender,
             bank_notification_sender.clone(),
+            geyser_plugin_service
+                .as_ref()
+                .map(|service| service.slot_status_notifier()),
             TvuConfig {
                 max_ledger_shreds: config.max_ledger_shreds,
+                transaction_history_limits: config.transaction_history_limits,
                 halt_on_trusted_validators_accounts_hash_mismatch: config
                     .halt_on_trusted_validators_accounts_hash_mismatch,
                 shred_version: node.info.shred_version,
                 trusted_validators: config.trusted_validators.clone(),
                 repair_validators: config.repair_validators.clone(),
                 accounts_hash_fault_injection_slots: config.accounts_hash_fault_injection_slots,
+                remote_vote_signer,
             },
         );
 
@@ -587,6 +656,8 @@ impl Validator {
             retransmit_slots_receiver,
             node.sockets.tpu,
             node.sockets.tpu_forwards,
+            node.sockets.tpu_quic,
+            config.tpu_quic_config.clone(),
             node.sockets.broadcast,
             &subscriptions,
             transaction_status_sender,
@@ -611,6 +682,7 @@ impl Validator {
             transaction_status_service,
             rewards_recorder_service,
             cache_block_time_service,
+            account_history_service,
             sample_performance_service,
             snapshot_packager_service,
             completed_data_sets_service,
@@ -620,6 +692,7 @@ impl Validator {
             poh_recorder,
             ip_echo_server,
             validator_exit,
+            geyser_plugin_service,
         }
     }
 
@@ -687,6 +760,10 @@ impl Validator {
             cache_block_time_service.join()?;
         }
 
+        if let Some(account_history_service) = self.account_history_service {
+            account_history_service.join()?;
+        }
+
         if let Some(sample_performance_service) = self.sample_performance_service {
             sample_performance_service.join()?;
         }
@@ -702,6 +779,10 @@ impl Validator {
         self.completed_data_sets_service.join()?;
         self.ip_echo_server.shutdown_now();
 
+        if let Some(geyser_plugin_service) = self.geyser_plugin_service {
+            geyser_plugin_service.join();
+        }
+
         Ok(())
     }
 }
@@ -775,6 +856,10 @@ fn post_process_restored_tower(
                     "And there is an existing vote_account containing actual votes. \
                      Aborting due to possible conflicting duplicate votes",
                 );
+                error!(
+                    "The previously saved tower can be inspected and restored with \
+                     `solana-ledger-tool tower --backup`",
+                );
                 process::exit(1);
             }
             if err.is_file_missing() && !voting_has_been_active {
@@ -799,7 +884,7 @@ fn post_process_restored_tower(
         })
 }
 
-#[allow(clippy::type_complexity)]
+#[allow(clippy::type_complexity, clippy::too_many_arguments)]
 fn new_banks_from_ledger(
     validator_identity: &Pubkey,
     vote_account: &Pubkey,
@@ -807,6 +892,8 @@ fn new_banks_from_ledger(
     ledger_path: &Path,
     poh_verify: bool,
     exit: &Arc<AtomicBool>,
+    accounts_update_notifier: Option<AccountsUpdateNotifier>,
+    transaction_notifier: Option<TransactionNotifierLock>,
 ) -> (
     GenesisConfig,
     BankForks,
@@ -844,8 +931,16 @@ fn new_banks_from_ledger(
         ledger_signal_receiver,
         completed_slots_receiver,
         ..
-    } = Blockstore::open_with_signal(ledger_path, config.wal_recovery_mode.clone())
-        .expect("Failed to open ledger database");
+    } = if config.blockstore_in_memory {
+        // Nothing outlives the process, so bootstrap the ledger from genesis on every start
+        Blockstore::open_in_memory_with_signal().and_then(|signals| {
+            write_genesis_slot(&signals.blockstore, &genesis_config)?;
+            Ok(signals)
+        })
+    } else {
+        Blockstore::open_with_signal(ledger_path, config.wal_recovery_mode.clone())
+    }
+    .expect("Failed to open ledger database");
     blockstore.set_no_compaction(config.no_rocksdb_compaction);
 
     let restored_tower = Tower::restore(ledger_path, &validator_identity);
@@ -856,23 +951,46 @@ fn new_banks_from_ledger(
         });
     }
 
+    let blockstore = Arc::new(blockstore);
+    let enable_rpc_transaction_history =
+        config.rpc_addrs.is_some() && config.rpc_config.enable_rpc_transaction_history;
+    let mut transaction_history_services =
+        if enable_rpc_transaction_history || transaction_notifier.is_some() {
+            initialize_rpc_transaction_history_services(
+                blockstore.clone(),
+                exit,
+                enable_rpc_transaction_history,
+                transaction_notifier,
+            )
+        } else {
+            TransactionHistoryServices::default()
+        };
+    // Started before the ledger is processed, so that the slots rooted while catching up with the
+    // ledger are recorded too
+    if config.rpc_config.enable_rpc_account_history {
+        let (account_history_sender, account_history_receiver) = unbounded();
+        transaction_history_services.account_history_sender = Some(account_history_sender);
+        transaction_history_services.account_history_service = Some(AccountHistoryService::new(
+            account_history_receiver,
+            blockstore.clone(),
+            exit,
+        ));
+    }
+
     let process_options = blockstore_processor::ProcessOptions {
         poh_verify,
         dev_halt_at_slot: config.dev_halt_at_slot,
         new_hard_forks: config.new_hard_forks.clone(),
         frozen_accounts: config.frozen_accounts.clone(),
         debug_keys: config.debug_keys.clone(),
+        account_indexes: config.account_indexes.clone(),
+        accounts_update_notifier,
+        account_history_sender: transaction_history_services
+            .account_history_sender
+            .clone(),
         ..blockstore_processor::ProcessOptions::default()
     };
 
-    let blockstore = Arc::new(blockstore);
-    let transaction_history_services =
-        if config.rpc_addrs.is_some() && config.rpc_config.enable_rpc_transaction_history {
-            initialize_rpc_transaction_history_services(blockstore.clone(), exit)
-        } else {
-            TransactionHistoryServices::default()
-        };
-
     let (mut bank_forks, mut leader_schedule_cache, snapshot_hash) = bank_forks_utils::load(
         &genesis_config,
         &blockstore,
@@ -992,15 +1110,28 @@ fn backup_and_clear_blockstore(ledger_path: &Path, start_slot: Slot, shred_versi
 fn initialize_rpc_transaction_history_services(
     blockstore: Arc<Blockstore>,
     exit: &Arc<AtomicBool>,
+    enable_rpc_transaction_history: bool,
+    transaction_notifier: Option<TransactionNotifierLock>,
 ) -> TransactionHistoryServices {
     let (transaction_status_sender, transaction_status_receiver) = unbounded();
     let transaction_status_sender = Some(transaction_status_sender);
     let transaction_status_service = Some(TransactionStatusService::new(
         transaction_status_receiver,
         blockstore.clone(),
+        enable_rpc_transaction_history,
+        transaction_notifier,
         exit,
     ));
 
+    // Only the transaction notifier is needed when rpc transaction history is disabled
+    if !enable_rpc_transaction_history {
+        return TransactionHistoryServices {
+            transaction_status_sender,
+            transaction_status_service,
+            ..TransactionHistoryServices::default()
+        };
+    }
+
     let (rewards_recorder_sender, rewards_receiver) = unbounded();
     let rewards_recorder_sender = Some(rewards_recorder_sender);
     let rewards_recorder_service = Some(RewardsRecorderService::new(
@@ -1023,6 +1154,7 @@ fn initialize_rpc_transaction_history_services(
         rewards_recorder_service,
         cache_block_time_sender,
         cache_block_time_service,
+        ..TransactionHistoryServices::default()
     }
 }
 
@@ -1233,6 +1365,7 @@ mod tests {
                 validator_node.info.rpc_pubsub,
                 validator_node.info.rpc_banks,
             )),
+            blockstore_in_memory: true,
             ..ValidatorConfig::default()
         };
         let validator = Validator::new(
@@ -1307,6 +1440,7 @@ mod tests {
                         validator_node.info.rpc_pubsub,
                         validator_node.info.rpc_banks,
                     )),
+                    blockstore_in_memory: true,
                     ..ValidatorConfig::default()
                 };
                 Validator::new(
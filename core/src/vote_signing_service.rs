@@ -0,0 +1,83 @@
+//! The `vote_signing_service` gets the votes of the replay stage signed by a remote
+//! vote signer and sends them, so that the replay stage never waits on the signer.
+
+use crate::cluster_info::ClusterInfo;
+use solana_sdk::{clock::Slot, transaction::Transaction};
+use solana_vote_signer::{remote::RemoteVoteSigner, slashing_protection::BankHashLink};
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{channel, Receiver, RecvTimeoutError, Sender},
+        Arc,
+    },
+    thread::{self, Builder, JoinHandle},
+    time::Duration,
+};
+
+pub struct VoteSigningRequest {
+    pub slot: Slot,
+    /// Vote transaction, partially signed by the node
+    pub vote_tx: Transaction,
+    pub ancestry: Vec<BankHashLink>,
+    pub tower_index: usize,
+}
+
+pub struct VoteSigningService {
+    t_signer: JoinHandle<()>,
+}
+
+impl VoteSigningService {
+    pub fn new(
+        exit: &Arc<AtomicBool>,
+        remote_vote_signer: Arc<RemoteVoteSigner>,
+        cluster_info: Arc<ClusterInfo>,
+    ) -> (Sender<VoteSigningRequest>, Self) {
+        let (sender, receiver) = channel();
+        let exit = exit.clone();
+        let t_signer = Builder::new()
+            .name("solana-vote-signing".to_string())
+            .spawn(move || loop {
+                if exit.load(Ordering::Relaxed) {
+                    break;
+                }
+                if let Err(RecvTimeoutError::Disconnected) =
+                    Self::run(&receiver, &remote_vote_signer, &cluster_info)
+                {
+                    break;
+                }
+            })
+            .unwrap();
+        (sender, Self { t_signer })
+    }
+
+    fn run(
+        receiver: &Receiver<VoteSigningRequest>,
+        remote_vote_signer: &RemoteVoteSigner,
+        cluster_info: &ClusterInfo,
+    ) -> Result<(), RecvTimeoutError> {
+        let VoteSigningRequest {
+            slot,
+            mut vote_tx,
+            ancestry,
+            tower_index,
+        } = receiver.recv_timeout(Duration::from_secs(1))?;
+
+        if let Err(err) = remote_vote_signer.sign_vote_transaction(&mut vote_tx, ancestry) {
+            warn!(
+                "The vote signer {} did not sign the vote for slot {}: {}",
+                remote_vote_signer.addr(),
+                slot,
+                err
+            );
+            inc_new_counter_info!("replay_stage-remote_vote_signer_failed", 1);
+            return Ok(());
+        }
+        let _ = cluster_info.send_vote(&vote_tx);
+        cluster_info.push_vote(tower_index, vote_tx);
+        Ok(())
+    }
+
+    pub fn join(self) -> thread::Result<()> {
+        self.t_signer.join()
+    }
+}
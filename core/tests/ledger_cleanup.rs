@@ -2,7 +2,7 @@
 
 #[cfg(test)]
 mod tests {
-    use solana_core::ledger_cleanup_service::LedgerCleanupService;
+    use solana_core::ledger_cleanup_service::{LedgerCleanupService, TransactionHistoryLimits};
     use solana_ledger::blockstore::{make_many_slot_entries, Blockstore};
     use solana_ledger::get_tmp_ledger_path;
     use solana_ledger::shred::Shred;
@@ -223,8 +223,13 @@ mod tests {
 
         let (sender, receiver) = channel();
         let exit = Arc::new(AtomicBool::new(false));
-        let cleaner =
-            LedgerCleanupService::new(receiver, blockstore.clone(), max_ledger_shreds, &exit);
+        let cleaner = LedgerCleanupService::new(
+            receiver,
+            blockstore.clone(),
+            max_ledger_shreds,
+            TransactionHistoryLimits::default(),
+            &exit,
+        );
 
         let exit_cpu = Arc::new(AtomicBool::new(false));
         let sys = CpuStatsUpdater::new(&exit_cpu);
@@ -380,6 +385,7 @@ mod tests {
             &receiver,
             &blockstore,
             max_ledger_shreds,
+            TransactionHistoryLimits::default(),
             &mut last_purge_slot,
             10,
             &mut last_compaction_slot,
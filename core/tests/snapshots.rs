@@ -44,9 +44,11 @@ mod tests {
     };
     use solana_runtime::{
         accounts_background_service::SnapshotRequestHandler,
+        accounts_index::AccountSecondaryIndexes,
         bank::{Bank, BankSlotDelta},
         bank_forks::{BankForks, CompressionType, SnapshotConfig},
         genesis_utils::{create_genesis_config, GenesisConfigInfo},
+        snapshot_package::SnapshotType,
         snapshot_utils,
         snapshot_utils::SnapshotVersion,
         status_cache::MAX_CACHE_ENTRIES,
@@ -93,6 +95,7 @@ mod tests {
                 &[],
                 None,
                 None,
+                AccountSecondaryIndexes::default(),
             );
             bank0.freeze();
             let mut bank_forks = BankForks::new(bank0);
@@ -100,6 +103,7 @@ mod tests {
 
             let snapshot_config = SnapshotConfig {
                 snapshot_interval_slots,
+                incremental_snapshot_interval_slots: std::u64::MAX,
                 snapshot_package_output_path: PathBuf::from(snapshot_output_path.path()),
                 snapshot_path: PathBuf::from(snapshot_dir.path()),
                 compression: CompressionType::Bzip2,
@@ -145,9 +149,11 @@ mod tests {
                 &CompressionType::Bzip2,
             ),
             CompressionType::Bzip2,
+            None,
             old_genesis_config,
             None,
             None,
+            AccountSecondaryIndexes::default(),
         )
         .unwrap();
 
@@ -223,6 +229,7 @@ mod tests {
             last_bank.get_snapshot_storages(),
             CompressionType::Bzip2,
             snapshot_version,
+            SnapshotType::FullSnapshot,
         )
         .unwrap();
         snapshot_utils::archive_snapshot_package(&snapshot_package).unwrap();
@@ -343,6 +350,7 @@ mod tests {
                 &snapshot_package_output_path,
                 snapshot_config.snapshot_version,
                 &snapshot_config.compression,
+                SnapshotType::FullSnapshot,
             )
             .unwrap();
 
@@ -217,3 +217,64 @@ pub fn download_snapshot(
         Err("Snapshot couldn't be downloaded".to_string())
     }
 }
+
+pub fn download_incremental_snapshot(
+    rpc_addr: &SocketAddr,
+    ledger_path: &Path,
+    full_snapshot_slot: Slot,
+    desired_snapshot_hash: (Slot, Hash),
+    use_progress_bar: bool,
+) -> Result<(), String> {
+    // Remove all incremental snapshots not matching the desired full snapshot slot and hash
+    let snapshot_packages = snapshot_utils::get_incremental_snapshot_archives(ledger_path);
+    let mut found_package = false;
+    for (snapshot_package, (base_slot, snapshot_slot, snapshot_hash, _compression)) in
+        snapshot_packages.iter()
+    {
+        if (*base_slot, (*snapshot_slot, *snapshot_hash))
+            != (full_snapshot_slot, desired_snapshot_hash)
+        {
+            info!("Removing old incremental snapshot: {:?}", snapshot_package);
+            fs::remove_file(snapshot_package)
+                .unwrap_or_else(|err| info!("Failed to remove old incremental snapshot: {:}", err));
+        } else {
+            found_package = true;
+        }
+    }
+
+    if found_package {
+        Ok(())
+    } else {
+        for compression in &[
+            CompressionType::Zstd,
+            CompressionType::Gzip,
+            CompressionType::Bzip2,
+        ] {
+            let desired_snapshot_package = snapshot_utils::get_incremental_snapshot_archive_path(
+                ledger_path,
+                full_snapshot_slot,
+                &desired_snapshot_hash,
+                compression,
+            );
+
+            if download_file(
+                &format!(
+                    "http://{}/{}",
+                    rpc_addr,
+                    desired_snapshot_package
+                        .file_name()
+                        .unwrap()
+                        .to_str()
+                        .unwrap()
+                ),
+                &desired_snapshot_package,
+                use_progress_bar,
+            )
+            .is_ok()
+            {
+                return Ok(());
+            }
+        }
+        Err("Incremental snapshot couldn't be downloaded".to_string())
+    }
+}
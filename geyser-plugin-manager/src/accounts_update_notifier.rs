@@ -0,0 +1,95 @@
+/// Module responsible for notifying plugins of account updates
+use {
+    crate::geyser_plugin_manager::GeyserPluginManager,
+    log::*,
+    solana_geyser_plugin_interface::geyser_plugin_interface::ReplicaAccountInfo,
+    solana_runtime::accounts_update_notifier_interface::AccountsUpdateNotifierInterface,
+    solana_sdk::{account::Account, clock::Slot, pubkey::Pubkey},
+    std::sync::{Arc, RwLock},
+};
+
+#[derive(Debug)]
+pub(crate) struct AccountsUpdateNotifierImpl {
+    plugin_manager: Arc<RwLock<GeyserPluginManager>>,
+}
+
+impl AccountsUpdateNotifierInterface for AccountsUpdateNotifierImpl {
+    fn notify_account_update(&self, slot: Slot, pubkey: &Pubkey, account: &Account) {
+        let account_info = ReplicaAccountInfo {
+            pubkey: pubkey.as_ref(),
+            lamports: account.lamports,
+            owner: account.owner.as_ref(),
+            executable: account.executable,
+            rent_epoch: account.rent_epoch,
+            data: &account.data,
+        };
+
+        let mut plugin_manager = self.plugin_manager.write().unwrap();
+        for plugin in plugin_manager.plugins.iter_mut() {
+            match plugin.update_account(account_info.clone(), slot) {
+                Err(err) => {
+                    error!(
+                        "Failed to update account {} at slot {}, error: {} to plugin {}",
+                        pubkey,
+                        slot,
+                        err,
+                        plugin.name()
+                    )
+                }
+                Ok(_) => {
+                    trace!(
+                        "Successfully updated account {} at slot {} to plugin {}",
+                        pubkey,
+                        slot,
+                        plugin.name()
+                    );
+                }
+            }
+        }
+    }
+}
+
+impl AccountsUpdateNotifierImpl {
+    pub fn new(plugin_manager: Arc<RwLock<GeyserPluginManager>>) -> Self {
+        AccountsUpdateNotifierImpl { plugin_manager }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::geyser_plugin_manager::tests::{Notification, TestPlugin},
+    };
+
+    #[test]
+    fn test_notify_account_update() {
+        // A failing plugin does not keep the others from being notified
+        let failing_plugin = TestPlugin {
+            fail: true,
+            ..TestPlugin::default()
+        };
+        let failing_notifications = failing_plugin.notifications.clone();
+        let plugin = TestPlugin::default();
+        let notifications = plugin.notifications.clone();
+        let mut plugin_manager = GeyserPluginManager::new();
+        plugin_manager.plugins.push(Box::new(failing_plugin));
+        plugin_manager.plugins.push(Box::new(plugin));
+        let notifier = AccountsUpdateNotifierImpl::new(Arc::new(RwLock::new(plugin_manager)));
+
+        let pubkey = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let mut account = Account::new(42, 3, &owner);
+        account.data = vec![1, 2, 3];
+        notifier.notify_account_update(7, &pubkey, &account);
+        account.lamports = 0;
+        notifier.notify_account_update(8, &pubkey, &account);
+
+        let expected = vec![
+            Notification::Account(7, pubkey, 42, owner, vec![1, 2, 3]),
+            Notification::Account(8, pubkey, 0, owner, vec![1, 2, 3]),
+        ];
+        assert_eq!(*failing_notifications.read().unwrap(), expected);
+        assert_eq!(*notifications.read().unwrap(), expected);
+    }
+}
@@ -0,0 +1,248 @@
+/// Managing the Geyser plugins
+use {
+    libloading::{Library, Symbol},
+    log::*,
+    solana_geyser_plugin_interface::geyser_plugin_interface::GeyserPlugin,
+    std::{error::Error, path::Path},
+};
+
+type PluginConstructor = unsafe fn() -> *mut dyn GeyserPlugin;
+
+#[derive(Default, Debug)]
+pub struct GeyserPluginManager {
+    pub plugins: Vec<Box<dyn GeyserPlugin>>,
+    libs: Vec<Library>,
+}
+
+impl GeyserPluginManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load a plugin from the dynamic library at `libpath` and call its `on_load` with the
+    /// config file it was configured by.
+    ///
+    /// # Safety
+    ///
+    /// The library must export a `_create_plugin` function returning a boxed `GeyserPlugin`
+    /// built against the same version of this interface and of the Rust compiler.
+    pub unsafe fn load_plugin(
+        &mut self,
+        libpath: &Path,
+        config_file: &str,
+    ) -> Result<(), Box<dyn Error>> {
+        let lib = Library::new(libpath)?;
+        let constructor: Symbol<PluginConstructor> = lib.get(b"_create_plugin")?;
+        let plugin = Box::from_raw(constructor());
+        self.add_plugin(plugin, config_file)?;
+        self.libs.push(lib);
+        Ok(())
+    }
+
+    /// Call the `on_load` of `plugin` and keep it if that succeeds
+    fn add_plugin(
+        &mut self,
+        mut plugin: Box<dyn GeyserPlugin>,
+        config_file: &str,
+    ) -> Result<(), Box<dyn Error>> {
+        plugin.on_load(config_file)?;
+        self.plugins.push(plugin);
+        Ok(())
+    }
+
+    /// Unload all plugins and the libraries they were loaded from
+    pub fn unload(&mut self) {
+        for mut plugin in self.plugins.drain(..) {
+            info!("Unloading plugin for {:?}", plugin.name());
+            plugin.on_unload();
+        }
+
+        // The plugins must be dropped before the code backing them is unmapped
+        self.libs.clear();
+    }
+
+    pub fn notify_end_of_startup(&mut self) {
+        for plugin in self.plugins.iter_mut() {
+            if let Err(err) = plugin.notify_end_of_startup() {
+                error!(
+                    "Failed to notify the end of startup to plugin {}, error: {}",
+                    plugin.name(),
+                    err
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use {
+        super::*,
+        solana_geyser_plugin_interface::geyser_plugin_interface::{
+            GeyserPluginError, ReplicaAccountInfo, ReplicaTransactionInfo, Result as PluginResult,
+            SlotStatus,
+        },
+        solana_sdk::{clock::Slot, pubkey::Pubkey},
+        std::{
+            fs,
+            sync::{Arc, RwLock},
+        },
+        tempfile::TempDir,
+    };
+
+    #[derive(Debug, PartialEq)]
+    pub(crate) enum Notification {
+        Load(String),
+        Account(Slot, Pubkey, u64, Pubkey, Vec<u8>),
+        Transaction(Slot, bool),
+        Slot(Slot, Option<Slot>, SlotStatus),
+        EndOfStartup,
+        Unload,
+    }
+
+    /// A plugin recording every call made to it, which fails all the calls that can fail if
+    /// `fail` is set
+    #[derive(Debug, Default)]
+    pub(crate) struct TestPlugin {
+        pub notifications: Arc<RwLock<Vec<Notification>>>,
+        pub fail: bool,
+    }
+
+    impl TestPlugin {
+        fn record(&self, notification: Notification) -> PluginResult<()> {
+            self.notifications.write().unwrap().push(notification);
+            if self.fail {
+                Err(GeyserPluginError::Custom("test failure".into()))
+            } else {
+                Ok(())
+            }
+        }
+    }
+
+    impl GeyserPlugin for TestPlugin {
+        fn name(&self) -> &'static str {
+            "test-plugin"
+        }
+
+        fn on_load(&mut self, config_file: &str) -> PluginResult<()> {
+            self.record(Notification::Load(config_file.to_string()))
+        }
+
+        fn on_unload(&mut self) {
+            self.notifications
+                .write()
+                .unwrap()
+                .push(Notification::Unload);
+        }
+
+        fn update_account(&mut self, account: ReplicaAccountInfo, slot: Slot) -> PluginResult<()> {
+            self.record(Notification::Account(
+                slot,
+                Pubkey::new(account.pubkey),
+                account.lamports,
+                Pubkey::new(account.owner),
+                account.data.to_vec(),
+            ))
+        }
+
+        fn notify_end_of_startup(&mut self) -> PluginResult<()> {
+            self.record(Notification::EndOfStartup)
+        }
+
+        fn update_slot_status(
+            &mut self,
+            slot: Slot,
+            parent: Option<Slot>,
+            status: SlotStatus,
+        ) -> PluginResult<()> {
+            self.record(Notification::Slot(slot, parent, status))
+        }
+
+        fn notify_transaction(
+            &mut self,
+            transaction: ReplicaTransactionInfo,
+            slot: Slot,
+        ) -> PluginResult<()> {
+            self.record(Notification::Transaction(slot, transaction.is_vote))
+        }
+    }
+
+    #[test]
+    fn test_load_and_unload() {
+        let plugin = TestPlugin::default();
+        let notifications = plugin.notifications.clone();
+        let mut plugin_manager = GeyserPluginManager::new();
+        plugin_manager
+            .add_plugin(Box::new(plugin), "config.json")
+            .unwrap();
+        assert_eq!(plugin_manager.plugins.len(), 1);
+
+        plugin_manager.unload();
+        assert!(plugin_manager.plugins.is_empty());
+        assert_eq!(
+            *notifications.read().unwrap(),
+            vec![
+                Notification::Load("config.json".to_string()),
+                Notification::Unload
+            ]
+        );
+
+        // Unloading again has nothing left to unload
+        plugin_manager.unload();
+        assert_eq!(notifications.read().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_load_plugin_failures() {
+        // A plugin failing its `on_load` is not kept, and so never unloaded
+        let plugin = TestPlugin {
+            fail: true,
+            ..TestPlugin::default()
+        };
+        let notifications = plugin.notifications.clone();
+        let mut plugin_manager = GeyserPluginManager::new();
+        assert!(plugin_manager
+            .add_plugin(Box::new(plugin), "config.json")
+            .is_err());
+        assert!(plugin_manager.plugins.is_empty());
+        plugin_manager.unload();
+        assert_eq!(
+            *notifications.read().unwrap(),
+            vec![Notification::Load("config.json".to_string())]
+        );
+
+        // Neither a missing file nor a file that is not a shared library can be loaded
+        let dir = TempDir::new().unwrap();
+        let libpath = dir.path().join("libplugin.so");
+        assert!(unsafe { plugin_manager.load_plugin(&libpath, "config.json") }.is_err());
+        fs::write(&libpath, b"not a shared library").unwrap();
+        assert!(unsafe { plugin_manager.load_plugin(&libpath, "config.json") }.is_err());
+        assert!(plugin_manager.plugins.is_empty());
+        assert!(plugin_manager.libs.is_empty());
+    }
+
+    #[test]
+    fn test_notify_end_of_startup() {
+        // A failing plugin does not keep the others from being notified
+        let failing_plugin = TestPlugin {
+            fail: true,
+            ..TestPlugin::default()
+        };
+        let failing_notifications = failing_plugin.notifications.clone();
+        let plugin = TestPlugin::default();
+        let notifications = plugin.notifications.clone();
+        let mut plugin_manager = GeyserPluginManager::new();
+        plugin_manager.plugins.push(Box::new(failing_plugin));
+        plugin_manager.plugins.push(Box::new(plugin));
+
+        plugin_manager.notify_end_of_startup();
+        assert_eq!(
+            *failing_notifications.read().unwrap(),
+            vec![Notification::EndOfStartup]
+        );
+        assert_eq!(
+            *notifications.read().unwrap(),
+            vec![Notification::EndOfStartup]
+        );
+    }
+}
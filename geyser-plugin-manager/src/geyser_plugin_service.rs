@@ -0,0 +1,263 @@
+use {
+    crate::{
+        accounts_update_notifier::AccountsUpdateNotifierImpl,
+        geyser_plugin_manager::GeyserPluginManager,
+        slot_status_notifier::{SlotStatusNotifier, SlotStatusNotifierImpl},
+        transaction_notifier::{TransactionNotifierImpl, TransactionNotifierLock},
+    },
+    log::*,
+    solana_runtime::accounts_update_notifier_interface::AccountsUpdateNotifier,
+    std::{
+        fs::File,
+        io::Read,
+        path::{Path, PathBuf},
+        sync::{Arc, RwLock},
+    },
+    thiserror::Error,
+};
+
+#[derive(Error, Debug)]
+pub enum GeyserPluginServiceError {
+    #[error("Cannot open the plugin config file")]
+    CannotOpenConfigFile(String),
+
+    #[error("Cannot read the plugin config file")]
+    CannotReadConfigFile(String),
+
+    #[error("The config file is not in a valid Json format")]
+    InvalidConfigFileFormat(String),
+
+    #[error("Plugin library path is not specified in the config file")]
+    LibPathNotSet,
+
+    #[error("Invalid plugin path")]
+    InvalidPluginPath,
+
+    #[error("Cannot load plugin shared library")]
+    PluginLoadError(String),
+}
+
+/// The service managing the Geyser plugin workflow.
+pub struct GeyserPluginService {
+    plugin_manager: Arc<RwLock<GeyserPluginManager>>,
+    accounts_update_notifier: AccountsUpdateNotifier,
+    transaction_notifier: TransactionNotifierLock,
+    slot_status_notifier: SlotStatusNotifier,
+}
+
+impl GeyserPluginService {
+    /// Creates and returns the GeyserPluginService.
+    /// # Arguments
+    /// * `geyser_plugin_config_files` - the config files of the plugins, in JSON format. Each
+    ///   config file must have a "libpath" field with the path of the shared library
+    ///   implementing the plugin; a relative path is resolved against the directory of the
+    ///   config file. The config file is also passed to the plugin's `on_load`, so it can
+    ///   carry any plugin specific settings.
+    pub fn new(geyser_plugin_config_files: &[PathBuf]) -> Result<Self, GeyserPluginServiceError> {
+        info!(
+            "Starting GeyserPluginService from config files: {:?}",
+            geyser_plugin_config_files
+        );
+        let mut plugin_manager = GeyserPluginManager::new();
+        for geyser_plugin_config_file in geyser_plugin_config_files {
+            Self::load_plugin(&mut plugin_manager, geyser_plugin_config_file)?;
+        }
+        info!("Started GeyserPluginService");
+        Ok(Self::new_with_plugin_manager(plugin_manager))
+    }
+
+    fn new_with_plugin_manager(plugin_manager: GeyserPluginManager) -> Self {
+        let plugin_manager = Arc::new(RwLock::new(plugin_manager));
+        let accounts_update_notifier: AccountsUpdateNotifier = Arc::new(RwLock::new(
+            AccountsUpdateNotifierImpl::new(plugin_manager.clone()),
+        ));
+        let transaction_notifier: TransactionNotifierLock = Arc::new(RwLock::new(
+            TransactionNotifierImpl::new(plugin_manager.clone()),
+        ));
+        let slot_status_notifier: SlotStatusNotifier = Arc::new(RwLock::new(
+            SlotStatusNotifierImpl::new(plugin_manager.clone()),
+        ));
+        Self {
+            plugin_manager,
+            accounts_update_notifier,
+            transaction_notifier,
+            slot_status_notifier,
+        }
+    }
+
+    fn load_plugin(
+        plugin_manager: &mut GeyserPluginManager,
+        geyser_plugin_config_file: &Path,
+    ) -> Result<(), GeyserPluginServiceError> {
+        let mut file = File::open(geyser_plugin_config_file).map_err(|err| {
+            GeyserPluginServiceError::CannotOpenConfigFile(format!(
+                "Failed to open the plugin config file {:?}, error: {:?}",
+                geyser_plugin_config_file, err
+            ))
+        })?;
+
+        let mut contents = String::new();
+        file.read_to_string(&mut contents).map_err(|err| {
+            GeyserPluginServiceError::CannotReadConfigFile(format!(
+                "Failed to read the plugin config file {:?}, error: {:?}",
+                geyser_plugin_config_file, err
+            ))
+        })?;
+
+        let config: serde_json::Value = serde_json::from_str(&contents).map_err(|err| {
+            GeyserPluginServiceError::InvalidConfigFileFormat(format!(
+                "The config file {:?} is not in a valid Json format, error: {:?}",
+                geyser_plugin_config_file, err
+            ))
+        })?;
+
+        let mut libpath = PathBuf::from(
+            config["libpath"]
+                .as_str()
+                .ok_or(GeyserPluginServiceError::LibPathNotSet)?,
+        );
+        if libpath.is_relative() {
+            let config_dir = geyser_plugin_config_file
+                .parent()
+                .ok_or(GeyserPluginServiceError::InvalidPluginPath)?;
+            libpath = config_dir.join(libpath);
+        }
+
+        let config_file = geyser_plugin_config_file
+            .to_str()
+            .ok_or(GeyserPluginServiceError::InvalidPluginPath)?;
+
+        unsafe { plugin_manager.load_plugin(&libpath, config_file) }.map_err(|err| {
+            GeyserPluginServiceError::PluginLoadError(format!(
+                "Failed to load the plugin {:?}, error: {}",
+                libpath, err
+            ))
+        })
+    }
+
+    pub fn accounts_update_notifier(&self) -> AccountsUpdateNotifier {
+        self.accounts_update_notifier.clone()
+    }
+
+    pub fn transaction_notifier(&self) -> TransactionNotifierLock {
+        self.transaction_notifier.clone()
+    }
+
+    pub fn slot_status_notifier(&self) -> SlotStatusNotifier {
+        self.slot_status_notifier.clone()
+    }
+
+    /// Tell the plugins that the validator is done loading and replaying its ledger
+    pub fn notify_end_of_startup(&self) {
+        self.plugin_manager.write().unwrap().notify_end_of_startup();
+    }
+
+    pub fn join(self) {
+        self.plugin_manager.write().unwrap().unload();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::geyser_plugin_manager::tests::{Notification, TestPlugin},
+        solana_geyser_plugin_interface::geyser_plugin_interface::SlotStatus,
+        solana_sdk::{account::Account, pubkey::Pubkey, signature::Keypair, system_transaction},
+        solana_transaction_status::TransactionStatusMeta,
+        std::{fs, io::Write},
+        tempfile::TempDir,
+    };
+
+    fn write_config(dir: &TempDir, contents: &str) -> PathBuf {
+        let path = dir.path().join("config.json");
+        let mut file = fs::File::create(&path).unwrap();
+        file.write_all(contents.as_bytes()).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_load_plugin_config_errors() {
+        let dir = TempDir::new().unwrap();
+        assert!(matches!(
+            GeyserPluginService::new(&[dir.path().join("missing.json")]),
+            Err(GeyserPluginServiceError::CannotOpenConfigFile(_))
+        ));
+
+        let config_file = write_config(&dir, "{ not json");
+        assert!(matches!(
+            GeyserPluginService::new(&[config_file]),
+            Err(GeyserPluginServiceError::InvalidConfigFileFormat(_))
+        ));
+
+        let config_file = write_config(&dir, r#"{"connection": "localhost"}"#);
+        assert!(matches!(
+            GeyserPluginService::new(&[config_file]),
+            Err(GeyserPluginServiceError::LibPathNotSet)
+        ));
+
+        let config_file = write_config(&dir, r#"{"libpath": "libmissing.so"}"#);
+        match GeyserPluginService::new(&[config_file]) {
+            Err(GeyserPluginServiceError::PluginLoadError(msg)) => {
+                // Relative library paths are resolved against the config file directory
+                assert!(msg.contains(dir.path().join("libmissing.so").to_str().unwrap()));
+            }
+            _ => panic!("expected a plugin load error"),
+        }
+    }
+
+    #[test]
+    fn test_notifiers() {
+        let plugin = TestPlugin::default();
+        let notifications = plugin.notifications.clone();
+        let mut plugin_manager = GeyserPluginManager::new();
+        plugin_manager.plugins.push(Box::new(plugin));
+        let service = GeyserPluginService::new_with_plugin_manager(plugin_manager);
+
+        let pubkey = Pubkey::new_unique();
+        service
+            .accounts_update_notifier()
+            .read()
+            .unwrap()
+            .notify_account_update(1, &pubkey, &Account::new(42, 0, &Pubkey::default()));
+
+        let keypair = Keypair::new();
+        let transaction = system_transaction::transfer(
+            &keypair,
+            &Pubkey::new_unique(),
+            1,
+            solana_sdk::hash::Hash::default(),
+        );
+        service
+            .transaction_notifier()
+            .read()
+            .unwrap()
+            .notify_transaction(
+                1,
+                &transaction.signatures[0],
+                &TransactionStatusMeta::default(),
+                &transaction,
+            );
+
+        let slot_status_notifier = service.slot_status_notifier();
+        let slot_status_notifier = slot_status_notifier.read().unwrap();
+        slot_status_notifier.notify_slot_processed(1, Some(0));
+        slot_status_notifier.notify_slot_confirmed(1, Some(0));
+        slot_status_notifier.notify_slot_rooted(1, Some(0));
+        service.notify_end_of_startup();
+        service.join();
+
+        assert_eq!(
+            *notifications.read().unwrap(),
+            vec![
+                Notification::Account(1, pubkey, 42, Pubkey::default(), vec![]),
+                Notification::Transaction(1, false),
+                Notification::Slot(1, Some(0), SlotStatus::Processed),
+                Notification::Slot(1, Some(0), SlotStatus::Confirmed),
+                Notification::Slot(1, Some(0), SlotStatus::Rooted),
+                Notification::EndOfStartup,
+                Notification::Unload,
+            ]
+        );
+    }
+}
@@ -0,0 +1,5 @@
+pub mod accounts_update_notifier;
+pub mod geyser_plugin_manager;
+pub mod geyser_plugin_service;
+pub mod slot_status_notifier;
+pub mod transaction_notifier;
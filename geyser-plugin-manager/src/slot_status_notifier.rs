@@ -0,0 +1,106 @@
+/// Module responsible for notifying plugins of slot status changes
+use {
+    crate::geyser_plugin_manager::GeyserPluginManager,
+    log::*,
+    solana_geyser_plugin_interface::geyser_plugin_interface::SlotStatus,
+    solana_sdk::clock::Slot,
+    std::sync::{Arc, RwLock},
+};
+
+pub trait SlotStatusNotifierInterface {
+    /// Notified when a slot is optimistically confirmed
+    fn notify_slot_confirmed(&self, slot: Slot, parent: Option<Slot>);
+
+    /// Notified when a slot is marked frozen.
+    fn notify_slot_processed(&self, slot: Slot, parent: Option<Slot>);
+
+    /// Notified when a slot is rooted.
+    fn notify_slot_rooted(&self, slot: Slot, parent: Option<Slot>);
+}
+
+pub type SlotStatusNotifier = Arc<RwLock<dyn SlotStatusNotifierInterface + Sync + Send>>;
+
+pub(crate) struct SlotStatusNotifierImpl {
+    plugin_manager: Arc<RwLock<GeyserPluginManager>>,
+}
+
+impl SlotStatusNotifierInterface for SlotStatusNotifierImpl {
+    fn notify_slot_confirmed(&self, slot: Slot, parent: Option<Slot>) {
+        self.notify_slot_status(slot, parent, SlotStatus::Confirmed);
+    }
+
+    fn notify_slot_processed(&self, slot: Slot, parent: Option<Slot>) {
+        self.notify_slot_status(slot, parent, SlotStatus::Processed);
+    }
+
+    fn notify_slot_rooted(&self, slot: Slot, parent: Option<Slot>) {
+        self.notify_slot_status(slot, parent, SlotStatus::Rooted);
+    }
+}
+
+impl SlotStatusNotifierImpl {
+    pub fn new(plugin_manager: Arc<RwLock<GeyserPluginManager>>) -> Self {
+        Self { plugin_manager }
+    }
+
+    fn notify_slot_status(&self, slot: Slot, parent: Option<Slot>, slot_status: SlotStatus) {
+        let mut plugin_manager = self.plugin_manager.write().unwrap();
+        for plugin in plugin_manager.plugins.iter_mut() {
+            match plugin.update_slot_status(slot, parent, slot_status) {
+                Err(err) => {
+                    error!(
+                        "Failed to update slot status at slot {}, error: {} to plugin {}",
+                        slot,
+                        err,
+                        plugin.name()
+                    )
+                }
+                Ok(_) => {
+                    trace!(
+                        "Successfully updated slot status at slot {} to plugin {}",
+                        slot,
+                        plugin.name()
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::geyser_plugin_manager::tests::{Notification, TestPlugin},
+    };
+
+    #[test]
+    fn test_notify_slot_status() {
+        // A failing plugin does not keep the others from being notified
+        let failing_plugin = TestPlugin {
+            fail: true,
+            ..TestPlugin::default()
+        };
+        let failing_notifications = failing_plugin.notifications.clone();
+        let plugin = TestPlugin::default();
+        let notifications = plugin.notifications.clone();
+        let mut plugin_manager = GeyserPluginManager::new();
+        plugin_manager.plugins.push(Box::new(failing_plugin));
+        plugin_manager.plugins.push(Box::new(plugin));
+        let notifier = SlotStatusNotifierImpl::new(Arc::new(RwLock::new(plugin_manager)));
+
+        notifier.notify_slot_processed(2, Some(1));
+        notifier.notify_slot_confirmed(2, Some(1));
+        notifier.notify_slot_rooted(2, Some(1));
+        notifier.notify_slot_rooted(0, None);
+
+        let expected = vec![
+            Notification::Slot(2, Some(1), SlotStatus::Processed),
+            Notification::Slot(2, Some(1), SlotStatus::Confirmed),
+            Notification::Slot(2, Some(1), SlotStatus::Rooted),
+            Notification::Slot(0, None, SlotStatus::Rooted),
+        ];
+        assert_eq!(*failing_notifications.read().unwrap(), expected);
+        assert_eq!(*notifications.read().unwrap(), expected);
+    }
+}
@@ -0,0 +1,74 @@
+/// Module responsible for notifying plugins of transactions
+use {
+    crate::geyser_plugin_manager::GeyserPluginManager,
+    log::*,
+    solana_geyser_plugin_interface::geyser_plugin_interface::ReplicaTransactionInfo,
+    solana_runtime::bank::is_simple_vote_transaction,
+    solana_sdk::{clock::Slot, signature::Signature, transaction::Transaction},
+    solana_transaction_status::TransactionStatusMeta,
+    std::sync::{Arc, RwLock},
+};
+
+/// Interface for notifying transaction changes
+pub trait TransactionNotifierInterface {
+    fn notify_transaction(
+        &self,
+        slot: Slot,
+        signature: &Signature,
+        transaction_status_meta: &TransactionStatusMeta,
+        transaction: &Transaction,
+    );
+}
+
+pub type TransactionNotifierLock = Arc<RwLock<dyn TransactionNotifierInterface + Sync + Send>>;
+
+/// This implementation of TransactionNotifierInterface passes notifications to plugins
+pub(crate) struct TransactionNotifierImpl {
+    plugin_manager: Arc<RwLock<GeyserPluginManager>>,
+}
+
+impl TransactionNotifierInterface for TransactionNotifierImpl {
+    fn notify_transaction(
+        &self,
+        slot: Slot,
+        signature: &Signature,
+        transaction_status_meta: &TransactionStatusMeta,
+        transaction: &Transaction,
+    ) {
+        let transaction_info = ReplicaTransactionInfo {
+            signature,
+            is_vote: is_simple_vote_transaction(transaction),
+            transaction,
+            transaction_status_meta,
+        };
+
+        let mut plugin_manager = self.plugin_manager.write().unwrap();
+        for plugin in plugin_manager.plugins.iter_mut() {
+            match plugin.notify_transaction(transaction_info.clone(), slot) {
+                Err(err) => {
+                    error!(
+                        "Failed to notify transaction {} at slot {}, error: {} to plugin {}",
+                        signature,
+                        slot,
+                        err,
+                        plugin.name()
+                    )
+                }
+                Ok(_) => {
+                    trace!(
+                        "Successfully notified transaction {} at slot {} to plugin {}",
+                        signature,
+                        slot,
+                        plugin.name()
+                    );
+                }
+            }
+        }
+    }
+}
+
+impl TransactionNotifierImpl {
+    pub fn new(plugin_manager: Arc<RwLock<GeyserPluginManager>>) -> Self {
+        Self { plugin_manager }
+    }
+}
@@ -2,42 +2,51 @@
 use clap::{value_t, value_t_or_exit, App, AppSettings, Arg, ArgMatches, SubCommand};
 use solana_clap_utils::{
     input_parsers::pubkey_of,
-    input_validators::{is_slot, is_valid_pubkey},
+    input_validators::{is_parsable, is_slot, is_valid_pubkey},
 };
 use solana_cli_output::display::println_transaction;
-use solana_ledger::{blockstore::Blockstore, blockstore_db::AccessType};
+use solana_ledger::{
+    bigtable_upload::ConfirmedBlockUploadConfig, blockstore::Blockstore, blockstore_db::AccessType,
+};
 use solana_sdk::{clock::Slot, pubkey::Pubkey, signature::Signature};
+use solana_storage_bigtable::LedgerStorage;
 use solana_transaction_status::ConfirmedBlock;
 use std::{
-    path::Path,
+    num::NonZeroUsize,
+    path::{Path, PathBuf},
     process::exit,
     result::Result,
     sync::{atomic::AtomicBool, Arc},
 };
 
+async fn connect(
+    read_only: bool,
+    local_storage_path: Option<&Path>,
+) -> Result<LedgerStorage, Box<dyn std::error::Error>> {
+    LedgerStorage::new_with_local_path(read_only, local_storage_path)
+        .await
+        .map_err(|err| format!("Failed to connect to storage: {:?}", err).into())
+}
+
 async fn upload(
+    bigtable: LedgerStorage,
     blockstore: Blockstore,
     starting_slot: Slot,
     ending_slot: Option<Slot>,
-    allow_missing_metadata: bool,
+    config: ConfirmedBlockUploadConfig,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let bigtable = solana_storage_bigtable::LedgerStorage::new(false)
-        .await
-        .map_err(|err| format!("Failed to connect to storage: {:?}", err))?;
-
     solana_ledger::bigtable_upload::upload_confirmed_blocks(
         Arc::new(blockstore),
         bigtable,
         starting_slot,
         ending_slot,
-        allow_missing_metadata,
+        config,
         Arc::new(AtomicBool::new(false)),
     )
     .await
 }
 
-async fn first_available_block() -> Result<(), Box<dyn std::error::Error>> {
-    let bigtable = solana_storage_bigtable::LedgerStorage::new(true).await?;
+async fn first_available_block(bigtable: LedgerStorage) -> Result<(), Box<dyn std::error::Error>> {
     match bigtable.get_first_available_block().await? {
         Some(block) => println!("{}", block),
         None => println!("No blocks available"),
@@ -46,11 +55,7 @@ async fn first_available_block() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
-async fn block(slot: Slot) -> Result<(), Box<dyn std::error::Error>> {
-    let bigtable = solana_storage_bigtable::LedgerStorage::new(false)
-        .await
-        .map_err(|err| format!("Failed to connect to storage: {:?}", err))?;
-
+async fn block(bigtable: LedgerStorage, slot: Slot) -> Result<(), Box<dyn std::error::Error>> {
     let block = bigtable.get_confirmed_block(slot).await?;
 
     println!("Slot: {}", slot);
@@ -74,11 +79,11 @@ async fn block(slot: Slot) -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
-async fn blocks(starting_slot: Slot, limit: usize) -> Result<(), Box<dyn std::error::Error>> {
-    let bigtable = solana_storage_bigtable::LedgerStorage::new(false)
-        .await
-        .map_err(|err| format!("Failed to connect to storage: {:?}", err))?;
-
+async fn blocks(
+    bigtable: LedgerStorage,
+    starting_slot: Slot,
+    limit: usize,
+) -> Result<(), Box<dyn std::error::Error>> {
     let slots = bigtable.get_confirmed_blocks(starting_slot, limit).await?;
     println!("{:?}", slots);
     println!("{} blocks found", slots.len());
@@ -86,11 +91,11 @@ async fn blocks(starting_slot: Slot, limit: usize) -> Result<(), Box<dyn std::er
     Ok(())
 }
 
-async fn confirm(signature: &Signature, verbose: bool) -> Result<(), Box<dyn std::error::Error>> {
-    let bigtable = solana_storage_bigtable::LedgerStorage::new(false)
-        .await
-        .map_err(|err| format!("Failed to connect to storage: {:?}", err))?;
-
+async fn confirm(
+    bigtable: LedgerStorage,
+    signature: &Signature,
+    verbose: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
     let transaction_status = bigtable.get_signature_status(signature).await?;
 
     if verbose {
@@ -119,6 +124,7 @@ async fn confirm(signature: &Signature, verbose: bool) -> Result<(), Box<dyn std
 }
 
 pub async fn transaction_history(
+    bigtable: LedgerStorage,
     address: &Pubkey,
     mut limit: usize,
     mut before: Option<Signature>,
@@ -127,8 +133,6 @@ pub async fn transaction_history(
     show_transactions: bool,
     query_chunk_size: usize,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let bigtable = solana_storage_bigtable::LedgerStorage::new(true).await?;
-
     let mut loaded_block: Option<(Slot, ConfirmedBlock)> = None;
     while limit > 0 {
         let results = bigtable
@@ -215,6 +219,16 @@ impl BigTableSubCommand for App<'_, '_> {
             SubCommand::with_name("bigtable")
                 .about("Ledger data on a BigTable instance")
                 .setting(AppSettings::ArgRequiredElseHelp)
+                .arg(
+                    Arg::with_name("local_storage_path")
+                        .long("local-storage")
+                        .value_name("DIR")
+                        .takes_value(true)
+                        .help(
+                            "Use the ledger storage kept in a local database at DIR \
+                             instead of a BigTable instance",
+                        ),
+                )
                 .subcommand(
                     SubCommand::with_name("upload")
                         .about("Upload the ledger to BigTable")
@@ -243,6 +257,34 @@ impl BigTableSubCommand for App<'_, '_> {
                                 .long("allow-missing-metadata")
                                 .takes_value(false)
                                 .help("Don't panic if transaction metadata is missing"),
+                        )
+                        .arg(
+                            Arg::with_name("upload_concurrency")
+                                .long("upload-concurrency")
+                                .validator(is_parsable::<NonZeroUsize>)
+                                .value_name("NUMBER")
+                                .takes_value(true)
+                                .default_value("32")
+                                .help("Upload this many blocks in parallel"),
+                        )
+                        .arg(
+                            Arg::with_name("checkpoint")
+                                .long("checkpoint")
+                                .value_name("FILE")
+                                .takes_value(true)
+                                .help(
+                                    "Record the last slot uploaded in this file, and resume \
+                                     from it when uploading again",
+                                ),
+                        )
+                        .arg(
+                            Arg::with_name("verify")
+                                .long("verify")
+                                .takes_value(false)
+                                .help(
+                                    "After uploading, read every block back from BigTable and \
+                                     compare it with the ledger",
+                                ),
                         ),
                 )
                 .subcommand(
@@ -379,32 +421,49 @@ impl BigTableSubCommand for App<'_, '_> {
 
 pub fn bigtable_process_command(ledger_path: &Path, matches: &ArgMatches<'_>) {
     let mut runtime = tokio::runtime::Runtime::new().unwrap();
+    let local_storage_path = value_t!(matches, "local_storage_path", PathBuf).ok();
+    let local_storage_path = local_storage_path.as_deref();
 
     let future = match matches.subcommand() {
         ("upload", Some(arg_matches)) => {
             let starting_slot = value_t!(arg_matches, "starting_slot", Slot).unwrap_or(0);
             let ending_slot = value_t!(arg_matches, "ending_slot", Slot).ok();
-            let allow_missing_metadata = arg_matches.is_present("allow_missing_metadata");
+            let num_blocks_to_upload_in_parallel =
+                value_t_or_exit!(arg_matches, "upload_concurrency", NonZeroUsize).get();
+            let config = ConfirmedBlockUploadConfig {
+                num_blocks_to_upload_in_parallel,
+                block_read_ahead_depth: num_blocks_to_upload_in_parallel * 2,
+                allow_missing_metadata: arg_matches.is_present("allow_missing_metadata"),
+                checkpoint_path: value_t!(arg_matches, "checkpoint", PathBuf).ok(),
+                verify: arg_matches.is_present("verify"),
+            };
             let blockstore =
                 crate::open_blockstore(&ledger_path, AccessType::TryPrimaryThenSecondary, None);
 
-            runtime.block_on(upload(
-                blockstore,
-                starting_slot,
-                ending_slot,
-                allow_missing_metadata,
-            ))
+            runtime.block_on(async {
+                let bigtable = connect(false, local_storage_path).await?;
+                upload(bigtable, blockstore, starting_slot, ending_slot, config).await
+            })
         }
-        ("first-available-block", Some(_arg_matches)) => runtime.block_on(first_available_block()),
+        ("first-available-block", Some(_arg_matches)) => runtime.block_on(async {
+            let bigtable = connect(true, local_storage_path).await?;
+            first_available_block(bigtable).await
+        }),
         ("block", Some(arg_matches)) => {
             let slot = value_t_or_exit!(arg_matches, "slot", Slot);
-            runtime.block_on(block(slot))
+            runtime.block_on(async {
+                let bigtable = connect(false, local_storage_path).await?;
+                block(bigtable, slot).await
+            })
         }
         ("blocks", Some(arg_matches)) => {
             let starting_slot = value_t_or_exit!(arg_matches, "starting_slot", Slot);
             let limit = value_t_or_exit!(arg_matches, "limit", usize);
 
-            runtime.block_on(blocks(starting_slot, limit))
+            runtime.block_on(async {
+                let bigtable = connect(false, local_storage_path).await?;
+                blocks(bigtable, starting_slot, limit).await
+            })
         }
         ("confirm", Some(arg_matches)) => {
             let signature = arg_matches
@@ -414,7 +473,10 @@ pub fn bigtable_process_command(ledger_path: &Path, matches: &ArgMatches<'_>) {
                 .expect("Invalid signature");
             let verbose = arg_matches.is_present("verbose");
 
-            runtime.block_on(confirm(&signature, verbose))
+            runtime.block_on(async {
+                let bigtable = connect(false, local_storage_path).await?;
+                confirm(bigtable, &signature, verbose).await
+            })
         }
         ("transaction-history", Some(arg_matches)) => {
             let address = pubkey_of(arg_matches, "address").unwrap();
@@ -429,15 +491,20 @@ pub fn bigtable_process_command(ledger_path: &Path, matches: &ArgMatches<'_>) {
             let verbose = arg_matches.is_present("verbose");
             let show_transactions = arg_matches.is_present("show_transactions");
 
-            runtime.block_on(transaction_history(
-                &address,
-                limit,
-                before,
-                until,
-                verbose,
-                show_transactions,
-                query_chunk_size,
-            ))
+            runtime.block_on(async {
+                let bigtable = connect(true, local_storage_path).await?;
+                transaction_history(
+                    bigtable,
+                    &address,
+                    limit,
+                    before,
+                    until,
+                    verbose,
+                    show_transactions,
+                    query_chunk_size,
+                )
+                .await
+            })
         }
         _ => unreachable!(),
     };
@@ -8,15 +8,19 @@ use regex::Regex;
 use serde::Serialize;
 use serde_json::json;
 use solana_clap_utils::{
-    input_parsers::{cluster_type_of, pubkey_of, pubkeys_of},
-    input_validators::{is_parsable, is_pubkey_or_keypair, is_slot, is_valid_percentage},
+    input_parsers::{cluster_type_of, keypair_of, pubkey_of, pubkeys_of},
+    input_validators::{
+        is_parsable, is_pubkey, is_pubkey_or_keypair, is_slot, is_valid_percentage,
+    },
 };
+use solana_core::consensus::Tower;
 use solana_ledger::entry::Entry;
 use solana_ledger::{
     ancestor_iterator::AncestorIterator,
     bank_forks_utils,
     blockstore::{create_new_ledger, Blockstore, PurgeType},
     blockstore_db::{self, AccessType, BlockstoreRecoveryMode, Column, Database},
+    blockstore_meta::AccountHistoryEntry,
     blockstore_processor::ProcessOptions,
     rooted_slot_iterator::RootedSlotIterator,
 };
@@ -24,6 +28,8 @@ use solana_runtime::{
     bank::{Bank, RewardCalculationEvent},
     bank_forks::{BankForks, CompressionType, SnapshotConfig},
     hardened_unpack::{open_genesis_config, MAX_GENESIS_ARCHIVE_UNPACKED_SIZE},
+    replay_tracer::{ReplayTraceEvent, ReplayTracerInterface},
+    snapshot_package::SnapshotType,
     snapshot_utils,
     snapshot_utils::SnapshotVersion,
 };
@@ -33,7 +39,7 @@ use solana_sdk::{
     feature::{self, Feature},
     feature_set,
     genesis_config::{ClusterType, GenesisConfig},
-    hash::Hash,
+    hash::{self, Hash},
     inflation::Inflation,
     native_token::{lamports_to_sol, sol_to_lamports, Sol},
     pubkey::Pubkey,
@@ -55,7 +61,7 @@ use std::{
     path::{Path, PathBuf},
     process::{exit, Command, Stdio},
     str::FromStr,
-    sync::Arc,
+    sync::{Arc, Mutex},
 };
 
 mod bigtable;
@@ -618,6 +624,32 @@ fn analyze_storage(database: &Database) -> Result<(), String> {
     Ok(())
 }
 
+// Towers are saved as `tower-<identity>.bin` in the ledger directory
+fn find_tower_identity(ledger_path: &Path) -> Option<Pubkey> {
+    let mut identities = fs::read_dir(ledger_path)
+        .ok()?
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            // a tower may only be left at its tmp or backup path
+            let file_name = path.file_name()?.to_str()?;
+            let identity = ["bin", "bin.new", "bin.bak"].iter().find_map(|extension| {
+                file_name
+                    .strip_prefix("tower-")?
+                    .strip_suffix(extension)?
+                    .strip_suffix('.')
+            })?;
+            Pubkey::from_str(identity).ok()
+        })
+        .collect::<Vec<_>>();
+    identities.sort();
+    identities.dedup();
+    if identities.len() == 1 {
+        identities.pop()
+    } else {
+        None
+    }
+}
+
 fn open_blockstore(
     ledger_path: &Path,
     access_type: AccessType,
@@ -673,6 +705,7 @@ fn load_bank_forks(
             snapshot_archive_path.unwrap_or_else(|| ledger_path.clone());
         Some(SnapshotConfig {
             snapshot_interval_slots: 0, // Value doesn't matter
+            incremental_snapshot_interval_slots: std::u64::MAX,
             snapshot_package_output_path,
             snapshot_path,
             compression: CompressionType::Bzip2,
@@ -708,12 +741,193 @@ fn load_bank_forks(
     )
 }
 
+// Archives a full snapshot of `bank`, which must already be squashed and hashed, into
+// `output_directory`.  Returns the path of the new archive
+fn create_snapshot_archive(
+    bank: &Bank,
+    ledger_path: &Path,
+    output_directory: &Path,
+    snapshot_version: SnapshotVersion,
+) -> snapshot_utils::Result<PathBuf> {
+    let temp_dir = tempfile::tempdir_in(ledger_path)?;
+    let storages: Vec<_> = bank.get_snapshot_storages();
+    let slot_snapshot_paths =
+        snapshot_utils::add_snapshot(&temp_dir, bank, &storages, snapshot_version)?;
+    let package = snapshot_utils::package_snapshot(
+        bank,
+        &slot_snapshot_paths,
+        &temp_dir,
+        bank.src.slot_deltas(&bank.src.roots()),
+        output_directory,
+        storages,
+        CompressionType::Zstd,
+        snapshot_version,
+        SnapshotType::FullSnapshot,
+    )?;
+    snapshot_utils::archive_snapshot_package(&package)?;
+    Ok(package.tar_output_file.clone())
+}
+
+// Copies the data shreds, roots and dead slot markers of `[starting_slot, ending_slot]` from
+// `source` into `target`.  Returns the number of slots copied
+fn copy_blockstore_range(
+    source: &Blockstore,
+    target: &Blockstore,
+    starting_slot: Slot,
+    ending_slot: Slot,
+) -> usize {
+    let mut roots = vec![];
+    let mut num_slots = 0;
+    for (slot, _meta) in source.slot_meta_iterator(starting_slot).unwrap() {
+        if slot > ending_slot {
+            break;
+        }
+        let shreds = source
+            .get_data_shreds_for_slot(slot, 0)
+            .unwrap_or_else(|err| {
+                eprintln!("Failed to read shreds for slot {}: {:?}", slot, err);
+                exit(1);
+            });
+        target
+            .insert_shreds(shreds, None, true)
+            .unwrap_or_else(|err| {
+                eprintln!("Failed to insert shreds for slot {}: {:?}", slot, err);
+                exit(1);
+            });
+        if source.is_dead(slot) {
+            target.set_dead_slot(slot).unwrap();
+        }
+        if source.is_root(slot) {
+            roots.push(slot);
+        }
+        num_slots += 1;
+    }
+    target.set_roots(&roots).unwrap();
+    num_slots
+}
+
 fn open_genesis_config_by(ledger_path: &Path, matches: &ArgMatches<'_>) -> GenesisConfig {
     let max_genesis_archive_unpacked_size =
         value_t_or_exit!(matches, "max_genesis_archive_unpacked_size", u64);
     open_genesis_config(ledger_path, max_genesis_archive_unpacked_size)
 }
 
+// Replays the ledger up to `slot` and returns the bank of that slot
+fn load_bank_at_slot(
+    arg_matches: &ArgMatches,
+    ledger_path: &PathBuf,
+    slot: Slot,
+    wal_recovery_mode: Option<BlockstoreRecoveryMode>,
+    snapshot_archive_path: Option<PathBuf>,
+) -> Arc<Bank> {
+    let process_options = ProcessOptions {
+        dev_halt_at_slot: Some(slot),
+        new_hard_forks: hardforks_of(arg_matches, "hard_forks"),
+        poh_verify: false,
+        ..ProcessOptions::default()
+    };
+    match load_bank_forks(
+        arg_matches,
+        ledger_path,
+        &open_genesis_config_by(ledger_path, arg_matches),
+        process_options,
+        AccessType::TryPrimaryThenSecondary,
+        wal_recovery_mode,
+        snapshot_archive_path,
+    ) {
+        Ok((bank_forks, _leader_schedule_cache, _snapshot_hash)) => {
+            bank_forks.get(slot).cloned().unwrap_or_else(|| {
+                eprintln!("Slot {} is not available in {:?}", slot, ledger_path);
+                exit(1);
+            })
+        }
+        Err(err) => {
+            eprintln!("Failed to load ledger {:?}: {:?}", ledger_path, err);
+            exit(1);
+        }
+    }
+}
+
+// Describes how two versions of an account written in the same slot differ, if they do
+fn describe_account_diff(
+    (account, hash): &(Account, Hash),
+    (other_account, other_hash): &(Account, Hash),
+) -> Option<String> {
+    let mut diffs = vec![];
+    if account.lamports != other_account.lamports {
+        diffs.push(format!(
+            "lamports {} vs {}",
+            account.lamports, other_account.lamports
+        ));
+    }
+    if account.owner != other_account.owner {
+        diffs.push(format!(
+            "owner {} vs {}",
+            account.owner, other_account.owner
+        ));
+    }
+    if account.rent_epoch != other_account.rent_epoch {
+        diffs.push(format!(
+            "rent_epoch {} vs {}",
+            account.rent_epoch, other_account.rent_epoch
+        ));
+    }
+    if account.executable != other_account.executable {
+        diffs.push(format!(
+            "executable {} vs {}",
+            account.executable, other_account.executable
+        ));
+    }
+    if account.data != other_account.data {
+        diffs.push(format!(
+            "data {} bytes ({}) vs {} bytes ({})",
+            account.data.len(),
+            hash::hash(&account.data),
+            other_account.data.len(),
+            hash::hash(&other_account.data),
+        ));
+    }
+    if diffs.is_empty() && hash != other_hash {
+        diffs.push(format!("hash {} vs {}", hash, other_hash));
+    }
+    if diffs.is_empty() {
+        None
+    } else {
+        Some(diffs.join(", "))
+    }
+}
+
+// Writes the replay events of a single slot as JSON lines, so that the traces of two nodes
+// can be diffed
+struct JsonReplayTracer {
+    slot: Slot,
+    output: Mutex<Box<dyn Write + Send>>,
+}
+
+impl std::fmt::Debug for JsonReplayTracer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("JsonReplayTracer")
+            .field("slot", &self.slot)
+            .finish()
+    }
+}
+
+impl ReplayTracerInterface for JsonReplayTracer {
+    fn trace_slot(&self, slot: Slot) -> bool {
+        slot == self.slot
+    }
+
+    fn trace(&self, event: ReplayTraceEvent) {
+        let mut output = self.output.lock().unwrap();
+        serde_json::to_writer(&mut *output, &event)
+            .and_then(|()| writeln!(output).map_err(serde_json::Error::io))
+            .unwrap_or_else(|err| {
+                eprintln!("Unable to write trace: {}", err);
+                exit(1);
+            });
+    }
+}
+
 fn assert_capitalization(bank: &Bank) {
     assert!(bank.calculate_and_verify_capitalization());
 }
@@ -977,6 +1191,32 @@ fn main() {
             .about("Prints the hash of the working bank after reading the ledger")
             .arg(&max_genesis_archive_unpacked_size_arg)
         )
+        .subcommand(
+            SubCommand::with_name("bank-hash-diff")
+            .about("Replay a slot on this ledger and on another one, and print the accounts \
+                    written by the slot whose lamports, owner, rent epoch, executable flag or \
+                    data differ between the two")
+            .arg(&no_snapshot_arg)
+            .arg(&hard_forks_arg)
+            .arg(&max_genesis_archive_unpacked_size_arg)
+            .arg(
+                Arg::with_name("slot")
+                    .index(1)
+                    .value_name("SLOT")
+                    .validator(is_slot)
+                    .takes_value(true)
+                    .required(true)
+                    .help("Slot to compare"),
+            )
+            .arg(
+                Arg::with_name("other_ledger")
+                    .index(2)
+                    .value_name("DIR")
+                    .takes_value(true)
+                    .required(true)
+                    .help("Ledger to compare with, using its own snapshots"),
+            )
+        )
         .subcommand(
             SubCommand::with_name("bounds")
             .about("Print lowest and highest non-empty slots. Note that there may be empty slots within the bounds")
@@ -1146,6 +1386,65 @@ fn main() {
                     .takes_value(false)
                     .help("Remove all existing stake accounts from the new snapshot.")
             )
+        ).subcommand(
+            SubCommand::with_name("extract-ledger")
+            .about("Extract a slot range of the ledger, along with a snapshot of its first \
+                    slot, into a new self-contained ledger")
+            .arg(&no_snapshot_arg)
+            .arg(&account_paths_arg)
+            .arg(&hard_forks_arg)
+            .arg(&max_genesis_archive_unpacked_size_arg)
+            .arg(&snapshot_version_arg)
+            .arg(
+                Arg::with_name("starting_slot")
+                    .index(1)
+                    .value_name("SLOT")
+                    .validator(is_slot)
+                    .takes_value(true)
+                    .required(true)
+                    .help("First slot to extract, at which the snapshot is created"),
+            )
+            .arg(
+                Arg::with_name("ending_slot")
+                    .index(2)
+                    .value_name("SLOT")
+                    .validator(is_slot)
+                    .takes_value(true)
+                    .required(true)
+                    .help("Last slot to extract"),
+            )
+            .arg(
+                Arg::with_name("output_directory")
+                    .index(3)
+                    .value_name("DIR")
+                    .takes_value(true)
+                    .required(true)
+                    .help("Directory for the new ledger, which must not already contain one"),
+            )
+        ).subcommand(
+            SubCommand::with_name("trace-slot")
+            .about("Replay a slot and print a JSON line for every transaction, account write \
+                    by an instruction, rent collection, reward and sysvar update of the slot")
+            .arg(&no_snapshot_arg)
+            .arg(&account_paths_arg)
+            .arg(&hard_forks_arg)
+            .arg(&max_genesis_archive_unpacked_size_arg)
+            .arg(
+                Arg::with_name("slot")
+                    .index(1)
+                    .value_name("SLOT")
+                    .validator(is_slot)
+                    .takes_value(true)
+                    .required(true)
+                    .help("Slot to trace"),
+            )
+            .arg(
+                Arg::with_name("output_file")
+                    .long("output")
+                    .value_name("FILE")
+                    .takes_value(true)
+                    .help("Write the trace to this file instead of stdout"),
+            )
         ).subcommand(
             SubCommand::with_name("accounts")
             .about("Print account contents after processing in the ledger")
@@ -1166,6 +1465,70 @@ fn main() {
                     .help("Exclude account data (useful for large number of accounts)"),
             )
             .arg(&max_genesis_archive_unpacked_size_arg)
+        ).subcommand(
+            SubCommand::with_name("account-history")
+            .about("Print the writes of an account recorded in the account history")
+            .arg(
+                Arg::with_name("address")
+                    .index(1)
+                    .value_name("ADDRESS")
+                    .validator(is_pubkey)
+                    .takes_value(true)
+                    .required(true)
+                    .help("Account address"),
+            )
+            .arg(
+                Arg::with_name("slot")
+                    .long("slot")
+                    .value_name("SLOT")
+                    .validator(is_slot)
+                    .takes_value(true)
+                    .help("Only print the state of the account at the end of this slot"),
+            )
+            .arg(
+                Arg::with_name("exclude_account_data")
+                    .long("exclude-account-data")
+                    .takes_value(false)
+                    .help("Exclude account data"),
+            )
+        ).subcommand(
+            SubCommand::with_name("tower")
+            .about("Print the tower saved by a validator, optionally overriding its root")
+            .arg(
+                Arg::with_name("identity")
+                    .long("identity")
+                    .value_name("KEYPAIR")
+                    .validator(is_pubkey_or_keypair)
+                    .takes_value(true)
+                    .help("Identity of the validator that saved the tower \
+                           [default: the only tower found in the ledger directory]"),
+            )
+            .arg(
+                Arg::with_name("backup")
+                    .long("backup")
+                    .takes_value(false)
+                    .help("Use the backup of the previously saved tower"),
+            )
+            .arg(
+                Arg::with_name("restore")
+                    .long("restore")
+                    .takes_value(false)
+                    .requires("backup")
+                    .help("Replace the saved tower with the backup, for a validator that \
+                           refuses to start because its tower is missing or corrupt"),
+            )
+            .arg(
+                Arg::with_name("set_root")
+                    .long("set-root")
+                    .value_name("SLOT")
+                    .validator(is_slot)
+                    .takes_value(true)
+                    .requires("identity")
+                    .help("Override the root of the tower for recovery, dropping the votes \
+                           at or below it. The tower is re-signed with the --identity keypair \
+                           and the current one is kept as the backup. With --backup, also \
+                           requires --restore"),
+            )
         ).subcommand(
             SubCommand::with_name("capitalization")
             .about("Print capitalization (aka, total suppy) while checksumming it")
@@ -1449,6 +1812,65 @@ fn main() {
                 }
             }
         }
+        ("bank-hash-diff", Some(arg_matches)) => {
+            let slot = value_t_or_exit!(arg_matches, "slot", Slot);
+            let other_ledger_path =
+                PathBuf::from(value_t_or_exit!(arg_matches, "other_ledger", String));
+            let bank = load_bank_at_slot(
+                arg_matches,
+                &ledger_path,
+                slot,
+                wal_recovery_mode.clone(),
+                snapshot_archive_path,
+            );
+            let other_bank = load_bank_at_slot(
+                arg_matches,
+                &other_ledger_path,
+                slot,
+                wal_recovery_mode,
+                None,
+            );
+            println!("Bank hash: {} vs {}", bank.hash(), other_bank.hash());
+            println!(
+                "Accounts delta hash: {} vs {}",
+                bank.rc.accounts.accounts_db.get_accounts_delta_hash(slot),
+                other_bank
+                    .rc
+                    .accounts
+                    .accounts_db
+                    .get_accounts_delta_hash(slot),
+            );
+
+            let to_map = |bank: &Bank| -> BTreeMap<_, _> {
+                bank.get_accounts_delta_hash_inputs()
+                    .into_iter()
+                    .map(|(pubkey, account, hash)| (pubkey, (account, hash)))
+                    .collect()
+            };
+            let accounts = to_map(&bank);
+            let other_accounts = to_map(&other_bank);
+            let pubkeys: BTreeSet<_> = accounts.keys().chain(other_accounts.keys()).collect();
+            let mut differences = 0;
+            for pubkey in pubkeys {
+                let diff = match (accounts.get(pubkey), other_accounts.get(pubkey)) {
+                    (Some(account), Some(other_account)) => {
+                        describe_account_diff(account, other_account)
+                    }
+                    (Some(_), None) => Some(format!("only written in {:?}", ledger_path)),
+                    (None, Some(_)) => Some(format!("only written in {:?}", other_ledger_path)),
+                    (None, None) => None,
+                };
+                if let Some(diff) = diff {
+                    println!("{}: {}", pubkey, diff);
+                    differences += 1;
+                }
+            }
+            if differences == 0 {
+                println!("No account differences in slot {}", slot);
+            } else {
+                println!("{} accounts differ in slot {}", differences, slot);
+            }
+        }
         ("slot", Some(arg_matches)) => {
             let slots = values_t_or_exit!(arg_matches, "slots", Slot);
             let allow_dead_slots = arg_matches.is_present("allow_dead_slots");
@@ -1866,45 +2288,172 @@ fn main() {
                         bank.rehash();
                     }
 
-                    let temp_dir = tempfile::tempdir_in(ledger_path).unwrap_or_else(|err| {
-                        eprintln!("Unable to create temporary directory: {}", err);
+                    let archive_path = create_snapshot_archive(
+                        &bank,
+                        &ledger_path,
+                        Path::new(&output_directory),
+                        snapshot_version,
+                    )
+                    .unwrap_or_else(|err| {
+                        eprintln!("Unable to create snapshot archive: {}", err);
                         exit(1);
                     });
+                    println!(
+                        "Successfully created snapshot for slot {}, hash {}: {:?}",
+                        bank.slot(),
+                        bank.hash(),
+                        archive_path
+                    );
+                    println!(
+                        "Shred version: {}",
+                        compute_shred_version(
+                            &genesis_config.hash(),
+                            Some(&bank.hard_forks().read().unwrap())
+                        )
+                    );
+                }
+                Err(err) => {
+                    eprintln!("Failed to load ledger: {:?}", err);
+                    exit(1);
+                }
+            }
+        }
+        ("extract-ledger", Some(arg_matches)) => {
+            let starting_slot = value_t_or_exit!(arg_matches, "starting_slot", Slot);
+            let ending_slot = value_t_or_exit!(arg_matches, "ending_slot", Slot);
+            let output_directory =
+                PathBuf::from(value_t_or_exit!(arg_matches, "output_directory", String));
+            if ending_slot < starting_slot {
+                eprintln!(
+                    "Error: ending slot {} is before starting slot {}",
+                    ending_slot, starting_slot
+                );
+                exit(1);
+            }
+            if output_directory.join("rocksdb").exists() {
+                eprintln!("Error: {:?} already contains a ledger", output_directory);
+                exit(1);
+            }
 
-                    let storages: Vec<_> = bank.get_snapshot_storages();
-                    snapshot_utils::add_snapshot(&temp_dir, &bank, &storages, snapshot_version)
-                        .and_then(|slot_snapshot_paths| {
-                            snapshot_utils::package_snapshot(
-                                &bank,
-                                &slot_snapshot_paths,
-                                &temp_dir,
-                                bank.src.slot_deltas(&bank.src.roots()),
-                                output_directory,
-                                storages,
-                                CompressionType::Zstd,
-                                snapshot_version,
-                            )
-                        })
-                        .and_then(|package| {
-                            snapshot_utils::archive_snapshot_package(&package).map(|ok| {
-                                println!(
-                                    "Successfully created snapshot for slot {}, hash {}: {:?}",
-                                    bank.slot(),
-                                    bank.hash(),
-                                    package.tar_output_file
-                                );
-                                println!(
-                                    "Shred version: {}",
-                                    compute_shred_version(
-                                        &genesis_config.hash(),
-                                        Some(&bank.hard_forks().read().unwrap())
-                                    )
-                                );
-                                ok
-                            })
+            let snapshot_version =
+                arg_matches
+                    .value_of("snapshot_version")
+                    .map_or(SnapshotVersion::default(), |s| {
+                        s.parse::<SnapshotVersion>().unwrap_or_else(|e| {
+                            eprintln!("Error: {}", e);
+                            exit(1)
                         })
+                    });
+            let process_options = ProcessOptions {
+                dev_halt_at_slot: Some(starting_slot),
+                new_hard_forks: hardforks_of(arg_matches, "hard_forks"),
+                poh_verify: false,
+                ..ProcessOptions::default()
+            };
+            let genesis_config = open_genesis_config_by(&ledger_path, arg_matches);
+            genesis_config
+                .write(&output_directory)
+                .unwrap_or_else(|err| {
+                    eprintln!("Failed to write genesis config: {}", err);
+                    exit(1);
+                });
+
+            let bank = match load_bank_forks(
+                arg_matches,
+                &ledger_path,
+                &genesis_config,
+                process_options,
+                AccessType::TryPrimaryThenSecondary,
+                wal_recovery_mode,
+                snapshot_archive_path,
+            ) {
+                Ok((bank_forks, _leader_schedule_cache, _snapshot_hash)) => bank_forks
+                    .get(starting_slot)
+                    .unwrap_or_else(|| {
+                        eprintln!("Error: Slot {} is not available", starting_slot);
+                        exit(1);
+                    })
+                    .clone(),
+                Err(err) => {
+                    eprintln!("Failed to load ledger: {:?}", err);
+                    exit(1);
+                }
+            };
+            assert!(bank.is_complete());
+            bank.squash();
+            bank.clean_accounts(true);
+            bank.update_accounts_hash();
+            let archive_path =
+                create_snapshot_archive(&bank, &ledger_path, &output_directory, snapshot_version)
+                    .unwrap_or_else(|err| {
+                        eprintln!("Unable to create snapshot archive: {}", err);
+                        exit(1);
+                    });
+            println!(
+                "Created snapshot for slot {}, hash {}: {:?}",
+                bank.slot(),
+                bank.hash(),
+                archive_path
+            );
+
+            let source = open_blockstore(&ledger_path, AccessType::TryPrimaryThenSecondary, None);
+            let target = open_blockstore(&output_directory, AccessType::PrimaryOnly, None);
+            let num_slots = copy_blockstore_range(&source, &target, starting_slot, ending_slot);
+            println!(
+                "Extracted {} slots in [{}, {}] into {:?}",
+                num_slots, starting_slot, ending_slot, output_directory
+            );
+        }
+        ("trace-slot", Some(arg_matches)) => {
+            let slot = value_t_or_exit!(arg_matches, "slot", Slot);
+            let output: Box<dyn Write + Send> = match arg_matches.value_of("output_file") {
+                Some(output_file) => Box::new(io::BufWriter::new(
+                    File::create(output_file).unwrap_or_else(|err| {
+                        eprintln!("Unable to create {}: {}", output_file, err);
+                        exit(1);
+                    }),
+                )),
+                None => Box::new(stdout()),
+            };
+            let replay_tracer = Arc::new(JsonReplayTracer {
+                slot,
+                output: Mutex::new(output),
+            });
+            let process_options = ProcessOptions {
+                dev_halt_at_slot: Some(slot),
+                new_hard_forks: hardforks_of(arg_matches, "hard_forks"),
+                poh_verify: false,
+                replay_tracer: Some(replay_tracer.clone()),
+                ..ProcessOptions::default()
+            };
+            let genesis_config = open_genesis_config_by(&ledger_path, arg_matches);
+            match load_bank_forks(
+                arg_matches,
+                &ledger_path,
+                &genesis_config,
+                process_options,
+                AccessType::TryPrimaryThenSecondary,
+                wal_recovery_mode,
+                snapshot_archive_path,
+            ) {
+                Ok((bank_forks, _leader_schedule_cache, snapshot_hash)) => {
+                    // A slot at or before the snapshot is never replayed
+                    let replayed = bank_forks.get(slot).is_some()
+                        && !matches!(snapshot_hash, Some((snapshot_slot, _)) if slot <= snapshot_slot);
+                    if !replayed {
+                        eprintln!(
+                            "Slot {} was not replayed, try again with --no-snapshot",
+                            slot
+                        );
+                        exit(1);
+                    }
+                    replay_tracer
+                        .output
+                        .lock()
+                        .unwrap()
+                        .flush()
                         .unwrap_or_else(|err| {
-                            eprintln!("Unable to create snapshot archive: {}", err);
+                            eprintln!("Unable to write trace: {}", err);
                             exit(1);
                         });
                 }
@@ -1971,6 +2520,141 @@ fn main() {
                 }
             }
         }
+        ("account-history", Some(arg_matches)) => {
+            let pubkey = pubkey_of(arg_matches, "address").unwrap();
+            let exclude_account_data = arg_matches.is_present("exclude_account_data");
+            let blockstore = open_blockstore(
+                &ledger_path,
+                AccessType::TryPrimaryThenSecondary,
+                wal_recovery_mode,
+            );
+
+            let history = match value_t!(arg_matches, "slot", Slot).ok() {
+                Some(slot) => blockstore
+                    .get_account_history_at_slot(&pubkey, slot)
+                    .map(|entry| entry.into_iter().collect()),
+                None => blockstore.get_account_history(&pubkey),
+            }
+            .unwrap_or_else(|err| {
+                eprintln!("Failed to read the account history: {:?}", err);
+                exit(1);
+            });
+
+            println!("---");
+            println!("{}:", pubkey);
+            for (slot, AccountHistoryEntry { hash, account }) in history {
+                let data_len = account.data.len();
+                println!("  - slot: {}", slot);
+                println!("    hash: {}", hash);
+                println!("    balance: {} SOL", lamports_to_sol(account.lamports));
+                println!("    owner: '{}'", account.owner);
+                println!("    executable: {}", account.executable);
+                println!("    rent_epoch: {}", account.rent_epoch);
+                if !exclude_account_data {
+                    println!("    data: '{}'", bs58::encode(account.data).into_string());
+                }
+                println!("    data_len: {}", data_len);
+            }
+        }
+        ("tower", Some(arg_matches)) => {
+            let node_pubkey = pubkey_of(arg_matches, "identity")
+                .or_else(|| find_tower_identity(&ledger_path))
+                .unwrap_or_else(|| {
+                    eprintln!(
+                        "Error: Unable to find a single tower in {:?}; specify --identity",
+                        ledger_path
+                    );
+                    exit(1);
+                });
+            let tower_filename = Tower::get_filename(&ledger_path, &node_pubkey);
+            let mut tower = if arg_matches.is_present("backup") {
+                let backup_filename = Tower::get_backup_filename(&tower_filename);
+                let mut tower = Tower::restore_from_file(&backup_filename, &node_pubkey)
+                    .unwrap_or_else(|err| {
+                        eprintln!("Failed to load tower from {:?}: {}", backup_filename, err);
+                        exit(1);
+                    });
+                if arg_matches.is_present("restore") {
+                    // a leftover tmp tower must not be recovered over the restored one
+                    let tmp_filename = Tower::get_tmp_filename(&tower_filename);
+                    if tmp_filename.exists() {
+                        fs::remove_file(&tmp_filename).unwrap_or_else(|err| {
+                            eprintln!("Failed to remove {:?}: {}", tmp_filename, err);
+                            exit(1);
+                        });
+                    }
+                    fs::copy(&backup_filename, &tower_filename).unwrap_or_else(|err| {
+                        eprintln!("Failed to restore tower to {:?}: {}", tower_filename, err);
+                        exit(1);
+                    });
+                    tower.set_path(tower_filename);
+                    println!("Tower restored from {:?}", backup_filename);
+                } else if arg_matches.is_present("set_root") {
+                    eprintln!("Error: --set-root on the backup requires --restore");
+                    exit(1);
+                }
+                tower
+            } else {
+                // load it the way the validator will, including recovering an interrupted save
+                Tower::restore(&ledger_path, &node_pubkey).unwrap_or_else(|err| {
+                    eprintln!("Failed to load tower from {:?}: {}", tower_filename, err);
+                    exit(1);
+                })
+            };
+
+            if let Ok(root) = value_t!(arg_matches, "set_root", Slot) {
+                let identity_keypair =
+                    Arc::new(keypair_of(arg_matches, "identity").unwrap_or_else(|| {
+                        eprintln!("Error: --set-root requires the --identity keypair");
+                        exit(1);
+                    }));
+                let old_root = tower.root();
+                tower.set_root(root);
+                tower.save(&identity_keypair).unwrap_or_else(|err| {
+                    eprintln!("Failed to save tower: {}", err);
+                    exit(1);
+                });
+                println!("Tower root changed from {} to {}", old_root, root);
+            }
+
+            println!("Tower: {}", tower.path().display());
+            println!("  identity: {}", tower.node_pubkey());
+            println!("  root: {}", tower.root());
+            let last_vote = tower.last_vote();
+            match last_vote.last_voted_slot() {
+                Some(slot) => println!(
+                    "  last vote: slot {} (bank hash {}, timestamp {:?})",
+                    slot, last_vote.hash, last_vote.timestamp
+                ),
+                None => println!("  last vote: none"),
+            }
+            println!(
+                "  threshold: depth {}, size {:.1}%",
+                tower.threshold_depth(),
+                tower.threshold_size() * 100.0
+            );
+            match tower.threshold_vote() {
+                Some(lockout) => println!(
+                    "  threshold vote: slot {} (confirmation count {})",
+                    lockout.slot, lockout.confirmation_count
+                ),
+                None => println!("  threshold vote: none"),
+            }
+            println!("  lockouts:");
+            println!(
+                "    {:>12} {:>14} {:>12} {:>12}",
+                "slot", "confirmations", "lockout", "expiration"
+            );
+            for lockout in tower.lockouts().iter().rev() {
+                println!(
+                    "    {:>12} {:>14} {:>12} {:>12}",
+                    lockout.slot,
+                    lockout.confirmation_count,
+                    lockout.lockout(),
+                    lockout.expiration_slot()
+                );
+            }
+        }
         ("capitalization", Some(arg_matches)) => {
             let dev_halt_at_slot = value_t!(arg_matches, "halt_at_slot", Slot).ok();
             let process_options = ProcessOptions {
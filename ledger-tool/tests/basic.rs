@@ -1,8 +1,19 @@
 use assert_cmd::prelude::*;
+use solana_core::consensus::Tower;
+use solana_ledger::blockstore::Blockstore;
+use solana_ledger::blockstore_processor::fill_blockstore_slot_with_ticks;
 use solana_ledger::create_new_tmp_ledger;
 use solana_ledger::genesis_utils::create_genesis_config;
+use solana_runtime::bank::Bank;
+use solana_sdk::{
+    account::Account,
+    hash::Hash,
+    pubkey::Pubkey,
+    signature::{write_keypair_file, Keypair, Signer},
+};
 use std::process::Command;
 use std::process::Output;
+use std::sync::Arc;
 
 fn run_ledger_tool(args: &[&str]) -> Output {
     Command::cargo_bin(env!("CARGO_PKG_NAME"))
@@ -47,3 +58,234 @@ fn nominal() {
     assert!(output.status.success());
     assert_eq!(count_newlines(&output.stdout), ticks + meta_lines);
 }
+
+#[test]
+fn extract_ledger() {
+    let genesis_config = create_genesis_config(100).genesis_config;
+    let ticks_per_slot = genesis_config.ticks_per_slot;
+
+    let (ledger_path, blockhash) = create_new_tmp_ledger!(&genesis_config);
+    {
+        let blockstore = Blockstore::open(&ledger_path).unwrap();
+        let mut last_hash = blockhash;
+        for slot in 1..=5 {
+            last_hash = fill_blockstore_slot_with_ticks(
+                &blockstore,
+                ticks_per_slot,
+                slot,
+                slot - 1,
+                last_hash,
+            );
+        }
+        blockstore.set_roots(&[0, 1, 2, 3, 4, 5]).unwrap();
+    }
+    let ledger_path = ledger_path.to_str().unwrap();
+    let output_dir = tempfile::TempDir::new().unwrap();
+    let output_path = output_dir.path().join("extracted");
+    let output_path = output_path.to_str().unwrap();
+
+    let output = run_ledger_tool(&[
+        "-l",
+        ledger_path,
+        "extract-ledger",
+        "--no-snapshot",
+        "2",
+        "4",
+        output_path,
+    ]);
+    assert!(output.status.success());
+
+    // The extracted ledger replays from its snapshot, and holds nothing past the range
+    let output = run_ledger_tool(&["-l", output_path, "verify"]);
+    assert!(output.status.success());
+    let output = run_ledger_tool(&["-l", output_path, "bounds"]);
+    assert!(output.status.success());
+    let bounds = String::from_utf8(output.stdout).unwrap();
+    // The parent of the first slot shows up as an empty slot meta
+    assert!(bounds.contains("slots 1 to 4"), "{}", bounds);
+
+    // Refuse to overwrite an existing ledger
+    let output = run_ledger_tool(&[
+        "-l",
+        ledger_path,
+        "extract-ledger",
+        "--no-snapshot",
+        "2",
+        "4",
+        output_path,
+    ]);
+    assert!(!output.status.success());
+}
+
+#[test]
+fn trace_slot() {
+    let genesis_config = create_genesis_config(100).genesis_config;
+    let ticks_per_slot = genesis_config.ticks_per_slot;
+
+    let (ledger_path, blockhash) = create_new_tmp_ledger!(&genesis_config);
+    {
+        let blockstore = Blockstore::open(&ledger_path).unwrap();
+        fill_blockstore_slot_with_ticks(&blockstore, ticks_per_slot, 1, 0, blockhash);
+    }
+    let ledger_path = ledger_path.to_str().unwrap();
+
+    let output = run_ledger_tool(&["-l", ledger_path, "trace-slot", "1"]);
+    assert!(output.status.success());
+
+    // At least the sysvar updates of the slot are traced, one JSON event per line
+    let trace = String::from_utf8(output.stdout).unwrap();
+    assert!(trace.lines().count() > 0);
+    for line in trace.lines() {
+        let event: serde_json::Value = serde_json::from_str(line).unwrap();
+        assert_eq!(event["slot"], 1, "{}", line);
+    }
+
+    // Slots that are never replayed can't be traced
+    let output = run_ledger_tool(&["-l", ledger_path, "trace-slot", "2"]);
+    assert!(!output.status.success());
+}
+
+#[test]
+fn bank_hash_diff() {
+    let genesis_config = create_genesis_config(100).genesis_config;
+    let other_genesis_config = create_genesis_config(100).genesis_config;
+    let ticks_per_slot = genesis_config.ticks_per_slot;
+
+    let mut ledger_paths = vec![];
+    for genesis_config in &[&genesis_config, &genesis_config, &other_genesis_config] {
+        let (ledger_path, blockhash) = create_new_tmp_ledger!(genesis_config);
+        let blockstore = Blockstore::open(&ledger_path).unwrap();
+        fill_blockstore_slot_with_ticks(&blockstore, ticks_per_slot, 1, 0, blockhash);
+        ledger_paths.push(ledger_path.to_str().unwrap().to_string());
+    }
+
+    // Ledgers built from the same genesis write the same accounts
+    let output = run_ledger_tool(&[
+        "-l",
+        &ledger_paths[0],
+        "bank-hash-diff",
+        "1",
+        &ledger_paths[1],
+    ]);
+    assert!(output.status.success());
+    let diff = String::from_utf8(output.stdout).unwrap();
+    assert!(
+        diff.contains("No account differences in slot 1"),
+        "{}",
+        diff
+    );
+
+    // With a different genesis, the recent blockhashes sysvar written by slot 1 differs
+    let output = run_ledger_tool(&[
+        "-l",
+        &ledger_paths[0],
+        "bank-hash-diff",
+        "1",
+        &ledger_paths[2],
+    ]);
+    assert!(output.status.success());
+    let diff = String::from_utf8(output.stdout).unwrap();
+    let recent_blockhashes = solana_sdk::sysvar::recent_blockhashes::id().to_string();
+    assert!(
+        diff.lines()
+            .any(|line| line.starts_with(&format!("{}: ", recent_blockhashes))),
+        "{}",
+        diff
+    );
+}
+
+#[test]
+fn account_history() {
+    let genesis_config = create_genesis_config(100).genesis_config;
+    let (ledger_path, _blockhash) = create_new_tmp_ledger!(&genesis_config);
+    let pubkey = solana_sdk::pubkey::new_rand();
+    {
+        let blockstore = Blockstore::open(&ledger_path).unwrap();
+        for slot in &[2, 5] {
+            let account = Account::new(*slot, 0, &Pubkey::default());
+            blockstore
+                .write_account_history(*slot, &[(pubkey, account, Hash::default())])
+                .unwrap();
+        }
+    }
+    let ledger_path = ledger_path.to_str().unwrap();
+    let pubkey = pubkey.to_string();
+
+    let output = run_ledger_tool(&["-l", ledger_path, "account-history", &pubkey]);
+    assert!(output.status.success());
+    let history = String::from_utf8(output.stdout).unwrap();
+    assert!(history.contains("slot: 2"), "{}", history);
+    assert!(history.contains("slot: 5"), "{}", history);
+
+    // Only the last write at or before the slot
+    let output = run_ledger_tool(&["-l", ledger_path, "account-history", &pubkey, "--slot", "4"]);
+    assert!(output.status.success());
+    let history = String::from_utf8(output.stdout).unwrap();
+    assert!(history.contains("slot: 2"), "{}", history);
+    assert!(!history.contains("slot: 5"), "{}", history);
+}
+
+#[test]
+fn tower() {
+    let genesis_config = create_genesis_config(100).genesis_config;
+    let (ledger_path, _blockhash) = create_new_tmp_ledger!(&genesis_config);
+    let bank = Bank::new(&genesis_config);
+    let identity_keypair = Arc::new(Keypair::new());
+    let tower = Tower::new(
+        &identity_keypair.pubkey(),
+        &solana_sdk::pubkey::new_rand(),
+        3,
+        &bank,
+        &ledger_path,
+    );
+    tower.save(&identity_keypair).unwrap();
+    let keypair_dir = tempfile::TempDir::new().unwrap();
+    let keypair_file = keypair_dir.path().join("identity.json");
+    let keypair_file = keypair_file.to_str().unwrap();
+    write_keypair_file(&identity_keypair, keypair_file).unwrap();
+    let ledger_path = ledger_path.to_str().unwrap();
+
+    // The only tower in the ledger directory is picked up by default
+    let output = run_ledger_tool(&["-l", ledger_path, "tower"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("root: 3"), "{}", stdout);
+
+    // Overriding the root requires the identity keypair to re-sign the tower
+    let pubkey = identity_keypair.pubkey().to_string();
+    let output = run_ledger_tool(&[
+        "-l",
+        ledger_path,
+        "tower",
+        "--identity",
+        &pubkey,
+        "--set-root",
+        "5",
+    ]);
+    assert!(!output.status.success());
+    let output = run_ledger_tool(&[
+        "-l",
+        ledger_path,
+        "tower",
+        "--identity",
+        keypair_file,
+        "--set-root",
+        "5",
+    ]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("root: 5"), "{}", stdout);
+
+    // The overridden tower is kept as the backup
+    let output = run_ledger_tool(&[
+        "-l",
+        ledger_path,
+        "tower",
+        "--identity",
+        &pubkey,
+        "--backup",
+    ]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("root: 3"), "{}", stdout);
+}
@@ -58,15 +58,31 @@ pub fn load(
                     process::exit(1);
                 }
 
+                let incremental_snapshot_archive =
+                    snapshot_utils::get_highest_incremental_snapshot_archive_path(
+                        &snapshot_config.snapshot_package_output_path,
+                        archive_slot,
+                    );
+                if let Some((incremental_archive_filename, _)) = &incremental_snapshot_archive {
+                    info!(
+                        "Loading incremental snapshot package: {:?}",
+                        incremental_archive_filename
+                    );
+                }
+
                 let deserialized_bank = snapshot_utils::bank_from_archive(
                     &account_paths,
                     &process_options.frozen_accounts,
                     &snapshot_config.snapshot_path,
                     &archive_filename,
                     compression,
+                    incremental_snapshot_archive
+                        .as_ref()
+                        .map(|(filename, (_, _, compression))| (filename, compression.clone())),
                     genesis_config,
                     process_options.debug_keys.clone(),
                     Some(&crate::builtins::get(genesis_config.cluster_type)),
+                    process_options.account_indexes.clone(),
                 )
                 .expect("Load from snapshot failed");
 
@@ -75,14 +91,25 @@ pub fn load(
                     deserialized_bank.get_accounts_hash(),
                 );
 
-                if deserialized_snapshot_hash != (archive_slot, archive_snapshot_hash) {
+                let expected_snapshot_hash = incremental_snapshot_archive
+                    .map(|(_, (slot, hash, _))| (slot, hash))
+                    .unwrap_or((archive_slot, archive_snapshot_hash));
+                if deserialized_snapshot_hash != expected_snapshot_hash {
                     error!(
                         "Snapshot has mismatch:\narchive: {:?}\ndeserialized: {:?}",
-                        archive_snapshot_hash, deserialized_snapshot_hash
+                        expected_snapshot_hash, deserialized_snapshot_hash
                     );
                     process::exit(1);
                 }
 
+                if snapshot_config.incremental_snapshot_interval_slots != std::u64::MAX {
+                    deserialized_bank
+                        .rc
+                        .accounts
+                        .accounts_db
+                        .set_last_full_snapshot_slot(archive_slot);
+                }
+
                 return to_loadresult(
                     blockstore_processor::process_blockstore_from_root(
                         blockstore,
@@ -91,7 +118,7 @@ pub fn load(
                         &VerifyRecyclers::default(),
                         transaction_status_sender,
                     ),
-                    Some(deserialized_snapshot_hash),
+                    Some((archive_slot, archive_snapshot_hash)),
                 );
             }
             None => info!("No snapshot package available"),
@@ -1,30 +1,123 @@
 use crate::blockstore::Blockstore;
+use backoff::{future::FutureOperation as _, ExponentialBackoff};
 use log::*;
 use solana_measure::measure::Measure;
 use solana_sdk::clock::Slot;
+use solana_storage_proto::convert::generated;
 use std::{
     collections::HashSet,
+    fs,
+    future::Future,
+    path::{Path, PathBuf},
     result::Result,
     sync::{
         atomic::{AtomicBool, Ordering},
         Arc,
     },
-    time::Duration,
 };
-use tokio::time::delay_for;
 
-// Attempt to upload this many blocks in parallel
-const NUM_BLOCKS_TO_UPLOAD_IN_PARALLEL: usize = 32;
+#[derive(Clone, Debug)]
+pub struct ConfirmedBlockUploadConfig {
+    /// Attempt to upload this many blocks in parallel
+    pub num_blocks_to_upload_in_parallel: usize,
+    /// Read up to this many blocks from blockstore before blocking on the upload process
+    pub block_read_ahead_depth: usize,
+    /// Don't panic if transaction metadata is missing
+    pub allow_missing_metadata: bool,
+    /// Record upload progress in this file, and resume from it
+    pub checkpoint_path: Option<PathBuf>,
+    /// Read every block of the range back from bigtable and compare it with blockstore
+    pub verify: bool,
+}
+
+impl Default for ConfirmedBlockUploadConfig {
+    fn default() -> Self {
+        const NUM_BLOCKS_TO_UPLOAD_IN_PARALLEL: usize = 32;
+        Self {
+            num_blocks_to_upload_in_parallel: NUM_BLOCKS_TO_UPLOAD_IN_PARALLEL,
+            block_read_ahead_depth: NUM_BLOCKS_TO_UPLOAD_IN_PARALLEL * 2,
+            allow_missing_metadata: false,
+            checkpoint_path: None,
+            verify: false,
+        }
+    }
+}
+
+/// Every block from `starting_slot` through `last_uploaded_slot` is in bigtable
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct UploadCheckpoint {
+    starting_slot: Slot,
+    last_uploaded_slot: Slot,
+}
+
+fn read_checkpoint(path: &Path) -> Result<Option<UploadCheckpoint>, String> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let contents = fs::read_to_string(path)
+        .map_err(|err| format!("Failed to read checkpoint {:?}: {}", path, err))?;
+    let slots = contents
+        .split_whitespace()
+        .map(|slot| slot.parse::<Slot>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|err| format!("Invalid checkpoint {:?}: {}", path, err))?;
+    match slots[..] {
+        [starting_slot, last_uploaded_slot] if starting_slot <= last_uploaded_slot => {
+            Ok(Some(UploadCheckpoint {
+                starting_slot,
+                last_uploaded_slot,
+            }))
+        }
+        _ => Err(format!("Invalid checkpoint {:?}: {:?}", path, contents)),
+    }
+}
+
+// Write to a temporary file first so an interrupted write never leaves a truncated checkpoint
+fn write_checkpoint(path: &Path, checkpoint: UploadCheckpoint) -> Result<(), String> {
+    let tmp_path = path.with_extension("tmp");
+    fs::write(
+        &tmp_path,
+        format!(
+            "{} {}\n",
+            checkpoint.starting_slot, checkpoint.last_uploaded_slot
+        ),
+    )
+    .and_then(|()| fs::rename(&tmp_path, path))
+    .map_err(|err| format!("Failed to write checkpoint {:?}: {}", path, err))
+}
 
-// Read up to this many blocks from blockstore before blocking on the upload process
-const BLOCK_READ_AHEAD_DEPTH: usize = NUM_BLOCKS_TO_UPLOAD_IN_PARALLEL * 2;
+// Retry `operation` with exponential backoff for as long as it fails with a transient error
+async fn retry_transient_errors<T, F, Fut>(
+    description: &str,
+    mut operation: F,
+) -> solana_storage_bigtable::Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = solana_storage_bigtable::Result<T>>,
+{
+    (|| {
+        let result = operation();
+        async move {
+            result.await.map_err(|err| {
+                if err.is_transient() {
+                    warn!("{} failed, retrying: {:?}", description, err);
+                    backoff::Error::Transient(err)
+                } else {
+                    backoff::Error::Permanent(err)
+                }
+            })
+        }
+    })
+    .retry(ExponentialBackoff::default())
+    .await
+}
 
 pub async fn upload_confirmed_blocks(
     blockstore: Arc<Blockstore>,
     bigtable: solana_storage_bigtable::LedgerStorage,
     starting_slot: Slot,
     ending_slot: Option<Slot>,
-    allow_missing_metadata: bool,
+    config: ConfirmedBlockUploadConfig,
     exit: Arc<AtomicBool>,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let mut measure = Measure::start("entire upload");
@@ -63,28 +156,97 @@ pub async fn upload_confirmed_blocks(
         blockstore_slots.last().unwrap()
     );
 
+    // Resume after the checkpoint, as long as it covers the slots leading up to `starting_slot`
+    let mut checkpoint_starting_slot = starting_slot;
+    let mut upload_starting_slot = starting_slot;
+    if let Some(checkpoint_path) = &config.checkpoint_path {
+        match read_checkpoint(checkpoint_path)? {
+            Some(checkpoint)
+                if checkpoint.starting_slot <= starting_slot
+                    && checkpoint.last_uploaded_slot.saturating_add(1) >= starting_slot =>
+            {
+                info!(
+                    "Resuming upload after slot {}",
+                    checkpoint.last_uploaded_slot
+                );
+                checkpoint_starting_slot = checkpoint.starting_slot;
+                upload_starting_slot = checkpoint.last_uploaded_slot.saturating_add(1);
+            }
+            Some(checkpoint) => warn!(
+                "Ignoring checkpoint for slots {} to {}, which does not cover slot {}",
+                checkpoint.starting_slot, checkpoint.last_uploaded_slot, starting_slot
+            ),
+            None => {}
+        }
+    }
+    let slots_to_upload: Vec<_> = blockstore_slots
+        .iter()
+        .cloned()
+        .filter(|slot| *slot >= upload_starting_slot)
+        .collect();
+
+    let mut failures = 0;
+    if slots_to_upload.is_empty() {
+        info!(
+            "All blocks before slot {} were already uploaded",
+            upload_starting_slot
+        );
+    } else {
+        failures += upload_blocks(
+            blockstore.clone(),
+            &bigtable,
+            slots_to_upload,
+            &config,
+            checkpoint_starting_slot,
+            &exit,
+        )
+        .await?;
+    }
+
+    if config.verify && !exit.load(Ordering::Relaxed) {
+        failures += verify_blocks(&blockstore, &bigtable, &blockstore_slots, &config, &exit).await;
+    }
+
+    measure.stop();
+    info!("{}", measure);
+    if failures > 0 {
+        Err(format!("Incomplete upload, {} operations failed", failures).into())
+    } else {
+        Ok(())
+    }
+}
+
+// Upload the blocks of `blockstore_slots` that are not already in bigtable, returning the number
+// of failed uploads
+async fn upload_blocks(
+    blockstore: Arc<Blockstore>,
+    bigtable: &solana_storage_bigtable::LedgerStorage,
+    blockstore_slots: Vec<Slot>,
+    config: &ConfirmedBlockUploadConfig,
+    checkpoint_starting_slot: Slot,
+    exit: &Arc<AtomicBool>,
+) -> Result<usize, Box<dyn std::error::Error>> {
+    let first_blockstore_slot = *blockstore_slots.first().unwrap();
+    let last_blockstore_slot = *blockstore_slots.last().unwrap();
+
     // Gather the blocks that are already present in bigtable, by slot
     let bigtable_slots = {
         let mut bigtable_slots = vec![];
-        let first_blockstore_slot = *blockstore_slots.first().unwrap();
-        let last_blockstore_slot = *blockstore_slots.last().unwrap();
         info!(
             "Loading list of bigtable blocks between slots {} and {}...",
             first_blockstore_slot, last_blockstore_slot
         );
 
-        let mut start_slot = *blockstore_slots.first().unwrap();
+        let mut start_slot = first_blockstore_slot;
         while start_slot <= last_blockstore_slot {
-            let mut next_bigtable_slots = loop {
-                match bigtable.get_confirmed_blocks(start_slot, 1000).await {
-                    Ok(slots) => break slots,
-                    Err(err) => {
-                        error!("get_confirmed_blocks for {} failed: {:?}", start_slot, err);
-                        // Consider exponential backoff...
-                        delay_for(Duration::from_secs(2)).await;
-                    }
-                }
-            };
+            let mut next_bigtable_slots =
+                retry_transient_errors(&format!("get_confirmed_blocks for {}", start_slot), || {
+                    bigtable.get_confirmed_blocks(start_slot, 1000)
+                })
+                .await
+                .map_err(|err| {
+                    format!("get_confirmed_blocks for {} failed: {:?}", start_slot, err)
+                })?;
             if next_bigtable_slots.is_empty() {
                 break;
             }
@@ -107,13 +269,25 @@ pub async fn upload_confirmed_blocks(
             .difference(&bigtable_slots)
             .cloned()
             .collect::<Vec<_>>();
-        blocks_to_upload.sort();
+        blocks_to_upload.sort_unstable();
         blocks_to_upload
     };
 
+    let save_checkpoint = |last_uploaded_slot: Slot| match &config.checkpoint_path {
+        Some(checkpoint_path) => write_checkpoint(
+            checkpoint_path,
+            UploadCheckpoint {
+                starting_slot: checkpoint_starting_slot,
+                last_uploaded_slot,
+            },
+        ),
+        None => Ok(()),
+    };
+
     if blocks_to_upload.is_empty() {
         info!("No blocks need to be uploaded to bigtable");
-        return Ok(());
+        save_checkpoint(last_blockstore_slot)?;
+        return Ok(0);
     }
     info!(
         "{} blocks to be uploaded to the bucket in the range ({}, {})",
@@ -125,8 +299,9 @@ pub async fn upload_confirmed_blocks(
     // Load the blocks out of blockstore in a separate thread to allow for concurrent block uploading
     let (_loader_thread, receiver) = {
         let exit = exit.clone();
+        let num_blocks_to_upload_in_parallel = config.num_blocks_to_upload_in_parallel;
 
-        let (sender, receiver) = std::sync::mpsc::sync_channel(BLOCK_READ_AHEAD_DEPTH);
+        let (sender, receiver) = std::sync::mpsc::sync_channel(config.block_read_ahead_depth);
         (
             std::thread::spawn(move || {
                 let mut measure = Measure::start("block loader thread");
@@ -146,7 +321,7 @@ pub async fn upload_confirmed_blocks(
                         }
                     };
 
-                    if i > 0 && i % NUM_BLOCKS_TO_UPLOAD_IN_PARALLEL == 0 {
+                    if i > 0 && i % num_blocks_to_upload_in_parallel == 0 {
                         info!(
                             "{}% of blocks processed ({}/{})",
                             i * 100 / blocks_to_upload.len(),
@@ -165,8 +340,7 @@ pub async fn upload_confirmed_blocks(
     let mut failures = 0;
     use futures::stream::StreamExt;
 
-    let mut stream =
-        tokio::stream::iter(receiver.into_iter()).chunks(NUM_BLOCKS_TO_UPLOAD_IN_PARALLEL);
+    let mut stream = tokio::stream::iter(receiver).chunks(config.num_blocks_to_upload_in_parallel);
 
     while let Some(blocks) = stream.next().await {
         if exit.load(Ordering::Relaxed) {
@@ -175,6 +349,7 @@ pub async fn upload_confirmed_blocks(
 
         let mut measure_upload = Measure::start("Upload");
         let mut num_blocks = blocks.len();
+        let last_slot_in_chunk = blocks.last().map(|(slot, _)| *slot).unwrap();
         info!("Preparing the next {} blocks for upload", num_blocks);
 
         let uploads = blocks.into_iter().filter_map(|(slot, block)| match block {
@@ -188,7 +363,7 @@ pub async fn upload_confirmed_blocks(
                     .iter()
                     .any(|transaction| transaction.meta.is_none())
                 {
-                    if allow_missing_metadata {
+                    if config.allow_missing_metadata {
                         info!("Transaction metadata missing from slot {}", slot);
                     } else {
                         panic!("Transaction metadata missing from slot {}", slot);
@@ -207,13 +382,196 @@ pub async fn upload_confirmed_blocks(
 
         measure_upload.stop();
         info!("{} for {} blocks", measure_upload, num_blocks);
+
+        // Blocks that can't be loaded from blockstore won't be uploaded by a later run either, so
+        // only failed uploads hold the checkpoint back
+        if failures == 0 {
+            save_checkpoint(last_slot_in_chunk)?;
+        }
+    }
+
+    // The blocks after the last upload were already in bigtable
+    if failures == 0 && !exit.load(Ordering::Relaxed) {
+        save_checkpoint(last_blockstore_slot)?;
+    }
+
+    Ok(failures)
+}
+
+// Read the blocks of `slots` back from bigtable and compare them with blockstore, returning the
+// number of blocks that are missing or differ
+async fn verify_blocks(
+    blockstore: &Blockstore,
+    bigtable: &solana_storage_bigtable::LedgerStorage,
+    slots: &[Slot],
+    config: &ConfirmedBlockUploadConfig,
+    exit: &AtomicBool,
+) -> usize {
+    let mut measure = Measure::start("Verify");
+    info!(
+        "Verifying bigtable blocks between slots {} and {}...",
+        slots.first().unwrap(),
+        slots.last().unwrap()
+    );
+
+    let mut num_verified = 0;
+    let mut mismatches = 0;
+    for slots in slots.chunks(config.num_blocks_to_upload_in_parallel) {
+        if exit.load(Ordering::Relaxed) {
+            break;
+        }
+
+        // Slots without a confirmed block in blockstore were never uploaded
+        let blocks: Vec<_> = slots
+            .iter()
+            .filter_map(|slot| {
+                blockstore
+                    .get_confirmed_block(*slot)
+                    .ok()
+                    .map(|block| (*slot, block))
+            })
+            .collect();
+        let reads = blocks.iter().map(|(slot, _)| {
+            retry_transient_errors("get_confirmed_block", move || {
+                bigtable.get_confirmed_block(*slot)
+            })
+        });
+
+        for ((slot, blockstore_block), result) in
+            blocks.iter().zip(futures::future::join_all(reads).await)
+        {
+            num_verified += 1;
+            match result {
+                // Blocks are stored as protobuf, which doesn't tell empty and missing
+                // transaction metadata fields apart
+                Ok(bigtable_block) => {
+                    if generated::ConfirmedBlock::from(bigtable_block)
+                        != generated::ConfirmedBlock::from(blockstore_block.clone())
+                    {
+                        error!("Block in slot {} differs from blockstore", slot);
+                        mismatches += 1;
+                    }
+                }
+                Err(err) => {
+                    error!("get_confirmed_block for {} failed: {:?}", slot, err);
+                    mismatches += 1;
+                }
+            }
+        }
     }
 
     measure.stop();
-    info!("{}", measure);
-    if failures > 0 {
-        Err(format!("Incomplete upload, {} operations failed", failures).into())
-    } else {
-        Ok(())
+    info!(
+        "{} for {} blocks, {} did not match",
+        measure, num_verified, mismatches
+    );
+    mismatches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        blockstore_processor::fill_blockstore_slot_with_ticks,
+        genesis_utils::create_genesis_config, get_tmp_ledger_path,
+    };
+    use solana_sdk::hash::Hash;
+
+    #[test]
+    fn test_upload_confirmed_blocks_checkpoint() {
+        let ledger_path = get_tmp_ledger_path!();
+        let blockstore = Arc::new(Blockstore::open(&ledger_path).unwrap());
+        let ticks_per_slot = create_genesis_config(100).genesis_config.ticks_per_slot;
+        let mut last_hash = Hash::default();
+        for slot in 1..=5 {
+            last_hash = fill_blockstore_slot_with_ticks(
+                &blockstore,
+                ticks_per_slot,
+                slot,
+                slot - 1,
+                last_hash,
+            );
+        }
+        blockstore.set_roots(&[1, 2, 3, 4, 5]).unwrap();
+
+        let storage_path = tempfile::TempDir::new().unwrap();
+        let bigtable =
+            solana_storage_bigtable::LedgerStorage::new_local(storage_path.path(), false).unwrap();
+        let checkpoint_path = storage_path.path().join("checkpoint");
+        let config = ConfirmedBlockUploadConfig {
+            num_blocks_to_upload_in_parallel: 2,
+            block_read_ahead_depth: 2,
+            checkpoint_path: Some(checkpoint_path.clone()),
+            verify: true,
+            ..ConfirmedBlockUploadConfig::default()
+        };
+        let upload = |starting_slot, ending_slot| {
+            let mut runtime = tokio::runtime::Runtime::new().unwrap();
+            runtime
+                .block_on(upload_confirmed_blocks(
+                    blockstore.clone(),
+                    bigtable.clone(),
+                    starting_slot,
+                    Some(ending_slot),
+                    config.clone(),
+                    Arc::new(AtomicBool::new(false)),
+                ))
+                .unwrap();
+            read_checkpoint(&checkpoint_path).unwrap().unwrap()
+        };
+
+        assert_eq!(
+            upload(1, 3),
+            UploadCheckpoint {
+                starting_slot: 1,
+                last_uploaded_slot: 3
+            }
+        );
+        // Continuing from the end of the checkpoint extends it
+        assert_eq!(
+            upload(4, 5),
+            UploadCheckpoint {
+                starting_slot: 1,
+                last_uploaded_slot: 5
+            }
+        );
+        // Everything after the checkpoint is already uploaded, and still verifies
+        assert_eq!(
+            upload(2, 5),
+            UploadCheckpoint {
+                starting_slot: 1,
+                last_uploaded_slot: 5
+            }
+        );
+
+        let mut runtime = tokio::runtime::Runtime::new().unwrap();
+        assert_eq!(
+            runtime
+                .block_on(bigtable.get_confirmed_blocks(0, 10))
+                .unwrap(),
+            vec![1, 2, 3, 4, 5]
+        );
+
+        drop(blockstore);
+        Blockstore::destroy(&ledger_path).unwrap();
+    }
+
+    #[test]
+    fn test_read_checkpoint() {
+        let checkpoint_dir = tempfile::TempDir::new().unwrap();
+        let checkpoint_path = checkpoint_dir.path().join("checkpoint");
+        assert_eq!(read_checkpoint(&checkpoint_path), Ok(None));
+
+        let checkpoint = UploadCheckpoint {
+            starting_slot: 7,
+            last_uploaded_slot: 42,
+        };
+        write_checkpoint(&checkpoint_path, checkpoint).unwrap();
+        assert_eq!(read_checkpoint(&checkpoint_path), Ok(Some(checkpoint)));
+
+        fs::write(&checkpoint_path, "42 7").unwrap();
+        assert!(read_checkpoint(&checkpoint_path).is_err());
+        fs::write(&checkpoint_path, "42").unwrap();
+        assert!(read_checkpoint(&checkpoint_path).is_err());
     }
 }
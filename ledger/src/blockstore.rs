@@ -20,7 +20,6 @@ use rayon::{
     iter::{IntoParallelRefIterator, ParallelIterator},
     ThreadPool,
 };
-use rocksdb::DBRawIterator;
 use solana_measure::measure::Measure;
 use solana_metrics::{datapoint_debug, datapoint_error};
 use solana_rayon_threadlimit::get_thread_count;
@@ -29,6 +28,7 @@ use solana_runtime::{
     vote_account::ArcVoteAccount,
 };
 use solana_sdk::{
+    account::Account,
     clock::{Slot, UnixTimestamp, DEFAULT_TICKS_PER_SECOND, MS_PER_TICK},
     genesis_config::GenesisConfig,
     hash::Hash,
@@ -98,6 +98,31 @@ pub enum PurgeType {
     PrimaryIndex,
 }
 
+/// The last slot purged from each of the transaction metadata columns that can be retained for
+/// longer than the shreds of their slots, or 0 if none was purged
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TransactionMetadataCleanupSlots {
+    pub transaction_status: Slot,
+    pub address_signatures: Slot,
+    pub rewards: Slot,
+}
+
+impl TransactionMetadataCleanupSlots {
+    pub fn new(slot: Slot) -> Self {
+        Self {
+            transaction_status: slot,
+            address_signatures: slot,
+            rewards: slot,
+        }
+    }
+
+    /// Transaction statuses and address signatures are only returned for rooted slots, so roots
+    /// are retained for as long as either of them
+    pub fn root(&self) -> Slot {
+        self.transaction_status.min(self.address_signatures)
+    }
+}
+
 #[derive(Error, Debug)]
 pub enum InsertDataShredError {
     Exists,
@@ -142,11 +167,14 @@ pub struct Blockstore {
     rewards_cf: LedgerColumn<cf::Rewards>,
     blocktime_cf: LedgerColumn<cf::Blocktime>,
     perf_samples_cf: LedgerColumn<cf::PerfSamples>,
+    account_history_cf: LedgerColumn<cf::AccountHistory>,
+    account_history_slots_cf: LedgerColumn<cf::AccountHistorySlots>,
     last_root: Arc<RwLock<Slot>>,
     insert_shreds_lock: Arc<Mutex<()>>,
     pub new_shreds_signals: Vec<SyncSender<bool>>,
     pub completed_slots_senders: Vec<SyncSender<Vec<Slot>>>,
     pub lowest_cleanup_slot: Arc<RwLock<u64>>,
+    pub lowest_cleanup_transaction_metadata_slots: Arc<RwLock<TransactionMetadataCleanupSlots>>,
     no_compaction: bool,
 }
 
@@ -278,7 +306,19 @@ impl Blockstore {
         let mut measure = Measure::start("open");
         info!("Opening database at {:?}", blockstore_path);
         let db = Database::open(&blockstore_path, access_type, recovery_mode)?;
+        let blockstore = Self::open_with_database(db)?;
+        measure.stop();
+        info!("{:?} {}", blockstore_path, measure);
+        Ok(blockstore)
+    }
+
+    /// Open an empty blockstore that is only kept in memory, for tests that don't need the
+    /// ledger to be persisted
+    pub fn open_in_memory() -> Result<Blockstore> {
+        Self::open_with_database(Database::open_in_memory())
+    }
 
+    fn open_with_database(db: Database) -> Result<Blockstore> {
         // Create the metadata column family
         let meta_cf = db.column();
 
@@ -301,6 +341,8 @@ impl Blockstore {
         let rewards_cf = db.column();
         let blocktime_cf = db.column();
         let perf_samples_cf = db.column();
+        let account_history_cf = db.column();
+        let account_history_slots_cf = db.column();
 
         let db = Arc::new(db);
 
@@ -328,8 +370,9 @@ impl Blockstore {
             })
             .unwrap_or(0);
 
-        measure.stop();
-        info!("{:?} {}", blockstore_path, measure);
+        let lowest_cleanup_transaction_metadata_slots =
+            Self::find_lowest_cleanup_transaction_metadata_slots(&db)?;
+
         let blockstore = Blockstore {
             db,
             meta_cf,
@@ -347,11 +390,16 @@ impl Blockstore {
             rewards_cf,
             blocktime_cf,
             perf_samples_cf,
+            account_history_cf,
+            account_history_slots_cf,
             new_shreds_signals: vec![],
             completed_slots_senders: vec![],
             insert_shreds_lock: Arc::new(Mutex::new(())),
             last_root,
             lowest_cleanup_slot: Arc::new(RwLock::new(0)),
+            lowest_cleanup_transaction_metadata_slots: Arc::new(RwLock::new(
+                lowest_cleanup_transaction_metadata_slots,
+            )),
             no_compaction: false,
         };
         if initialize_transaction_status_index {
@@ -360,23 +408,55 @@ impl Blockstore {
         Ok(blockstore)
     }
 
+    // Transaction metadata purged by a previous run may still be on disk, as transaction statuses
+    // and address signatures are deleted a whole primary index at a time. Slots below the lowest
+    // surviving root were purged from them, and so were slots below the lowest surviving rewards
+    fn find_lowest_cleanup_transaction_metadata_slots(
+        db: &Database,
+    ) -> Result<TransactionMetadataCleanupSlots> {
+        let root = db
+            .iter::<cf::Root>(IteratorMode::Start)?
+            .next()
+            .map(|(slot, _)| slot.saturating_sub(1))
+            .unwrap_or_default();
+        let rewards = db
+            .iter::<cf::Rewards>(IteratorMode::Start)?
+            .next()
+            .map(|(slot, _)| slot.saturating_sub(1))
+            .unwrap_or_default();
+        Ok(TransactionMetadataCleanupSlots {
+            transaction_status: root,
+            address_signatures: root,
+            rewards,
+        })
+    }
+
     pub fn open_with_signal(
         ledger_path: &Path,
         recovery_mode: Option<BlockstoreRecoveryMode>,
     ) -> Result<BlockstoreSignals> {
-        let mut blockstore =
+        let blockstore =
             Self::open_with_access_type(ledger_path, AccessType::PrimaryOnly, recovery_mode)?;
+        Ok(Self::with_signals(blockstore))
+    }
+
+    /// Like `open_with_signal`, but for a blockstore that is only kept in memory
+    pub fn open_in_memory_with_signal() -> Result<BlockstoreSignals> {
+        Ok(Self::with_signals(Self::open_in_memory()?))
+    }
+
+    fn with_signals(mut blockstore: Blockstore) -> BlockstoreSignals {
         let (ledger_signal_sender, ledger_signal_receiver) = sync_channel(1);
         let (completed_slots_sender, completed_slots_receiver) =
             sync_channel(MAX_COMPLETED_SLOTS_IN_CHANNEL);
         blockstore.new_shreds_signals = vec![ledger_signal_sender];
         blockstore.completed_slots_senders = vec![completed_slots_sender];
 
-        Ok(BlockstoreSignals {
+        BlockstoreSignals {
             blockstore,
             ledger_signal_receiver,
             completed_slots_receiver,
-        })
+        }
     }
 
     pub fn add_tree(
@@ -1476,7 +1556,8 @@ impl Blockstore {
     // indexes in the ledger in the range [start_index, end_index)
     // for the slot with the specified slot
     fn find_missing_indexes<C>(
-        db_iterator: &mut DBRawIterator,
+        // Starts at the first shred of the slot with index >= start_index
+        mut db_iterator: impl Iterator<Item = (C::Index, Box<[u8]>)>,
         slot: Slot,
         first_timestamp: u64,
         start_index: u64,
@@ -1494,22 +1575,21 @@ impl Blockstore {
         let ticks_since_first_insert =
             DEFAULT_TICKS_PER_SECOND * (timestamp() - first_timestamp) / 1000;
 
-        // Seek to the first shred with index >= start_index
-        db_iterator.seek(&C::key((slot, start_index)));
-
         // The index of the first missing shred in the slot
         let mut prev_index = start_index;
         'outer: loop {
-            if !db_iterator.valid() {
-                for i in prev_index..end_index {
-                    missing_indexes.push(i);
-                    if missing_indexes.len() == max_missing {
-                        break;
+            let ((current_slot, index), value) = match db_iterator.next() {
+                Some(item) => item,
+                None => {
+                    for i in prev_index..end_index {
+                        missing_indexes.push(i);
+                        if missing_indexes.len() == max_missing {
+                            break;
+                        }
                     }
+                    break;
                 }
-                break;
-            }
-            let (current_slot, index) = C::index(&db_iterator.key().expect("Expect a valid key"));
+            };
 
             let current_index = {
                 if current_slot > slot {
@@ -1521,9 +1601,7 @@ impl Blockstore {
 
             let upper_index = cmp::min(current_index, end_index);
             // the tick that will be used to figure out the timeout for this hole
-            let reference_tick = u64::from(Shred::reference_tick_from_data(
-                &db_iterator.value().expect("couldn't read value"),
-            ));
+            let reference_tick = u64::from(Shred::reference_tick_from_data(&value));
 
             if ticks_since_first_insert < reference_tick + MAX_TURBINE_DELAY_IN_TICKS {
                 // The higher index holes have not timed out yet
@@ -1545,7 +1623,6 @@ impl Blockstore {
             }
 
             prev_index = current_index + 1;
-            db_iterator.next();
         }
 
         missing_indexes
@@ -1559,12 +1636,12 @@ impl Blockstore {
         end_index: u64,
         max_missing: usize,
     ) -> Vec<u64> {
-        if let Ok(mut db_iterator) = self
-            .db
-            .raw_iterator_cf(self.db.cf_handle::<cf::ShredData>())
-        {
+        if let Ok(db_iterator) = self.db.iter::<cf::ShredData>(IteratorMode::From(
+            (slot, start_index),
+            IteratorDirection::Forward,
+        )) {
             Self::find_missing_indexes::<cf::ShredData>(
-                &mut db_iterator,
+                db_iterator,
                 slot,
                 first_timestamp,
                 start_index,
@@ -1674,6 +1751,27 @@ impl Blockstore {
         Ok(root_iterator.next().unwrap_or_default())
     }
 
+    /// Returns the lowest root whose address signatures are retained. This is below
+    /// `get_first_available_block()` when address signatures are kept for longer than shreds
+    pub fn get_first_available_address_signatures_slot(&self) -> Result<Slot> {
+        let lowest_cleanup_slot = self
+            .lowest_cleanup_transaction_metadata_slots
+            .read()
+            .unwrap()
+            .address_signatures;
+        let start_slot = if lowest_cleanup_slot > 0 {
+            lowest_cleanup_slot + 1
+        } else {
+            0
+        };
+        Ok(self
+            .db
+            .iter::<cf::Root>(IteratorMode::From(start_slot, IteratorDirection::Forward))?
+            .map(|(slot, _)| slot)
+            .next()
+            .unwrap_or_default())
+    }
+
     pub fn get_confirmed_block(&self, slot: Slot) -> Result<ConfirmedBlock> {
         datapoint_info!(
             "blockstore-rpc-api",
@@ -1887,6 +1985,11 @@ impl Blockstore {
         signature: Signature,
     ) -> Result<(Option<(Slot, TransactionStatusMeta)>, u64)> {
         let mut counter = 0;
+        let lowest_cleanup_slot = self
+            .lowest_cleanup_transaction_metadata_slots
+            .read()
+            .unwrap()
+            .transaction_status;
         for transaction_status_cf_primary_index in 0..=1 {
             let index_iterator = self.transaction_status_cf.iter(IteratorMode::From(
                 (transaction_status_cf_primary_index, signature, 0),
@@ -1897,6 +2000,10 @@ impl Blockstore {
                 if i != transaction_status_cf_primary_index || sig != signature {
                     break;
                 }
+                // Purged statuses remain on disk until their whole primary index is purged
+                if lowest_cleanup_slot > 0 && slot <= lowest_cleanup_slot {
+                    continue;
+                }
                 if self.is_root(slot) {
                     let status: TransactionStatusMeta = deserialize(&data)?;
                     return Ok((Some((slot, status)), counter));
@@ -1967,6 +2074,17 @@ impl Blockstore {
         end_slot: Slot,
     ) -> Result<Vec<(Slot, Signature)>> {
         let mut signatures: Vec<(Slot, Signature)> = vec![];
+        let lowest_cleanup_slot = self
+            .lowest_cleanup_transaction_metadata_slots
+            .read()
+            .unwrap()
+            .address_signatures;
+        // Purged signatures remain on disk until their whole primary index is purged
+        let start_slot = if lowest_cleanup_slot > 0 {
+            start_slot.max(lowest_cleanup_slot + 1)
+        } else {
+            start_slot
+        };
         for transaction_status_cf_primary_index in 0..=1 {
             let index_iterator = self.address_signatures_cf.iter(IteratorMode::From(
                 (
@@ -2125,7 +2243,7 @@ impl Blockstore {
         get_until_slot_timer.stop();
 
         // Fetch the list of signatures that affect the given address
-        let first_available_block = self.get_first_available_block()?;
+        let first_available_slot = self.get_first_available_address_signatures_slot()?;
         let mut address_signatures = vec![];
 
         // Get signatures in `slot`
@@ -2168,7 +2286,7 @@ impl Blockstore {
                     }
                     if i == starting_primary_index
                         && key_address == address
-                        && slot >= first_available_block
+                        && slot >= first_available_slot
                     {
                         if self.is_root(slot) {
                             address_signatures.push((slot, signature));
@@ -2204,9 +2322,7 @@ impl Blockstore {
                 if slot < lowest_slot {
                     break;
                 }
-                if i == next_primary_index
-                    && key_address == address
-                    && slot >= first_available_block
+                if i == next_primary_index && key_address == address && slot >= first_available_slot
                 {
                     if self.is_root(slot) {
                         address_signatures.push((slot, signature));
@@ -2335,6 +2451,95 @@ impl Blockstore {
         self.perf_samples_cf.put(index, perf_sample)
     }
 
+    /// Record the accounts written by a rooted slot, along with their hashes. Every recorded slot
+    /// is kept in `AccountHistorySlots`, even if it wrote no accounts
+    pub fn write_account_history(
+        &self,
+        slot: Slot,
+        accounts: &[(Pubkey, Account, Hash)],
+    ) -> Result<()> {
+        let mut write_batch = self.db.batch()?;
+        for (pubkey, account, hash) in accounts {
+            write_batch.put::<cf::AccountHistory>(
+                (*pubkey, slot),
+                &AccountHistoryEntry {
+                    hash: *hash,
+                    account: account.clone(),
+                },
+            )?;
+        }
+        let pubkeys: Vec<_> = accounts.iter().map(|(pubkey, _, _)| *pubkey).collect();
+        write_batch.put::<cf::AccountHistorySlots>(slot, &pubkeys)?;
+        self.db.write(write_batch)
+    }
+
+    /// Returns the first and last slots recorded in the account history, if any
+    pub fn get_account_history_slot_range(&self) -> Result<Option<(Slot, Slot)>> {
+        let first_slot = self
+            .account_history_slots_cf
+            .iter(IteratorMode::Start)?
+            .next()
+            .map(|(slot, _)| slot);
+        let last_slot = self
+            .account_history_slots_cf
+            .iter(IteratorMode::End)?
+            .next()
+            .map(|(slot, _)| slot);
+        Ok(first_slot.zip(last_slot))
+    }
+
+    /// Returns the last recorded write of `pubkey` at or before `slot`, and the slot of that write
+    pub fn get_account_history_at_slot(
+        &self,
+        pubkey: &Pubkey,
+        slot: Slot,
+    ) -> Result<Option<(Slot, AccountHistoryEntry)>> {
+        let entry = self
+            .account_history_cf
+            .iter(IteratorMode::From(
+                (*pubkey, slot),
+                IteratorDirection::Reverse,
+            ))?
+            .next();
+        match entry {
+            Some(((entry_pubkey, entry_slot), data)) if entry_pubkey == *pubkey => {
+                Ok(Some((entry_slot, deserialize(&data)?)))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Returns the slot of the first recorded write of `pubkey` after `slot`
+    pub fn get_next_account_history_slot(
+        &self,
+        pubkey: &Pubkey,
+        slot: Slot,
+    ) -> Result<Option<Slot>> {
+        let next_slot = match slot.checked_add(1) {
+            Some(next_slot) => next_slot,
+            None => return Ok(None),
+        };
+        Ok(self
+            .account_history_cf
+            .iter(IteratorMode::From(
+                (*pubkey, next_slot),
+                IteratorDirection::Forward,
+            ))?
+            .next()
+            .map(|((entry_pubkey, entry_slot), _)| (entry_pubkey, entry_slot))
+            .filter(|(entry_pubkey, _)| entry_pubkey == pubkey)
+            .map(|(_, entry_slot)| entry_slot))
+    }
+
+    /// Returns every recorded write of `pubkey`, in slot order
+    pub fn get_account_history(&self, pubkey: &Pubkey) -> Result<Vec<(Slot, AccountHistoryEntry)>> {
+        self.account_history_cf
+            .iter(IteratorMode::From((*pubkey, 0), IteratorDirection::Forward))?
+            .take_while(|((entry_pubkey, _), _)| entry_pubkey == pubkey)
+            .map(|((_, slot), data)| Ok((slot, deserialize(&data)?)))
+            .collect()
+    }
+
     /// Returns the entry vector for the slot starting with `shred_start_index`
     pub fn get_slot_entries(&self, slot: Slot, shred_start_index: u64) -> Result<Vec<Entry>> {
         self.get_slot_entries_with_shred_info(slot, shred_start_index, false)
@@ -3160,17 +3365,9 @@ fn slot_has_updates(slot_meta: &SlotMeta, slot_meta_backup: &Option<SlotMeta>) -
 // Creates a new ledger with slot 0 full of ticks (and only ticks).
 //
 // Returns the blockhash that can be used to append entries with.
-pub fn create_new_ledger(
-    ledger_path: &Path,
-    genesis_config: &GenesisConfig,
-    max_genesis_archive_unpacked_size: u64,
-    access_type: AccessType,
-) -> Result<Hash> {
-    Blockstore::destroy(ledger_path)?;
-    genesis_config.write(&ledger_path)?;
-
-    // Fill slot 0 with ticks that link back to the genesis_config to bootstrap the ledger.
-    let blockstore = Blockstore::open_with_access_type(ledger_path, access_type, None)?;
+/// Fill slot 0 with ticks that link back to the genesis_config to bootstrap the ledger, returning
+/// the hash of the last tick
+pub fn write_genesis_slot(blockstore: &Blockstore, genesis_config: &GenesisConfig) -> Result<Hash> {
     let ticks_per_slot = genesis_config.ticks_per_slot;
     let hashes_per_tick = genesis_config.poh_config.hashes_per_tick.unwrap_or(0);
     let entries = create_ticks(ticks_per_slot, hashes_per_tick, genesis_config.hash());
@@ -3184,6 +3381,20 @@ pub fn create_new_ledger(
 
     blockstore.insert_shreds(shreds, None, false)?;
     blockstore.set_roots(&[0])?;
+    Ok(last_hash)
+}
+
+pub fn create_new_ledger(
+    ledger_path: &Path,
+    genesis_config: &GenesisConfig,
+    max_genesis_archive_unpacked_size: u64,
+    access_type: AccessType,
+) -> Result<Hash> {
+    Blockstore::destroy(ledger_path)?;
+    genesis_config.write(&ledger_path)?;
+
+    let blockstore = Blockstore::open_with_access_type(ledger_path, access_type, None)?;
+    let last_hash = write_genesis_slot(&blockstore, genesis_config)?;
     // Explicitly close the blockstore before we create the archived genesis file
     drop(blockstore);
 
@@ -3473,6 +3684,7 @@ pub mod tests {
     use bincode::serialize;
     use itertools::Itertools;
     use rand::{seq::SliceRandom, thread_rng};
+    use solana_account_decoder::parse_token::token_amount_to_ui_amount;
     use solana_runtime::bank::{Bank, RewardType};
     use solana_sdk::{
         hash::{self, hash, Hash},
@@ -3484,7 +3696,7 @@ pub mod tests {
         transaction::TransactionError,
     };
     use solana_storage_proto::convert::generated;
-    use solana_transaction_status::{InnerInstructions, Reward, Rewards};
+    use solana_transaction_status::{InnerInstructions, Reward, Rewards, TransactionTokenBalance};
     use solana_vote_program::{vote_instruction, vote_state::Vote};
     use std::{iter::FromIterator, time::Duration};
 
@@ -5687,6 +5899,9 @@ pub mod tests {
                             post_balances: post_balances.clone(),
                             inner_instructions: Some(vec![]),
                             log_messages: Some(vec![]),
+                            pre_token_balances: Some(vec![]),
+                            post_token_balances: Some(vec![]),
+                            loaded_addresses: None,
                         },
                     )
                     .unwrap();
@@ -5701,6 +5916,9 @@ pub mod tests {
                             post_balances: post_balances.clone(),
                             inner_instructions: Some(vec![]),
                             log_messages: Some(vec![]),
+                            pre_token_balances: Some(vec![]),
+                            post_token_balances: Some(vec![]),
+                            loaded_addresses: None,
                         },
                     )
                     .unwrap();
@@ -5713,6 +5931,9 @@ pub mod tests {
                         post_balances,
                         inner_instructions: Some(vec![]),
                         log_messages: Some(vec![]),
+                        pre_token_balances: Some(vec![]),
+                        post_token_balances: Some(vec![]),
+                        loaded_addresses: None,
                     }),
                 }
             })
@@ -5903,6 +6124,12 @@ pub mod tests {
                 instructions: vec![CompiledInstruction::new(1, &(), vec![0])],
             }];
             let log_messages_vec = vec![String::from("Test message\n")];
+            let pre_token_balances_vec = vec![];
+            let post_token_balances_vec = vec![TransactionTokenBalance {
+                account_index: 0,
+                mint: Pubkey::new_unique().to_string(),
+                ui_token_amount: token_amount_to_ui_amount(42, 2),
+            }];
 
             // result not found
             assert!(transaction_status_cf
@@ -5923,6 +6150,9 @@ pub mod tests {
                         post_balances: post_balances_vec.clone(),
                         inner_instructions: Some(inner_instructions_vec.clone()),
                         log_messages: Some(log_messages_vec.clone()),
+                        pre_token_balances: Some(pre_token_balances_vec.clone()),
+                        post_token_balances: Some(post_token_balances_vec.clone()),
+                        loaded_addresses: None,
                     },
                 )
                 .is_ok());
@@ -5935,6 +6165,9 @@ pub mod tests {
                 post_balances,
                 inner_instructions,
                 log_messages,
+                pre_token_balances,
+                post_token_balances,
+                loaded_addresses,
             } = transaction_status_cf
                 .get((0, Signature::default(), 0))
                 .unwrap()
@@ -5945,6 +6178,9 @@ pub mod tests {
             assert_eq!(post_balances, post_balances_vec);
             assert_eq!(inner_instructions.unwrap(), inner_instructions_vec);
             assert_eq!(log_messages.unwrap(), log_messages_vec);
+            assert_eq!(pre_token_balances.unwrap(), pre_token_balances_vec);
+            assert_eq!(post_token_balances.unwrap(), post_token_balances_vec);
+            assert_eq!(loaded_addresses, None);
 
             // insert value
             assert!(transaction_status_cf
@@ -5957,6 +6193,9 @@ pub mod tests {
                         post_balances: post_balances_vec.clone(),
                         inner_instructions: Some(inner_instructions_vec.clone()),
                         log_messages: Some(log_messages_vec.clone()),
+                        pre_token_balances: Some(pre_token_balances_vec.clone()),
+                        post_token_balances: Some(post_token_balances_vec.clone()),
+                        loaded_addresses: None,
                     },
                 )
                 .is_ok());
@@ -5969,6 +6208,9 @@ pub mod tests {
                 post_balances,
                 inner_instructions,
                 log_messages,
+                pre_token_balances,
+                post_token_balances,
+                loaded_addresses,
             } = transaction_status_cf
                 .get((0, Signature::new(&[2u8; 64]), 9))
                 .unwrap()
@@ -5981,6 +6223,9 @@ pub mod tests {
             assert_eq!(post_balances, post_balances_vec);
             assert_eq!(inner_instructions.unwrap(), inner_instructions_vec);
             assert_eq!(log_messages.unwrap(), log_messages_vec);
+            assert_eq!(pre_token_balances.unwrap(), pre_token_balances_vec);
+            assert_eq!(post_token_balances.unwrap(), post_token_balances_vec);
+            assert_eq!(loaded_addresses, None);
         }
         Blockstore::destroy(&blockstore_path).expect("Expected successful database destruction");
     }
@@ -6209,6 +6454,9 @@ pub mod tests {
                 post_balances: post_balances_vec,
                 inner_instructions: Some(vec![]),
                 log_messages: Some(vec![]),
+                pre_token_balances: Some(vec![]),
+                post_token_balances: Some(vec![]),
+                loaded_addresses: None,
             };
 
             let signature1 = Signature::new(&[1u8; 64]);
@@ -6343,6 +6591,8 @@ pub mod tests {
                     instructions: vec![CompiledInstruction::new(1, &(), vec![0])],
                 }]);
                 let log_messages = Some(vec![String::from("Test message\n")]);
+                let pre_token_balances = Some(vec![]);
+                let post_token_balances = Some(vec![]);
                 let signature = transaction.signatures[0];
                 blockstore
                     .transaction_status_cf
@@ -6355,6 +6605,9 @@ pub mod tests {
                             post_balances: post_balances.clone(),
                             inner_instructions: inner_instructions.clone(),
                             log_messages: log_messages.clone(),
+                            pre_token_balances: pre_token_balances.clone(),
+                            post_token_balances: post_token_balances.clone(),
+                            loaded_addresses: None,
                         },
                     )
                     .unwrap();
@@ -6367,6 +6620,9 @@ pub mod tests {
                         post_balances,
                         inner_instructions,
                         log_messages,
+                        pre_token_balances,
+                        post_token_balances,
+                        loaded_addresses: None,
                     }),
                 }
             })
@@ -6808,6 +7064,9 @@ pub mod tests {
                             post_balances: vec![],
                             inner_instructions: Some(vec![]),
                             log_messages: Some(vec![]),
+                            pre_token_balances: Some(vec![]),
+                            post_token_balances: Some(vec![]),
+                            loaded_addresses: None,
                         },
                     )
                     .unwrap();
@@ -6864,6 +7123,72 @@ pub mod tests {
         Blockstore::destroy(&blockstore_path).expect("Expected successful database destruction");
     }
 
+    #[test]
+    fn test_write_get_account_history() {
+        let blockstore_path = get_tmp_ledger_path!();
+        {
+            let blockstore = Blockstore::open(&blockstore_path).unwrap();
+            // Keys sort bytewise, so the lookup must not run into a neighbouring pubkey
+            let pubkey = Pubkey::new(&[1; 32]);
+            let lower_pubkey = Pubkey::new(&[0; 32]);
+            let higher_pubkey = Pubkey::new(&[2; 32]);
+            let account = |lamports| Account::new(lamports, 0, &Pubkey::default());
+
+            blockstore
+                .write_account_history(3, &[(lower_pubkey, account(1), Hash::new_unique())])
+                .unwrap();
+            let history: Vec<_> = [5, 8]
+                .iter()
+                .map(|slot| {
+                    let hash = Hash::new_unique();
+                    blockstore
+                        .write_account_history(
+                            *slot,
+                            &[
+                                (pubkey, account(*slot), hash),
+                                (higher_pubkey, account(1), Hash::new_unique()),
+                            ],
+                        )
+                        .unwrap();
+                    (
+                        *slot,
+                        AccountHistoryEntry {
+                            hash,
+                            account: account(*slot),
+                        },
+                    )
+                })
+                .collect();
+
+            assert_eq!(blockstore.get_account_history(&pubkey).unwrap(), history);
+            assert_eq!(
+                blockstore.get_account_history_at_slot(&pubkey, 4).unwrap(),
+                None
+            );
+            assert_eq!(
+                blockstore.get_account_history_at_slot(&pubkey, 5).unwrap(),
+                Some(history[0].clone())
+            );
+            assert_eq!(
+                blockstore.get_account_history_at_slot(&pubkey, 7).unwrap(),
+                Some(history[0].clone())
+            );
+            assert_eq!(
+                blockstore
+                    .get_account_history_at_slot(&pubkey, 100)
+                    .unwrap(),
+                Some(history[1].clone())
+            );
+            assert_eq!(
+                blockstore
+                    .get_account_history(&solana_sdk::pubkey::new_rand())
+                    .unwrap(),
+                vec![]
+            );
+        }
+        Blockstore::destroy(&blockstore_path).expect("Expected successful database destruction");
+    }
+
     #[test]
     fn test_lowest_slot() {
         let blockstore_path = get_tmp_ledger_path!();
@@ -7327,4 +7652,52 @@ pub mod tests {
         assert!(stored_shred.last_in_slot());
         assert_eq!(entries, ledger.get_any_valid_slot_entries(0, 0));
     }
+
+    #[test]
+    fn test_open_in_memory() {
+        let blockstore = Blockstore::open_in_memory().unwrap();
+        let (shreds, entries) = make_slot_entries(1, 0, 10);
+        blockstore.insert_shreds(shreds, None, false).unwrap();
+        assert!(blockstore.is_full(1));
+        assert_eq!(blockstore.get_slot_entries(1, 0).unwrap(), entries);
+
+        blockstore.set_roots(&[0, 1, 3]).unwrap();
+        assert_eq!(blockstore.max_root(), 3);
+        // Reverse iteration from a missing key starts at the closest lower key
+        let roots: Vec<_> = blockstore
+            .db
+            .iter::<cf::Root>(IteratorMode::From(2, IteratorDirection::Reverse))
+            .unwrap()
+            .map(|(slot, _)| slot)
+            .collect();
+        assert_eq!(roots, vec![1, 0]);
+
+        // Purging deletes ranges of keys
+        blockstore.purge_slots(1, 1, PurgeType::Exact);
+        assert!(blockstore.meta(1).unwrap().is_none());
+        assert!(blockstore
+            .get_data_shreds_for_slot(1, 0)
+            .unwrap()
+            .is_empty());
+        assert!(blockstore.is_root(0));
+        assert!(blockstore.is_root(3));
+    }
+
+    #[test]
+    fn test_write_genesis_slot_in_memory() {
+        let genesis_config = create_genesis_config(2).genesis_config;
+        let BlockstoreSignals {
+            blockstore,
+            ledger_signal_receiver,
+            ..
+        } = Blockstore::open_in_memory_with_signal().unwrap();
+
+        let last_hash = write_genesis_slot(&blockstore, &genesis_config).unwrap();
+        assert!(ledger_signal_receiver.try_recv().is_ok());
+        assert!(blockstore.is_full(0));
+        assert!(blockstore.is_root(0));
+        let entries = blockstore.get_slot_entries(0, 0).unwrap();
+        assert_eq!(entries.len() as u64, genesis_config.ticks_per_slot);
+        assert_eq!(entries.last().unwrap().hash, last_hash);
+    }
 }
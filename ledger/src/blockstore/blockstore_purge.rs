@@ -1,4 +1,5 @@
 use super::*;
+use crate::blockstore_db::ColumnName;
 
 #[derive(Default)]
 pub struct PurgeStats {
@@ -12,12 +13,24 @@ impl Blockstore {
     /// Does not check for integrity and does not update slot metas that refer to deleted slots
     /// Modifies multiple column families simultaneously
     pub fn purge_slots(&self, from_slot: Slot, to_slot: Slot, purge_type: PurgeType) {
+        self.purge_slots_with_type(from_slot, to_slot, Some(purge_type));
+    }
+
+    /// Like `purge_slots`, but retains the roots, transaction statuses, address signatures and
+    /// rewards of the purged slots, for `purge_transaction_metadata` to purge separately
+    pub fn purge_shreds(&self, from_slot: Slot, to_slot: Slot) {
+        self.purge_slots_with_type(from_slot, to_slot, None);
+    }
+
+    // A `purge_type` of `None` retains the transaction metadata of the purged slots
+    fn purge_slots_with_type(&self, from_slot: Slot, to_slot: Slot, purge_type: Option<PurgeType>) {
         let mut purge_stats = PurgeStats::default();
         let purge_result =
-            self.run_purge_with_stats(from_slot, to_slot, purge_type, &mut purge_stats);
+            self.run_purge_columns_with_stats(from_slot, to_slot, purge_type, &mut purge_stats);
 
         datapoint_info!(
             "blockstore-purge",
+            ("transaction_metadata", purge_type.is_some(), bool),
             ("from_slot", from_slot as i64, i64),
             ("to_slot", to_slot as i64, i64),
             ("delete_range_us", purge_stats.delete_range as i64, i64),
@@ -31,6 +44,59 @@ impl Blockstore {
         }
     }
 
+    /// Purges the transaction statuses, address signatures and rewards of the slots up to and
+    /// including their slot in `cleanup_slots`, along with the roots neither transaction statuses
+    /// nor address signatures need anymore. Transaction statuses and address signatures are
+    /// purged by primary index, so they stay on disk until their whole index is purged, but are
+    /// no longer returned
+    pub fn purge_transaction_metadata(&self, cleanup_slots: TransactionMetadataCleanupSlots) {
+        let mut purge_stats = PurgeStats::default();
+        let purge_result =
+            self.run_purge_transaction_metadata_with_stats(cleanup_slots, &mut purge_stats);
+
+        datapoint_info!(
+            "blockstore-purge-transaction-metadata",
+            (
+                "transaction_status_slot",
+                cleanup_slots.transaction_status as i64,
+                i64
+            ),
+            (
+                "address_signatures_slot",
+                cleanup_slots.address_signatures as i64,
+                i64
+            ),
+            ("rewards_slot", cleanup_slots.rewards as i64, i64),
+            ("delete_range_us", purge_stats.delete_range as i64, i64),
+            ("write_batch_us", purge_stats.write_batch as i64, i64)
+        );
+        if let Err(e) = purge_result {
+            error!(
+                "Error: {:?}; Purge of transaction metadata up to {:?} failed",
+                e, cleanup_slots
+            );
+        }
+    }
+
+    /// Purges the account history of the slots up to and including `to_slot`
+    pub fn purge_account_history(&self, to_slot: Slot) {
+        let mut purge_stats = PurgeStats::default();
+        let purge_result = self.run_purge_account_history_with_stats(to_slot, &mut purge_stats);
+
+        datapoint_info!(
+            "blockstore-purge-account-history",
+            ("to_slot", to_slot as i64, i64),
+            ("delete_range_us", purge_stats.delete_range as i64, i64),
+            ("write_batch_us", purge_stats.write_batch as i64, i64)
+        );
+        if let Err(e) = purge_result {
+            error!(
+                "Error: {:?}; Purge of account history up to {:?} failed",
+                e, to_slot
+            );
+        }
+    }
+
     pub fn purge_and_compact_slots(&self, from_slot: Slot, to_slot: Slot) {
         self.purge_slots(from_slot, to_slot, PurgeType::Exact);
         if let Err(e) = self.compact_storage(from_slot, to_slot) {
@@ -89,6 +155,16 @@ impl Blockstore {
         to_slot: Slot,
         purge_type: PurgeType,
         purge_stats: &mut PurgeStats,
+    ) -> Result<bool> {
+        self.run_purge_columns_with_stats(from_slot, to_slot, Some(purge_type), purge_stats)
+    }
+
+    fn run_purge_columns_with_stats(
+        &self,
+        from_slot: Slot,
+        to_slot: Slot,
+        purge_type: Option<PurgeType>,
+        purge_stats: &mut PurgeStats,
     ) -> Result<bool> {
         let mut write_batch = self
             .db
@@ -98,82 +174,216 @@ impl Blockstore {
         let to_slot = to_slot.checked_add(1).unwrap_or_else(|| std::u64::MAX);
 
         let mut delete_range_timer = Measure::start("delete_range");
-        let mut columns_purged = self
-            .db
-            .delete_range_cf::<cf::SlotMeta>(&mut write_batch, from_slot, to_slot)
-            .is_ok()
-            & self
+        let mut columns_purged = self.purge_shred_columns(&mut write_batch, from_slot, to_slot);
+        // Held until the batch is written, so that writers never see a toggled primary index
+        // before it is persisted
+        let mut w_active_transaction_status_index =
+            self.active_transaction_status_index.write().unwrap();
+        if let Some(purge_type) = purge_type {
+            columns_purged &= self
                 .db
                 .delete_range_cf::<cf::Root>(&mut write_batch, from_slot, to_slot)
                 .is_ok()
-            & self
+                & self
+                    .db
+                    .delete_range_cf::<cf::Rewards>(&mut write_batch, from_slot, to_slot)
+                    .is_ok();
+            self.purge_account_history_columns(&mut write_batch, from_slot, to_slot)?;
+            match purge_type {
+                PurgeType::Exact => {
+                    self.purge_special_columns_exact(&mut write_batch, from_slot, to_slot)?;
+                }
+                PurgeType::PrimaryIndex => {
+                    self.purge_special_columns_with_primary_index(
+                        &mut write_batch,
+                        &mut columns_purged,
+                        &mut w_active_transaction_status_index,
+                        to_slot,
+                        to_slot,
+                    )?;
+                }
+            }
+        }
+        delete_range_timer.stop();
+        self.write_purge_batch(write_batch, from_slot, purge_stats)?;
+        purge_stats.delete_range += delete_range_timer.as_us();
+        Ok(columns_purged)
+    }
+
+    // Returns whether or not all columns successfully purged the slot range
+    fn run_purge_transaction_metadata_with_stats(
+        &self,
+        cleanup_slots: TransactionMetadataCleanupSlots,
+        purge_stats: &mut PurgeStats,
+    ) -> Result<bool> {
+        let mut write_batch = self
+            .db
+            .batch()
+            .expect("Database Error: Failed to get write batch");
+
+        let mut delete_range_timer = Measure::start("delete_range");
+        let mut w_active_transaction_status_index =
+            self.active_transaction_status_index.write().unwrap();
+        let purged_slots = *self
+            .lowest_cleanup_transaction_metadata_slots
+            .read()
+            .unwrap();
+        // delete range cf is not inclusive
+        let mut columns_purged = true;
+        if cleanup_slots.root() > purged_slots.root() {
+            columns_purged &= self
                 .db
-                .delete_range_cf::<cf::ShredData>(&mut write_batch, from_slot, to_slot)
-                .is_ok()
+                .delete_range_cf::<cf::Root>(
+                    &mut write_batch,
+                    purged_slots.root(),
+                    cleanup_slots.root() + 1,
+                )
+                .is_ok();
+        }
+        if cleanup_slots.rewards > purged_slots.rewards {
+            columns_purged &= self
+                .db
+                .delete_range_cf::<cf::Rewards>(
+                    &mut write_batch,
+                    purged_slots.rewards,
+                    cleanup_slots.rewards + 1,
+                )
+                .is_ok();
+        }
+        self.purge_special_columns_with_primary_index(
+            &mut write_batch,
+            &mut columns_purged,
+            &mut w_active_transaction_status_index,
+            cleanup_slots.transaction_status + 1,
+            cleanup_slots.address_signatures + 1,
+        )?;
+        delete_range_timer.stop();
+        self.write_purge_batch(write_batch, purged_slots.root(), purge_stats)?;
+        purge_stats.delete_range += delete_range_timer.as_us();
+
+        // Only advanced once the batch is written, so that readers never skip metadata that is
+        // still stored
+        let mut w_lowest_cleanup_slots = self
+            .lowest_cleanup_transaction_metadata_slots
+            .write()
+            .unwrap();
+        w_lowest_cleanup_slots.transaction_status = w_lowest_cleanup_slots
+            .transaction_status
+            .max(cleanup_slots.transaction_status);
+        w_lowest_cleanup_slots.address_signatures = w_lowest_cleanup_slots
+            .address_signatures
+            .max(cleanup_slots.address_signatures);
+        w_lowest_cleanup_slots.rewards = w_lowest_cleanup_slots.rewards.max(cleanup_slots.rewards);
+        Ok(columns_purged)
+    }
+
+    fn run_purge_account_history_with_stats(
+        &self,
+        to_slot: Slot,
+        purge_stats: &mut PurgeStats,
+    ) -> Result<()> {
+        let mut write_batch = self
+            .db
+            .batch()
+            .expect("Database Error: Failed to get write batch");
+        let mut delete_range_timer = Measure::start("delete_range");
+        // delete range cf is not inclusive
+        let to_slot = to_slot.checked_add(1).unwrap_or(std::u64::MAX);
+        self.purge_account_history_columns(&mut write_batch, 0, to_slot)?;
+        delete_range_timer.stop();
+        self.write_purge_batch(write_batch, 0, purge_stats)?;
+        purge_stats.delete_range += delete_range_timer.as_us();
+        Ok(())
+    }
+
+    // `AccountHistory` is ordered by pubkey, so the writes of the purged slots are deleted one by
+    // one, as listed in `AccountHistorySlots`
+    fn purge_account_history_columns(
+        &self,
+        write_batch: &mut WriteBatch,
+        from_slot: Slot,
+        to_slot: Slot, // Exclusive
+    ) -> Result<()> {
+        for (slot, pubkeys) in self.account_history_slots_cf.iter(IteratorMode::From(
+            from_slot,
+            IteratorDirection::Forward,
+        ))? {
+            if slot >= to_slot {
+                break;
+            }
+            let pubkeys: Vec<Pubkey> = deserialize(&pubkeys)?;
+            for pubkey in pubkeys {
+                write_batch.delete::<cf::AccountHistory>((pubkey, slot))?;
+            }
+        }
+        self.db
+            .delete_range_cf::<cf::AccountHistorySlots>(write_batch, from_slot, to_slot)
+    }
+
+    fn write_purge_batch(
+        &self,
+        write_batch: WriteBatch,
+        from_slot: Slot,
+        purge_stats: &mut PurgeStats,
+    ) -> Result<()> {
+        let mut write_timer = Measure::start("write_batch");
+        if let Err(e) = self.db.write(write_batch) {
+            error!(
+                "Error: {:?} while submitting write batch for slot {:?} retrying...",
+                e, from_slot
+            );
+            return Err(e);
+        }
+        write_timer.stop();
+        purge_stats.write_batch += write_timer.as_us();
+        Ok(())
+    }
+
+    fn purge_shred_columns(
+        &self,
+        write_batch: &mut WriteBatch,
+        from_slot: Slot,
+        to_slot: Slot, // Exclusive
+    ) -> bool {
+        self.db
+            .delete_range_cf::<cf::SlotMeta>(write_batch, from_slot, to_slot)
+            .is_ok()
             & self
                 .db
-                .delete_range_cf::<cf::ShredCode>(&mut write_batch, from_slot, to_slot)
+                .delete_range_cf::<cf::ShredData>(write_batch, from_slot, to_slot)
                 .is_ok()
             & self
                 .db
-                .delete_range_cf::<cf::DeadSlots>(&mut write_batch, from_slot, to_slot)
+                .delete_range_cf::<cf::ShredCode>(write_batch, from_slot, to_slot)
                 .is_ok()
             & self
                 .db
-                .delete_range_cf::<cf::DuplicateSlots>(&mut write_batch, from_slot, to_slot)
+                .delete_range_cf::<cf::DeadSlots>(write_batch, from_slot, to_slot)
                 .is_ok()
             & self
                 .db
-                .delete_range_cf::<cf::ErasureMeta>(&mut write_batch, from_slot, to_slot)
+                .delete_range_cf::<cf::DuplicateSlots>(write_batch, from_slot, to_slot)
                 .is_ok()
             & self
                 .db
-                .delete_range_cf::<cf::Orphans>(&mut write_batch, from_slot, to_slot)
+                .delete_range_cf::<cf::ErasureMeta>(write_batch, from_slot, to_slot)
                 .is_ok()
             & self
                 .db
-                .delete_range_cf::<cf::Index>(&mut write_batch, from_slot, to_slot)
+                .delete_range_cf::<cf::Orphans>(write_batch, from_slot, to_slot)
                 .is_ok()
             & self
                 .db
-                .delete_range_cf::<cf::Rewards>(&mut write_batch, from_slot, to_slot)
+                .delete_range_cf::<cf::Index>(write_batch, from_slot, to_slot)
                 .is_ok()
             & self
                 .db
-                .delete_range_cf::<cf::Blocktime>(&mut write_batch, from_slot, to_slot)
+                .delete_range_cf::<cf::Blocktime>(write_batch, from_slot, to_slot)
                 .is_ok()
             & self
                 .db
-                .delete_range_cf::<cf::PerfSamples>(&mut write_batch, from_slot, to_slot)
-                .is_ok();
-        let mut w_active_transaction_status_index =
-            self.active_transaction_status_index.write().unwrap();
-        match purge_type {
-            PurgeType::Exact => {
-                self.purge_special_columns_exact(&mut write_batch, from_slot, to_slot)?;
-            }
-            PurgeType::PrimaryIndex => {
-                self.purge_special_columns_with_primary_index(
-                    &mut write_batch,
-                    &mut columns_purged,
-                    &mut w_active_transaction_status_index,
-                    to_slot,
-                )?;
-            }
-        }
-        delete_range_timer.stop();
-        let mut write_timer = Measure::start("write_batch");
-        if let Err(e) = self.db.write(write_batch) {
-            error!(
-                "Error: {:?} while submitting write batch for slot {:?} retrying...",
-                e, from_slot
-            );
-            return Err(e);
-        }
-        write_timer.stop();
-        purge_stats.delete_range += delete_range_timer.as_us();
-        purge_stats.write_batch += write_timer.as_us();
-        Ok(columns_purged)
+                .delete_range_cf::<cf::PerfSamples>(write_batch, from_slot, to_slot)
+                .is_ok()
     }
 
     pub fn compact_storage(&self, from_slot: Slot, to_slot: Slot) -> Result<bool> {
@@ -243,6 +453,10 @@ impl Blockstore {
             && self
                 .perf_samples_cf
                 .compact_range(from_slot, to_slot)
+                .unwrap_or(false)
+            && self
+                .account_history_slots_cf
+                .compact_range(from_slot, to_slot)
                 .unwrap_or(false);
         compact_timer.stop();
         if !result {
@@ -302,19 +516,43 @@ impl Blockstore {
         Ok(())
     }
 
-    /// Purges special columns (using a non-Slot primary-index) by range. Purge occurs if frozen
-    /// primary index has a max-slot less than the highest slot being purged.
+    /// Purges special columns (using a non-Slot primary-index) by range. A column is purged if
+    /// the frozen primary index has a max-slot less than the highest slot being purged from it,
+    /// and the primary index is toggled once both columns are purged.
     fn purge_special_columns_with_primary_index(
         &self,
         write_batch: &mut WriteBatch,
         columns_purged: &mut bool,
         w_active_transaction_status_index: &mut u64,
-        to_slot: Slot,
+        transaction_status_to_slot: Slot, // Exclusive
+        address_signatures_to_slot: Slot, // Exclusive
     ) -> Result<()> {
+        if transaction_status_to_slot != address_signatures_to_slot {
+            // The column retained for less time is purged from the frozen index ahead of the
+            // toggle
+            if let Some((index, max_slot)) = self.frozen_transaction_status_index()? {
+                if transaction_status_to_slot > max_slot
+                    && self.special_column_has_index::<cf::TransactionStatus>(index)?
+                {
+                    *columns_purged &= self
+                        .db
+                        .delete_range_cf::<cf::TransactionStatus>(write_batch, index, index + 1)
+                        .is_ok();
+                }
+                if address_signatures_to_slot > max_slot
+                    && self.special_column_has_index::<cf::AddressSignatures>(index)?
+                {
+                    *columns_purged &= self
+                        .db
+                        .delete_range_cf::<cf::AddressSignatures>(write_batch, index, index + 1)
+                        .is_ok();
+                }
+            }
+        }
         if let Some(index) = self.toggle_transaction_status_index(
             write_batch,
             w_active_transaction_status_index,
-            to_slot,
+            transaction_status_to_slot.min(address_signatures_to_slot),
         )? {
             *columns_purged &= self
                 .db
@@ -327,6 +565,34 @@ impl Blockstore {
         }
         Ok(())
     }
+
+    // Returns the frozen primary index and its max-slot, if any
+    fn frozen_transaction_status_index(&self) -> Result<Option<(u64, Slot)>> {
+        for index in 0..=1 {
+            if let Some(index_meta) = self.transaction_status_index_cf.get(index)? {
+                if index_meta.frozen {
+                    return Ok(Some((index, index_meta.max_slot)));
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    // Whether a special column still holds entries under primary index `index`
+    fn special_column_has_index<C>(&self, index: u64) -> Result<bool>
+    where
+        C: Column + ColumnName,
+    {
+        Ok(self
+            .db
+            .iter::<C>(IteratorMode::From(
+                C::as_index(index),
+                IteratorDirection::Forward,
+            ))?
+            .next()
+            .map(|(key, _)| C::primary_index(key) == index)
+            .unwrap_or(false))
+    }
 }
 
 #[cfg(test)]
@@ -423,6 +689,13 @@ pub mod tests {
                 .unwrap()
                 .next()
                 .map(|(slot, _)| slot >= min_slot)
+                .unwrap_or(true)
+            & blockstore
+                .db
+                .iter::<cf::AccountHistorySlots>(IteratorMode::Start)
+                .unwrap()
+                .next()
+                .map(|(slot, _)| slot >= min_slot)
                 .unwrap_or(true);
         assert!(condition_met);
     }
@@ -520,6 +793,143 @@ pub mod tests {
         Blockstore::destroy(&blockstore_path).expect("Expected successful database destruction");
     }
 
+    #[test]
+    fn test_purge_transaction_metadata() {
+        let blockstore_path = get_tmp_ledger_path!();
+        {
+            let blockstore = Blockstore::open(&blockstore_path).unwrap();
+            let (shreds, _) = make_many_slot_entries(0, 10, 5);
+            blockstore.insert_shreds(shreds, None, false).unwrap();
+            let roots: Vec<Slot> = (0..10).collect();
+            blockstore.set_roots(&roots).unwrap();
+            let signature = Signature::new(&[1; 64]);
+            let address = Pubkey::new(&[2; 32]);
+            blockstore
+                .write_transaction_status(
+                    2,
+                    signature,
+                    vec![&address],
+                    vec![],
+                    &TransactionStatusMeta::default(),
+                )
+                .unwrap();
+            blockstore.write_rewards(2, vec![]).unwrap();
+            let transaction_status = |blockstore: &Blockstore| {
+                blockstore
+                    .get_transaction_status(signature)
+                    .unwrap()
+                    .map(|(slot, _)| slot)
+            };
+            let address_signatures = |blockstore: &Blockstore| {
+                blockstore
+                    .get_confirmed_signatures_for_address(address, 0, 10)
+                    .unwrap()
+            };
+
+            // Purging shreds keeps the transaction metadata of the purged slots readable
+            blockstore.purge_shreds(0, 5);
+            assert!(blockstore.meta(2).unwrap().is_none());
+            assert!(blockstore.get_data_shred(2, 0).unwrap().is_none());
+            assert!(blockstore.is_root(2));
+            assert!(blockstore.read_rewards(2).unwrap().is_some());
+            assert_eq!(transaction_status(&blockstore), Some(2));
+            assert_eq!(address_signatures(&blockstore), vec![signature]);
+
+            // Each column is purged up to its own slot. Roots are kept for the address signatures
+            let cleanup_slots = TransactionMetadataCleanupSlots {
+                transaction_status: 5,
+                address_signatures: 0,
+                rewards: 5,
+            };
+            blockstore.purge_transaction_metadata(cleanup_slots);
+            assert_eq!(
+                *blockstore
+                    .lowest_cleanup_transaction_metadata_slots
+                    .read()
+                    .unwrap(),
+                cleanup_slots
+            );
+            assert!(blockstore.is_root(2));
+            assert!(blockstore.read_rewards(2).unwrap().is_none());
+            assert_eq!(transaction_status(&blockstore), None);
+            assert_eq!(address_signatures(&blockstore), vec![signature]);
+
+            // The first primary index purge only freezes the active index. Once frozen, the
+            // transaction statuses are deleted from it ahead of the address signatures
+            assert!(blockstore
+                .transaction_status_cf
+                .get((0, signature, 2))
+                .unwrap()
+                .is_some());
+            blockstore.purge_transaction_metadata(cleanup_slots);
+            assert!(blockstore
+                .transaction_status_cf
+                .get((0, signature, 2))
+                .unwrap()
+                .is_none());
+            assert!(blockstore
+                .address_signatures_cf
+                .get((0, address, 2, signature))
+                .unwrap()
+                .is_some());
+
+            blockstore.purge_transaction_metadata(TransactionMetadataCleanupSlots::new(5));
+            assert!(!blockstore.is_root(2));
+            assert!(address_signatures(&blockstore).is_empty());
+            assert!(blockstore.is_root(6));
+            assert!(blockstore.meta(6).unwrap().is_some());
+            test_all_empty_or_min(&blockstore, 6);
+        }
+        Blockstore::destroy(&blockstore_path).expect("Expected successful database destruction");
+    }
+
+    #[test]
+    fn test_purge_account_history() {
+        let blockstore_path = get_tmp_ledger_path!();
+        {
+            let blockstore = Blockstore::open(&blockstore_path).unwrap();
+            let pubkey = Pubkey::new(&[1; 32]);
+            let other_pubkey = Pubkey::new(&[2; 32]);
+            let write = |pubkey| (pubkey, Account::default(), Hash::default());
+            blockstore
+                .write_account_history(2, &[write(pubkey)])
+                .unwrap();
+            blockstore
+                .write_account_history(4, &[write(pubkey), write(other_pubkey)])
+                .unwrap();
+            blockstore.write_account_history(5, &[]).unwrap();
+            blockstore
+                .write_account_history(6, &[write(pubkey)])
+                .unwrap();
+            let history_slots = |pubkey| {
+                blockstore
+                    .get_account_history(&pubkey)
+                    .unwrap()
+                    .into_iter()
+                    .map(|(slot, _)| slot)
+                    .collect::<Vec<_>>()
+            };
+            assert_eq!(
+                blockstore.get_account_history_slot_range().unwrap(),
+                Some((2, 6))
+            );
+
+            blockstore.purge_account_history(4);
+            assert_eq!(history_slots(pubkey), vec![6]);
+            assert!(history_slots(other_pubkey).is_empty());
+            assert_eq!(
+                blockstore.get_account_history_slot_range().unwrap(),
+                Some((5, 6))
+            );
+
+            // Purging slots purges their account history too
+            blockstore.purge_slots(5, 10, PurgeType::Exact);
+            assert!(history_slots(pubkey).is_empty());
+            assert_eq!(blockstore.get_account_history_slot_range().unwrap(), None);
+        }
+        Blockstore::destroy(&blockstore_path).expect("Expected successful database destruction");
+    }
+
     #[test]
     #[allow(clippy::cognitive_complexity)]
     fn test_purge_transaction_status() {
@@ -5,7 +5,7 @@ use log::*;
 use prost::Message;
 pub use rocksdb::Direction as IteratorDirection;
 use rocksdb::{
-    self, ColumnFamily, ColumnFamilyDescriptor, DBIterator, DBRawIterator, DBRecoveryMode,
+    self, ColumnFamilyDescriptor, DBCompressionType, DBRecoveryMode,
     IteratorMode as RocksIteratorMode, Options, WriteBatch as RWriteBatch, DB,
 };
 use serde::de::DeserializeOwned;
@@ -18,7 +18,13 @@ use solana_sdk::{
 };
 use solana_storage_proto::convert::generated;
 use solana_transaction_status::TransactionStatusMeta;
-use std::{collections::HashMap, fs, marker::PhantomData, path::Path, sync::Arc};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    marker::PhantomData,
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock},
+};
 use thiserror::Error;
 
 const MAX_WRITE_BUFFER_SIZE: u64 = 256 * 1024 * 1024; // 256MB
@@ -56,6 +62,10 @@ const REWARDS_CF: &str = "rewards";
 const BLOCKTIME_CF: &str = "blocktime";
 /// Column family for Performance Samples
 const PERF_SAMPLES_CF: &str = "perf_samples";
+/// Column family for Account History
+const ACCOUNT_HISTORY_CF: &str = "account_history";
+/// Column family for the accounts each slot wrote to the Account History
+const ACCOUNT_HISTORY_SLOTS_CF: &str = "account_history_slots";
 
 #[derive(Error, Debug)]
 pub enum BlockstoreError {
@@ -150,6 +160,14 @@ pub mod columns {
     #[derive(Debug)]
     /// The performance samples column
     pub struct PerfSamples;
+
+    #[derive(Debug)]
+    /// The account history column
+    pub struct AccountHistory;
+
+    #[derive(Debug)]
+    /// The account history slots column
+    pub struct AccountHistorySlots;
 }
 
 pub enum AccessType {
@@ -200,8 +218,42 @@ impl Into<DBRecoveryMode> for BlockstoreRecoveryMode {
     }
 }
 
+/// Iterator over the raw keys and values of a column, in key order
+pub type BackendIterator<'a> = Box<dyn Iterator<Item = (Box<[u8]>, Box<[u8]>)> + 'a>;
+
+/// A single write of a `WriteBatch`
+pub enum WriteOp {
+    Put(&'static str, Vec<u8>, Vec<u8>),
+    Delete(&'static str, Vec<u8>),
+    /// Deletes the keys of a column in `[from, to)`
+    DeleteRange(&'static str, Vec<u8>, Vec<u8>),
+}
+
+/// The storage engine under a `Database`. Columns are addressed by name, and keys are ordered
+/// bytewise
+pub trait Backend: std::fmt::Debug + Send + Sync {
+    fn get_cf(&self, cf: &str, key: &[u8]) -> Result<Option<Vec<u8>>>;
+
+    fn put_cf(&self, cf: &str, key: &[u8], value: &[u8]) -> Result<()>;
+
+    fn iterator_cf(
+        &self,
+        cf: &str,
+        iterator_mode: IteratorMode<Vec<u8>>,
+    ) -> Result<BackendIterator<'_>>;
+
+    /// Apply all the writes of a batch atomically
+    fn write(&self, ops: Vec<WriteOp>) -> Result<()>;
+
+    fn compact_range_cf(&self, cf: &str, from: &[u8], to: &[u8]);
+
+    fn storage_size(&self) -> Result<u64>;
+
+    fn is_primary_access(&self) -> bool;
+}
+
 #[derive(Debug)]
-struct Rocks(rocksdb::DB, ActualAccessType);
+struct Rocks(rocksdb::DB, ActualAccessType, PathBuf);
 
 impl Rocks {
     fn open(
@@ -210,9 +262,9 @@ impl Rocks {
         recovery_mode: Option<BlockstoreRecoveryMode>,
     ) -> Result<Rocks> {
         use columns::{
-            AddressSignatures, Blocktime, DeadSlots, DuplicateSlots, ErasureMeta, Index, Orphans,
-            PerfSamples, Rewards, Root, ShredCode, ShredData, SlotMeta, TransactionStatus,
-            TransactionStatusIndex,
+            AccountHistory, AccountHistorySlots, AddressSignatures, Blocktime, DeadSlots,
+            DuplicateSlots, ErasureMeta, Index, Orphans, PerfSamples, Rewards, Root, ShredCode,
+            ShredData, SlotMeta, TransactionStatus, TransactionStatusIndex,
         };
 
         fs::create_dir_all(&path)?;
@@ -245,18 +297,30 @@ impl Rocks {
             ColumnFamilyDescriptor::new(ShredData::NAME, get_cf_options(&access_type));
         let shred_code_cf_descriptor =
             ColumnFamilyDescriptor::new(ShredCode::NAME, get_cf_options(&access_type));
-        let transaction_status_cf_descriptor =
-            ColumnFamilyDescriptor::new(TransactionStatus::NAME, get_cf_options(&access_type));
-        let address_signatures_cf_descriptor =
-            ColumnFamilyDescriptor::new(AddressSignatures::NAME, get_cf_options(&access_type));
+        let transaction_status_cf_descriptor = ColumnFamilyDescriptor::new(
+            TransactionStatus::NAME,
+            get_transaction_metadata_cf_options(&access_type),
+        );
+        let address_signatures_cf_descriptor = ColumnFamilyDescriptor::new(
+            AddressSignatures::NAME,
+            get_transaction_metadata_cf_options(&access_type),
+        );
         let transaction_status_index_cf_descriptor =
             ColumnFamilyDescriptor::new(TransactionStatusIndex::NAME, get_cf_options(&access_type));
-        let rewards_cf_descriptor =
-            ColumnFamilyDescriptor::new(Rewards::NAME, get_cf_options(&access_type));
+        let rewards_cf_descriptor = ColumnFamilyDescriptor::new(
+            Rewards::NAME,
+            get_transaction_metadata_cf_options(&access_type),
+        );
         let blocktime_cf_descriptor =
             ColumnFamilyDescriptor::new(Blocktime::NAME, get_cf_options(&access_type));
         let perf_samples_cf_descriptor =
             ColumnFamilyDescriptor::new(PerfSamples::NAME, get_cf_options(&access_type));
+        let account_history_cf_descriptor = ColumnFamilyDescriptor::new(
+            AccountHistory::NAME,
+            get_transaction_metadata_cf_options(&access_type),
+        );
+        let account_history_slots_cf_descriptor =
+            ColumnFamilyDescriptor::new(AccountHistorySlots::NAME, get_cf_options(&access_type));
 
         let cfs = vec![
             (SlotMeta::NAME, meta_cf_descriptor),
@@ -277,6 +341,8 @@ impl Rocks {
             (Rewards::NAME, rewards_cf_descriptor),
             (Blocktime::NAME, blocktime_cf_descriptor),
             (PerfSamples::NAME, perf_samples_cf_descriptor),
+            (AccountHistory::NAME, account_history_cf_descriptor),
+            (AccountHistorySlots::NAME, account_history_slots_cf_descriptor),
         ];
 
         // Open the database
@@ -284,12 +350,13 @@ impl Rocks {
             AccessType::PrimaryOnly | AccessType::PrimaryOnlyForMaintenance => Rocks(
                 DB::open_cf_descriptors(&db_options, path, cfs.into_iter().map(|c| c.1))?,
                 ActualAccessType::Primary,
+                path.to_path_buf(),
             ),
             AccessType::TryPrimaryThenSecondary => {
                 let names: Vec<_> = cfs.iter().map(|c| c.0).collect();
 
                 match DB::open_cf_descriptors(&db_options, path, cfs.into_iter().map(|c| c.1)) {
-                    Ok(db) => Rocks(db, ActualAccessType::Primary),
+                    Ok(db) => Rocks(db, ActualAccessType::Primary, path.to_path_buf()),
                     Err(err) => {
                         let secondary_path = path.join("solana-secondary");
 
@@ -303,6 +370,7 @@ impl Rocks {
                         Rocks(
                             DB::open_cf_as_secondary(&db_options, path, &secondary_path, names)?,
                             ActualAccessType::Secondary,
+                            path.to_path_buf(),
                         )
                     }
                 }
@@ -312,92 +380,172 @@ impl Rocks {
         Ok(db)
     }
 
-    fn columns(&self) -> Vec<&'static str> {
-        use columns::{
-            AddressSignatures, Blocktime, DeadSlots, DuplicateSlots, ErasureMeta, Index, Orphans,
-            PerfSamples, Rewards, Root, ShredCode, ShredData, SlotMeta, TransactionStatus,
-            TransactionStatusIndex,
-        };
-
-        vec![
-            ErasureMeta::NAME,
-            DeadSlots::NAME,
-            DuplicateSlots::NAME,
-            Index::NAME,
-            Orphans::NAME,
-            Root::NAME,
-            SlotMeta::NAME,
-            ShredData::NAME,
-            ShredCode::NAME,
-            TransactionStatus::NAME,
-            AddressSignatures::NAME,
-            TransactionStatusIndex::NAME,
-            Rewards::NAME,
-            Blocktime::NAME,
-            PerfSamples::NAME,
-        ]
-    }
-
     fn destroy(path: &Path) -> Result<()> {
         DB::destroy(&Options::default(), path)?;
 
         Ok(())
     }
 
-    fn cf_handle(&self, cf: &str) -> &ColumnFamily {
+    fn cf_handle(&self, cf: &str) -> &rocksdb::ColumnFamily {
         self.0
             .cf_handle(cf)
             .expect("should never get an unknown column")
     }
+}
 
-    fn get_cf(&self, cf: &ColumnFamily, key: &[u8]) -> Result<Option<Vec<u8>>> {
-        let opt = self.0.get_cf(cf, key)?.map(|db_vec| db_vec.to_vec());
+impl Backend for Rocks {
+    fn get_cf(&self, cf: &str, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        let opt = self
+            .0
+            .get_cf(self.cf_handle(cf), key)?
+            .map(|db_vec| db_vec.to_vec());
         Ok(opt)
     }
 
-    fn put_cf(&self, cf: &ColumnFamily, key: &[u8], value: &[u8]) -> Result<()> {
-        self.0.put_cf(cf, key, value)?;
+    fn put_cf(&self, cf: &str, key: &[u8], value: &[u8]) -> Result<()> {
+        self.0.put_cf(self.cf_handle(cf), key, value)?;
         Ok(())
     }
 
-    fn iterator_cf<C>(
+    fn iterator_cf(
         &self,
-        cf: &ColumnFamily,
-        iterator_mode: IteratorMode<C::Index>,
-    ) -> Result<DBIterator>
-    where
-        C: Column,
-    {
+        cf: &str,
+        iterator_mode: IteratorMode<Vec<u8>>,
+    ) -> Result<BackendIterator<'_>> {
         let start_key;
         let iterator_mode = match iterator_mode {
             IteratorMode::From(start_from, direction) => {
-                start_key = C::key(start_from);
+                start_key = start_from;
                 RocksIteratorMode::From(&start_key, direction)
             }
             IteratorMode::Start => RocksIteratorMode::Start,
             IteratorMode::End => RocksIteratorMode::End,
         };
-        let iter = self.0.iterator_cf(cf, iterator_mode);
-        Ok(iter)
+        let iter = self.0.iterator_cf(self.cf_handle(cf), iterator_mode);
+        Ok(Box::new(iter))
+    }
+
+    fn write(&self, ops: Vec<WriteOp>) -> Result<()> {
+        let mut batch = RWriteBatch::default();
+        for op in ops {
+            match op {
+                WriteOp::Put(cf, key, value) => batch.put_cf(self.cf_handle(cf), key, value),
+                WriteOp::Delete(cf, key) => batch.delete_cf(self.cf_handle(cf), key),
+                WriteOp::DeleteRange(cf, from, to) => {
+                    batch.delete_range_cf(self.cf_handle(cf), from, to)
+                }
+            }
+        }
+        self.0.write(batch)?;
+        Ok(())
     }
 
-    fn raw_iterator_cf(&self, cf: &ColumnFamily) -> Result<DBRawIterator> {
-        let raw_iter = self.0.raw_iterator_cf(cf);
+    fn compact_range_cf(&self, cf: &str, from: &[u8], to: &[u8]) {
+        self.0
+            .compact_range_cf(self.cf_handle(cf), Some(from), Some(to));
+    }
 
-        Ok(raw_iter)
+    fn storage_size(&self) -> Result<u64> {
+        Ok(fs_extra::dir::get_size(&self.2)?)
+    }
+
+    fn is_primary_access(&self) -> bool {
+        self.1 == ActualAccessType::Primary
     }
+}
 
-    fn batch(&self) -> Result<RWriteBatch> {
-        Ok(RWriteBatch::default())
+type MemoryColumn = BTreeMap<Vec<u8>, Vec<u8>>;
+
+/// Keeps every column in memory, for tests that don't need the ledger to outlive the process
+#[derive(Debug, Default)]
+struct Memory {
+    columns: RwLock<HashMap<String, MemoryColumn>>,
+}
+
+impl Backend for Memory {
+    fn get_cf(&self, cf: &str, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        let columns = self.columns.read().unwrap();
+        Ok(columns.get(cf).and_then(|column| column.get(key)).cloned())
     }
 
-    fn write(&self, batch: RWriteBatch) -> Result<()> {
-        self.0.write(batch)?;
+    fn put_cf(&self, cf: &str, key: &[u8], value: &[u8]) -> Result<()> {
+        let mut columns = self.columns.write().unwrap();
+        columns
+            .entry(cf.to_string())
+            .or_default()
+            .insert(key.to_vec(), value.to_vec());
         Ok(())
     }
 
+    // Iterates over a copy of the requested range so no lock is held by the iterator
+    fn iterator_cf(
+        &self,
+        cf: &str,
+        iterator_mode: IteratorMode<Vec<u8>>,
+    ) -> Result<BackendIterator<'_>> {
+        let columns = self.columns.read().unwrap();
+        let column = match columns.get(cf) {
+            Some(column) => column,
+            None => return Ok(Box::new(std::iter::empty())),
+        };
+        let into_boxed =
+            |(key, value): (&Vec<u8>, &Vec<u8>)| (key.clone().into(), value.clone().into());
+        let entries: Vec<_> = match iterator_mode {
+            IteratorMode::Start => column.iter().map(into_boxed).collect(),
+            IteratorMode::End => column.iter().rev().map(into_boxed).collect(),
+            IteratorMode::From(start_key, IteratorDirection::Forward) => {
+                column.range(start_key..).map(into_boxed).collect()
+            }
+            IteratorMode::From(start_key, IteratorDirection::Reverse) => {
+                column.range(..=start_key).rev().map(into_boxed).collect()
+            }
+        };
+        Ok(Box::new(entries.into_iter()))
+    }
+
+    fn write(&self, ops: Vec<WriteOp>) -> Result<()> {
+        let mut columns = self.columns.write().unwrap();
+        for op in ops {
+            match op {
+                WriteOp::Put(cf, key, value) => {
+                    columns
+                        .entry(cf.to_string())
+                        .or_default()
+                        .insert(key, value);
+                }
+                WriteOp::Delete(cf, key) => {
+                    if let Some(column) = columns.get_mut(cf) {
+                        column.remove(&key);
+                    }
+                }
+                WriteOp::DeleteRange(cf, from, to) => match columns.get_mut(cf) {
+                    Some(column) if from < to => {
+                        let keys: Vec<_> =
+                            column.range(from..to).map(|(key, _)| key.clone()).collect();
+                        for key in keys {
+                            column.remove(&key);
+                        }
+                    }
+                    _ => (),
+                },
+            }
+        }
+        Ok(())
+    }
+
+    fn compact_range_cf(&self, _cf: &str, _from: &[u8], _to: &[u8]) {}
+
+    fn storage_size(&self) -> Result<u64> {
+        let columns = self.columns.read().unwrap();
+        Ok(columns
+            .values()
+            .flat_map(|column| column.iter())
+            .map(|(key, value)| (key.len() + value.len()) as u64)
+            .sum())
+    }
+
     fn is_primary_access(&self) -> bool {
-        self.1 == ActualAccessType::Primary
+        true
     }
 }
 
@@ -580,6 +728,49 @@ impl TypedColumn for columns::PerfSamples {
     type Type = blockstore_meta::PerfSample;
 }
 
+// Keys are ordered by pubkey first, so a slot range does not map to a key range and the column is
+// never purged or compacted by slot range. It is purged through the pubkeys each slot wrote, which
+// `AccountHistorySlots` records
+impl Column for columns::AccountHistory {
+    type Index = (Pubkey, Slot);
+
+    fn key((pubkey, slot): (Pubkey, Slot)) -> Vec<u8> {
+        let mut key = vec![0; 32 + 8]; // size_of Pubkey + size_of Slot
+        key[0..32].clone_from_slice(&pubkey.as_ref()[0..32]);
+        BigEndian::write_u64(&mut key[32..40], slot);
+        key
+    }
+
+    fn index(key: &[u8]) -> (Pubkey, Slot) {
+        let pubkey = Pubkey::new(&key[0..32]);
+        let slot = BigEndian::read_u64(&key[32..40]);
+        (pubkey, slot)
+    }
+
+    fn primary_index(index: Self::Index) -> Slot {
+        index.1
+    }
+
+    fn as_index(slot: Slot) -> Self::Index {
+        (Pubkey::default(), slot)
+    }
+}
+
+impl ColumnName for columns::AccountHistory {
+    const NAME: &'static str = ACCOUNT_HISTORY_CF;
+}
+impl TypedColumn for columns::AccountHistory {
+    type Type = blockstore_meta::AccountHistoryEntry;
+}
+
+impl SlotColumn for columns::AccountHistorySlots {}
+impl ColumnName for columns::AccountHistorySlots {
+    const NAME: &'static str = ACCOUNT_HISTORY_SLOTS_CF;
+}
+impl TypedColumn for columns::AccountHistorySlots {
+    type Type = Vec<Pubkey>;
+}
+
 impl Column for columns::ShredCode {
     type Index = (u64, u64);
 
@@ -715,8 +906,7 @@ impl TypedColumn for columns::ErasureMeta {
 
 #[derive(Debug, Clone)]
 pub struct Database {
-    backend: Arc<Rocks>,
-    path: Arc<Path>,
+    backend: Arc<dyn Backend>,
 }
 
 #[derive(Debug, Clone)]
@@ -724,13 +914,13 @@ pub struct LedgerColumn<C>
 where
     C: Column,
 {
-    backend: Arc<Rocks>,
+    backend: Arc<dyn Backend>,
     column: PhantomData<C>,
 }
 
-pub struct WriteBatch<'a> {
-    write_batch: RWriteBatch,
-    map: HashMap<&'static str, &'a ColumnFamily>,
+#[derive(Default)]
+pub struct WriteBatch {
+    ops: Vec<WriteOp>,
 }
 
 impl Database {
@@ -741,10 +931,16 @@ impl Database {
     ) -> Result<Self> {
         let backend = Arc::new(Rocks::open(path, access_type, recovery_mode)?);
 
-        Ok(Database {
-            backend,
-            path: Arc::from(path),
-        })
+        Ok(Database { backend })
+    }
+
+    /// Open an empty database that is only kept in memory
+    pub fn open_in_memory() -> Self {
+        Self::open_with_backend(Arc::new(Memory::default()))
+    }
+
+    pub fn open_with_backend(backend: Arc<dyn Backend>) -> Self {
+        Database { backend }
     }
 
     pub fn destroy(path: &Path) -> Result<()> {
@@ -757,7 +953,7 @@ impl Database {
     where
         C: TypedColumn + ColumnName,
     {
-        if let Some(serialized_value) = self.backend.get_cf(self.cf_handle::<C>(), &C::key(key))? {
+        if let Some(serialized_value) = self.backend.get_cf(C::NAME, &C::key(key))? {
             let value = deserialize(&serialized_value)?;
 
             Ok(Some(value))
@@ -773,19 +969,12 @@ impl Database {
     where
         C: Column + ColumnName,
     {
-        let cf = self.cf_handle::<C>();
-        let iter = self.backend.iterator_cf::<C>(cf, iterator_mode)?;
+        let iter = self
+            .backend
+            .iterator_cf(C::NAME, key_iterator_mode::<C>(iterator_mode))?;
         Ok(iter.map(|(key, value)| (C::index(&key), value)))
     }
 
-    #[inline]
-    pub fn cf_handle<C: ColumnName>(&self) -> &ColumnFamily
-    where
-        C: Column + ColumnName,
-    {
-        self.backend.cf_handle(C::NAME)
-    }
-
     pub fn column<C>(&self) -> LedgerColumn<C>
     where
         C: Column + ColumnName,
@@ -796,29 +985,16 @@ impl Database {
         }
     }
 
-    #[inline]
-    pub fn raw_iterator_cf(&self, cf: &ColumnFamily) -> Result<DBRawIterator> {
-        self.backend.raw_iterator_cf(cf)
-    }
-
     pub fn batch(&self) -> Result<WriteBatch> {
-        let write_batch = self.backend.batch()?;
-        let map = self
-            .backend
-            .columns()
-            .into_iter()
-            .map(|desc| (desc, self.backend.cf_handle(desc)))
-            .collect();
-
-        Ok(WriteBatch { write_batch, map })
+        Ok(WriteBatch::default())
     }
 
     pub fn write(&self, batch: WriteBatch) -> Result<()> {
-        self.backend.write(batch.write_batch)
+        self.backend.write(batch.ops)
     }
 
     pub fn storage_size(&self) -> Result<u64> {
-        Ok(fs_extra::dir::get_size(&self.path)?)
+        self.backend.storage_size()
     }
 
     // Adds a range to delete to the given write batch
@@ -826,10 +1002,9 @@ impl Database {
     where
         C: Column + ColumnName,
     {
-        let cf = self.cf_handle::<C>();
         let from_index = C::as_index(from);
         let to_index = C::as_index(to);
-        batch.delete_range_cf::<C>(cf, from_index, to_index)
+        batch.delete_range_cf::<C>(from_index, to_index)
     }
 
     pub fn is_primary_access(&self) -> bool {
@@ -837,20 +1012,31 @@ impl Database {
     }
 }
 
+fn key_iterator_mode<C: Column>(iterator_mode: IteratorMode<C::Index>) -> IteratorMode<Vec<u8>> {
+    match iterator_mode {
+        IteratorMode::Start => IteratorMode::Start,
+        IteratorMode::End => IteratorMode::End,
+        IteratorMode::From(start_from, direction) => {
+            IteratorMode::From(C::key(start_from), direction)
+        }
+    }
+}
+
 impl<C> LedgerColumn<C>
 where
     C: Column + ColumnName,
 {
     pub fn get_bytes(&self, key: C::Index) -> Result<Option<Vec<u8>>> {
-        self.backend.get_cf(self.handle(), &C::key(key))
+        self.backend.get_cf(C::NAME, &C::key(key))
     }
 
     pub fn iter<'a>(
         &'a self,
         iterator_mode: IteratorMode<C::Index>,
     ) -> Result<impl Iterator<Item = (C::Index, Box<[u8]>)> + 'a> {
-        let cf = self.handle();
-        let iter = self.backend.iterator_cf::<C>(cf, iterator_mode)?;
+        let iter = self
+            .backend
+            .iterator_cf(C::NAME, key_iterator_mode::<C>(iterator_mode))?;
         Ok(iter.map(|(key, value)| (C::index(&key), value)))
     }
 
@@ -892,27 +1078,19 @@ where
     where
         C::Index: PartialOrd + Copy,
     {
-        let cf = self.handle();
-        let from = Some(C::key(C::as_index(from)));
-        let to = Some(C::key(C::as_index(to)));
-        self.backend.0.compact_range_cf(cf, from, to);
+        let from = C::key(C::as_index(from));
+        let to = C::key(C::as_index(to));
+        self.backend.compact_range_cf(C::NAME, &from, &to);
         Ok(true)
     }
 
-    #[inline]
-    pub fn handle(&self) -> &ColumnFamily {
-        self.backend.cf_handle(C::NAME)
-    }
-
     #[cfg(test)]
     pub fn is_empty(&self) -> Result<bool> {
-        let mut iter = self.backend.raw_iterator_cf(self.handle())?;
-        iter.seek_to_first();
-        Ok(!iter.valid())
+        Ok(self.iter(IteratorMode::Start)?.next().is_none())
     }
 
     pub fn put_bytes(&self, key: C::Index, value: &[u8]) -> Result<()> {
-        self.backend.put_cf(self.handle(), &C::key(key), value)
+        self.backend.put_cf(C::NAME, &C::key(key), value)
     }
 }
 
@@ -921,7 +1099,7 @@ where
     C: TypedColumn + ColumnName,
 {
     pub fn get(&self, key: C::Index) -> Result<Option<C::Type>> {
-        if let Some(serialized_value) = self.backend.get_cf(self.handle(), &C::key(key))? {
+        if let Some(serialized_value) = self.backend.get_cf(C::NAME, &C::key(key))? {
             let value = deserialize(&serialized_value)?;
 
             Ok(Some(value))
@@ -934,7 +1112,7 @@ where
         let serialized_value = serialize(value)?;
 
         self.backend
-            .put_cf(self.handle(), &C::key(key), &serialized_value)
+            .put_cf(C::NAME, &C::key(key), &serialized_value)
     }
 }
 
@@ -946,7 +1124,7 @@ where
         &self,
         key: C::Index,
     ) -> Result<Option<C::Type>> {
-        if let Some(serialized_value) = self.backend.get_cf(self.handle(), &C::key(key))? {
+        if let Some(serialized_value) = self.backend.get_cf(C::NAME, &C::key(key))? {
             let value = match C::Type::decode(&serialized_value[..]) {
                 Ok(value) => value,
                 Err(_) => deserialize::<T>(&serialized_value)?.into(),
@@ -958,7 +1136,7 @@ where
     }
 
     pub fn get_protobuf(&self, key: C::Index) -> Result<Option<C::Type>> {
-        if let Some(serialized_value) = self.backend.get_cf(self.handle(), &C::key(key))? {
+        if let Some(serialized_value) = self.backend.get_cf(C::NAME, &C::key(key))? {
             Ok(Some(C::Type::decode(&serialized_value[..])?))
         } else {
             Ok(None)
@@ -968,19 +1146,19 @@ where
     pub fn put_protobuf(&self, key: C::Index, value: &C::Type) -> Result<()> {
         let mut buf = Vec::with_capacity(value.encoded_len());
         value.encode(&mut buf)?;
-        self.backend.put_cf(self.handle(), &C::key(key), &buf)
+        self.backend.put_cf(C::NAME, &C::key(key), &buf)
     }
 }
 
-impl<'a> WriteBatch<'a> {
+impl WriteBatch {
     pub fn put_bytes<C: Column + ColumnName>(&mut self, key: C::Index, bytes: &[u8]) -> Result<()> {
-        self.write_batch
-            .put_cf(self.get_cf::<C>(), &C::key(key), bytes);
+        self.ops
+            .push(WriteOp::Put(C::NAME, C::key(key), bytes.to_vec()));
         Ok(())
     }
 
     pub fn delete<C: Column + ColumnName>(&mut self, key: C::Index) -> Result<()> {
-        self.write_batch.delete_cf(self.get_cf::<C>(), &C::key(key));
+        self.ops.push(WriteOp::Delete(C::NAME, C::key(key)));
         Ok(())
     }
 
@@ -990,24 +1168,18 @@ impl<'a> WriteBatch<'a> {
         value: &C::Type,
     ) -> Result<()> {
         let serialized_value = serialize(&value)?;
-        self.write_batch
-            .put_cf(self.get_cf::<C>(), &C::key(key), &serialized_value);
+        self.ops
+            .push(WriteOp::Put(C::NAME, C::key(key), serialized_value));
         Ok(())
     }
 
-    #[inline]
-    fn get_cf<C: Column + ColumnName>(&self) -> &'a ColumnFamily {
-        self.map[C::NAME]
-    }
-
-    pub fn delete_range_cf<C: Column>(
+    pub fn delete_range_cf<C: Column + ColumnName>(
         &mut self,
-        cf: &ColumnFamily,
         from: C::Index,
         to: C::Index,
     ) -> Result<()> {
-        self.write_batch
-            .delete_range_cf(cf, C::key(from), C::key(to));
+        self.ops
+            .push(WriteOp::DeleteRange(C::NAME, C::key(from), C::key(to)));
         Ok(())
     }
 }
@@ -1033,6 +1205,13 @@ fn get_cf_options(access_type: &AccessType) -> Options {
     options
 }
 
+// Transaction metadata may be retained for much longer than shreds, so trade some CPU for disk
+fn get_transaction_metadata_cf_options(access_type: &AccessType) -> Options {
+    let mut options = get_cf_options(access_type);
+    options.set_compression_type(DBCompressionType::Lz4);
+    options
+}
+
 fn get_db_options(access_type: &AccessType) -> Options {
     let mut options = Options::default();
     options.create_if_missing(true);
@@ -15,6 +15,8 @@ use solana_measure::{measure::Measure, thread_mem_usage};
 use solana_metrics::{datapoint_error, inc_new_counter_debug};
 use solana_rayon_threadlimit::get_thread_count;
 use solana_runtime::{
+    accounts_index::AccountSecondaryIndexes,
+    accounts_update_notifier_interface::AccountsUpdateNotifier,
     bank::{
         Bank, InnerInstructionsList, TransactionBalancesSet, TransactionLogMessages,
         TransactionProcessResult, TransactionResults,
@@ -22,6 +24,7 @@ use solana_runtime::{
     bank_forks::BankForks,
     bank_utils,
     commitment::VOTE_THRESHOLD_SIZE,
+    replay_tracer::ReplayTracer,
     transaction_batch::TransactionBatch,
     transaction_utils::OrderedIterator,
     vote_account::ArcVoteAccount,
@@ -36,6 +39,9 @@ use solana_sdk::{
     timing::duration_as_ms,
     transaction::{Result, Transaction, TransactionError},
 };
+use solana_transaction_status::token_balances::{
+    collect_token_balances, TransactionTokenBalancesSet,
+};
 use std::{
     cell::RefCell,
     collections::{HashMap, HashSet},
@@ -102,6 +108,16 @@ fn execute_batch(
     transaction_status_sender: Option<TransactionStatusSender>,
     replay_vote_sender: Option<&ReplayVoteSender>,
 ) -> Result<()> {
+    let record_token_balances = transaction_status_sender.is_some();
+
+    let mut mint_decimals: HashMap<Pubkey, u8> = HashMap::new();
+
+    let pre_token_balances = if record_token_balances {
+        collect_token_balances(bank, batch, &mut mint_decimals)
+    } else {
+        vec![]
+    };
+
     let (tx_results, balances, inner_instructions, transaction_logs) =
         batch.bank().load_execute_and_commit_transactions(
             batch,
@@ -120,12 +136,17 @@ fn execute_batch(
     } = tx_results;
 
     if let Some(sender) = transaction_status_sender {
+        let post_token_balances = collect_token_balances(bank, batch, &mut mint_decimals);
+        let token_balances =
+            TransactionTokenBalancesSet::new(pre_token_balances, post_token_balances);
+
         send_transaction_status_batch(
             bank.clone(),
             batch.transactions(),
             batch.iteration_order_vec(),
             processing_results,
             balances,
+            token_balances,
             inner_instructions,
             transaction_logs,
             sender,
@@ -319,6 +340,10 @@ pub struct ProcessOptions {
     pub new_hard_forks: Option<Vec<Slot>>,
     pub frozen_accounts: Vec<Pubkey>,
     pub debug_keys: Option<Arc<HashSet<Pubkey>>>,
+    pub account_indexes: AccountSecondaryIndexes,
+    pub accounts_update_notifier: Option<AccountsUpdateNotifier>,
+    pub replay_tracer: Option<ReplayTracer>,
+    pub account_history_sender: Option<AccountHistorySender>,
 }
 
 pub fn process_blockstore(
@@ -343,8 +368,11 @@ pub fn process_blockstore(
         &opts.frozen_accounts,
         opts.debug_keys.clone(),
         Some(&crate::builtins::get(genesis_config.cluster_type)),
+        opts.account_indexes.clone(),
     );
     let bank0 = Arc::new(bank0);
+    set_accounts_update_notifier(&bank0, &opts);
+    set_replay_tracer(&bank0, &opts);
     info!("processing ledger for slot 0...");
     let recyclers = VerifyRecyclers::default();
     process_bank_0(&bank0, blockstore, &opts, &recyclers)?;
@@ -359,13 +387,26 @@ pub(crate) fn process_blockstore_from_root(
     recyclers: &VerifyRecyclers,
     transaction_status_sender: Option<TransactionStatusSender>,
 ) -> BlockstoreProcessorResult {
-    do_process_blockstore_from_root(
-        blockstore,
-        Arc::new(bank),
-        opts,
-        recyclers,
-        transaction_status_sender,
-    )
+    let bank = Arc::new(bank);
+    set_accounts_update_notifier(&bank, opts);
+    set_replay_tracer(&bank, opts);
+    do_process_blockstore_from_root(blockstore, bank, opts, recyclers, transaction_status_sender)
+}
+
+// Register the accounts update notifier before replay so that every account written while
+// catching up with the ledger is reported
+fn set_accounts_update_notifier(bank: &Bank, opts: &ProcessOptions) {
+    if let Some(accounts_update_notifier) = &opts.accounts_update_notifier {
+        bank.accounts()
+            .set_accounts_update_notifier(accounts_update_notifier.clone());
+    }
+}
+
+// Register the replay tracer on the root bank, every bank replayed from it inherits the tracer
+fn set_replay_tracer(bank: &Bank, opts: &ProcessOptions) {
+    if let Some(replay_tracer) = &opts.replay_tracer {
+        bank.set_replay_tracer(replay_tracer.clone());
+    }
 }
 
 fn do_process_blockstore_from_root(
@@ -898,6 +939,9 @@ fn load_frozen_forks(
             *root = new_root_bank.slot();
             last_root_slot = new_root_bank.slot();
             leader_schedule_cache.set_root(&new_root_bank);
+            if let Some(account_history_sender) = &opts.account_history_sender {
+                send_account_history_batch(new_root_bank, account_history_sender);
+            }
             new_root_bank.squash();
 
             if last_free.elapsed() > Duration::from_secs(30) {
@@ -1031,6 +1075,7 @@ pub struct TransactionStatusBatch {
     pub iteration_order: Option<Vec<usize>>,
     pub statuses: Vec<TransactionProcessResult>,
     pub balances: TransactionBalancesSet,
+    pub token_balances: TransactionTokenBalancesSet,
     pub inner_instructions: Vec<Option<InnerInstructionsList>>,
     pub transaction_logs: Vec<TransactionLogMessages>,
 }
@@ -1043,6 +1088,7 @@ pub fn send_transaction_status_batch(
     iteration_order: Option<Vec<usize>>,
     statuses: Vec<TransactionProcessResult>,
     balances: TransactionBalancesSet,
+    token_balances: TransactionTokenBalancesSet,
     inner_instructions: Vec<Option<InnerInstructionsList>>,
     transaction_logs: Vec<TransactionLogMessages>,
     transaction_status_sender: TransactionStatusSender,
@@ -1054,6 +1100,7 @@ pub fn send_transaction_status_batch(
         iteration_order,
         statuses,
         balances,
+        token_balances,
         inner_instructions,
         transaction_logs,
     }) {
@@ -1065,6 +1112,43 @@ pub fn send_transaction_status_batch(
     }
 }
 
+/// Banks rooted together, whose writes are recorded in the account history. Cleaning does not
+/// reclaim the writes of the slots rooted after `held_root` until the batch is recorded and
+/// `held_root` is released
+pub struct AccountHistoryBatch {
+    pub rooted_banks: Vec<Arc<Bank>>,
+    pub held_root: Slot,
+}
+
+pub type AccountHistorySender = Sender<AccountHistoryBatch>;
+
+/// Sends the banks that `new_root_bank` roots to the account history. Must be called before
+/// `new_root_bank` is squashed
+pub fn send_account_history_batch(
+    new_root_bank: &Arc<Bank>,
+    account_history_sender: &AccountHistorySender,
+) {
+    let held_root = new_root_bank.hold_max_root();
+    let mut rooted_banks: Vec<_> = new_root_bank
+        .parents()
+        .into_iter()
+        .filter(|bank| bank.slot() > held_root)
+        .collect();
+    rooted_banks.reverse();
+    rooted_banks.push(new_root_bank.clone());
+    if let Err(e) = account_history_sender.send(AccountHistoryBatch {
+        rooted_banks,
+        held_root,
+    }) {
+        trace!(
+            "Slot {} account_history send batch failed: {:?}",
+            new_root_bank.slot(),
+            e
+        );
+        new_root_bank.release_max_root(held_root);
+    }
+}
+
 // used for tests only
 pub fn fill_blockstore_slot_with_ticks(
     blockstore: &Blockstore,
@@ -1108,8 +1192,9 @@ pub mod tests {
     use crossbeam_channel::unbounded;
     use matches::assert_matches;
     use rand::{thread_rng, Rng};
-    use solana_runtime::genesis_utils::{
-        self, create_genesis_config_with_vote_accounts, ValidatorVoteKeypairs,
+    use solana_runtime::{
+        accounts_update_notifier_interface::AccountsUpdateNotifierInterface,
+        genesis_utils::{self, create_genesis_config_with_vote_accounts, ValidatorVoteKeypairs},
     };
     use solana_sdk::{
         account::Account,
@@ -1375,6 +1460,44 @@ pub mod tests {
         assert_eq!(frozen_bank_slots(&bank_forks), vec![0, 3]);
     }
 
+    #[derive(Debug, Default)]
+    struct TestAccountsUpdateNotifier {
+        updated_slots: RwLock<BTreeSet<Slot>>,
+    }
+
+    impl AccountsUpdateNotifierInterface for TestAccountsUpdateNotifier {
+        fn notify_account_update(&self, slot: Slot, _pubkey: &Pubkey, _account: &Account) {
+            self.updated_slots.write().unwrap().insert(slot);
+        }
+    }
+
+    #[test]
+    fn test_process_blockstore_notifies_account_updates() {
+        let GenesisConfigInfo { genesis_config, .. } = create_genesis_config(10_000);
+        let ticks_per_slot = genesis_config.ticks_per_slot;
+        let (ledger_path, blockhash) = create_new_tmp_ledger!(&genesis_config);
+        let blockstore = Blockstore::open(&ledger_path).unwrap();
+        fill_blockstore_slot_with_ticks(&blockstore, ticks_per_slot, 1, 0, blockhash);
+
+        let notifier = Arc::new(RwLock::new(TestAccountsUpdateNotifier::default()));
+        let opts = ProcessOptions {
+            accounts_update_notifier: Some(notifier.clone()),
+            ..ProcessOptions::default()
+        };
+        let (bank_forks, _leader_schedule) =
+            process_blockstore(&genesis_config, &blockstore, Vec::new(), opts).unwrap();
+        assert_eq!(frozen_bank_slots(&bank_forks), vec![0, 1]);
+
+        // Sysvar updates made while replaying slot 1 are reported
+        assert!(notifier
+            .read()
+            .unwrap()
+            .updated_slots
+            .read()
+            .unwrap()
+            .contains(&1));
+    }
+
     #[test]
     fn test_process_blockstore_with_two_forks_and_squash() {
         solana_logger::setup();
@@ -2830,7 +2953,14 @@ pub mod tests {
         genesis_config: &GenesisConfig,
         account_paths: Vec<PathBuf>,
     ) -> EpochSchedule {
-        let bank = Bank::new_with_paths(&genesis_config, account_paths, &[], None, None);
+        let bank = Bank::new_with_paths(
+            &genesis_config,
+            account_paths,
+            &[],
+            None,
+            None,
+            AccountSecondaryIndexes::default(),
+        );
         *bank.epoch_schedule()
     }
 
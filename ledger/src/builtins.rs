@@ -25,11 +25,18 @@ fn genesis_builtins(cluster_type: ClusterType) -> Vec<Builtin> {
 
 /// Builtin programs activated dynamically by feature
 fn feature_builtins() -> Vec<(Builtin, Pubkey, ActivationType)> {
-    let builtins = vec![(
-        solana_bpf_loader_program!(),
-        feature_set::bpf_loader2_program::id(),
-        ActivationType::NewProgram,
-    )];
+    let builtins = vec![
+        (
+            solana_bpf_loader_program!(),
+            feature_set::bpf_loader2_program::id(),
+            ActivationType::NewProgram,
+        ),
+        (
+            solana_bpf_loader_upgradeable_program!(),
+            feature_set::bpf_loader_upgradeable_program::id(),
+            ActivationType::NewProgram,
+        ),
+    ];
 
     builtins
         .into_iter()
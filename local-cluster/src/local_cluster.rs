@@ -101,16 +101,22 @@ pub struct LocalCluster {
 }
 
 impl LocalCluster {
+    /// Start a cluster whose validators keep their blockstores in memory, so their ledgers can't
+    /// be reopened after they exit
     pub fn new_with_equal_stakes(
         num_nodes: usize,
         cluster_lamports: u64,
         lamports_per_node: u64,
     ) -> Self {
         let stakes: Vec<_> = (0..num_nodes).map(|_| lamports_per_node).collect();
+        let validator_config = ValidatorConfig {
+            blockstore_in_memory: true,
+            ..ValidatorConfig::default()
+        };
         let mut config = ClusterConfig {
             node_stakes: stakes,
             cluster_lamports,
-            validator_configs: vec![ValidatorConfig::default(); num_nodes],
+            validator_configs: vec![validator_config; num_nodes],
             ..ClusterConfig::default()
         };
         Self::new(&mut config)
@@ -256,6 +256,7 @@ fn test_validator_exit_2() {
     let num_nodes = 2;
     let mut validator_config = ValidatorConfig::default();
     validator_config.rpc_config.enable_validator_exit = true;
+    validator_config.blockstore_in_memory = true;
     validator_config.wait_for_supermajority = Some(0);
 
     let mut config = ClusterConfig {
@@ -277,6 +278,7 @@ fn test_leader_failure_4() {
     let num_nodes = 4;
     let mut validator_config = ValidatorConfig::default();
     validator_config.rpc_config.enable_validator_exit = true;
+    validator_config.blockstore_in_memory = true;
     let mut config = ClusterConfig {
         cluster_lamports: 10_000,
         node_stakes: vec![100; 4],
@@ -324,6 +326,7 @@ fn run_cluster_partition<E, F>(
     let enable_partition = Arc::new(AtomicBool::new(true));
     let mut validator_config = ValidatorConfig::default();
     validator_config.enable_partition = Some(enable_partition.clone());
+    validator_config.blockstore_in_memory = true;
 
     // Returns:
     // 1) The keys for the validators
@@ -678,7 +681,13 @@ fn test_forwarding() {
     let mut config = ClusterConfig {
         node_stakes: vec![999_990, 3],
         cluster_lamports: 2_000_000,
-        validator_configs: vec![ValidatorConfig::default(); 2],
+        validator_configs: vec![
+            ValidatorConfig {
+                blockstore_in_memory: true,
+                ..ValidatorConfig::default()
+            };
+            2
+        ],
         ..ClusterConfig::default()
     };
     let cluster = LocalCluster::new(&mut config);
@@ -743,7 +752,13 @@ fn test_listener_startup() {
         node_stakes: vec![100; 1],
         cluster_lamports: 1_000,
         num_listeners: 3,
-        validator_configs: vec![ValidatorConfig::default(); 1],
+        validator_configs: vec![
+            ValidatorConfig {
+                blockstore_in_memory: true,
+                ..ValidatorConfig::default()
+            };
+            1
+        ],
         ..ClusterConfig::default()
     };
     let cluster = LocalCluster::new(&mut config);
@@ -2432,6 +2447,7 @@ fn setup_snapshot_validator_config(
     let snapshot_output_path = tempfile::tempdir_in(farf_dir()).unwrap();
     let snapshot_config = SnapshotConfig {
         snapshot_interval_slots,
+        incremental_snapshot_interval_slots: std::u64::MAX,
         snapshot_package_output_path: PathBuf::from(snapshot_output_path.path()),
         snapshot_path: PathBuf::from(snapshot_dir.path()),
         compression: CompressionType::Bzip2,
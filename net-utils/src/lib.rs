@@ -452,6 +452,42 @@ pub fn multi_bind_in_range(
     Ok((port, sockets))
 }
 
+// binds many sockets to the same port in a range, and one more socket to the port `offset`
+// above it, which is also in the range. Ports whose pair isn't free are skipped.
+pub fn multi_bind_in_range_with_offset(
+    ip_addr: IpAddr,
+    range: PortRange,
+    mut num: usize,
+    offset: u16,
+) -> io::Result<(u16, Vec<UdpSocket>, UdpSocket)> {
+    if cfg!(windows) && num != 1 {
+        // See https://github.com/solana-labs/solana/issues/4607
+        warn!(
+            "multi_bind_in_range_with_offset() only supports 1 socket in windows ({} requested)",
+            num
+        );
+        num = 1;
+    }
+    for port in range.0..range.1.saturating_sub(offset) {
+        // probe without reuse, so a port already shared by other sockets isn't picked
+        if bind_to(ip_addr, port, false).is_err() {
+            continue;
+        }
+        let offset_socket = match bind_to(ip_addr, port + offset, false) {
+            Ok(socket) => socket,
+            Err(_) => continue,
+        };
+        let sockets: io::Result<Vec<_>> = (0..num).map(|_| bind_to(ip_addr, port, true)).collect();
+        if let Ok(sockets) = sockets {
+            return Ok((port, sockets, offset_socket));
+        }
+    }
+    Err(io::Error::new(
+        io::ErrorKind::Other,
+        format!("No available UDP ports {} apart in {:?}", offset, range),
+    ))
+}
+
 pub fn bind_to(ip_addr: IpAddr, port: u16, reuseaddr: bool) -> io::Result<UdpSocket> {
     let sock = udp_socket(reuseaddr)?;
 
@@ -566,6 +602,28 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_multi_bind_in_range_with_offset() {
+        let ip_addr = IpAddr::V4(Ipv4Addr::new(0, 0, 0, 0));
+        let (port, sockets, offset_socket) =
+            multi_bind_in_range_with_offset(ip_addr, (2200, 2300), 4, 6).unwrap();
+        assert!(2200 <= port && port + 6 < 2300);
+        for socket in &sockets {
+            assert_eq!(socket.local_addr().unwrap().port(), port);
+        }
+        assert_eq!(offset_socket.local_addr().unwrap().port(), port + 6);
+
+        // a port whose offset port is taken is skipped
+        let _taken = bind_to(ip_addr, 2406, false).unwrap();
+        let (port, _sockets, _offset_socket) =
+            multi_bind_in_range_with_offset(ip_addr, (2400, 2500), 4, 6).unwrap();
+        assert_ne!(port, 2400);
+
+        // both ports have to be in the range
+        multi_bind_in_range_with_offset(ip_addr, (2600, 2606), 1, 6).unwrap_err();
+        multi_bind_in_range_with_offset(ip_addr, (2600, 2607), 1, 6).unwrap();
+    }
+
     #[test]
     fn test_bind_in_range_nil() {
         let ip_addr = IpAddr::V4(Ipv4Addr::new(0, 0, 0, 0));
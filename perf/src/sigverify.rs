@@ -12,7 +12,9 @@ use bincode::serialized_size;
 use rayon::ThreadPool;
 use solana_metrics::inc_new_counter_debug;
 use solana_rayon_threadlimit::get_thread_count;
-use solana_sdk::message::MessageHeader;
+use solana_sdk::compute_budget::{self, ComputeBudgetInstruction};
+use solana_sdk::hash::Hash;
+use solana_sdk::message::{MessageHeader, MESSAGE_VERSION_0, MESSAGE_VERSION_PREFIX};
 use solana_sdk::pubkey::Pubkey;
 use solana_sdk::short_vec::decode_len;
 use solana_sdk::signature::Signature;
@@ -59,6 +61,7 @@ pub enum PacketError {
     InvalidSignatureLen,
     MismatchSignatureLen,
     PayerNotWritable,
+    UnsupportedVersion,
 }
 
 impl std::convert::From<std::boxed::Box<bincode::ErrorKind>> for PacketError {
@@ -143,16 +146,32 @@ fn do_get_packet_offsets(
         return Err(PacketError::InvalidSignatureLen);
     }
 
+    // Versioned messages start with a prefix byte ahead of the MessageHeader. Legacy messages
+    // start with MessageHeader.num_required_signatures, which never has the high bit set.
+    let message_prefix = packet.data[msg_start_offset];
+    let msg_header_offset = if message_prefix & MESSAGE_VERSION_PREFIX != 0 {
+        if message_prefix & !MESSAGE_VERSION_PREFIX != MESSAGE_VERSION_0 {
+            return Err(PacketError::UnsupportedVersion);
+        }
+        // Packet should also have data for the version prefix
+        if (msg_start_offset + 1 + message_header_size + 1) > packet.meta.size {
+            return Err(PacketError::InvalidSignatureLen);
+        }
+        msg_start_offset + 1
+    } else {
+        msg_start_offset
+    };
+
     // read MessageHeader.num_required_signatures (serialized with u8)
-    let sig_len_maybe_trusted = packet.data[msg_start_offset] as usize;
+    let sig_len_maybe_trusted = packet.data[msg_header_offset] as usize;
 
-    let message_account_keys_len_offset = msg_start_offset + message_header_size;
+    let message_account_keys_len_offset = msg_header_offset + message_header_size;
 
     // This reads and compares the MessageHeader num_required_signatures and
     // num_readonly_signed_accounts bytes. If num_required_signatures is not larger than
     // num_readonly_signed_accounts, the first account is not debitable, and cannot be charged
     // required transaction fees.
-    if packet.data[msg_start_offset] <= packet.data[msg_start_offset + 1] {
+    if packet.data[msg_header_offset] <= packet.data[msg_header_offset + 1] {
         return Err(PacketError::PayerNotWritable);
     }
 
@@ -168,7 +187,7 @@ fn do_get_packet_offsets(
 
     let sig_start = current_offset as usize + sig_size;
     let msg_start = current_offset as usize + msg_start_offset;
-    let pubkey_start = msg_start + message_header_size + pubkey_len_size;
+    let pubkey_start = current_offset as usize + message_account_keys_len_offset + pubkey_len_size;
 
     if sig_len_maybe_trusted != sig_len_untrusted {
         return Err(PacketError::MismatchSignatureLen);
@@ -183,6 +202,10 @@ fn do_get_packet_offsets(
 }
 
 fn get_packet_offsets(packet: &Packet, current_offset: u32) -> PacketOffsets {
+    // Packets already discarded upstream are not worth verifying, zeros fail them the same way
+    if packet.meta.discard {
+        return PacketOffsets::new(0, 0, 0, 0);
+    }
     let unsanitized_packet_offsets = do_get_packet_offsets(packet, current_offset);
     if let Ok(offsets) = unsanitized_packet_offsets {
         offsets
@@ -192,6 +215,73 @@ fn get_packet_offsets(packet: &Packet, current_offset: u32) -> PacketOffsets {
     }
 }
 
+/// The price per compute unit the packet's transaction pays, zero if it does not request a compute
+/// budget or is malformed. Only the compute budget request is parsed, so this is cheap enough to
+/// run on every packet ahead of signature verification.
+pub fn packet_compute_price(packet: &Packet) -> u64 {
+    parse_requested_units(&packet.data[..packet.meta.size]).map_or(
+        0,
+        |(units, lamports_per_unit)| {
+            compute_budget::requested_units_price(units, lamports_per_unit)
+        },
+    )
+}
+
+// Finds the compute budget request of a serialized transaction without deserializing it, the
+// same one `compute_budget::get_requested_units()` returns for the deserialized message
+fn parse_requested_units(data: &[u8]) -> Option<(u32, u64)> {
+    let read_len = |offset: &mut usize| {
+        let (len, size) = decode_len(data.get(*offset..)?).ok()?;
+        *offset += size;
+        Some(len)
+    };
+    let skip = |offset: usize, len: usize| offset.checked_add(len);
+
+    let mut offset = 0;
+    let num_signatures = read_len(&mut offset)?;
+    offset = skip(offset, num_signatures.checked_mul(size_of::<Signature>())?)?;
+    // Versioned messages start with a prefix byte ahead of the MessageHeader
+    let message_prefix = *data.get(offset)?;
+    if message_prefix & MESSAGE_VERSION_PREFIX != 0 {
+        if message_prefix & !MESSAGE_VERSION_PREFIX != MESSAGE_VERSION_0 {
+            return None;
+        }
+        offset += 1;
+    }
+    offset = skip(offset, size_of::<MessageHeader>())?;
+    // Programs can't be loaded from a lookup table, so the static account keys are enough
+    let num_account_keys = read_len(&mut offset)?;
+    let account_keys =
+        data.get(offset..skip(offset, num_account_keys.checked_mul(size_of::<Pubkey>())?)?)?;
+    offset = skip(offset, account_keys.len() + size_of::<Hash>())?;
+
+    let num_instructions = read_len(&mut offset)?;
+    for _ in 0..num_instructions {
+        let program_id_index = *data.get(offset)? as usize;
+        offset += 1;
+        let num_accounts = read_len(&mut offset)?;
+        offset = skip(offset, num_accounts)?;
+        let data_len = read_len(&mut offset)?;
+        let instruction_data = data.get(offset..skip(offset, data_len)?)?;
+        offset += data_len;
+
+        let program_id = account_keys
+            .chunks_exact(size_of::<Pubkey>())
+            .nth(program_id_index);
+        if program_id != Some(compute_budget::id().as_ref()) {
+            continue;
+        }
+        if let Ok(ComputeBudgetInstruction::RequestUnits {
+            units,
+            lamports_per_unit,
+        }) = bincode::deserialize(instruction_data)
+        {
+            return Some((units, lamports_per_unit));
+        }
+    }
+    None
+}
+
 pub fn generate_offsets(
     batches: &[Packets],
     recycler: &Recycler<TxOffset>,
@@ -415,10 +505,11 @@ mod tests {
     use crate::sigverify::PacketOffsets;
     use crate::test_tx::{test_multisig_tx, test_tx};
     use bincode::{deserialize, serialize};
-    use solana_sdk::hash::Hash;
-    use solana_sdk::message::{Message, MessageHeader};
-    use solana_sdk::signature::Signature;
-    use solana_sdk::transaction::Transaction;
+    use solana_sdk::instruction::Instruction;
+    use solana_sdk::message::{v0, Message, VersionedMessage};
+    use solana_sdk::signature::{Keypair, Signature, Signer};
+    use solana_sdk::system_instruction;
+    use solana_sdk::transaction::{Transaction, VersionedTransaction};
 
     const SIG_OFFSET: usize = 1;
 
@@ -511,9 +602,11 @@ mod tests {
 
         let unsanitized_packet_offsets = sigverify::do_get_packet_offsets(&packet, 0);
 
+        // A first message byte with the high bit set is read as a version prefix, so the packet
+        // is rejected before the signature counts are compared
         assert_eq!(
             unsanitized_packet_offsets,
-            Err(PacketError::MismatchSignatureLen)
+            Err(PacketError::UnsupportedVersion)
         );
     }
 
@@ -648,6 +741,119 @@ mod tests {
         );
     }
 
+    fn make_packet_from_versioned_transaction(tx: VersionedTransaction) -> Packet {
+        let tx_bytes = serialize(&tx).unwrap();
+        let mut packet = Packet::default();
+        packet.meta.size = tx_bytes.len();
+        packet.data[..packet.meta.size].copy_from_slice(&tx_bytes);
+        packet
+    }
+
+    fn new_v0_transaction(payer: &Keypair, instructions: &[Instruction]) -> VersionedTransaction {
+        let message = v0::Message::new_with_address_table_lookups(
+            instructions,
+            Some(&payer.pubkey()),
+            &[(Pubkey::new_unique(), vec![Pubkey::new_unique()])],
+        );
+        VersionedTransaction::try_new(VersionedMessage::V0(message), &[payer]).unwrap()
+    }
+
+    #[test]
+    fn test_get_versioned_packet_offsets() {
+        let keypair = Keypair::new();
+        let tx = new_v0_transaction(
+            &keypair,
+            &[system_instruction::transfer(
+                &keypair.pubkey(),
+                &Pubkey::new_unique(),
+                1,
+            )],
+        );
+
+        // The version prefix comes between the message start and the message header
+        let packet = make_packet_from_versioned_transaction(tx);
+        let packet_offsets = sigverify::get_packet_offsets(&packet, 0);
+        assert_eq!(
+            PacketOffsets::new(
+                packet_offsets.sig_len,
+                packet_offsets.sig_start,
+                packet_offsets.msg_start - packet_offsets.sig_start,
+                packet_offsets.pubkey_start - packet_offsets.msg_start,
+            ),
+            PacketOffsets::new(1, 1, 64, 5)
+        );
+        let batches = generate_packet_vec(&packet, 1, 1);
+        assert_eq!(sigverify::ed25519_verify_cpu(&batches), vec![vec![1]]);
+
+        let mut packet = packet;
+        packet.data[1 + size_of::<Signature>()] = MESSAGE_VERSION_PREFIX | 1;
+        assert_eq!(
+            sigverify::do_get_packet_offsets(&packet, 0),
+            Err(PacketError::UnsupportedVersion)
+        );
+    }
+
+    #[test]
+    fn test_packet_compute_price() {
+        let payer = Keypair::new();
+        let transfer = system_instruction::transfer(&payer.pubkey(), &Pubkey::new_unique(), 1);
+        let malformed = Instruction::new(compute_budget::id(), &0u8, vec![]);
+        let messages = vec![
+            Message::new(std::slice::from_ref(&transfer), Some(&payer.pubkey())),
+            Message::new(
+                &[transfer.clone(), compute_budget::request_units(1_000, 3)],
+                Some(&payer.pubkey()),
+            ),
+            Message::new(
+                &[
+                    malformed,
+                    compute_budget::request_units(0, u64::MAX),
+                    compute_budget::request_units(1_000, 3),
+                ],
+                Some(&payer.pubkey()),
+            ),
+            Message::new(
+                &[compute_budget::request_units(u32::MAX, 2), transfer.clone()],
+                Some(&payer.pubkey()),
+            ),
+        ];
+        let mut packets: Vec<_> = messages
+            .into_iter()
+            .map(|message| {
+                let tx = Transaction::new(&[&payer], message, Hash::default());
+                let packet = sigverify::make_packet_from_transaction(tx.clone());
+                assert_eq!(
+                    parse_requested_units(&packet.data[..packet.meta.size]),
+                    compute_budget::get_requested_units(&tx.message)
+                );
+                packet
+            })
+            .collect();
+        packets.push(make_packet_from_versioned_transaction(new_v0_transaction(
+            &payer,
+            &[transfer, compute_budget::request_units(1_000, 4)],
+        )));
+        assert_eq!(
+            packets[4].data[1 + size_of::<Signature>()],
+            MESSAGE_VERSION_PREFIX
+        );
+
+        // Zero units pay nothing, whatever their price
+        assert_eq!(
+            packets.iter().map(packet_compute_price).collect::<Vec<_>>(),
+            vec![0, 3, 0, 2, 4]
+        );
+
+        let mut truncated = packets[1].clone();
+        truncated.meta.size -= 20;
+        assert_eq!(packet_compute_price(&truncated), 0);
+
+        // Unknown message versions don't pay anything either
+        let mut unsupported = packets[4].clone();
+        unsupported.data[1 + size_of::<Signature>()] = MESSAGE_VERSION_PREFIX | 1;
+        assert_eq!(packet_compute_price(&unsupported), 0);
+    }
+
     fn generate_packet_vec(
         packet: &Packet,
         num_packets_per_batch: usize,
@@ -723,6 +929,20 @@ mod tests {
         test_verify_n(71, false);
     }
 
+    #[test]
+    fn test_verify_discarded() {
+        let tx = test_tx();
+        let packet = sigverify::make_packet_from_transaction(tx);
+        let mut batches = generate_packet_vec(&packet, 3, 1);
+        batches[0].packets[1].meta.discard = true;
+
+        let recycler = Recycler::default();
+        let recycler_out = Recycler::default();
+        let ans = sigverify::ed25519_verify(&batches, &recycler, &recycler_out);
+        assert_eq!(ans, vec![vec![1u8, 0, 1]]);
+        assert_eq!(ed25519_verify_cpu(&batches), ans);
+    }
+
     #[test]
     fn test_verify_multisig() {
         solana_logger::setup();
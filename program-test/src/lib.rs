@@ -7,14 +7,15 @@ use {
     solana_banks_client::start_client,
     solana_banks_server::banks_server::start_local_server,
     solana_program::{
-        account_info::AccountInfo, entrypoint::ProgramResult, fee_calculator::FeeCalculator,
-        hash::Hash, instruction::Instruction, instruction::InstructionError, message::Message,
-        native_token::sol_to_lamports, program_error::ProgramError, program_stubs, pubkey::Pubkey,
-        rent::Rent,
+        account_info::AccountInfo, clock::Slot, entrypoint::ProgramResult,
+        fee_calculator::FeeCalculator, hash::Hash, instruction::Instruction,
+        instruction::InstructionError, message::Message, native_token::sol_to_lamports,
+        program_error::ProgramError, program_stubs, pubkey::Pubkey, rent::Rent, sysvar::Sysvar,
     },
     solana_runtime::{
         bank::{Bank, Builtin},
         bank_forks::BankForks,
+        commitment::BlockCommitmentCache,
         genesis_utils::create_genesis_config_with_leader,
     },
     solana_sdk::{
@@ -28,6 +29,7 @@ use {
         cell::RefCell,
         collections::HashMap,
         convert::TryFrom,
+        fmt,
         fs::File,
         io::{self, Read},
         path::{Path, PathBuf},
@@ -578,6 +580,15 @@ impl ProgramTest {
     /// Returns a `BanksClient` interface into the test environment as well as a payer `Keypair`
     /// with SOL for sending transactions
     pub async fn start(self) -> (BanksClient, Keypair, Hash) {
+        let context = self.start_with_context().await;
+        (context.banks_client, context.payer, context.last_blockhash)
+    }
+
+    /// Start the test client
+    ///
+    /// Returns a `ProgramTestContext`, which additionally allows the test to move the bank
+    /// forward in time and to modify accounts and sysvars between transactions
+    pub async fn start_with_context(self) -> ProgramTestContext {
         {
             use std::sync::Once;
             static ONCE: Once = Once::new();
@@ -609,6 +620,7 @@ impl ProgramTest {
         for loader in &[
             solana_bpf_loader_deprecated_program!(),
             solana_bpf_loader_program!(),
+            solana_bpf_loader_upgradeable_program!(),
         ] {
             bank.add_builtin(&loader.0, loader.1, loader.2);
         }
@@ -677,8 +689,12 @@ impl ProgramTest {
             0
         );
 
+        let slot = bank.slot();
         let bank_forks = Arc::new(RwLock::new(BankForks::new(bank)));
-        let transport = start_local_server(&bank_forks).await;
+        let block_commitment_cache = Arc::new(RwLock::new(
+            BlockCommitmentCache::new_for_tests_with_slots(slot, slot),
+        ));
+        let transport = start_local_server(&bank_forks, &block_commitment_cache).await;
         let banks_client = start_client(transport)
             .await
             .unwrap_or_else(|err| panic!("Failed to start banks client: {}", err));
@@ -686,9 +702,10 @@ impl ProgramTest {
         // Run a simulated PohService to provide the client with new blockhashes.  New blockhashes
         // are required when sending multiple otherwise identical transactions in series from a
         // test
+        let ticker_bank_forks = bank_forks.clone();
         tokio::spawn(async move {
             loop {
-                bank_forks
+                ticker_bank_forks
                     .read()
                     .unwrap()
                     .working_bank()
@@ -697,7 +714,99 @@ impl ProgramTest {
             }
         });
 
-        (banks_client, payer, last_blockhash)
+        ProgramTestContext {
+            banks_client,
+            payer,
+            last_blockhash,
+            bank_forks,
+            block_commitment_cache,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ProgramTestError {
+    /// The chosen warp slot is not in the future
+    InvalidWarpSlot,
+}
+
+impl fmt::Display for ProgramTestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProgramTestError::InvalidWarpSlot => write!(f, "warp slot is not in the future"),
+        }
+    }
+}
+
+impl std::error::Error for ProgramTestError {}
+
+pub struct ProgramTestContext {
+    pub banks_client: BanksClient,
+    pub payer: Keypair,
+    pub last_blockhash: Hash,
+    bank_forks: Arc<RwLock<BankForks>>,
+    block_commitment_cache: Arc<RwLock<BlockCommitmentCache>>,
+}
+
+impl ProgramTestContext {
+    /// Force the working bank ahead to a new slot
+    ///
+    /// Banks between the current working slot and `warp_slot` are skipped rather than
+    /// processed. The clock and epoch of the new working bank reflect `warp_slot`, and if an
+    /// epoch boundary is crossed its stake activation and rewards are processed once
+    pub fn warp_to_slot(&mut self, warp_slot: Slot) -> Result<(), ProgramTestError> {
+        let mut bank_forks = self.bank_forks.write().unwrap();
+        let bank = bank_forks.working_bank();
+        let working_slot = bank.slot();
+        if warp_slot <= working_slot {
+            return Err(ProgramTestError::InvalidWarpSlot);
+        }
+
+        // `Bank::warp_from_parent()` returns a frozen bank, so warp to the slot before
+        // `warp_slot` and then create an unfrozen child at `warp_slot` to process transactions
+        let pre_warp_slot = warp_slot - 1;
+        let warp_bank = if pre_warp_slot == working_slot {
+            bank.freeze();
+            bank
+        } else {
+            bank_forks.insert(Bank::warp_from_parent(
+                &bank,
+                bank.collector_id(),
+                pre_warp_slot,
+            ))
+        };
+        bank_forks.set_root(pre_warp_slot, &None, Some(pre_warp_slot));
+        bank_forks.insert(Bank::new_from_parent(
+            &warp_bank,
+            warp_bank.collector_id(),
+            warp_slot,
+        ));
+
+        // Point the banks server at the new working bank
+        *self.block_commitment_cache.write().unwrap() =
+            BlockCommitmentCache::new_for_tests_with_slots(warp_slot, warp_slot);
+        Ok(())
+    }
+
+    /// Overwrite a sysvar of the working bank, such as `Clock`, `Rent` or `EpochSchedule`
+    ///
+    /// Only the sysvar account seen by programs is changed, and the next bank created by
+    /// `warp_to_slot` recomputes the clock from its own slot
+    pub fn set_sysvar<T: Sysvar>(&self, sysvar: &T) {
+        self.bank_forks
+            .read()
+            .unwrap()
+            .working_bank()
+            .set_sysvar_for_tests(sysvar);
+    }
+
+    /// Create or overwrite an account in the working bank
+    pub fn set_account(&mut self, address: &Pubkey, account: &Account) {
+        self.bank_forks
+            .read()
+            .unwrap()
+            .working_bank()
+            .store_account(address, account);
     }
 }
 
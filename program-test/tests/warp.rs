@@ -0,0 +1,94 @@
+use {
+    solana_program::{
+        clock::Clock, pubkey::Pubkey, rent::Rent, system_instruction, sysvar::Sysvar,
+    },
+    solana_program_test::{ProgramTest, ProgramTestContext, ProgramTestError},
+    solana_sdk::{
+        account::{from_account, Account},
+        signature::Signer,
+        transaction::Transaction,
+    },
+};
+
+async fn get_sysvar<T: Sysvar>(context: &mut ProgramTestContext) -> T {
+    let account = context
+        .banks_client
+        .get_account(T::id())
+        .await
+        .unwrap()
+        .expect("sysvar account");
+    from_account(&account).unwrap()
+}
+
+#[tokio::test]
+async fn warp_to_slot() {
+    let mut context = ProgramTest::default().start_with_context().await;
+
+    let clock = get_sysvar::<Clock>(&mut context).await;
+    let warp_slot = clock.slot + 1_000;
+    context.warp_to_slot(warp_slot).unwrap();
+    let clock = get_sysvar::<Clock>(&mut context).await;
+    assert_eq!(clock.slot, warp_slot);
+    assert!(clock.epoch > 0);
+
+    assert_eq!(
+        context.warp_to_slot(warp_slot),
+        Err(ProgramTestError::InvalidWarpSlot)
+    );
+    context.warp_to_slot(warp_slot + 1).unwrap();
+
+    // Transactions are processed by the new working bank
+    let recipient = Pubkey::new_unique();
+    let transaction = Transaction::new_signed_with_payer(
+        &[system_instruction::transfer(
+            &context.payer.pubkey(),
+            &recipient,
+            1_000_000,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+    assert_eq!(
+        context.banks_client.get_balance(recipient).await.unwrap(),
+        1_000_000
+    );
+}
+
+#[tokio::test]
+async fn set_sysvar_and_account() {
+    let mut context = ProgramTest::default().start_with_context().await;
+
+    let clock = Clock {
+        unix_timestamp: 1_000_000,
+        ..get_sysvar::<Clock>(&mut context).await
+    };
+    context.set_sysvar(&clock);
+    assert_eq!(get_sysvar::<Clock>(&mut context).await, clock);
+
+    let rent = Rent {
+        lamports_per_byte_year: 1,
+        ..Rent::default()
+    };
+    context.set_sysvar(&rent);
+    assert_eq!(get_sysvar::<Rent>(&mut context).await, rent);
+
+    let address = Pubkey::new_unique();
+    let account = Account {
+        lamports: 42,
+        data: vec![1, 2, 3],
+        owner: Pubkey::new_unique(),
+        executable: false,
+        rent_epoch: 0,
+    };
+    context.set_account(&address, &account);
+    assert_eq!(
+        context.banks_client.get_account(address).await.unwrap(),
+        Some(account)
+    );
+}
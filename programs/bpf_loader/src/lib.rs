@@ -4,6 +4,7 @@ pub mod bpf_verifier;
 pub mod deprecated;
 pub mod serialization;
 pub mod syscalls;
+pub mod upgradeable;
 
 use crate::{
     bpf_verifier::VerifierError,
@@ -17,17 +18,24 @@ use solana_rbpf::{
     memory_region::MemoryRegion,
     vm::{Config, EbpfVm, Executable, InstructionMeter},
 };
+use solana_runtime::message_processor::MessageProcessor;
 use solana_sdk::{
+    account_utils::State,
     bpf_loader, bpf_loader_deprecated,
+    bpf_loader_upgradeable::{self, UpgradeableLoaderState},
+    clock::Clock,
     decode_error::DecodeError,
     entrypoint::SUCCESS,
     feature_set::bpf_compute_budget_balancing,
     instruction::InstructionError,
-    keyed_account::{is_executable, next_keyed_account, KeyedAccount},
+    keyed_account::{from_keyed_account, is_executable, next_keyed_account, KeyedAccount},
     loader_instruction::LoaderInstruction,
+    loader_upgradeable_instruction::UpgradeableLoaderInstruction,
     process_instruction::{stable_log, ComputeMeter, Executor, InvokeContext},
     program_utils::limited_deserialize,
     pubkey::Pubkey,
+    rent::Rent,
+    system_instruction::{self, MAX_PERMITTED_DATA_LENGTH},
 };
 use std::{cell::RefCell, fmt::Debug, rc::Rc, sync::Arc};
 use thiserror::Error;
@@ -94,12 +102,13 @@ fn map_ebpf_error(
 const IS_JIT_ENABLED: bool = false;
 
 pub fn create_and_cache_executor(
-    program: &KeyedAccount,
+    key: &Pubkey,
+    data: &[u8],
     invoke_context: &mut dyn InvokeContext,
 ) -> Result<Arc<BPFExecutor>, InstructionError> {
     let bpf_compute_budget = invoke_context.get_bpf_compute_budget();
     let mut executable = Executable::<BPFError, ThisInstructionMeter>::from_elf(
-        &program.try_account_ref()?.data,
+        data,
         None,
         Config {
             max_call_depth: bpf_compute_budget.max_call_depth,
@@ -124,7 +133,7 @@ pub fn create_and_cache_executor(
         return Err(BPFLoaderError::JustInTimeCompilationFailed.into());
     }
     let executor = Arc::new(BPFExecutor { executable });
-    invoke_context.add_executor(program.unsigned_key(), executor.clone());
+    invoke_context.add_executor(key, executor.clone());
     Ok(executor)
 }
 
@@ -159,7 +168,11 @@ pub fn process_instruction(
     instruction_data: &[u8],
     invoke_context: &mut dyn InvokeContext,
 ) -> Result<(), InstructionError> {
-    debug_assert!(bpf_loader::check_id(program_id) || bpf_loader_deprecated::check_id(program_id));
+    debug_assert!(
+        bpf_loader::check_id(program_id)
+            || bpf_loader_deprecated::check_id(program_id)
+            || bpf_loader_upgradeable::check_id(program_id)
+    );
 
     let logger = invoke_context.get_logger();
 
@@ -170,47 +183,464 @@ pub fn process_instruction(
     let program = &keyed_accounts[0];
 
     if is_executable(keyed_accounts)? {
+        // Upgradeable programs keep their program bits in the ProgramData
+        // account that follows the Program account
+        let (program_data, program_data_offset) = if bpf_loader_upgradeable::check_id(program_id) {
+            let programdata = keyed_accounts
+                .get(1)
+                .ok_or(InstructionError::NotEnoughAccountKeys)?;
+            if let UpgradeableLoaderState::Program {
+                programdata_address,
+            } = program.state()?
+            {
+                if programdata_address != *programdata.unsigned_key() {
+                    log!(logger, "Wrong ProgramData account for this Program account");
+                    return Err(InstructionError::InvalidArgument);
+                }
+            } else {
+                log!(logger, "Invalid Program account");
+                return Err(InstructionError::InvalidAccountData);
+            }
+            (
+                programdata,
+                UpgradeableLoaderState::programdata_data_offset()?,
+            )
+        } else {
+            (program, 0)
+        };
+
         let executor = match invoke_context.get_executor(program.unsigned_key()) {
             Some(executor) => executor,
-            None => create_and_cache_executor(program, invoke_context)?,
+            None => create_and_cache_executor(
+                program.unsigned_key(),
+                program_data
+                    .try_account_ref()?
+                    .data
+                    .get(program_data_offset..)
+                    .ok_or(InstructionError::InvalidAccountData)?,
+                invoke_context,
+            )?,
         };
         executor.execute(program_id, keyed_accounts, instruction_data, invoke_context)?
-    } else if !keyed_accounts.is_empty() {
-        match limited_deserialize(instruction_data)? {
-            LoaderInstruction::Write { offset, bytes } => {
-                if program.signer_key().is_none() {
-                    log!(logger, "key[0] did not sign the transaction");
+    } else if bpf_loader_upgradeable::check_id(program_id) {
+        process_loader_upgradeable_instruction(
+            program_id,
+            keyed_accounts,
+            instruction_data,
+            invoke_context,
+        )?;
+    } else {
+        process_loader_instruction(program_id, keyed_accounts, instruction_data, invoke_context)?;
+    }
+    Ok(())
+}
+
+fn process_loader_instruction(
+    _program_id: &Pubkey,
+    keyed_accounts: &[KeyedAccount],
+    instruction_data: &[u8],
+    invoke_context: &mut dyn InvokeContext,
+) -> Result<(), InstructionError> {
+    let logger = invoke_context.get_logger();
+    let program = &keyed_accounts[0];
+
+    match limited_deserialize(instruction_data)? {
+        LoaderInstruction::Write { offset, bytes } => {
+            if program.signer_key().is_none() {
+                log!(logger, "key[0] did not sign the transaction");
+                return Err(InstructionError::MissingRequiredSignature);
+            }
+            write_program_data(program, offset as usize, &bytes, invoke_context)?;
+        }
+        LoaderInstruction::Finalize => {
+            if program.signer_key().is_none() {
+                log!(logger, "key[0] did not sign the transaction");
+                return Err(InstructionError::MissingRequiredSignature);
+            }
+
+            let _ = create_and_cache_executor(
+                program.unsigned_key(),
+                &program.try_account_ref()?.data,
+                invoke_context,
+            )?;
+            program.try_account_ref_mut()?.executable = true;
+            log!(
+                logger,
+                "Finalized account {:?}",
+                program.signer_key().unwrap()
+            );
+        }
+    }
+    Ok(())
+}
+
+fn process_loader_upgradeable_instruction(
+    program_id: &Pubkey,
+    keyed_accounts: &[KeyedAccount],
+    instruction_data: &[u8],
+    invoke_context: &mut dyn InvokeContext,
+) -> Result<(), InstructionError> {
+    let logger = invoke_context.get_logger();
+    let account_iter = &mut keyed_accounts.iter();
+
+    match limited_deserialize(instruction_data)? {
+        UpgradeableLoaderInstruction::InitializeBuffer => {
+            let buffer = next_keyed_account(account_iter)?;
+            let authority = next_keyed_account(account_iter)
+                .ok()
+                .map(|account| *account.unsigned_key());
+
+            if UpgradeableLoaderState::Uninitialized != buffer.state()? {
+                log!(logger, "Buffer account already initialized");
+                return Err(InstructionError::AccountAlreadyInitialized);
+            }
+            buffer.set_state(&UpgradeableLoaderState::Buffer {
+                authority_address: authority,
+            })?;
+        }
+        UpgradeableLoaderInstruction::Write { offset, bytes } => {
+            let buffer = next_keyed_account(account_iter)?;
+            let authority = next_keyed_account(account_iter)?;
+
+            if let UpgradeableLoaderState::Buffer { authority_address } = buffer.state()? {
+                if authority_address.is_none() {
+                    log!(logger, "Buffer is immutable");
+                    return Err(InstructionError::Immutable);
+                }
+                if authority_address != Some(*authority.unsigned_key()) {
+                    log!(logger, "Incorrect buffer authority provided");
+                    return Err(InstructionError::IncorrectAuthority);
+                }
+                if authority.signer_key().is_none() {
+                    log!(logger, "Buffer authority did not sign");
                     return Err(InstructionError::MissingRequiredSignature);
                 }
-                let offset = offset as usize;
-                let len = bytes.len();
-                if program.data_len()? < offset + len {
-                    log!(
-                        logger,
-                        "Write overflow: {} < {}",
-                        program.data_len()?,
-                        offset + len
-                    );
-                    return Err(InstructionError::AccountDataTooSmall);
+            } else {
+                log!(logger, "Invalid Buffer account");
+                return Err(InstructionError::InvalidAccountData);
+            }
+            write_program_data(
+                buffer,
+                UpgradeableLoaderState::buffer_data_offset()? + offset as usize,
+                &bytes,
+                invoke_context,
+            )?;
+        }
+        UpgradeableLoaderInstruction::DeployWithMaxDataLen { max_data_len } => {
+            let payer = next_keyed_account(account_iter)?;
+            let programdata = next_keyed_account(account_iter)?;
+            let program = next_keyed_account(account_iter)?;
+            let buffer = next_keyed_account(account_iter)?;
+            let rent = from_keyed_account::<Rent>(next_keyed_account(account_iter)?)?;
+            let clock = from_keyed_account::<Clock>(next_keyed_account(account_iter)?)?;
+            let system = next_keyed_account(account_iter)?;
+            let authority = next_keyed_account(account_iter)?;
+            let upgrade_authority_address = Some(*authority.unsigned_key());
+
+            // Verify Program account
+
+            if UpgradeableLoaderState::Uninitialized != program.state()? {
+                log!(logger, "Program account already initialized");
+                return Err(InstructionError::AccountAlreadyInitialized);
+            }
+            if program.data_len()? < UpgradeableLoaderState::program_len()? {
+                log!(logger, "Program account too small");
+                return Err(InstructionError::AccountDataTooSmall);
+            }
+            if program.lamports()? < rent.minimum_balance(program.data_len()?) {
+                log!(logger, "Program account not rent-exempt");
+                return Err(InstructionError::ExecutableAccountNotRentExempt);
+            }
+            let new_program_id = *program.unsigned_key();
+
+            // Verify Buffer account
+
+            if let UpgradeableLoaderState::Buffer { authority_address } = buffer.state()? {
+                if authority_address != upgrade_authority_address {
+                    log!(logger, "Buffer and upgrade authority don't match");
+                    return Err(InstructionError::IncorrectAuthority);
+                }
+                if authority.signer_key().is_none() {
+                    log!(logger, "Upgrade authority did not sign");
+                    return Err(InstructionError::MissingRequiredSignature);
+                }
+            } else {
+                log!(logger, "Invalid Buffer account");
+                return Err(InstructionError::InvalidArgument);
+            }
+
+            let buffer_data_offset = UpgradeableLoaderState::buffer_data_offset()?;
+            let buffer_data_len = buffer.data_len()?.saturating_sub(buffer_data_offset);
+            let programdata_data_offset = UpgradeableLoaderState::programdata_data_offset()?;
+            let programdata_len = UpgradeableLoaderState::programdata_len(max_data_len)?;
+
+            if buffer.data_len()? < buffer_data_offset || buffer_data_len == 0 {
+                log!(logger, "Buffer account too small");
+                return Err(InstructionError::InvalidAccountData);
+            }
+            if max_data_len < buffer_data_len {
+                log!(logger, "Max data length is too small to hold Buffer data");
+                return Err(InstructionError::AccountDataTooSmall);
+            }
+            if programdata_len > MAX_PERMITTED_DATA_LENGTH as usize {
+                log!(logger, "Max data length is too large");
+                return Err(InstructionError::InvalidArgument);
+            }
+
+            // Create ProgramData account
+
+            let (derived_address, bump_seed) =
+                Pubkey::find_program_address(&[new_program_id.as_ref()], program_id);
+            if derived_address != *programdata.unsigned_key() {
+                log!(logger, "ProgramData address is not derived");
+                return Err(InstructionError::InvalidArgument);
+            }
+
+            let signers = [Pubkey::create_program_address(
+                &[new_program_id.as_ref(), &[bump_seed]],
+                program_id,
+            )
+            .map_err(|_| InstructionError::InvalidSeeds)?];
+            MessageProcessor::native_invoke(
+                invoke_context,
+                system_instruction::create_account(
+                    payer.unsigned_key(),
+                    programdata.unsigned_key(),
+                    1.max(rent.minimum_balance(programdata_len)),
+                    programdata_len as u64,
+                    program_id,
+                ),
+                &[payer, programdata, system],
+                &signers,
+            )?;
+
+            // Load and verify the program bits
+            let _ = create_and_cache_executor(
+                &new_program_id,
+                &buffer.try_account_ref()?.data[buffer_data_offset..],
+                invoke_context,
+            )?;
+
+            // Update the ProgramData account and record the program bits
+            programdata.set_state(&UpgradeableLoaderState::ProgramData {
+                slot: clock.slot,
+                upgrade_authority_address,
+            })?;
+            programdata.try_account_ref_mut()?.data
+                [programdata_data_offset..programdata_data_offset + buffer_data_len]
+                .copy_from_slice(&buffer.try_account_ref()?.data[buffer_data_offset..]);
+
+            // Update the Program account
+            program.set_state(&UpgradeableLoaderState::Program {
+                programdata_address: *programdata.unsigned_key(),
+            })?;
+            program.try_account_ref_mut()?.executable = true;
+
+            // Drain the Buffer account back to the payer
+            payer.try_account_ref_mut()?.lamports += buffer.lamports()?;
+            buffer.try_account_ref_mut()?.lamports = 0;
+
+            log!(logger, "Deployed program {:?}", new_program_id);
+        }
+        UpgradeableLoaderInstruction::Upgrade => {
+            let programdata = next_keyed_account(account_iter)?;
+            let program = next_keyed_account(account_iter)?;
+            let buffer = next_keyed_account(account_iter)?;
+            let spill = next_keyed_account(account_iter)?;
+            let rent = from_keyed_account::<Rent>(next_keyed_account(account_iter)?)?;
+            let clock = from_keyed_account::<Clock>(next_keyed_account(account_iter)?)?;
+            let authority = next_keyed_account(account_iter)?;
+
+            // Verify Program account
+
+            if !program.executable()? {
+                log!(logger, "Program account not executable");
+                return Err(InstructionError::AccountNotExecutable);
+            }
+            if &program.owner()? != program_id {
+                log!(logger, "Program account not owned by loader");
+                return Err(InstructionError::IncorrectProgramId);
+            }
+            if let UpgradeableLoaderState::Program {
+                programdata_address,
+            } = program.state()?
+            {
+                if programdata_address != *programdata.unsigned_key() {
+                    log!(logger, "Program and ProgramData account mismatch");
+                    return Err(InstructionError::InvalidArgument);
+                }
+            } else {
+                log!(logger, "Invalid Program account");
+                return Err(InstructionError::InvalidAccountData);
+            }
+
+            // Verify Buffer account
+
+            if let UpgradeableLoaderState::Buffer { authority_address } = buffer.state()? {
+                if authority_address != Some(*authority.unsigned_key()) {
+                    log!(logger, "Buffer and upgrade authority don't match");
+                    return Err(InstructionError::IncorrectAuthority);
                 }
-                program.try_account_ref_mut()?.data[offset..offset + len].copy_from_slice(&bytes);
+            } else {
+                log!(logger, "Invalid Buffer account");
+                return Err(InstructionError::InvalidArgument);
+            }
+
+            let buffer_data_offset = UpgradeableLoaderState::buffer_data_offset()?;
+            let buffer_data_len = buffer.data_len()?.saturating_sub(buffer_data_offset);
+            let programdata_data_offset = UpgradeableLoaderState::programdata_data_offset()?;
+            let programdata_balance_required = 1.max(rent.minimum_balance(programdata.data_len()?));
+
+            if buffer.data_len()? < buffer_data_offset || buffer_data_len == 0 {
+                log!(logger, "Buffer account too small");
+                return Err(InstructionError::InvalidAccountData);
             }
-            LoaderInstruction::Finalize => {
-                if program.signer_key().is_none() {
-                    log!(logger, "key[0] did not sign the transaction");
+
+            // Verify ProgramData account
+
+            if programdata.data_len()? < UpgradeableLoaderState::programdata_len(buffer_data_len)? {
+                log!(logger, "ProgramData account not large enough");
+                return Err(InstructionError::AccountDataTooSmall);
+            }
+            if programdata.lamports()? + buffer.lamports()? < programdata_balance_required {
+                log!(logger, "Buffer account balance too low to fund upgrade");
+                return Err(InstructionError::InsufficientFunds);
+            }
+            if let UpgradeableLoaderState::ProgramData {
+                slot: _,
+                upgrade_authority_address,
+            } = programdata.state()?
+            {
+                if upgrade_authority_address.is_none() {
+                    log!(logger, "Program not upgradeable");
+                    return Err(InstructionError::Immutable);
+                }
+                if upgrade_authority_address != Some(*authority.unsigned_key()) {
+                    log!(logger, "Incorrect upgrade authority provided");
+                    return Err(InstructionError::IncorrectAuthority);
+                }
+                if authority.signer_key().is_none() {
+                    log!(logger, "Upgrade authority did not sign");
                     return Err(InstructionError::MissingRequiredSignature);
                 }
+            } else {
+                log!(logger, "Invalid ProgramData account");
+                return Err(InstructionError::InvalidAccountData);
+            }
 
-                let _ = create_and_cache_executor(program, invoke_context)?;
-                program.try_account_ref_mut()?.executable = true;
-                log!(
-                    logger,
-                    "Finalized account {:?}",
-                    program.signer_key().unwrap()
-                );
+            // Load and verify the program bits
+            let _ = create_and_cache_executor(
+                program.unsigned_key(),
+                &buffer.try_account_ref()?.data[buffer_data_offset..],
+                invoke_context,
+            )?;
+
+            // Update the ProgramData account, record the upgraded data, and zero
+            // the rest
+            programdata.set_state(&UpgradeableLoaderState::ProgramData {
+                slot: clock.slot,
+                upgrade_authority_address: Some(*authority.unsigned_key()),
+            })?;
+            {
+                let mut programdata_account = programdata.try_account_ref_mut()?;
+                programdata_account.data
+                    [programdata_data_offset..programdata_data_offset + buffer_data_len]
+                    .copy_from_slice(&buffer.try_account_ref()?.data[buffer_data_offset..]);
+                for byte in
+                    programdata_account.data[programdata_data_offset + buffer_data_len..].iter_mut()
+                {
+                    *byte = 0;
+                }
             }
+
+            // Fund ProgramData to rent-exemption, spill the rest
+
+            spill.try_account_ref_mut()?.lamports += (programdata.lamports()?
+                + buffer.lamports()?)
+            .saturating_sub(programdata_balance_required);
+            buffer.try_account_ref_mut()?.lamports = 0;
+            programdata.try_account_ref_mut()?.lamports = programdata_balance_required;
+
+            log!(logger, "Upgraded program {:?}", program.unsigned_key());
         }
+        UpgradeableLoaderInstruction::SetAuthority => {
+            let account = next_keyed_account(account_iter)?;
+            let present_authority = next_keyed_account(account_iter)?;
+            let new_authority = next_keyed_account(account_iter)
+                .ok()
+                .map(|account| account.unsigned_key());
+
+            match account.state()? {
+                UpgradeableLoaderState::Buffer { authority_address } => {
+                    if authority_address.is_none() {
+                        log!(logger, "Buffer is immutable");
+                        return Err(InstructionError::Immutable);
+                    }
+                    if authority_address != Some(*present_authority.unsigned_key()) {
+                        log!(logger, "Incorrect buffer authority provided");
+                        return Err(InstructionError::IncorrectAuthority);
+                    }
+                    if present_authority.signer_key().is_none() {
+                        log!(logger, "Buffer authority did not sign");
+                        return Err(InstructionError::MissingRequiredSignature);
+                    }
+                    account.set_state(&UpgradeableLoaderState::Buffer {
+                        authority_address: new_authority.cloned(),
+                    })?;
+                }
+                UpgradeableLoaderState::ProgramData {
+                    slot,
+                    upgrade_authority_address,
+                } => {
+                    if upgrade_authority_address.is_none() {
+                        log!(logger, "Program not upgradeable");
+                        return Err(InstructionError::Immutable);
+                    }
+                    if upgrade_authority_address != Some(*present_authority.unsigned_key()) {
+                        log!(logger, "Incorrect upgrade authority provided");
+                        return Err(InstructionError::IncorrectAuthority);
+                    }
+                    if present_authority.signer_key().is_none() {
+                        log!(logger, "Upgrade authority did not sign");
+                        return Err(InstructionError::MissingRequiredSignature);
+                    }
+                    account.set_state(&UpgradeableLoaderState::ProgramData {
+                        slot,
+                        upgrade_authority_address: new_authority.cloned(),
+                    })?;
+                }
+                _ => {
+                    log!(logger, "Account does not support authorities");
+                    return Err(InstructionError::InvalidAccountData);
+                }
+            }
+
+            log!(logger, "New authority {:?}", new_authority);
+        }
+    }
+
+    Ok(())
+}
+
+fn write_program_data(
+    account: &KeyedAccount,
+    offset: usize,
+    bytes: &[u8],
+    invoke_context: &mut dyn InvokeContext,
+) -> Result<(), InstructionError> {
+    let logger = invoke_context.get_logger();
+
+    let len = bytes.len();
+    if account.data_len()? < offset + len {
+        log!(
+            logger,
+            "Write overflow: {} < {}",
+            account.data_len()?,
+            offset + len
+        );
+        return Err(InstructionError::AccountDataTooSmall);
     }
+    account.try_account_ref_mut()?.data[offset..offset + len].copy_from_slice(&bytes);
     Ok(())
 }
 
@@ -259,6 +689,10 @@ impl Executor for BPFExecutor {
 
         let mut keyed_accounts_iter = keyed_accounts.iter();
         let program = next_keyed_account(&mut keyed_accounts_iter)?;
+        if bpf_loader_upgradeable::check_id(program_id) {
+            // Skip the ProgramData account
+            let _ = next_keyed_account(&mut keyed_accounts_iter)?;
+        }
 
         let parameter_accounts = keyed_accounts_iter.as_slice();
         let mut parameter_bytes = serialize_parameters(
@@ -338,12 +772,15 @@ mod tests {
     use rand::Rng;
     use solana_runtime::message_processor::{Executors, ThisInvokeContext};
     use solana_sdk::{
-        account::Account,
+        account::{create_account, Account},
+        account_utils::StateMut,
         feature_set::FeatureSet,
         instruction::InstructionError,
+        native_loader,
         process_instruction::{BpfComputeBudget, MockInvokeContext},
         pubkey::Pubkey,
         rent::Rent,
+        system_program, sysvar,
     };
     use std::{cell::RefCell, fs::File, io::Read, ops::Range, rc::Rc};
 
@@ -726,6 +1163,565 @@ mod tests {
         }
     }
 
+    fn mock_system_process_instruction(
+        _program_id: &Pubkey,
+        keyed_accounts: &[KeyedAccount],
+        instruction_data: &[u8],
+        _invoke_context: &mut dyn InvokeContext,
+    ) -> Result<(), InstructionError> {
+        match limited_deserialize(instruction_data)? {
+            system_instruction::SystemInstruction::CreateAccount {
+                lamports,
+                space,
+                owner,
+            } => {
+                let from = &keyed_accounts[0];
+                let to = &keyed_accounts[1];
+                if from.signer_key().is_none() || to.signer_key().is_none() {
+                    return Err(InstructionError::MissingRequiredSignature);
+                }
+                from.try_account_ref_mut()?.lamports -= lamports;
+                let mut to = to.try_account_ref_mut()?;
+                to.lamports += lamports;
+                to.data = vec![0; space as usize];
+                to.owner = owner;
+                Ok(())
+            }
+            _ => Err(InstructionError::InvalidInstructionData),
+        }
+    }
+
+    fn read_elf(name: &str) -> Vec<u8> {
+        let mut file = File::open(name).expect("file open failed");
+        let mut elf = Vec::new();
+        file.read_to_end(&mut elf).unwrap();
+        elf
+    }
+
+    #[test]
+    fn test_bpf_loader_upgradeable_initialize_buffer() {
+        let instruction_data =
+            bincode::serialize(&UpgradeableLoaderInstruction::InitializeBuffer).unwrap();
+        let buffer_address = Pubkey::new_unique();
+        let buffer_account = Account::new_ref(
+            1,
+            UpgradeableLoaderState::buffer_len(9).unwrap(),
+            &bpf_loader_upgradeable::id(),
+        );
+        let authority_address = Pubkey::new_unique();
+        let authority_account = Account::new_ref(
+            1,
+            UpgradeableLoaderState::buffer_len(9).unwrap(),
+            &bpf_loader_upgradeable::id(),
+        );
+
+        // Case: Success
+        assert_eq!(
+            Ok(()),
+            process_instruction(
+                &bpf_loader_upgradeable::id(),
+                &[
+                    KeyedAccount::new(&buffer_address, false, &buffer_account),
+                    KeyedAccount::new_readonly(&authority_address, false, &authority_account)
+                ],
+                &instruction_data,
+                &mut MockInvokeContext::default()
+            )
+        );
+        let state: UpgradeableLoaderState = buffer_account.borrow().state().unwrap();
+        assert_eq!(
+            state,
+            UpgradeableLoaderState::Buffer {
+                authority_address: Some(authority_address)
+            }
+        );
+
+        // Case: Already initialized
+        assert_eq!(
+            Err(InstructionError::AccountAlreadyInitialized),
+            process_instruction(
+                &bpf_loader_upgradeable::id(),
+                &[KeyedAccount::new(&buffer_address, false, &buffer_account)],
+                &instruction_data,
+                &mut MockInvokeContext::default()
+            )
+        );
+    }
+
+    #[test]
+    fn test_bpf_loader_upgradeable_write() {
+        let buffer_address = Pubkey::new_unique();
+        let buffer_account = Account::new_ref(
+            1,
+            UpgradeableLoaderState::buffer_len(9).unwrap(),
+            &bpf_loader_upgradeable::id(),
+        );
+        let authority_address = Pubkey::new_unique();
+        let authority_account = Account::new_ref(1, 0, &Pubkey::new_unique());
+        buffer_account
+            .borrow_mut()
+            .set_state(&UpgradeableLoaderState::Buffer {
+                authority_address: Some(authority_address),
+            })
+            .unwrap();
+        let instruction_data = bincode::serialize(&UpgradeableLoaderInstruction::Write {
+            offset: 3,
+            bytes: vec![42; 6],
+        })
+        .unwrap();
+
+        // Case: Success
+        assert_eq!(
+            Ok(()),
+            process_instruction(
+                &bpf_loader_upgradeable::id(),
+                &[
+                    KeyedAccount::new(&buffer_address, false, &buffer_account),
+                    KeyedAccount::new_readonly(&authority_address, true, &authority_account)
+                ],
+                &instruction_data,
+                &mut MockInvokeContext::default()
+            )
+        );
+        assert_eq!(
+            &buffer_account.borrow().data[UpgradeableLoaderState::buffer_data_offset().unwrap()..],
+            &[0, 0, 0, 42, 42, 42, 42, 42, 42]
+        );
+
+        // Case: Authority did not sign
+        assert_eq!(
+            Err(InstructionError::MissingRequiredSignature),
+            process_instruction(
+                &bpf_loader_upgradeable::id(),
+                &[
+                    KeyedAccount::new(&buffer_address, false, &buffer_account),
+                    KeyedAccount::new_readonly(&authority_address, false, &authority_account)
+                ],
+                &instruction_data,
+                &mut MockInvokeContext::default()
+            )
+        );
+
+        // Case: Incorrect authority
+        let wrong_authority_address = Pubkey::new_unique();
+        assert_eq!(
+            Err(InstructionError::IncorrectAuthority),
+            process_instruction(
+                &bpf_loader_upgradeable::id(),
+                &[
+                    KeyedAccount::new(&buffer_address, false, &buffer_account),
+                    KeyedAccount::new_readonly(&wrong_authority_address, true, &authority_account)
+                ],
+                &instruction_data,
+                &mut MockInvokeContext::default()
+            )
+        );
+
+        // Case: Overflow
+        let instruction_data = bincode::serialize(&UpgradeableLoaderInstruction::Write {
+            offset: 4,
+            bytes: vec![42; 6],
+        })
+        .unwrap();
+        assert_eq!(
+            Err(InstructionError::AccountDataTooSmall),
+            process_instruction(
+                &bpf_loader_upgradeable::id(),
+                &[
+                    KeyedAccount::new(&buffer_address, false, &buffer_account),
+                    KeyedAccount::new_readonly(&authority_address, true, &authority_account)
+                ],
+                &instruction_data,
+                &mut MockInvokeContext::default()
+            )
+        );
+
+        // Case: Immutable buffer
+        buffer_account
+            .borrow_mut()
+            .set_state(&UpgradeableLoaderState::Buffer {
+                authority_address: None,
+            })
+            .unwrap();
+        assert_eq!(
+            Err(InstructionError::Immutable),
+            process_instruction(
+                &bpf_loader_upgradeable::id(),
+                &[
+                    KeyedAccount::new(&buffer_address, false, &buffer_account),
+                    KeyedAccount::new_readonly(&authority_address, true, &authority_account)
+                ],
+                &instruction_data,
+                &mut MockInvokeContext::default()
+            )
+        );
+    }
+
+    #[test]
+    fn test_bpf_loader_upgradeable_deploy_with_max_len() {
+        let elf = read_elf("test_elfs/noop_aligned.so");
+        let rent = Rent::default();
+        let max_data_len = elf.len() * 2;
+        let mut invoke_context = MockInvokeContext::default();
+        invoke_context
+            .programs
+            .push((system_program::id(), mock_system_process_instruction));
+
+        let payer_address = Pubkey::new_unique();
+        let payer_account = Account::new_ref(1_000_000_000, 0, &system_program::id());
+        let program_address = Pubkey::new_unique();
+        let program_account = Account::new_ref(
+            rent.minimum_balance(UpgradeableLoaderState::program_len().unwrap()),
+            UpgradeableLoaderState::program_len().unwrap(),
+            &bpf_loader_upgradeable::id(),
+        );
+        let programdata_address =
+            bpf_loader_upgradeable::get_program_data_address(&program_address);
+        let programdata_account = Account::new_ref(0, 0, &system_program::id());
+        let authority_address = Pubkey::new_unique();
+        let authority_account = Account::new_ref(1, 0, &system_program::id());
+        let buffer_address = Pubkey::new_unique();
+        let buffer_account = Account::new_ref(
+            rent.minimum_balance(UpgradeableLoaderState::buffer_len(elf.len()).unwrap()),
+            UpgradeableLoaderState::buffer_len(elf.len()).unwrap(),
+            &bpf_loader_upgradeable::id(),
+        );
+        buffer_account
+            .borrow_mut()
+            .set_state(&UpgradeableLoaderState::Buffer {
+                authority_address: Some(authority_address),
+            })
+            .unwrap();
+        buffer_account.borrow_mut().data[UpgradeableLoaderState::buffer_data_offset().unwrap()..]
+            .copy_from_slice(&elf);
+        let buffer_lamports = buffer_account.borrow().lamports;
+        let rent_account = RefCell::new(create_account(&rent, 1));
+        let clock_account = RefCell::new(create_account(&Clock::default(), 1));
+        let system_account = RefCell::new(native_loader::create_loadable_account(""));
+        let instruction_data =
+            bincode::serialize(&UpgradeableLoaderInstruction::DeployWithMaxDataLen {
+                max_data_len,
+            })
+            .unwrap();
+
+        // Case: Incorrect authority
+        let wrong_authority_address = Pubkey::new_unique();
+        assert_eq!(
+            Err(InstructionError::IncorrectAuthority),
+            process_instruction(
+                &bpf_loader_upgradeable::id(),
+                &[
+                    KeyedAccount::new(&payer_address, true, &payer_account),
+                    KeyedAccount::new(&programdata_address, false, &programdata_account),
+                    KeyedAccount::new(&program_address, false, &program_account),
+                    KeyedAccount::new(&buffer_address, false, &buffer_account),
+                    KeyedAccount::new_readonly(&sysvar::rent::id(), false, &rent_account),
+                    KeyedAccount::new_readonly(&sysvar::clock::id(), false, &clock_account),
+                    KeyedAccount::new_readonly(&system_program::id(), false, &system_account),
+                    KeyedAccount::new_readonly(&wrong_authority_address, true, &authority_account),
+                ],
+                &instruction_data,
+                &mut invoke_context
+            )
+        );
+
+        // Case: Success
+        assert_eq!(
+            Ok(()),
+            process_instruction(
+                &bpf_loader_upgradeable::id(),
+                &[
+                    KeyedAccount::new(&payer_address, true, &payer_account),
+                    KeyedAccount::new(&programdata_address, false, &programdata_account),
+                    KeyedAccount::new(&program_address, false, &program_account),
+                    KeyedAccount::new(&buffer_address, false, &buffer_account),
+                    KeyedAccount::new_readonly(&sysvar::rent::id(), false, &rent_account),
+                    KeyedAccount::new_readonly(&sysvar::clock::id(), false, &clock_account),
+                    KeyedAccount::new_readonly(&system_program::id(), false, &system_account),
+                    KeyedAccount::new_readonly(&authority_address, true, &authority_account),
+                ],
+                &instruction_data,
+                &mut invoke_context
+            )
+        );
+        let program = program_account.borrow();
+        assert!(program.executable);
+        assert_eq!(
+            program.state(),
+            Ok(UpgradeableLoaderState::Program {
+                programdata_address
+            })
+        );
+        let programdata = programdata_account.borrow();
+        assert_eq!(programdata.owner, bpf_loader_upgradeable::id());
+        assert_eq!(
+            programdata.data.len(),
+            UpgradeableLoaderState::programdata_len(max_data_len).unwrap()
+        );
+        assert_eq!(
+            programdata.state(),
+            Ok(UpgradeableLoaderState::ProgramData {
+                slot: 0,
+                upgrade_authority_address: Some(authority_address)
+            })
+        );
+        let offset = UpgradeableLoaderState::programdata_data_offset().unwrap();
+        assert_eq!(&programdata.data[offset..offset + elf.len()], &elf[..]);
+        assert_eq!(buffer_account.borrow().lamports, 0);
+        assert_eq!(
+            payer_account.borrow().lamports,
+            1_000_000_000 + buffer_lamports - programdata.lamports
+        );
+    }
+
+    #[test]
+    fn test_bpf_loader_upgradeable_upgrade() {
+        let elf = read_elf("test_elfs/noop_aligned.so");
+        let rent = Rent::default();
+        let programdata_len = UpgradeableLoaderState::programdata_len(elf.len()).unwrap();
+
+        let program_address = Pubkey::new_unique();
+        let programdata_address =
+            bpf_loader_upgradeable::get_program_data_address(&program_address);
+        let authority_address = Pubkey::new_unique();
+        let program_account = Account::new_ref(
+            1,
+            UpgradeableLoaderState::program_len().unwrap(),
+            &bpf_loader_upgradeable::id(),
+        );
+        program_account
+            .borrow_mut()
+            .set_state(&UpgradeableLoaderState::Program {
+                programdata_address,
+            })
+            .unwrap();
+        program_account.borrow_mut().executable = true;
+        let programdata_account = Account::new_ref(
+            rent.minimum_balance(programdata_len),
+            programdata_len,
+            &bpf_loader_upgradeable::id(),
+        );
+        programdata_account
+            .borrow_mut()
+            .set_state(&UpgradeableLoaderState::ProgramData {
+                slot: 0,
+                upgrade_authority_address: Some(authority_address),
+            })
+            .unwrap();
+        let buffer_address = Pubkey::new_unique();
+        let buffer_account = Account::new_ref(
+            42,
+            UpgradeableLoaderState::buffer_len(elf.len()).unwrap(),
+            &bpf_loader_upgradeable::id(),
+        );
+        buffer_account
+            .borrow_mut()
+            .set_state(&UpgradeableLoaderState::Buffer {
+                authority_address: Some(authority_address),
+            })
+            .unwrap();
+        buffer_account.borrow_mut().data[UpgradeableLoaderState::buffer_data_offset().unwrap()..]
+            .copy_from_slice(&elf);
+        let spill_address = Pubkey::new_unique();
+        let spill_account = Account::new_ref(0, 0, &system_program::id());
+        let authority_account = Account::new_ref(1, 0, &system_program::id());
+        let mut clock = Clock::default();
+        clock.slot = 42;
+        let rent_account = RefCell::new(create_account(&rent, 1));
+        let clock_account = RefCell::new(create_account(&clock, 1));
+        let instruction_data = bincode::serialize(&UpgradeableLoaderInstruction::Upgrade).unwrap();
+
+        // Case: Authority did not sign
+        assert_eq!(
+            Err(InstructionError::MissingRequiredSignature),
+            process_instruction(
+                &bpf_loader_upgradeable::id(),
+                &[
+                    KeyedAccount::new(&programdata_address, false, &programdata_account),
+                    KeyedAccount::new_readonly(&program_address, false, &program_account),
+                    KeyedAccount::new(&buffer_address, false, &buffer_account),
+                    KeyedAccount::new(&spill_address, false, &spill_account),
+                    KeyedAccount::new_readonly(&sysvar::rent::id(), false, &rent_account),
+                    KeyedAccount::new_readonly(&sysvar::clock::id(), false, &clock_account),
+                    KeyedAccount::new_readonly(&authority_address, false, &authority_account),
+                ],
+                &instruction_data,
+                &mut MockInvokeContext::default()
+            )
+        );
+
+        // Case: Success
+        assert_eq!(
+            Ok(()),
+            process_instruction(
+                &bpf_loader_upgradeable::id(),
+                &[
+                    KeyedAccount::new(&programdata_address, false, &programdata_account),
+                    KeyedAccount::new_readonly(&program_address, false, &program_account),
+                    KeyedAccount::new(&buffer_address, false, &buffer_account),
+                    KeyedAccount::new(&spill_address, false, &spill_account),
+                    KeyedAccount::new_readonly(&sysvar::rent::id(), false, &rent_account),
+                    KeyedAccount::new_readonly(&sysvar::clock::id(), false, &clock_account),
+                    KeyedAccount::new_readonly(&authority_address, true, &authority_account),
+                ],
+                &instruction_data,
+                &mut MockInvokeContext::default()
+            )
+        );
+        let programdata = programdata_account.borrow();
+        assert_eq!(
+            programdata.state(),
+            Ok(UpgradeableLoaderState::ProgramData {
+                slot: 42,
+                upgrade_authority_address: Some(authority_address)
+            })
+        );
+        let offset = UpgradeableLoaderState::programdata_data_offset().unwrap();
+        assert_eq!(&programdata.data[offset..], &elf[..]);
+        assert_eq!(programdata.lamports, rent.minimum_balance(programdata_len));
+        assert_eq!(buffer_account.borrow().lamports, 0);
+        assert_eq!(spill_account.borrow().lamports, 42);
+    }
+
+    #[test]
+    fn test_bpf_loader_upgradeable_set_upgrade_authority() {
+        let instruction_data =
+            bincode::serialize(&UpgradeableLoaderInstruction::SetAuthority).unwrap();
+        let programdata_address = Pubkey::new_unique();
+        let programdata_account = Account::new_ref(
+            1,
+            UpgradeableLoaderState::programdata_len(0).unwrap(),
+            &bpf_loader_upgradeable::id(),
+        );
+        let upgrade_authority_address = Pubkey::new_unique();
+        let upgrade_authority_account = Account::new_ref(1, 0, &Pubkey::new_unique());
+        let new_upgrade_authority_address = Pubkey::new_unique();
+        let new_upgrade_authority_account = Account::new_ref(1, 0, &Pubkey::new_unique());
+        programdata_account
+            .borrow_mut()
+            .set_state(&UpgradeableLoaderState::ProgramData {
+                slot: 0,
+                upgrade_authority_address: Some(upgrade_authority_address),
+            })
+            .unwrap();
+
+        // Case: Authority did not sign
+        assert_eq!(
+            Err(InstructionError::MissingRequiredSignature),
+            process_instruction(
+                &bpf_loader_upgradeable::id(),
+                &[
+                    KeyedAccount::new(&programdata_address, false, &programdata_account),
+                    KeyedAccount::new_readonly(
+                        &upgrade_authority_address,
+                        false,
+                        &upgrade_authority_account
+                    ),
+                    KeyedAccount::new_readonly(
+                        &new_upgrade_authority_address,
+                        false,
+                        &new_upgrade_authority_account
+                    ),
+                ],
+                &instruction_data,
+                &mut MockInvokeContext::default()
+            )
+        );
+
+        // Case: Set to new authority
+        assert_eq!(
+            Ok(()),
+            process_instruction(
+                &bpf_loader_upgradeable::id(),
+                &[
+                    KeyedAccount::new(&programdata_address, false, &programdata_account),
+                    KeyedAccount::new_readonly(
+                        &upgrade_authority_address,
+                        true,
+                        &upgrade_authority_account
+                    ),
+                    KeyedAccount::new_readonly(
+                        &new_upgrade_authority_address,
+                        false,
+                        &new_upgrade_authority_account
+                    ),
+                ],
+                &instruction_data,
+                &mut MockInvokeContext::default()
+            )
+        );
+        let state: UpgradeableLoaderState = programdata_account.borrow().state().unwrap();
+        assert_eq!(
+            state,
+            UpgradeableLoaderState::ProgramData {
+                slot: 0,
+                upgrade_authority_address: Some(new_upgrade_authority_address),
+            }
+        );
+
+        // Case: Old authority no longer valid
+        assert_eq!(
+            Err(InstructionError::IncorrectAuthority),
+            process_instruction(
+                &bpf_loader_upgradeable::id(),
+                &[
+                    KeyedAccount::new(&programdata_address, false, &programdata_account),
+                    KeyedAccount::new_readonly(
+                        &upgrade_authority_address,
+                        true,
+                        &upgrade_authority_account
+                    ),
+                ],
+                &instruction_data,
+                &mut MockInvokeContext::default()
+            )
+        );
+
+        // Case: Revoke the authority
+        assert_eq!(
+            Ok(()),
+            process_instruction(
+                &bpf_loader_upgradeable::id(),
+                &[
+                    KeyedAccount::new(&programdata_address, false, &programdata_account),
+                    KeyedAccount::new_readonly(
+                        &new_upgrade_authority_address,
+                        true,
+                        &new_upgrade_authority_account
+                    ),
+                ],
+                &instruction_data,
+                &mut MockInvokeContext::default()
+            )
+        );
+        let state: UpgradeableLoaderState = programdata_account.borrow().state().unwrap();
+        assert_eq!(
+            state,
+            UpgradeableLoaderState::ProgramData {
+                slot: 0,
+                upgrade_authority_address: None,
+            }
+        );
+
+        // Case: Program is immutable
+        assert_eq!(
+            Err(InstructionError::Immutable),
+            process_instruction(
+                &bpf_loader_upgradeable::id(),
+                &[
+                    KeyedAccount::new(&programdata_address, false, &programdata_account),
+                    KeyedAccount::new_readonly(
+                        &new_upgrade_authority_address,
+                        true,
+                        &new_upgrade_authority_account
+                    ),
+                ],
+                &instruction_data,
+                &mut MockInvokeContext::default()
+            )
+        );
+    }
+
     #[test]
     #[ignore]
     fn test_fuzz() {
@@ -12,7 +12,9 @@ use solana_runtime::message_processor::MessageProcessor;
 use solana_sdk::{
     account::Account,
     account_info::AccountInfo,
+    account_utils::StateMut,
     bpf_loader_deprecated,
+    bpf_loader_upgradeable::{self, UpgradeableLoaderState},
     entrypoint::{MAX_PERMITTED_DATA_INCREASE, SUCCESS},
     feature_set::{
         pubkey_log_syscall_enabled, ristretto_mul_syscall_enabled, sha256_syscall_enabled,
@@ -1399,7 +1401,39 @@ fn call<'a>(
     if !program_account.borrow().executable {
         return Err(SyscallError::InstructionError(InstructionError::AccountNotExecutable).into());
     }
-    let executable_accounts = vec![(callee_program_id, program_account)];
+    let programdata_executable =
+        if bpf_loader_upgradeable::check_id(&program_account.borrow().owner) {
+            if let UpgradeableLoaderState::Program {
+                programdata_address,
+            } = program_account
+                .borrow()
+                .state()
+                .map_err(SyscallError::InstructionError)?
+            {
+                // The ProgramData account must be available to the caller
+                let programdata = syscall
+                    .get_callers_keyed_accounts()
+                    .iter()
+                    .find(|keyed_account| keyed_account.unsigned_key() == &programdata_address)
+                    .ok_or(SyscallError::InstructionError(
+                        InstructionError::MissingAccount,
+                    ))?
+                    .try_account_ref()
+                    .map_err(SyscallError::InstructionError)?
+                    .clone();
+                Some((programdata_address, RefCell::new(programdata)))
+            } else {
+                return Err(
+                    SyscallError::InstructionError(InstructionError::MissingAccount).into(),
+                );
+            }
+        } else {
+            None
+        };
+    let mut executable_accounts = vec![(callee_program_id, program_account)];
+    if let Some(programdata) = programdata_executable {
+        executable_accounts.push(programdata);
+    }
 
     #[allow(clippy::deref_addrof)]
     match MessageProcessor::process_cross_program_instruction(
@@ -6,6 +6,7 @@ use dashmap::DashMap;
 use rand::Rng;
 use solana_runtime::{
     accounts::{create_test_accounts, Accounts},
+    accounts_index::AccountSecondaryIndexes,
     bank::*,
 };
 use solana_sdk::{
@@ -49,6 +50,7 @@ fn test_accounts_create(bencher: &mut Bencher) {
         &[],
         None,
         None,
+        AccountSecondaryIndexes::default(),
     );
     bencher.iter(|| {
         let mut pubkeys: Vec<Pubkey> = vec![];
@@ -66,6 +68,7 @@ fn test_accounts_squash(bencher: &mut Bencher) {
         &[],
         None,
         None,
+        AccountSecondaryIndexes::default(),
     ));
     let mut pubkeys: Vec<Pubkey> = vec![];
     deposit_many(&bank1, &mut pubkeys, 250_000);
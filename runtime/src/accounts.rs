@@ -2,7 +2,8 @@ use crate::{
     accounts_db::{
         AccountInfo, AccountStorage, AccountsDB, AppendVecId, BankHashInfo, ErrorCounters,
     },
-    accounts_index::{AccountsIndex, Ancestors},
+    accounts_index::{AccountSecondaryIndexes, AccountsIndex, Ancestors, IndexKey},
+    accounts_update_notifier_interface::AccountsUpdateNotifier,
     append_vec::StoredAccount,
     bank::{HashAgeKind, TransactionProcessResult},
     blockhash_queue::BlockhashQueue,
@@ -16,12 +17,17 @@ use rayon::slice::ParallelSliceMut;
 use solana_sdk::{
     account::Account,
     account_utils::StateMut,
+    address_lookup_table::{self, AddressLookupTableState},
+    bpf_loader_upgradeable::{self, UpgradeableLoaderState},
     clock::{Epoch, Slot},
     feature_set::{self, FeatureSet},
     fee_calculator::{FeeCalculator, FeeConfig},
     genesis_config::ClusterType,
     hash::Hash,
-    message::Message,
+    message::{
+        v0::{LoadedAddresses, MessageAddressTableLookup},
+        Message,
+    },
     native_loader, nonce,
     pubkey::Pubkey,
     transaction::Result,
@@ -72,8 +78,20 @@ pub enum AccountAddressFilter {
 
 impl Accounts {
     pub fn new(paths: Vec<PathBuf>, cluster_type: &ClusterType) -> Self {
+        Self::new_with_indexes(paths, cluster_type, AccountSecondaryIndexes::default())
+    }
+
+    pub fn new_with_indexes(
+        paths: Vec<PathBuf>,
+        cluster_type: &ClusterType,
+        account_indexes: AccountSecondaryIndexes,
+    ) -> Self {
         Self {
-            accounts_db: Arc::new(AccountsDB::new(paths, cluster_type)),
+            accounts_db: Arc::new(AccountsDB::new_with_indexes(
+                paths,
+                cluster_type,
+                account_indexes,
+            )),
             account_locks: Mutex::new(HashSet::new()),
             readonly_locks: Arc::new(RwLock::new(Some(HashMap::new()))),
             ..Self::default()
@@ -260,6 +278,28 @@ impl Accounts {
 
             // add loader to chain
             let program_owner = program.owner;
+
+            if bpf_loader_upgradeable::check_id(&program_owner) {
+                // The upgradeable loader requires the derived ProgramData account
+                if let Ok(UpgradeableLoaderState::Program {
+                    programdata_address,
+                }) = program.state()
+                {
+                    if let Some(programdata) =
+                        AccountsDB::load(storage, ancestors, accounts_index, &programdata_address)
+                            .map(|(account, _)| account)
+                    {
+                        accounts.insert(0, (programdata_address, programdata));
+                    } else {
+                        error_counters.account_not_found += 1;
+                        return Err(TransactionError::ProgramAccountNotFound);
+                    }
+                } else {
+                    error_counters.invalid_program_for_execution += 1;
+                    return Err(TransactionError::InvalidProgramForExecution);
+                }
+            }
+
             accounts.insert(0, (program_id, program));
             program_id = program_owner;
         }
@@ -311,6 +351,8 @@ impl Accounts {
         let fee_config = FeeConfig {
             secp256k1_program_enabled: feature_set
                 .is_active(&feature_set::secp256k1_program_enabled::id()),
+            compute_budget_program_enabled: feature_set
+                .is_active(&feature_set::compute_budget_program::id()),
         };
         OrderedIterator::new(txs, txs_iteration_order)
             .zip(lock_results.into_iter())
@@ -419,6 +461,35 @@ impl Accounts {
         }
     }
 
+    /// Load the addresses referenced by `lookup` from its address lookup table, as of
+    /// `current_slot`
+    pub fn load_lookup_table_addresses(
+        &self,
+        ancestors: &Ancestors,
+        current_slot: Slot,
+        lookup: &MessageAddressTableLookup,
+    ) -> Result<LoadedAddresses> {
+        let (table_account, _) = self
+            .load_slow(ancestors, &lookup.account_key)
+            .ok_or(TransactionError::AddressLookupTableNotFound)?;
+        if !address_lookup_table::check_id(&table_account.owner) {
+            return Err(TransactionError::InvalidAddressLookupTableOwner);
+        }
+        let table = match table_account.state() {
+            Ok(AddressLookupTableState::LookupTable(table)) => table,
+            _ => return Err(TransactionError::InvalidAddressLookupTableData),
+        };
+        let lookup_addresses = |indexes: &[u8]| {
+            table
+                .lookup(current_slot, indexes)
+                .ok_or(TransactionError::InvalidAddressLookupTableIndex)
+        };
+        Ok(LoadedAddresses {
+            writable: lookup_addresses(&lookup.writable_indexes)?,
+            readonly: lookup_addresses(&lookup.readonly_indexes)?,
+        })
+    }
+
     /// scans underlying accounts_db for this delta (slot) with a map function
     ///   from StoredAccount to B
     /// returns only the latest/current version of B for this slot
@@ -569,6 +640,21 @@ impl Accounts {
         )
     }
 
+    pub fn load_by_index_key_with_filter<F: Fn(&Account) -> bool>(
+        &self,
+        ancestors: &Ancestors,
+        index_key: &IndexKey,
+        filter: F,
+    ) -> Vec<(Pubkey, Account)> {
+        self.accounts_db.index_scan_accounts(
+            ancestors,
+            *index_key,
+            |collector: &mut Vec<(Pubkey, Account)>, some_account_tuple| {
+                Self::load_while_filtering(collector, some_account_tuple, &filter)
+            },
+        )
+    }
+
     pub fn load_all(&self, ancestors: &Ancestors) -> Vec<(Pubkey, Account, Slot)> {
         self.accounts_db.scan_accounts(
             ancestors,
@@ -612,6 +698,21 @@ impl Accounts {
     /// Slow because lock is held for 1 operation instead of many
     pub fn store_slow(&self, slot: Slot, pubkey: &Pubkey, account: &Account) {
         self.accounts_db.store(slot, &[(pubkey, account)]);
+        self.notify_account_updates(slot, &[(pubkey, account)]);
+    }
+
+    /// Register a notifier that receives every account stored through `Accounts`
+    pub fn set_accounts_update_notifier(&self, notifier: AccountsUpdateNotifier) {
+        *self.accounts_db.accounts_update_notifier.write().unwrap() = Some(notifier);
+    }
+
+    fn notify_account_updates(&self, slot: Slot, accounts: &[(&Pubkey, &Account)]) {
+        if let Some(notifier) = &*self.accounts_db.accounts_update_notifier.read().unwrap() {
+            let notifier = notifier.read().unwrap();
+            for (pubkey, account) in accounts {
+                notifier.notify_account_update(slot, pubkey, account);
+            }
+        }
     }
 
     fn is_locked_readonly(&self, key: &Pubkey) -> bool {
@@ -704,6 +805,11 @@ impl Accounts {
             Err(TransactionError::AccountInUse) => (),
             Err(TransactionError::SanitizeFailure) => (),
             Err(TransactionError::AccountLoadedTwice) => (),
+            Err(TransactionError::AddressLookupTableNotFound) => (),
+            Err(TransactionError::InvalidAddressLookupTableOwner) => (),
+            Err(TransactionError::InvalidAddressLookupTableData) => (),
+            Err(TransactionError::InvalidAddressLookupTableIndex) => (),
+            Err(TransactionError::UnsupportedVersion) => (),
             _ => {
                 let (writable_keys, readonly_keys) = &tx.message().get_account_keys_by_lock_type();
                 for k in writable_keys {
@@ -803,6 +909,7 @@ impl Accounts {
             rent_fix_enabled,
         );
         self.accounts_db.store(slot, &accounts_to_store);
+        self.notify_account_updates(slot, &accounts_to_store);
     }
 
     /// Purge a slot if it is not a root
@@ -974,7 +1081,10 @@ mod tests {
     // TODO: all the bank tests are bank specific, issue: 2194
 
     use super::*;
-    use crate::{bank::HashAgeKind, rent_collector::RentCollector};
+    use crate::{
+        accounts_update_notifier_interface::AccountsUpdateNotifierInterface, bank::HashAgeKind,
+        rent_collector::RentCollector,
+    };
     use solana_sdk::{
         account::Account,
         epoch_schedule::EpochSchedule,
@@ -1608,6 +1718,45 @@ mod tests {
         assert_eq!(error_counters.account_not_found, 1);
     }
 
+    #[derive(Debug, Default)]
+    struct TestAccountsUpdateNotifier {
+        updates: Mutex<Vec<(Slot, Pubkey, Account)>>,
+    }
+
+    impl AccountsUpdateNotifierInterface for TestAccountsUpdateNotifier {
+        fn notify_account_update(&self, slot: Slot, pubkey: &Pubkey, account: &Account) {
+            self.updates
+                .lock()
+                .unwrap()
+                .push((slot, *pubkey, account.clone()));
+        }
+    }
+
+    #[test]
+    fn test_accounts_update_notifier() {
+        let accounts = Accounts::new(Vec::new(), &ClusterType::Development);
+        let pubkey0 = solana_sdk::pubkey::new_rand();
+        let account0 = Account::new(1, 0, &Pubkey::default());
+        // Stores before a notifier is registered are not reported
+        accounts.store_slow(0, &pubkey0, &account0);
+
+        let notifier = Arc::new(RwLock::new(TestAccountsUpdateNotifier::default()));
+        accounts.set_accounts_update_notifier(notifier.clone());
+
+        let pubkey1 = solana_sdk::pubkey::new_rand();
+        let account1 = Account::new(2, 0, &Pubkey::default());
+        accounts.store_slow(0, &pubkey1, &account1);
+
+        // Child accounts share the notifier with their parent
+        let child = Accounts::new_from_parent(&accounts, 1, 0, 0);
+        child.store_slow(1, &pubkey0, &account1);
+
+        assert_eq!(
+            *notifier.read().unwrap().updates.lock().unwrap(),
+            vec![(0, pubkey1, account1.clone()), (1, pubkey0, account1)]
+        );
+    }
+
     #[test]
     #[should_panic]
     fn test_accounts_empty_bank_hash() {
@@ -5,7 +5,7 @@
 use crate::{
     bank::{Bank, BankSlotDelta},
     bank_forks::{BankForks, SnapshotConfig},
-    snapshot_package::AccountsPackageSender,
+    snapshot_package::{AccountsPackageSender, SnapshotType},
     snapshot_utils,
 };
 use crossbeam_channel::{Receiver, Sender};
@@ -67,6 +67,8 @@ impl SnapshotRequestHandler {
                 snapshot_root_bank.clean_accounts(true);
                 clean_time.stop();
 
+                let snapshot_type = self.snapshot_type(&snapshot_root_bank);
+
                 // Generate an accounts package
                 let mut snapshot_time = Measure::start("snapshot_time");
                 let r = snapshot_utils::snapshot_bank(
@@ -77,6 +79,7 @@ impl SnapshotRequestHandler {
                     &self.snapshot_config.snapshot_package_output_path,
                     self.snapshot_config.snapshot_version,
                     &self.snapshot_config.compression,
+                    snapshot_type,
                 );
                 if r.is_err() {
                     warn!(
@@ -84,6 +87,17 @@ impl SnapshotRequestHandler {
                         snapshot_root_bank.slot(),
                         r
                     );
+                } else if snapshot_type == SnapshotType::FullSnapshot
+                    && self.incremental_snapshots_enabled()
+                    && snapshot_root_bank.block_height()
+                        % self.snapshot_config.snapshot_interval_slots
+                        == 0
+                {
+                    snapshot_root_bank
+                        .rc
+                        .accounts
+                        .accounts_db
+                        .set_last_full_snapshot_slot(snapshot_root_bank.slot());
                 }
                 snapshot_time.stop();
 
@@ -107,6 +121,29 @@ impl SnapshotRequestHandler {
                 snapshot_root_bank.block_height()
             })
     }
+
+    fn incremental_snapshots_enabled(&self) -> bool {
+        self.snapshot_config.incremental_snapshot_interval_slots != std::u64::MAX
+    }
+
+    // Incremental snapshots are taken at the incremental interval once a full snapshot exists
+    // to base them on; every other request produces a full snapshot
+    fn snapshot_type(&self, snapshot_root_bank: &Bank) -> SnapshotType {
+        let block_height = snapshot_root_bank.block_height();
+        if !self.incremental_snapshots_enabled()
+            || block_height % self.snapshot_config.incremental_snapshot_interval_slots != 0
+            || block_height % self.snapshot_config.snapshot_interval_slots == 0
+        {
+            return SnapshotType::FullSnapshot;
+        }
+        snapshot_root_bank
+            .rc
+            .accounts
+            .accounts_db
+            .last_full_snapshot_slot()
+            .map(SnapshotType::IncrementalSnapshot)
+            .unwrap_or(SnapshotType::FullSnapshot)
+    }
 }
 
 pub struct AccountsBackgroundService {
@@ -19,7 +19,10 @@
 //! commit for each slot entry would be indexed.
 
 use crate::{
-    accounts_index::{AccountsIndex, Ancestors, SlotList, SlotSlice},
+    accounts_index::{
+        AccountSecondaryIndexes, AccountsIndex, Ancestors, IndexKey, SlotList, SlotSlice,
+    },
+    accounts_update_notifier_interface::AccountsUpdateNotifier,
     append_vec::{AppendVec, StoredAccount, StoredMeta},
 };
 use blake3::traits::digest::Digest;
@@ -125,6 +128,12 @@ impl Versioned for (u64, AccountInfo) {
     }
 }
 
+impl Versioned for (u64, Account, Hash) {
+    fn version(&self) -> u64 {
+        self.0
+    }
+}
+
 #[derive(Clone, Default, Debug)]
 pub struct AccountStorage(pub DashMap<Slot, SlotStores>);
 
@@ -458,6 +467,15 @@ pub struct AccountsDB {
     stats: AccountsStats,
 
     pub cluster_type: Option<ClusterType>,
+
+    /// Secondary indexes to maintain in `accounts_index`
+    pub account_indexes: AccountSecondaryIndexes,
+
+    /// Receives a notification for every account written by `Accounts`
+    pub(crate) accounts_update_notifier: RwLock<Option<AccountsUpdateNotifier>>,
+
+    /// Slot of the full snapshot that incremental snapshots are currently taken against
+    last_full_snapshot_slot: RwLock<Option<Slot>>,
 }
 
 #[derive(Debug, Default)]
@@ -535,17 +553,29 @@ impl Default for AccountsDB {
             frozen_accounts: HashMap::new(),
             stats: AccountsStats::default(),
             cluster_type: None,
+            account_indexes: AccountSecondaryIndexes::default(),
+            accounts_update_notifier: RwLock::new(None),
+            last_full_snapshot_slot: RwLock::new(None),
         }
     }
 }
 
 impl AccountsDB {
     pub fn new(paths: Vec<PathBuf>, cluster_type: &ClusterType) -> Self {
+        AccountsDB::new_with_indexes(paths, cluster_type, AccountSecondaryIndexes::default())
+    }
+
+    pub fn new_with_indexes(
+        paths: Vec<PathBuf>,
+        cluster_type: &ClusterType,
+        account_indexes: AccountSecondaryIndexes,
+    ) -> Self {
         let new = if !paths.is_empty() {
             Self {
                 paths,
                 temp_paths: None,
                 cluster_type: Some(*cluster_type),
+                account_indexes,
                 ..Self::default()
             }
         } else {
@@ -556,6 +586,7 @@ impl AccountsDB {
                 paths,
                 temp_paths: Some(temp_dirs),
                 cluster_type: Some(*cluster_type),
+                account_indexes,
                 ..Self::default()
             }
         };
@@ -740,6 +771,17 @@ impl AccountsDB {
     // collection
     // Only remove those accounts where the entire rooted history of the account
     // can be purged because there are no live append vecs in the ancestors
+    /// Record the slot of the full snapshot that later incremental snapshots are based on.
+    /// Zero-lamport accounts updated after this slot are not purged by `clean_accounts()`,
+    /// because the full snapshot may still hold older versions of them
+    pub fn set_last_full_snapshot_slot(&self, slot: Slot) {
+        *self.last_full_snapshot_slot.write().unwrap() = Some(slot);
+    }
+
+    pub fn last_full_snapshot_slot(&self) -> Option<Slot> {
+        *self.last_full_snapshot_slot.read().unwrap()
+    }
+
     pub fn clean_accounts(&self, max_clean_root: Option<Slot>) {
         // hold a lock to prevent slot shrinking from running because it might modify some rooted
         // slot storages which can not happen as long as we're cleaning accounts because we're also
@@ -752,6 +794,7 @@ impl AccountsDB {
                 Some(std::cmp::min(min_scan_root, max_clean_root))
             }
         };
+        let last_full_snapshot_slot = self.last_full_snapshot_slot();
         let mut candidates = self.shrink_candidate_slots.lock().unwrap();
 
         self.report_store_stats();
@@ -776,7 +819,10 @@ impl AccountsDB {
                         self.accounts_index.get(pubkey, None, max_clean_root)
                     {
                         let (slot, account_info) = &locked_entry.slot_list()[index];
-                        if account_info.lamports == 0 {
+                        let is_newer_than_full_snapshot = last_full_snapshot_slot
+                            .map(|full_snapshot_slot| *slot > full_snapshot_slot)
+                            .unwrap_or(false);
+                        if account_info.lamports == 0 && !is_newer_than_full_snapshot {
                             purges.insert(
                                 *pubkey,
                                 self.accounts_index
@@ -1314,6 +1360,30 @@ impl AccountsDB {
         collector
     }
 
+    pub fn index_scan_accounts<F, A>(
+        &self,
+        ancestors: &Ancestors,
+        index_key: IndexKey,
+        scan_func: F,
+    ) -> A
+    where
+        F: Fn(&mut A, Option<(&Pubkey, Account, Slot)>),
+        A: Default,
+    {
+        let mut collector = A::default();
+        self.accounts_index.index_scan_accounts(
+            ancestors,
+            index_key,
+            |pubkey, (account_info, slot)| {
+                let account_slot = self
+                    .get_account_from_storage(slot, account_info)
+                    .map(|account| (pubkey, account, slot));
+                scan_func(&mut collector, account_slot)
+            },
+        );
+        collector
+    }
+
     pub fn range_scan_accounts<F, A, R>(&self, ancestors: &Ancestors, range: R, scan_func: F) -> A
     where
         F: Fn(&mut A, Option<(&Pubkey, Account, Slot)>),
@@ -2175,7 +2245,7 @@ impl AccountsDB {
         hash
     }
 
-    fn accumulate_account_hashes_and_capitalization(
+    pub fn accumulate_account_hashes_and_capitalization(
         hashes: Vec<(Pubkey, Hash, u64)>,
         slot: Slot,
         debug: bool,
@@ -2420,6 +2490,37 @@ impl AccountsDB {
         ret
     }
 
+    /// The latest version of each account stored in `slot` along with its hash, which are the
+    /// inputs of the slot's accounts delta hash
+    pub fn get_accounts_delta_hash_inputs(&self, slot: Slot) -> Vec<(Pubkey, Account, Hash)> {
+        let mut accumulator: Vec<HashMap<Pubkey, (u64, Account, Hash)>> = self
+            .scan_account_storage(
+                slot,
+                |stored_account: &StoredAccount,
+                 _store_id: AppendVecId,
+                 accum: &mut HashMap<Pubkey, (u64, Account, Hash)>| {
+                    accum.insert(
+                        stored_account.meta.pubkey,
+                        (
+                            stored_account.meta.write_version,
+                            stored_account.clone_account(),
+                            *stored_account.hash,
+                        ),
+                    );
+                },
+            );
+        let mut account_maps = HashMap::new();
+        while let Some(maps) = accumulator.pop() {
+            AccountsDB::merge(&mut account_maps, &maps);
+        }
+        let mut inputs: Vec<_> = account_maps
+            .into_iter()
+            .map(|(pubkey, (_, account, hash))| (pubkey, account, hash))
+            .collect();
+        inputs.sort_by_key(|(pubkey, _, _)| *pubkey);
+        inputs
+    }
+
     fn update_index(
         &self,
         slot: Slot,
@@ -2428,9 +2529,16 @@ impl AccountsDB {
     ) -> SlotList<AccountInfo> {
         let mut reclaims = SlotList::<AccountInfo>::with_capacity(infos.len() * 2);
         for (info, pubkey_account) in infos.into_iter().zip(accounts.iter()) {
-            let pubkey = pubkey_account.0;
-            self.accounts_index
-                .upsert(slot, pubkey, info, &mut reclaims);
+            let (pubkey, account) = pubkey_account;
+            self.accounts_index.upsert(
+                slot,
+                pubkey,
+                &account.owner,
+                &account.data,
+                &self.account_indexes,
+                info,
+                &mut reclaims,
+            );
         }
         reclaims
     }
@@ -2865,6 +2973,13 @@ impl AccountsDB {
                             offset: stored_account.offset,
                             lamports: stored_account.account_meta.lamports,
                         };
+                        self.accounts_index.update_secondary_indexes(
+                            &stored_account.meta.pubkey,
+                            *slot,
+                            &stored_account.account_meta.owner,
+                            &stored_account.data,
+                            &self.account_indexes,
+                        );
                         let entry = accum
                             .entry(stored_account.meta.pubkey)
                             .or_insert_with(Vec::new);
@@ -2889,9 +3004,13 @@ impl AccountsDB {
                 for (pubkey, account_infos) in accounts_map.iter_mut() {
                     account_infos.sort_by(|a, b| a.0.cmp(&b.0));
                     for (_, account_info) in account_infos {
+                        // Secondary indexes were already updated while scanning the storages
                         self.accounts_index.upsert(
                             *slot,
                             pubkey,
+                            &Pubkey::default(),
+                            &[],
+                            &AccountSecondaryIndexes::default(),
                             account_info.clone(),
                             &mut _reclaims,
                         );
@@ -3729,6 +3848,35 @@ pub mod tests {
         assert!(accounts.accounts_index.get(&pubkey, None, None).is_none());
     }
 
+    #[test]
+    fn test_clean_zero_lamport_newer_than_last_full_snapshot() {
+        solana_logger::setup();
+
+        let accounts = AccountsDB::new(Vec::new(), &ClusterType::Development);
+        let pubkey = solana_sdk::pubkey::new_rand();
+        let account = Account::new(1, 0, &Account::default().owner);
+        let zero_lamport_account = Account::new(0, 0, &Account::default().owner);
+
+        accounts.store(0, &[(&pubkey, &account)]);
+        accounts.store(1, &[(&pubkey, &zero_lamport_account)]);
+        accounts.add_root(0);
+        accounts.add_root(1);
+
+        // The full snapshot at slot 0 still holds the funded account, so the zero-lamport
+        // update must survive for incremental snapshots to record the deletion
+        accounts.set_last_full_snapshot_slot(0);
+        accounts.clean_accounts(None);
+        assert_eq!(accounts.alive_account_count_in_store(0), 0);
+        assert_eq!(accounts.alive_account_count_in_store(1), 1);
+        assert!(accounts.accounts_index.get(&pubkey, None, None).is_some());
+
+        // Once a full snapshot includes the deletion the account can be purged
+        accounts.set_last_full_snapshot_slot(1);
+        accounts.clean_accounts(None);
+        assert_eq!(accounts.alive_account_count_in_store(1), 0);
+        assert!(accounts.accounts_index.get(&pubkey, None, None).is_none());
+    }
+
     #[test]
     fn test_clean_old_with_normal_account() {
         solana_logger::setup();
@@ -4795,6 +4943,35 @@ pub mod tests {
         );
     }
 
+    #[test]
+    fn test_get_accounts_delta_hash_inputs() {
+        let db = AccountsDB::new(Vec::new(), &ClusterType::Development);
+        let key0 = solana_sdk::pubkey::new_rand();
+        let key1 = solana_sdk::pubkey::new_rand();
+        let account0 = Account::new(1, 0, &key0);
+        let account1 = Account::new(2, 0, &key1);
+        db.store(1, &[(&key0, &account0), (&key1, &account1)]);
+        let account0_updated = Account::new(3, 0, &key0);
+        db.store(1, &[(&key0, &account0_updated)]);
+        // Other slots are not part of the delta
+        db.store(2, &[(&key1, &account0)]);
+
+        let mut expected = vec![
+            (
+                key0,
+                account0_updated.clone(),
+                AccountsDB::hash_account(1, &account0_updated, &key0, &ClusterType::Development),
+            ),
+            (
+                key1,
+                account1.clone(),
+                AccountsDB::hash_account(1, &account1, &key1, &ClusterType::Development),
+            ),
+        ];
+        expected.sort_by_key(|(pubkey, _, _)| *pubkey);
+        assert_eq!(db.get_accounts_delta_hash_inputs(1), expected);
+    }
+
     #[test]
     fn test_bad_bank_hash() {
         solana_logger::setup();
@@ -5369,12 +5546,60 @@ pub mod tests {
             lamports: 0,
         };
         let mut reclaims = vec![];
-        accounts_index.upsert(0, &key0, info0, &mut reclaims);
-        accounts_index.upsert(1, &key0, info1.clone(), &mut reclaims);
-        accounts_index.upsert(1, &key1, info1, &mut reclaims);
-        accounts_index.upsert(2, &key1, info2.clone(), &mut reclaims);
-        accounts_index.upsert(2, &key2, info2, &mut reclaims);
-        accounts_index.upsert(3, &key2, info3, &mut reclaims);
+        accounts_index.upsert(
+            0,
+            &key0,
+            &Pubkey::default(),
+            &[],
+            &AccountSecondaryIndexes::default(),
+            info0,
+            &mut reclaims,
+        );
+        accounts_index.upsert(
+            1,
+            &key0,
+            &Pubkey::default(),
+            &[],
+            &AccountSecondaryIndexes::default(),
+            info1.clone(),
+            &mut reclaims,
+        );
+        accounts_index.upsert(
+            1,
+            &key1,
+            &Pubkey::default(),
+            &[],
+            &AccountSecondaryIndexes::default(),
+            info1,
+            &mut reclaims,
+        );
+        accounts_index.upsert(
+            2,
+            &key1,
+            &Pubkey::default(),
+            &[],
+            &AccountSecondaryIndexes::default(),
+            info2.clone(),
+            &mut reclaims,
+        );
+        accounts_index.upsert(
+            2,
+            &key2,
+            &Pubkey::default(),
+            &[],
+            &AccountSecondaryIndexes::default(),
+            info2,
+            &mut reclaims,
+        );
+        accounts_index.upsert(
+            3,
+            &key2,
+            &Pubkey::default(),
+            &[],
+            &AccountSecondaryIndexes::default(),
+            info3,
+            &mut reclaims,
+        );
         accounts_index.add_root(0);
         accounts_index.add_root(1);
         accounts_index.add_root(2);
@@ -1,3 +1,4 @@
+use crate::{bank::inline_spl_token_v2_0, secondary_index::SecondaryIndex};
 use ouroboros::self_referencing;
 use solana_sdk::{clock::Slot, pubkey::Pubkey};
 use std::ops::{
@@ -24,6 +25,29 @@ pub type AccountMap<K, V> = BTreeMap<K, V>;
 
 type AccountMapEntry<T> = Arc<AccountMapEntryInner<T>>;
 
+/// Optional secondary indexes that can be maintained alongside the primary
+/// pubkey index to speed up scans over a subset of the accounts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AccountIndex {
+    ProgramId,
+    SplTokenMint,
+    SplTokenOwner,
+}
+
+pub type AccountSecondaryIndexes = HashSet<AccountIndex>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IndexKey {
+    ProgramId(Pubkey),
+    SplTokenMint(Pubkey),
+    SplTokenOwner(Pubkey),
+}
+
+enum ScanTypes<R: RangeBounds<Pubkey>> {
+    Unindexed(Option<R>),
+    Indexed(IndexKey),
+}
+
 #[derive(Debug)]
 pub struct AccountMapEntryInner<T> {
     ref_count: AtomicU64,
@@ -184,6 +208,9 @@ impl<'a, T: 'static + Clone> Iterator for AccountsIndexIterator<'a, T> {
 #[derive(Debug, Default)]
 pub struct AccountsIndex<T> {
     pub account_maps: RwLock<AccountMap<Pubkey, AccountMapEntry<T>>>,
+    program_id_index: SecondaryIndex,
+    spl_token_mint_index: SecondaryIndex,
+    spl_token_owner_index: SecondaryIndex,
     roots_tracker: RwLock<RootsTracker>,
     ongoing_scan_roots: RwLock<BTreeMap<Slot, u64>>,
 }
@@ -200,29 +227,12 @@ impl<T: 'static + Clone> AccountsIndex<T> {
         &'a self,
         ancestors: &Ancestors,
         func: F,
-        range: Option<R>,
+        scan_type: ScanTypes<R>,
     ) where
         F: FnMut(&Pubkey, (&T, Slot)),
         R: RangeBounds<Pubkey>,
     {
-        let max_root = {
-            let mut w_ongoing_scan_roots = self
-                // This lock is also grabbed by clean_accounts(), so clean
-                // has at most cleaned up to the current `max_root` (since
-                // clean only happens *after* BankForks::set_root() which sets
-                // the `max_root`)
-                .ongoing_scan_roots
-                .write()
-                .unwrap();
-            // `max_root()` grabs a lock while
-            // the `ongoing_scan_roots` lock is held,
-            // make sure inverse doesn't happen to avoid
-            // deadlock
-            let max_root = self.max_root();
-            *w_ongoing_scan_roots.entry(max_root).or_default() += 1;
-
-            max_root
-        };
+        let max_root = self.hold_max_root();
 
         // First we show that for any bank `B` that is a descendant of
         // the current `max_root`, it must be true that and `B.ancestors.contains(max_root)`,
@@ -338,15 +348,45 @@ impl<T: 'static + Clone> AccountsIndex<T> {
         so a potential newer max root at 3 will not clean up any of the ancestors > 1, so slot 4
         will not be cleaned in the middle of the scan either.
         */
-        self.do_scan_accounts(ancestors, func, range, Some(max_root));
-        {
-            let mut ongoing_scan_roots = self.ongoing_scan_roots.write().unwrap();
-            let count = ongoing_scan_roots.get_mut(&max_root).unwrap();
-            *count -= 1;
-            if *count == 0 {
-                ongoing_scan_roots.remove(&max_root);
+        match scan_type {
+            ScanTypes::Unindexed(range) => {
+                self.do_scan_accounts(ancestors, func, range, Some(max_root));
+            }
+            ScanTypes::Indexed(index_key) => {
+                self.do_scan_secondary_index(ancestors, func, &index_key, Some(max_root));
             }
         }
+        self.release_max_root(max_root);
+    }
+
+    /// Returns the current max root, and keeps `clean_accounts()` from cleaning any newer root
+    /// until it is passed to `release_max_root()`
+    pub fn hold_max_root(&self) -> Slot {
+        let mut w_ongoing_scan_roots = self
+            // This lock is also grabbed by clean_accounts(), so clean
+            // has at most cleaned up to the current `max_root` (since
+            // clean only happens *after* BankForks::set_root() which sets
+            // the `max_root`)
+            .ongoing_scan_roots
+            .write()
+            .unwrap();
+        // `max_root()` grabs a lock while
+        // the `ongoing_scan_roots` lock is held,
+        // make sure inverse doesn't happen to avoid
+        // deadlock
+        let max_root = self.max_root();
+        *w_ongoing_scan_roots.entry(max_root).or_default() += 1;
+
+        max_root
+    }
+
+    pub fn release_max_root(&self, max_root: Slot) {
+        let mut ongoing_scan_roots = self.ongoing_scan_roots.write().unwrap();
+        let count = ongoing_scan_roots.get_mut(&max_root).unwrap();
+        *count -= 1;
+        if *count == 0 {
+            ongoing_scan_roots.remove(&max_root);
+        }
     }
 
     fn do_unchecked_scan_accounts<'a, F, R>(
@@ -384,6 +424,30 @@ impl<T: 'static + Clone> AccountsIndex<T> {
         }
     }
 
+    fn do_scan_secondary_index<'a, F>(
+        &'a self,
+        ancestors: &Ancestors,
+        mut func: F,
+        index_key: &IndexKey,
+        max_root: Option<Slot>,
+    ) where
+        F: FnMut(&Pubkey, (&T, Slot)),
+    {
+        let pubkeys = match index_key {
+            IndexKey::ProgramId(key) => self.program_id_index.get(key),
+            IndexKey::SplTokenMint(key) => self.spl_token_mint_index.get(key),
+            IndexKey::SplTokenOwner(key) => self.spl_token_owner_index.get(key),
+        };
+        for pubkey in pubkeys {
+            if let Some((list_r, index)) = self.get(&pubkey, Some(ancestors), max_root) {
+                func(
+                    &pubkey,
+                    (&list_r.slot_list()[index].1, list_r.slot_list()[index].0),
+                );
+            }
+        }
+    }
+
     pub fn get_account_read_entry(&self, pubkey: &Pubkey) -> Option<ReadAccountMapEntry<T>> {
         self.account_maps
             .read()
@@ -433,6 +497,7 @@ impl<T: 'static + Clone> AccountsIndex<T> {
                 if let btree_map::Entry::Occupied(index_entry) = w_index.entry(*key) {
                     if index_entry.get().slot_list.read().unwrap().is_empty() {
                         index_entry.remove();
+                        self.purge_secondary_indexes_by_inner_key(key, None);
                     }
                 }
             }
@@ -444,7 +509,7 @@ impl<T: 'static + Clone> AccountsIndex<T> {
     where
         F: FnMut(&Pubkey, (&T, Slot)),
     {
-        self.do_checked_scan_accounts(ancestors, func, None::<Range<Pubkey>>);
+        self.do_checked_scan_accounts(ancestors, func, ScanTypes::Unindexed(None::<Range<Pubkey>>));
     }
 
     pub(crate) fn unchecked_scan_accounts<F>(&self, ancestors: &Ancestors, func: F)
@@ -464,6 +529,19 @@ impl<T: 'static + Clone> AccountsIndex<T> {
         self.do_unchecked_scan_accounts(ancestors, func, Some(range));
     }
 
+    /// call func with every pubkey and index visible from a given set of ancestors that was
+    /// stored under `index_key` in the corresponding secondary index
+    pub(crate) fn index_scan_accounts<F>(&self, ancestors: &Ancestors, index_key: IndexKey, func: F)
+    where
+        F: FnMut(&Pubkey, (&T, Slot)),
+    {
+        self.do_checked_scan_accounts(
+            ancestors,
+            func,
+            ScanTypes::<Range<Pubkey>>::Indexed(index_key),
+        );
+    }
+
     pub fn get_rooted_entries(&self, slice: SlotSlice<T>, max: Option<Slot>) -> SlotList<T> {
         slice
             .iter()
@@ -488,16 +566,18 @@ impl<T: 'static + Clone> AccountsIndex<T> {
     // if this account has no more entries.
     pub fn purge(&self, pubkey: &Pubkey) -> (SlotList<T>, bool) {
         let mut write_account_map_entry = self.get_account_write_entry(pubkey).unwrap();
-        write_account_map_entry.slot_list_mut(|slot_list| {
+        let (reclaims, is_empty) = write_account_map_entry.slot_list_mut(|slot_list| {
             let reclaims = self.get_rooted_entries(slot_list, None);
             slot_list.retain(|(slot, _)| !self.is_root(*slot));
             (reclaims, slot_list.is_empty())
-        })
+        });
+        self.purge_secondary_indexes_by_slot_list(pubkey, &reclaims);
+        (reclaims, is_empty)
     }
 
     pub fn purge_exact(&self, pubkey: &Pubkey, slots: HashSet<Slot>) -> (SlotList<T>, bool) {
         let mut write_account_map_entry = self.get_account_write_entry(pubkey).unwrap();
-        write_account_map_entry.slot_list_mut(|slot_list| {
+        let (reclaims, is_empty) = write_account_map_entry.slot_list_mut(|slot_list| {
             let reclaims = slot_list
                 .iter()
                 .filter(|(slot, _)| slots.contains(&slot))
@@ -505,7 +585,9 @@ impl<T: 'static + Clone> AccountsIndex<T> {
                 .collect();
             slot_list.retain(|(slot, _)| !slots.contains(slot));
             (reclaims, slot_list.is_empty())
-        })
+        });
+        self.purge_secondary_indexes_by_inner_key(pubkey, Some(&slots));
+        (reclaims, is_empty)
     }
 
     pub fn min_ongoing_scan_root(&self) -> Option<Slot> {
@@ -596,15 +678,77 @@ impl<T: 'static + Clone> AccountsIndex<T> {
         &self,
         slot: Slot,
         pubkey: &Pubkey,
+        account_owner: &Pubkey,
+        account_data: &[u8],
+        account_indexes: &AccountSecondaryIndexes,
         account_info: T,
         reclaims: &mut SlotList<T>,
     ) -> bool {
         let (mut w_account_entry, is_newly_inserted) =
             self.get_account_write_entry_else_create(pubkey);
         w_account_entry.update(slot, account_info, reclaims);
+        self.update_secondary_indexes(pubkey, slot, account_owner, account_data, account_indexes);
         is_newly_inserted
     }
 
+    pub fn update_secondary_indexes(
+        &self,
+        pubkey: &Pubkey,
+        slot: Slot,
+        account_owner: &Pubkey,
+        account_data: &[u8],
+        account_indexes: &AccountSecondaryIndexes,
+    ) {
+        if account_indexes.is_empty() {
+            return;
+        }
+
+        if account_indexes.contains(&AccountIndex::ProgramId) {
+            self.program_id_index.insert(account_owner, pubkey, slot);
+        }
+
+        // Only index initialized-length SPL Token accounts; anything else owned by the token
+        // program (e.g. mints and multisigs) has a different layout
+        if *account_owner == inline_spl_token_v2_0::id()
+            && account_data.len() == inline_spl_token_v2_0::SPL_TOKEN_ACCOUNT_LEN
+        {
+            if account_indexes.contains(&AccountIndex::SplTokenMint) {
+                let mint_key = Pubkey::new(
+                    &account_data[inline_spl_token_v2_0::SPL_TOKEN_ACCOUNT_MINT_OFFSET
+                        ..inline_spl_token_v2_0::SPL_TOKEN_ACCOUNT_MINT_OFFSET + 32],
+                );
+                self.spl_token_mint_index.insert(&mint_key, pubkey, slot);
+            }
+
+            if account_indexes.contains(&AccountIndex::SplTokenOwner) {
+                let owner_key = Pubkey::new(
+                    &account_data[inline_spl_token_v2_0::SPL_TOKEN_ACCOUNT_OWNER_OFFSET
+                        ..inline_spl_token_v2_0::SPL_TOKEN_ACCOUNT_OWNER_OFFSET + 32],
+                );
+                self.spl_token_owner_index.insert(&owner_key, pubkey, slot);
+            }
+        }
+    }
+
+    fn purge_secondary_indexes_by_inner_key(
+        &self,
+        inner_key: &Pubkey,
+        slots: Option<&HashSet<Slot>>,
+    ) {
+        self.program_id_index.remove_by_inner_key(inner_key, slots);
+        self.spl_token_mint_index
+            .remove_by_inner_key(inner_key, slots);
+        self.spl_token_owner_index
+            .remove_by_inner_key(inner_key, slots);
+    }
+
+    fn purge_secondary_indexes_by_slot_list(&self, inner_key: &Pubkey, slot_list: SlotSlice<T>) {
+        if !slot_list.is_empty() {
+            let slots: HashSet<Slot> = slot_list.iter().map(|(slot, _)| *slot).collect();
+            self.purge_secondary_indexes_by_inner_key(inner_key, Some(&slots));
+        }
+    }
+
     pub fn unref_from_storage(&self, pubkey: &Pubkey) {
         if let Some(locked_entry) = self.get_account_read_entry(pubkey) {
             locked_entry.ref_count().fetch_sub(1, Ordering::Relaxed);
@@ -644,9 +788,12 @@ impl<T: 'static + Clone> AccountsIndex<T> {
         max_clean_root: Option<Slot>,
     ) {
         if let Some(mut locked_entry) = self.get_account_write_entry(pubkey) {
+            let mut new_reclaims = vec![];
             locked_entry.slot_list_mut(|slot_list| {
-                self.purge_older_root_entries(slot_list, reclaims, max_clean_root);
+                self.purge_older_root_entries(slot_list, &mut new_reclaims, max_clean_root);
             });
+            self.purge_secondary_indexes_by_slot_list(pubkey, &new_reclaims);
+            reclaims.extend(new_reclaims);
         }
     }
 
@@ -665,6 +812,10 @@ impl<T: 'static + Clone> AccountsIndex<T> {
                     *slot != purge_slot
                 });
             });
+            self.purge_secondary_indexes_by_inner_key(
+                pubkey,
+                Some(&[purge_slot].iter().cloned().collect()),
+            );
         }
     }
 
@@ -764,7 +915,15 @@ mod tests {
         let key = Keypair::new();
         let index = AccountsIndex::<bool>::default();
         let mut gc = Vec::new();
-        index.upsert(0, &key.pubkey(), true, &mut gc);
+        index.upsert(
+            0,
+            &key.pubkey(),
+            &Pubkey::default(),
+            &[],
+            &AccountSecondaryIndexes::default(),
+            true,
+            &mut gc,
+        );
         assert!(gc.is_empty());
 
         let ancestors = HashMap::new();
@@ -781,7 +940,15 @@ mod tests {
         let key = Keypair::new();
         let index = AccountsIndex::<bool>::default();
         let mut gc = Vec::new();
-        index.upsert(0, &key.pubkey(), true, &mut gc);
+        index.upsert(
+            0,
+            &key.pubkey(),
+            &Pubkey::default(),
+            &[],
+            &AccountSecondaryIndexes::default(),
+            true,
+            &mut gc,
+        );
         assert!(gc.is_empty());
 
         let ancestors = vec![(1, 1)].into_iter().collect();
@@ -797,7 +964,15 @@ mod tests {
         let key = Keypair::new();
         let index = AccountsIndex::<bool>::default();
         let mut gc = Vec::new();
-        index.upsert(0, &key.pubkey(), true, &mut gc);
+        index.upsert(
+            0,
+            &key.pubkey(),
+            &Pubkey::default(),
+            &[],
+            &AccountSecondaryIndexes::default(),
+            true,
+            &mut gc,
+        );
         assert!(gc.is_empty());
 
         let ancestors = vec![(0, 0)].into_iter().collect();
@@ -822,7 +997,15 @@ mod tests {
 
         let mut pubkeys: Vec<Pubkey> = std::iter::repeat_with(|| {
             let new_pubkey = solana_sdk::pubkey::new_rand();
-            index.upsert(root_slot, &new_pubkey, true, &mut vec![]);
+            index.upsert(
+                root_slot,
+                &new_pubkey,
+                &Pubkey::default(),
+                &[],
+                &AccountSecondaryIndexes::default(),
+                true,
+                &mut vec![],
+            );
             new_pubkey
         })
         .take(num_pubkeys.saturating_sub(1))
@@ -830,7 +1013,15 @@ mod tests {
 
         if num_pubkeys != 0 {
             pubkeys.push(Pubkey::default());
-            index.upsert(root_slot, &Pubkey::default(), true, &mut vec![]);
+            index.upsert(
+                root_slot,
+                &Pubkey::default(),
+                &Pubkey::default(),
+                &[],
+                &AccountSecondaryIndexes::default(),
+                true,
+                &mut vec![],
+            );
         }
 
         index.add_root(root_slot);
@@ -953,7 +1144,15 @@ mod tests {
         let mut iter = index.iter(None::<Range<Pubkey>>);
         assert!(iter.next().is_none());
         let mut gc = vec![];
-        index.upsert(0, &solana_sdk::pubkey::new_rand(), true, &mut gc);
+        index.upsert(
+            0,
+            &solana_sdk::pubkey::new_rand(),
+            &Pubkey::default(),
+            &[],
+            &AccountSecondaryIndexes::default(),
+            true,
+            &mut gc,
+        );
         assert!(iter.next().is_none());
     }
 
@@ -970,7 +1169,15 @@ mod tests {
         let key = Keypair::new();
         let index = AccountsIndex::<bool>::default();
         let mut gc = Vec::new();
-        index.upsert(0, &key.pubkey(), true, &mut gc);
+        index.upsert(
+            0,
+            &key.pubkey(),
+            &Pubkey::default(),
+            &[],
+            &AccountSecondaryIndexes::default(),
+            true,
+            &mut gc,
+        );
         assert!(gc.is_empty());
 
         index.add_root(0);
@@ -1076,14 +1283,30 @@ mod tests {
         let index = AccountsIndex::<bool>::default();
         let ancestors = vec![(0, 0)].into_iter().collect();
         let mut gc = Vec::new();
-        index.upsert(0, &key.pubkey(), true, &mut gc);
+        index.upsert(
+            0,
+            &key.pubkey(),
+            &Pubkey::default(),
+            &[],
+            &AccountSecondaryIndexes::default(),
+            true,
+            &mut gc,
+        );
         assert!(gc.is_empty());
         let (list, idx) = index.get(&key.pubkey(), Some(&ancestors), None).unwrap();
         assert_eq!(list.slot_list()[idx], (0, true));
         drop(list);
 
         let mut gc = Vec::new();
-        index.upsert(0, &key.pubkey(), false, &mut gc);
+        index.upsert(
+            0,
+            &key.pubkey(),
+            &Pubkey::default(),
+            &[],
+            &AccountSecondaryIndexes::default(),
+            false,
+            &mut gc,
+        );
         assert_eq!(gc, vec![(0, true)]);
         let (list, idx) = index.get(&key.pubkey(), Some(&ancestors), None).unwrap();
         assert_eq!(list.slot_list()[idx], (0, false));
@@ -1096,9 +1319,25 @@ mod tests {
         let index = AccountsIndex::<bool>::default();
         let ancestors = vec![(0, 0)].into_iter().collect();
         let mut gc = Vec::new();
-        index.upsert(0, &key.pubkey(), true, &mut gc);
+        index.upsert(
+            0,
+            &key.pubkey(),
+            &Pubkey::default(),
+            &[],
+            &AccountSecondaryIndexes::default(),
+            true,
+            &mut gc,
+        );
         assert!(gc.is_empty());
-        index.upsert(1, &key.pubkey(), false, &mut gc);
+        index.upsert(
+            1,
+            &key.pubkey(),
+            &Pubkey::default(),
+            &[],
+            &AccountSecondaryIndexes::default(),
+            false,
+            &mut gc,
+        );
         assert!(gc.is_empty());
         let (list, idx) = index.get(&key.pubkey(), Some(&ancestors), None).unwrap();
         assert_eq!(list.slot_list()[idx], (0, true));
@@ -1112,15 +1351,55 @@ mod tests {
         let key = Keypair::new();
         let index = AccountsIndex::<bool>::default();
         let mut gc = Vec::new();
-        index.upsert(0, &key.pubkey(), true, &mut gc);
+        index.upsert(
+            0,
+            &key.pubkey(),
+            &Pubkey::default(),
+            &[],
+            &AccountSecondaryIndexes::default(),
+            true,
+            &mut gc,
+        );
         assert!(gc.is_empty());
-        index.upsert(1, &key.pubkey(), false, &mut gc);
-        index.upsert(2, &key.pubkey(), true, &mut gc);
-        index.upsert(3, &key.pubkey(), true, &mut gc);
+        index.upsert(
+            1,
+            &key.pubkey(),
+            &Pubkey::default(),
+            &[],
+            &AccountSecondaryIndexes::default(),
+            false,
+            &mut gc,
+        );
+        index.upsert(
+            2,
+            &key.pubkey(),
+            &Pubkey::default(),
+            &[],
+            &AccountSecondaryIndexes::default(),
+            true,
+            &mut gc,
+        );
+        index.upsert(
+            3,
+            &key.pubkey(),
+            &Pubkey::default(),
+            &[],
+            &AccountSecondaryIndexes::default(),
+            true,
+            &mut gc,
+        );
         index.add_root(0);
         index.add_root(1);
         index.add_root(3);
-        index.upsert(4, &key.pubkey(), true, &mut gc);
+        index.upsert(
+            4,
+            &key.pubkey(),
+            &Pubkey::default(),
+            &[],
+            &AccountSecondaryIndexes::default(),
+            true,
+            &mut gc,
+        );
 
         // Updating index should not purge older roots, only purges
         // previous updates within the same slot
@@ -1146,9 +1425,25 @@ mod tests {
         let key = Keypair::new();
         let index = AccountsIndex::<u64>::default();
         let mut gc = Vec::new();
-        assert!(index.upsert(1, &key.pubkey(), 12, &mut gc));
-
-        assert!(!index.upsert(1, &key.pubkey(), 10, &mut gc));
+        assert!(index.upsert(
+            1,
+            &key.pubkey(),
+            &Pubkey::default(),
+            &[],
+            &AccountSecondaryIndexes::default(),
+            12,
+            &mut gc
+        ));
+
+        assert!(!index.upsert(
+            1,
+            &key.pubkey(),
+            &Pubkey::default(),
+            &[],
+            &AccountSecondaryIndexes::default(),
+            10,
+            &mut gc
+        ));
 
         let purges = index.purge(&key.pubkey());
         assert_eq!(purges, (vec![], false));
@@ -1157,7 +1452,15 @@ mod tests {
         let purges = index.purge(&key.pubkey());
         assert_eq!(purges, (vec![(1, 10)], true));
 
-        assert!(!index.upsert(1, &key.pubkey(), 9, &mut gc));
+        assert!(!index.upsert(
+            1,
+            &key.pubkey(),
+            &Pubkey::default(),
+            &[],
+            &AccountSecondaryIndexes::default(),
+            9,
+            &mut gc
+        ));
     }
 
     #[test]
@@ -1270,4 +1573,138 @@ mod tests {
         assert_eq!(reclaims, vec![(1, true), (2, true)]);
         assert_eq!(slot_list, vec![(5, true), (9, true)]);
     }
+
+    fn spl_token_account_data(mint: &Pubkey, owner: &Pubkey) -> Vec<u8> {
+        let mut data = vec![0; inline_spl_token_v2_0::SPL_TOKEN_ACCOUNT_LEN];
+        data[inline_spl_token_v2_0::SPL_TOKEN_ACCOUNT_MINT_OFFSET
+            ..inline_spl_token_v2_0::SPL_TOKEN_ACCOUNT_MINT_OFFSET + 32]
+            .copy_from_slice(mint.as_ref());
+        data[inline_spl_token_v2_0::SPL_TOKEN_ACCOUNT_OWNER_OFFSET
+            ..inline_spl_token_v2_0::SPL_TOKEN_ACCOUNT_OWNER_OFFSET + 32]
+            .copy_from_slice(owner.as_ref());
+        data
+    }
+
+    fn index_scan_keys(index: &AccountsIndex<bool>, index_key: IndexKey) -> Vec<Pubkey> {
+        let mut found = vec![];
+        index.index_scan_accounts(&HashMap::new(), index_key, |pubkey, _| found.push(*pubkey));
+        found.sort();
+        found
+    }
+
+    #[test]
+    fn test_secondary_indexes() {
+        let index = AccountsIndex::<bool>::default();
+        let account_indexes: AccountSecondaryIndexes = vec![
+            AccountIndex::ProgramId,
+            AccountIndex::SplTokenMint,
+            AccountIndex::SplTokenOwner,
+        ]
+        .into_iter()
+        .collect();
+        let token_account = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let token_data = spl_token_account_data(&mint, &owner);
+
+        index.upsert(
+            1,
+            &token_account,
+            &inline_spl_token_v2_0::id(),
+            &token_data,
+            &account_indexes,
+            true,
+            &mut vec![],
+        );
+        index.add_root(1);
+        assert_eq!(
+            index_scan_keys(&index, IndexKey::ProgramId(inline_spl_token_v2_0::id())),
+            vec![token_account]
+        );
+        assert_eq!(
+            index_scan_keys(&index, IndexKey::SplTokenMint(mint)),
+            vec![token_account]
+        );
+        assert_eq!(
+            index_scan_keys(&index, IndexKey::SplTokenOwner(owner)),
+            vec![token_account]
+        );
+
+        // Non-token accounts are only added to the program id index
+        let other_account = Pubkey::new_unique();
+        let other_program = Pubkey::new_unique();
+        index.upsert(
+            1,
+            &other_account,
+            &other_program,
+            &token_data,
+            &account_indexes,
+            true,
+            &mut vec![],
+        );
+        assert_eq!(
+            index_scan_keys(&index, IndexKey::ProgramId(other_program)),
+            vec![other_account]
+        );
+        assert_eq!(
+            index_scan_keys(&index, IndexKey::SplTokenOwner(owner)),
+            vec![token_account]
+        );
+
+        // Transferring ownership leaves the old owner entry in place until the
+        // older root is cleaned
+        let new_owner = Pubkey::new_unique();
+        index.upsert(
+            2,
+            &token_account,
+            &inline_spl_token_v2_0::id(),
+            &spl_token_account_data(&mint, &new_owner),
+            &account_indexes,
+            true,
+            &mut vec![],
+        );
+        index.add_root(2);
+        assert_eq!(
+            index_scan_keys(&index, IndexKey::SplTokenOwner(owner)),
+            vec![token_account]
+        );
+        let mut reclaims = vec![];
+        index.clean_rooted_entries(&token_account, &mut reclaims, None);
+        assert_eq!(reclaims, vec![(1, true)]);
+        assert!(index_scan_keys(&index, IndexKey::SplTokenOwner(owner)).is_empty());
+        assert_eq!(
+            index_scan_keys(&index, IndexKey::SplTokenOwner(new_owner)),
+            vec![token_account]
+        );
+
+        // Removing the account purges it from every secondary index
+        let (_, is_empty) = index.purge_exact(&token_account, vec![2].into_iter().collect());
+        assert!(is_empty);
+        index.handle_dead_keys(&[token_account]);
+        assert!(index_scan_keys(&index, IndexKey::SplTokenMint(mint)).is_empty());
+        assert!(index_scan_keys(&index, IndexKey::SplTokenOwner(new_owner)).is_empty());
+        assert!(
+            index_scan_keys(&index, IndexKey::ProgramId(inline_spl_token_v2_0::id())).is_empty()
+        );
+    }
+
+    #[test]
+    fn test_secondary_indexes_disabled() {
+        let index = AccountsIndex::<bool>::default();
+        let token_account = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        index.upsert(
+            0,
+            &token_account,
+            &inline_spl_token_v2_0::id(),
+            &spl_token_account_data(&Pubkey::new_unique(), &owner),
+            &AccountSecondaryIndexes::default(),
+            true,
+            &mut vec![],
+        );
+        index.add_root(0);
+        assert!(index.program_id_index.is_empty());
+        assert!(index.spl_token_mint_index.is_empty());
+        assert!(index.spl_token_owner_index.is_empty());
+    }
 }
@@ -0,0 +1,9 @@
+use solana_sdk::{account::Account, clock::Slot, pubkey::Pubkey};
+use std::sync::{Arc, RwLock};
+
+pub trait AccountsUpdateNotifierInterface: std::fmt::Debug {
+    /// Notified when an account is written to the accounts store
+    fn notify_account_update(&self, slot: Slot, pubkey: &Pubkey, account: &Account);
+}
+
+pub type AccountsUpdateNotifier = Arc<RwLock<dyn AccountsUpdateNotifierInterface + Sync + Send>>;
@@ -0,0 +1,278 @@
+use solana_sdk::{
+    account_utils::State,
+    address_lookup_table::{
+        AddressLookupTable, AddressLookupTableInstruction, AddressLookupTableState,
+        LookupTableMeta, LOOKUP_TABLE_MAX_ADDRESSES,
+    },
+    instruction::InstructionError,
+    keyed_account::{from_keyed_account, next_keyed_account, KeyedAccount},
+    process_instruction::InvokeContext,
+    program_utils::limited_deserialize,
+    pubkey::Pubkey,
+    sysvar::clock::Clock,
+};
+
+fn get_lookup_table(
+    program_id: &Pubkey,
+    table_account: &KeyedAccount,
+) -> Result<AddressLookupTable, InstructionError> {
+    if table_account.owner()? != *program_id {
+        return Err(InstructionError::IncorrectProgramId);
+    }
+    match table_account.state()? {
+        AddressLookupTableState::LookupTable(table) => Ok(table),
+        AddressLookupTableState::Uninitialized => Err(InstructionError::UninitializedAccount),
+    }
+}
+
+fn check_authority(
+    table: &AddressLookupTable,
+    authority_account: &KeyedAccount,
+) -> Result<(), InstructionError> {
+    match table.meta.authority {
+        // Frozen tables can't be modified
+        None => Err(InstructionError::Immutable),
+        Some(authority) if authority != *authority_account.unsigned_key() => {
+            Err(InstructionError::IncorrectAuthority)
+        }
+        Some(_) if authority_account.signer_key().is_none() => {
+            Err(InstructionError::MissingRequiredSignature)
+        }
+        Some(_) => Ok(()),
+    }
+}
+
+fn set_lookup_table(
+    table_account: &KeyedAccount,
+    table: AddressLookupTable,
+) -> Result<(), InstructionError> {
+    let state = AddressLookupTableState::LookupTable(table);
+    let size = bincode::serialized_size(&state).map_err(|_| InstructionError::GenericError)?;
+    if size as usize > table_account.data_len()? {
+        return Err(InstructionError::AccountDataTooSmall);
+    }
+    table_account.set_state(&state)
+}
+
+pub fn process_instruction(
+    program_id: &Pubkey,
+    keyed_accounts: &[KeyedAccount],
+    instruction_data: &[u8],
+    _invoke_context: &mut dyn InvokeContext,
+) -> Result<(), InstructionError> {
+    let keyed_accounts_iter = &mut keyed_accounts.iter();
+
+    match limited_deserialize(instruction_data)? {
+        AddressLookupTableInstruction::InitializeLookupTable { authority } => {
+            let table_account = next_keyed_account(keyed_accounts_iter)?;
+            let clock: Clock = from_keyed_account(next_keyed_account(keyed_accounts_iter)?)?;
+            if table_account.owner()? != *program_id {
+                return Err(InstructionError::IncorrectProgramId);
+            }
+            if let AddressLookupTableState::LookupTable(_) = table_account.state()? {
+                return Err(InstructionError::AccountAlreadyInitialized);
+            }
+            set_lookup_table(
+                table_account,
+                AddressLookupTable {
+                    meta: LookupTableMeta {
+                        authority: Some(authority),
+                        last_extended_slot: clock.slot,
+                        last_extended_slot_start_index: 0,
+                    },
+                    addresses: vec![],
+                },
+            )
+        }
+        AddressLookupTableInstruction::ExtendLookupTable { new_addresses } => {
+            let table_account = next_keyed_account(keyed_accounts_iter)?;
+            let authority_account = next_keyed_account(keyed_accounts_iter)?;
+            let clock: Clock = from_keyed_account(next_keyed_account(keyed_accounts_iter)?)?;
+            let mut table = get_lookup_table(program_id, table_account)?;
+            check_authority(&table, authority_account)?;
+
+            if new_addresses.is_empty() {
+                return Err(InstructionError::InvalidInstructionData);
+            }
+            if table.addresses.len() + new_addresses.len() > LOOKUP_TABLE_MAX_ADDRESSES {
+                return Err(InstructionError::InvalidInstructionData);
+            }
+            if table.meta.last_extended_slot != clock.slot {
+                table.meta.last_extended_slot = clock.slot;
+                table.meta.last_extended_slot_start_index = table.addresses.len() as u8;
+            }
+            table.addresses.extend(new_addresses);
+            set_lookup_table(table_account, table)
+        }
+        AddressLookupTableInstruction::FreezeLookupTable => {
+            let table_account = next_keyed_account(keyed_accounts_iter)?;
+            let authority_account = next_keyed_account(keyed_accounts_iter)?;
+            let mut table = get_lookup_table(program_id, table_account)?;
+            check_authority(&table, authority_account)?;
+            if table.addresses.is_empty() {
+                return Err(InstructionError::InvalidInstructionData);
+            }
+            table.meta.authority = None;
+            set_lookup_table(table_account, table)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::{
+        account::{self, Account},
+        account_utils::StateMut,
+        address_lookup_table::id,
+        process_instruction::MockInvokeContext,
+        sysvar,
+    };
+    use std::cell::RefCell;
+
+    fn process(
+        instruction: &AddressLookupTableInstruction,
+        keyed_accounts: &[KeyedAccount],
+    ) -> Result<(), InstructionError> {
+        process_instruction(
+            &id(),
+            keyed_accounts,
+            &bincode::serialize(instruction).unwrap(),
+            &mut MockInvokeContext::default(),
+        )
+    }
+
+    fn clock_account(slot: u64) -> RefCell<Account> {
+        RefCell::new(account::create_account(
+            &Clock {
+                slot,
+                ..Clock::default()
+            },
+            1,
+        ))
+    }
+
+    fn get_table(table_account: &RefCell<Account>) -> AddressLookupTable {
+        match table_account.borrow().state().unwrap() {
+            AddressLookupTableState::LookupTable(table) => table,
+            AddressLookupTableState::Uninitialized => panic!("uninitialized"),
+        }
+    }
+
+    #[test]
+    fn test_lookup_table_lifecycle() {
+        let table_key = Pubkey::new_unique();
+        let table_account = RefCell::new(Account::new(1, AddressLookupTable::space(3), &id()));
+        let authority = Pubkey::new_unique();
+        let authority_account = RefCell::new(Account::default());
+        let clock_key = sysvar::clock::id();
+
+        let clock = clock_account(1);
+        assert_eq!(
+            process(
+                &AddressLookupTableInstruction::InitializeLookupTable { authority },
+                &[
+                    KeyedAccount::new(&table_key, false, &table_account),
+                    KeyedAccount::new_readonly(&clock_key, false, &clock),
+                ],
+            ),
+            Ok(())
+        );
+        assert_eq!(
+            process(
+                &AddressLookupTableInstruction::InitializeLookupTable { authority },
+                &[
+                    KeyedAccount::new(&table_key, false, &table_account),
+                    KeyedAccount::new_readonly(&clock_key, false, &clock),
+                ],
+            ),
+            Err(InstructionError::AccountAlreadyInitialized)
+        );
+
+        let extend = |addresses: Vec<Pubkey>, is_signer: bool, slot: u64| {
+            process(
+                &AddressLookupTableInstruction::ExtendLookupTable {
+                    new_addresses: addresses,
+                },
+                &[
+                    KeyedAccount::new(&table_key, false, &table_account),
+                    KeyedAccount::new_readonly(&authority, is_signer, &authority_account),
+                    KeyedAccount::new_readonly(&clock_key, false, &clock_account(slot)),
+                ],
+            )
+        };
+
+        let addresses: Vec<_> = (0..4).map(|_| Pubkey::new_unique()).collect();
+        assert_eq!(
+            extend(addresses[..1].to_vec(), false, 2),
+            Err(InstructionError::MissingRequiredSignature)
+        );
+        assert_eq!(extend(addresses[..1].to_vec(), true, 2), Ok(()));
+        assert_eq!(extend(addresses[1..2].to_vec(), true, 3), Ok(()));
+        assert_eq!(extend(addresses[2..3].to_vec(), true, 3), Ok(()));
+
+        // Addresses appended in slot 3 aren't active until slot 4
+        let table = get_table(&table_account);
+        assert_eq!(table.addresses, addresses[..3].to_vec());
+        assert_eq!(table.meta.last_extended_slot, 3);
+        assert_eq!(table.meta.last_extended_slot_start_index, 1);
+        assert_eq!(table.active_addresses(3), &addresses[..1]);
+        assert_eq!(table.active_addresses(4), &addresses[..3]);
+
+        // The account was sized for 3 addresses
+        assert_eq!(
+            extend(addresses[3..].to_vec(), true, 4),
+            Err(InstructionError::AccountDataTooSmall)
+        );
+
+        assert_eq!(
+            process(
+                &AddressLookupTableInstruction::FreezeLookupTable,
+                &[
+                    KeyedAccount::new(&table_key, false, &table_account),
+                    KeyedAccount::new_readonly(&authority, true, &authority_account),
+                ],
+            ),
+            Ok(())
+        );
+        assert_eq!(get_table(&table_account).meta.authority, None);
+        assert_eq!(
+            extend(addresses[3..].to_vec(), true, 4),
+            Err(InstructionError::Immutable)
+        );
+    }
+
+    #[test]
+    fn test_extend_wrong_authority() {
+        let table_key = Pubkey::new_unique();
+        let table_account = RefCell::new(Account::new(1, AddressLookupTable::space(1), &id()));
+        table_account
+            .borrow_mut()
+            .set_state(&AddressLookupTableState::LookupTable(AddressLookupTable {
+                meta: LookupTableMeta {
+                    authority: Some(Pubkey::new_unique()),
+                    ..LookupTableMeta::default()
+                },
+                addresses: vec![],
+            }))
+            .unwrap();
+        let other_authority = Pubkey::new_unique();
+        let authority_account = RefCell::new(Account::default());
+        let clock_key = sysvar::clock::id();
+        let clock = clock_account(1);
+
+        assert_eq!(
+            process(
+                &AddressLookupTableInstruction::ExtendLookupTable {
+                    new_addresses: vec![Pubkey::new_unique()],
+                },
+                &[
+                    KeyedAccount::new(&table_key, false, &table_account),
+                    KeyedAccount::new_readonly(&other_authority, true, &authority_account),
+                    KeyedAccount::new_readonly(&clock_key, false, &clock),
+                ],
+            ),
+            Err(InstructionError::IncorrectAuthority)
+        );
+    }
+}
@@ -8,7 +8,7 @@ use crate::{
         TransactionLoaders,
     },
     accounts_db::{ErrorCounters, SnapshotStorages},
-    accounts_index::Ancestors,
+    accounts_index::{AccountSecondaryIndexes, Ancestors, IndexKey},
     blockhash_queue::BlockhashQueue,
     builtins::{self, ActivationType},
     epoch_stakes::{EpochStakes, NodeVoteAccounts},
@@ -16,6 +16,7 @@ use crate::{
     log_collector::LogCollector,
     message_processor::{Executors, MessageProcessor},
     rent_collector::RentCollector,
+    replay_tracer::{self, InstructionAccountWrite, ReplayTraceEvent, ReplayTracer},
     stakes::Stakes,
     status_cache::{SlotDelta, StatusCache},
     system_instruction_processor::{get_system_account_kind, SystemAccountKind},
@@ -46,7 +47,10 @@ use solana_sdk::{
     incinerator,
     inflation::Inflation,
     instruction::CompiledInstruction,
-    message::Message,
+    message::{
+        v0::{self, LoadedAddresses},
+        Message,
+    },
     native_loader,
     native_token::sol_to_lamports,
     nonce, nonce_account,
@@ -62,7 +66,7 @@ use solana_sdk::{
         calculate_stake_weighted_timestamp, EstimateType, DEPRECATED_TIMESTAMP_SLOT_RANGE,
     },
     system_transaction,
-    sysvar::{self},
+    sysvar::{self, Sysvar},
     timing::years_as_slots,
     transaction::{self, Result, Transaction, TransactionError},
 };
@@ -89,6 +93,18 @@ use std::{
 // Partial SPL Token v2.0.x declarations inlined to avoid an external dependency on the spl-token crate
 pub mod inline_spl_token_v2_0 {
     solana_sdk::declare_id!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+
+    /*
+        Account {
+            mint: Pubkey,
+            owner: Pubkey,
+            ...
+        }
+    */
+    pub const SPL_TOKEN_ACCOUNT_MINT_OFFSET: usize = 0;
+    pub const SPL_TOKEN_ACCOUNT_OWNER_OFFSET: usize = 32;
+    pub const SPL_TOKEN_ACCOUNT_LEN: usize = 165;
+
     pub mod native_mint {
         solana_sdk::declare_id!("So11111111111111111111111111111111111111112");
 
@@ -114,7 +130,7 @@ pub const SECONDS_PER_YEAR: f64 = 365.25 * 24.0 * 60.0 * 60.0;
 pub const MAX_LEADER_SCHEDULE_STAKES: Epoch = 5;
 
 type BankStatusCache = StatusCache<Result<()>>;
-#[frozen_abi(digest = "4nZ6EdivqQPcnrnXisbjuTjpcUBoHLDEQWvbZQDCoQQR")]
+#[frozen_abi(digest = "Gw1qeNkefQntcR2cWbeFgEHtV8kP6E2tvFR5w8tt1FaX")]
 pub type BankSlotDelta = SlotDelta<Result<()>>;
 type TransactionAccountRefCells = Vec<Rc<RefCell<Account>>>;
 type TransactionLoaderRefCells = Vec<Vec<(Pubkey, RefCell<Account>)>>;
@@ -244,6 +260,19 @@ impl AbiExample for CachedExecutors {
     }
 }
 
+/// The replay tracer a bank reports to, if any
+#[derive(Clone, Debug, Default)]
+struct OptionalReplayTracer(Option<ReplayTracer>);
+
+#[cfg(RUSTC_WITH_SPECIALIZATION)]
+impl AbiExample for OptionalReplayTracer {
+    fn example() -> Self {
+        // Delegate AbiExample impl to Default as Arc<dyn ReplayTracerInterface> can't provide
+        // an example. This is safe because the replay tracer is never serialized.
+        Self::default()
+    }
+}
+
 impl Clone for CachedExecutors {
     fn clone(&self) -> Self {
         let mut executors = HashMap::new();
@@ -405,6 +434,29 @@ pub type InnerInstructionsList = Vec<InnerInstructions>;
 /// A list of log messages emitted during a transaction
 pub type TransactionLogMessages = Vec<String>;
 
+/// The compute units consumed by each executed instruction of a transaction
+pub type TransactionComputeUnits = Vec<u64>;
+
+/// What a committed transaction recorded while it was executed
+#[derive(Debug)]
+pub struct TransactionExecutionDetails {
+    pub result: Result<()>,
+    pub logs: TransactionLogMessages,
+    pub units_consumed: TransactionComputeUnits,
+    pub inner_instructions: Option<InnerInstructionsList>,
+}
+
+/// The outcome of running a transaction against a bank without committing it
+#[derive(Debug)]
+pub struct TransactionSimulationResult {
+    pub result: Result<()>,
+    pub logs: TransactionLogMessages,
+    /// The state of every account loaded by the transaction after execution
+    pub post_simulation_accounts: Vec<(Pubkey, Account)>,
+    pub units_consumed: TransactionComputeUnits,
+    pub inner_instructions: Option<InnerInstructionsList>,
+}
+
 #[derive(Serialize, Deserialize, AbiExample, AbiEnumVisitor, Debug, PartialEq)]
 pub enum TransactionLogCollectorFilter {
     All,
@@ -648,6 +700,10 @@ pub struct Bank {
     /// Hash of this Bank's state. Only meaningful after freezing.
     hash: RwLock<Hash>,
 
+    /// Hash of the accounts stored in this Bank's slot, if this Bank was frozen by
+    /// this process rather than loaded from a snapshot
+    accounts_delta_hash: RwLock<Option<Hash>>,
+
     /// Hash of this Bank's parent's state
     parent_hash: Hash,
 
@@ -764,6 +820,9 @@ pub struct Bank {
 
     transaction_debug_keys: Option<Arc<HashSet<Pubkey>>>,
 
+    /// Receives the replay events of this bank and its descendants
+    replay_tracer: RwLock<OptionalReplayTracer>,
+
     // Global configuration for how transaction logs should be collected across all banks
     pub transaction_log_collector_config: Arc<RwLock<TransactionLogCollectorConfig>>,
 
@@ -782,7 +841,14 @@ impl Default for BlockhashQueue {
 
 impl Bank {
     pub fn new(genesis_config: &GenesisConfig) -> Self {
-        Self::new_with_paths(&genesis_config, Vec::new(), &[], None, None)
+        Self::new_with_paths(
+            &genesis_config,
+            Vec::new(),
+            &[],
+            None,
+            None,
+            AccountSecondaryIndexes::default(),
+        )
     }
 
     pub fn new_with_paths(
@@ -791,13 +857,18 @@ impl Bank {
         frozen_account_pubkeys: &[Pubkey],
         debug_keys: Option<Arc<HashSet<Pubkey>>>,
         additional_builtins: Option<&Builtins>,
+        account_indexes: AccountSecondaryIndexes,
     ) -> Self {
         let mut bank = Self::default();
         bank.transaction_debug_keys = debug_keys;
         bank.cluster_type = Some(genesis_config.cluster_type);
         bank.ancestors.insert(bank.slot(), 0);
 
-        bank.rc.accounts = Arc::new(Accounts::new(paths, &genesis_config.cluster_type));
+        bank.rc.accounts = Arc::new(Accounts::new_with_indexes(
+            paths,
+            &genesis_config.cluster_type,
+            account_indexes,
+        ));
         bank.process_genesis_config(genesis_config);
         bank.finish_init(genesis_config, additional_builtins);
 
@@ -899,6 +970,7 @@ impl Bank {
             collector_fees: AtomicU64::new(0),
             ancestors: HashMap::new(),
             hash: RwLock::new(Hash::default()),
+            accounts_delta_hash: RwLock::new(None),
             is_delta: AtomicBool::new(false),
             tick_height: AtomicU64::new(parent.tick_height.load(Relaxed)),
             signature_count: AtomicU64::new(0),
@@ -915,6 +987,7 @@ impl Bank {
             rewards_pool_pubkeys: parent.rewards_pool_pubkeys.clone(),
             cached_executors: RwLock::new((*parent.cached_executors.read().unwrap()).clone()),
             transaction_debug_keys: parent.transaction_debug_keys.clone(),
+            replay_tracer: RwLock::new(parent.replay_tracer.read().unwrap().clone()),
             transaction_log_collector_config: parent.transaction_log_collector_config.clone(),
             transaction_log_collector: Arc::new(RwLock::new(TransactionLogCollector::default())),
             feature_set: parent.feature_set.clone(),
@@ -990,6 +1063,7 @@ impl Bank {
             blockhash_queue: RwLock::new(fields.blockhash_queue),
             ancestors: fields.ancestors,
             hash: RwLock::new(fields.hash),
+            accounts_delta_hash: RwLock::new(None),
             parent_hash: fields.parent_hash,
             parent_slot: fields.parent_slot,
             hard_forks: Arc::new(RwLock::new(fields.hard_forks)),
@@ -1033,6 +1107,7 @@ impl Bank {
                 CachedExecutors::new(MAX_CACHED_EXECUTORS),
             )))),
             transaction_debug_keys: debug_keys,
+            replay_tracer: new(),
             transaction_log_collector_config: new(),
             transaction_log_collector: new(),
             feature_set: new(),
@@ -1135,6 +1210,16 @@ impl Bank {
         *self.hash.read().unwrap()
     }
 
+    pub fn parent_hash(&self) -> Hash {
+        self.parent_hash
+    }
+
+    /// The accounts delta hash that went into this Bank's hash, if it was frozen by
+    /// this process
+    pub fn accounts_delta_hash(&self) -> Option<Hash> {
+        *self.accounts_delta_hash.read().unwrap()
+    }
+
     pub fn is_frozen(&self) -> bool {
         *self.hash.read().unwrap() != Hash::default()
     }
@@ -1165,6 +1250,17 @@ impl Bank {
         let old_account = self.get_sysvar_account(pubkey);
         let new_account = updater(&old_account);
         self.store_account(pubkey, &new_account);
+
+        if let Some(replay_tracer) = self.replay_tracer() {
+            replay_tracer.trace(ReplayTraceEvent::SysvarUpdate {
+                slot: self.slot,
+                pubkey: *pubkey,
+                pre_hash: old_account
+                    .map(|account| replay_tracer::hash_account(pubkey, &account))
+                    .unwrap_or_default(),
+                post_hash: replay_tracer::hash_account(pubkey, &new_account),
+            });
+        }
     }
 
     fn inherit_sysvar_account_balance(&self, old_account: &Option<Account>) -> u64 {
@@ -1176,6 +1272,15 @@ impl Bank {
         (rooted_slot + (unused - 1)) / unused
     }
 
+    /// Overwrite a sysvar account of this bank. Only the account seen by programs changes; the
+    /// bank's own copy of the sysvar (its rent collector or epoch schedule, say) is untouched and
+    /// child banks recompute the account as usual.
+    pub fn set_sysvar_for_tests<T: Sysvar>(&self, sysvar: &T) {
+        self.update_sysvar_account(&T::id(), |account| {
+            create_account(sysvar, self.inherit_sysvar_account_balance(account))
+        });
+    }
+
     pub fn clock(&self) -> sysvar::clock::Clock {
         from_account(&self.get_account(&sysvar::clock::id()).unwrap_or_default())
             .unwrap_or_default()
@@ -1803,12 +1908,28 @@ impl Bank {
             self.distribute_rent();
             self.update_slot_history();
             self.run_incinerator();
+            self.trace_rewards();
 
             // freeze is a one-way trip, idempotent
             *hash = self.hash_internal_state();
         }
     }
 
+    // Rewards are reported once the bank is frozen, when the fee and rent rewards are known
+    fn trace_rewards(&self) {
+        if let Some(replay_tracer) = self.replay_tracer() {
+            for (pubkey, reward_info) in self.rewards.read().unwrap().iter() {
+                replay_tracer.trace(ReplayTraceEvent::Reward {
+                    slot: self.slot,
+                    pubkey: *pubkey,
+                    reward_type: reward_info.reward_type,
+                    lamports: reward_info.lamports,
+                    post_balance: reward_info.post_balance,
+                });
+            }
+        }
+    }
+
     // Should not be called outside of startup, will race with
     // concurrent cleaning logic in AccountsBackgroundService
     pub fn exhaustively_free_unused_resource(&self) {
@@ -2145,6 +2266,24 @@ impl Bank {
             .map_or(Ok(()), |sig| self.get_signature_status(sig).unwrap())
     }
 
+    /// Load the addresses referenced by the message's address table lookups, writable
+    /// addresses first
+    pub fn load_lookup_table_addresses(&self, message: &v0::Message) -> Result<LoadedAddresses> {
+        if !self.versioned_tx_message_enabled() {
+            return Err(TransactionError::UnsupportedVersion);
+        }
+        let mut loaded_addresses = LoadedAddresses::default();
+        for lookup in &message.address_table_lookups {
+            let LoadedAddresses { writable, readonly } = self
+                .rc
+                .accounts
+                .load_lookup_table_addresses(&self.ancestors, self.slot(), lookup)?;
+            loaded_addresses.writable.extend(writable);
+            loaded_addresses.readonly.extend(readonly);
+        }
+        Ok(loaded_addresses)
+    }
+
     pub fn prepare_batch<'a, 'b>(
         &'a self,
         txs: &'b [Transaction],
@@ -2171,20 +2310,27 @@ impl Bank {
     }
 
     /// Run transactions against a frozen bank without committing the results
-    pub fn simulate_transaction(
-        &self,
-        transaction: Transaction,
-    ) -> (Result<()>, TransactionLogMessages) {
+    pub fn simulate_transaction(&self, transaction: Transaction) -> TransactionSimulationResult {
         assert!(self.is_frozen(), "simulation bank must be frozen");
 
+        self.simulate_transaction_unchecked(transaction)
+    }
+
+    /// Run transactions against a bank without committing the results; does not check if the
+    /// bank is frozen, enabling use in single-Bank test frameworks
+    pub fn simulate_transaction_unchecked(
+        &self,
+        transaction: Transaction,
+    ) -> TransactionSimulationResult {
         let txs = &[transaction];
         let batch = self.prepare_simulation_batch(txs);
 
         let (
-            _loaded_accounts,
+            loaded_accounts,
             executed,
-            _inner_instructions,
+            inner_instructions,
             log_messages,
+            compute_units,
             _retryable_transactions,
             _transaction_count,
             _signature_count,
@@ -2194,16 +2340,34 @@ impl Bank {
             // for processing. During forwarding, the transaction could expire if the
             // delay is not accounted for.
             MAX_PROCESSING_AGE - MAX_TRANSACTION_FORWARDING_DELAY,
-            false,
+            true,
             true,
         );
 
-        let transaction_result = executed[0].0.clone().map(|_| ());
-        let log_messages = log_messages
+        let result = executed[0].0.clone().map(|_| ());
+        let logs = log_messages
             .get(0)
             .map_or(vec![], |messages| messages.to_vec());
-
-        (transaction_result, log_messages)
+        let post_simulation_accounts = match &loaded_accounts[0].0 {
+            Ok((accounts, _loaders, _rents)) => txs[0]
+                .message
+                .account_keys
+                .iter()
+                .cloned()
+                .zip(accounts.iter().cloned())
+                .collect(),
+            Err(_) => vec![],
+        };
+        let units_consumed = compute_units.into_iter().next().unwrap_or_default();
+        let inner_instructions = inner_instructions.into_iter().next().flatten();
+
+        TransactionSimulationResult {
+            result,
+            logs,
+            post_simulation_accounts,
+            units_consumed,
+            inner_instructions,
+        }
     }
 
     pub fn unlock_accounts(&self, batch: &mut TransactionBatch) {
@@ -2601,6 +2765,7 @@ impl Bank {
         Vec<TransactionProcessResult>,
         Vec<Option<InnerInstructionsList>>,
         Vec<TransactionLogMessages>,
+        Vec<TransactionComputeUnits>,
         Vec<usize>,
         u64,
         u64,
@@ -2644,15 +2809,27 @@ impl Bank {
         let mut inner_instructions: Vec<Option<InnerInstructionsList>> =
             Vec::with_capacity(txs.len());
         let mut transaction_log_messages = Vec::with_capacity(txs.len());
+        let mut transaction_compute_units = Vec::with_capacity(txs.len());
         let bpf_compute_budget = self
             .bpf_compute_budget
             .unwrap_or_else(|| BpfComputeBudget::new(&self.feature_set));
+        let replay_tracer = self.replay_tracer();
 
         let executed: Vec<TransactionProcessResult> = loaded_accounts
             .iter_mut()
             .zip(OrderedIterator::new(txs, batch.iteration_order()))
             .map(|(accs, (_, tx))| match accs {
-                (Err(e), hash_age_kind) => (Err(e.clone()), hash_age_kind.clone()),
+                (Err(e), hash_age_kind) => {
+                    transaction_compute_units.push(vec![]);
+                    if let Some(replay_tracer) = &replay_tracer {
+                        replay_tracer.trace(ReplayTraceEvent::Transaction {
+                            slot: self.slot,
+                            signature: tx.signatures[0],
+                            result: Err(e.clone()),
+                        });
+                    }
+                    (Err(e.clone()), hash_age_kind.clone())
+                }
                 (Ok((accounts, loaders, _rents)), hash_age_kind) => {
                     signature_count += u64::from(tx.message().header.num_required_signatures);
 
@@ -2676,6 +2853,9 @@ impl Bank {
                         None
                     };
 
+                    let mut account_writes = replay_tracer.as_ref().map(|_| vec![]);
+
+                    let mut compute_units = Vec::with_capacity(tx.message.instructions.len());
                     let process_result = self.message_processor.process_message(
                         tx.message(),
                         &loader_refcells,
@@ -2684,9 +2864,21 @@ impl Bank {
                         log_collector.clone(),
                         executors.clone(),
                         instruction_recorders.as_deref(),
+                        &mut compute_units,
+                        account_writes.as_mut(),
                         self.feature_set.clone(),
                         bpf_compute_budget,
                     );
+                    transaction_compute_units.push(compute_units);
+
+                    if let Some(replay_tracer) = &replay_tracer {
+                        self.trace_transaction(
+                            replay_tracer,
+                            tx,
+                            &process_result,
+                            account_writes.unwrap_or_default(),
+                        );
+                    }
 
                     if enable_log_recording {
                         let log_messages: TransactionLogMessages =
@@ -2710,7 +2902,11 @@ impl Bank {
                         loader_refcells,
                     );
 
-                    self.update_executors(executors);
+                    // Only cache executors of successful transactions, a failed program
+                    // upgrade must not replace the executor of the deployed program
+                    if process_result.is_ok() {
+                        self.update_executors(executors);
+                    }
 
                     if let Err(TransactionError::InstructionError(_, _)) = &process_result {
                         error_counters.instruction_error += 1;
@@ -2809,12 +3005,38 @@ impl Bank {
             executed,
             inner_instructions,
             transaction_log_messages,
+            transaction_compute_units,
             retryable_txs,
             tx_count,
             signature_count,
         )
     }
 
+    fn trace_transaction(
+        &self,
+        replay_tracer: &ReplayTracer,
+        tx: &Transaction,
+        result: &Result<()>,
+        account_writes: Vec<InstructionAccountWrite>,
+    ) {
+        let signature = tx.signatures[0];
+        replay_tracer.trace(ReplayTraceEvent::Transaction {
+            slot: self.slot,
+            signature,
+            result: result.clone(),
+        });
+        for account_write in account_writes {
+            replay_tracer.trace(ReplayTraceEvent::AccountWrite {
+                slot: self.slot,
+                signature,
+                instruction_index: account_write.instruction_index,
+                pubkey: account_write.pubkey,
+                pre_hash: account_write.pre_hash,
+                post_hash: account_write.post_hash,
+            });
+        }
+    }
+
     fn filter_program_errors_and_collect_fee(
         &self,
         txs: &[Transaction],
@@ -2826,6 +3048,7 @@ impl Bank {
 
         let fee_config = FeeConfig {
             secp256k1_program_enabled: self.secp256k1_program_enabled(),
+            compute_budget_program_enabled: self.compute_budget_program_enabled(),
         };
 
         let results = OrderedIterator::new(txs, iteration_order)
@@ -3160,15 +3383,25 @@ impl Bank {
 
         // parallelize?
         let mut rent = 0;
+        let replay_tracer = self.replay_tracer();
         for (pubkey, mut account) in accounts {
-            rent += self.rent_collector.collect_from_existing_account(
+            let collected = self.rent_collector.collect_from_existing_account(
                 &pubkey,
                 &mut account,
                 self.cumulative_rent_related_fixes_enabled(),
             );
+            rent += collected;
             // Store all of them unconditionally to purge old AppendVec,
             // even if collected rent is 0 (= not updated).
             self.store_account(&pubkey, &account);
+            if let Some(replay_tracer) = &replay_tracer {
+                replay_tracer.trace(ReplayTraceEvent::RentCollection {
+                    slot: self.slot,
+                    pubkey,
+                    rent: collected,
+                    post_hash: replay_tracer::hash_account(&pubkey, &account),
+                });
+            }
         }
         self.collected_rent.fetch_add(rent, Relaxed);
 
@@ -3523,6 +3756,7 @@ impl Bank {
             inner_instructions,
             transaction_logs,
             _,
+            _,
             tx_count,
             signature_count,
         ) = self.load_and_execute_transactions(
@@ -3561,6 +3795,46 @@ impl Bank {
             .fee_collection_results
     }
 
+    /// Process a Transaction, recording its logs, inner instructions and compute units. Returns
+    /// an error if the transaction could not be executed, for example because its blockhash
+    /// expired or its accounts failed to load.
+    pub fn process_transaction_with_metadata(
+        &self,
+        tx: Transaction,
+    ) -> Result<TransactionExecutionDetails> {
+        let txs = vec![tx];
+        let batch = self.prepare_batch(&txs, None);
+        let (
+            mut loaded_accounts,
+            executed,
+            inner_instructions,
+            transaction_logs,
+            compute_units,
+            _retryable_transactions,
+            tx_count,
+            signature_count,
+        ) = self.load_and_execute_transactions(&batch, MAX_PROCESSING_AGE, true, true);
+        if let Err(err) = &loaded_accounts[0].0 {
+            return Err(err.clone());
+        }
+
+        let results = self.commit_transactions(
+            batch.transactions(),
+            batch.iteration_order(),
+            &mut loaded_accounts,
+            &executed,
+            tx_count,
+            signature_count,
+        );
+        results.fee_collection_results[0].clone()?;
+        Ok(TransactionExecutionDetails {
+            result: executed[0].0.clone(),
+            logs: transaction_logs.into_iter().next().unwrap_or_default(),
+            units_consumed: compute_units.into_iter().next().unwrap_or_default(),
+            inner_instructions: inner_instructions.into_iter().next().flatten(),
+        })
+    }
+
     /// Create, sign, and process a Transaction from `keypair` to `to` of
     /// `n` lamports where `blockhash` is the last Entry ID observed by the client.
     pub fn transfer(&self, n: u64, keypair: &Keypair, to: &Pubkey) -> Result<Signature> {
@@ -3716,6 +3990,22 @@ impl Bank {
         self.bpf_compute_budget = bpf_compute_budget;
     }
 
+    /// Report the replay events of this bank, and of the banks descending from it, to
+    /// `replay_tracer`
+    pub fn set_replay_tracer(&self, replay_tracer: ReplayTracer) {
+        *self.replay_tracer.write().unwrap() = OptionalReplayTracer(Some(replay_tracer));
+    }
+
+    fn replay_tracer(&self) -> Option<ReplayTracer> {
+        self.replay_tracer
+            .read()
+            .unwrap()
+            .0
+            .as_ref()
+            .filter(|replay_tracer| replay_tracer.trace_slot(self.slot))
+            .cloned()
+    }
+
     pub fn hard_forks(&self) -> Arc<RwLock<HardForks>> {
         self.hard_forks.clone()
     }
@@ -3751,6 +4041,16 @@ impl Bank {
             .load_by_program(&self.ancestors, program_id)
     }
 
+    pub fn get_filtered_indexed_accounts<F: Fn(&Account) -> bool>(
+        &self,
+        index_key: &IndexKey,
+        filter: F,
+    ) -> Vec<(Pubkey, Account)> {
+        self.rc
+            .accounts
+            .load_by_index_key_with_filter(&self.ancestors, index_key, filter)
+    }
+
     pub fn get_all_accounts_with_modified_slots(&self) -> Vec<(Pubkey, Account, Slot)> {
         self.rc.accounts.load_all(&self.ancestors)
     }
@@ -3788,6 +4088,29 @@ impl Bank {
         self.rc.accounts.load_by_program_slot(self.slot(), None)
     }
 
+    /// The accounts written by this bank along with their hashes, from which its accounts delta
+    /// hash is computed
+    pub fn get_accounts_delta_hash_inputs(&self) -> Vec<(Pubkey, Account, Hash)> {
+        self.rc
+            .accounts
+            .accounts_db
+            .get_accounts_delta_hash_inputs(self.slot())
+    }
+
+    /// Returns the current max root, and keeps cleaning from reclaiming the writes of any slot
+    /// rooted after it until it is passed to `release_max_root()`
+    pub fn hold_max_root(&self) -> Slot {
+        self.rc.accounts.accounts_db.accounts_index.hold_max_root()
+    }
+
+    pub fn release_max_root(&self, max_root: Slot) {
+        self.rc
+            .accounts
+            .accounts_db
+            .accounts_index
+            .release_max_root(max_root)
+    }
+
     pub fn get_account_modified_since_parent(&self, pubkey: &Pubkey) -> Option<(Account, Slot)> {
         let just_self: Ancestors = vec![(self.slot(), 0)].into_iter().collect();
         if let Some((account, slot)) = self.rc.accounts.load_slow(&just_self, pubkey) {
@@ -3866,6 +4189,7 @@ impl Bank {
     fn hash_internal_state(&self) -> Hash {
         // If there are no accounts, return the hash of the previous state and the latest blockhash
         let accounts_delta_hash = self.rc.accounts.bank_hash_info_at(self.slot());
+        *self.accounts_delta_hash.write().unwrap() = Some(accounts_delta_hash.hash);
         let mut signature_count_buf = [0u8; 8];
         LittleEndian::write_u64(&mut signature_count_buf[..], self.signature_count() as u64);
 
@@ -4272,6 +4596,16 @@ impl Bank {
             .is_active(&feature_set::secp256k1_program_enabled::id())
     }
 
+    pub fn compute_budget_program_enabled(&self) -> bool {
+        self.feature_set
+            .is_active(&feature_set::compute_budget_program::id())
+    }
+
+    pub fn versioned_tx_message_enabled(&self) -> bool {
+        self.feature_set
+            .is_active(&feature_set::versioned_tx_message_enabled::id())
+    }
+
     pub fn no_overflow_rent_distribution_enabled(&self) -> bool {
         self.feature_set
             .is_active(&feature_set::no_overflow_rent_distribution::id())
@@ -4506,7 +4840,7 @@ pub fn goto_end_of_slot(bank: &mut Bank) {
     }
 }
 
-fn is_simple_vote_transaction(transaction: &Transaction) -> bool {
+pub fn is_simple_vote_transaction(transaction: &Transaction) -> bool {
     if transaction.message.instructions.len() == 1 {
         let instruction = &transaction.message.instructions[0];
         let program_pubkey =
@@ -4514,7 +4848,10 @@ fn is_simple_vote_transaction(transaction: &Transaction) -> bool {
         if program_pubkey == solana_vote_program::id() {
             if let Ok(vote_instruction) = limited_deserialize::<VoteInstruction>(&instruction.data)
             {
-                return matches!(vote_instruction, VoteInstruction::Vote(_) | VoteInstruction::VoteSwitch(_, _));
+                return matches!(
+                    vote_instruction,
+                    VoteInstruction::Vote(_) | VoteInstruction::VoteSwitch(_, _)
+                );
             }
         }
     }
@@ -4525,19 +4862,24 @@ fn is_simple_vote_transaction(transaction: &Transaction) -> bool {
 pub(crate) mod tests {
     use super::*;
     use crate::{
-        accounts_index::{AccountMap, Ancestors, ITER_BATCH_SIZE},
+        accounts_index::{AccountIndex, AccountMap, Ancestors, ITER_BATCH_SIZE},
         genesis_utils::{
             activate_all_features, bootstrap_validator_stake_lamports,
             create_genesis_config_with_leader, create_genesis_config_with_vote_accounts,
             GenesisConfigInfo, ValidatorVoteKeypairs,
         },
         native_loader::NativeLoaderError,
+        replay_tracer::ReplayTracerInterface,
         status_cache::MAX_CACHE_ENTRIES,
     };
     use crossbeam_channel::bounded;
     use solana_sdk::{
         account_utils::StateMut,
+        address_lookup_table::{
+            self, AddressLookupTable, AddressLookupTableState, LookupTableMeta,
+        },
         clock::{DEFAULT_SLOTS_PER_EPOCH, DEFAULT_TICKS_PER_SLOT},
+        compute_budget,
         epoch_schedule::MINIMUM_SLOTS_PER_EPOCH,
         feature::Feature,
         genesis_config::create_genesis_config,
@@ -6998,6 +7340,142 @@ pub(crate) mod tests {
         );
     }
 
+    #[test]
+    fn test_bank_compute_budget_fee() {
+        let leader = solana_sdk::pubkey::new_rand();
+        let GenesisConfigInfo {
+            mut genesis_config,
+            mint_keypair,
+            ..
+        } = create_genesis_config_with_leader(1_000_000, &leader, 3);
+        genesis_config.fee_rate_governor = FeeRateGovernor::new(4, 0);
+        let bank = Bank::new(&genesis_config);
+        assert!(bank.compute_budget_program_enabled());
+        let (blockhash, fee_calculator) = bank.last_blockhash_with_fee_calculator();
+
+        // The requested units are paid for on top of the signature fee
+        let key = solana_sdk::pubkey::new_rand();
+        let message = Message::new(
+            &[
+                compute_budget::request_units(1_000, 2),
+                system_instruction::transfer(&mint_keypair.pubkey(), &key, 1),
+            ],
+            Some(&mint_keypair.pubkey()),
+        );
+        let tx = Transaction::new(&[&mint_keypair], message, blockhash);
+        let initial_balance = bank.get_balance(&mint_keypair.pubkey());
+        assert_eq!(bank.process_transaction(&tx), Ok(()));
+        assert_eq!(
+            bank.get_balance(&mint_keypair.pubkey()),
+            initial_balance - 1 - fee_calculator.lamports_per_signature - 2_000
+        );
+
+        // Requesting more than the maximum fails, but the fee is still collected
+        let message = Message::new(
+            &[compute_budget::request_units(
+                compute_budget::MAX_UNITS + 1,
+                0,
+            )],
+            Some(&mint_keypair.pubkey()),
+        );
+        let tx = Transaction::new(&[&mint_keypair], message, blockhash);
+        let initial_balance = bank.get_balance(&mint_keypair.pubkey());
+        assert_eq!(
+            bank.process_transaction(&tx),
+            Err(TransactionError::InstructionError(
+                0,
+                InstructionError::InvalidInstructionData
+            ))
+        );
+        assert_eq!(
+            bank.get_balance(&mint_keypair.pubkey()),
+            initial_balance - fee_calculator.lamports_per_signature
+        );
+    }
+
+    #[test]
+    fn test_bank_address_table_lookups() {
+        let leader = solana_sdk::pubkey::new_rand();
+        let GenesisConfigInfo {
+            genesis_config,
+            mint_keypair,
+            ..
+        } = create_genesis_config_with_leader(1_000_000, &leader, 3);
+        let bank = Bank::new(&genesis_config);
+        assert!(bank.versioned_tx_message_enabled());
+
+        // The second address was appended in the current slot, so it can't be looked up yet
+        let table_key = solana_sdk::pubkey::new_rand();
+        let addresses = vec![
+            solana_sdk::pubkey::new_rand(),
+            solana_sdk::pubkey::new_rand(),
+        ];
+        let table = AddressLookupTableState::LookupTable(AddressLookupTable {
+            meta: LookupTableMeta {
+                authority: None,
+                last_extended_slot: bank.slot(),
+                last_extended_slot_start_index: 1,
+            },
+            addresses: addresses.clone(),
+        });
+        let mut table_account = Account::new(
+            1,
+            AddressLookupTable::space(addresses.len()),
+            &address_lookup_table::id(),
+        );
+        table_account.set_state(&table).unwrap();
+        bank.store_account(&table_key, &table_account);
+
+        let transfer_message = |to: &Pubkey, table_key: &Pubkey| {
+            v0::Message::new_with_address_table_lookups(
+                &[system_instruction::transfer(&mint_keypair.pubkey(), to, 1)],
+                Some(&mint_keypair.pubkey()),
+                &[(*table_key, addresses.clone())],
+            )
+        };
+
+        let message = transfer_message(&addresses[0], &table_key);
+        assert_eq!(message.account_keys.len(), 2);
+        assert_eq!(
+            bank.load_lookup_table_addresses(&message),
+            Ok(LoadedAddresses {
+                writable: vec![addresses[0]],
+                readonly: vec![],
+            })
+        );
+
+        assert_eq!(
+            bank.load_lookup_table_addresses(&transfer_message(&addresses[1], &table_key)),
+            Err(TransactionError::InvalidAddressLookupTableIndex)
+        );
+        assert_eq!(
+            bank.load_lookup_table_addresses(&transfer_message(
+                &addresses[0],
+                &solana_sdk::pubkey::new_rand()
+            )),
+            Err(TransactionError::AddressLookupTableNotFound)
+        );
+
+        // Lookup tables must be owned by the address lookup table program
+        let not_a_table_key = solana_sdk::pubkey::new_rand();
+        table_account.owner = system_program::id();
+        bank.store_account(&not_a_table_key, &table_account);
+        assert_eq!(
+            bank.load_lookup_table_addresses(&transfer_message(&addresses[0], &not_a_table_key)),
+            Err(TransactionError::InvalidAddressLookupTableOwner)
+        );
+
+        // The addresses become active in the next slot
+        let bank = Bank::new_from_parent(&Arc::new(bank), &leader, 1);
+        assert_eq!(
+            bank.load_lookup_table_addresses(&transfer_message(&addresses[1], &table_key)),
+            Ok(LoadedAddresses {
+                writable: vec![addresses[1]],
+                readonly: vec![],
+            })
+        );
+    }
+
     #[test]
     fn test_filter_program_errors_and_collect_fee() {
         let leader = solana_sdk::pubkey::new_rand();
@@ -7289,6 +7767,58 @@ pub(crate) mod tests {
             .unwrap();
     }
 
+    #[test]
+    fn test_bank_process_transaction_with_metadata() {
+        let (genesis_config, mint_keypair) = create_genesis_config(10_000);
+        let key1 = Keypair::new();
+        let bank = Bank::new(&genesis_config);
+
+        let tx =
+            system_transaction::transfer(&mint_keypair, &key1.pubkey(), 1, bank.last_blockhash());
+        let details = bank.process_transaction_with_metadata(tx.clone()).unwrap();
+        assert_eq!(details.result, Ok(()));
+        assert_eq!(details.logs.len(), 2);
+        assert_eq!(details.units_consumed.len(), 1);
+        assert_eq!(bank.get_balance(&key1.pubkey()), 1);
+
+        // The duplicate never executes
+        assert_eq!(
+            bank.process_transaction_with_metadata(tx).unwrap_err(),
+            TransactionError::DuplicateSignature
+        );
+
+        // A failed instruction still commits the fee and returns its logs
+        let tx =
+            system_transaction::transfer(&key1, &mint_keypair.pubkey(), 2, bank.last_blockhash());
+        let details = bank.process_transaction_with_metadata(tx).unwrap();
+        assert_eq!(
+            details.result,
+            Err(TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(1)
+            ))
+        );
+        assert!(!details.logs.is_empty());
+    }
+
+    #[test]
+    fn test_bank_set_sysvar_for_tests() {
+        let (genesis_config, _mint_keypair) = create_genesis_config(1);
+        let bank = Arc::new(Bank::new(&genesis_config));
+
+        let expected_clock = sysvar::clock::Clock {
+            unix_timestamp: 1_000_000,
+            ..bank.clock()
+        };
+        bank.set_sysvar_for_tests(&expected_clock);
+        assert_eq!(bank.clock(), expected_clock);
+
+        // Child banks recompute the clock from their own slot
+        let child = new_from_parent(&bank);
+        assert_eq!(child.clock().slot, 1);
+        assert_ne!(child.clock().unix_timestamp, expected_clock.unix_timestamp);
+    }
+
     fn new_from_parent(parent: &Arc<Bank>) -> Bank {
         Bank::new_from_parent(parent, &Pubkey::default(), parent.slot() + 1)
     }
@@ -8184,6 +8714,56 @@ pub(crate) mod tests {
         assert_eq!(bank3.get_program_accounts(&program_id).len(), 2);
     }
 
+    #[test]
+    fn test_get_filtered_indexed_accounts() {
+        let (genesis_config, _mint_keypair) = create_genesis_config(500);
+        let mut account_indexes = AccountSecondaryIndexes::default();
+        account_indexes.insert(AccountIndex::ProgramId);
+        let bank = Arc::new(Bank::new_with_paths(
+            &genesis_config,
+            Vec::new(),
+            &[],
+            None,
+            None,
+            account_indexes,
+        ));
+
+        let address = Pubkey::new_unique();
+        let program_id = Pubkey::new_unique();
+        let account = Account::new(1, 0, &program_id);
+        bank.store_account(&address, &account);
+
+        let indexed_accounts =
+            bank.get_filtered_indexed_accounts(&IndexKey::ProgramId(program_id), |_| true);
+        assert_eq!(indexed_accounts, vec![(address, account)]);
+
+        // Even though the account is re-stored in the bank (and the index) under a new program id,
+        // it is still present in the index under the original program id as well. This
+        // demonstrates the need for a redundant post-processing filter.
+        let another_program_id = Pubkey::new_unique();
+        let new_account = Account::new(1, 0, &another_program_id);
+        let bank = Arc::new(new_from_parent(&bank));
+        bank.store_account(&address, &new_account);
+        let indexed_accounts =
+            bank.get_filtered_indexed_accounts(&IndexKey::ProgramId(program_id), |_| true);
+        assert_eq!(indexed_accounts, vec![(address, new_account.clone())]);
+        let indexed_accounts =
+            bank.get_filtered_indexed_accounts(&IndexKey::ProgramId(another_program_id), |_| true);
+        assert_eq!(indexed_accounts, vec![(address, new_account.clone())]);
+
+        // Post-processing filter
+        let indexed_accounts = bank
+            .get_filtered_indexed_accounts(&IndexKey::ProgramId(program_id), |account| {
+                account.owner == program_id
+            });
+        assert!(indexed_accounts.is_empty());
+        let indexed_accounts = bank
+            .get_filtered_indexed_accounts(&IndexKey::ProgramId(another_program_id), |account| {
+                account.owner == another_program_id
+            });
+        assert_eq!(indexed_accounts, vec![(address, new_account)]);
+    }
+
     #[test]
     fn test_status_cache_ancestors() {
         solana_logger::setup();
@@ -11130,4 +11710,82 @@ pub(crate) mod tests {
         }
         assert_eq!(bank.get_inflation_num_slots(), 2 * slots_per_epoch);
     }
+
+    #[derive(Debug)]
+    struct TestReplayTracer {
+        traced_slot: Slot,
+        events: RwLock<Vec<ReplayTraceEvent>>,
+    }
+
+    impl ReplayTracerInterface for TestReplayTracer {
+        fn trace_slot(&self, slot: Slot) -> bool {
+            slot == self.traced_slot
+        }
+
+        fn trace(&self, event: ReplayTraceEvent) {
+            self.events.write().unwrap().push(event);
+        }
+    }
+
+    #[test]
+    fn test_replay_tracer() {
+        let (genesis_config, mint_keypair) = create_genesis_config(10_000);
+        let bank0 = Arc::new(Bank::new(&genesis_config));
+        let replay_tracer = Arc::new(TestReplayTracer {
+            traced_slot: 1,
+            events: RwLock::new(vec![]),
+        });
+        bank0.set_replay_tracer(replay_tracer.clone());
+
+        // Slots other than the traced one are not reported
+        let to = solana_sdk::pubkey::new_rand();
+        bank0.transfer(1, &mint_keypair, &to).unwrap();
+        bank0.freeze();
+        assert!(replay_tracer.events.read().unwrap().is_empty());
+
+        // Child banks inherit the tracer
+        let bank1 = Bank::new_from_parent(&bank0, &Pubkey::default(), 1);
+        let signature = bank1.transfer(2, &mint_keypair, &to).unwrap();
+        bank1.freeze();
+
+        let events = replay_tracer.events.read().unwrap();
+        assert!(events.iter().all(|event| event.slot() == 1));
+        assert!(events.iter().any(|event| matches!(
+            event,
+            ReplayTraceEvent::SysvarUpdate { pubkey, .. } if *pubkey == sysvar::clock::id()
+        )));
+
+        let transaction_position = events
+            .iter()
+            .position(|event| {
+                *event
+                    == ReplayTraceEvent::Transaction {
+                        slot: 1,
+                        signature,
+                        result: Ok(()),
+                    }
+            })
+            .unwrap();
+        let to_write = events[transaction_position + 1..]
+            .iter()
+            .find_map(|event| match event {
+                ReplayTraceEvent::AccountWrite {
+                    signature: write_signature,
+                    instruction_index,
+                    pubkey,
+                    pre_hash,
+                    post_hash,
+                    ..
+                } if *write_signature == signature && *pubkey == to => {
+                    Some((*instruction_index, *pre_hash, *post_hash))
+                }
+                _ => None,
+            })
+            .unwrap();
+        let to_account = bank1.get_account(&to).unwrap();
+        assert_eq!(to_account.lamports, 3);
+        assert_eq!(to_write.0, 0);
+        assert_ne!(to_write.1, to_write.2);
+        assert_eq!(to_write.2, replay_tracer::hash_account(&to, &to_account));
+    }
 }
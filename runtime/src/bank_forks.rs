@@ -30,6 +30,10 @@ pub struct SnapshotConfig {
     // Generate a new snapshot every this many slots
     pub snapshot_interval_slots: u64,
 
+    // Generate an incremental snapshot, based on the last full snapshot, every this many slots.
+    // `std::u64::MAX` disables incremental snapshots
+    pub incremental_snapshot_interval_slots: u64,
+
     // Where to store the latest packaged snapshot
     pub snapshot_package_output_path: PathBuf,
 
@@ -1,6 +1,7 @@
 use crate::{
+    address_lookup_table_processor,
     bank::{Builtin, Builtins},
-    system_instruction_processor,
+    compute_budget_processor, system_instruction_processor,
 };
 use solana_sdk::{
     feature_set,
@@ -111,6 +112,24 @@ fn feature_builtins() -> Vec<(Builtin, Pubkey, ActivationType)> {
             feature_set::stake_program_v2::id(),
             ActivationType::NewVersion,
         ),
+        (
+            Builtin::new(
+                "compute_budget_program",
+                solana_sdk::compute_budget::id(),
+                compute_budget_processor::process_instruction,
+            ),
+            feature_set::compute_budget_program::id(),
+            ActivationType::NewProgram,
+        ),
+        (
+            Builtin::new(
+                "address_lookup_table_program",
+                solana_sdk::address_lookup_table::id(),
+                with_program_logging!(address_lookup_table_processor::process_instruction),
+            ),
+            feature_set::versioned_tx_message_enabled::id(),
+            ActivationType::NewProgram,
+        ),
     ]
 }
 
@@ -0,0 +1,68 @@
+use solana_sdk::{
+    compute_budget::{ComputeBudgetInstruction, MAX_UNITS},
+    instruction::InstructionError,
+    keyed_account::KeyedAccount,
+    process_instruction::InvokeContext,
+    program_utils::limited_deserialize,
+    pubkey::Pubkey,
+};
+
+/// The requested budget is applied by the runtime before the message is processed, so the
+/// instruction itself only has to be well-formed
+pub fn process_instruction(
+    _program_id: &Pubkey,
+    _keyed_accounts: &[KeyedAccount],
+    instruction_data: &[u8],
+    _invoke_context: &mut dyn InvokeContext,
+) -> Result<(), InstructionError> {
+    match limited_deserialize(instruction_data)? {
+        ComputeBudgetInstruction::RequestUnits { units, .. } => {
+            if units > MAX_UNITS {
+                return Err(InstructionError::InvalidInstructionData);
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::process_instruction::MockInvokeContext;
+
+    #[test]
+    fn test_process_instruction() {
+        let process = |instruction: &ComputeBudgetInstruction| {
+            process_instruction(
+                &solana_sdk::compute_budget::id(),
+                &[],
+                &bincode::serialize(instruction).unwrap(),
+                &mut MockInvokeContext::default(),
+            )
+        };
+
+        assert_eq!(
+            process(&ComputeBudgetInstruction::RequestUnits {
+                units: MAX_UNITS,
+                lamports_per_unit: 1,
+            }),
+            Ok(())
+        );
+        assert_eq!(
+            process(&ComputeBudgetInstruction::RequestUnits {
+                units: MAX_UNITS + 1,
+                lamports_per_unit: 1,
+            }),
+            Err(InstructionError::InvalidInstructionData)
+        );
+        assert_eq!(
+            process_instruction(
+                &solana_sdk::compute_budget::id(),
+                &[],
+                &[0],
+                &mut MockInvokeContext::default(),
+            ),
+            Err(InstructionError::InvalidInstructionData)
+        );
+    }
+}
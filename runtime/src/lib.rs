@@ -3,6 +3,8 @@ pub mod accounts;
 pub mod accounts_background_service;
 pub mod accounts_db;
 pub mod accounts_index;
+pub mod accounts_update_notifier_interface;
+mod address_lookup_table_processor;
 pub mod append_vec;
 pub mod bank;
 pub mod bank_client;
@@ -12,6 +14,7 @@ mod blockhash_queue;
 pub mod bloom;
 pub mod builtins;
 pub mod commitment;
+mod compute_budget_processor;
 pub mod epoch_stakes;
 pub mod genesis_utils;
 pub mod hardened_unpack;
@@ -21,6 +24,8 @@ pub mod log_collector;
 pub mod message_processor;
 mod native_loader;
 pub mod rent_collector;
+pub mod replay_tracer;
+pub mod secondary_index;
 pub mod serde_snapshot;
 pub mod snapshot_package;
 pub mod snapshot_utils;
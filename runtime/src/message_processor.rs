@@ -1,13 +1,18 @@
 use crate::{
-    instruction_recorder::InstructionRecorder, log_collector::LogCollector,
-    native_loader::NativeLoader, rent_collector::RentCollector,
+    instruction_recorder::InstructionRecorder,
+    log_collector::LogCollector,
+    native_loader::NativeLoader,
+    rent_collector::RentCollector,
+    replay_tracer::{self, InstructionAccountWrite},
 };
 use log::*;
 use serde::{Deserialize, Serialize};
 use solana_sdk::{
     account::Account,
     clock::Epoch,
-    feature_set::{instructions_sysvar_enabled, FeatureSet},
+    compute_budget,
+    feature_set::{compute_budget_program, instructions_sysvar_enabled, FeatureSet},
+    hash::Hash,
     instruction::{CompiledInstruction, Instruction, InstructionError},
     keyed_account::{create_keyed_readonly_accounts, KeyedAccount},
     message::Message,
@@ -530,6 +535,77 @@ impl MessageProcessor {
         }
     }
 
+    /// Process a cross-program instruction issued by a builtin program
+    ///
+    /// The accounts referenced by `instruction` must be present in the caller's
+    /// `keyed_accounts` with at least the requested privileges, unless they are
+    /// signers derived from the caller's program id and listed in `signers`.
+    /// On success the callee's modifications are written back to the caller's
+    /// accounts.
+    pub fn native_invoke(
+        invoke_context: &mut dyn InvokeContext,
+        instruction: Instruction,
+        keyed_accounts: &[&KeyedAccount],
+        signers: &[Pubkey],
+    ) -> Result<(), InstructionError> {
+        let message = Message::new(&[instruction.clone()], None);
+        let callee_program_id_index = message.instructions[0].program_id_index as usize;
+        let callee_program_id = message.account_keys[callee_program_id_index];
+
+        // Check for privilege escalation and gather the accounts
+        let mut accounts = Vec::with_capacity(message.account_keys.len());
+        for (i, key) in message.account_keys.iter().enumerate() {
+            let keyed_account = keyed_accounts
+                .iter()
+                .find(|keyed_account| keyed_account.unsigned_key() == key)
+                .ok_or(InstructionError::MissingAccount)?;
+            if message.is_writable(i) && !keyed_account.is_writable() {
+                return Err(InstructionError::PrivilegeEscalation);
+            }
+            if message.is_signer(i)
+                && keyed_account.signer_key().is_none()
+                && !signers.contains(key)
+            {
+                return Err(InstructionError::PrivilegeEscalation);
+            }
+            accounts.push(Rc::new(RefCell::new(
+                keyed_account.try_account_ref()?.clone(),
+            )));
+        }
+
+        let program_account = accounts[callee_program_id_index].clone();
+        if !program_account.borrow().executable {
+            return Err(InstructionError::AccountNotExecutable);
+        }
+        let executable_accounts = vec![(callee_program_id, (*program_account).clone())];
+
+        invoke_context.record_instruction(&instruction);
+
+        Self::process_cross_program_instruction(
+            &message,
+            &executable_accounts,
+            &accounts,
+            invoke_context,
+        )?;
+
+        // Copy results back to the caller's accounts
+        for (i, (key, account)) in message.account_keys.iter().zip(accounts).enumerate() {
+            if message.is_writable(i) {
+                if let Some(keyed_account) = keyed_accounts
+                    .iter()
+                    .find(|keyed_account| keyed_account.unsigned_key() == key)
+                {
+                    let account = account.borrow();
+                    if !account.executable {
+                        *keyed_account.try_account_ref_mut()? = account.clone();
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
     /// Record the initial state of the accounts so that they can be compared
     /// after the instruction is processed
     pub fn create_pre_accounts(
@@ -661,6 +737,7 @@ impl MessageProcessor {
         executors: Rc<RefCell<Executors>>,
         instruction_recorder: Option<InstructionRecorder>,
         instruction_index: usize,
+        compute_units_consumed: &mut Vec<u64>,
         feature_set: Arc<FeatureSet>,
         bpf_compute_budget: BpfComputeBudget,
     ) -> Result<(), InstructionError> {
@@ -693,7 +770,14 @@ impl MessageProcessor {
         );
         let keyed_accounts =
             Self::create_keyed_accounts(message, instruction, executable_accounts, accounts)?;
-        self.process_instruction(&keyed_accounts, &instruction.data, &mut invoke_context)?;
+        let result =
+            self.process_instruction(&keyed_accounts, &instruction.data, &mut invoke_context);
+        compute_units_consumed.push(
+            bpf_compute_budget
+                .max_units
+                .saturating_sub(invoke_context.get_compute_meter().borrow().get_remaining()),
+        );
+        result?;
         Self::verify(
             message,
             instruction,
@@ -705,9 +789,41 @@ impl MessageProcessor {
         Ok(())
     }
 
+    /// Hash the writable accounts referenced by an instruction, in account index order
+    fn hash_writable_accounts(
+        message: &Message,
+        instruction: &CompiledInstruction,
+        accounts: &[Rc<RefCell<Account>>],
+    ) -> Vec<(usize, Hash)> {
+        let mut indexes: Vec<_> = instruction
+            .accounts
+            .iter()
+            .map(|index| *index as usize)
+            .filter(|index| message.is_writable(*index))
+            .collect();
+        indexes.sort_unstable();
+        indexes.dedup();
+        indexes
+            .into_iter()
+            .map(|index| {
+                let hash = replay_tracer::hash_account(
+                    &message.account_keys[index],
+                    &accounts[index].borrow(),
+                );
+                (index, hash)
+            })
+            .collect()
+    }
+
     /// Process a message.
     /// This method calls each instruction in the message over the set of loaded Accounts
     /// The accounts are committed back to the bank only if every instruction succeeds
+    /// The compute units consumed by each executed instruction are appended to
+    /// `compute_units_consumed`
+    /// If the message requests a compute budget, that budget is shared by all of its instructions
+    /// instead of each instruction getting `bpf_compute_budget.max_units`
+    /// When `account_writes` is given, the writable accounts changed by each successful
+    /// instruction are appended to it along with their hashes before and after the instruction
     #[allow(clippy::too_many_arguments)]
     pub fn process_message(
         &self,
@@ -718,13 +834,31 @@ impl MessageProcessor {
         log_collector: Option<Rc<LogCollector>>,
         executors: Rc<RefCell<Executors>>,
         instruction_recorders: Option<&[InstructionRecorder]>,
+        compute_units_consumed: &mut Vec<u64>,
+        mut account_writes: Option<&mut Vec<InstructionAccountWrite>>,
         feature_set: Arc<FeatureSet>,
         bpf_compute_budget: BpfComputeBudget,
     ) -> Result<(), TransactionError> {
+        let mut remaining_units = if feature_set.is_active(&compute_budget_program::id()) {
+            compute_budget::get_requested_units(message)
+                .map(|(units, _)| u64::from(units.min(compute_budget::MAX_UNITS)))
+        } else {
+            None
+        };
         for (instruction_index, instruction) in message.instructions.iter().enumerate() {
             let instruction_recorder = instruction_recorders
                 .as_ref()
                 .map(|recorders| recorders[instruction_index].clone());
+            let bpf_compute_budget = match remaining_units {
+                Some(max_units) => BpfComputeBudget {
+                    max_units,
+                    ..bpf_compute_budget
+                },
+                None => bpf_compute_budget,
+            };
+            let pre_hashes = account_writes
+                .as_ref()
+                .map(|_| Self::hash_writable_accounts(message, instruction, accounts));
             self.execute_instruction(
                 message,
                 instruction,
@@ -735,10 +869,29 @@ impl MessageProcessor {
                 executors.clone(),
                 instruction_recorder,
                 instruction_index,
+                compute_units_consumed,
                 feature_set.clone(),
                 bpf_compute_budget,
             )
             .map_err(|err| TransactionError::InstructionError(instruction_index as u8, err))?;
+            if let (Some(account_writes), Some(pre_hashes)) = (account_writes.as_mut(), pre_hashes)
+            {
+                let post_hashes = Self::hash_writable_accounts(message, instruction, accounts);
+                for ((index, pre_hash), (_, post_hash)) in pre_hashes.into_iter().zip(post_hashes) {
+                    if pre_hash != post_hash {
+                        account_writes.push(InstructionAccountWrite {
+                            instruction_index,
+                            pubkey: message.account_keys[index],
+                            pre_hash,
+                            post_hash,
+                        });
+                    }
+                }
+            }
+            if let Some(remaining_units) = remaining_units.as_mut() {
+                let consumed = compute_units_consumed.last().copied().unwrap_or_default();
+                *remaining_units = remaining_units.saturating_sub(consumed);
+            }
         }
         Ok(())
     }
@@ -1326,6 +1479,7 @@ mod tests {
             Some(&from_pubkey),
         );
 
+        let mut compute_units_consumed = vec![];
         let result = message_processor.process_message(
             &message,
             &loaders,
@@ -1334,12 +1488,15 @@ mod tests {
             None,
             executors.clone(),
             None,
+            &mut compute_units_consumed,
+            None,
             Arc::new(FeatureSet::all_enabled()),
             BpfComputeBudget::new(&FeatureSet::all_enabled()),
         );
         assert_eq!(result, Ok(()));
         assert_eq!(accounts[0].borrow().lamports, 100);
         assert_eq!(accounts[1].borrow().lamports, 0);
+        assert_eq!(compute_units_consumed, vec![0]);
 
         let message = Message::new(
             &[Instruction::new(
@@ -1358,6 +1515,8 @@ mod tests {
             None,
             executors.clone(),
             None,
+            &mut vec![],
+            None,
             Arc::new(FeatureSet::all_enabled()),
             BpfComputeBudget::new(&FeatureSet::all_enabled()),
         );
@@ -1386,6 +1545,8 @@ mod tests {
             None,
             executors,
             None,
+            &mut vec![],
+            None,
             Arc::new(FeatureSet::all_enabled()),
             BpfComputeBudget::new(&FeatureSet::all_enabled()),
         );
@@ -1398,6 +1559,117 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_process_message_compute_budget() {
+        fn mock_process_instruction(
+            _program_id: &Pubkey,
+            _keyed_accounts: &[KeyedAccount],
+            data: &[u8],
+            invoke_context: &mut dyn InvokeContext,
+        ) -> Result<(), InstructionError> {
+            let units: u64 = bincode::deserialize(data).unwrap();
+            invoke_context
+                .get_compute_meter()
+                .borrow_mut()
+                .consume(units)
+        }
+
+        let mock_program_id = Pubkey::new_unique();
+        let rent_collector = RentCollector::default();
+        let mut message_processor = MessageProcessor::default();
+        message_processor.add_program(mock_program_id, mock_process_instruction);
+        message_processor.add_program(
+            compute_budget::id(),
+            crate::compute_budget_processor::process_instruction,
+        );
+
+        let payer = Pubkey::new_unique();
+        let accounts = vec![Account::new_ref(100, 0, &system_program::id())];
+        let mock_loader = || {
+            vec![(
+                mock_program_id,
+                RefCell::new(create_loadable_account("mock_program")),
+            )]
+        };
+        let compute_budget_loader = || {
+            vec![(
+                compute_budget::id(),
+                RefCell::new(create_loadable_account("compute_budget_program")),
+            )]
+        };
+        let bpf_compute_budget = BpfComputeBudget {
+            max_units: 100,
+            ..BpfComputeBudget::default()
+        };
+        let process_message = |message: &Message, feature_set: FeatureSet| {
+            let loaders: Vec<_> = message
+                .instructions
+                .iter()
+                .map(|instruction| {
+                    if instruction.program_id(&message.account_keys) == &mock_program_id {
+                        mock_loader()
+                    } else {
+                        compute_budget_loader()
+                    }
+                })
+                .collect();
+            let mut compute_units_consumed = vec![];
+            let result = message_processor.process_message(
+                message,
+                &loaders,
+                &accounts,
+                &rent_collector,
+                None,
+                Rc::new(RefCell::new(Executors::default())),
+                None,
+                &mut compute_units_consumed,
+                None,
+                Arc::new(feature_set),
+                bpf_compute_budget,
+            );
+            (result, compute_units_consumed)
+        };
+
+        // Each instruction consumes its entire default budget
+        let message = Message::new(
+            &[
+                Instruction::new(mock_program_id, &100u64, vec![]),
+                Instruction::new(mock_program_id, &100u64, vec![]),
+            ],
+            Some(&payer),
+        );
+        assert_eq!(
+            process_message(&message, FeatureSet::all_enabled()),
+            (Ok(()), vec![100, 100])
+        );
+
+        // The requested budget is shared across all of the instructions
+        let message = Message::new(
+            &[
+                compute_budget::request_units(150, 1),
+                Instruction::new(mock_program_id, &100u64, vec![]),
+                Instruction::new(mock_program_id, &100u64, vec![]),
+            ],
+            Some(&payer),
+        );
+        assert_eq!(
+            process_message(&message, FeatureSet::all_enabled()),
+            (
+                Err(TransactionError::InstructionError(
+                    2,
+                    InstructionError::ComputationalBudgetExceeded
+                )),
+                vec![0, 100, 50]
+            )
+        );
+
+        // The request is ignored until the feature is activated
+        assert_eq!(
+            process_message(&message, FeatureSet::default()),
+            (Ok(()), vec![0, 100, 100])
+        );
+    }
+
     #[test]
     fn test_process_message_duplicate_accounts() {
         #[derive(Serialize, Deserialize)]
@@ -1498,6 +1770,8 @@ mod tests {
             None,
             executors.clone(),
             None,
+            &mut vec![],
+            None,
             Arc::new(FeatureSet::all_enabled()),
             BpfComputeBudget::new(&FeatureSet::all_enabled()),
         );
@@ -1526,6 +1800,8 @@ mod tests {
             None,
             executors.clone(),
             None,
+            &mut vec![],
+            None,
             Arc::new(FeatureSet::all_enabled()),
             BpfComputeBudget::new(&FeatureSet::all_enabled()),
         );
@@ -1551,6 +1827,8 @@ mod tests {
             None,
             executors,
             None,
+            &mut vec![],
+            None,
             Arc::new(FeatureSet::all_enabled()),
             BpfComputeBudget::new(&FeatureSet::all_enabled()),
         );
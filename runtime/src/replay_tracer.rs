@@ -0,0 +1,139 @@
+use crate::bank::RewardType;
+use serde::{Serialize, Serializer};
+use solana_sdk::{
+    account::Account,
+    clock::Slot,
+    hash::{Hash, Hasher},
+    pubkey::Pubkey,
+    signature::Signature,
+    transaction::Result,
+};
+use std::{fmt::Display, sync::Arc};
+
+/// A single step of replaying a slot, in the order the bank performed it
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum ReplayTraceEvent {
+    Transaction {
+        slot: Slot,
+        #[serde(serialize_with = "serialize_display")]
+        signature: Signature,
+        result: Result<()>,
+    },
+    AccountWrite {
+        slot: Slot,
+        #[serde(serialize_with = "serialize_display")]
+        signature: Signature,
+        instruction_index: usize,
+        #[serde(serialize_with = "serialize_display")]
+        pubkey: Pubkey,
+        #[serde(serialize_with = "serialize_display")]
+        pre_hash: Hash,
+        #[serde(serialize_with = "serialize_display")]
+        post_hash: Hash,
+    },
+    RentCollection {
+        slot: Slot,
+        #[serde(serialize_with = "serialize_display")]
+        pubkey: Pubkey,
+        rent: u64,
+        #[serde(serialize_with = "serialize_display")]
+        post_hash: Hash,
+    },
+    Reward {
+        slot: Slot,
+        #[serde(serialize_with = "serialize_display")]
+        pubkey: Pubkey,
+        reward_type: RewardType,
+        lamports: i64,
+        post_balance: u64,
+    },
+    SysvarUpdate {
+        slot: Slot,
+        #[serde(serialize_with = "serialize_display")]
+        pubkey: Pubkey,
+        #[serde(serialize_with = "serialize_display")]
+        pre_hash: Hash,
+        #[serde(serialize_with = "serialize_display")]
+        post_hash: Hash,
+    },
+}
+
+impl ReplayTraceEvent {
+    pub fn slot(&self) -> Slot {
+        match self {
+            Self::Transaction { slot, .. }
+            | Self::AccountWrite { slot, .. }
+            | Self::RentCollection { slot, .. }
+            | Self::Reward { slot, .. }
+            | Self::SysvarUpdate { slot, .. } => *slot,
+        }
+    }
+}
+
+pub trait ReplayTracerInterface: std::fmt::Debug {
+    /// Whether the bank for `slot` should report its replay events
+    fn trace_slot(&self, _slot: Slot) -> bool {
+        true
+    }
+
+    /// Called for every replay event of a traced slot
+    fn trace(&self, event: ReplayTraceEvent);
+}
+
+pub type ReplayTracer = Arc<dyn ReplayTracerInterface + Sync + Send>;
+
+/// An account write made by one instruction of a transaction
+#[derive(Debug, Clone, PartialEq)]
+pub struct InstructionAccountWrite {
+    pub instruction_index: usize,
+    pub pubkey: Pubkey,
+    pub pre_hash: Hash,
+    pub post_hash: Hash,
+}
+
+/// Hash of the contents of an account. Unlike the accounts db hash this does not depend on the
+/// slot the account is stored in, so writes traced on different nodes compare directly
+pub fn hash_account(pubkey: &Pubkey, account: &Account) -> Hash {
+    let mut hasher = Hasher::default();
+    hasher.hash(&account.lamports.to_le_bytes());
+    hasher.hash(&account.rent_epoch.to_le_bytes());
+    hasher.hash(&account.data);
+    hasher.hash(&[account.executable as u8]);
+    hasher.hash(account.owner.as_ref());
+    hasher.hash(pubkey.as_ref());
+    hasher.result()
+}
+
+fn serialize_display<T: Display, S: Serializer>(
+    value: &T,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    serializer.collect_str(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hash_account() {
+        let pubkey = solana_sdk::pubkey::new_rand();
+        let account = Account::new(1, 8, &Pubkey::default());
+        assert_eq!(
+            hash_account(&pubkey, &account),
+            hash_account(&pubkey, &account.clone())
+        );
+
+        let mut modified = account.clone();
+        modified.data[0] = 1;
+        assert_ne!(
+            hash_account(&pubkey, &account),
+            hash_account(&pubkey, &modified)
+        );
+        assert_ne!(
+            hash_account(&pubkey, &account),
+            hash_account(&solana_sdk::pubkey::new_rand(), &account)
+        );
+    }
+}
@@ -0,0 +1,143 @@
+use dashmap::DashMap;
+use solana_sdk::{clock::Slot, pubkey::Pubkey};
+use std::collections::{HashMap, HashSet};
+
+// The slots at which an account was stored under a given index key
+type SecondaryIndexEntry = HashMap<Pubkey, HashSet<Slot>>;
+
+/// Maps an index key (a program id, SPL token owner or SPL token mint) to the
+/// accounts that were stored under that key.
+///
+/// Entries are tracked per slot, so an account that moves from one key to
+/// another stays reachable from both keys until the older slot is purged or
+/// cleaned from the primary index. Callers must therefore re-check the loaded
+/// account against the key they scanned for.
+#[derive(Debug, Default)]
+pub struct SecondaryIndex {
+    // index key -> (account key -> slots)
+    index: DashMap<Pubkey, SecondaryIndexEntry>,
+    // account key -> (slot, index key)
+    reverse_index: DashMap<Pubkey, HashSet<(Slot, Pubkey)>>,
+}
+
+impl SecondaryIndex {
+    pub fn insert(&self, key: &Pubkey, inner_key: &Pubkey, slot: Slot) {
+        let is_new = self
+            .reverse_index
+            .entry(*inner_key)
+            .or_default()
+            .insert((slot, *key));
+        if is_new {
+            self.index
+                .entry(*key)
+                .or_default()
+                .entry(*inner_key)
+                .or_default()
+                .insert(slot);
+        }
+    }
+
+    /// Remove `inner_key` from the index for the given slots, or for every
+    /// slot if `slots` is `None`
+    pub fn remove_by_inner_key(&self, inner_key: &Pubkey, slots: Option<&HashSet<Slot>>) {
+        let removed: Vec<(Slot, Pubkey)> = match slots {
+            None => self
+                .reverse_index
+                .remove(inner_key)
+                .map(|(_, entries)| entries.into_iter().collect())
+                .unwrap_or_default(),
+            Some(slots) => {
+                let mut removed = vec![];
+                if let Some(mut entries) = self.reverse_index.get_mut(inner_key) {
+                    entries.retain(|entry| {
+                        if slots.contains(&entry.0) {
+                            removed.push(*entry);
+                            false
+                        } else {
+                            true
+                        }
+                    });
+                }
+                self.reverse_index
+                    .remove_if(inner_key, |_, entries| entries.is_empty());
+                removed
+            }
+        };
+
+        for (slot, key) in removed {
+            if let Some(mut entry) = self.index.get_mut(&key) {
+                if let Some(inner_slots) = entry.get_mut(inner_key) {
+                    inner_slots.remove(&slot);
+                    if inner_slots.is_empty() {
+                        entry.remove(inner_key);
+                    }
+                }
+            }
+            self.index.remove_if(&key, |_, entry| entry.is_empty());
+        }
+    }
+
+    pub fn get(&self, key: &Pubkey) -> Vec<Pubkey> {
+        self.index
+            .get(key)
+            .map(|entry| entry.keys().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insert_and_get() {
+        let index = SecondaryIndex::default();
+        let key = Pubkey::new_unique();
+        let account0 = Pubkey::new_unique();
+        let account1 = Pubkey::new_unique();
+        index.insert(&key, &account0, 0);
+        index.insert(&key, &account0, 1);
+        index.insert(&key, &account1, 1);
+
+        let mut found = index.get(&key);
+        found.sort();
+        let mut expected = vec![account0, account1];
+        expected.sort();
+        assert_eq!(found, expected);
+        assert!(index.get(&Pubkey::new_unique()).is_empty());
+    }
+
+    #[test]
+    fn test_remove_by_inner_key() {
+        let index = SecondaryIndex::default();
+        let key0 = Pubkey::new_unique();
+        let key1 = Pubkey::new_unique();
+        let account = Pubkey::new_unique();
+
+        // The account moved from `key0` to `key1` at slot 2
+        index.insert(&key0, &account, 1);
+        index.insert(&key1, &account, 2);
+        assert_eq!(index.get(&key0), vec![account]);
+        assert_eq!(index.get(&key1), vec![account]);
+
+        // Purging the older slot only drops the stale key
+        index.remove_by_inner_key(&account, Some(&[1].iter().cloned().collect()));
+        assert!(index.get(&key0).is_empty());
+        assert_eq!(index.get(&key1), vec![account]);
+        assert_eq!(index.len(), 1);
+
+        // Removing every slot drops the account entirely
+        index.remove_by_inner_key(&account, None);
+        assert!(index.get(&key1).is_empty());
+        assert!(index.is_empty());
+        assert!(index.reverse_index.is_empty());
+    }
+}
@@ -2,7 +2,7 @@ use {
     crate::{
         accounts::Accounts,
         accounts_db::{AccountStorageEntry, AccountsDB, AppendVecId, BankHashInfo},
-        accounts_index::Ancestors,
+        accounts_index::{AccountSecondaryIndexes, Ancestors},
         append_vec::AppendVec,
         bank::{Bank, BankFieldsToDeserialize, BankRc, Builtins},
         blockhash_queue::BlockhashQueue,
@@ -66,11 +66,46 @@ const MAX_STREAM_SIZE: u64 = 32 * 1024 * 1024 * 1024;
 #[derive(Clone, Debug, Default, Deserialize, Serialize, AbiExample)]
 struct AccountsDbFields<T>(HashMap<Slot, Vec<T>>, u64, Slot, BankHashInfo);
 
+impl<T> AccountsDbFields<T> {
+    /// Storages of an incremental snapshot are only packaged for slots newer than the full
+    /// snapshot it is based on, so take the remaining storages from the full snapshot's fields
+    fn with_full_snapshot_storages(
+        mut self,
+        full_snapshot_slot: Slot,
+        full_snapshot_fields: AccountsDbFields<T>,
+    ) -> Self {
+        self.0.retain(|slot, _| *slot > full_snapshot_slot);
+        self.0.extend(
+            full_snapshot_fields
+                .0
+                .into_iter()
+                .filter(|(slot, _)| *slot <= full_snapshot_slot),
+        );
+        self
+    }
+}
+
+/// The bank fields and AppendVec layout of a snapshot, which can be read without a genesis config
+#[derive(Clone, Debug, PartialEq)]
+pub struct SnapshotFields {
+    pub slot: Slot,
+    pub capitalization: u64,
+    pub accounts_hash: Hash,
+    /// The slot, AppendVec id and length in use of every storage in the snapshot
+    pub storages: Vec<(Slot, AppendVecId, usize)>,
+}
+
+trait SerializableStorage {
+    fn id(&self) -> AppendVecId;
+    fn current_len(&self) -> usize;
+}
+
 trait TypeContext<'a> {
     type SerializableAccountStorageEntry: Serialize
         + DeserializeOwned
         + From<&'a AccountStorageEntry>
-        + Into<AccountStorageEntry>;
+        + Into<AccountStorageEntry>
+        + SerializableStorage;
 
     fn serialize_bank_and_storage<S: serde::ser::Serializer>(
         serializer: S,
@@ -117,15 +152,18 @@ where
         .deserialize_from::<R, T>(reader)
 }
 
-pub(crate) fn bank_from_stream<R, P>(
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn bank_from_streams<R, P>(
     serde_style: SerdeStyle,
     stream: &mut BufReader<R>,
+    incremental_stream: Option<&mut BufReader<R>>,
     append_vecs_path: P,
     account_paths: &[PathBuf],
     genesis_config: &GenesisConfig,
     frozen_account_pubkeys: &[Pubkey],
     debug_keys: Option<Arc<HashSet<Pubkey>>>,
     additional_builtins: Option<&Builtins>,
+    account_indexes: AccountSecondaryIndexes,
 ) -> std::result::Result<Bank, Error>
 where
     R: Read,
@@ -133,7 +171,15 @@ where
 {
     macro_rules! INTO {
         ($x:ident) => {{
-            let (bank_fields, accounts_db_fields) = $x::deserialize_bank_fields(stream)?;
+            let (mut bank_fields, mut accounts_db_fields) = $x::deserialize_bank_fields(stream)?;
+            if let Some(incremental_stream) = incremental_stream {
+                let full_snapshot_slot = bank_fields.slot;
+                let (incremental_bank_fields, incremental_accounts_db_fields) =
+                    $x::deserialize_bank_fields(incremental_stream)?;
+                bank_fields = incremental_bank_fields;
+                accounts_db_fields = incremental_accounts_db_fields
+                    .with_full_snapshot_storages(full_snapshot_slot, accounts_db_fields);
+            }
 
             let bank = reconstruct_bank_from_fields(
                 bank_fields,
@@ -144,6 +190,7 @@ where
                 append_vecs_path,
                 debug_keys,
                 additional_builtins,
+                account_indexes,
             )?;
             Ok(bank)
         }};
@@ -152,7 +199,44 @@ where
         SerdeStyle::NEWER => INTO!(TypeContextFuture),
     }
     .map_err(|err| {
-        warn!("bankrc_from_stream error: {:?}", err);
+        warn!("bank_from_streams error: {:?}", err);
+        err
+    })
+}
+
+pub(crate) fn snapshot_fields_from_stream<R>(
+    serde_style: SerdeStyle,
+    stream: &mut BufReader<R>,
+) -> std::result::Result<SnapshotFields, Error>
+where
+    R: Read,
+{
+    macro_rules! INTO {
+        ($x:ident) => {{
+            let (bank_fields, accounts_db_fields) = $x::deserialize_bank_fields(stream)?;
+            let AccountsDbFields(storage, _version, _slot, bank_hash_info) = accounts_db_fields;
+            let mut storages: Vec<_> = storage
+                .into_iter()
+                .flat_map(|(slot, entries)| {
+                    entries
+                        .into_iter()
+                        .map(move |entry| (slot, entry.id(), entry.current_len()))
+                })
+                .collect();
+            storages.sort_unstable();
+            Ok(SnapshotFields {
+                slot: bank_fields.slot,
+                capitalization: bank_fields.capitalization,
+                accounts_hash: bank_hash_info.snapshot_hash,
+                storages,
+            })
+        }};
+    }
+    match serde_style {
+        SerdeStyle::NEWER => INTO!(TypeContextFuture),
+    }
+    .map_err(|err| {
+        warn!("snapshot_fields_from_stream error: {:?}", err);
         err
     })
 }
@@ -230,6 +314,7 @@ fn reconstruct_bank_from_fields<E, P>(
     append_vecs_path: P,
     debug_keys: Option<Arc<HashSet<Pubkey>>>,
     additional_builtins: Option<&Builtins>,
+    account_indexes: AccountSecondaryIndexes,
 ) -> Result<Bank, Error>
 where
     E: Into<AccountStorageEntry>,
@@ -240,6 +325,7 @@ where
         account_paths,
         append_vecs_path,
         &genesis_config.cluster_type,
+        account_indexes,
     )?;
     accounts_db.freeze_accounts(&bank_fields.ancestors, frozen_account_pubkeys);
 
@@ -260,12 +346,14 @@ fn reconstruct_accountsdb_from_fields<E, P>(
     account_paths: &[PathBuf],
     stream_append_vecs_path: P,
     cluster_type: &ClusterType,
+    account_indexes: AccountSecondaryIndexes,
 ) -> Result<AccountsDB, Error>
 where
     E: Into<AccountStorageEntry>,
     P: AsRef<Path>,
 {
-    let mut accounts_db = AccountsDB::new(account_paths.to_vec(), cluster_type);
+    let mut accounts_db =
+        AccountsDB::new_with_indexes(account_paths.to_vec(), cluster_type, account_indexes);
 
     let AccountsDbFields(storage, version, slot, bank_hash_info) = accounts_db_fields;
 
@@ -24,6 +24,16 @@ impl From<&AccountStorageEntry> for SerializableAccountStorageEntry {
     }
 }
 
+impl SerializableStorage for SerializableAccountStorageEntry {
+    fn id(&self) -> AppendVecId {
+        self.id
+    }
+
+    fn current_len(&self) -> usize {
+        self.accounts_current_len
+    }
+}
+
 impl Into<AccountStorageEntry> for SerializableAccountStorageEntry {
     fn into(self) -> AccountStorageEntry {
         AccountStorageEntry::new_empty_map(self.id, self.accounts_current_len)
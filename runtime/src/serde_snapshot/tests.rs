@@ -69,6 +69,7 @@ where
         account_paths,
         stream_append_vecs_path,
         &ClusterType::Development,
+        HashSet::new(),
     )
 }
 
@@ -203,15 +204,17 @@ fn test_bank_serialize_style(serde_style: SerdeStyle) {
     // Create a directory to simulate AppendVecs unpackaged from a snapshot tar
     let copied_accounts = TempDir::new().unwrap();
     copy_append_vecs(&bank2.rc.accounts.accounts_db, copied_accounts.path()).unwrap();
-    let mut dbank = crate::serde_snapshot::bank_from_stream(
+    let mut dbank = crate::serde_snapshot::bank_from_streams(
         serde_style,
         &mut reader,
+        None,
         copied_accounts.path(),
         &dbank_paths,
         &genesis_config,
         &[],
         None,
         None,
+        HashSet::new(),
     )
     .unwrap();
     dbank.src = ref_sc;
@@ -13,6 +13,13 @@ pub type AccountsPackageSender = Sender<AccountsPackage>;
 pub type AccountsPackageReceiver = Receiver<AccountsPackage>;
 pub type AccountsPackageSendError = SendError<AccountsPackage>;
 
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SnapshotType {
+    FullSnapshot,
+    /// Only the accounts changed since the full snapshot at the given slot
+    IncrementalSnapshot(Slot),
+}
+
 #[derive(Debug)]
 pub struct AccountsPackage {
     pub root: Slot,
@@ -24,6 +31,7 @@ pub struct AccountsPackage {
     pub hash: Hash,
     pub compression: CompressionType,
     pub snapshot_version: SnapshotVersion,
+    pub snapshot_type: SnapshotType,
 }
 
 impl AccountsPackage {
@@ -37,6 +45,7 @@ impl AccountsPackage {
         hash: Hash,
         compression: CompressionType,
         snapshot_version: SnapshotVersion,
+        snapshot_type: SnapshotType,
     ) -> Self {
         Self {
             root,
@@ -48,6 +57,7 @@ impl AccountsPackage {
             hash,
             compression,
             snapshot_version,
+            snapshot_type,
         }
     }
 }
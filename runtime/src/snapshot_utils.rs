@@ -1,11 +1,15 @@
 use crate::{
+    accounts_index::AccountSecondaryIndexes,
     bank::{Bank, BankSlotDelta, Builtins},
     bank_forks::CompressionType,
     hardened_unpack::{unpack_snapshot, UnpackError},
     serde_snapshot::{
-        bank_from_stream, bank_to_stream, SerdeStyle, SnapshotStorage, SnapshotStorages,
+        bank_from_streams, bank_to_stream, snapshot_fields_from_stream, SerdeStyle, SnapshotFields,
+        SnapshotStorage, SnapshotStorages,
+    },
+    snapshot_package::{
+        AccountsPackage, AccountsPackageSendError, AccountsPackageSender, SnapshotType,
     },
-    snapshot_package::{AccountsPackage, AccountsPackageSendError, AccountsPackageSender},
 };
 use bincode::{config::Options, serialize_into};
 use bzip2::bufread::BzDecoder;
@@ -41,6 +45,7 @@ pub const TAR_ACCOUNTS_DIR: &str = "accounts";
 pub const TAR_VERSION_FILE: &str = "version";
 
 pub const MAX_SNAPSHOTS: usize = 8; // Save some snapshots but not too many
+const MAX_INCREMENTAL_SNAPSHOT_ARCHIVES: usize = 2;
 const MAX_SNAPSHOT_DATA_FILE_SIZE: u64 = 32 * 1024 * 1024 * 1024; // 32 GiB
 const VERSION_STRING_V1_2_0: &str = "1.2.0";
 const DEFAULT_SNAPSHOT_VERSION: SnapshotVersion = SnapshotVersion::V1_2_0;
@@ -169,6 +174,7 @@ pub fn package_snapshot<P: AsRef<Path>, Q: AsRef<Path>>(
     snapshot_storages: SnapshotStorages,
     compression: CompressionType,
     snapshot_version: SnapshotVersion,
+    snapshot_type: SnapshotType,
 ) -> Result<AccountsPackage> {
     // Hard link all the snapshots we need for this package
     let snapshot_hard_links_dir = tempfile::Builder::new()
@@ -186,11 +192,20 @@ pub fn package_snapshot<P: AsRef<Path>, Q: AsRef<Path>>(
     // any temporary state created for the AccountsPackage (like the snapshot_hard_links_dir)
     snapshot_files.copy_snapshot_directory(snapshot_hard_links_dir.path())?;
 
-    let snapshot_package_output_file = get_snapshot_archive_path(
-        &snapshot_package_output_path,
-        &(bank.slot(), bank.get_accounts_hash()),
-        &compression,
-    );
+    let snapshot_hash = (bank.slot(), bank.get_accounts_hash());
+    let snapshot_package_output_file = match snapshot_type {
+        SnapshotType::FullSnapshot => {
+            get_snapshot_archive_path(&snapshot_package_output_path, &snapshot_hash, &compression)
+        }
+        SnapshotType::IncrementalSnapshot(full_snapshot_slot) => {
+            get_incremental_snapshot_archive_path(
+                &snapshot_package_output_path,
+                full_snapshot_slot,
+                &snapshot_hash,
+                &compression,
+            )
+        }
+    };
 
     let package = AccountsPackage::new(
         bank.slot(),
@@ -202,6 +217,7 @@ pub fn package_snapshot<P: AsRef<Path>, Q: AsRef<Path>>(
         bank.get_accounts_hash(),
         compression,
         snapshot_version,
+        snapshot_type,
     );
 
     Ok(package)
@@ -271,8 +287,19 @@ pub fn archive_snapshot_package(snapshot_package: &AccountsPackage) -> Result<()
         &staging_snapshots_dir,
     )?;
 
-    // Add the AppendVecs into the compressible list
-    for storage in snapshot_package.storages.iter().flatten() {
+    // Add the AppendVecs into the compressible list. An incremental snapshot only needs the
+    // AppendVecs written after the full snapshot it is based on.
+    let storages = snapshot_package
+        .storages
+        .iter()
+        .flatten()
+        .filter(|storage| match snapshot_package.snapshot_type {
+            SnapshotType::FullSnapshot => true,
+            SnapshotType::IncrementalSnapshot(full_snapshot_slot) => {
+                storage.slot() > full_snapshot_slot
+            }
+        });
+    for storage in storages {
         storage.flush()?;
         let storage_path = storage.get_path();
         let output_path =
@@ -371,6 +398,7 @@ pub fn archive_snapshot_package(snapshot_package: &AccountsPackage) -> Result<()
         fs::remove_file(old_archive.0)
             .unwrap_or_else(|err| info!("Failed to remove old snapshot: {:}", err));
     }
+    purge_old_incremental_snapshot_archives(tar_dir);
 
     timer.stop();
     info!(
@@ -384,11 +412,36 @@ pub fn archive_snapshot_package(snapshot_package: &AccountsPackage) -> Result<()
         "snapshot-package",
         ("slot", snapshot_package.root, i64),
         ("duration_ms", timer.as_ms(), i64),
-        ("size", metadata.len(), i64)
+        ("size", metadata.len(), i64),
+        (
+            "incremental",
+            snapshot_package.snapshot_type != SnapshotType::FullSnapshot,
+            bool
+        )
     );
     Ok(())
 }
 
+// Incremental snapshot archives can only be restored together with the full snapshot archive
+// they are based on, so only keep the newest few based on the highest full snapshot archive
+fn purge_old_incremental_snapshot_archives(snapshot_output_dir: &Path) {
+    let highest_full_snapshot_slot = get_highest_snapshot_archive_path(snapshot_output_dir)
+        .map(|(_, (full_snapshot_slot, _, _))| full_snapshot_slot);
+    let mut num_kept = 0;
+    for (archive, (full_snapshot_slot, _, _, _)) in
+        get_incremental_snapshot_archives(snapshot_output_dir)
+    {
+        if Some(full_snapshot_slot) == highest_full_snapshot_slot
+            && num_kept < MAX_INCREMENTAL_SNAPSHOT_ARCHIVES
+        {
+            num_kept += 1;
+        } else {
+            fs::remove_file(archive)
+                .unwrap_or_else(|err| info!("Failed to remove old incremental snapshot: {:}", err));
+        }
+    }
+}
+
 pub fn get_snapshot_paths<P: AsRef<Path>>(snapshot_path: P) -> Vec<SlotSnapshotPaths>
 where
     P: fmt::Debug,
@@ -595,39 +648,74 @@ pub fn remove_snapshot<P: AsRef<Path>>(slot: Slot, snapshot_path: P) -> Result<(
     Ok(())
 }
 
+/// Rebuild a bank from a full snapshot archive and, optionally, an incremental snapshot
+/// archive based on it
 pub fn bank_from_archive<P: AsRef<Path>>(
     account_paths: &[PathBuf],
     frozen_account_pubkeys: &[Pubkey],
     snapshot_path: &PathBuf,
     snapshot_tar: P,
     compression: CompressionType,
+    incremental_snapshot_tar: Option<(P, CompressionType)>,
     genesis_config: &GenesisConfig,
     debug_keys: Option<Arc<HashSet<Pubkey>>>,
     additional_builtins: Option<&Builtins>,
+    account_indexes: AccountSecondaryIndexes,
 ) -> Result<Bank> {
     // Untar the snapshot into a temporary directory
     let unpack_dir = tempfile::Builder::new()
         .prefix(TMP_SNAPSHOT_DIR_PREFIX)
         .tempdir_in(snapshot_path)?;
     untar_snapshot_in(&snapshot_tar, &unpack_dir, compression)?;
+    let incremental_unpack_dir = match incremental_snapshot_tar {
+        Some((incremental_snapshot_tar, incremental_compression)) => {
+            let incremental_unpack_dir = tempfile::Builder::new()
+                .prefix(TMP_SNAPSHOT_DIR_PREFIX)
+                .tempdir_in(snapshot_path)?;
+            untar_snapshot_in(
+                &incremental_snapshot_tar,
+                &incremental_unpack_dir,
+                incremental_compression,
+            )?;
+            Some(incremental_unpack_dir)
+        }
+        None => None,
+    };
 
     let mut measure = Measure::start("bank rebuild from snapshot");
     let unpacked_accounts_dir = unpack_dir.as_ref().join(TAR_ACCOUNTS_DIR);
     let unpacked_snapshots_dir = unpack_dir.as_ref().join(TAR_SNAPSHOTS_DIR);
-    let unpacked_version_file = unpack_dir.as_ref().join(TAR_VERSION_FILE);
-
-    let mut snapshot_version = String::new();
-    File::open(unpacked_version_file).and_then(|mut f| f.read_to_string(&mut snapshot_version))?;
+    let snapshot_version = read_snapshot_version(unpack_dir.as_ref())?;
+
+    let unpacked_incremental_snapshots_dir = match &incremental_unpack_dir {
+        Some(incremental_unpack_dir) => {
+            if read_snapshot_version(incremental_unpack_dir.as_ref())? != snapshot_version {
+                return Err(get_io_error(
+                    "incremental snapshot version does not match the full snapshot",
+                ));
+            }
+            // Gather the AppendVecs of both archives in one place for the rebuild. They do not
+            // collide because the incremental archive only has AppendVecs for newer slots.
+            for entry in fs::read_dir(incremental_unpack_dir.as_ref().join(TAR_ACCOUNTS_DIR))? {
+                let entry = entry?;
+                fs::rename(entry.path(), unpacked_accounts_dir.join(entry.file_name()))?;
+            }
+            Some(incremental_unpack_dir.as_ref().join(TAR_SNAPSHOTS_DIR))
+        }
+        None => None,
+    };
 
     let bank = rebuild_bank_from_snapshots(
-        snapshot_version.trim(),
+        &snapshot_version,
         account_paths,
         frozen_account_pubkeys,
         &unpacked_snapshots_dir,
+        unpacked_incremental_snapshots_dir.as_ref(),
         unpacked_accounts_dir,
         genesis_config,
         debug_keys,
         additional_builtins,
+        account_indexes,
     )?;
 
     if !bank.verify_snapshot_bank() {
@@ -649,6 +737,8 @@ pub fn bank_from_archive<P: AsRef<Path>>(
     info!("{}", measure);
 
     // Move the unpacked snapshots into `snapshot_path`
+    let unpacked_snapshots_dir =
+        unpacked_incremental_snapshots_dir.unwrap_or(unpacked_snapshots_dir);
     let dir_files = fs::read_dir(&unpacked_snapshots_dir).unwrap_or_else(|err| {
         panic!(
             "Invalid snapshot path {:?}: {}",
@@ -665,6 +755,13 @@ pub fn bank_from_archive<P: AsRef<Path>>(
     Ok(bank)
 }
 
+fn read_snapshot_version(unpack_dir: &Path) -> Result<String> {
+    let mut snapshot_version = String::new();
+    File::open(unpack_dir.join(TAR_VERSION_FILE))
+        .and_then(|mut f| f.read_to_string(&mut snapshot_version))?;
+    Ok(snapshot_version.trim().to_string())
+}
+
 pub fn get_snapshot_archive_path<P: AsRef<Path>>(
     snapshot_output_dir: P,
     snapshot_hash: &(Slot, Hash),
@@ -678,6 +775,21 @@ pub fn get_snapshot_archive_path<P: AsRef<Path>>(
     ))
 }
 
+pub fn get_incremental_snapshot_archive_path<P: AsRef<Path>>(
+    snapshot_output_dir: P,
+    full_snapshot_slot: Slot,
+    snapshot_hash: &(Slot, Hash),
+    compression: &CompressionType,
+) -> PathBuf {
+    snapshot_output_dir.as_ref().join(format!(
+        "incremental-snapshot-{}-{}-{}{}",
+        full_snapshot_slot,
+        snapshot_hash.0,
+        snapshot_hash.1,
+        get_compression_ext(compression),
+    ))
+}
+
 fn compression_type_from_str(compress: &str) -> Option<CompressionType> {
     match compress {
         "tar.bz2" => Some(CompressionType::Bzip2),
@@ -688,9 +800,9 @@ fn compression_type_from_str(compress: &str) -> Option<CompressionType> {
     }
 }
 
-fn snapshot_hash_of(archive_filename: &str) -> Option<(Slot, Hash, CompressionType)> {
+pub fn snapshot_hash_of(archive_filename: &str) -> Option<(Slot, Hash, CompressionType)> {
     let snapshot_filename_regex =
-        Regex::new(r"snapshot-(\d+)-([[:alnum:]]+)\.(tar|tar\.bz2|tar\.zst|tar\.gz)$").unwrap();
+        Regex::new(r"^snapshot-(\d+)-([[:alnum:]]+)\.(tar|tar\.bz2|tar\.zst|tar\.gz)$").unwrap();
 
     if let Some(captures) = snapshot_filename_regex.captures(archive_filename) {
         let slot_str = captures.get(1).unwrap().as_str();
@@ -708,6 +820,35 @@ fn snapshot_hash_of(archive_filename: &str) -> Option<(Slot, Hash, CompressionTy
     None
 }
 
+// Returns the full snapshot slot, slot, hash and compression of an incremental snapshot archive
+fn incremental_snapshot_hash_of(
+    archive_filename: &str,
+) -> Option<(Slot, Slot, Hash, CompressionType)> {
+    let incremental_snapshot_filename_regex = Regex::new(
+        r"^incremental-snapshot-(\d+)-(\d+)-([[:alnum:]]+)\.(tar|tar\.bz2|tar\.zst|tar\.gz)$",
+    )
+    .unwrap();
+
+    if let Some(captures) = incremental_snapshot_filename_regex.captures(archive_filename) {
+        let full_slot_str = captures.get(1).unwrap().as_str();
+        let slot_str = captures.get(2).unwrap().as_str();
+        let hash_str = captures.get(3).unwrap().as_str();
+        let ext = captures.get(4).unwrap().as_str();
+
+        if let (Ok(full_snapshot_slot), Ok(slot), Ok(hash), Some(compression)) = (
+            full_slot_str.parse::<Slot>(),
+            slot_str.parse::<Slot>(),
+            hash_str.parse::<Hash>(),
+            compression_type_from_str(ext),
+        ) {
+            if full_snapshot_slot < slot {
+                return Some((full_snapshot_slot, slot, hash, compression));
+            }
+        }
+    }
+    None
+}
+
 pub fn get_snapshot_archives<P: AsRef<Path>>(
     snapshot_output_dir: P,
 ) -> Vec<(PathBuf, (Slot, Hash, CompressionType))> {
@@ -746,6 +887,44 @@ pub fn get_highest_snapshot_archive_path<P: AsRef<Path>>(
     archives.into_iter().next()
 }
 
+pub fn get_incremental_snapshot_archives<P: AsRef<Path>>(
+    snapshot_output_dir: P,
+) -> Vec<(PathBuf, (Slot, Slot, Hash, CompressionType))> {
+    match fs::read_dir(&snapshot_output_dir) {
+        Err(err) => {
+            info!("Unable to read snapshot directory: {}", err);
+            vec![]
+        }
+        Ok(files) => {
+            let mut archives: Vec<_> = files
+                .filter_map(|entry| {
+                    let path = entry.ok()?.path();
+                    if !path.is_file() {
+                        return None;
+                    }
+                    let snapshot_hash = incremental_snapshot_hash_of(path.file_name()?.to_str()?)?;
+                    Some((path, snapshot_hash))
+                })
+                .collect();
+
+            archives.sort_by(|a, b| (b.1).1.cmp(&(a.1).1)); // reverse sort by slot
+            archives
+        }
+    }
+}
+
+/// Returns the highest incremental snapshot archive based on the full snapshot at
+/// `full_snapshot_slot`
+pub fn get_highest_incremental_snapshot_archive_path<P: AsRef<Path>>(
+    snapshot_output_dir: P,
+    full_snapshot_slot: Slot,
+) -> Option<(PathBuf, (Slot, Hash, CompressionType))> {
+    get_incremental_snapshot_archives(snapshot_output_dir)
+        .into_iter()
+        .find(|(_, (base_slot, _, _, _))| *base_slot == full_snapshot_slot)
+        .map(|(path, (_, slot, hash, compression))| (path, (slot, hash, compression)))
+}
+
 pub fn untar_snapshot_in<P: AsRef<Path>, Q: AsRef<Path>>(
     snapshot_tar: P,
     unpack_dir: Q,
@@ -785,10 +964,12 @@ fn rebuild_bank_from_snapshots<P>(
     account_paths: &[PathBuf],
     frozen_account_pubkeys: &[Pubkey],
     unpacked_snapshots_dir: &PathBuf,
+    unpacked_incremental_snapshots_dir: Option<&PathBuf>,
     append_vecs_path: P,
     genesis_config: &GenesisConfig,
     debug_keys: Option<Arc<HashSet<Pubkey>>>,
     additional_builtins: Option<&Builtins>,
+    account_indexes: AccountSecondaryIndexes,
 ) -> Result<Bank>
 where
     P: AsRef<Path>,
@@ -802,31 +983,59 @@ where
                 snapshot_version
             ))
         })?;
-    let mut snapshot_paths = get_snapshot_paths(&unpacked_snapshots_dir);
-    if snapshot_paths.len() > 1 {
-        return Err(get_io_error("invalid snapshot format"));
-    }
-    let root_paths = snapshot_paths
-        .pop()
-        .ok_or_else(|| get_io_error("No snapshots found in snapshots directory"))?;
+    let root_paths = get_unpacked_snapshot_paths(unpacked_snapshots_dir)?;
+    let incremental_root_paths = unpacked_incremental_snapshots_dir
+        .map(get_unpacked_snapshot_paths)
+        .transpose()?;
 
+    let serde_style = match snapshot_version_enum {
+        SnapshotVersion::V1_2_0 => SerdeStyle::NEWER,
+    };
     info!("Loading bank from {:?}", &root_paths.snapshot_file_path);
     let bank = deserialize_snapshot_data_file(&root_paths.snapshot_file_path, |mut stream| {
-        Ok(match snapshot_version_enum {
-            SnapshotVersion::V1_2_0 => bank_from_stream(
-                SerdeStyle::NEWER,
+        match &incremental_root_paths {
+            None => Ok(bank_from_streams(
+                serde_style,
                 &mut stream,
+                None,
                 &append_vecs_path,
                 account_paths,
                 genesis_config,
                 frozen_account_pubkeys,
                 debug_keys,
                 additional_builtins,
-            ),
-        }?)
+                account_indexes,
+            )?),
+            Some(incremental_root_paths) => {
+                info!(
+                    "Applying incremental snapshot {:?}",
+                    &incremental_root_paths.snapshot_file_path
+                );
+                deserialize_snapshot_data_file(
+                    &incremental_root_paths.snapshot_file_path,
+                    |mut incremental_stream| {
+                        Ok(bank_from_streams(
+                            serde_style,
+                            stream,
+                            Some(&mut incremental_stream),
+                            &append_vecs_path,
+                            account_paths,
+                            genesis_config,
+                            frozen_account_pubkeys,
+                            debug_keys,
+                            additional_builtins,
+                            account_indexes,
+                        )?)
+                    },
+                )
+            }
+        }
     })?;
 
-    let status_cache_path = unpacked_snapshots_dir.join(SNAPSHOT_STATUS_CACHE_FILE_NAME);
+    // The status cache of an incremental snapshot supersedes the full snapshot's
+    let status_cache_path = unpacked_incremental_snapshots_dir
+        .unwrap_or(unpacked_snapshots_dir)
+        .join(SNAPSHOT_STATUS_CACHE_FILE_NAME);
     let slot_deltas = deserialize_snapshot_data_file(&status_cache_path, |stream| {
         info!("Rebuilding status cache...");
         let slot_deltas: Vec<BankSlotDelta> = bincode::options()
@@ -843,6 +1052,36 @@ where
     Ok(bank)
 }
 
+/// Read the bank fields of a full snapshot archive unpacked by `untar_snapshot_in`, without
+/// rebuilding the bank
+pub fn snapshot_fields_from_unpacked_archive(unpack_dir: &Path) -> Result<SnapshotFields> {
+    let snapshot_version = read_snapshot_version(unpack_dir)?;
+    let serde_style = match SnapshotVersion::maybe_from_string(&snapshot_version) {
+        Some(SnapshotVersion::V1_2_0) => SerdeStyle::NEWER,
+        None => {
+            return Err(get_io_error(&format!(
+                "unsupported snapshot version: {}",
+                snapshot_version
+            )))
+        }
+    };
+    let root_paths = get_unpacked_snapshot_paths(&unpack_dir.join(TAR_SNAPSHOTS_DIR))?;
+    deserialize_snapshot_data_file(&root_paths.snapshot_file_path, |stream| {
+        Ok(snapshot_fields_from_stream(serde_style, stream)?)
+    })
+}
+
+// An unpacked snapshot archive holds exactly one bank snapshot
+fn get_unpacked_snapshot_paths(unpacked_snapshots_dir: &PathBuf) -> Result<SlotSnapshotPaths> {
+    let mut snapshot_paths = get_snapshot_paths(unpacked_snapshots_dir);
+    if snapshot_paths.len() > 1 {
+        return Err(get_io_error("invalid snapshot format"));
+    }
+    snapshot_paths
+        .pop()
+        .ok_or_else(|| get_io_error("No snapshots found in snapshots directory"))
+}
+
 fn get_snapshot_file_name(slot: Slot) -> String {
     slot.to_string()
 }
@@ -900,6 +1139,7 @@ pub fn snapshot_bank(
     snapshot_package_output_path: &Path,
     snapshot_version: SnapshotVersion,
     compression: &CompressionType,
+    snapshot_type: SnapshotType,
 ) -> Result<()> {
     let storages: Vec<_> = root_bank.get_snapshot_storages();
     let mut add_snapshot_time = Measure::start("add-snapshot-ms");
@@ -922,6 +1162,7 @@ pub fn snapshot_bank(
         storages,
         compression.clone(),
         snapshot_version,
+        snapshot_type,
     )?;
 
     accounts_package_sender.send(package)?;
@@ -932,8 +1173,14 @@ pub fn snapshot_bank(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{
+        accounts_db::get_temp_accounts_paths,
+        append_vec::AppendVec,
+        genesis_utils::{create_genesis_config, GenesisConfigInfo},
+    };
     use assert_matches::assert_matches;
     use bincode::{deserialize_from, serialize_into};
+    use solana_sdk::signature::{Keypair, Signer};
     use std::mem::size_of;
 
     #[test]
@@ -1057,5 +1304,191 @@ mod tests {
         );
 
         assert!(snapshot_hash_of("invalid").is_none());
+        assert!(snapshot_hash_of(&format!(
+            "incremental-snapshot-42-43-{}.tar",
+            Hash::default()
+        ))
+        .is_none());
+    }
+
+    #[test]
+    fn test_incremental_snapshot_hash_of() {
+        assert_eq!(
+            incremental_snapshot_hash_of(&format!(
+                "incremental-snapshot-42-50-{}.tar.bz2",
+                Hash::default()
+            )),
+            Some((42, 50, Hash::default(), CompressionType::Bzip2))
+        );
+        assert_eq!(
+            incremental_snapshot_hash_of(&format!(
+                "incremental-snapshot-42-50-{}.tar.zst",
+                Hash::default()
+            )),
+            Some((42, 50, Hash::default(), CompressionType::Zstd))
+        );
+
+        // An incremental snapshot must be newer than the full snapshot it is based on
+        assert!(incremental_snapshot_hash_of(&format!(
+            "incremental-snapshot-42-42-{}.tar",
+            Hash::default()
+        ))
+        .is_none());
+        assert!(
+            incremental_snapshot_hash_of(&format!("snapshot-42-{}.tar", Hash::default())).is_none()
+        );
+        assert!(incremental_snapshot_hash_of("invalid").is_none());
+    }
+
+    fn snapshot_bank_for_tests(
+        bank: &Bank,
+        snapshot_path: &Path,
+        snapshot_output_path: &Path,
+        snapshot_type: SnapshotType,
+    ) {
+        bank.squash();
+        bank.update_accounts_hash();
+        let (sender, receiver) = std::sync::mpsc::channel();
+        snapshot_bank(
+            bank,
+            bank.src.slot_deltas(&bank.src.roots()),
+            &sender,
+            snapshot_path,
+            snapshot_output_path,
+            SnapshotVersion::default(),
+            &CompressionType::Bzip2,
+            snapshot_type,
+        )
+        .unwrap();
+        archive_snapshot_package(&receiver.recv().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_bank_from_full_and_incremental_snapshot_archives() {
+        solana_logger::setup();
+        let GenesisConfigInfo {
+            genesis_config,
+            mint_keypair,
+            ..
+        } = create_genesis_config(1_000_000);
+        let key1 = Keypair::new();
+        let key2 = Keypair::new();
+        let key3 = Keypair::new();
+        let snapshot_path = tempfile::TempDir::new().unwrap();
+        let snapshot_output_path = tempfile::TempDir::new().unwrap();
+
+        let bank0 = Arc::new(Bank::new(&genesis_config));
+        bank0.transfer(100, &mint_keypair, &key1.pubkey()).unwrap();
+        bank0.transfer(100, &mint_keypair, &key2.pubkey()).unwrap();
+        while !bank0.is_complete() {
+            bank0.register_tick(&Hash::new_unique());
+        }
+        bank0.freeze();
+        snapshot_bank_for_tests(
+            &bank0,
+            snapshot_path.path(),
+            snapshot_output_path.path(),
+            SnapshotType::FullSnapshot,
+        );
+        bank0
+            .rc
+            .accounts
+            .accounts_db
+            .set_last_full_snapshot_slot(bank0.slot());
+
+        // Drain key1 to zero lamports and fund key3 after the full snapshot
+        let bank1 = Arc::new(Bank::new_from_parent(&bank0, &Pubkey::default(), 1));
+        bank1.transfer(100, &key1, &key3.pubkey()).unwrap();
+        bank1.transfer(50, &mint_keypair, &key3.pubkey()).unwrap();
+        while !bank1.is_complete() {
+            bank1.register_tick(&Hash::new_unique());
+        }
+        bank1.freeze();
+        bank1.clean_accounts(true);
+        snapshot_bank_for_tests(
+            &bank1,
+            snapshot_path.path(),
+            snapshot_output_path.path(),
+            SnapshotType::IncrementalSnapshot(bank0.slot()),
+        );
+
+        // Only the accounts written after the full snapshot go into the incremental archive
+        let (full_archive, (full_slot, _, full_compression)) =
+            get_highest_snapshot_archive_path(snapshot_output_path.path()).unwrap();
+        assert_eq!(full_slot, bank0.slot());
+        let (incremental_archive, (incremental_slot, incremental_hash, incremental_compression)) =
+            get_highest_incremental_snapshot_archive_path(snapshot_output_path.path(), full_slot)
+                .unwrap();
+        assert_eq!(incremental_slot, bank1.slot());
+        assert_eq!(incremental_hash, bank1.get_accounts_hash());
+
+        let (_accounts_dir, account_paths) = get_temp_accounts_paths(1).unwrap();
+        let restore_path = tempfile::TempDir::new().unwrap();
+        let restored_bank = bank_from_archive(
+            &account_paths,
+            &[],
+            &restore_path.path().to_path_buf(),
+            &full_archive,
+            full_compression,
+            Some((&incremental_archive, incremental_compression)),
+            &genesis_config,
+            None,
+            None,
+            HashSet::new(),
+        )
+        .unwrap();
+        assert_eq!(restored_bank.slot(), bank1.slot());
+        assert_eq!(restored_bank.get_balance(&key1.pubkey()), 0);
+        assert_eq!(restored_bank.get_balance(&key2.pubkey()), 100);
+        assert_eq!(restored_bank.get_balance(&key3.pubkey()), 150);
+        assert_eq!(restored_bank.get_accounts_hash(), bank1.get_accounts_hash());
+        assert_eq!(restored_bank, *bank1);
+    }
+
+    #[test]
+    fn test_snapshot_fields_from_unpacked_archive() {
+        solana_logger::setup();
+        let GenesisConfigInfo {
+            genesis_config,
+            mint_keypair,
+            ..
+        } = create_genesis_config(1_000_000);
+        let snapshot_path = tempfile::TempDir::new().unwrap();
+        let snapshot_output_path = tempfile::TempDir::new().unwrap();
+
+        let bank0 = Arc::new(Bank::new(&genesis_config));
+        bank0
+            .transfer(100, &mint_keypair, &solana_sdk::pubkey::new_rand())
+            .unwrap();
+        while !bank0.is_complete() {
+            bank0.register_tick(&Hash::new_unique());
+        }
+        bank0.freeze();
+        snapshot_bank_for_tests(
+            &bank0,
+            snapshot_path.path(),
+            snapshot_output_path.path(),
+            SnapshotType::FullSnapshot,
+        );
+
+        let (archive, (slot, hash, compression)) =
+            get_highest_snapshot_archive_path(snapshot_output_path.path()).unwrap();
+        let unpack_dir = tempfile::TempDir::new().unwrap();
+        untar_snapshot_in(&archive, unpack_dir.path(), compression).unwrap();
+        let fields = snapshot_fields_from_unpacked_archive(unpack_dir.path()).unwrap();
+        assert_eq!(fields.slot, slot);
+        assert_eq!(fields.accounts_hash, hash);
+        assert_eq!(fields.accounts_hash, bank0.get_accounts_hash());
+        assert_eq!(fields.capitalization, bank0.capitalization());
+
+        // Every storage listed in the bank fields was packaged in the archive
+        assert!(!fields.storages.is_empty());
+        for (slot, id, len) in fields.storages {
+            let path = unpack_dir
+                .path()
+                .join(TAR_ACCOUNTS_DIR)
+                .join(AppendVec::new_relative_path(slot, id));
+            assert!(fs::metadata(path).unwrap().len() as usize >= len);
+        }
     }
 }
@@ -1,12 +1,36 @@
-use clap::{crate_description, crate_name, value_t_or_exit, App, Arg};
+use clap::{
+    crate_description, crate_name, value_t_or_exit, App, AppSettings, Arg, ArgMatches, SubCommand,
+};
 use log::*;
-use solana_runtime::append_vec::AppendVec;
+use solana_runtime::{
+    accounts_db::AccountsDB,
+    append_vec::AppendVec,
+    serde_snapshot::SnapshotFields,
+    snapshot_utils::{
+        snapshot_fields_from_unpacked_archive, snapshot_hash_of, untar_snapshot_in,
+        TAR_ACCOUNTS_DIR,
+    },
+};
+use solana_sdk::{clock::Slot, genesis_config::ClusterType, hash::Hash, pubkey::Pubkey};
+use std::{collections::HashMap, path::Path, process::exit};
+
+/// A version of an account found in one of the storages of a snapshot
+struct StoredVersion {
+    slot: Slot,
+    write_version: u64,
+    hash: Hash,
+    lamports: u64,
+    owner: Pubkey,
+    executable: bool,
+    data_len: u64,
+}
 
 fn main() {
     solana_logger::setup_with_default("solana=info");
     let matches = App::new(crate_name!())
         .about(crate_description!())
         .version(solana_version::version!())
+        .setting(AppSettings::ArgsNegateSubcommands)
         .arg(
             Arg::with_name("file")
                 .long("file")
@@ -21,8 +45,51 @@ fn main() {
                 .value_name("LEN")
                 .help("len of store to open"),
         )
+        .subcommand(
+            SubCommand::with_name("verify-snapshot")
+                .about("Verify the storages and accounts hash of a full snapshot archive")
+                .arg(
+                    Arg::with_name("archive")
+                        .index(1)
+                        .required(true)
+                        .takes_value(true)
+                        .value_name("<PATH>")
+                        .help("snapshot archive to open"),
+                )
+                .arg(
+                    Arg::with_name("cluster_type")
+                        .long("cluster-type")
+                        .takes_value(true)
+                        .possible_values(&ClusterType::STRINGS)
+                        .default_value("mainnet-beta")
+                        .help(
+                            "cluster the snapshot was taken on, which selects the account hashing",
+                        ),
+                )
+                .arg(
+                    Arg::with_name("unpack_dir")
+                        .long("unpack-dir")
+                        .takes_value(true)
+                        .value_name("<DIR>")
+                        .help(
+                            "directory to unpack the archive under \
+                             [default: the directory of the archive]",
+                        ),
+                ),
+        )
         .get_matches();
 
+    match matches.subcommand() {
+        ("verify-snapshot", Some(arg_matches)) => {
+            if !verify_snapshot(arg_matches) {
+                exit(1);
+            }
+        }
+        _ => dump_store(&matches),
+    }
+}
+
+fn dump_store(matches: &ArgMatches) {
     let file = value_t_or_exit!(matches, "file", String);
     let len = value_t_or_exit!(matches, "len", usize);
     let mut store = AppendVec::new_empty_map(len);
@@ -43,5 +110,371 @@ fn main() {
     }
 }
 
+/// Returns false if any check of the snapshot failed
+fn verify_snapshot(matches: &ArgMatches) -> bool {
+    let archive = value_t_or_exit!(matches, "archive", String);
+    let archive = Path::new(&archive);
+    let cluster_type = value_t_or_exit!(matches, "cluster_type", ClusterType);
+    let unpack_dir = matches
+        .value_of("unpack_dir")
+        .map(Path::new)
+        .or_else(|| archive.parent())
+        .unwrap_or_else(|| Path::new("."));
+
+    let report = check_snapshot_archive(archive, &cluster_type, unpack_dir).unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        exit(1);
+    });
+    info!(
+        "accounts stored more than once: {}",
+        report.duplicates.len()
+    );
+    for (pubkey, slots) in &report.duplicates {
+        info!("  duplicate: {} slots: {:?}", pubkey, slots);
+    }
+    if report.verified() {
+        info!("snapshot for slot {} verified", report.fields.slot);
+    } else {
+        error!("snapshot for slot {} failed to verify", report.fields.slot);
+    }
+    report.verified()
+}
+
+/// What the checks of a snapshot found
+struct SnapshotReport {
+    fields: SnapshotFields,
+    /// The slot or hash in the archive name differs from the bank fields
+    mismatched_archive_name: bool,
+    invalid_storages: usize,
+    mismatched_hashes: usize,
+    /// Accounts with more than one stored version, which have not been cleaned yet, and the
+    /// slots of those versions
+    duplicates: Vec<(Pubkey, Vec<Slot>)>,
+    mismatched_accounts_hash: bool,
+    mismatched_capitalization: bool,
+}
+
+impl SnapshotReport {
+    fn verified(&self) -> bool {
+        !self.mismatched_archive_name
+            && self.invalid_storages == 0
+            && self.mismatched_hashes == 0
+            && !self.mismatched_accounts_hash
+            && !self.mismatched_capitalization
+    }
+}
+
+/// Unpack a full snapshot archive in a temporary directory under `unpack_dir` and check it
+fn check_snapshot_archive(
+    archive: &Path,
+    cluster_type: &ClusterType,
+    unpack_dir: &Path,
+) -> Result<SnapshotReport, String> {
+    let (archive_slot, archive_hash, compression) = archive
+        .file_name()
+        .and_then(|file_name| snapshot_hash_of(&file_name.to_string_lossy()))
+        .ok_or_else(|| format!("{:?} is not named like a full snapshot archive", archive))?;
+    let unpack_dir = tempfile::Builder::new()
+        .prefix("store-tool-")
+        .tempdir_in(unpack_dir)
+        .map_err(|err| {
+            format!(
+                "Unable to create a directory under {:?}: {}",
+                unpack_dir, err
+            )
+        })?;
+    untar_snapshot_in(archive, unpack_dir.path(), compression)
+        .map_err(|err| format!("Unable to unpack {:?}: {}", archive, err))?;
+
+    let mut report = check_unpacked_snapshot(unpack_dir.path(), cluster_type)?;
+    if (archive_slot, archive_hash) != (report.fields.slot, report.fields.accounts_hash) {
+        error!(
+            "archive name has slot {} and hash {}, which the bank fields do not match",
+            archive_slot, archive_hash
+        );
+        report.mismatched_archive_name = true;
+    }
+    Ok(report)
+}
+
+/// Check the storages and accounts hash of a snapshot archive unpacked in `unpack_dir`
+fn check_unpacked_snapshot(
+    unpack_dir: &Path,
+    cluster_type: &ClusterType,
+) -> Result<SnapshotReport, String> {
+    let fields = snapshot_fields_from_unpacked_archive(unpack_dir)
+        .map_err(|err| format!("Unable to read the bank fields of the snapshot: {}", err))?;
+    info!(
+        "snapshot: slot: {} capitalization: {} accounts hash: {} storages: {}",
+        fields.slot,
+        fields.capitalization,
+        fields.accounts_hash,
+        fields.storages.len()
+    );
+
+    // Validate the layout and hashes of every storage, and gather all the versions of each account
+    let accounts_dir = unpack_dir.join(TAR_ACCOUNTS_DIR);
+    let mut versions: HashMap<Pubkey, Vec<StoredVersion>> = HashMap::new();
+    let mut stored_count = 0;
+    let mut invalid_storages = 0;
+    let mut mismatched_hashes = 0;
+    for (slot, store_id, len) in &fields.storages {
+        let path = accounts_dir.join(AppendVec::new_relative_path(*slot, *store_id));
+        let mut store = AppendVec::new_empty_map(*len);
+        if let Err(err) = store.set_file(&path) {
+            error!("store {:?}: invalid storage of len {}: {}", path, len, err);
+            invalid_storages += 1;
+            continue;
+        }
+        for account in store.accounts(0) {
+            let hash = AccountsDB::hash_stored_account(*slot, &account, cluster_type);
+            if hash != *account.hash {
+                error!(
+                    "store {:?}: account {} version {} has hash {}, expected {}",
+                    path, account.meta.pubkey, account.meta.write_version, account.hash, hash
+                );
+                mismatched_hashes += 1;
+            }
+            stored_count += 1;
+            versions
+                .entry(account.meta.pubkey)
+                .or_default()
+                .push(StoredVersion {
+                    slot: *slot,
+                    write_version: account.meta.write_version,
+                    hash: *account.hash,
+                    lamports: account.account_meta.lamports,
+                    owner: account.account_meta.owner,
+                    executable: account.account_meta.executable,
+                    data_len: account.meta.data_len,
+                });
+        }
+    }
+    info!(
+        "stored accounts: {} distinct accounts: {} mismatched hashes: {}",
+        stored_count,
+        versions.len(),
+        mismatched_hashes
+    );
+
+    // Accounts that are stored more than once have not been cleaned yet, only the newest version
+    // counts towards the accounts hash
+    let mut duplicates: Vec<_> = versions
+        .iter()
+        .filter(|(_pubkey, versions)| versions.len() > 1)
+        .map(|(pubkey, versions)| {
+            let mut slots: Vec<_> = versions.iter().map(|v| v.slot).collect();
+            slots.sort_unstable();
+            (*pubkey, slots)
+        })
+        .collect();
+    duplicates.sort_unstable();
+
+    let mut owners: HashMap<Pubkey, (usize, u64)> = HashMap::new();
+    let hashes: Vec<_> = versions
+        .iter()
+        .filter_map(|(pubkey, versions)| {
+            let newest = versions
+                .iter()
+                .max_by_key(|v| (v.slot, v.write_version))
+                .unwrap();
+            if newest.lamports == 0 {
+                return None;
+            }
+            let owner = owners.entry(newest.owner).or_default();
+            owner.0 += 1;
+            owner.1 += newest.data_len;
+            let balance = AccountsDB::account_balance_for_capitalization(
+                newest.lamports,
+                &newest.owner,
+                newest.executable,
+            );
+            Some((*pubkey, newest.hash, balance))
+        })
+        .collect();
+
+    let mut owners: Vec<_> = owners.into_iter().collect();
+    owners.sort_unstable_by(|a, b| (b.1).1.cmp(&(a.1).1).then(a.0.cmp(&b.0)));
+    info!("live accounts: {} owners: {}", hashes.len(), owners.len());
+    for (owner, (count, data_len)) in owners {
+        info!("  owner: {} accounts: {} data: {}", owner, count, data_len);
+    }
+
+    let (accounts_hash, capitalization) =
+        AccountsDB::accumulate_account_hashes_and_capitalization(hashes, fields.slot, false);
+    let mismatched_accounts_hash = accounts_hash != fields.accounts_hash;
+    if mismatched_accounts_hash {
+        error!(
+            "mismatched accounts hash: {} (calculated) != {} (expected)",
+            accounts_hash, fields.accounts_hash
+        );
+    }
+    let mismatched_capitalization = capitalization != fields.capitalization;
+    if mismatched_capitalization {
+        error!(
+            "mismatched capitalization: {} (calculated) != {} (expected)",
+            capitalization, fields.capitalization
+        );
+    }
+
+    Ok(SnapshotReport {
+        fields,
+        mismatched_archive_name: false,
+        invalid_storages,
+        mismatched_hashes,
+        duplicates,
+        mismatched_accounts_hash,
+        mismatched_capitalization,
+    })
+}
+
 #[cfg(test)]
-pub mod test {}
+mod tests {
+    use super::*;
+    use solana_runtime::{
+        bank::Bank,
+        bank_forks::CompressionType,
+        genesis_utils::{create_genesis_config, GenesisConfigInfo},
+        snapshot_package::SnapshotType,
+        snapshot_utils::{self, SnapshotVersion},
+    };
+    use std::{
+        fs::OpenOptions,
+        io::{Seek, SeekFrom, Write},
+        path::PathBuf,
+        sync::{mpsc::channel, Arc},
+    };
+    use tempfile::TempDir;
+
+    fn freeze(bank: &Bank) {
+        while !bank.is_complete() {
+            bank.register_tick(&Hash::new_unique());
+        }
+        bank.freeze();
+    }
+
+    // Returns the path of the archive
+    fn archive_snapshot(bank: &Bank, snapshot_output_path: &Path) -> PathBuf {
+        let snapshot_path = TempDir::new().unwrap();
+        bank.squash();
+        bank.update_accounts_hash();
+        let (sender, receiver) = channel();
+        snapshot_utils::snapshot_bank(
+            bank,
+            vec![],
+            &sender,
+            snapshot_path.path(),
+            snapshot_output_path,
+            SnapshotVersion::default(),
+            &CompressionType::Bzip2,
+            SnapshotType::FullSnapshot,
+        )
+        .unwrap();
+        snapshot_utils::archive_snapshot_package(&receiver.recv().unwrap()).unwrap();
+        snapshot_utils::get_highest_snapshot_archive_path(snapshot_output_path)
+            .unwrap()
+            .0
+    }
+
+    #[test]
+    fn test_verify_snapshot() {
+        let GenesisConfigInfo {
+            genesis_config,
+            mint_keypair,
+            ..
+        } = create_genesis_config(1_000_000);
+        let bank = Bank::new(&genesis_config);
+        bank.transfer(100, &mint_keypair, &Pubkey::new_unique())
+            .unwrap();
+        freeze(&bank);
+        let snapshot_output_path = TempDir::new().unwrap();
+        let archive = archive_snapshot(&bank, snapshot_output_path.path());
+
+        let unpack_dir = TempDir::new().unwrap();
+        let report =
+            check_snapshot_archive(&archive, &genesis_config.cluster_type, unpack_dir.path())
+                .unwrap();
+        assert!(report.verified());
+        assert_eq!(report.fields.slot, bank.slot());
+        assert_eq!(report.fields.accounts_hash, bank.get_accounts_hash());
+    }
+
+    #[test]
+    fn test_verify_snapshot_corrupted_account_hash() {
+        let GenesisConfigInfo {
+            genesis_config,
+            mint_keypair,
+            ..
+        } = create_genesis_config(1_000_000);
+        let bank = Bank::new(&genesis_config);
+        bank.transfer(100, &mint_keypair, &Pubkey::new_unique())
+            .unwrap();
+        freeze(&bank);
+        let snapshot_output_path = TempDir::new().unwrap();
+        let archive = archive_snapshot(&bank, snapshot_output_path.path());
+        let (_slot, _hash, compression) =
+            snapshot_hash_of(&archive.file_name().unwrap().to_string_lossy()).unwrap();
+        let unpack_dir = TempDir::new().unwrap();
+        untar_snapshot_in(&archive, unpack_dir.path(), compression).unwrap();
+
+        // Flip a bit of the hash stored with the first account of the first storage
+        let fields = snapshot_fields_from_unpacked_archive(unpack_dir.path()).unwrap();
+        let (slot, store_id, len) = fields.storages[0];
+        let path = unpack_dir
+            .path()
+            .join(TAR_ACCOUNTS_DIR)
+            .join(AppendVec::new_relative_path(slot, store_id));
+        let hash_offset = {
+            let mut store = AppendVec::new_empty_map(len);
+            store.set_no_remove_on_drop();
+            store.set_file(&path).unwrap();
+            let (account, _next) = store.get_account(0).unwrap();
+            account.offset + (account.hash as *const Hash as usize)
+                - (account.meta as *const _ as usize)
+        };
+        let mut file = OpenOptions::new().write(true).open(&path).unwrap();
+        let mut corrupted_hash = Hash::default().as_ref().to_vec();
+        corrupted_hash[0] = 1;
+        file.seek(SeekFrom::Start(hash_offset as u64)).unwrap();
+        file.write_all(&corrupted_hash).unwrap();
+        drop(file);
+
+        let report =
+            check_unpacked_snapshot(unpack_dir.path(), &genesis_config.cluster_type).unwrap();
+        assert_eq!(report.mismatched_hashes, 1);
+        assert!(!report.verified());
+    }
+
+    #[test]
+    fn test_verify_snapshot_duplicate_account() {
+        let GenesisConfigInfo {
+            genesis_config,
+            mint_keypair,
+            ..
+        } = create_genesis_config(1_000_000);
+        let pubkey = Pubkey::new_unique();
+        let bank0 = Arc::new(Bank::new(&genesis_config));
+        bank0.transfer(100, &mint_keypair, &pubkey).unwrap();
+        freeze(&bank0);
+
+        // Store the same account again in the next slot, which leaves the balances and the
+        // accounts hash unchanged
+        let bank1 = Bank::new_from_parent(&bank0, &Pubkey::default(), 1);
+        bank1.store_account(&pubkey, &bank0.get_account(&pubkey).unwrap());
+        freeze(&bank1);
+        let snapshot_output_path = TempDir::new().unwrap();
+        let archive = archive_snapshot(&bank1, snapshot_output_path.path());
+
+        let unpack_dir = TempDir::new().unwrap();
+        let report =
+            check_snapshot_archive(&archive, &genesis_config.cluster_type, unpack_dir.path())
+                .unwrap();
+        let (_, slots) = report
+            .duplicates
+            .iter()
+            .find(|(key, _)| *key == pubkey)
+            .unwrap();
+        assert!(slots.contains(&0) && slots.contains(&1));
+        assert!(report.verified());
+    }
+}
@@ -0,0 +1,208 @@
+//! The address lookup table program stores lists of addresses on chain, so that versioned
+//! messages can reference accounts by a one byte index into a table rather than listing the full
+//! address.
+
+crate::declare_id!("AddressLookupTab1e1111111111111111111111111");
+
+use crate::{
+    clock::Slot,
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    system_instruction, sysvar,
+};
+
+/// The maximum number of addresses a lookup table can hold, so that every address can be
+/// referenced by a `u8` index
+pub const LOOKUP_TABLE_MAX_ADDRESSES: usize = 256;
+
+/// The size of the table metadata stored before the addresses
+pub const LOOKUP_TABLE_META_SIZE: usize = 4 // enum discriminant
+    + 1 + 32 // authority
+    + 8 // last_extended_slot
+    + 1 // last_extended_slot_start_index
+    + 8; // addresses length
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Default)]
+pub struct LookupTableMeta {
+    /// Authority allowed to extend or freeze the table, `None` once the table is frozen
+    pub authority: Option<Pubkey>,
+
+    /// The slot the table was last extended in
+    pub last_extended_slot: Slot,
+
+    /// The number of addresses the table held before it was extended in `last_extended_slot`.
+    /// Addresses appended in the current slot can't be looked up until the next one, so that
+    /// leaders and validators replaying the block always see the same table.
+    pub last_extended_slot_start_index: u8,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Default)]
+pub struct AddressLookupTable {
+    pub meta: LookupTableMeta,
+    pub addresses: Vec<Pubkey>,
+}
+
+impl AddressLookupTable {
+    /// The account data size needed to hold `max_addresses` addresses
+    pub fn space(max_addresses: usize) -> usize {
+        LOOKUP_TABLE_META_SIZE + max_addresses * std::mem::size_of::<Pubkey>()
+    }
+
+    /// The addresses that may be looked up at `current_slot`
+    pub fn active_addresses(&self, current_slot: Slot) -> &[Pubkey] {
+        if self.meta.last_extended_slot == current_slot {
+            let start_index = self.meta.last_extended_slot_start_index as usize;
+            &self.addresses[..start_index.min(self.addresses.len())]
+        } else {
+            &self.addresses
+        }
+    }
+
+    /// Look up `indexes` in the table as of `current_slot`, returning `None` if any index isn't
+    /// active
+    pub fn lookup(&self, current_slot: Slot, indexes: &[u8]) -> Option<Vec<Pubkey>> {
+        let active_addresses = self.active_addresses(current_slot);
+        indexes
+            .iter()
+            .map(|index| active_addresses.get(*index as usize).cloned())
+            .collect()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub enum AddressLookupTableState {
+    Uninitialized,
+    LookupTable(AddressLookupTable),
+}
+
+impl Default for AddressLookupTableState {
+    fn default() -> Self {
+        Self::Uninitialized
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub enum AddressLookupTableInstruction {
+    /// Initialize an empty lookup table
+    ///
+    /// # Account references
+    ///   0. [WRITE] Uninitialized lookup table account
+    ///   1. [] Clock sysvar
+    InitializeLookupTable {
+        /// Authority allowed to extend and freeze the table
+        authority: Pubkey,
+    },
+
+    /// Append addresses to a lookup table. The new addresses can be looked up starting with the
+    /// next slot.
+    ///
+    /// # Account references
+    ///   0. [WRITE] Lookup table account
+    ///   1. [SIGNER] Lookup table authority
+    ///   2. [] Clock sysvar
+    ExtendLookupTable { new_addresses: Vec<Pubkey> },
+
+    /// Permanently remove the lookup table's authority so that it can no longer be extended
+    ///
+    /// # Account references
+    ///   0. [WRITE] Lookup table account
+    ///   1. [SIGNER] Lookup table authority
+    FreezeLookupTable,
+}
+
+/// Create and initialize a lookup table account able to hold up to `max_addresses` addresses
+pub fn create_lookup_table(
+    payer: &Pubkey,
+    lookup_table: &Pubkey,
+    authority: &Pubkey,
+    lamports: u64,
+    max_addresses: usize,
+) -> Vec<Instruction> {
+    vec![
+        system_instruction::create_account(
+            payer,
+            lookup_table,
+            lamports,
+            AddressLookupTable::space(max_addresses) as u64,
+            &id(),
+        ),
+        Instruction::new(
+            id(),
+            &AddressLookupTableInstruction::InitializeLookupTable {
+                authority: *authority,
+            },
+            vec![
+                AccountMeta::new(*lookup_table, false),
+                AccountMeta::new_readonly(sysvar::clock::id(), false),
+            ],
+        ),
+    ]
+}
+
+pub fn extend_lookup_table(
+    lookup_table: &Pubkey,
+    authority: &Pubkey,
+    new_addresses: Vec<Pubkey>,
+) -> Instruction {
+    Instruction::new(
+        id(),
+        &AddressLookupTableInstruction::ExtendLookupTable { new_addresses },
+        vec![
+            AccountMeta::new(*lookup_table, false),
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ],
+    )
+}
+
+pub fn freeze_lookup_table(lookup_table: &Pubkey, authority: &Pubkey) -> Instruction {
+    Instruction::new(
+        id(),
+        &AddressLookupTableInstruction::FreezeLookupTable,
+        vec![
+            AccountMeta::new(*lookup_table, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_space() {
+        let table = AddressLookupTableState::LookupTable(AddressLookupTable {
+            meta: LookupTableMeta {
+                authority: Some(Pubkey::new_unique()),
+                ..LookupTableMeta::default()
+            },
+            addresses: vec![Pubkey::new_unique(); 3],
+        });
+        assert_eq!(
+            bincode::serialized_size(&table).unwrap() as usize,
+            AddressLookupTable::space(3)
+        );
+    }
+
+    #[test]
+    fn test_active_addresses() {
+        let addresses: Vec<_> = (0..4).map(|_| Pubkey::new_unique()).collect();
+        let table = AddressLookupTable {
+            meta: LookupTableMeta {
+                authority: None,
+                last_extended_slot: 5,
+                last_extended_slot_start_index: 2,
+            },
+            addresses: addresses.clone(),
+        };
+        assert_eq!(table.active_addresses(5), &addresses[..2]);
+        assert_eq!(table.active_addresses(6), &addresses[..]);
+        assert_eq!(
+            table.lookup(5, &[1, 0]),
+            Some(vec![addresses[1], addresses[0]])
+        );
+        assert_eq!(table.lookup(5, &[2]), None);
+        assert_eq!(table.lookup(6, &[3]), Some(vec![addresses[3]]));
+    }
+}
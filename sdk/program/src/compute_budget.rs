@@ -0,0 +1,121 @@
+//! The compute budget program lets a transaction request a transaction-wide compute budget in
+//! place of the default per-instruction budget, and pay for the requested units.
+
+crate::declare_id!("ComputeBudget111111111111111111111111111111");
+
+use crate::{instruction::Instruction, message::Message};
+
+/// The largest compute budget a transaction may request
+pub const MAX_UNITS: u32 = 1_400_000;
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ComputeBudgetInstruction {
+    /// Request a compute budget of `units` shared by all of the transaction's instructions.
+    ///
+    /// The fee payer is charged `units * lamports_per_unit` on top of the signature fee,
+    /// whether or not the units are consumed. Units beyond `MAX_UNITS` are not charged for.
+    ///
+    /// # Account references
+    ///   None
+    RequestUnits {
+        /// Compute units available to the whole transaction
+        units: u32,
+
+        /// Price paid for each requested unit
+        lamports_per_unit: u64,
+    },
+}
+
+/// Create a `ComputeBudgetInstruction::RequestUnits` instruction
+pub fn request_units(units: u32, lamports_per_unit: u64) -> Instruction {
+    Instruction::new(
+        id(),
+        &ComputeBudgetInstruction::RequestUnits {
+            units,
+            lamports_per_unit,
+        },
+        vec![],
+    )
+}
+
+/// Return the `(units, lamports_per_unit)` requested by the message, if any.
+///
+/// Only the first well-formed request is honored, later ones are ignored
+pub fn get_requested_units(message: &Message) -> Option<(u32, u64)> {
+    message.instructions.iter().find_map(|instruction| {
+        let program_index = instruction.program_id_index as usize;
+        // Message may not be sanitized here
+        let program_id = message.account_keys.get(program_index)?;
+        if !check_id(program_id) {
+            return None;
+        }
+        match bincode::deserialize(&instruction.data).ok()? {
+            ComputeBudgetInstruction::RequestUnits {
+                units,
+                lamports_per_unit,
+            } => Some((units, lamports_per_unit)),
+        }
+    })
+}
+
+/// The fee charged for the requested units. Requests beyond `MAX_UNITS` are charged as
+/// `MAX_UNITS`, since no more units can be used.
+pub fn requested_units_fee(units: u32, lamports_per_unit: u64) -> u64 {
+    u64::from(units.min(MAX_UNITS)).saturating_mul(lamports_per_unit)
+}
+
+/// The price paid per requested unit, which transactions are prioritized by. A request for no
+/// units pays nothing, whatever its price.
+pub fn requested_units_price(units: u32, lamports_per_unit: u64) -> u64 {
+    if units == 0 {
+        0
+    } else {
+        lamports_per_unit
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{pubkey::Pubkey, system_instruction};
+
+    #[test]
+    fn test_get_requested_units() {
+        let payer = Pubkey::new_unique();
+        let transfer = system_instruction::transfer(&payer, &Pubkey::new_unique(), 1);
+
+        let message = Message::new(&[transfer.clone()], Some(&payer));
+        assert_eq!(get_requested_units(&message), None);
+
+        let message = Message::new(
+            &[
+                transfer.clone(),
+                request_units(300_000, 2),
+                request_units(400_000, 3),
+            ],
+            Some(&payer),
+        );
+        assert_eq!(get_requested_units(&message), Some((300_000, 2)));
+
+        // Malformed requests are skipped
+        let malformed = Instruction::new(id(), &0u8, vec![]);
+        let message = Message::new(
+            &[malformed, transfer, request_units(400_000, 3)],
+            Some(&payer),
+        );
+        assert_eq!(get_requested_units(&message), Some((400_000, 3)));
+    }
+
+    #[test]
+    fn test_requested_units_fee() {
+        assert_eq!(requested_units_fee(300_000, 2), 600_000);
+        assert_eq!(
+            requested_units_fee(MAX_UNITS + 1, 2),
+            u64::from(MAX_UNITS) * 2
+        );
+        assert_eq!(requested_units_fee(u32::MAX, u64::MAX), u64::MAX);
+
+        assert_eq!(requested_units_price(300_000, 2), 2);
+        assert_eq!(requested_units_price(0, u64::MAX), 0);
+    }
+}
@@ -1,4 +1,5 @@
 use crate::clock::{DEFAULT_TICKS_PER_SECOND, DEFAULT_TICKS_PER_SLOT};
+use crate::compute_budget;
 use crate::message::Message;
 use crate::secp256k1_program;
 use log::*;
@@ -21,12 +22,16 @@ impl Default for FeeCalculator {
 
 pub struct FeeConfig {
     pub secp256k1_program_enabled: bool,
+    pub compute_budget_program_enabled: bool,
 }
 
+/// Defaults to the features that are active on every cluster, callers with a bank should build
+/// it from the features active on the bank instead
 impl Default for FeeConfig {
     fn default() -> Self {
         Self {
             secp256k1_program_enabled: true,
+            compute_budget_program_enabled: false,
         }
     }
 }
@@ -57,8 +62,19 @@ impl FeeCalculator {
             }
         }
 
-        self.lamports_per_signature
-            * (u64::from(message.header.num_required_signatures) + num_secp256k1_signatures)
+        let compute_fee = if fee_config.compute_budget_program_enabled {
+            compute_budget::get_requested_units(message)
+                .map(|(units, lamports_per_unit)| {
+                    compute_budget::requested_units_fee(units, lamports_per_unit)
+                })
+                .unwrap_or_default()
+        } else {
+            0
+        };
+
+        (self.lamports_per_signature
+            * (u64::from(message.header.num_required_signatures) + num_secp256k1_signatures))
+            .saturating_add(compute_fee)
     }
 }
 
@@ -263,7 +279,8 @@ mod tests {
             FeeCalculator::new(1).calculate_fee_with_config(
                 &message,
                 &FeeConfig {
-                    secp256k1_program_enabled: false
+                    secp256k1_program_enabled: false,
+                    ..FeeConfig::default()
                 }
             ),
             1
@@ -275,6 +292,43 @@ mod tests {
         assert_eq!(FeeCalculator::new(1).calculate_fee(&message), 11);
     }
 
+    #[test]
+    fn test_fee_calculator_calculate_fee_compute_budget() {
+        let pubkey0 = Pubkey::new(&[0; 32]);
+        let pubkey1 = Pubkey::new(&[1; 32]);
+        let ix0 = system_instruction::transfer(&pubkey0, &pubkey1, 1);
+        let ix1 = compute_budget::request_units(300_000, 2);
+
+        let fee_config = FeeConfig {
+            compute_budget_program_enabled: true,
+            ..FeeConfig::default()
+        };
+
+        let message = Message::new(&[ix0, ix1], Some(&pubkey0));
+        assert_eq!(
+            FeeCalculator::new(5).calculate_fee_with_config(&message, &fee_config),
+            600_005
+        );
+        // Nothing is charged for compute until the feature is active
+        assert_eq!(FeeCalculator::new(5).calculate_fee(&message), 5);
+
+        // Units beyond the maximum aren't charged for
+        let ix0 = compute_budget::request_units(compute_budget::MAX_UNITS + 1, 2);
+        let message = Message::new(&[ix0], Some(&pubkey0));
+        assert_eq!(
+            FeeCalculator::new(5).calculate_fee_with_config(&message, &fee_config),
+            u64::from(compute_budget::MAX_UNITS) * 2 + 5
+        );
+
+        // The compute fee saturates rather than overflowing
+        let ix0 = compute_budget::request_units(u32::MAX, u64::MAX);
+        let message = Message::new(&[ix0], Some(&pubkey0));
+        assert_eq!(
+            FeeCalculator::new(5).calculate_fee_with_config(&message, &fee_config),
+            u64::MAX
+        );
+    }
+
     #[test]
     fn test_fee_rate_governor_derived_default() {
         solana_logger::setup();
@@ -167,6 +167,18 @@ pub enum InstructionError {
     /// Computational budget exceeded
     #[error("Computational budget exceeded")]
     ComputationalBudgetExceeded,
+
+    /// Cross-program invocation with unauthorized signer or writable account
+    #[error("Cross-program invocation with unauthorized signer or writable account")]
+    PrivilegeEscalation,
+
+    /// Account is immutable
+    #[error("Account is immutable")]
+    Immutable,
+
+    /// Incorrect authority provided
+    #[error("Incorrect authority provided")]
+    IncorrectAuthority,
 }
 
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
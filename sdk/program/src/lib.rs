@@ -5,9 +5,12 @@
 extern crate self as solana_program;
 
 pub mod account_info;
+pub mod address_lookup_table;
 pub mod bpf_loader;
 pub mod bpf_loader_deprecated;
+pub mod bpf_loader_upgradeable;
 pub mod clock;
+pub mod compute_budget;
 pub mod decode_error;
 pub mod entrypoint;
 pub mod entrypoint_deprecated;
@@ -18,6 +21,7 @@ pub mod hash;
 pub mod incinerator;
 pub mod instruction;
 pub mod loader_instruction;
+pub mod loader_upgradeable_instruction;
 pub mod log;
 pub mod message;
 pub mod native_token;
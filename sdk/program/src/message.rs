@@ -13,6 +13,11 @@ use crate::{
 use itertools::Itertools;
 use std::convert::TryFrom;
 
+pub mod v0;
+mod versions;
+
+pub use versions::{VersionedMessage, MESSAGE_VERSION_0, MESSAGE_VERSION_PREFIX};
+
 fn position(keys: &[Pubkey], key: &Pubkey) -> u8 {
     keys.iter().position(|k| k == key).unwrap() as u8
 }
@@ -0,0 +1,407 @@
+//! Version 0 messages, which can load some of their account keys from on-chain address lookup
+//! tables instead of listing them all inline
+
+use crate::{
+    hash::Hash,
+    instruction::{CompiledInstruction, Instruction},
+    message::{self, compile_instructions, get_keys, get_program_ids, InstructionKeys},
+    pubkey::Pubkey,
+    sanitize::{Sanitize, SanitizeError},
+    short_vec,
+};
+use std::{collections::HashMap, convert::TryFrom};
+
+pub use message::MessageHeader;
+
+/// Account keys loaded from an on-chain address lookup table
+#[derive(Serialize, Deserialize, Default, Debug, PartialEq, Eq, Clone, AbiExample)]
+#[serde(rename_all = "camelCase")]
+pub struct MessageAddressTableLookup {
+    /// Address of the lookup table account
+    pub account_key: Pubkey,
+
+    /// Indexes of the table's addresses loaded as writable accounts
+    #[serde(with = "short_vec")]
+    pub writable_indexes: Vec<u8>,
+
+    /// Indexes of the table's addresses loaded as read-only accounts
+    #[serde(with = "short_vec")]
+    pub readonly_indexes: Vec<u8>,
+}
+
+/// A message whose instructions index into its `account_keys`, followed by the writable and
+/// then the read-only addresses loaded through its `address_table_lookups`
+#[frozen_abi(digest = "FBMKpxKC2fchXNEsfvTXNSvV4QinqJRGnpeL1e3pjAnU")]
+#[derive(Serialize, Deserialize, Default, Debug, PartialEq, Eq, Clone, AbiExample)]
+#[serde(rename_all = "camelCase")]
+pub struct Message {
+    /// The message header, identifying signed and read-only `account_keys`. Loaded addresses
+    /// are never signers, their lookup tells whether they are writable.
+    /// NOTE: Serialization-related changes must be paired with the direct read at sigverify.
+    pub header: MessageHeader,
+
+    /// The account keys listed in the message itself
+    #[serde(with = "short_vec")]
+    pub account_keys: Vec<Pubkey>,
+
+    /// The id of a recent ledger entry.
+    pub recent_blockhash: Hash,
+
+    /// Programs that will be executed in sequence and committed in one atomic transaction if all
+    /// succeed.
+    #[serde(with = "short_vec")]
+    pub instructions: Vec<CompiledInstruction>,
+
+    /// Address lookup tables the remaining account keys are loaded from
+    #[serde(with = "short_vec")]
+    pub address_table_lookups: Vec<MessageAddressTableLookup>,
+}
+
+impl Sanitize for Message {
+    fn sanitize(&self) -> std::result::Result<(), SanitizeError> {
+        let num_static_account_keys = self.account_keys.len();
+        // signing area and read-only non-signing area should not overlap
+        if self.header.num_required_signatures as usize
+            + self.header.num_readonly_unsigned_accounts as usize
+            > num_static_account_keys
+        {
+            return Err(SanitizeError::IndexOutOfBounds);
+        }
+
+        // there should be at least 1 RW fee-payer account.
+        if self.header.num_readonly_signed_accounts >= self.header.num_required_signatures {
+            return Err(SanitizeError::IndexOutOfBounds);
+        }
+
+        let mut num_loaded_accounts = 0;
+        for lookup in &self.address_table_lookups {
+            // each lookup table must be used to load at least one account
+            let num_indexes = lookup.writable_indexes.len() + lookup.readonly_indexes.len();
+            if num_indexes == 0 {
+                return Err(SanitizeError::InvalidValue);
+            }
+            num_loaded_accounts += num_indexes;
+        }
+
+        // account indexes must fit in a u8
+        let num_account_keys = num_static_account_keys + num_loaded_accounts;
+        if num_account_keys > 256 {
+            return Err(SanitizeError::IndexOutOfBounds);
+        }
+
+        for ci in &self.instructions {
+            // Programs can't be loaded from a lookup table
+            if ci.program_id_index as usize >= num_static_account_keys {
+                return Err(SanitizeError::IndexOutOfBounds);
+            }
+            // A program cannot be a payer.
+            if ci.program_id_index == 0 {
+                return Err(SanitizeError::IndexOutOfBounds);
+            }
+            for ai in &ci.accounts {
+                if *ai as usize >= num_account_keys {
+                    return Err(SanitizeError::IndexOutOfBounds);
+                }
+            }
+        }
+        self.account_keys.sanitize()?;
+        self.recent_blockhash.sanitize()?;
+        self.instructions.sanitize()?;
+        Ok(())
+    }
+}
+
+impl Message {
+    /// Create a message that loads the unsigned, non-program accounts found in
+    /// `address_lookup_tables`, given as `(table address, table addresses)` pairs, from those
+    /// tables instead of listing them inline
+    pub fn new_with_address_table_lookups(
+        instructions: &[Instruction],
+        payer: Option<&Pubkey>,
+        address_lookup_tables: &[(Pubkey, Vec<Pubkey>)],
+    ) -> Self {
+        let InstructionKeys {
+            signed_keys,
+            unsigned_keys,
+            num_readonly_signed_accounts,
+            num_readonly_unsigned_accounts,
+        } = get_keys(instructions, payer);
+        let num_required_signatures = signed_keys.len() as u8;
+        let num_writable_unsigned_keys =
+            unsigned_keys.len() - num_readonly_unsigned_accounts as usize;
+        let program_ids = get_program_ids(instructions);
+
+        let mut account_keys = signed_keys;
+        let mut num_readonly_unsigned_accounts = num_readonly_unsigned_accounts;
+        let mut address_table_lookups: Vec<MessageAddressTableLookup> = address_lookup_tables
+            .iter()
+            .map(|(account_key, _)| MessageAddressTableLookup {
+                account_key: *account_key,
+                ..MessageAddressTableLookup::default()
+            })
+            .collect();
+        for (i, key) in unsigned_keys.into_iter().enumerate() {
+            let is_writable = i < num_writable_unsigned_keys;
+            let table_entry = if program_ids.contains(&key) {
+                None
+            } else {
+                address_lookup_tables.iter().enumerate().find_map(
+                    |(table_index, (_, addresses))| {
+                        let index = addresses.iter().position(|address| address == &key)?;
+                        u8::try_from(index).ok().map(|index| (table_index, index))
+                    },
+                )
+            };
+            match table_entry {
+                Some((table_index, index)) => {
+                    let lookup = &mut address_table_lookups[table_index];
+                    if is_writable {
+                        lookup.writable_indexes.push(index);
+                    } else {
+                        lookup.readonly_indexes.push(index);
+                        num_readonly_unsigned_accounts -= 1;
+                    }
+                }
+                None => account_keys.push(key),
+            }
+        }
+        address_table_lookups.retain(|lookup| {
+            !lookup.writable_indexes.is_empty() || !lookup.readonly_indexes.is_empty()
+        });
+
+        let table_addresses: HashMap<_, _> = address_lookup_tables.iter().cloned().collect();
+        let loaded_addresses = LoadedAddresses::from_lookups(&address_table_lookups, |lookup| {
+            &table_addresses[&lookup.account_key]
+        });
+        let mut all_account_keys = account_keys.clone();
+        all_account_keys.extend(&loaded_addresses.writable);
+        all_account_keys.extend(&loaded_addresses.readonly);
+        let instructions = compile_instructions(instructions, &all_account_keys);
+        Self {
+            header: MessageHeader {
+                num_required_signatures,
+                num_readonly_signed_accounts,
+                num_readonly_unsigned_accounts,
+            },
+            account_keys,
+            recent_blockhash: Hash::default(),
+            instructions,
+            address_table_lookups,
+        }
+    }
+
+    /// Whether the account listed at index `i` of `account_keys` is writable
+    fn is_writable_static(&self, i: usize) -> bool {
+        i < (self.header.num_required_signatures - self.header.num_readonly_signed_accounts)
+            as usize
+            || (i >= self.header.num_required_signatures as usize
+                && i < self.account_keys.len()
+                    - self.header.num_readonly_unsigned_accounts as usize)
+    }
+}
+
+/// The addresses a message loaded from its address lookup tables
+#[derive(Serialize, Deserialize, Default, Debug, PartialEq, Eq, Clone, AbiExample)]
+pub struct LoadedAddresses {
+    /// Addresses loaded as writable accounts, in lookup order
+    pub writable: Vec<Pubkey>,
+
+    /// Addresses loaded as read-only accounts, in lookup order
+    pub readonly: Vec<Pubkey>,
+}
+
+impl LoadedAddresses {
+    /// Collect the addresses selected by `lookups` from the table addresses `table_addresses`
+    /// returns for each lookup
+    fn from_lookups<'a, F>(lookups: &[MessageAddressTableLookup], table_addresses: F) -> Self
+    where
+        F: Fn(&MessageAddressTableLookup) -> &'a Vec<Pubkey>,
+    {
+        let mut loaded_addresses = Self::default();
+        for lookup in lookups {
+            let addresses = table_addresses(lookup);
+            let select = |indexes: &[u8]| {
+                indexes
+                    .iter()
+                    .map(|index| addresses[*index as usize])
+                    .collect::<Vec<_>>()
+            };
+            loaded_addresses
+                .writable
+                .extend(select(&lookup.writable_indexes));
+            loaded_addresses
+                .readonly
+                .extend(select(&lookup.readonly_indexes));
+        }
+        loaded_addresses
+    }
+
+    pub fn len(&self) -> usize {
+        self.writable.len() + self.readonly.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.writable.is_empty() && self.readonly.is_empty()
+    }
+}
+
+/// A version 0 message along with the addresses loaded from its address lookup tables
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct LoadedMessage {
+    pub message: Message,
+    pub loaded_addresses: LoadedAddresses,
+}
+
+impl Sanitize for LoadedMessage {
+    fn sanitize(&self) -> std::result::Result<(), SanitizeError> {
+        self.message.sanitize()?;
+        // loaded addresses must match the lookups they were loaded from
+        let (num_writable, num_readonly) = self.message.address_table_lookups.iter().fold(
+            (0, 0),
+            |(num_writable, num_readonly), lookup| {
+                (
+                    num_writable + lookup.writable_indexes.len(),
+                    num_readonly + lookup.readonly_indexes.len(),
+                )
+            },
+        );
+        if self.loaded_addresses.writable.len() != num_writable
+            || self.loaded_addresses.readonly.len() != num_readonly
+        {
+            return Err(SanitizeError::InvalidValue);
+        }
+        Ok(())
+    }
+}
+
+impl LoadedMessage {
+    /// All the account keys used by the message, in the order its instructions index them
+    pub fn account_keys(&self) -> impl Iterator<Item = &Pubkey> {
+        self.message
+            .account_keys
+            .iter()
+            .chain(&self.loaded_addresses.writable)
+            .chain(&self.loaded_addresses.readonly)
+    }
+
+    pub fn is_writable(&self, i: usize) -> bool {
+        let num_static_account_keys = self.message.account_keys.len();
+        if i < num_static_account_keys {
+            self.message.is_writable_static(i)
+        } else {
+            i - num_static_account_keys < self.loaded_addresses.writable.len()
+        }
+    }
+
+    pub fn is_signer(&self, i: usize) -> bool {
+        i < self.message.header.num_required_signatures as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instruction::AccountMeta;
+
+    fn new_message_with_lookups() -> (Message, Pubkey, Vec<Pubkey>) {
+        let payer = Pubkey::new_unique();
+        let program_id = Pubkey::new_unique();
+        let table_key = Pubkey::new_unique();
+        let mut table_addresses: Vec<_> = (0..3).map(|_| Pubkey::new_unique()).collect();
+        // Programs are never loaded from a table
+        table_addresses.push(program_id);
+        let static_key = Pubkey::new_unique();
+        let instructions = vec![Instruction::new(
+            program_id,
+            &0,
+            vec![
+                AccountMeta::new(table_addresses[2], false),
+                AccountMeta::new_readonly(table_addresses[1], false),
+                AccountMeta::new(static_key, false),
+            ],
+        )];
+        let message = Message::new_with_address_table_lookups(
+            &instructions,
+            Some(&payer),
+            &[
+                (Pubkey::new_unique(), vec![]),
+                (table_key, table_addresses.clone()),
+            ],
+        );
+        (message, table_key, table_addresses)
+    }
+
+    fn load(message: Message, table_addresses: &[Pubkey]) -> LoadedMessage {
+        let table_addresses = table_addresses.to_vec();
+        let loaded_addresses =
+            LoadedAddresses::from_lookups(&message.address_table_lookups, |_| &table_addresses);
+        LoadedMessage {
+            message,
+            loaded_addresses,
+        }
+    }
+
+    #[test]
+    fn test_new_with_address_table_lookups() {
+        let (message, table_key, table_addresses) = new_message_with_lookups();
+        assert_eq!(
+            message.address_table_lookups,
+            vec![MessageAddressTableLookup {
+                account_key: table_key,
+                writable_indexes: vec![2],
+                readonly_indexes: vec![1],
+            }]
+        );
+        // The payer, the static writable key and the program id
+        assert_eq!(message.account_keys.len(), 3);
+        assert_eq!(message.account_keys[2], table_addresses[3]);
+        assert_eq!(message.header.num_readonly_unsigned_accounts, 1);
+        assert_eq!(message.instructions[0].accounts, vec![3, 4, 1]);
+        assert_eq!(message.sanitize(), Ok(()));
+
+        let loaded_message = load(message, &table_addresses);
+        assert_eq!(
+            loaded_message.loaded_addresses,
+            LoadedAddresses {
+                writable: vec![table_addresses[2]],
+                readonly: vec![table_addresses[1]],
+            }
+        );
+        assert_eq!(loaded_message.sanitize(), Ok(()));
+        assert_eq!(loaded_message.account_keys().count(), 5);
+        assert!(loaded_message.is_writable(0));
+        assert!(loaded_message.is_writable(1));
+        assert!(!loaded_message.is_writable(2));
+        assert!(loaded_message.is_writable(3));
+        assert!(!loaded_message.is_writable(4));
+        assert!(loaded_message.is_signer(0));
+        assert!(!loaded_message.is_signer(3));
+    }
+
+    #[test]
+    fn test_sanitize_address_table_lookups() {
+        let (message, _, table_addresses) = new_message_with_lookups();
+
+        // Programs can't be loaded from a lookup table
+        let mut invalid = message.clone();
+        invalid.instructions[0].program_id_index = 3;
+        assert_eq!(invalid.sanitize(), Err(SanitizeError::IndexOutOfBounds));
+
+        // Instructions may only reference loaded accounts that exist
+        let mut invalid = message.clone();
+        invalid.instructions[0].accounts.push(5);
+        assert_eq!(invalid.sanitize(), Err(SanitizeError::IndexOutOfBounds));
+
+        // Empty lookups are rejected
+        let mut invalid = message.clone();
+        invalid
+            .address_table_lookups
+            .push(MessageAddressTableLookup::default());
+        assert_eq!(invalid.sanitize(), Err(SanitizeError::InvalidValue));
+
+        // Loaded addresses must match the lookups
+        let mut invalid = load(message, &table_addresses);
+        invalid.loaded_addresses.writable.push(Pubkey::new_unique());
+        assert_eq!(invalid.sanitize(), Err(SanitizeError::InvalidValue));
+    }
+}
@@ -0,0 +1,270 @@
+use crate::{
+    hash::Hash,
+    instruction::CompiledInstruction,
+    message::{v0, Message, MessageHeader},
+    pubkey::Pubkey,
+    sanitize::{Sanitize, SanitizeError},
+    short_vec::ShortVec,
+};
+use serde::{
+    de::{self, Deserializer, SeqAccess, Visitor},
+    ser::{SerializeTuple, Serializer},
+    Deserialize, Serialize,
+};
+use std::fmt;
+
+/// Bit set in the first byte of a versioned message. Legacy messages start with
+/// `MessageHeader::num_required_signatures`, which can never have this bit set in a valid
+/// transaction.
+/// NOTE: Serialization-related changes must be paired with the direct read at sigverify.
+pub const MESSAGE_VERSION_PREFIX: u8 = 0x80;
+
+/// The only message version currently supported, besides the legacy format
+pub const MESSAGE_VERSION_0: u8 = 0;
+
+/// Either a legacy message or a versioned message. Versioned messages are serialized as
+/// `MESSAGE_VERSION_PREFIX | version` followed by the message of that version.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum VersionedMessage {
+    Legacy(Message),
+    V0(v0::Message),
+}
+
+impl Default for VersionedMessage {
+    fn default() -> Self {
+        Self::Legacy(Message::default())
+    }
+}
+
+impl From<Message> for VersionedMessage {
+    fn from(message: Message) -> Self {
+        Self::Legacy(message)
+    }
+}
+
+impl Sanitize for VersionedMessage {
+    fn sanitize(&self) -> std::result::Result<(), SanitizeError> {
+        match self {
+            Self::Legacy(message) => message.sanitize(),
+            Self::V0(message) => message.sanitize(),
+        }
+    }
+}
+
+impl VersionedMessage {
+    pub fn header(&self) -> &MessageHeader {
+        match self {
+            Self::Legacy(message) => &message.header,
+            Self::V0(message) => &message.header,
+        }
+    }
+
+    /// The account keys listed in the message, without any loaded from address lookup tables
+    pub fn static_account_keys(&self) -> &[Pubkey] {
+        match self {
+            Self::Legacy(message) => &message.account_keys,
+            Self::V0(message) => &message.account_keys,
+        }
+    }
+
+    pub fn recent_blockhash(&self) -> &Hash {
+        match self {
+            Self::Legacy(message) => &message.recent_blockhash,
+            Self::V0(message) => &message.recent_blockhash,
+        }
+    }
+
+    pub fn set_recent_blockhash(&mut self, recent_blockhash: Hash) {
+        match self {
+            Self::Legacy(message) => message.recent_blockhash = recent_blockhash,
+            Self::V0(message) => message.recent_blockhash = recent_blockhash,
+        }
+    }
+
+    pub fn instructions(&self) -> &[CompiledInstruction] {
+        match self {
+            Self::Legacy(message) => &message.instructions,
+            Self::V0(message) => &message.instructions,
+        }
+    }
+
+    pub fn serialize(&self) -> Vec<u8> {
+        bincode::serialize(self).unwrap()
+    }
+}
+
+impl Serialize for VersionedMessage {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Self::Legacy(message) => Serialize::serialize(message, serializer),
+            Self::V0(message) => {
+                let mut seq = serializer.serialize_tuple(2)?;
+                seq.serialize_element(&(MESSAGE_VERSION_PREFIX | MESSAGE_VERSION_0))?;
+                seq.serialize_element(message)?;
+                seq.end()
+            }
+        }
+    }
+}
+
+struct MessageVisitor;
+
+impl<'de> Visitor<'de> for MessageVisitor {
+    type Value = VersionedMessage;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a legacy or versioned message")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<VersionedMessage, A::Error> {
+        let prefix: u8 = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+        if prefix & MESSAGE_VERSION_PREFIX != 0 {
+            let version = prefix & !MESSAGE_VERSION_PREFIX;
+            if version != MESSAGE_VERSION_0 {
+                return Err(de::Error::custom(format!(
+                    "unsupported message version: {}",
+                    version
+                )));
+            }
+            let message: v0::Message = seq
+                .next_element()?
+                .ok_or_else(|| de::Error::invalid_length(1, &self))?;
+            return Ok(VersionedMessage::V0(message));
+        }
+
+        let num_readonly_signed_accounts: u8 = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(1, &self))?;
+        let num_readonly_unsigned_accounts: u8 = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(2, &self))?;
+        let account_keys: ShortVec<Pubkey> = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(3, &self))?;
+        let recent_blockhash: Hash = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(4, &self))?;
+        let instructions: ShortVec<CompiledInstruction> = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(5, &self))?;
+        Ok(VersionedMessage::Legacy(Message {
+            header: MessageHeader {
+                num_required_signatures: prefix,
+                num_readonly_signed_accounts,
+                num_readonly_unsigned_accounts,
+            },
+            account_keys: account_keys.0,
+            recent_blockhash,
+            instructions: instructions.0,
+        }))
+    }
+}
+
+// Mirrors what `Serialize` writes for formats like JSON, where a legacy message is a struct and
+// a versioned one a sequence
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum HumanReadableMessage {
+    Legacy(Message),
+    Versioned(u8, v0::Message),
+}
+
+impl<'de> Deserialize<'de> for VersionedMessage {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<VersionedMessage, D::Error> {
+        if !deserializer.is_human_readable() {
+            // A legacy message is its three header bytes followed by three more fields, while a
+            // versioned one is a prefix byte followed by the message
+            return deserializer.deserialize_tuple(6, MessageVisitor);
+        }
+        match HumanReadableMessage::deserialize(deserializer)? {
+            HumanReadableMessage::Legacy(message) => Ok(VersionedMessage::Legacy(message)),
+            HumanReadableMessage::Versioned(prefix, message) => {
+                if prefix != MESSAGE_VERSION_PREFIX | MESSAGE_VERSION_0 {
+                    return Err(de::Error::custom(format!(
+                        "unsupported message version: {}",
+                        prefix & !MESSAGE_VERSION_PREFIX
+                    )));
+                }
+                Ok(VersionedMessage::V0(message))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instruction::{AccountMeta, Instruction};
+
+    fn new_v0_message() -> v0::Message {
+        let payer = Pubkey::new_unique();
+        let table_address = Pubkey::new_unique();
+        let instructions = vec![Instruction::new(
+            Pubkey::new_unique(),
+            &0,
+            vec![
+                AccountMeta::new(table_address, false),
+                AccountMeta::new(Pubkey::new_unique(), false),
+            ],
+        )];
+        v0::Message::new_with_address_table_lookups(
+            &instructions,
+            Some(&payer),
+            &[(Pubkey::new_unique(), vec![table_address])],
+        )
+    }
+
+    #[test]
+    fn test_legacy_message_serialization() {
+        let program_id = Pubkey::new_unique();
+        let id0 = Pubkey::new_unique();
+        let message = Message::new(
+            &[Instruction::new(
+                program_id,
+                &0,
+                vec![AccountMeta::new(id0, true)],
+            )],
+            Some(&id0),
+        );
+        let versioned_message = VersionedMessage::from(message.clone());
+
+        // Legacy messages serialize exactly as before
+        let serialized = versioned_message.serialize();
+        assert_eq!(serialized, message.serialize());
+        assert_eq!(
+            bincode::deserialize::<VersionedMessage>(&serialized).unwrap(),
+            versioned_message
+        );
+
+        let json = serde_json::to_string(&versioned_message).unwrap();
+        assert_eq!(json, serde_json::to_string(&message).unwrap());
+        assert_eq!(
+            serde_json::from_str::<VersionedMessage>(&json).unwrap(),
+            versioned_message
+        );
+    }
+
+    #[test]
+    fn test_v0_message_serialization() {
+        let versioned_message = VersionedMessage::V0(new_v0_message());
+        let serialized = versioned_message.serialize();
+        assert_eq!(serialized[0], MESSAGE_VERSION_PREFIX | MESSAGE_VERSION_0);
+        assert_eq!(
+            bincode::deserialize::<VersionedMessage>(&serialized).unwrap(),
+            versioned_message
+        );
+
+        let json = serde_json::to_string(&versioned_message).unwrap();
+        assert_eq!(
+            serde_json::from_str::<VersionedMessage>(&json).unwrap(),
+            versioned_message
+        );
+
+        // Unknown versions are rejected
+        let mut serialized = serialized;
+        serialized[0] = MESSAGE_VERSION_PREFIX | 1;
+        assert!(bincode::deserialize::<VersionedMessage>(&serialized).is_err());
+    }
+}
@@ -94,6 +94,18 @@ pub mod rewrite_stake {
     solana_sdk::declare_id!("6ap2eGy7wx5JmsWUmQ5sHwEWrFSDUxSti2k5Hbfv5BZG");
 }
 
+pub mod bpf_loader_upgradeable_program {
+    solana_sdk::declare_id!("CBoyLK8k8EET2HYpsxv9jWZeia8TkLLhn1zzDrrhb1su");
+}
+
+pub mod compute_budget_program {
+    solana_sdk::declare_id!("8vRpSqUGNiB9qmdNYgqEHzRdEhQfFhoxxMfiZmrqPZGm");
+}
+
+pub mod versioned_tx_message_enabled {
+    solana_sdk::declare_id!("4zoXGbmbfmq7F9amWmKoed4pxsfPweGsEc9tU1JpkLQW");
+}
+
 lazy_static! {
     /// Map of feature identifiers to user-visible description
     pub static ref FEATURE_NAMES: HashMap<Pubkey, &'static str> = [
@@ -119,6 +131,9 @@ lazy_static! {
         (timestamp_bounding::id(), "add timestamp-correction bounding #13120"),
         (stake_program_v2::id(), "solana_stake_program v2"),
         (rewrite_stake::id(), "rewrite stake"),
+        (bpf_loader_upgradeable_program::id(), "upgradeable bpf loader"),
+        (compute_budget_program::id(), "compute budget program"),
+        (versioned_tx_message_enabled::id(), "versioned transaction messages and address lookup tables"),
         /*************** ADD NEW FEATURES HERE ***************/
     ]
     .iter()
@@ -32,6 +32,7 @@ pub mod poh_config;
 pub mod process_instruction;
 pub mod program_utils;
 pub mod pubkey;
+pub mod quic;
 pub mod recent_blockhashes_account;
 pub mod rpc_port;
 pub mod secp256k1_instruction;
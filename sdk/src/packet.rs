@@ -12,7 +12,7 @@ use std::{
 ///   8 bytes is the size of the fragment header
 pub const PACKET_DATA_SIZE: usize = 1280 - 40 - 8;
 
-#[frozen_abi(digest = "9AiPd36yycNg18hDuCBVGwpTfzjX1VV4QtUKUdqeyAKH")]
+#[frozen_abi(digest = "2s1kVMs8aS6EnYs8EBpnDk4tujL7m991L8D1bP5jB8PE")]
 #[derive(Clone, Default, Debug, PartialEq, Serialize, Deserialize, AbiExample)]
 #[repr(C)]
 pub struct Meta {
@@ -25,6 +25,7 @@ pub struct Meta {
     pub v6: bool,
     pub seed: [u8; 32],
     pub slot: Slot,
+    pub from_quic: bool,
 }
 
 #[derive(Clone)]
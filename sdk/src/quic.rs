@@ -0,0 +1,21 @@
+//! Definitions shared by the QUIC TPU listener and the clients that send to it
+
+use std::net::SocketAddr;
+
+/// The QUIC TPU listens on the UDP TPU port plus this offset, so a node's QUIC address can be
+/// derived from the `tpu` address it advertises
+pub const QUIC_PORT_OFFSET: u16 = 6;
+
+/// Application protocol negotiated by QUIC TPU connections
+pub const QUIC_ALPN_PROTOCOL: &[u8] = b"solana-tpu";
+
+/// Connections that have been idle for this long are closed
+pub const QUIC_MAX_TIMEOUT_MS: u64 = 2_000;
+
+/// The QUIC TPU address of a node with the UDP TPU address `tpu_addr`
+pub fn tpu_quic_addr(tpu_addr: &SocketAddr) -> SocketAddr {
+    SocketAddr::new(
+        tpu_addr.ip(),
+        tpu_addr.port().saturating_add(QUIC_PORT_OFFSET),
+    )
+}
@@ -7,7 +7,7 @@ use crate::secp256k1_instruction::verify_eth_addresses;
 use crate::{
     hash::Hash,
     instruction::{CompiledInstruction, Instruction, InstructionError},
-    message::Message,
+    message::{Message, VersionedMessage},
     program_utils::limited_deserialize,
     pubkey::Pubkey,
     short_vec,
@@ -20,7 +20,9 @@ use std::result;
 use thiserror::Error;
 
 /// Reasons a transaction might be rejected.
-#[derive(Error, Serialize, Deserialize, Debug, PartialEq, Eq, Clone, AbiExample, AbiEnumVisitor)]
+#[derive(
+    Error, Serialize, Deserialize, Debug, PartialEq, Eq, Clone, AbiExample, AbiEnumVisitor,
+)]
 pub enum TransactionError {
     /// An account is already being processed in another transaction in a way
     /// that does not support parallelism
@@ -92,6 +94,27 @@ pub enum TransactionError {
 
     #[error("Transactions are currently disabled due to cluster maintenance")]
     ClusterMaintenance,
+
+    /// Transaction uses an address lookup table that does not exist
+    #[error("Transaction loads an address table account that doesn't exist")]
+    AddressLookupTableNotFound,
+
+    /// Transaction uses an address lookup table account that isn't owned by the address lookup
+    /// table program
+    #[error("Transaction loads an address table account with an invalid owner")]
+    InvalidAddressLookupTableOwner,
+
+    /// Transaction uses an address lookup table account whose data can't be deserialized
+    #[error("Transaction loads an address table account with invalid data")]
+    InvalidAddressLookupTableData,
+
+    /// Transaction looks up an address that isn't active in the lookup table
+    #[error("Transaction address table lookup uses an invalid index")]
+    InvalidAddressLookupTableIndex,
+
+    /// Transaction uses a message version that isn't supported yet
+    #[error("Transaction version is unsupported")]
+    UnsupportedVersion,
 }
 
 pub type Result<T> = result::Result<T, TransactionError>;
@@ -400,6 +423,105 @@ impl Transaction {
     }
 }
 
+/// An atomic transaction whose message may be a legacy or a versioned message
+#[derive(Debug, PartialEq, Default, Eq, Clone, Serialize, Deserialize)]
+pub struct VersionedTransaction {
+    /// Signatures of the serialized message, one for each of its first
+    /// `header.num_required_signatures` account keys
+    #[serde(with = "short_vec")]
+    pub signatures: Vec<Signature>,
+    /// The message to sign.
+    pub message: VersionedMessage,
+}
+
+impl Sanitize for VersionedTransaction {
+    fn sanitize(&self) -> std::result::Result<(), SanitizeError> {
+        self.message.sanitize()?;
+        if self.message.header().num_required_signatures as usize > self.signatures.len() {
+            return Err(SanitizeError::IndexOutOfBounds);
+        }
+        // Signers can't be loaded from address lookup tables
+        if self.signatures.len() > self.message.static_account_keys().len() {
+            return Err(SanitizeError::IndexOutOfBounds);
+        }
+        Ok(())
+    }
+}
+
+impl From<Transaction> for VersionedTransaction {
+    fn from(transaction: Transaction) -> Self {
+        Self {
+            signatures: transaction.signatures,
+            message: VersionedMessage::Legacy(transaction.message),
+        }
+    }
+}
+
+impl VersionedTransaction {
+    /// Sign `message` with `keypairs`, which must be exactly its required signers, in order
+    pub fn try_new<T: Signers>(
+        message: VersionedMessage,
+        keypairs: &T,
+    ) -> result::Result<Self, SignerError> {
+        let num_required_signatures = message.header().num_required_signatures as usize;
+        let static_account_keys = message.static_account_keys();
+        if static_account_keys.len() < num_required_signatures {
+            return Err(SignerError::NotEnoughSigners);
+        }
+        let pubkeys = keypairs.pubkeys();
+        if pubkeys.len() < num_required_signatures {
+            return Err(SignerError::NotEnoughSigners);
+        }
+        if pubkeys[..] != static_account_keys[..num_required_signatures] {
+            return Err(SignerError::KeypairPubkeyMismatch);
+        }
+
+        let signatures = keypairs.try_sign_message(&message.serialize())?;
+        Ok(Self {
+            signatures,
+            message,
+        })
+    }
+
+    /// Return the serialized message data to sign.
+    pub fn message_data(&self) -> Vec<u8> {
+        self.message.serialize()
+    }
+
+    pub fn verify_with_results(&self) -> Vec<bool> {
+        let message_data = self.message_data();
+        self.signatures
+            .iter()
+            .zip(self.message.static_account_keys())
+            .map(|(signature, pubkey)| signature.verify(pubkey.as_ref(), &message_data))
+            .collect()
+    }
+
+    /// Verify the transaction
+    pub fn verify(&self) -> Result<()> {
+        if !self
+            .verify_with_results()
+            .iter()
+            .all(|verify_result| *verify_result)
+        {
+            Err(TransactionError::SignatureFailure)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Return the transaction as a legacy transaction, if its message is a legacy message
+    pub fn into_legacy_transaction(self) -> Option<Transaction> {
+        match self.message {
+            VersionedMessage::Legacy(message) => Some(Transaction {
+                signatures: self.signatures,
+                message,
+            }),
+            VersionedMessage::V0(_) => None,
+        }
+    }
+}
+
 pub fn uses_durable_nonce(tx: &Transaction) -> Option<&CompiledInstruction> {
     let message = tx.message();
     message
@@ -937,4 +1059,47 @@ mod tests {
         nonce_ix.accounts[0] = 255u8;
         assert_eq!(get_nonce_pubkey_from_instruction(&nonce_ix, &tx), None,);
     }
+
+    #[test]
+    fn test_versioned_transaction() {
+        let payer = Keypair::new();
+        let program_id = Pubkey::new_unique();
+        let table_address = Pubkey::new_unique();
+        let instructions = vec![Instruction::new(
+            program_id,
+            &0,
+            vec![AccountMeta::new(table_address, false)],
+        )];
+        let message =
+            VersionedMessage::V0(crate::message::v0::Message::new_with_address_table_lookups(
+                &instructions,
+                Some(&payer.pubkey()),
+                &[(Pubkey::new_unique(), vec![table_address])],
+            ));
+        let tx = VersionedTransaction::try_new(message.clone(), &[&payer]).unwrap();
+        assert_eq!(tx.sanitize(), Ok(()));
+        assert_eq!(tx.verify(), Ok(()));
+        assert_eq!(
+            deserialize::<VersionedTransaction>(&serialize(&tx).unwrap()).unwrap(),
+            tx
+        );
+        assert_eq!(tx.clone().into_legacy_transaction(), None);
+
+        // Signers must match the message's required signers
+        assert_eq!(
+            VersionedTransaction::try_new(message, &[&Keypair::new()]),
+            Err(SignerError::KeypairPubkeyMismatch)
+        );
+
+        // Legacy transactions serialize exactly as before
+        let legacy_tx = Transaction::new(
+            &[&payer],
+            Message::new(&instructions, Some(&payer.pubkey())),
+            Hash::default(),
+        );
+        let tx = VersionedTransaction::from(legacy_tx.clone());
+        assert_eq!(serialize(&tx).unwrap(), serialize(&legacy_tx).unwrap());
+        assert_eq!(tx.verify(), Ok(()));
+        assert_eq!(tx.into_legacy_transaction(), Some(legacy_tx));
+    }
 }
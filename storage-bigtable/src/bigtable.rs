@@ -5,6 +5,7 @@ use crate::{
     compression::{compress_best, decompress},
     root_ca_certificate,
 };
+use async_trait::async_trait;
 use log::*;
 use thiserror::Error;
 use tonic::{metadata::MetadataValue, transport::ClientTlsConfig, Request};
@@ -68,6 +69,28 @@ pub enum Error {
 
     #[error("RPC error: {0}")]
     RpcError(tonic::Status),
+
+    #[error("Local storage error: {0}")]
+    LocalStorageError(rocksdb::Error),
+}
+
+impl Error {
+    /// Whether the operation that failed may succeed if retried
+    pub fn is_transient(&self) -> bool {
+        match self {
+            Self::TransportError(_) | Self::RowWriteFailed => true,
+            Self::RpcError(status) => matches!(
+                status.code(),
+                tonic::Code::Unavailable
+                    | tonic::Code::DeadlineExceeded
+                    | tonic::Code::ResourceExhausted
+                    | tonic::Code::Aborted
+                    | tonic::Code::Internal
+                    | tonic::Code::Unknown
+            ),
+            _ => false,
+        }
+    }
 }
 
 impl std::convert::From<std::io::Error> for Error {
@@ -88,8 +111,106 @@ impl std::convert::From<tonic::Status> for Error {
     }
 }
 
+impl std::convert::From<rocksdb::Error> for Error {
+    fn from(err: rocksdb::Error) -> Self {
+        Self::LocalStorageError(err)
+    }
+}
+
 pub type Result<T> = std::result::Result<T, Error>;
 
+/// Row level access to tables following the BigTable data model: rows of named cells, ordered
+/// lexically by row key. `LedgerStorage` keeps its `blocks`, `tx` and `tx-by-addr` tables in one
+#[async_trait]
+pub trait Storage: Send + Sync {
+    /// Get `table` row keys in lexical order, see `BigTable::get_row_keys`
+    async fn get_row_keys(
+        &self,
+        table_name: &str,
+        start_at: Option<RowKey>,
+        end_at: Option<RowKey>,
+        rows_limit: i64,
+    ) -> Result<Vec<RowKey>>;
+
+    /// Get latest data from `table`, see `BigTable::get_row_data`
+    async fn get_row_data(
+        &self,
+        table_name: &str,
+        start_at: Option<RowKey>,
+        end_at: Option<RowKey>,
+        rows_limit: i64,
+    ) -> Result<Vec<(RowKey, RowData)>>;
+
+    /// Get latest data from a single row of `table`. Returns `Error::RowNotFound` if that row
+    /// does not exist
+    async fn get_single_row_data(&self, table_name: &str, row_key: RowKey) -> Result<RowData>;
+
+    /// Store data for one or more `table` rows in the `family_name` column family
+    async fn put_row_data(
+        &self,
+        table_name: &str,
+        family_name: &str,
+        row_data: &[(&RowKey, RowData)],
+    ) -> Result<()>;
+}
+
+impl dyn Storage {
+    pub async fn get_bincode_cell<T>(&self, table: &str, key: RowKey) -> Result<T>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        let row_data = self.get_single_row_data(table, key.clone()).await?;
+        deserialize_bincode_cell_data(&row_data, table, key.to_string())
+    }
+
+    pub async fn get_protobuf_or_bincode_cell<B, P>(
+        &self,
+        table: &str,
+        key: RowKey,
+    ) -> Result<CellData<B, P>>
+    where
+        B: serde::de::DeserializeOwned,
+        P: prost::Message + Default,
+    {
+        let row_data = self.get_single_row_data(table, key.clone()).await?;
+        deserialize_protobuf_or_bincode_cell_data(&row_data, table, key)
+    }
+
+    pub async fn put_bincode_cells<T>(&self, table: &str, cells: &[(RowKey, T)]) -> Result<usize>
+    where
+        T: serde::ser::Serialize,
+    {
+        let mut bytes_written = 0;
+        let mut new_row_data = vec![];
+        for (row_key, data) in cells {
+            let data = compress_best(&bincode::serialize(&data).unwrap())?;
+            bytes_written += data.len();
+            new_row_data.push((row_key, vec![("bin".to_string(), data)]));
+        }
+
+        self.put_row_data(table, "x", &new_row_data).await?;
+        Ok(bytes_written)
+    }
+
+    pub async fn put_protobuf_cells<T>(&self, table: &str, cells: &[(RowKey, T)]) -> Result<usize>
+    where
+        T: prost::Message,
+    {
+        let mut bytes_written = 0;
+        let mut new_row_data = vec![];
+        for (row_key, data) in cells {
+            let mut buf = Vec::with_capacity(data.encoded_len());
+            data.encode(&mut buf).unwrap();
+            let data = compress_best(&buf)?;
+            bytes_written += data.len();
+            new_row_data.push((row_key, vec![("proto".to_string(), data)]));
+        }
+
+        self.put_row_data(table, "x", &new_row_data).await?;
+        Ok(bytes_written)
+    }
+}
+
 #[derive(Clone)]
 pub struct BigTableConnection {
     access_token: Option<AccessToken>,
@@ -185,36 +306,60 @@ impl BigTableConnection {
             table_prefix: self.table_prefix.clone(),
         }
     }
+}
 
-    pub async fn put_bincode_cells_with_retry<T>(
+#[async_trait]
+impl Storage for BigTableConnection {
+    async fn get_row_keys(
         &self,
-        table: &str,
-        cells: &[(RowKey, T)],
-    ) -> Result<usize>
-    where
-        T: serde::ser::Serialize,
-    {
-        use backoff::{future::FutureOperation as _, ExponentialBackoff};
-        (|| async {
-            let mut client = self.client();
-            Ok(client.put_bincode_cells(table, cells).await?)
-        })
-        .retry(ExponentialBackoff::default())
-        .await
+        table_name: &str,
+        start_at: Option<RowKey>,
+        end_at: Option<RowKey>,
+        rows_limit: i64,
+    ) -> Result<Vec<RowKey>> {
+        let mut client = self.client();
+        client
+            .get_row_keys(table_name, start_at, end_at, rows_limit)
+            .await
     }
 
-    pub async fn put_protobuf_cells_with_retry<T>(
+    async fn get_row_data(
         &self,
-        table: &str,
-        cells: &[(RowKey, T)],
-    ) -> Result<usize>
-    where
-        T: prost::Message,
-    {
+        table_name: &str,
+        start_at: Option<RowKey>,
+        end_at: Option<RowKey>,
+        rows_limit: i64,
+    ) -> Result<Vec<(RowKey, RowData)>> {
+        let mut client = self.client();
+        client
+            .get_row_data(table_name, start_at, end_at, rows_limit)
+            .await
+    }
+
+    async fn get_single_row_data(&self, table_name: &str, row_key: RowKey) -> Result<RowData> {
+        let mut client = self.client();
+        client.get_single_row_data(table_name, row_key).await
+    }
+
+    async fn put_row_data(
+        &self,
+        table_name: &str,
+        family_name: &str,
+        row_data: &[(&RowKey, RowData)],
+    ) -> Result<()> {
         use backoff::{future::FutureOperation as _, ExponentialBackoff};
         (|| async {
             let mut client = self.client();
-            Ok(client.put_protobuf_cells(table, cells).await?)
+            client
+                .put_row_data(table_name, family_name, row_data)
+                .await
+                .map_err(|err| {
+                    if err.is_transient() {
+                        backoff::Error::Transient(err)
+                    } else {
+                        backoff::Error::Permanent(err)
+                    }
+                })
         })
         .retry(ExponentialBackoff::default())
         .await
@@ -501,69 +646,6 @@ impl BigTable {
 
         Ok(())
     }
-
-    pub async fn get_bincode_cell<T>(&mut self, table: &str, key: RowKey) -> Result<T>
-    where
-        T: serde::de::DeserializeOwned,
-    {
-        let row_data = self.get_single_row_data(table, key.clone()).await?;
-        deserialize_bincode_cell_data(&row_data, table, key.to_string())
-    }
-
-    pub async fn get_protobuf_or_bincode_cell<B, P>(
-        &mut self,
-        table: &str,
-        key: RowKey,
-    ) -> Result<CellData<B, P>>
-    where
-        B: serde::de::DeserializeOwned,
-        P: prost::Message + Default,
-    {
-        let row_data = self.get_single_row_data(table, key.clone()).await?;
-        deserialize_protobuf_or_bincode_cell_data(&row_data, table, key)
-    }
-
-    pub async fn put_bincode_cells<T>(
-        &mut self,
-        table: &str,
-        cells: &[(RowKey, T)],
-    ) -> Result<usize>
-    where
-        T: serde::ser::Serialize,
-    {
-        let mut bytes_written = 0;
-        let mut new_row_data = vec![];
-        for (row_key, data) in cells {
-            let data = compress_best(&bincode::serialize(&data).unwrap())?;
-            bytes_written += data.len();
-            new_row_data.push((row_key, vec![("bin".to_string(), data)]));
-        }
-
-        self.put_row_data(table, "x", &new_row_data).await?;
-        Ok(bytes_written)
-    }
-
-    pub async fn put_protobuf_cells<T>(
-        &mut self,
-        table: &str,
-        cells: &[(RowKey, T)],
-    ) -> Result<usize>
-    where
-        T: prost::Message,
-    {
-        let mut bytes_written = 0;
-        let mut new_row_data = vec![];
-        for (row_key, data) in cells {
-            let mut buf = Vec::with_capacity(data.encoded_len());
-            data.encode(&mut buf).unwrap();
-            let data = compress_best(&buf)?;
-            bytes_written += data.len();
-            new_row_data.push((row_key, vec![("proto".to_string(), data)]));
-        }
-
-        self.put_row_data(table, "x", &new_row_data).await?;
-        Ok(bytes_written)
-    }
 }
 
 pub(crate) fn deserialize_protobuf_or_bincode_cell_data<B, P>(
@@ -656,6 +738,9 @@ mod tests {
                 post_balances: vec![0, 42, 1],
                 inner_instructions: Some(vec![]),
                 log_messages: Some(vec![]),
+                pre_token_balances: Some(vec![]),
+                post_token_balances: Some(vec![]),
+                loaded_addresses: None,
             }),
         };
         let block = ConfirmedBlock {
@@ -705,6 +790,8 @@ mod tests {
             if let Some(meta) = &mut block.transactions[0].meta {
                 meta.inner_instructions = None; // Legacy bincode implementation does not support inner_instructions
                 meta.log_messages = None; // Legacy bincode implementation does not support log_messages
+                meta.pre_token_balances = None; // Legacy bincode implementation does not support token balances
+                meta.post_token_balances = None; // Legacy bincode implementation does not support token balances
             }
             assert_eq!(block, bincode_block.into());
         } else {
@@ -12,7 +12,7 @@ use solana_transaction_status::{
     ConfirmedBlock, ConfirmedTransaction, ConfirmedTransactionStatusWithSignature, Reward,
     TransactionStatus, TransactionStatusMeta, TransactionWithStatusMeta,
 };
-use std::{collections::HashMap, convert::TryInto};
+use std::{collections::HashMap, convert::TryInto, path::Path, sync::Arc};
 use thiserror::Error;
 
 #[macro_use]
@@ -21,6 +21,7 @@ extern crate serde_derive;
 mod access_token;
 mod bigtable;
 mod compression;
+mod local_storage;
 mod root_ca_certificate;
 
 #[derive(Debug, Error)]
@@ -41,6 +42,17 @@ pub enum Error {
     SignatureNotFound,
 }
 
+impl Error {
+    /// Whether the storage operation that failed may succeed if retried, such as after a
+    /// transient gRPC error
+    pub fn is_transient(&self) -> bool {
+        match self {
+            Self::BigTableError(err) => err.is_transient(),
+            _ => false,
+        }
+    }
+}
+
 impl std::convert::From<bigtable::Error> for Error {
     fn from(err: bigtable::Error) -> Self {
         Self::BigTableError(err)
@@ -183,6 +195,9 @@ impl From<StoredConfirmedBlockTransactionStatusMeta> for TransactionStatusMeta {
             post_balances,
             inner_instructions: None,
             log_messages: None,
+            pre_token_balances: None,
+            post_token_balances: None,
+            loaded_addresses: None,
         }
     }
 }
@@ -271,18 +286,42 @@ struct TransactionByAddrInfo {
 
 #[derive(Clone)]
 pub struct LedgerStorage {
-    connection: bigtable::BigTableConnection,
+    storage: Arc<dyn bigtable::Storage>,
 }
 
 impl LedgerStorage {
+    /// Connect to the `solana-ledger` BigTable instance.
     pub async fn new(read_only: bool) -> Result<Self> {
         let connection = bigtable::BigTableConnection::new("solana-ledger", read_only).await?;
-        Ok(Self { connection })
+        Ok(Self {
+            storage: Arc::new(connection),
+        })
+    }
+
+    /// Keep the ledger in a local database at `local_path` if one is given, see `new_local()`,
+    /// otherwise connect to the `solana-ledger` BigTable instance
+    pub async fn new_with_local_path(read_only: bool, local_path: Option<&Path>) -> Result<Self> {
+        match local_path {
+            Some(local_path) => {
+                info!("Using local ledger storage at {:?}", local_path);
+                Self::new_local(local_path, read_only)
+            }
+            None => Self::new(read_only).await,
+        }
+    }
+
+    /// Keep the ledger in a local database at `path`, with the same tables, row keys and cells
+    /// as the BigTable instance
+    pub fn new_local(path: &Path, read_only: bool) -> Result<Self> {
+        let storage = local_storage::LocalStorage::open(path, read_only)?;
+        Ok(Self {
+            storage: Arc::new(storage),
+        })
     }
 
     /// Return the available slot that contains a block
     pub async fn get_first_available_block(&self) -> Result<Option<Slot>> {
-        let mut bigtable = self.connection.client();
+        let bigtable = &self.storage;
         let blocks = bigtable.get_row_keys("blocks", None, None, 1).await?;
         if blocks.is_empty() {
             return Ok(None);
@@ -295,7 +334,7 @@ impl LedgerStorage {
     /// start_slot: slot to start the search from (inclusive)
     /// limit: stop after this many slots have been found.
     pub async fn get_confirmed_blocks(&self, start_slot: Slot, limit: usize) -> Result<Vec<Slot>> {
-        let mut bigtable = self.connection.client();
+        let bigtable = &self.storage;
         let blocks = bigtable
             .get_row_keys("blocks", Some(slot_to_key(start_slot)), None, limit as i64)
             .await?;
@@ -304,7 +343,7 @@ impl LedgerStorage {
 
     /// Fetch the confirmed block from the desired slot
     pub async fn get_confirmed_block(&self, slot: Slot) -> Result<ConfirmedBlock> {
-        let mut bigtable = self.connection.client();
+        let bigtable = &self.storage;
         let block_cell_data = bigtable
             .get_protobuf_or_bincode_cell::<StoredConfirmedBlock, generated::ConfirmedBlock>(
                 "blocks",
@@ -320,7 +359,7 @@ impl LedgerStorage {
     }
 
     pub async fn get_signature_status(&self, signature: &Signature) -> Result<TransactionStatus> {
-        let mut bigtable = self.connection.client();
+        let bigtable = &self.storage;
         let transaction_info = bigtable
             .get_bincode_cell::<TransactionInfo>("tx", signature.to_string())
             .await?;
@@ -332,7 +371,7 @@ impl LedgerStorage {
         &self,
         signature: &Signature,
     ) -> Result<Option<ConfirmedTransaction>> {
-        let mut bigtable = self.connection.client();
+        let bigtable = &self.storage;
 
         // Figure out which block the transaction is located in
         let TransactionInfo { slot, index, .. } = bigtable
@@ -380,7 +419,7 @@ impl LedgerStorage {
             u32, /*slot index*/
         )>,
     > {
-        let mut bigtable = self.connection.client();
+        let bigtable = &self.storage;
         let address_prefix = format!("{}/", address);
 
         // Figure out where to start listing from based on `before_signature`
@@ -520,18 +559,15 @@ impl LedgerStorage {
 
         if !tx_cells.is_empty() {
             bytes_written += self
-                .connection
-                .put_bincode_cells_with_retry::<TransactionInfo>("tx", &tx_cells)
+                .storage
+                .put_bincode_cells::<TransactionInfo>("tx", &tx_cells)
                 .await?;
         }
 
         if !tx_by_addr_cells.is_empty() {
             bytes_written += self
-                .connection
-                .put_bincode_cells_with_retry::<Vec<TransactionByAddrInfo>>(
-                    "tx-by-addr",
-                    &tx_by_addr_cells,
-                )
+                .storage
+                .put_bincode_cells::<Vec<TransactionByAddrInfo>>("tx-by-addr", &tx_by_addr_cells)
                 .await?;
         }
 
@@ -542,8 +578,8 @@ impl LedgerStorage {
         // `get_confirmed_block()` and `get_confirmed_blocks()`
         let blocks_cells = [(slot_to_key(slot), confirmed_block.into())];
         bytes_written += self
-            .connection
-            .put_protobuf_cells_with_retry::<generated::ConfirmedBlock>("blocks", &blocks_cells)
+            .storage
+            .put_protobuf_cells::<generated::ConfirmedBlock>("blocks", &blocks_cells)
             .await?;
         info!(
             "uploaded block for slot {}: {} transactions, {} bytes",
@@ -557,10 +593,94 @@ impl LedgerStorage {
 #[cfg(test)]
 mod test {
     use super::*;
+    use futures::executor::block_on;
+    use solana_sdk::{hash::Hash, signature::Keypair, system_transaction};
 
     #[test]
     fn test_slot_to_key() {
         assert_eq!(slot_to_key(0), "0000000000000000");
         assert_eq!(slot_to_key(!0), "ffffffffffffffff");
     }
+
+    #[test]
+    fn test_local_ledger_storage() {
+        let path = tempfile::TempDir::new().unwrap();
+        let ledger_storage = LedgerStorage::new_local(path.path(), false).unwrap();
+        assert_eq!(
+            block_on(ledger_storage.get_first_available_block()).unwrap(),
+            None
+        );
+
+        let from = Keypair::new();
+        let recipient = solana_sdk::pubkey::new_rand();
+        let transaction = system_transaction::transfer(&from, &recipient, 42, Hash::default());
+        let signature = transaction.signatures[0];
+        let block = ConfirmedBlock {
+            transactions: vec![TransactionWithStatusMeta {
+                transaction,
+                meta: Some(TransactionStatusMeta {
+                    status: Ok(()),
+                    fee: 1,
+                    pre_balances: vec![43, 0, 1],
+                    post_balances: vec![0, 42, 1],
+                    inner_instructions: Some(vec![]),
+                    log_messages: Some(vec![]),
+                    pre_token_balances: Some(vec![]),
+                    post_token_balances: Some(vec![]),
+                    loaded_addresses: None,
+                }),
+            }],
+            parent_slot: 1,
+            blockhash: Hash::default().to_string(),
+            previous_blockhash: Hash::default().to_string(),
+            rewards: vec![],
+            block_time: Some(1_234_567_890),
+        };
+        for slot in &[2, 3] {
+            block_on(ledger_storage.upload_confirmed_block(*slot, block.clone())).unwrap();
+        }
+
+        assert_eq!(
+            block_on(ledger_storage.get_first_available_block()).unwrap(),
+            Some(2)
+        );
+        assert_eq!(
+            block_on(ledger_storage.get_confirmed_blocks(3, 10)).unwrap(),
+            vec![3]
+        );
+        assert_eq!(
+            block_on(ledger_storage.get_confirmed_block(2)).unwrap(),
+            block
+        );
+
+        // The transaction was uploaded last in slot 3
+        assert_eq!(
+            block_on(ledger_storage.get_signature_status(&signature))
+                .unwrap()
+                .slot,
+            3
+        );
+        let confirmed_transaction = block_on(ledger_storage.get_confirmed_transaction(&signature))
+            .unwrap()
+            .unwrap();
+        assert_eq!(confirmed_transaction.slot, 3);
+        assert_eq!(confirmed_transaction.transaction, block.transactions[0]);
+
+        let signatures: Vec<_> = block_on(
+            ledger_storage.get_confirmed_signatures_for_address(&recipient, None, None, 10),
+        )
+        .unwrap()
+        .into_iter()
+        .map(|(status, _)| (status.slot, status.signature))
+        .collect();
+        assert_eq!(signatures, vec![(3, signature), (2, signature)]);
+
+        // A reader sees the uploads
+        drop(ledger_storage);
+        let ledger_storage = LedgerStorage::new_local(path.path(), true).unwrap();
+        assert_eq!(
+            block_on(ledger_storage.get_confirmed_blocks(0, 10)).unwrap(),
+            vec![2, 3]
+        );
+    }
 }
@@ -0,0 +1,242 @@
+// Local stand-in for BigTable, so an archive node can keep long-term ledger history without GCP
+
+use crate::bigtable::{Error, Result, RowData, RowKey, Storage};
+use async_trait::async_trait;
+use log::*;
+use rocksdb::{Direction, IteratorMode, Options, WriteBatch, DB};
+use std::{path::Path, sync::Mutex};
+
+/// Keeps every table in a single RocksDB database, under `<table>/<row key>` keys. Row keys are
+/// ordered lexically within a table just like they are in BigTable, and each row holds the
+/// latest version of its cells
+pub struct LocalStorage {
+    db: DB,
+    secondary: bool,
+    // Serializes the read-modify-write of rows in `put_row_data`
+    write_lock: Mutex<()>,
+}
+
+impl LocalStorage {
+    /// Open the database at `path`, creating it if needed.
+    ///
+    /// If `read_only` and another process is writing to the database, it is opened as a secondary
+    /// instance instead, which catches up with the writer before every read
+    pub fn open(path: &Path, read_only: bool) -> Result<Self> {
+        std::fs::create_dir_all(path)?;
+        let mut options = Options::default();
+        options.create_if_missing(true);
+
+        let (db, secondary) = match DB::open(&options, path) {
+            Ok(db) => (db, false),
+            Err(err) if read_only => {
+                let secondary_path = path.join("secondary");
+                warn!("Error when opening local storage as primary: {}", err);
+                warn!("Trying as secondary at : {:?}", secondary_path);
+
+                // This is needed according to https://github.com/facebook/rocksdb/wiki/Secondary-instance
+                options.set_max_open_files(-1);
+                (
+                    DB::open_as_secondary(&options, path, &secondary_path)?,
+                    true,
+                )
+            }
+            Err(err) => return Err(err.into()),
+        };
+        Ok(Self {
+            db,
+            secondary,
+            write_lock: Mutex::new(()),
+        })
+    }
+
+    fn read_rows(
+        &self,
+        table_name: &str,
+        start_at: Option<RowKey>,
+        end_at: Option<RowKey>,
+        rows_limit: i64,
+    ) -> Result<Vec<(RowKey, Box<[u8]>)>> {
+        if self.secondary {
+            self.db.try_catch_up_with_primary()?;
+        }
+
+        let table_prefix = table_key(table_name, "");
+        let start_key = table_key(table_name, &start_at.unwrap_or_default());
+        let end_key = end_at.map(|end_at| table_key(table_name, &end_at));
+
+        let mut rows = vec![];
+        for (key, value) in self
+            .db
+            .iterator(IteratorMode::From(&start_key, Direction::Forward))
+        {
+            if !key.starts_with(&table_prefix)
+                || end_key
+                    .as_ref()
+                    .map(|end_key| *key > **end_key)
+                    .unwrap_or(false)
+                || (rows_limit > 0 && rows.len() as i64 >= rows_limit)
+            {
+                break;
+            }
+            let row_key = String::from_utf8(key[table_prefix.len()..].to_vec()).map_err(|_| {
+                Error::ObjectCorrupt(format!("{}/{}", table_name, String::from_utf8_lossy(&key)))
+            })?;
+            rows.push((row_key, value));
+        }
+        Ok(rows)
+    }
+}
+
+#[async_trait]
+impl Storage for LocalStorage {
+    async fn get_row_keys(
+        &self,
+        table_name: &str,
+        start_at: Option<RowKey>,
+        end_at: Option<RowKey>,
+        rows_limit: i64,
+    ) -> Result<Vec<RowKey>> {
+        let rows = self.read_rows(table_name, start_at, end_at, rows_limit)?;
+        Ok(rows.into_iter().map(|(row_key, _)| row_key).collect())
+    }
+
+    async fn get_row_data(
+        &self,
+        table_name: &str,
+        start_at: Option<RowKey>,
+        end_at: Option<RowKey>,
+        rows_limit: i64,
+    ) -> Result<Vec<(RowKey, RowData)>> {
+        self.read_rows(table_name, start_at, end_at, rows_limit)?
+            .into_iter()
+            .map(|(row_key, value)| {
+                let row_data = deserialize_row_data(&value, table_name, &row_key)?;
+                Ok((row_key, row_data))
+            })
+            .collect()
+    }
+
+    async fn get_single_row_data(&self, table_name: &str, row_key: RowKey) -> Result<RowData> {
+        if self.secondary {
+            self.db.try_catch_up_with_primary()?;
+        }
+        let value = self
+            .db
+            .get(table_key(table_name, &row_key))?
+            .ok_or(Error::RowNotFound)?;
+        deserialize_row_data(&value, table_name, &row_key)
+    }
+
+    // There is only one column family, so `family_name` is not part of the key
+    async fn put_row_data(
+        &self,
+        table_name: &str,
+        _family_name: &str,
+        row_data: &[(&RowKey, RowData)],
+    ) -> Result<()> {
+        let _write_lock = self.write_lock.lock().unwrap();
+        let mut batch = WriteBatch::default();
+        for (row_key, new_cells) in row_data {
+            let key = table_key(table_name, row_key);
+            let mut cells = match self.db.get(&key)? {
+                Some(value) => deserialize_row_data(&value, table_name, row_key)?,
+                None => vec![],
+            };
+            // Like BigTable, a cell write replaces the cell of the same name and keeps the others
+            for (cell_name, cell_value) in new_cells {
+                match cells.iter_mut().find(|(name, _)| name == cell_name) {
+                    Some((_, value)) => *value = cell_value.clone(),
+                    None => cells.push((cell_name.clone(), cell_value.clone())),
+                }
+            }
+            batch.put(key, bincode::serialize(&cells).unwrap());
+        }
+        self.db.write(batch)?;
+        Ok(())
+    }
+}
+
+fn table_key(table_name: &str, row_key: &str) -> Vec<u8> {
+    format!("{}/{}", table_name, row_key).into_bytes()
+}
+
+fn deserialize_row_data(value: &[u8], table_name: &str, row_key: &str) -> Result<RowData> {
+    bincode::deserialize(value).map_err(|err| {
+        warn!("Failed to deserialize {}/{}: {}", table_name, row_key, err);
+        Error::ObjectCorrupt(format!("{}/{}", table_name, row_key))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::executor::block_on;
+
+    #[test]
+    fn test_local_storage_rows() {
+        let path = tempfile::TempDir::new().unwrap();
+        let storage = LocalStorage::open(path.path(), false).unwrap();
+
+        let rows: Vec<_> = ["b", "a", "c"]
+            .iter()
+            .map(|row_key| row_key.to_string())
+            .collect();
+        let row_data: Vec<_> = rows
+            .iter()
+            .map(|row_key| {
+                (
+                    row_key,
+                    vec![("bin".to_string(), row_key.clone().into_bytes())],
+                )
+            })
+            .collect();
+        block_on(storage.put_row_data("table", "x", &row_data)).unwrap();
+        block_on(storage.put_row_data(
+            "other",
+            "x",
+            &[(&"0".to_string(), vec![("bin".to_string(), vec![0])])],
+        ))
+        .unwrap();
+
+        // Rows are listed in lexical order, within the bounds of the table
+        assert_eq!(
+            block_on(storage.get_row_keys("table", None, None, 0)).unwrap(),
+            vec!["a", "b", "c"]
+        );
+        assert_eq!(
+            block_on(storage.get_row_keys("table", Some("b".to_string()), None, 0)).unwrap(),
+            vec!["b", "c"]
+        );
+        assert_eq!(
+            block_on(storage.get_row_keys("table", None, Some("b".to_string()), 0)).unwrap(),
+            vec!["a", "b"]
+        );
+        assert_eq!(
+            block_on(storage.get_row_keys("table", None, None, 1)).unwrap(),
+            vec!["a"]
+        );
+        assert_eq!(
+            block_on(storage.get_row_data("other", None, None, 0)).unwrap(),
+            vec![("0".to_string(), vec![("bin".to_string(), vec![0])])]
+        );
+
+        // Writing a cell keeps the other cells of the row
+        block_on(storage.put_row_data(
+            "table",
+            "x",
+            &[(&"a".to_string(), vec![("proto".to_string(), vec![1])])],
+        ))
+        .unwrap();
+        assert_eq!(
+            block_on(storage.get_single_row_data("table", "a".to_string())).unwrap(),
+            vec![
+                ("bin".to_string(), b"a".to_vec()),
+                ("proto".to_string(), vec![1])
+            ]
+        );
+        assert!(matches!(
+            block_on(storage.get_single_row_data("table", "d".to_string())),
+            Err(Error::RowNotFound)
+        ));
+    }
+}
@@ -1,15 +1,16 @@
 use crate::StoredExtendedRewards;
+use solana_account_decoder::parse_token::UiTokenAmount;
 use solana_sdk::{
     hash::Hash,
     instruction::CompiledInstruction,
-    message::{Message, MessageHeader},
+    message::{v0::LoadedAddresses, Message, MessageHeader},
     pubkey::Pubkey,
     signature::Signature,
     transaction::Transaction,
 };
 use solana_transaction_status::{
     ConfirmedBlock, InnerInstructions, Reward, RewardType, TransactionStatusMeta,
-    TransactionWithStatusMeta,
+    TransactionTokenBalance, TransactionWithStatusMeta,
 };
 use std::convert::{TryFrom, TryInto};
 
@@ -260,6 +261,9 @@ impl From<TransactionStatusMeta> for generated::TransactionStatusMeta {
             post_balances,
             inner_instructions,
             log_messages,
+            pre_token_balances,
+            post_token_balances,
+            loaded_addresses,
         } = value;
         let err = match status {
             Ok(()) => None,
@@ -273,6 +277,25 @@ impl From<TransactionStatusMeta> for generated::TransactionStatusMeta {
             .map(|ii| ii.into())
             .collect();
         let log_messages = log_messages.unwrap_or_default();
+        let pre_token_balances = pre_token_balances
+            .unwrap_or_default()
+            .into_iter()
+            .map(|balance| balance.into())
+            .collect();
+        let post_token_balances = post_token_balances
+            .unwrap_or_default()
+            .into_iter()
+            .map(|balance| balance.into())
+            .collect();
+        let LoadedAddresses { writable, readonly } = loaded_addresses.unwrap_or_default();
+        let loaded_writable_addresses = writable
+            .into_iter()
+            .map(|key| <Pubkey as AsRef<[u8]>>::as_ref(&key).into())
+            .collect();
+        let loaded_readonly_addresses = readonly
+            .into_iter()
+            .map(|key| <Pubkey as AsRef<[u8]>>::as_ref(&key).into())
+            .collect();
         Self {
             err,
             fee,
@@ -280,6 +303,10 @@ impl From<TransactionStatusMeta> for generated::TransactionStatusMeta {
             post_balances,
             inner_instructions,
             log_messages,
+            pre_token_balances,
+            post_token_balances,
+            loaded_writable_addresses,
+            loaded_readonly_addresses,
         }
     }
 }
@@ -295,6 +322,10 @@ impl TryFrom<generated::TransactionStatusMeta> for TransactionStatusMeta {
             post_balances,
             inner_instructions,
             log_messages,
+            pre_token_balances,
+            post_token_balances,
+            loaded_writable_addresses,
+            loaded_readonly_addresses,
         } = value;
         let status = match &err {
             None => Ok(()),
@@ -307,6 +338,34 @@ impl TryFrom<generated::TransactionStatusMeta> for TransactionStatusMeta {
                 .collect(),
         );
         let log_messages = Some(log_messages);
+        let pre_token_balances = Some(
+            pre_token_balances
+                .into_iter()
+                .map(|balance| balance.into())
+                .collect(),
+        );
+        let post_token_balances = Some(
+            post_token_balances
+                .into_iter()
+                .map(|balance| balance.into())
+                .collect(),
+        );
+        // Empty loaded addresses aren't distinguished from legacy transactions, which load none
+        let loaded_addresses =
+            if loaded_writable_addresses.is_empty() && loaded_readonly_addresses.is_empty() {
+                None
+            } else {
+                Some(LoadedAddresses {
+                    writable: loaded_writable_addresses
+                        .into_iter()
+                        .map(|key| Pubkey::new(&key))
+                        .collect(),
+                    readonly: loaded_readonly_addresses
+                        .into_iter()
+                        .map(|key| Pubkey::new(&key))
+                        .collect(),
+                })
+            };
         Ok(Self {
             status,
             fee,
@@ -314,6 +373,9 @@ impl TryFrom<generated::TransactionStatusMeta> for TransactionStatusMeta {
             post_balances,
             inner_instructions,
             log_messages,
+            pre_token_balances,
+            post_token_balances,
+            loaded_addresses,
         })
     }
 }
@@ -336,6 +398,35 @@ impl From<generated::InnerInstructions> for InnerInstructions {
     }
 }
 
+impl From<TransactionTokenBalance> for generated::TokenBalance {
+    fn from(value: TransactionTokenBalance) -> Self {
+        Self {
+            account_index: value.account_index as u32,
+            mint: value.mint,
+            ui_token_amount: Some(generated::UiTokenAmount {
+                ui_amount: value.ui_token_amount.ui_amount,
+                decimals: value.ui_token_amount.decimals as u32,
+                amount: value.ui_token_amount.amount,
+            }),
+        }
+    }
+}
+
+impl From<generated::TokenBalance> for TransactionTokenBalance {
+    fn from(value: generated::TokenBalance) -> Self {
+        let ui_token_amount = value.ui_token_amount.unwrap_or_default();
+        Self {
+            account_index: value.account_index as u8,
+            mint: value.mint,
+            ui_token_amount: UiTokenAmount {
+                ui_amount: ui_token_amount.ui_amount,
+                decimals: ui_token_amount.decimals as u8,
+                amount: ui_token_amount.amount,
+            },
+        }
+    }
+}
+
 impl From<CompiledInstruction> for generated::CompiledInstruction {
     fn from(value: CompiledInstruction) -> Self {
         Self {
@@ -387,4 +478,45 @@ mod test {
         let gen_reward: generated::Reward = reward.clone().into();
         assert_eq!(reward, gen_reward.into());
     }
+
+    #[test]
+    fn test_loaded_addresses_encode() {
+        let meta = TransactionStatusMeta {
+            inner_instructions: Some(vec![]),
+            log_messages: Some(vec![]),
+            pre_token_balances: Some(vec![]),
+            post_token_balances: Some(vec![]),
+            loaded_addresses: Some(LoadedAddresses {
+                writable: vec![Pubkey::new_unique()],
+                readonly: vec![Pubkey::new_unique(), Pubkey::new_unique()],
+            }),
+            ..TransactionStatusMeta::default()
+        };
+        let gen_meta: generated::TransactionStatusMeta = meta.clone().into();
+        assert_eq!(meta, gen_meta.try_into().unwrap());
+
+        let legacy_meta = TransactionStatusMeta::default();
+        let gen_meta: generated::TransactionStatusMeta = legacy_meta.into();
+        assert_eq!(
+            TransactionStatusMeta::try_from(gen_meta)
+                .unwrap()
+                .loaded_addresses,
+            None
+        );
+    }
+
+    #[test]
+    fn test_token_balance_encode() {
+        let token_balance = TransactionTokenBalance {
+            account_index: 3,
+            mint: Pubkey::new_unique().to_string(),
+            ui_token_amount: UiTokenAmount {
+                ui_amount: 4.2,
+                decimals: 2,
+                amount: "420".to_string(),
+            },
+        };
+        let gen_token_balance: generated::TokenBalance = token_balance.clone().into();
+        assert_eq!(token_balance, gen_token_balance.into());
+    }
 }
@@ -0,0 +1,551 @@
+//! The `quic` module accepts transactions over QUIC connections, one transaction per
+//! unidirectional stream, and forwards them as `Packets` to the same channel the UDP TPU
+//! receivers feed.  Unlike the UDP port every sender has a connection identity, so the number of
+//! connections is bounded by the stake of the sender and each connection is rate limited.
+
+use crate::{
+    packet::{Packet, Packets, PACKETS_PER_BATCH, PACKET_DATA_SIZE},
+    streamer::PacketSender,
+};
+use bytes::BytesMut;
+use quinn_proto::{
+    Certificate, CertificateChain, Connection, ConnectionHandle, DatagramEvent, Dir, Endpoint,
+    EndpointConfig, Event, PrivateKey, ReadError, ServerConfig, StreamId, TransportConfig, VarInt,
+};
+use solana_sdk::quic::{QUIC_ALPN_PROTOCOL, QUIC_MAX_TIMEOUT_MS};
+use std::{
+    collections::{HashMap, HashSet},
+    io,
+    net::{IpAddr, SocketAddr, UdpSocket},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, RwLock,
+    },
+    thread::{Builder, JoinHandle},
+    time::{Duration, Instant},
+};
+use thiserror::Error;
+
+/// Stake of the nodes that may connect, keyed by the address they connect from
+pub type StakedNodes = Arc<RwLock<HashMap<IpAddr, u64>>>;
+
+const MAX_CONCURRENT_UNI_STREAMS: u64 = 128;
+const MAX_DATAGRAM_SIZE: usize = 64 * 1024;
+const MAX_DATAGRAMS_PER_POLL: usize = 256;
+const POLL_TIMEOUT: Duration = Duration::from_millis(100);
+const STATS_REPORT_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Application error code sent when a connection or stream is refused
+const QUIC_REFUSED: u32 = 1;
+
+#[derive(Error, Debug)]
+pub enum QuicServerError {
+    #[error("unable to generate the server certificate: {0}")]
+    Certificate(#[from] rcgen::RcgenError),
+
+    #[error("invalid server certificate: {0}")]
+    CertificateParse(#[from] quinn_proto::ParseError),
+
+    #[error("tls error: {0}")]
+    Tls(#[from] rustls::TLSError),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct QuicServerConfig {
+    /// Connections an unstaked address may hold open at once
+    pub max_connections_per_ip: usize,
+    /// Connections shared by all staked addresses, each gets a share proportional to its stake
+    pub max_staked_connections: usize,
+    /// Connections shared by all unstaked addresses
+    pub max_unstaked_connections: usize,
+    /// Transactions accepted on one connection per second, further streams are refused
+    pub max_streams_per_second: u64,
+}
+
+impl Default for QuicServerConfig {
+    fn default() -> Self {
+        Self {
+            max_connections_per_ip: 8,
+            max_staked_connections: 2000,
+            max_unstaked_connections: 500,
+            max_streams_per_second: 1000,
+        }
+    }
+}
+
+#[derive(Default)]
+struct QuicServerStats {
+    connections_accepted: usize,
+    connections_refused: usize,
+    connections_lost: usize,
+    streams_received: usize,
+    streams_rate_limited: usize,
+    streams_failed: usize,
+    packets_sent: usize,
+}
+
+impl QuicServerStats {
+    fn report(&mut self, connection_table: &ConnectionTable) {
+        datapoint_info!(
+            "quic-server",
+            ("staked_connections", connection_table.staked, i64),
+            ("unstaked_connections", connection_table.unstaked, i64),
+            ("connections_accepted", self.connections_accepted, i64),
+            ("connections_refused", self.connections_refused, i64),
+            ("connections_lost", self.connections_lost, i64),
+            ("streams_received", self.streams_received, i64),
+            ("streams_rate_limited", self.streams_rate_limited, i64),
+            ("streams_failed", self.streams_failed, i64),
+            ("packets_sent", self.packets_sent, i64),
+        );
+        *self = Self::default();
+    }
+}
+
+/// Tracks the open connections of every remote address against its quota
+#[derive(Default, Debug)]
+struct ConnectionTable {
+    connections: HashMap<IpAddr, usize>,
+    staked: usize,
+    unstaked: usize,
+}
+
+impl ConnectionTable {
+    /// The number of connections `ip` may hold open, and whether they count as staked
+    fn quota(
+        ip: &IpAddr,
+        staked_nodes: &HashMap<IpAddr, u64>,
+        config: &QuicServerConfig,
+    ) -> (usize, bool) {
+        match staked_nodes.get(ip) {
+            Some(stake) if *stake > 0 => {
+                let total_stake: u64 = staked_nodes.values().sum();
+                let share = (config.max_staked_connections as u128 * u128::from(*stake)
+                    / u128::from(total_stake)) as usize;
+                (share.max(1), true)
+            }
+            _ => (config.max_connections_per_ip, false),
+        }
+    }
+
+    /// Reserves a connection for `ip`, returns whether it is staked or None if `ip` is over quota
+    fn try_add(
+        &mut self,
+        ip: IpAddr,
+        staked_nodes: &HashMap<IpAddr, u64>,
+        config: &QuicServerConfig,
+    ) -> Option<bool> {
+        let (quota, staked) = Self::quota(&ip, staked_nodes, config);
+        let open = self.connections.get(&ip).copied().unwrap_or_default();
+        if open >= quota {
+            return None;
+        }
+        if staked {
+            if self.staked >= config.max_staked_connections {
+                return None;
+            }
+            self.staked += 1;
+        } else {
+            if self.unstaked >= config.max_unstaked_connections {
+                return None;
+            }
+            self.unstaked += 1;
+        }
+        *self.connections.entry(ip).or_default() += 1;
+        Some(staked)
+    }
+
+    fn remove(&mut self, ip: &IpAddr, staked: bool) {
+        if let Some(open) = self.connections.get_mut(ip) {
+            *open -= 1;
+            if *open == 0 {
+                self.connections.remove(ip);
+            }
+        }
+        if staked {
+            self.staked -= 1;
+        } else {
+            self.unstaked -= 1;
+        }
+    }
+}
+
+struct ServerConnection {
+    connection: Connection,
+    remote_ip: IpAddr,
+    /// Whether the connection holds a staked slot of the connection table, None until the
+    /// handshake completes or if it was refused
+    staked: Option<bool>,
+    /// Whether the connection was counted against its quota, which waits for the handshake so
+    /// that connections which never complete it hold no slot
+    counted: bool,
+    window_start: Instant,
+    window_streams: u64,
+    /// Data received so far on each open stream
+    streams: HashMap<StreamId, Vec<u8>>,
+}
+
+impl ServerConnection {
+    /// Counts a new stream against the rate limit, returns false if it must be refused
+    fn admit_stream(&mut self, max_streams_per_second: u64) -> bool {
+        if self.staked.is_none() {
+            return false;
+        }
+        if self.window_start.elapsed() >= Duration::from_secs(1) {
+            self.window_start = Instant::now();
+            self.window_streams = 0;
+        }
+        if self.window_streams >= max_streams_per_second {
+            return false;
+        }
+        self.window_streams += 1;
+        true
+    }
+
+    /// Reads whatever has arrived on `stream`, a finished stream becomes a packet
+    fn read_stream(
+        &mut self,
+        stream: StreamId,
+        packets: &mut Vec<Packet>,
+        stats: &mut QuicServerStats,
+    ) {
+        let mut buf = [0u8; PACKET_DATA_SIZE];
+        while let Some(data) = self.streams.get_mut(&stream) {
+            match self.connection.read(stream, &mut buf) {
+                Ok(Some(len)) if data.len() + len <= PACKET_DATA_SIZE => {
+                    data.extend_from_slice(&buf[..len]);
+                }
+                Ok(Some(_)) => {
+                    // A transaction never spans more than one packet
+                    stats.streams_failed += 1;
+                    self.streams.remove(&stream);
+                    let _ = self
+                        .connection
+                        .stop_sending(stream, VarInt::from_u32(QUIC_REFUSED));
+                }
+                Ok(None) => {
+                    let data = self.streams.remove(&stream).unwrap();
+                    if data.is_empty() {
+                        stats.streams_failed += 1;
+                        continue;
+                    }
+                    let mut packet = Packet::default();
+                    packet.data[..data.len()].copy_from_slice(&data);
+                    packet.meta.size = data.len();
+                    packet.meta.set_addr(&self.connection.remote_address());
+                    // The handshake proved that the sender owns this address
+                    packet.meta.from_quic = true;
+                    packets.push(packet);
+                }
+                Err(ReadError::Blocked) => break,
+                Err(_) => {
+                    stats.streams_failed += 1;
+                    self.streams.remove(&stream);
+                }
+            }
+        }
+    }
+}
+
+fn new_server_config() -> Result<ServerConfig, QuicServerError> {
+    let cert = rcgen::generate_simple_self_signed(vec!["solana-tpu".to_string()])?;
+    let cert_der = cert.serialize_der()?;
+    let key_der = cert.serialize_private_key_der();
+
+    let mut transport_config = TransportConfig::default();
+    transport_config
+        .stream_window_uni(MAX_CONCURRENT_UNI_STREAMS)
+        .stream_window_bidi(0)
+        .max_idle_timeout(Some(Duration::from_millis(QUIC_MAX_TIMEOUT_MS)))
+        .expect("idle timeout is in range");
+
+    let mut server_config = ServerConfig::default();
+    server_config.transport = Arc::new(transport_config);
+    // Senders prove they own their address before any connection state is kept, so their quota
+    // can't be taken up by spoofed handshakes
+    server_config.use_stateless_retry(true);
+    server_config.migration(false).certificate(
+        CertificateChain::from_certs(vec![Certificate::from_der(&cert_der)?]),
+        PrivateKey::from_der(&key_der)?,
+    )?;
+    Arc::make_mut(&mut server_config.crypto).set_protocols(&[QUIC_ALPN_PROTOCOL.to_vec()]);
+    Ok(server_config)
+}
+
+/// Starts accepting QUIC connections on `sock`, the received transactions are sent in batches to
+/// `packet_sender`
+pub fn spawn_server(
+    sock: UdpSocket,
+    packet_sender: PacketSender,
+    staked_nodes: StakedNodes,
+    config: QuicServerConfig,
+    exit: Arc<AtomicBool>,
+) -> Result<JoinHandle<()>, QuicServerError> {
+    let endpoint = Endpoint::new(
+        Arc::new(EndpointConfig::default()),
+        Some(Arc::new(new_server_config()?)),
+    );
+    let mut server = QuicServer {
+        sock,
+        endpoint,
+        connections: HashMap::new(),
+        connection_table: ConnectionTable::default(),
+        staked_nodes,
+        config,
+        packets: Vec::with_capacity(PACKETS_PER_BATCH),
+        stats: QuicServerStats::default(),
+    };
+    Ok(Builder::new()
+        .name("solana-quic-server".to_string())
+        .spawn(move || {
+            let mut last_report = Instant::now();
+            while !exit.load(Ordering::Relaxed) {
+                if let Err(err) = server.poll(&packet_sender) {
+                    error!("quic server: {}", err);
+                    break;
+                }
+                if last_report.elapsed() > STATS_REPORT_INTERVAL {
+                    server.stats.report(&server.connection_table);
+                    last_report = Instant::now();
+                }
+            }
+        })
+        .unwrap())
+}
+
+struct QuicServer {
+    sock: UdpSocket,
+    endpoint: Endpoint,
+    connections: HashMap<ConnectionHandle, ServerConnection>,
+    connection_table: ConnectionTable,
+    staked_nodes: StakedNodes,
+    config: QuicServerConfig,
+    packets: Vec<Packet>,
+    stats: QuicServerStats,
+}
+
+impl QuicServer {
+    /// Waits for datagrams until the next connection timer expires, then drives the connections
+    /// and sends out the transactions that were completed
+    fn poll(&mut self, packet_sender: &PacketSender) -> io::Result<()> {
+        let now = Instant::now();
+        let timeout = self
+            .connections
+            .values_mut()
+            .filter_map(|c| c.connection.poll_timeout())
+            .min()
+            .map_or(POLL_TIMEOUT, |t| {
+                t.saturating_duration_since(now).min(POLL_TIMEOUT)
+            })
+            .max(Duration::from_millis(1));
+        self.sock.set_read_timeout(Some(timeout))?;
+
+        let mut buf = vec![0u8; MAX_DATAGRAM_SIZE];
+        let mut received = HashSet::new();
+        for i in 0..MAX_DATAGRAMS_PER_POLL {
+            // Block for the first datagram only, then drain whatever else is queued
+            self.sock.set_nonblocking(i > 0)?;
+            let (len, remote) = match self.sock.recv_from(&mut buf) {
+                Ok(datagram) => datagram,
+                Err(err)
+                    if err.kind() == io::ErrorKind::WouldBlock
+                        || err.kind() == io::ErrorKind::TimedOut =>
+                {
+                    break
+                }
+                Err(err) => return Err(err),
+            };
+            if let Some(handle) = self.handle_datagram(BytesMut::from(&buf[..len]), remote) {
+                received.insert(handle);
+            }
+        }
+        self.sock.set_nonblocking(false)?;
+
+        let now = Instant::now();
+        let handles: Vec<_> = self.connections.keys().copied().collect();
+        for handle in handles {
+            self.drive_connection(handle, now, received.contains(&handle))?;
+        }
+        while let Some(transmit) = self.endpoint.poll_transmit() {
+            self.sock
+                .send_to(&transmit.contents, transmit.destination)?;
+        }
+
+        if !self.packets.is_empty() {
+            self.stats.packets_sent += self.packets.len();
+            let packets =
+                std::mem::replace(&mut self.packets, Vec::with_capacity(PACKETS_PER_BATCH));
+            if packet_sender.send(Packets::new(packets)).is_err() {
+                return Err(io::Error::new(
+                    io::ErrorKind::BrokenPipe,
+                    "packet receiver hung up",
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn handle_datagram(&mut self, data: BytesMut, remote: SocketAddr) -> Option<ConnectionHandle> {
+        let now = Instant::now();
+        let (handle, event) = self.endpoint.handle(now, remote, None, data)?;
+        match event {
+            DatagramEvent::NewConnection(connection) => {
+                self.endpoint.accept();
+                // The connection is counted against its quota once the handshake completes
+                self.connections.insert(
+                    handle,
+                    ServerConnection {
+                        connection,
+                        remote_ip: remote.ip(),
+                        staked: None,
+                        counted: false,
+                        window_start: now,
+                        window_streams: 0,
+                        streams: HashMap::new(),
+                    },
+                );
+            }
+            DatagramEvent::ConnectionEvent(event) => {
+                if let Some(c) = self.connections.get_mut(&handle) {
+                    c.connection.handle_event(event);
+                }
+            }
+        }
+        Some(handle)
+    }
+
+    fn drive_connection(
+        &mut self,
+        handle: ConnectionHandle,
+        now: Instant,
+        received: bool,
+    ) -> io::Result<()> {
+        let c = self.connections.get_mut(&handle).unwrap();
+        let timer_expired = c.connection.poll_timeout().map_or(false, |t| t <= now);
+        if !received && !timer_expired {
+            return Ok(());
+        }
+        if timer_expired {
+            c.connection.handle_timeout(now);
+        }
+        // Before any of the streams that may have arrived along with the end of the handshake
+        if !c.counted && !c.connection.is_handshaking() && !c.connection.is_closed() {
+            c.counted = true;
+            c.staked = {
+                let staked_nodes = self.staked_nodes.read().unwrap();
+                self.connection_table
+                    .try_add(c.remote_ip, &staked_nodes, &self.config)
+            };
+            if c.staked.is_some() {
+                self.stats.connections_accepted += 1;
+            } else {
+                self.stats.connections_refused += 1;
+                c.connection
+                    .close(now, VarInt::from_u32(QUIC_REFUSED), "over quota".into());
+            }
+        }
+
+        while let Some(event) = c.connection.poll() {
+            match event {
+                Event::StreamOpened { dir: Dir::Uni } => {
+                    while let Some(stream) = c.connection.accept(Dir::Uni) {
+                        if !c.admit_stream(self.config.max_streams_per_second) {
+                            self.stats.streams_rate_limited += 1;
+                            let _ = c
+                                .connection
+                                .stop_sending(stream, VarInt::from_u32(QUIC_REFUSED));
+                            continue;
+                        }
+                        self.stats.streams_received += 1;
+                        c.streams.insert(stream, vec![]);
+                        c.read_stream(stream, &mut self.packets, &mut self.stats);
+                    }
+                }
+                Event::StreamReadable { stream } => {
+                    c.read_stream(stream, &mut self.packets, &mut self.stats);
+                }
+                Event::ConnectionLost { reason } => {
+                    debug!(
+                        "quic connection from {} lost: {}",
+                        c.connection.remote_address(),
+                        reason
+                    );
+                    self.stats.connections_lost += 1;
+                }
+                _ => (),
+            }
+        }
+
+        while let Some(event) = c.connection.poll_endpoint_events() {
+            if let Some(event) = self.endpoint.handle_event(handle, event) {
+                c.connection.handle_event(event);
+            }
+        }
+        while let Some(transmit) = c.connection.poll_transmit(now) {
+            self.sock
+                .send_to(&transmit.contents, transmit.destination)?;
+        }
+
+        if c.connection.is_drained() {
+            let c = self.connections.remove(&handle).unwrap();
+            if let Some(staked) = c.staked {
+                self.connection_table.remove(&c.remote_ip, staked);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_connection_table_quotas() {
+        let staked_ip: IpAddr = "10.0.0.1".parse().unwrap();
+        let small_staked_ip: IpAddr = "10.0.0.2".parse().unwrap();
+        let unstaked_ip: IpAddr = "10.0.0.3".parse().unwrap();
+        let other_ip: IpAddr = "10.0.0.4".parse().unwrap();
+        let staked_nodes: HashMap<_, _> = vec![(staked_ip, 99), (small_staked_ip, 1)]
+            .into_iter()
+            .collect();
+        let config = QuicServerConfig {
+            max_connections_per_ip: 2,
+            max_staked_connections: 10,
+            max_unstaked_connections: 3,
+            max_streams_per_second: 1,
+        };
+        let mut table = ConnectionTable::default();
+
+        // Stake proportional share of the staked pool, at least one connection
+        for _ in 0..9 {
+            assert_eq!(table.try_add(staked_ip, &staked_nodes, &config), Some(true));
+        }
+        assert_eq!(table.try_add(staked_ip, &staked_nodes, &config), None);
+        assert_eq!(
+            table.try_add(small_staked_ip, &staked_nodes, &config),
+            Some(true)
+        );
+        assert_eq!(table.try_add(small_staked_ip, &staked_nodes, &config), None);
+
+        // Unstaked addresses are limited per address and by the unstaked pool
+        assert_eq!(
+            table.try_add(unstaked_ip, &staked_nodes, &config),
+            Some(false)
+        );
+        assert_eq!(
+            table.try_add(unstaked_ip, &staked_nodes, &config),
+            Some(false)
+        );
+        assert_eq!(table.try_add(unstaked_ip, &staked_nodes, &config), None);
+        assert_eq!(table.try_add(other_ip, &staked_nodes, &config), Some(false));
+        assert_eq!(table.try_add(other_ip, &staked_nodes, &config), None);
+
+        table.remove(&unstaked_ip, false);
+        assert_eq!(table.try_add(other_ip, &staked_nodes, &config), Some(false));
+        table.remove(&staked_ip, true);
+        assert_eq!(table.try_add(staked_ip, &staked_nodes, &config), Some(true));
+        assert_eq!(table.staked, 10);
+        assert_eq!(table.unstaked, 3);
+    }
+}
@@ -10,21 +10,26 @@ pub mod parse_stake;
 pub mod parse_system;
 pub mod parse_token;
 pub mod parse_vote;
+pub mod token_balances;
 
 use crate::{
-    parse_accounts::{parse_accounts, ParsedAccount},
+    parse_accounts::{parse_accounts, parse_loaded_accounts, ParsedAccount},
     parse_instruction::{parse, ParsedInstruction},
 };
+use solana_account_decoder::parse_token::UiTokenAmount;
 pub use solana_runtime::bank::RewardType;
 use solana_sdk::{
     clock::{Slot, UnixTimestamp},
     commitment_config::CommitmentConfig,
     deserialize_utils::default_on_eof,
     instruction::CompiledInstruction,
-    message::{Message, MessageHeader},
+    message::{
+        v0::{LoadedAddresses, LoadedMessage, MessageAddressTableLookup},
+        MessageHeader, VersionedMessage,
+    },
     pubkey::Pubkey,
     signature::Signature,
-    transaction::{Result, Transaction, TransactionError},
+    transaction::{Result, Transaction, TransactionError, VersionedTransaction},
 };
 use std::fmt;
 
@@ -44,13 +49,13 @@ pub enum UiParsedInstruction {
 }
 
 impl UiInstruction {
-    fn parse(instruction: &CompiledInstruction, message: &Message) -> Self {
-        let program_id = instruction.program_id(&message.account_keys);
-        if let Ok(parsed_instruction) = parse(program_id, instruction, &message.account_keys) {
+    fn parse(instruction: &CompiledInstruction, account_keys: &[Pubkey]) -> Self {
+        let program_id = instruction.program_id(account_keys);
+        if let Ok(parsed_instruction) = parse(program_id, instruction, account_keys) {
             UiInstruction::Parsed(UiParsedInstruction::Parsed(parsed_instruction))
         } else {
             UiInstruction::Parsed(UiParsedInstruction::PartiallyDecoded(
-                UiPartiallyDecodedInstruction::from(instruction, &message.account_keys),
+                UiPartiallyDecodedInstruction::from(instruction, account_keys),
             ))
         }
     }
@@ -116,13 +121,13 @@ pub struct UiInnerInstructions {
 }
 
 impl UiInnerInstructions {
-    fn parse(inner_instructions: InnerInstructions, message: &Message) -> Self {
+    fn parse(inner_instructions: InnerInstructions, account_keys: &[Pubkey]) -> Self {
         Self {
             index: inner_instructions.index,
             instructions: inner_instructions
                 .instructions
                 .iter()
-                .map(|ix| UiInstruction::parse(ix, message))
+                .map(|ix| UiInstruction::parse(ix, account_keys))
                 .collect(),
         }
     }
@@ -141,6 +146,32 @@ impl From<InnerInstructions> for UiInnerInstructions {
     }
 }
 
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TransactionTokenBalance {
+    /// Index of the token account in the transaction account keys
+    pub account_index: u8,
+    pub mint: String,
+    pub ui_token_amount: UiTokenAmount,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UiTransactionTokenBalance {
+    pub account_index: u8,
+    pub mint: String,
+    pub ui_token_amount: UiTokenAmount,
+}
+
+impl From<TransactionTokenBalance> for UiTransactionTokenBalance {
+    fn from(token_balance: TransactionTokenBalance) -> Self {
+        Self {
+            account_index: token_balance.account_index,
+            mint: token_balance.mint,
+            ui_token_amount: token_balance.ui_token_amount,
+        }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct TransactionStatusMeta {
@@ -152,6 +183,12 @@ pub struct TransactionStatusMeta {
     pub inner_instructions: Option<Vec<InnerInstructions>>,
     #[serde(deserialize_with = "default_on_eof")]
     pub log_messages: Option<Vec<String>>,
+    #[serde(deserialize_with = "default_on_eof")]
+    pub pre_token_balances: Option<Vec<TransactionTokenBalance>>,
+    #[serde(deserialize_with = "default_on_eof")]
+    pub post_token_balances: Option<Vec<TransactionTokenBalance>>,
+    #[serde(deserialize_with = "default_on_eof")]
+    pub loaded_addresses: Option<LoadedAddresses>,
 }
 
 impl Default for TransactionStatusMeta {
@@ -163,6 +200,9 @@ impl Default for TransactionStatusMeta {
             post_balances: vec![],
             inner_instructions: None,
             log_messages: None,
+            pre_token_balances: None,
+            post_token_balances: None,
+            loaded_addresses: None,
         }
     }
 }
@@ -178,10 +218,39 @@ pub struct UiTransactionStatusMeta {
     pub post_balances: Vec<u64>,
     pub inner_instructions: Option<Vec<UiInnerInstructions>>,
     pub log_messages: Option<Vec<String>>,
+    pub pre_token_balances: Option<Vec<UiTransactionTokenBalance>>,
+    pub post_token_balances: Option<Vec<UiTransactionTokenBalance>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub loaded_addresses: Option<UiLoadedAddresses>,
+}
+
+/// A duplicate representation of LoadedAddresses
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UiLoadedAddresses {
+    pub writable: Vec<String>,
+    pub readonly: Vec<String>,
+}
+
+impl From<&LoadedAddresses> for UiLoadedAddresses {
+    fn from(loaded_addresses: &LoadedAddresses) -> Self {
+        Self {
+            writable: loaded_addresses
+                .writable
+                .iter()
+                .map(|pubkey| pubkey.to_string())
+                .collect(),
+            readonly: loaded_addresses
+                .readonly
+                .iter()
+                .map(|pubkey| pubkey.to_string())
+                .collect(),
+        }
+    }
 }
 
 impl UiTransactionStatusMeta {
-    fn parse(meta: TransactionStatusMeta, message: &Message) -> Self {
+    fn parse(meta: TransactionStatusMeta, account_keys: &[Pubkey]) -> Self {
         Self {
             err: meta.status.clone().err(),
             status: meta.status,
@@ -190,10 +259,20 @@ impl UiTransactionStatusMeta {
             post_balances: meta.post_balances,
             inner_instructions: meta.inner_instructions.map(|ixs| {
                 ixs.into_iter()
-                    .map(|ix| UiInnerInstructions::parse(ix, message))
+                    .map(|ix| UiInnerInstructions::parse(ix, account_keys))
                     .collect()
             }),
             log_messages: meta.log_messages,
+            pre_token_balances: meta
+                .pre_token_balances
+                .map(|balance| balance.into_iter().map(|balance| balance.into()).collect()),
+            post_token_balances: meta
+                .post_token_balances
+                .map(|balance| balance.into_iter().map(|balance| balance.into()).collect()),
+            loaded_addresses: meta
+                .loaded_addresses
+                .as_ref()
+                .map(|addresses| addresses.into()),
         }
     }
 }
@@ -210,6 +289,16 @@ impl From<TransactionStatusMeta> for UiTransactionStatusMeta {
                 .inner_instructions
                 .map(|ixs| ixs.into_iter().map(|ix| ix.into()).collect()),
             log_messages: meta.log_messages,
+            pre_token_balances: meta
+                .pre_token_balances
+                .map(|balance| balance.into_iter().map(|balance| balance.into()).collect()),
+            post_token_balances: meta
+                .post_token_balances
+                .map(|balance| balance.into_iter().map(|balance| balance.into()).collect()),
+            loaded_addresses: meta
+                .loaded_addresses
+                .as_ref()
+                .map(|addresses| addresses.into()),
         }
     }
 }
@@ -337,6 +426,27 @@ pub struct UiRawMessage {
     pub account_keys: Vec<String>,
     pub recent_blockhash: String,
     pub instructions: Vec<UiCompiledInstruction>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub address_table_lookups: Option<Vec<UiAddressTableLookup>>,
+}
+
+/// A duplicate representation of a MessageAddressTableLookup, for pretty JSON serialization
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UiAddressTableLookup {
+    pub account_key: String,
+    pub writable_indexes: Vec<u8>,
+    pub readonly_indexes: Vec<u8>,
+}
+
+impl From<&MessageAddressTableLookup> for UiAddressTableLookup {
+    fn from(lookup: &MessageAddressTableLookup) -> Self {
+        Self {
+            account_key: lookup.account_key.to_string(),
+            writable_indexes: lookup.writable_indexes.clone(),
+            readonly_indexes: lookup.readonly_indexes.clone(),
+        }
+    }
 }
 
 /// A duplicate representation of a Message, in parsed format, for pretty JSON serialization
@@ -357,10 +467,46 @@ pub struct TransactionWithStatusMeta {
 
 impl TransactionWithStatusMeta {
     fn encode(self, encoding: UiTransactionEncoding) -> EncodedTransactionWithStatusMeta {
-        let message = self.transaction.message();
-        let meta = self.meta.map(|meta| meta.encode(encoding, message));
+        VersionedTransactionWithStatusMeta::from(self).encode(encoding)
+    }
+}
+
+/// A transaction that may carry a versioned message, along with its status meta
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VersionedTransactionWithStatusMeta {
+    pub transaction: VersionedTransaction,
+    pub meta: Option<TransactionStatusMeta>,
+}
+
+impl From<TransactionWithStatusMeta> for VersionedTransactionWithStatusMeta {
+    fn from(value: TransactionWithStatusMeta) -> Self {
+        Self {
+            transaction: value.transaction.into(),
+            meta: value.meta,
+        }
+    }
+}
+
+impl VersionedTransactionWithStatusMeta {
+    pub fn encode(self, encoding: UiTransactionEncoding) -> EncodedTransactionWithStatusMeta {
+        let loaded_addresses = self
+            .meta
+            .as_ref()
+            .and_then(|meta| meta.loaded_addresses.clone());
+        // Instructions index into the static account keys followed by the loaded addresses
+        let mut account_keys = self.transaction.message.static_account_keys().to_vec();
+        if let Some(loaded_addresses) = &loaded_addresses {
+            account_keys.extend(&loaded_addresses.writable);
+            account_keys.extend(&loaded_addresses.readonly);
+        }
+        let meta = self.meta.map(|meta| meta.encode(encoding, &account_keys));
         EncodedTransactionWithStatusMeta {
-            transaction: EncodedTransaction::encode(self.transaction, encoding),
+            transaction: EncodedTransaction::encode_versioned(
+                self.transaction,
+                loaded_addresses,
+                encoding,
+            ),
             meta,
         }
     }
@@ -374,9 +520,13 @@ pub struct EncodedTransactionWithStatusMeta {
 }
 
 impl TransactionStatusMeta {
-    fn encode(self, encoding: UiTransactionEncoding, message: &Message) -> UiTransactionStatusMeta {
+    fn encode(
+        self,
+        encoding: UiTransactionEncoding,
+        account_keys: &[Pubkey],
+    ) -> UiTransactionStatusMeta {
         match encoding {
-            UiTransactionEncoding::JsonParsed => UiTransactionStatusMeta::parse(self, message),
+            UiTransactionEncoding::JsonParsed => UiTransactionStatusMeta::parse(self, account_keys),
             _ => self.into(),
         }
     }
@@ -410,6 +560,16 @@ pub enum EncodedTransaction {
 
 impl EncodedTransaction {
     pub fn encode(transaction: Transaction, encoding: UiTransactionEncoding) -> Self {
+        Self::encode_versioned(transaction.into(), None, encoding)
+    }
+
+    /// Encode a transaction along with the addresses its message loaded from address lookup
+    /// tables, which are needed to parse the instructions of a versioned message
+    pub fn encode_versioned(
+        transaction: VersionedTransaction,
+        loaded_addresses: Option<LoadedAddresses>,
+        encoding: UiTransactionEncoding,
+    ) -> Self {
         match encoding {
             UiTransactionEncoding::Binary => EncodedTransaction::LegacyBinary(
                 bs58::encode(bincode::serialize(&transaction).unwrap()).into_string(),
@@ -423,48 +583,82 @@ impl EncodedTransaction {
                 encoding,
             ),
             UiTransactionEncoding::Json | UiTransactionEncoding::JsonParsed => {
-                let message = if encoding == UiTransactionEncoding::Json {
-                    UiMessage::Raw(UiRawMessage {
-                        header: transaction.message.header,
-                        account_keys: transaction
-                            .message
-                            .account_keys
+                let signatures = transaction
+                    .signatures
+                    .iter()
+                    .map(|sig| sig.to_string())
+                    .collect();
+                let message = match (encoding, transaction.message, loaded_addresses) {
+                    (UiTransactionEncoding::JsonParsed, VersionedMessage::Legacy(message), _) => {
+                        UiMessage::Parsed(UiParsedMessage {
+                            account_keys: parse_accounts(&message),
+                            recent_blockhash: message.recent_blockhash.to_string(),
+                            instructions: message
+                                .instructions
+                                .iter()
+                                .map(|instruction| {
+                                    UiInstruction::parse(instruction, &message.account_keys)
+                                })
+                                .collect(),
+                        })
+                    }
+                    (
+                        UiTransactionEncoding::JsonParsed,
+                        VersionedMessage::V0(message),
+                        Some(loaded_addresses),
+                    ) => {
+                        let loaded_message = LoadedMessage {
+                            message,
+                            loaded_addresses,
+                        };
+                        let account_keys: Vec<Pubkey> =
+                            loaded_message.account_keys().cloned().collect();
+                        UiMessage::Parsed(UiParsedMessage {
+                            account_keys: parse_loaded_accounts(&loaded_message),
+                            recent_blockhash: loaded_message.message.recent_blockhash.to_string(),
+                            instructions: loaded_message
+                                .message
+                                .instructions
+                                .iter()
+                                .map(|instruction| UiInstruction::parse(instruction, &account_keys))
+                                .collect(),
+                        })
+                    }
+                    // Without its loaded addresses, a versioned message can only be shown raw
+                    (_, message, _) => UiMessage::Raw(UiRawMessage {
+                        header: message.header().clone(),
+                        // Loaded addresses are reported in the transaction status meta
+                        account_keys: message
+                            .static_account_keys()
                             .iter()
                             .map(|pubkey| pubkey.to_string())
                             .collect(),
-                        recent_blockhash: transaction.message.recent_blockhash.to_string(),
-                        instructions: transaction
-                            .message
-                            .instructions
+                        recent_blockhash: message.recent_blockhash().to_string(),
+                        instructions: message
+                            .instructions()
                             .iter()
                             .map(|instruction| instruction.into())
                             .collect(),
-                    })
-                } else {
-                    UiMessage::Parsed(UiParsedMessage {
-                        account_keys: parse_accounts(&transaction.message),
-                        recent_blockhash: transaction.message.recent_blockhash.to_string(),
-                        instructions: transaction
-                            .message
-                            .instructions
-                            .iter()
-                            .map(|instruction| {
-                                UiInstruction::parse(instruction, &transaction.message)
-                            })
-                            .collect(),
-                    })
+                        address_table_lookups: match &message {
+                            VersionedMessage::Legacy(_) => None,
+                            VersionedMessage::V0(message) => Some(
+                                message
+                                    .address_table_lookups
+                                    .iter()
+                                    .map(|lookup| lookup.into())
+                                    .collect(),
+                            ),
+                        },
+                    }),
                 };
                 EncodedTransaction::Json(UiTransaction {
-                    signatures: transaction
-                        .signatures
-                        .iter()
-                        .map(|sig| sig.to_string())
-                        .collect(),
+                    signatures,
                     message,
                 })
             }
         }
     }
+
     pub fn decode(&self) -> Option<Transaction> {
         match self {
             EncodedTransaction::Json(_) => None,
@@ -491,6 +685,11 @@ impl EncodedTransaction {
 #[cfg(test)]
 mod test {
     use super::*;
+    use serde_json::json;
+    use solana_sdk::{
+        message::v0,
+        signature::{Keypair, Signer},
+    };
 
     #[test]
     fn test_satisfies_commitment() {
@@ -514,4 +713,100 @@ mod test {
         assert!(!status.satisfies_commitment(CommitmentConfig::default()));
         assert!(status.satisfies_commitment(CommitmentConfig::recent()));
     }
+
+    #[test]
+    fn test_encode_loaded_addresses() {
+        let payer = Keypair::new();
+        let loaded_key = Pubkey::new_unique();
+        let table_key = Pubkey::new_unique();
+        let message = v0::Message::new_with_address_table_lookups(
+            &[solana_sdk::system_instruction::transfer(
+                &payer.pubkey(),
+                &loaded_key,
+                1,
+            )],
+            Some(&payer.pubkey()),
+            &[(table_key, vec![loaded_key])],
+        );
+        assert_eq!(message.account_keys.len(), 2);
+        let transaction =
+            VersionedTransaction::try_new(VersionedMessage::V0(message), &[&payer]).unwrap();
+        let transaction_with_meta = VersionedTransactionWithStatusMeta {
+            transaction,
+            meta: Some(TransactionStatusMeta {
+                loaded_addresses: Some(LoadedAddresses {
+                    writable: vec![loaded_key],
+                    readonly: vec![],
+                }),
+                ..TransactionStatusMeta::default()
+            }),
+        };
+
+        let encoded = transaction_with_meta
+            .clone()
+            .encode(UiTransactionEncoding::Json);
+        let meta = encoded.meta.unwrap();
+        assert_eq!(
+            meta.loaded_addresses,
+            Some(UiLoadedAddresses {
+                writable: vec![loaded_key.to_string()],
+                readonly: vec![],
+            })
+        );
+        match encoded.transaction {
+            EncodedTransaction::Json(UiTransaction {
+                message: UiMessage::Raw(message),
+                ..
+            }) => {
+                assert_eq!(message.account_keys.len(), 2);
+                assert_eq!(
+                    message.address_table_lookups,
+                    Some(vec![UiAddressTableLookup {
+                        account_key: table_key.to_string(),
+                        writable_indexes: vec![0],
+                        readonly_indexes: vec![],
+                    }])
+                );
+                assert_eq!(message.instructions[0].accounts, vec![0, 2]);
+            }
+            _ => panic!("expected raw json message"),
+        }
+
+        let encoded = transaction_with_meta
+            .clone()
+            .encode(UiTransactionEncoding::JsonParsed);
+        match encoded.transaction {
+            EncodedTransaction::Json(UiTransaction {
+                message: UiMessage::Parsed(message),
+                ..
+            }) => {
+                assert_eq!(message.account_keys[2].pubkey, loaded_key.to_string());
+                assert!(message.account_keys[2].writable);
+                match &message.instructions[0] {
+                    UiInstruction::Parsed(UiParsedInstruction::Parsed(instruction)) => {
+                        assert_eq!(
+                            instruction.parsed["info"]["destination"],
+                            json!(loaded_key.to_string())
+                        );
+                    }
+                    instruction => panic!("unexpected instruction {:?}", instruction),
+                }
+            }
+            _ => panic!("expected parsed json message"),
+        }
+
+        // Without its loaded addresses the message can't be parsed
+        let encoded = VersionedTransactionWithStatusMeta {
+            meta: None,
+            ..transaction_with_meta
+        }
+        .encode(UiTransactionEncoding::JsonParsed);
+        assert!(matches!(
+            encoded.transaction,
+            EncodedTransaction::Json(UiTransaction {
+                message: UiMessage::Raw(_),
+                ..
+            })
+        ));
+    }
 }
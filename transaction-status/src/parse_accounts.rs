@@ -1,4 +1,4 @@
-use solana_sdk::message::Message;
+use solana_sdk::message::{v0::LoadedMessage, Message};
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
@@ -20,6 +20,18 @@ pub fn parse_accounts(message: &Message) -> Vec<ParsedAccount> {
     accounts
 }
 
+pub fn parse_loaded_accounts(message: &LoadedMessage) -> Vec<ParsedAccount> {
+    let mut accounts: Vec<ParsedAccount> = vec![];
+    for (i, account_key) in message.account_keys().enumerate() {
+        accounts.push(ParsedAccount {
+            pubkey: account_key.to_string(),
+            writable: message.is_writable(i),
+            signer: message.is_signer(i),
+        });
+    }
+    accounts
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
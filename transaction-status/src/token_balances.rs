@@ -0,0 +1,206 @@
+use crate::TransactionTokenBalance;
+use solana_account_decoder::parse_token::{
+    pubkey_from_spl_token_v2_0, spl_token_id_v2_0, spl_token_v2_0_native_mint,
+    token_amount_to_ui_amount, UiTokenAmount,
+};
+use solana_runtime::{
+    bank::Bank, transaction_batch::TransactionBatch, transaction_utils::OrderedIterator,
+};
+use solana_sdk::pubkey::Pubkey;
+use spl_token_v2_0::{
+    solana_program::program_pack::Pack,
+    state::{Account as TokenAccount, Mint},
+};
+use std::collections::HashMap;
+
+pub type TransactionTokenBalances = Vec<Vec<TransactionTokenBalance>>;
+
+pub struct TransactionTokenBalancesSet {
+    pub pre_token_balances: TransactionTokenBalances,
+    pub post_token_balances: TransactionTokenBalances,
+}
+
+impl TransactionTokenBalancesSet {
+    pub fn new(
+        pre_token_balances: TransactionTokenBalances,
+        post_token_balances: TransactionTokenBalances,
+    ) -> Self {
+        assert_eq!(pre_token_balances.len(), post_token_balances.len());
+        Self {
+            pre_token_balances,
+            post_token_balances,
+        }
+    }
+}
+
+fn is_token_program(program_id: &Pubkey) -> bool {
+    program_id == &spl_token_id_v2_0()
+}
+
+fn get_mint_decimals(bank: &Bank, mint: &Pubkey) -> Option<u8> {
+    if mint == &spl_token_v2_0_native_mint() {
+        Some(spl_token_v2_0::native_mint::DECIMALS)
+    } else {
+        let mint_account = bank.get_account(mint)?;
+        if !is_token_program(&mint_account.owner) {
+            return None;
+        }
+        Mint::unpack(&mint_account.data)
+            .map(|mint| mint.decimals)
+            .ok()
+    }
+}
+
+/// Collect the balances of the SPL Token accounts referenced by each transaction in the batch,
+/// in batch iteration order. Transactions that don't invoke the SPL Token program get an empty
+/// list. `mint_decimals` caches decimals across calls so the pre- and post-execution passes only
+/// load each mint once.
+pub fn collect_token_balances(
+    bank: &Bank,
+    batch: &TransactionBatch,
+    mint_decimals: &mut HashMap<Pubkey, u8>,
+) -> TransactionTokenBalances {
+    let mut balances: TransactionTokenBalances = vec![];
+
+    for (_, transaction) in OrderedIterator::new(batch.transactions(), batch.iteration_order()) {
+        let account_keys = &transaction.message.account_keys;
+        let has_token_program = account_keys.iter().any(is_token_program);
+
+        let mut transaction_balances: Vec<TransactionTokenBalance> = vec![];
+        if has_token_program {
+            for (index, account_id) in account_keys.iter().enumerate() {
+                if is_token_program(account_id) {
+                    continue;
+                }
+
+                if let Some((mint, ui_token_amount)) =
+                    collect_token_balance_from_account(bank, account_id, mint_decimals)
+                {
+                    transaction_balances.push(TransactionTokenBalance {
+                        account_index: index as u8,
+                        mint,
+                        ui_token_amount,
+                    });
+                }
+            }
+        }
+        balances.push(transaction_balances);
+    }
+    balances
+}
+
+fn collect_token_balance_from_account(
+    bank: &Bank,
+    account_id: &Pubkey,
+    mint_decimals: &mut HashMap<Pubkey, u8>,
+) -> Option<(String, UiTokenAmount)> {
+    let account = bank.get_account(account_id)?;
+    if !is_token_program(&account.owner) {
+        return None;
+    }
+    let token_account = TokenAccount::unpack(&account.data).ok()?;
+    let mint = pubkey_from_spl_token_v2_0(&token_account.mint);
+
+    let decimals = match mint_decimals.get(&mint) {
+        Some(decimals) => *decimals,
+        None => {
+            let decimals = get_mint_decimals(bank, &mint)?;
+            mint_decimals.insert(mint, decimals);
+            decimals
+        }
+    };
+
+    Some((
+        mint.to_string(),
+        token_amount_to_ui_amount(token_account.amount, decimals),
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use solana_account_decoder::parse_token::spl_token_v2_0_pubkey;
+    use solana_sdk::{
+        account::Account, genesis_config::create_genesis_config, instruction::CompiledInstruction,
+        message::Message, signature::Signer, system_transaction, transaction::Transaction,
+    };
+    use spl_token_v2_0::state::AccountState;
+
+    #[test]
+    fn test_collect_token_balances() {
+        let (genesis_config, mint_keypair) = create_genesis_config(1_000_000);
+        let bank = Bank::new(&genesis_config);
+
+        let mint = Pubkey::new_unique();
+        let mut mint_account = Account::new(1, Mint::get_packed_len(), &spl_token_id_v2_0());
+        Mint::pack(
+            Mint {
+                decimals: 2,
+                is_initialized: true,
+                ..Mint::default()
+            },
+            &mut mint_account.data,
+        )
+        .unwrap();
+        bank.store_account(&mint, &mint_account);
+
+        let token_account_key = Pubkey::new_unique();
+        let mut token_account =
+            Account::new(1, TokenAccount::get_packed_len(), &spl_token_id_v2_0());
+        TokenAccount::pack(
+            TokenAccount {
+                mint: spl_token_v2_0_pubkey(&mint),
+                owner: spl_token_v2_0_pubkey(&Pubkey::new_unique()),
+                amount: 4_200,
+                state: AccountState::Initialized,
+                ..TokenAccount::default()
+            },
+            &mut token_account.data,
+        )
+        .unwrap();
+        bank.store_account(&token_account_key, &token_account);
+
+        let system_account_key = Pubkey::new_unique();
+        bank.store_account(&system_account_key, &Account::new(1, 0, &Pubkey::default()));
+
+        let message = Message::new_with_compiled_instructions(
+            1,
+            0,
+            1,
+            vec![
+                mint_keypair.pubkey(),
+                token_account_key,
+                system_account_key,
+                spl_token_id_v2_0(),
+            ],
+            bank.last_blockhash(),
+            vec![CompiledInstruction::new(3, &(), vec![1, 2])],
+        );
+        let txs = vec![
+            Transaction::new(&[&mint_keypair], message, bank.last_blockhash()),
+            system_transaction::transfer(
+                &mint_keypair,
+                &token_account_key,
+                1,
+                bank.last_blockhash(),
+            ),
+        ];
+        let batch = bank.prepare_batch(&txs, None);
+
+        let mut mint_decimals = HashMap::new();
+        let balances = collect_token_balances(&bank, &batch, &mut mint_decimals);
+        assert_eq!(
+            balances,
+            vec![
+                vec![TransactionTokenBalance {
+                    account_index: 1,
+                    mint: mint.to_string(),
+                    ui_token_amount: token_amount_to_ui_amount(4_200, 2),
+                }],
+                // The token program isn't invoked, so no token balances are collected
+                vec![],
+            ]
+        );
+        assert_eq!(mint_decimals.get(&mint), Some(&2));
+    }
+}
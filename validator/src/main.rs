@@ -13,7 +13,7 @@ use solana_clap_utils::{
 };
 use solana_client::rpc_client::RpcClient;
 use solana_core::ledger_cleanup_service::{
-    DEFAULT_MAX_LEDGER_SHREDS, DEFAULT_MIN_MAX_LEDGER_SHREDS,
+    TransactionHistoryLimits, DEFAULT_MAX_LEDGER_SHREDS, DEFAULT_MIN_MAX_LEDGER_SHREDS,
 };
 use solana_core::{
     cluster_info::{ClusterInfo, Node, MINIMUM_VALIDATOR_PORT_RANGE_WIDTH, VALIDATOR_PORT_RANGE},
@@ -23,10 +23,13 @@ use solana_core::{
     rpc_pubsub_service::PubSubConfig,
     validator::{Validator, ValidatorConfig},
 };
-use solana_download_utils::{download_genesis_if_missing, download_snapshot};
+use solana_download_utils::{
+    download_genesis_if_missing, download_incremental_snapshot, download_snapshot,
+};
 use solana_ledger::blockstore_db::BlockstoreRecoveryMode;
 use solana_perf::recycler::enable_recycler_warming;
 use solana_runtime::{
+    accounts_index::{AccountIndex, AccountSecondaryIndexes},
     bank_forks::{CompressionType, SnapshotConfig, SnapshotVersion},
     hardened_unpack::{unpack_genesis_archive, MAX_GENESIS_ARCHIVE_UNPACKED_SIZE},
     snapshot_utils::get_highest_snapshot_archive_path,
@@ -39,6 +42,8 @@ use solana_sdk::{
     pubkey::Pubkey,
     signature::{Keypair, Signer},
 };
+use solana_streamer::quic::QuicServerConfig;
+use solana_vote_signer::remote::{RemoteVoteSigner, VoteSignerAddr};
 use std::{
     collections::HashSet,
     env,
@@ -489,6 +494,17 @@ fn is_snapshot_config_invalid(
             || snapshot_interval_slots % accounts_hash_interval_slots != 0)
 }
 
+fn is_incremental_snapshot_config_invalid(
+    incremental_snapshot_interval_slots: u64,
+    snapshot_interval_slots: u64,
+    accounts_hash_interval_slots: u64,
+) -> bool {
+    incremental_snapshot_interval_slots != 0
+        && (snapshot_interval_slots == 0
+            || incremental_snapshot_interval_slots >= snapshot_interval_slots
+            || incremental_snapshot_interval_slots % accounts_hash_interval_slots != 0)
+}
+
 #[cfg(unix)]
 fn redirect_stderr(filename: &str) {
     use std::{fs::OpenOptions, os::unix::io::AsRawFd};
@@ -563,6 +579,11 @@ fn verify_reachable_ports(
     if ContactInfo::is_valid_address(&node.info.tpu_forwards) {
         udp_sockets.extend(node.sockets.tpu_forwards.iter());
     }
+    if let Some(tpu_quic) = &node.sockets.tpu_quic {
+        if ContactInfo::is_valid_address(&node.info.tpu) {
+            udp_sockets.push(tpu_quic);
+        }
+    }
     if ContactInfo::is_valid_address(&node.info.tvu) {
         udp_sockets.extend(node.sockets.tvu.iter());
         udp_sockets.extend(node.sockets.broadcast.iter());
@@ -745,15 +766,54 @@ fn rpc_bootstrap(
                         .map_err(|err| format!("Failed to get RPC node slot: {}", err))
                         .and_then(|slot| {
                             info!("RPC node root slot: {}", slot);
-                            let (_cluster_info, gossip_exit_flag, gossip_service) =
+                            let (cluster_info, gossip_exit_flag, gossip_service) =
                                 gossip.take().unwrap();
+                            // Also fetch the newest incremental snapshot the node built on top of
+                            // this full snapshot, unless trusted validators are required and the
+                            // node isn't one of them
+                            let incremental_snapshot_hash = if validator_config
+                                .trusted_validators
+                                .is_none()
+                                || is_trusted_validator(
+                                    &rpc_contact_info.id,
+                                    &validator_config.trusted_validators,
+                                ) {
+                                cluster_info
+                                    .get_incremental_snapshot_hashes_for_node(
+                                        &rpc_contact_info.id,
+                                        |incremental_snapshot_hashes| {
+                                            if incremental_snapshot_hashes.base == snapshot_hash {
+                                                incremental_snapshot_hashes.hashes.last().copied()
+                                            } else {
+                                                None
+                                            }
+                                        },
+                                    )
+                                    .flatten()
+                            } else {
+                                None
+                            };
                             gossip_exit_flag.store(true, Ordering::Relaxed);
                             let ret = download_snapshot(
                                 &rpc_contact_info.rpc,
                                 &ledger_path,
                                 snapshot_hash,
                                 use_progress_bar,
-                            );
+                            )
+                            .map(|()| {
+                                if let Some(incremental_snapshot_hash) = incremental_snapshot_hash
+                                {
+                                    if let Err(err) = download_incremental_snapshot(
+                                        &rpc_contact_info.rpc,
+                                        &ledger_path,
+                                        snapshot_hash.0,
+                                        incremental_snapshot_hash,
+                                        use_progress_bar,
+                                    ) {
+                                        warn!("{}, using the full snapshot only", err);
+                                    }
+                                }
+                            });
                             gossip_service.join().unwrap();
                             ret
                         })
@@ -764,14 +824,23 @@ fn rpc_bootstrap(
         })
         .map(|_| {
             if !validator_config.voting_disabled && !bootstrap_config.no_check_vote_account {
+                let mut authorized_voter_pubkeys: Vec<_> =
+                    authorized_voter_keypairs.iter().map(|k| k.pubkey()).collect();
+                if let Some(remote_vote_signer) = &validator_config.remote_vote_signer {
+                    match remote_vote_signer.pubkeys() {
+                        Ok(pubkeys) => authorized_voter_pubkeys.extend(pubkeys),
+                        Err(err) => warn!(
+                            "Unable to reach the vote signer {}: {}",
+                            remote_vote_signer.addr(),
+                            err
+                        ),
+                    }
+                }
                 check_vote_account(
                     &rpc_client,
                     &identity_keypair.pubkey(),
                     &vote_account,
-                    &authorized_voter_keypairs
-                        .iter()
-                        .map(|k| k.pubkey())
-                        .collect::<Vec<_>>(),
+                    &authorized_voter_pubkeys,
                 )
                 .unwrap_or_else(|err| {
                     // Consider failures here to be more likely due to user error (eg,
@@ -868,6 +937,18 @@ pub fn main() {
         PubSubConfig::default().max_in_buffer_capacity.to_string();
     let default_rpc_pubsub_max_out_buffer_capacity =
         PubSubConfig::default().max_out_buffer_capacity.to_string();
+    let default_tpu_quic_max_connections_per_ip = QuicServerConfig::default()
+        .max_connections_per_ip
+        .to_string();
+    let default_tpu_quic_max_staked_connections = QuicServerConfig::default()
+        .max_staked_connections
+        .to_string();
+    let default_tpu_quic_max_unstaked_connections = QuicServerConfig::default()
+        .max_unstaked_connections
+        .to_string();
+    let default_tpu_quic_max_streams_per_second = QuicServerConfig::default()
+        .max_streams_per_second
+        .to_string();
 
     let matches = App::new(crate_name!()).about(crate_description!())
         .version(solana_version::version!())
@@ -897,6 +978,19 @@ pub fn main() {
                        May be specified multiple times. \
                        [default: the --identity keypair]"),
         )
+        .arg(
+            Arg::with_name("remote_vote_signer")
+                .long("remote-vote-signer")
+                .value_name("ADDRESS")
+                .takes_value(true)
+                .validator(|value| value.parse::<VoteSignerAddr>().map(|_| ()))
+                .requires("vote_account")
+                .conflicts_with("authorized_voter_keypairs")
+                .help("Sign every vote with the solana-vote-signer listening on unix:<PATH> \
+                       or <HOST>:<PORT> instead of an authorized voter keypair. \
+                       The signer must authorize the --identity keypair, which signs \
+                       every request. The signer refuses votes that violate lockout"),
+        )
         .arg(
             Arg::with_name("vote_account")
                 .long("vote-account")
@@ -1023,6 +1117,15 @@ pub fn main() {
                        including the 'getConfirmedBlock' API.  \
                        This will cause an increase in disk usage and IOPS"),
         )
+        .arg(
+            Arg::with_name("enable_rpc_account_history")
+                .long("enable-rpc-account-history")
+                .takes_value(false)
+                .help("Record every account written by a rooted slot, and serve past \
+                       account states with the 'atSlot' option of 'getAccountInfo'. \
+                       Only slots rooted while this is enabled are recorded. \
+                       This will cause an increase in disk usage and IOPS"),
+        )
         .arg(
             Arg::with_name("enable_rpc_bigtable_ledger_storage")
                 .long("enable-rpc-bigtable-ledger-storage")
@@ -1038,6 +1141,15 @@ pub fn main() {
                 .takes_value(false)
                 .help("Upload new confirmed blocks into a BigTable instance"),
         )
+        .arg(
+            Arg::with_name("local_ledger_storage")
+                .long("local-ledger-storage")
+                .value_name("DIR")
+                .takes_value(true)
+                .help("Keep the ledger storage used by --enable-rpc-bigtable-ledger-storage \
+                       and --enable-bigtable-ledger-upload in a local database at DIR \
+                       instead of a BigTable instance"),
+        )
         .arg(
             Arg::with_name("health_check_slot_distance")
                 .long("health-check-slot-distance")
@@ -1130,6 +1242,16 @@ pub fn main() {
                 .help("Number of slots between generating snapshots, \
                       0 to disable snapshots"),
         )
+        .arg(
+            Arg::with_name("incremental_snapshot_interval_slots")
+                .long("incremental-snapshot-interval-slots")
+                .value_name("INCREMENTAL_SNAPSHOT_INTERVAL_SLOTS")
+                .takes_value(true)
+                .default_value("0")
+                .help("Number of slots between generating incremental snapshots, \
+                      which only contain the accounts changed since the last full snapshot. \
+                      0 to disable incremental snapshots"),
+        )
         .arg(
             Arg::with_name("contact_debug_interval")
                 .long("contact-debug-interval")
@@ -1165,6 +1287,58 @@ pub fn main() {
                 /* .default_value() intentionally not used here! */
                 .help("Keep this amount of shreds in root slots."),
         )
+        .arg(
+            Arg::with_name("limit_ledger_transaction_history")
+                .long("limit-ledger-transaction-history")
+                .value_name("SLOT_COUNT")
+                .takes_value(true)
+                .requires("limit_ledger_size")
+                .validator(is_parsable::<u64>)
+                .help("Keep the transaction statuses, address signatures and rewards of this \
+                       many slots below the latest root, even once --limit-ledger-size has purged \
+                       the shreds of those slots. Roots are kept along with transaction statuses \
+                       and address signatures, block times along with shreds"),
+        )
+        .arg(
+            Arg::with_name("limit_ledger_transaction_status")
+                .long("limit-ledger-transaction-status")
+                .value_name("SLOT_COUNT")
+                .takes_value(true)
+                .requires("limit_ledger_size")
+                .validator(is_parsable::<u64>)
+                .help("Keep the transaction statuses of this many slots below the latest root \
+                       [default: --limit-ledger-transaction-history]"),
+        )
+        .arg(
+            Arg::with_name("limit_ledger_address_signatures")
+                .long("limit-ledger-address-signatures")
+                .value_name("SLOT_COUNT")
+                .takes_value(true)
+                .requires("limit_ledger_size")
+                .validator(is_parsable::<u64>)
+                .help("Keep the address signatures of this many slots below the latest root \
+                       [default: --limit-ledger-transaction-history]"),
+        )
+        .arg(
+            Arg::with_name("limit_ledger_rewards")
+                .long("limit-ledger-rewards")
+                .value_name("SLOT_COUNT")
+                .takes_value(true)
+                .requires("limit_ledger_size")
+                .validator(is_parsable::<u64>)
+                .help("Keep the rewards of this many slots below the latest root \
+                       [default: --limit-ledger-transaction-history]"),
+        )
+        .arg(
+            Arg::with_name("limit_ledger_account_history")
+                .long("limit-ledger-account-history")
+                .value_name("SLOT_COUNT")
+                .takes_value(true)
+                .requires("limit_ledger_size")
+                .validator(is_parsable::<u64>)
+                .help("Keep the account history of this many slots below the latest root \
+                       [default: purged along with the shreds]"),
+        )
         .arg(
             Arg::with_name("skip_poh_verify")
                 .long("skip-poh-verify")
@@ -1348,6 +1522,50 @@ pub fn main() {
                 .default_value(&default_rpc_pubsub_max_out_buffer_capacity)
                 .help("The maximum size in bytes to which the outgoing websocket buffer can grow."),
         )
+        .arg(
+            Arg::with_name("tpu_use_quic")
+                .long("tpu-use-quic")
+                .takes_value(false)
+                .help("Also accept transactions over QUIC, on the port 6 above the TPU port. \
+                       Both ports are taken from --dynamic-port-range"),
+        )
+        .arg(
+            Arg::with_name("tpu_quic_max_connections_per_ip")
+                .long("tpu-quic-max-connections-per-ip")
+                .value_name("NUMBER")
+                .takes_value(true)
+                .validator(is_parsable::<usize>)
+                .default_value(&default_tpu_quic_max_connections_per_ip)
+                .help("The maximum number of QUIC TPU connections from an unstaked IP address"),
+        )
+        .arg(
+            Arg::with_name("tpu_quic_max_staked_connections")
+                .long("tpu-quic-max-staked-connections")
+                .value_name("NUMBER")
+                .takes_value(true)
+                .validator(is_parsable::<usize>)
+                .default_value(&default_tpu_quic_max_staked_connections)
+                .help("The number of QUIC TPU connections shared among staked nodes, \
+                       each node's quota is in proportion to its stake"),
+        )
+        .arg(
+            Arg::with_name("tpu_quic_max_unstaked_connections")
+                .long("tpu-quic-max-unstaked-connections")
+                .value_name("NUMBER")
+                .takes_value(true)
+                .validator(is_parsable::<usize>)
+                .default_value(&default_tpu_quic_max_unstaked_connections)
+                .help("The number of QUIC TPU connections shared among unstaked clients"),
+        )
+        .arg(
+            Arg::with_name("tpu_quic_max_streams_per_second")
+                .long("tpu-quic-max-streams-per-second")
+                .value_name("NUMBER")
+                .takes_value(true)
+                .validator(is_parsable::<u64>)
+                .default_value(&default_tpu_quic_max_streams_per_second)
+                .help("The maximum number of transactions accepted per second on one QUIC TPU connection"),
+        )
         .arg(
             Arg::with_name("halt_on_trusted_validators_accounts_hash_mismatch")
                 .long("halt-on-trusted-validators-accounts-hash-mismatch")
@@ -1399,13 +1617,42 @@ pub fn main() {
                     "Mode to recovery the ledger db write ahead log."
                 ),
         )
+        .arg(
+            Arg::with_name("account_indexes")
+                .long("account-index")
+                .takes_value(true)
+                .multiple(true)
+                .possible_values(&["program-id", "spl-token-owner", "spl-token-mint"])
+                .value_name("INDEX")
+                .help("Enable an accounts index, indexed by the selected account field"),
+        )
+        .arg(
+            Arg::with_name("geyser_plugin_config")
+                .long("geyser-plugin-config")
+                .value_name("FILE")
+                .takes_value(true)
+                .multiple(true)
+                .help("Load a Geyser plugin described by this JSON config file, \
+                       streaming account writes, transactions and slot status \
+                       updates to it"),
+        )
         .get_matches();
 
     let identity_keypair = Arc::new(keypair_of(&matches, "identity").unwrap_or_else(Keypair::new));
 
+    let remote_vote_signer = value_t!(matches, "remote_vote_signer", VoteSignerAddr)
+        .ok()
+        .map(|addr| Arc::new(RemoteVoteSigner::new(addr, identity_keypair.clone())));
+
     let authorized_voter_keypairs = keypairs_of(&matches, "authorized_voter_keypairs")
         .map(|keypairs| keypairs.into_iter().map(Arc::new).collect())
-        .unwrap_or_else(|| vec![identity_keypair.clone()]);
+        .unwrap_or_else(|| {
+            if remote_vote_signer.is_some() {
+                vec![]
+            } else {
+                vec![identity_keypair.clone()]
+            }
+        });
 
     let ledger_path = PathBuf::from(matches.value_of("ledger_path").unwrap());
     let init_complete_file = matches.value_of("init_complete_file");
@@ -1476,6 +1723,18 @@ pub fn main() {
 
     let contact_debug_interval = value_t_or_exit!(matches, "contact_debug_interval", u64);
 
+    let account_indexes: AccountSecondaryIndexes = matches
+        .values_of("account_indexes")
+        .into_iter()
+        .flatten()
+        .map(|value| match value {
+            "program-id" => AccountIndex::ProgramId,
+            "spl-token-mint" => AccountIndex::SplTokenMint,
+            "spl-token-owner" => AccountIndex::SplTokenOwner,
+            _ => unreachable!(),
+        })
+        .collect();
+
     let restricted_repair_only_mode = matches.is_present("restricted_repair_only_mode");
     let mut validator_config = ValidatorConfig {
         require_tower: matches.is_present("require_tower"),
@@ -1493,9 +1752,11 @@ pub fn main() {
             enable_validator_exit: matches.is_present("enable_rpc_exit"),
             enable_set_log_filter: matches.is_present("enable_rpc_set_log_filter"),
             enable_rpc_transaction_history: matches.is_present("enable_rpc_transaction_history"),
+            enable_rpc_account_history: matches.is_present("enable_rpc_account_history"),
             enable_bigtable_ledger_storage: matches
                 .is_present("enable_rpc_bigtable_ledger_storage"),
             enable_bigtable_ledger_upload: matches.is_present("enable_bigtable_ledger_upload"),
+            local_ledger_storage_path: value_t!(matches, "local_ledger_storage", PathBuf).ok(),
             identity_pubkey: identity_keypair.pubkey(),
             faucet_addr: matches.value_of("rpc_faucet_addr").map(|address| {
                 solana_net_utils::parse_host_port(address).expect("failed to parse faucet address")
@@ -1505,6 +1766,7 @@ pub fn main() {
                 "health_check_slot_distance",
                 u64
             ),
+            account_indexes: account_indexes.clone(),
         },
         rpc_addrs: value_t!(matches, "rpc_port", u16).ok().map(|rpc_port| {
             (
@@ -1541,6 +1803,35 @@ pub fn main() {
         poh_verify: !matches.is_present("skip_poh_verify"),
         debug_keys,
         contact_debug_interval,
+        account_indexes,
+        geyser_plugin_config_files: values_t!(matches, "geyser_plugin_config", PathBuf).ok(),
+        tpu_quic_config: if matches.is_present("tpu_use_quic") && !restricted_repair_only_mode {
+            Some(QuicServerConfig {
+                max_connections_per_ip: value_t_or_exit!(
+                    matches,
+                    "tpu_quic_max_connections_per_ip",
+                    usize
+                ),
+                max_staked_connections: value_t_or_exit!(
+                    matches,
+                    "tpu_quic_max_staked_connections",
+                    usize
+                ),
+                max_unstaked_connections: value_t_or_exit!(
+                    matches,
+                    "tpu_quic_max_unstaked_connections",
+                    usize
+                ),
+                max_streams_per_second: value_t_or_exit!(
+                    matches,
+                    "tpu_quic_max_streams_per_second",
+                    u64
+                ),
+            })
+        } else {
+            None
+        },
+        remote_vote_signer,
         ..ValidatorConfig::default()
     };
 
@@ -1580,6 +1871,8 @@ pub fn main() {
         .collect();
 
     let snapshot_interval_slots = value_t_or_exit!(matches, "snapshot_interval_slots", u64);
+    let incremental_snapshot_interval_slots =
+        value_t_or_exit!(matches, "incremental_snapshot_interval_slots", u64);
     let maximum_local_snapshot_age = value_t_or_exit!(matches, "maximum_local_snapshot_age", u64);
     let snapshot_path = ledger_path.join("snapshot");
     fs::create_dir_all(&snapshot_path).unwrap_or_else(|err| {
@@ -1616,6 +1909,11 @@ pub fn main() {
         } else {
             std::u64::MAX
         },
+        incremental_snapshot_interval_slots: if incremental_snapshot_interval_slots > 0 {
+            incremental_snapshot_interval_slots
+        } else {
+            std::u64::MAX
+        },
         snapshot_path,
         snapshot_package_output_path: ledger_path.clone(),
         compression: snapshot_compression,
@@ -1638,6 +1936,18 @@ pub fn main() {
         );
         exit(1);
     }
+    if is_incremental_snapshot_config_invalid(
+        incremental_snapshot_interval_slots,
+        snapshot_interval_slots,
+        validator_config.accounts_hash_interval_slots,
+    ) {
+        eprintln!("Invalid incremental snapshot interval provided ({}), must be a multiple of accounts_hash_interval_slots ({}) and less than the snapshot interval ({})",
+            incremental_snapshot_interval_slots,
+            validator_config.accounts_hash_interval_slots,
+            snapshot_interval_slots,
+        );
+        exit(1);
+    }
 
     if matches.is_present("limit_ledger_size") {
         let limit_ledger_size = match matches.value_of("limit_ledger_size") {
@@ -1652,6 +1962,19 @@ pub fn main() {
             exit(1);
         }
         validator_config.max_ledger_shreds = Some(limit_ledger_size);
+        let limit_ledger_transaction_history =
+            value_t!(matches, "limit_ledger_transaction_history", u64).ok();
+        let limit_ledger_column = |name| {
+            value_t!(matches, name, u64)
+                .ok()
+                .or(limit_ledger_transaction_history)
+        };
+        validator_config.transaction_history_limits = TransactionHistoryLimits {
+            transaction_status_slots: limit_ledger_column("limit_ledger_transaction_status"),
+            address_signatures_slots: limit_ledger_column("limit_ledger_address_signatures"),
+            rewards_slots: limit_ledger_column("limit_ledger_rewards"),
+            account_history_slots: value_t!(matches, "limit_ledger_account_history", u64).ok(),
+        };
     }
 
     if matches.is_present("halt_on_trusted_validators_accounts_hash_mismatch") {
@@ -1740,7 +2063,15 @@ pub fn main() {
         &gossip_addr,
         dynamic_port_range,
         bind_address,
-    );
+        validator_config.tpu_quic_config.is_some(),
+    )
+    .unwrap_or_else(|err| {
+        eprintln!(
+            "Unable to bind the validator ports in {:?}: {}",
+            dynamic_port_range, err
+        );
+        exit(1);
+    });
 
     if restricted_repair_only_mode {
         let any = SocketAddr::new(std::net::IpAddr::V4(std::net::Ipv4Addr::new(0, 0, 0, 0)), 0);
@@ -1813,4 +2144,14 @@ pub mod tests {
         assert!(!is_snapshot_config_invalid(500, 100));
         assert!(!is_snapshot_config_invalid(5, 5));
     }
+
+    #[test]
+    fn test_interval_check_incremental() {
+        assert!(!is_incremental_snapshot_config_invalid(0, 0, 100));
+        assert!(!is_incremental_snapshot_config_invalid(0, 500, 100));
+        assert!(!is_incremental_snapshot_config_invalid(100, 500, 100));
+        assert!(is_incremental_snapshot_config_invalid(100, 0, 100));
+        assert!(is_incremental_snapshot_config_invalid(500, 500, 100));
+        assert!(is_incremental_snapshot_config_invalid(150, 500, 100));
+    }
 }
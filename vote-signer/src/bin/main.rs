@@ -1,7 +1,16 @@
-use clap::{crate_description, crate_name, App, Arg};
-use solana_vote_signer::rpc::VoteSignerRpcService;
+use clap::{crate_description, crate_name, value_t_or_exit, App, Arg};
+use solana_clap_utils::{
+    input_parsers::{keypairs_of, pubkeys_of},
+    input_validators::{is_keypair_or_ask_keyword, is_pubkey_or_keypair},
+};
+use solana_vote_signer::{
+    remote::{RemoteVoteSignerService, VoteSignerAddr},
+    rpc::VoteSignerRpcService,
+    slashing_protection::SlashingProtectionDb,
+};
 use std::error;
 use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::path::PathBuf;
 use std::sync::atomic::AtomicBool;
 use std::sync::Arc;
 pub const RPC_PORT: u16 = 8989;
@@ -17,10 +26,72 @@ fn main() -> Result<(), Box<dyn error::Error>> {
                 .long("port")
                 .value_name("NUM")
                 .takes_value(true)
+                .conflicts_with("listen")
                 .help("JSON RPC listener port"),
         )
+        .arg(
+            Arg::with_name("listen")
+                .long("listen")
+                .value_name("ADDRESS")
+                .takes_value(true)
+                .validator(|value| value.parse::<VoteSignerAddr>().map(|_| ()))
+                .requires_all(&["keypairs", "authorized_clients", "slashing_protection_db"])
+                .help(
+                    "Serve validators as a remote vote signer on unix:<PATH> or \
+                     <HOST>:<PORT> instead of the JSON RPC service",
+                ),
+        )
+        .arg(
+            Arg::with_name("authorized_clients")
+                .long("authorized-client")
+                .value_name("PUBKEY")
+                .takes_value(true)
+                .multiple(true)
+                .validator(is_pubkey_or_keypair)
+                .help("Identity of a validator allowed to request signatures; requests must be signed by it [can be specified multiple times]"),
+        )
+        .arg(
+            Arg::with_name("keypairs")
+                .long("keypair")
+                .value_name("KEYPAIR")
+                .takes_value(true)
+                .multiple(true)
+                .validator(is_keypair_or_ask_keyword)
+                .help("Keypair to sign votes with, usually the authorized voter [can be specified multiple times]"),
+        )
+        .arg(
+            Arg::with_name("slashing_protection_db")
+                .long("slashing-protection-db")
+                .value_name("DIR")
+                .takes_value(true)
+                .help("Directory of the record of signed votes, checked before signing a vote"),
+        )
         .get_matches();
 
+    let exit = Arc::new(AtomicBool::new(false));
+
+    if matches.is_present("listen") {
+        let addr = value_t_or_exit!(matches, "listen", VoteSignerAddr);
+        let keypairs = keypairs_of(&matches, "keypairs").unwrap();
+        let authorized_clients = pubkeys_of(&matches, "authorized_clients")
+            .unwrap()
+            .into_iter()
+            .collect();
+        let slashing_protection_db = SlashingProtectionDb::open(&PathBuf::from(
+            matches.value_of("slashing_protection_db").unwrap(),
+        ))?;
+        let service = RemoteVoteSignerService::new(
+            &addr,
+            keypairs,
+            authorized_clients,
+            slashing_protection_db,
+            &exit,
+        )?;
+        println!("Vote signer listening on {}", addr);
+        service.join().unwrap();
+        return Ok(());
+    }
+
     let port = if let Some(p) = matches.value_of("port") {
         p.to_string()
             .parse()
@@ -29,7 +100,6 @@ fn main() -> Result<(), Box<dyn error::Error>> {
         RPC_PORT
     };
 
-    let exit = Arc::new(AtomicBool::new(false));
     let service = VoteSignerRpcService::new(
         SocketAddr::new(IpAddr::V4(Ipv4Addr::new(0, 0, 0, 0)), port),
         &exit,
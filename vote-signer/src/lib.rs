@@ -1,4 +1,6 @@
+pub mod remote;
 pub mod rpc;
+pub mod slashing_protection;
 
 #[macro_use]
 extern crate log;
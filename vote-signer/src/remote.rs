@@ -0,0 +1,1027 @@
+//! The `remote` module implements a vote signer that runs as a separate process and is
+//! reached over a Unix socket or TCP. Only vote transactions that pass the
+//! `SlashingProtectionDb` checks are signed.
+//!
+//! Requests and responses are bincode-encoded and prefixed with their length as a
+//! little-endian u32.
+//!
+//! When a connection is accepted the signer sends a random nonce. The client has a few
+//! seconds to answer with a small `VoteSignerAuth` frame proving it holds an authorized
+//! identity. Every request is then signed by that identity over the nonce and the sequence
+//! number of the request on the connection.
+
+use crate::slashing_protection::{BankHashLink, SlashingProtectionDb};
+use serde_derive::{Deserialize, Serialize};
+use solana_sdk::{
+    hash::Hash,
+    message::Message,
+    program_utils::limited_deserialize,
+    pubkey::Pubkey,
+    sanitize::Sanitize,
+    signature::{Keypair, Signature, Signer},
+    transaction::Transaction,
+};
+use solana_vote_program::{vote_instruction::VoteInstruction, vote_state::Vote};
+use std::{
+    collections::HashSet,
+    fmt,
+    io::{self, Read, Write},
+    net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
+    str::FromStr,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Mutex, RwLock,
+    },
+    thread::{self, sleep, Builder, JoinHandle},
+    time::{Duration, Instant},
+};
+#[cfg(unix)]
+use std::{
+    os::unix::net::{UnixListener, UnixStream},
+    path::PathBuf,
+};
+use thiserror::Error;
+
+const MAX_FRAME_SIZE: usize = 1024 * 1024;
+// Large enough for a `VoteSignerAuth`, the only frame read from a client before it is
+// authenticated
+const MAX_AUTH_FRAME_SIZE: usize = 128;
+const SIGNER_TIMEOUT: Duration = Duration::from_secs(1);
+const AUTH_TIMEOUT: Duration = Duration::from_secs(5);
+// Connections beyond this are closed as soon as they are accepted
+const MAX_CONNECTIONS: usize = 16;
+const AUTH_DOMAIN: &[u8] = b"solana-vote-signer-auth";
+
+#[derive(Error, Debug)]
+pub enum RemoteVoteSignerError {
+    #[error("IO Error: {0}")]
+    IoError(#[from] io::Error),
+
+    #[error("Serialization Error: {0}")]
+    SerializeError(#[from] bincode::Error),
+
+    #[error("The vote signer refused to sign: {0}")]
+    Rejected(String),
+
+    #[error("Unexpected response from the vote signer")]
+    UnexpectedResponse,
+
+    #[error("Invalid signature from the vote signer for {0}")]
+    InvalidSignature(Pubkey),
+}
+
+pub type Result<T> = std::result::Result<T, RemoteVoteSignerError>;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum VoteSignerRequest {
+    GetPubkeys,
+    // `ancestry` proves which banks the voted bank descends from, oldest first, and is
+    // used for the lockout checks
+    SignVote {
+        message: Message,
+        ancestry: Vec<BankHashLink>,
+    },
+}
+
+/// Proves that the client of a connection holds `identity`, by signing the nonce of the
+/// connection
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct VoteSignerAuth {
+    pub identity: Pubkey,
+    pub signature: Signature,
+}
+
+impl VoteSignerAuth {
+    pub fn new(identity: &Keypair, nonce: &Hash) -> Self {
+        Self {
+            identity: identity.pubkey(),
+            signature: identity.sign_message(&Self::signed_data(nonce)),
+        }
+    }
+
+    fn signed_data(nonce: &Hash) -> Vec<u8> {
+        [AUTH_DOMAIN, nonce.as_ref()].concat()
+    }
+
+    pub fn verify(&self, nonce: &Hash) -> bool {
+        self.signature
+            .verify(self.identity.as_ref(), &Self::signed_data(nonce))
+    }
+}
+
+/// A request signed by the identity of the client. The signature also covers the nonce of the
+/// connection and the sequence number of the request, so a request can't be replayed on
+/// another connection or out of order
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct SignedVoteSignerRequest {
+    pub identity: Pubkey,
+    pub sequence: u64,
+    pub request: VoteSignerRequest,
+    pub signature: Signature,
+}
+
+impl SignedVoteSignerRequest {
+    pub fn new(
+        identity: &Keypair,
+        nonce: &Hash,
+        sequence: u64,
+        request: VoteSignerRequest,
+    ) -> Self {
+        let signature = identity.sign_message(&Self::signed_data(nonce, sequence, &request));
+        Self {
+            identity: identity.pubkey(),
+            sequence,
+            request,
+            signature,
+        }
+    }
+
+    fn signed_data(nonce: &Hash, sequence: u64, request: &VoteSignerRequest) -> Vec<u8> {
+        bincode::serialize(&(nonce, sequence, request)).unwrap()
+    }
+
+    /// Whether this is request number `sequence` of the connection with `nonce`, signed by
+    /// `identity`
+    pub fn verify(&self, nonce: &Hash, sequence: u64) -> bool {
+        self.sequence == sequence
+            && self.signature.verify(
+                self.identity.as_ref(),
+                &Self::signed_data(nonce, sequence, &self.request),
+            )
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub enum VoteSignerResponse {
+    Authenticated,
+    Pubkeys(Vec<Pubkey>),
+    Signatures(Vec<(Pubkey, Signature)>),
+    Rejected(String),
+}
+
+/// Address of a remote vote signer: `unix:<PATH>` or `<HOST>:<PORT>`
+#[derive(Clone, Debug, PartialEq)]
+pub enum VoteSignerAddr {
+    Tcp(SocketAddr),
+    #[cfg(unix)]
+    Unix(PathBuf),
+}
+
+impl FromStr for VoteSignerAddr {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        #[cfg(unix)]
+        {
+            if let Some(path) = s.strip_prefix("unix:") {
+                return Ok(VoteSignerAddr::Unix(PathBuf::from(path)));
+            }
+        }
+        s.to_socket_addrs()
+            .map_err(|err| format!("Unable to resolve {}: {}", s, err))?
+            .next()
+            .map(VoteSignerAddr::Tcp)
+            .ok_or_else(|| format!("Unable to resolve {}", s))
+    }
+}
+
+impl fmt::Display for VoteSignerAddr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            VoteSignerAddr::Tcp(addr) => write!(f, "{}", addr),
+            #[cfg(unix)]
+            VoteSignerAddr::Unix(path) => write!(f, "unix:{}", path.display()),
+        }
+    }
+}
+
+#[derive(Debug)]
+enum SignerStream {
+    Tcp(TcpStream),
+    #[cfg(unix)]
+    Unix(UnixStream),
+}
+
+impl SignerStream {
+    fn connect(addr: &VoteSignerAddr) -> io::Result<Self> {
+        let stream = match addr {
+            VoteSignerAddr::Tcp(addr) => {
+                let stream = TcpStream::connect_timeout(addr, SIGNER_TIMEOUT)?;
+                stream.set_nodelay(true)?;
+                SignerStream::Tcp(stream)
+            }
+            #[cfg(unix)]
+            VoteSignerAddr::Unix(path) => SignerStream::Unix(UnixStream::connect(path)?),
+        };
+        stream.set_timeout(Some(SIGNER_TIMEOUT))?;
+        Ok(stream)
+    }
+
+    fn set_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        match self {
+            SignerStream::Tcp(stream) => {
+                stream.set_read_timeout(timeout)?;
+                stream.set_write_timeout(timeout)
+            }
+            #[cfg(unix)]
+            SignerStream::Unix(stream) => {
+                stream.set_read_timeout(timeout)?;
+                stream.set_write_timeout(timeout)
+            }
+        }
+    }
+}
+
+impl Read for SignerStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            SignerStream::Tcp(stream) => stream.read(buf),
+            #[cfg(unix)]
+            SignerStream::Unix(stream) => stream.read(buf),
+        }
+    }
+}
+
+impl Write for SignerStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            SignerStream::Tcp(stream) => stream.write(buf),
+            #[cfg(unix)]
+            SignerStream::Unix(stream) => stream.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            SignerStream::Tcp(stream) => stream.flush(),
+            #[cfg(unix)]
+            SignerStream::Unix(stream) => stream.flush(),
+        }
+    }
+}
+
+fn write_frame<W: Write, T: serde::Serialize>(writer: &mut W, value: &T) -> Result<()> {
+    let data = bincode::serialize(value)?;
+    writer.write_all(&(data.len() as u32).to_le_bytes())?;
+    writer.write_all(&data)?;
+    writer.flush()?;
+    Ok(())
+}
+
+// Reads exactly `buf.len()` bytes. Timeouts are retried until `exit` is set or `deadline`
+// passes, so that an idle connection can notice the exit without tearing a frame apart.
+fn read_exact_or_exit<R: Read>(
+    reader: &mut R,
+    buf: &mut [u8],
+    exit: &AtomicBool,
+    deadline: Option<Instant>,
+) -> Result<()> {
+    let mut offset = 0;
+    while offset < buf.len() {
+        match reader.read(&mut buf[offset..]) {
+            Ok(0) => return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into()),
+            Ok(n) => offset += n,
+            Err(err)
+                if err.kind() == io::ErrorKind::WouldBlock
+                    || err.kind() == io::ErrorKind::TimedOut
+                    || err.kind() == io::ErrorKind::Interrupted =>
+            {
+                if exit.load(Ordering::Relaxed)
+                    || deadline.map_or(false, |deadline| Instant::now() >= deadline)
+                {
+                    return Err(err.into());
+                }
+            }
+            Err(err) => return Err(err.into()),
+        }
+    }
+    Ok(())
+}
+
+fn read_frame<R: Read, T: serde::de::DeserializeOwned>(
+    reader: &mut R,
+    exit: &AtomicBool,
+) -> Result<T> {
+    read_frame_with_limit(reader, MAX_FRAME_SIZE, exit, None)
+}
+
+fn read_frame_with_limit<R: Read, T: serde::de::DeserializeOwned>(
+    reader: &mut R,
+    max_size: usize,
+    exit: &AtomicBool,
+    deadline: Option<Instant>,
+) -> Result<T> {
+    let mut len = [0u8; 4];
+    read_exact_or_exit(reader, &mut len, exit, deadline)?;
+    let len = u32::from_le_bytes(len) as usize;
+    if len > max_size {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("frame of {} bytes is too large", len),
+        )
+        .into());
+    }
+    let mut data = vec![0u8; len];
+    read_exact_or_exit(reader, &mut data, exit, deadline)?;
+    Ok(bincode::deserialize(&data)?)
+}
+
+// Returns the vote account and vote of a transaction made of a single vote instruction
+fn get_vote(message: &Message) -> std::result::Result<(Pubkey, Vote), String> {
+    if message.instructions.len() != 1 {
+        return Err("not a vote transaction".to_string());
+    }
+    let instruction = &message.instructions[0];
+    if message
+        .account_keys
+        .get(instruction.program_id_index as usize)
+        != Some(&solana_vote_program::id())
+    {
+        return Err("not a vote transaction".to_string());
+    }
+    let vote = match limited_deserialize(&instruction.data) {
+        Ok(VoteInstruction::Vote(vote)) | Ok(VoteInstruction::VoteSwitch(vote, _)) => vote,
+        _ => return Err("not a vote instruction".to_string()),
+    };
+    let vote_account = instruction
+        .accounts
+        .first()
+        .and_then(|index| message.account_keys.get(*index as usize))
+        .ok_or_else(|| "missing vote account".to_string())?;
+    Ok((*vote_account, vote))
+}
+
+struct VoteSignerState {
+    keypairs: Vec<Keypair>,
+    slashing_protection_db: SlashingProtectionDb,
+}
+
+impl VoteSignerState {
+    fn process_request(&mut self, request: VoteSignerRequest) -> VoteSignerResponse {
+        match request {
+            VoteSignerRequest::GetPubkeys => {
+                VoteSignerResponse::Pubkeys(self.keypairs.iter().map(|k| k.pubkey()).collect())
+            }
+            VoteSignerRequest::SignVote { message, ancestry } => {
+                match self.sign_vote(&message, &ancestry) {
+                    Ok(signatures) => VoteSignerResponse::Signatures(signatures),
+                    Err(err) => {
+                        warn!("vote signing request rejected: {}", err);
+                        VoteSignerResponse::Rejected(err)
+                    }
+                }
+            }
+        }
+    }
+
+    fn sign_vote(
+        &mut self,
+        message: &Message,
+        ancestry: &[BankHashLink],
+    ) -> std::result::Result<Vec<(Pubkey, Signature)>, String> {
+        // the message comes from the client, so it must not be trusted to index its own keys
+        message
+            .sanitize()
+            .map_err(|err| format!("invalid message: {}", err))?;
+        let (vote_account, vote) = get_vote(message)?;
+        let signer_keys = message
+            .account_keys
+            .get(..message.header.num_required_signatures as usize)
+            .ok_or_else(|| "invalid message header".to_string())?;
+        let keypairs: Vec<_> = self
+            .keypairs
+            .iter()
+            .filter(|keypair| signer_keys.contains(&keypair.pubkey()))
+            .collect();
+        if keypairs.is_empty() {
+            return Err("no keypair for the required signers".to_string());
+        }
+
+        self.slashing_protection_db
+            .check_and_record_vote(&vote_account, &vote, ancestry)
+            .map_err(|err| err.to_string())?;
+        info!("signing vote for {}: {:?}", vote_account, vote.slots);
+
+        let data = message.serialize();
+        Ok(keypairs
+            .into_iter()
+            .map(|keypair| (keypair.pubkey(), keypair.sign_message(&data)))
+            .collect())
+    }
+}
+
+enum SignerListener {
+    Tcp(TcpListener),
+    #[cfg(unix)]
+    Unix(UnixListener),
+}
+
+impl SignerListener {
+    // Requests are authenticated, so TCP may listen on any address. Unix sockets are
+    // still limited to their owner.
+    fn bind(addr: &VoteSignerAddr) -> io::Result<Self> {
+        let listener = match addr {
+            VoteSignerAddr::Tcp(addr) => SignerListener::Tcp(TcpListener::bind(addr)?),
+            #[cfg(unix)]
+            VoteSignerAddr::Unix(path) => {
+                use std::os::unix::fs::PermissionsExt;
+
+                Self::remove_stale_socket(path)?;
+                let listener = UnixListener::bind(path)?;
+                std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+                SignerListener::Unix(listener)
+            }
+        };
+        match &listener {
+            SignerListener::Tcp(listener) => listener.set_nonblocking(true)?,
+            #[cfg(unix)]
+            SignerListener::Unix(listener) => listener.set_nonblocking(true)?,
+        }
+        Ok(listener)
+    }
+
+    // A socket left behind by a previous run would fail the bind. Anything else at
+    // `path`, including the socket of a signer that is still running, is left alone.
+    #[cfg(unix)]
+    fn remove_stale_socket(path: &std::path::Path) -> io::Result<()> {
+        use std::os::unix::fs::FileTypeExt;
+
+        let metadata = match std::fs::symlink_metadata(path) {
+            Ok(metadata) => metadata,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(()),
+            Err(err) => return Err(err),
+        };
+        if !metadata.file_type().is_socket() {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("{} exists and is not a socket", path.display()),
+            ));
+        }
+        if UnixStream::connect(path).is_ok() {
+            return Err(io::Error::new(
+                io::ErrorKind::AddrInUse,
+                format!("another vote signer is listening on {}", path.display()),
+            ));
+        }
+        std::fs::remove_file(path)
+    }
+
+    fn accept(&self) -> io::Result<SignerStream> {
+        let stream = match self {
+            SignerListener::Tcp(listener) => {
+                let (stream, _) = listener.accept()?;
+                stream.set_nonblocking(false)?;
+                stream.set_nodelay(true)?;
+                SignerStream::Tcp(stream)
+            }
+            #[cfg(unix)]
+            SignerListener::Unix(listener) => {
+                let (stream, _) = listener.accept()?;
+                stream.set_nonblocking(false)?;
+                SignerStream::Unix(stream)
+            }
+        };
+        stream.set_timeout(Some(SIGNER_TIMEOUT))?;
+        Ok(stream)
+    }
+}
+
+pub struct RemoteVoteSignerService {
+    thread_hdl: JoinHandle<()>,
+}
+
+impl RemoteVoteSignerService {
+    /// Serves the requests signed by one of `authorized_clients` on `addr`
+    pub fn new(
+        addr: &VoteSignerAddr,
+        keypairs: Vec<Keypair>,
+        authorized_clients: HashSet<Pubkey>,
+        slashing_protection_db: SlashingProtectionDb,
+        exit: &Arc<AtomicBool>,
+    ) -> io::Result<Self> {
+        let listener = SignerListener::bind(addr)?;
+        let state = Arc::new(Mutex::new(VoteSignerState {
+            keypairs,
+            slashing_protection_db,
+        }));
+        let authorized_clients = Arc::new(authorized_clients);
+        let num_connections = Arc::new(AtomicUsize::new(0));
+        let exit = exit.clone();
+        let thread_hdl = Builder::new()
+            .name("solana-vote-signer-listener".to_string())
+            .spawn(move || {
+                while !exit.load(Ordering::Relaxed) {
+                    match listener.accept() {
+                        Ok(stream) => {
+                            if num_connections.load(Ordering::Relaxed) >= MAX_CONNECTIONS {
+                                warn!("vote signer connection refused: too many connections");
+                                continue;
+                            }
+                            num_connections.fetch_add(1, Ordering::Relaxed);
+                            Self::spawn_connection(
+                                stream,
+                                &state,
+                                &authorized_clients,
+                                &num_connections,
+                                &exit,
+                            )
+                        }
+                        Err(err) if err.kind() == io::ErrorKind::WouldBlock => {
+                            sleep(Duration::from_millis(100))
+                        }
+                        Err(err) => warn!("vote signer accept failed: {}", err),
+                    }
+                }
+            })
+            .unwrap();
+        Ok(Self { thread_hdl })
+    }
+
+    // Returns the identity the client proved to hold and the nonce of the connection, after
+    // telling the client whether it is authorized. Until then only a small frame is read,
+    // within `AUTH_TIMEOUT`.
+    fn authenticate(
+        stream: &mut SignerStream,
+        authorized_clients: &HashSet<Pubkey>,
+        exit: &AtomicBool,
+    ) -> Result<Option<(Pubkey, Hash)>> {
+        let nonce = Hash::new(&rand::random::<[u8; 32]>());
+        write_frame(stream, &nonce)?;
+        let auth: VoteSignerAuth = read_frame_with_limit(
+            stream,
+            MAX_AUTH_FRAME_SIZE,
+            exit,
+            Some(Instant::now() + AUTH_TIMEOUT),
+        )?;
+        if !authorized_clients.contains(&auth.identity) || !auth.verify(&nonce) {
+            warn!(
+                "vote signer connection from {} rejected: not authenticated",
+                auth.identity
+            );
+            let response = VoteSignerResponse::Rejected("not authenticated".to_string());
+            write_frame(stream, &response)?;
+            return Ok(None);
+        }
+        write_frame(stream, &VoteSignerResponse::Authenticated)?;
+        Ok(Some((auth.identity, nonce)))
+    }
+
+    fn spawn_connection(
+        mut stream: SignerStream,
+        state: &Arc<Mutex<VoteSignerState>>,
+        authorized_clients: &Arc<HashSet<Pubkey>>,
+        num_connections: &Arc<AtomicUsize>,
+        exit: &Arc<AtomicBool>,
+    ) {
+        let state = state.clone();
+        let authorized_clients = authorized_clients.clone();
+        let num_connections = num_connections.clone();
+        let exit = exit.clone();
+        Builder::new()
+            .name("solana-vote-signer-connection".to_string())
+            .spawn(move || {
+                Self::serve_connection(&mut stream, &state, &authorized_clients, &exit);
+                num_connections.fetch_sub(1, Ordering::Relaxed);
+            })
+            .unwrap();
+    }
+
+    fn serve_connection(
+        stream: &mut SignerStream,
+        state: &Mutex<VoteSignerState>,
+        authorized_clients: &HashSet<Pubkey>,
+        exit: &AtomicBool,
+    ) {
+        let (identity, nonce) = match Self::authenticate(stream, authorized_clients, exit) {
+            Ok(Some(authenticated)) => authenticated,
+            Ok(None) => return,
+            Err(err) => {
+                debug!("vote signer connection closed: {}", err);
+                return;
+            }
+        };
+        for sequence in 0.. {
+            let request: SignedVoteSignerRequest = match read_frame(stream, exit) {
+                Ok(request) => request,
+                Err(err) => {
+                    debug!("vote signer connection closed: {}", err);
+                    break;
+                }
+            };
+            if request.identity != identity || !request.verify(&nonce, sequence) {
+                warn!(
+                    "vote signer request from {} rejected: not authenticated",
+                    request.identity
+                );
+                let response =
+                    VoteSignerResponse::Rejected("request not authenticated".to_string());
+                let _ = write_frame(stream, &response);
+                break;
+            }
+            let response = state.lock().unwrap().process_request(request.request);
+            if let Err(err) = write_frame(stream, &response) {
+                debug!("vote signer connection closed: {}", err);
+                break;
+            }
+        }
+    }
+
+    pub fn join(self) -> thread::Result<()> {
+        self.thread_hdl.join()
+    }
+}
+
+#[derive(Debug)]
+struct SignerConnection {
+    stream: SignerStream,
+    nonce: Hash,
+    sequence: u64,
+}
+
+impl SignerConnection {
+    fn connect(addr: &VoteSignerAddr, identity: &Keypair) -> Result<Self> {
+        let mut stream = SignerStream::connect(addr)?;
+        // the client gives up on the first timeout
+        let nonce = read_frame(&mut stream, &AtomicBool::new(true))?;
+        write_frame(&mut stream, &VoteSignerAuth::new(identity, &nonce))?;
+        match read_frame(&mut stream, &AtomicBool::new(true))? {
+            VoteSignerResponse::Authenticated => (),
+            VoteSignerResponse::Rejected(err) => return Err(RemoteVoteSignerError::Rejected(err)),
+            _ => return Err(RemoteVoteSignerError::UnexpectedResponse),
+        }
+        Ok(Self {
+            stream,
+            nonce,
+            sequence: 0,
+        })
+    }
+
+    fn round_trip(
+        &mut self,
+        identity: &Keypair,
+        request: &VoteSignerRequest,
+    ) -> Result<VoteSignerResponse> {
+        let request =
+            SignedVoteSignerRequest::new(identity, &self.nonce, self.sequence, request.clone());
+        self.sequence += 1;
+        write_frame(&mut self.stream, &request)?;
+        read_frame(&mut self.stream, &AtomicBool::new(true))
+    }
+}
+
+/// Client of a `RemoteVoteSignerService`, signing its requests with the validator
+/// identity. The connection is established lazily and re-established once if a request
+/// fails on a stale connection.
+#[derive(Debug)]
+pub struct RemoteVoteSigner {
+    addr: VoteSignerAddr,
+    identity: Arc<Keypair>,
+    connection: Mutex<Option<SignerConnection>>,
+    pubkeys: RwLock<Option<Vec<Pubkey>>>,
+}
+
+impl RemoteVoteSigner {
+    pub fn new(addr: VoteSignerAddr, identity: Arc<Keypair>) -> Self {
+        Self {
+            addr,
+            identity,
+            connection: Mutex::new(None),
+            pubkeys: RwLock::new(None),
+        }
+    }
+
+    pub fn addr(&self) -> &VoteSignerAddr {
+        &self.addr
+    }
+
+    fn request(&self, request: &VoteSignerRequest) -> Result<VoteSignerResponse> {
+        let mut connection = self.connection.lock().unwrap();
+        if let Some(connection) = connection.as_mut() {
+            match connection.round_trip(&self.identity, request) {
+                Ok(response) => return Ok(response),
+                Err(err) => debug!("reconnecting to vote signer {}: {}", self.addr, err),
+            }
+        }
+        *connection = None;
+        let mut new_connection = SignerConnection::connect(&self.addr, &self.identity)?;
+        let response = new_connection.round_trip(&self.identity, request)?;
+        *connection = Some(new_connection);
+        Ok(response)
+    }
+
+    /// The pubkeys the signer holds keypairs for
+    pub fn pubkeys(&self) -> Result<Vec<Pubkey>> {
+        if let Some(pubkeys) = &*self.pubkeys.read().unwrap() {
+            return Ok(pubkeys.clone());
+        }
+        match self.request(&VoteSignerRequest::GetPubkeys)? {
+            VoteSignerResponse::Pubkeys(pubkeys) => {
+                *self.pubkeys.write().unwrap() = Some(pubkeys.clone());
+                Ok(pubkeys)
+            }
+            VoteSignerResponse::Rejected(err) => Err(RemoteVoteSignerError::Rejected(err)),
+            _ => Err(RemoteVoteSignerError::UnexpectedResponse),
+        }
+    }
+
+    /// Adds the signatures of the keypairs held by the signer to a vote transaction
+    pub fn sign_vote_transaction(
+        &self,
+        transaction: &mut Transaction,
+        ancestry: Vec<BankHashLink>,
+    ) -> Result<()> {
+        let request = VoteSignerRequest::SignVote {
+            message: transaction.message.clone(),
+            ancestry,
+        };
+        let signatures = match self.request(&request)? {
+            VoteSignerResponse::Signatures(signatures) => signatures,
+            VoteSignerResponse::Rejected(err) => return Err(RemoteVoteSignerError::Rejected(err)),
+            _ => return Err(RemoteVoteSignerError::UnexpectedResponse),
+        };
+
+        let data = transaction.message_data();
+        let num_required_signatures = transaction.message.header.num_required_signatures as usize;
+        for (pubkey, signature) in signatures {
+            let position = transaction.message.account_keys[..num_required_signatures]
+                .iter()
+                .position(|key| *key == pubkey);
+            match position {
+                Some(position) if signature.verify(pubkey.as_ref(), &data) => {
+                    transaction.signatures[position] = signature;
+                }
+                _ => return Err(RemoteVoteSignerError::InvalidSignature(pubkey)),
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::{clock::Slot, hash::Hash, system_transaction};
+    use solana_vote_program::vote_instruction;
+    use tempfile::TempDir;
+
+    fn run_remote_vote_signer(addr: VoteSignerAddr, db_path: &std::path::Path) {
+        let exit = Arc::new(AtomicBool::new(false));
+        let node_keypair = Arc::new(Keypair::new());
+        let authorized_voter_keypair = Keypair::new();
+        let vote_account = solana_sdk::pubkey::new_rand();
+        let service = RemoteVoteSignerService::new(
+            &addr,
+            vec![Keypair::from_bytes(&authorized_voter_keypair.to_bytes()).unwrap()],
+            vec![node_keypair.pubkey()].into_iter().collect(),
+            SlashingProtectionDb::open(db_path).unwrap(),
+            &exit,
+        )
+        .unwrap();
+
+        let signer = RemoteVoteSigner::new(addr, node_keypair.clone());
+        assert_eq!(
+            signer.pubkeys().unwrap(),
+            vec![authorized_voter_keypair.pubkey()]
+        );
+
+        let vote_transaction = |slots: Vec<Slot>, hash: Hash| {
+            let vote_ix = vote_instruction::vote(
+                &vote_account,
+                &authorized_voter_keypair.pubkey(),
+                Vote::new(slots, hash),
+            );
+            let mut transaction =
+                Transaction::new_with_payer(&[vote_ix], Some(&node_keypair.pubkey()));
+            transaction.partial_sign(&[node_keypair.as_ref()], Hash::default());
+            transaction
+        };
+        let new_link = |parent_hash: Hash| BankHashLink {
+            parent_hash,
+            accounts_delta_hash: Hash::new_unique(),
+            signature_count: 1,
+            last_blockhash: Hash::new_unique(),
+            hard_fork_data: None,
+        };
+        let bank1 = new_link(Hash::default());
+        let bank2 = new_link(bank1.hash());
+
+        let mut transaction = vote_transaction(vec![1], bank1.hash());
+        signer
+            .sign_vote_transaction(&mut transaction, vec![bank1.clone()])
+            .unwrap();
+        transaction.verify().unwrap();
+
+        // A vote for another block in the same slot is slashable
+        let mut transaction = vote_transaction(vec![1], Hash::new_unique());
+        assert!(matches!(
+            signer.sign_vote_transaction(&mut transaction, vec![]),
+            Err(RemoteVoteSignerError::Rejected(_))
+        ));
+
+        // Only vote transactions are signed
+        let mut transaction = system_transaction::transfer(
+            node_keypair.as_ref(),
+            &authorized_voter_keypair.pubkey(),
+            1,
+            Hash::default(),
+        );
+        assert!(matches!(
+            signer.sign_vote_transaction(&mut transaction, vec![]),
+            Err(RemoteVoteSignerError::Rejected(_))
+        ));
+
+        // A malformed message is rejected without disabling the signer
+        let mut transaction = vote_transaction(vec![2], bank2.hash());
+        transaction.message.header.num_required_signatures = 100;
+        assert!(matches!(
+            signer.sign_vote_transaction(&mut transaction, vec![bank2.clone()]),
+            Err(RemoteVoteSignerError::Rejected(_))
+        ));
+
+        // Slot 1 is locked out unless slot 2 is proven to descend from it
+        let mut transaction = vote_transaction(vec![2], bank2.hash());
+        assert!(matches!(
+            signer.sign_vote_transaction(&mut transaction, vec![]),
+            Err(RemoteVoteSignerError::Rejected(_))
+        ));
+        signer
+            .sign_vote_transaction(&mut transaction, vec![bank2])
+            .unwrap();
+        transaction.verify().unwrap();
+
+        exit.store(true, Ordering::Relaxed);
+        service.join().unwrap();
+    }
+
+    #[test]
+    fn test_remote_vote_signer_tcp() {
+        let dir = TempDir::new().unwrap();
+        let addr = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        run_remote_vote_signer(VoteSignerAddr::Tcp(addr), dir.path());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_remote_vote_signer_unix() {
+        let dir = TempDir::new().unwrap();
+        let addr = VoteSignerAddr::Unix(dir.path().join("vote-signer.sock"));
+        run_remote_vote_signer(addr, &dir.path().join("db"));
+    }
+
+    #[test]
+    fn test_remote_vote_signer_unauthenticated() {
+        let dir = TempDir::new().unwrap();
+        let exit = Arc::new(AtomicBool::new(false));
+        let identity = Keypair::new();
+        let addr = TcpListener::bind("0.0.0.0:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let addr = VoteSignerAddr::Tcp(addr);
+        let service = RemoteVoteSignerService::new(
+            &addr,
+            vec![Keypair::new()],
+            vec![identity.pubkey()].into_iter().collect(),
+            SlashingProtectionDb::open(dir.path()).unwrap(),
+            &exit,
+        )
+        .unwrap();
+
+        // Requests of unknown identities are rejected
+        let signer = RemoteVoteSigner::new(addr.clone(), Arc::new(Keypair::new()));
+        assert!(matches!(
+            signer.pubkeys(),
+            Err(RemoteVoteSignerError::Rejected(_))
+        ));
+
+        // So are requests replayed on another connection
+        let mut connection = SignerConnection::connect(&addr, &identity).unwrap();
+        let request = SignedVoteSignerRequest::new(
+            &identity,
+            &connection.nonce,
+            0,
+            VoteSignerRequest::GetPubkeys,
+        );
+        write_frame(&mut connection.stream, &request).unwrap();
+        let response: VoteSignerResponse =
+            read_frame(&mut connection.stream, &AtomicBool::new(true)).unwrap();
+        assert!(matches!(response, VoteSignerResponse::Pubkeys(_)));
+
+        let mut connection = SignerConnection::connect(&addr, &identity).unwrap();
+        write_frame(&mut connection.stream, &request).unwrap();
+        let response: VoteSignerResponse =
+            read_frame(&mut connection.stream, &AtomicBool::new(true)).unwrap();
+        assert!(matches!(response, VoteSignerResponse::Rejected(_)));
+
+        exit.store(true, Ordering::Relaxed);
+        service.join().unwrap();
+    }
+
+    #[test]
+    fn test_remote_vote_signer_connection_limits() {
+        let dir = TempDir::new().unwrap();
+        let exit = Arc::new(AtomicBool::new(false));
+        let identity = Arc::new(Keypair::new());
+        let addr = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let addr = VoteSignerAddr::Tcp(addr);
+        let service = RemoteVoteSignerService::new(
+            &addr,
+            vec![Keypair::new()],
+            vec![identity.pubkey()].into_iter().collect(),
+            SlashingProtectionDb::open(dir.path()).unwrap(),
+            &exit,
+        )
+        .unwrap();
+        let connect = || {
+            let mut stream = SignerStream::connect(&addr).unwrap();
+            let nonce: Result<Hash> = read_frame(&mut stream, &AtomicBool::new(true));
+            (stream, nonce)
+        };
+        let read_until_closed = |stream: &mut SignerStream| {
+            let deadline = Instant::now() + AUTH_TIMEOUT + SIGNER_TIMEOUT * 2;
+            let response: Result<VoteSignerResponse> = read_frame_with_limit(
+                stream,
+                MAX_FRAME_SIZE,
+                &AtomicBool::new(false),
+                Some(deadline),
+            );
+            matches!(response, Err(RemoteVoteSignerError::IoError(err)) if err.kind() == io::ErrorKind::UnexpectedEof)
+        };
+
+        // Connections beyond the limit are closed right away
+        let mut idle_streams: Vec<_> = (0..MAX_CONNECTIONS)
+            .map(|_| {
+                let (stream, nonce) = connect();
+                nonce.unwrap();
+                stream
+            })
+            .collect();
+        assert!(connect().1.is_err());
+
+        // A large frame is refused before authentication
+        let stream = &mut idle_streams[0];
+        stream
+            .write_all(&(MAX_AUTH_FRAME_SIZE as u32 + 1).to_le_bytes())
+            .unwrap();
+        assert!(read_until_closed(stream));
+
+        // And clients that don't authenticate in time are disconnected
+        for stream in idle_streams.iter_mut().skip(1) {
+            assert!(read_until_closed(stream));
+        }
+        let signer = RemoteVoteSigner::new(addr, identity);
+        assert_eq!(signer.pubkeys().unwrap().len(), 1);
+
+        exit.store(true, Ordering::Relaxed);
+        service.join().unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_bind_unix_existing_path() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("vote-signer.sock");
+        let addr = VoteSignerAddr::Unix(path.clone());
+
+        // A regular file is never removed
+        std::fs::write(&path, b"not a socket").unwrap();
+        assert_eq!(
+            SignerListener::bind(&addr).err().unwrap().kind(),
+            io::ErrorKind::AlreadyExists
+        );
+        assert_eq!(std::fs::read(&path).unwrap(), b"not a socket");
+        std::fs::remove_file(&path).unwrap();
+
+        // Neither is the socket of a running signer
+        let listener = SignerListener::bind(&addr).unwrap();
+        assert_eq!(
+            SignerListener::bind(&addr).err().unwrap().kind(),
+            io::ErrorKind::AddrInUse
+        );
+
+        // But a stale socket is
+        drop(listener);
+        assert!(path.exists());
+        SignerListener::bind(&addr).unwrap();
+    }
+
+    #[test]
+    fn test_vote_signer_addr() {
+        assert_eq!(
+            "127.0.0.1:8990".parse::<VoteSignerAddr>(),
+            Ok(VoteSignerAddr::Tcp("127.0.0.1:8990".parse().unwrap()))
+        );
+        #[cfg(unix)]
+        assert_eq!(
+            "unix:/tmp/vote-signer.sock".parse::<VoteSignerAddr>(),
+            Ok(VoteSignerAddr::Unix(PathBuf::from("/tmp/vote-signer.sock")))
+        );
+        assert!("not-an-address".parse::<VoteSignerAddr>().is_err());
+    }
+}
@@ -0,0 +1,454 @@
+//! The `slashing_protection` module keeps a persistent record of the votes signed for
+//! each vote account, and refuses to sign votes that would violate tower lockout.
+
+use serde_derive::{Deserialize, Serialize};
+use solana_sdk::{
+    clock::Slot,
+    hash::{extend_and_hash, hashv, Hash},
+    pubkey::Pubkey,
+};
+use solana_vote_program::vote_state::{Lockout, Vote, VoteState};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet, VecDeque},
+    fs::{self, File},
+    io::BufReader,
+    path::{Path, PathBuf},
+};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum SlashingProtectionError {
+    #[error("IO Error: {0}")]
+    IoError(#[from] std::io::Error),
+
+    #[error("Serialization Error: {0}")]
+    SerializeError(#[from] bincode::Error),
+
+    #[error("The vote has no slots")]
+    EmptyVote,
+
+    #[error("Slot {slot} is older than the last signed vote on slot {last_voted_slot}")]
+    VoteTooOld { slot: Slot, last_voted_slot: Slot },
+
+    #[error("Slot {0} was already signed with a different hash")]
+    ConflictingVote(Slot),
+
+    #[error("Slot {slot} is locked out by the signed vote on slot {locked_out_by}")]
+    LockedOut { slot: Slot, locked_out_by: Slot },
+
+    #[error("The ancestry does not chain up to the voted hash")]
+    InvalidAncestry,
+}
+
+pub type Result<T> = std::result::Result<T, SlashingProtectionError>;
+
+/// The inputs of a bank hash, which commit to the hash of the parent bank. A chain of
+/// links ending at the voted hash proves which banks the vote descends from.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct BankHashLink {
+    pub parent_hash: Hash,
+    pub accounts_delta_hash: Hash,
+    pub signature_count: u64,
+    pub last_blockhash: Hash,
+    pub hard_fork_data: Option<[u8; 8]>,
+}
+
+impl BankHashLink {
+    /// The bank hash, computed the same way as Bank::hash_internal_state()
+    pub fn hash(&self) -> Hash {
+        let hash = hashv(&[
+            self.parent_hash.as_ref(),
+            self.accounts_delta_hash.as_ref(),
+            &self.signature_count.to_le_bytes(),
+            self.last_blockhash.as_ref(),
+        ]);
+        match &self.hard_fork_data {
+            Some(buf) => extend_and_hash(&hash, buf),
+            None => hash,
+        }
+    }
+}
+
+// Returns the hashes of the banks proven to be ancestors of, or equal to, the bank with
+// `hash`. `ancestry` is ordered from the oldest bank to the voted one.
+fn verify_ancestry(hash: &Hash, ancestry: &[BankHashLink]) -> Result<HashSet<Hash>> {
+    let mut verified: HashSet<Hash> = vec![*hash].into_iter().collect();
+    let mut expected_hash = *hash;
+    for link in ancestry.iter().rev() {
+        if link.hash() != expected_hash {
+            return Err(SlashingProtectionError::InvalidAncestry);
+        }
+        expected_hash = link.parent_hash;
+        verified.insert(expected_hash);
+    }
+    Ok(verified)
+}
+
+/// The lockouts resulting from the votes signed for a vote account
+#[derive(Default, Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct SignedVotes {
+    pub votes: VecDeque<Lockout>,
+    pub root_slot: Option<Slot>,
+    pub last_vote: Option<(Slot, Hash)>,
+    /// For each slot in `votes`, the hash signed by the vote that added it. Slots of a
+    /// vote are ancestors of its hash, so a later vote descending from that hash
+    /// descends from the slot as well.
+    pub vote_hashes: BTreeMap<Slot, Hash>,
+}
+
+impl SignedVotes {
+    fn to_vote_state(&self) -> VoteState {
+        let mut vote_state = VoteState::default();
+        vote_state.votes = self.votes.clone();
+        vote_state.root_slot = self.root_slot;
+        vote_state
+    }
+
+    fn last_voted_slot(&self) -> Option<Slot> {
+        self.last_vote.map(|(slot, _hash)| slot)
+    }
+
+    // Applies `vote` the same way Tower::is_locked_out() and Tower::record_bank_vote()
+    // would. A lockout that hasn't expired only allows the vote if the hash it was
+    // signed with is proven by `ancestry` to be an ancestor of the voted hash; the
+    // ancestry claimed by the validator is never trusted on its own.
+    //
+    // Returns None if every slot of the vote has been signed already, which allows the
+    // same vote to be signed again with a different blockhash.
+    fn apply_vote(&self, vote: &Vote, ancestry: &[BankHashLink]) -> Result<Option<Self>> {
+        let last_slot = *vote
+            .slots
+            .last()
+            .ok_or(SlashingProtectionError::EmptyVote)?;
+        let verified_hashes = verify_ancestry(&vote.hash, ancestry)?;
+        let mut vote_state = self.to_vote_state();
+        let mut is_new_vote = false;
+
+        for slot in &vote.slots {
+            let slot = *slot;
+            if let Some(last_voted_slot) = self.last_voted_slot() {
+                if slot <= last_voted_slot {
+                    let is_signed = slot == last_voted_slot
+                        || self.root_slot.map_or(false, |root| slot <= root)
+                        || self.votes.iter().any(|lockout| lockout.slot == slot);
+                    if !is_signed {
+                        return Err(SlashingProtectionError::VoteTooOld {
+                            slot,
+                            last_voted_slot,
+                        });
+                    }
+                    if slot == last_slot && self.last_vote != Some((slot, vote.hash)) {
+                        return Err(SlashingProtectionError::ConflictingVote(slot));
+                    }
+                    continue;
+                }
+            }
+
+            vote_state.process_slot_vote_unchecked(slot);
+            for lockout in &vote_state.votes {
+                if vote.slots.contains(&lockout.slot) {
+                    continue;
+                }
+                let is_ancestor = self
+                    .vote_hashes
+                    .get(&lockout.slot)
+                    .map_or(false, |hash| verified_hashes.contains(hash));
+                if !is_ancestor {
+                    return Err(SlashingProtectionError::LockedOut {
+                        slot,
+                        locked_out_by: lockout.slot,
+                    });
+                }
+            }
+            is_new_vote = true;
+        }
+
+        if !is_new_vote {
+            return Ok(None);
+        }
+        let vote_hashes = vote_state
+            .votes
+            .iter()
+            .map(|lockout| {
+                let hash = if vote.slots.contains(&lockout.slot) {
+                    vote.hash
+                } else {
+                    self.vote_hashes[&lockout.slot]
+                };
+                (lockout.slot, hash)
+            })
+            .collect();
+        Ok(Some(Self {
+            votes: vote_state.votes,
+            root_slot: vote_state.root_slot,
+            last_vote: Some((last_slot, vote.hash)),
+            vote_hashes,
+        }))
+    }
+}
+
+/// Signed votes of every vote account, persisted as `<vote account>.bin` files under
+/// a directory. A vote is recorded durably before it may be signed.
+pub struct SlashingProtectionDb {
+    path: PathBuf,
+    signed_votes: HashMap<Pubkey, SignedVotes>,
+}
+
+impl SlashingProtectionDb {
+    pub fn open(path: &Path) -> Result<Self> {
+        fs::create_dir_all(path)?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            signed_votes: HashMap::new(),
+        })
+    }
+
+    pub fn get_filename(&self, vote_account: &Pubkey) -> PathBuf {
+        self.path
+            .join(vote_account.to_string())
+            .with_extension("bin")
+    }
+
+    pub fn signed_votes(&mut self, vote_account: &Pubkey) -> Result<&SignedVotes> {
+        if !self.signed_votes.contains_key(vote_account) {
+            let filename = self.get_filename(vote_account);
+            let signed_votes = match File::open(&filename) {
+                Ok(file) => bincode::deserialize_from(BufReader::new(file))?,
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => SignedVotes::default(),
+                Err(err) => return Err(err.into()),
+            };
+            self.signed_votes.insert(*vote_account, signed_votes);
+        }
+        Ok(&self.signed_votes[vote_account])
+    }
+
+    /// Checks `vote` against the votes already signed for `vote_account` and records it.
+    /// An error means the vote must not be signed.
+    pub fn check_and_record_vote(
+        &mut self,
+        vote_account: &Pubkey,
+        vote: &Vote,
+        ancestry: &[BankHashLink],
+    ) -> Result<()> {
+        let signed_votes = match self
+            .signed_votes(vote_account)?
+            .apply_vote(vote, ancestry)?
+        {
+            Some(signed_votes) => signed_votes,
+            None => return Ok(()),
+        };
+        self.save(vote_account, &signed_votes)?;
+        self.signed_votes.insert(*vote_account, signed_votes);
+        Ok(())
+    }
+
+    fn save(&self, vote_account: &Pubkey, signed_votes: &SignedVotes) -> Result<()> {
+        let filename = self.get_filename(vote_account);
+        let new_filename = filename.with_extension("bin.new");
+        {
+            let mut file = File::create(&new_filename)?;
+            bincode::serialize_into(&mut file, signed_votes)?;
+            file.sync_all()?;
+        }
+        fs::rename(&new_filename, &filename)?;
+        File::open(&self.path)?.sync_all()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn new_link(parent: &[BankHashLink]) -> Vec<BankHashLink> {
+        let parent_hash = parent.last().map_or_else(Hash::default, BankHashLink::hash);
+        let mut ancestry = parent.to_vec();
+        ancestry.push(BankHashLink {
+            parent_hash,
+            accounts_delta_hash: Hash::new_unique(),
+            signature_count: 0,
+            last_blockhash: Hash::new_unique(),
+            hard_fork_data: None,
+        });
+        ancestry
+    }
+
+    fn new_vote(slots: Vec<Slot>, ancestry: &[BankHashLink]) -> Vote {
+        Vote::new(slots, ancestry.last().unwrap().hash())
+    }
+
+    #[test]
+    fn test_verify_ancestry() {
+        let bank1 = new_link(&[]);
+        let bank2 = new_link(&bank1);
+        let hash = bank2[1].hash();
+        assert_eq!(
+            verify_ancestry(&hash, &bank2).unwrap(),
+            vec![Hash::default(), bank1[0].hash(), hash]
+                .into_iter()
+                .collect()
+        );
+        assert_eq!(
+            verify_ancestry(&hash, &[]).unwrap(),
+            vec![hash].into_iter().collect()
+        );
+
+        let mut hard_fork = bank2.clone();
+        hard_fork[1].hard_fork_data = Some([1, 0, 0, 0, 0, 0, 0, 0]);
+        assert!(matches!(
+            verify_ancestry(&hash, &hard_fork),
+            Err(SlashingProtectionError::InvalidAncestry)
+        ));
+        assert!(matches!(
+            verify_ancestry(&bank1[0].hash(), &bank2),
+            Err(SlashingProtectionError::InvalidAncestry)
+        ));
+    }
+
+    #[test]
+    fn test_double_vote() {
+        let dir = TempDir::new().unwrap();
+        let mut db = SlashingProtectionDb::open(dir.path()).unwrap();
+        let vote_account = solana_sdk::pubkey::new_rand();
+        let bank1 = new_link(&[]);
+        let bank2 = new_link(&bank1);
+        let bank3 = new_link(&bank1);
+
+        let vote = new_vote(vec![1], &bank1);
+        db.check_and_record_vote(&vote_account, &vote, &bank1)
+            .unwrap();
+        // Signing the same vote again is allowed
+        db.check_and_record_vote(&vote_account, &vote, &bank1)
+            .unwrap();
+        // But not for another block in the same slot
+        let other_bank1 = new_link(&[]);
+        assert!(matches!(
+            db.check_and_record_vote(
+                &vote_account,
+                &new_vote(vec![1], &other_bank1),
+                &other_bank1
+            ),
+            Err(SlashingProtectionError::ConflictingVote(1))
+        ));
+
+        db.check_and_record_vote(&vote_account, &new_vote(vec![3], &bank3), &bank3)
+            .unwrap();
+        assert!(matches!(
+            db.check_and_record_vote(&vote_account, &new_vote(vec![2], &bank2), &bank2),
+            Err(SlashingProtectionError::VoteTooOld {
+                slot: 2,
+                last_voted_slot: 3
+            })
+        ));
+    }
+
+    #[test]
+    fn test_lockout() {
+        let dir = TempDir::new().unwrap();
+        let mut db = SlashingProtectionDb::open(dir.path()).unwrap();
+        let vote_account = solana_sdk::pubkey::new_rand();
+        let bank1 = new_link(&[]);
+        let bank2 = new_link(&bank1);
+        let bank3 = new_link(&bank1);
+        let bank5 = new_link(&bank3);
+
+        // Slots 1 and 2 on the same fork
+        db.check_and_record_vote(&vote_account, &new_vote(vec![1], &bank1), &bank1)
+            .unwrap();
+        db.check_and_record_vote(&vote_account, &new_vote(vec![2], &bank2), &bank2)
+            .unwrap();
+        assert_eq!(
+            db.signed_votes(&vote_account)
+                .unwrap()
+                .votes
+                .iter()
+                .map(|lockout| (lockout.slot, lockout.confirmation_count))
+                .collect::<Vec<_>>(),
+            vec![(1, 2), (2, 1)]
+        );
+
+        // Slot 3 forks off of slot 1, while slot 2 is locked out until slot 4
+        assert!(matches!(
+            db.check_and_record_vote(&vote_account, &new_vote(vec![3], &bank3), &bank3),
+            Err(SlashingProtectionError::LockedOut {
+                slot: 3,
+                locked_out_by: 2
+            })
+        ));
+
+        // Slot 5 on the same fork as slot 3 is past the lockout of slot 2
+        db.check_and_record_vote(&vote_account, &new_vote(vec![5], &bank5), &bank5)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_lockout_conflicting_ancestry() {
+        let dir = TempDir::new().unwrap();
+        let mut db = SlashingProtectionDb::open(dir.path()).unwrap();
+        let vote_account = solana_sdk::pubkey::new_rand();
+        let bank1 = new_link(&[]);
+        let bank2 = new_link(&bank1);
+        let bank3 = new_link(&bank1);
+
+        db.check_and_record_vote(&vote_account, &new_vote(vec![1], &bank1), &bank1)
+            .unwrap();
+        db.check_and_record_vote(&vote_account, &new_vote(vec![2], &bank2), &bank2)
+            .unwrap();
+
+        // Without an ancestry, the lockouts of slots 1 and 2 can't be checked
+        assert!(matches!(
+            db.check_and_record_vote(&vote_account, &new_vote(vec![3], &bank3), &[]),
+            Err(SlashingProtectionError::LockedOut {
+                slot: 3,
+                locked_out_by: 1
+            })
+        ));
+
+        // Claiming slot 2 as an ancestor of slot 3 doesn't chain up to the hash of slot 3
+        let mut forged = bank2.clone();
+        forged.push(bank3[1].clone());
+        assert!(matches!(
+            db.check_and_record_vote(&vote_account, &new_vote(vec![3], &bank3), &forged),
+            Err(SlashingProtectionError::InvalidAncestry)
+        ));
+
+        // Nor does it when slot 3 is made a child of slot 2 with a different hash
+        let forged = new_link(&bank2);
+        assert!(matches!(
+            db.check_and_record_vote(&vote_account, &Vote::new(vec![3], bank3[1].hash()), &forged),
+            Err(SlashingProtectionError::InvalidAncestry)
+        ));
+
+        // Slot 3 really descending from slot 2 is allowed
+        let bank3 = new_link(&bank2);
+        db.check_and_record_vote(&vote_account, &new_vote(vec![3], &bank3), &bank3)
+            .unwrap();
+        assert_eq!(db.signed_votes(&vote_account).unwrap().votes.len(), 3);
+    }
+
+    #[test]
+    fn test_persistence() {
+        let dir = TempDir::new().unwrap();
+        let vote_account = solana_sdk::pubkey::new_rand();
+        let bank1 = new_link(&[]);
+        let bank2 = new_link(&bank1);
+        let signed_votes = {
+            let mut db = SlashingProtectionDb::open(dir.path()).unwrap();
+            db.check_and_record_vote(&vote_account, &new_vote(vec![1, 2], &bank2), &bank2)
+                .unwrap();
+            db.signed_votes(&vote_account).unwrap().clone()
+        };
+
+        let mut db = SlashingProtectionDb::open(dir.path()).unwrap();
+        assert_eq!(db.signed_votes(&vote_account).unwrap(), &signed_votes);
+        assert!(matches!(
+            db.check_and_record_vote(&vote_account, &Vote::new(vec![2], Hash::default()), &[]),
+            Err(SlashingProtectionError::ConflictingVote(2))
+        ));
+        assert_eq!(
+            db.signed_votes(&solana_sdk::pubkey::new_rand()).unwrap(),
+            &SignedVotes::default()
+        );
+    }
+}